@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{ Path, PathBuf };
+
+use criterion::{ criterion_group, criterion_main, Criterion };
+use msi_reader::MsiPackage;
+
+// The crate's own stream name encoder; its decoder, and the tests compiled without a test harness, are not used here.
+#[allow(dead_code, unused_imports)]
+#[path = "../src/streamname.rs"]
+mod streamname;
+
+const ROWS: usize = 500_000;
+
+// Name, type bits and cell of row `index` for every column of the File table.
+const FILE_COLUMNS: [(&str, i32); 8] = [
+    ("File", 0x2d48),
+    ("Component_", 0x0d48),
+    ("FileName", 0x0dff),
+    ("FileSize", 0x0104),
+    ("Version", 0x1d48),
+    ("Language", 0x1d14),
+    ("Attributes", 0x1502),
+    ("Sequence", 0x0104)
+];
+
+// The columns of the `_Validation` table, which the `msi` crate requires in the catalog; it is left empty.
+const VALIDATION_COLUMNS: [(&str, i32); 10] = [
+    ("Table", 0x2d20),
+    ("Column", 0x2d20),
+    ("Nullable", 0x0d04),
+    ("MinValue", 0x1104),
+    ("MaxValue", 0x1104),
+    ("KeyTable", 0x1dff),
+    ("KeyColumn", 0x1502),
+    ("Category", 0x1d20),
+    ("Set", 0x1dff),
+    ("Description", 0x1dff)
+];
+
+enum Cell {
+    Null,
+    Int(i32),
+    Str(String)
+}
+
+fn file_cell(column: usize, index: usize) -> Cell
+{
+    match column
+    {
+        0 => Cell::Str(format!("file{}", index)),
+        1 => Cell::Str(format!("Component{}", index % 1000)),
+        2 => Cell::Str(format!("FILE{}.DLL|file{}.dll", index % 10_000, index)),
+        3 => Cell::Int((index % 100_000) as i32),
+        4 if index.is_multiple_of(4) => Cell::Str("1.0.0.0".to_string()),
+        5 if index.is_multiple_of(4) => Cell::Str("1033".to_string()),
+        6 => Cell::Int(512),
+        7 => Cell::Int(index as i32 + 1),
+        _ => Cell::Null
+    }
+}
+
+// The `msi` crate cannot write pools of more than 64K strings, so the tables are encoded here with
+// three-byte string references and written over the catalog of an empty package.
+#[derive(Default)]
+struct Encoder {
+    ids: HashMap<String, u32>,
+    pool: Vec<u8>,
+    data: Vec<u8>
+}
+
+impl Encoder {
+
+    fn cell(&mut self, output: &mut Vec<u8>, cell: Cell, bits: i32)
+    {
+        match cell
+        {
+            Cell::Null if bits & 0x0800 != 0 => output.extend_from_slice(&[0, 0, 0]),
+            Cell::Null if bits & 0xff == 4 => output.extend_from_slice(&0u32.to_le_bytes()),
+            Cell::Null => output.extend_from_slice(&0u16.to_le_bytes()),
+            Cell::Int(value) if bits & 0xff == 4 => output.extend_from_slice(&((value as u32) ^ 0x8000_0000).to_le_bytes()),
+            Cell::Int(value) => output.extend_from_slice(&((value as u16) ^ 0x8000).to_le_bytes()),
+            Cell::Str(value) => {
+                let next = self.ids.len() as u32 + 1;
+                let id = *self.ids.entry(value.clone()).or_insert(next);
+                if id == next
+                {
+                    self.pool.extend_from_slice(&(value.len() as u16).to_le_bytes());
+                    self.pool.extend_from_slice(&1u16.to_le_bytes());
+                    self.data.extend_from_slice(value.as_bytes());
+                }
+                output.extend_from_slice(&id.to_le_bytes()[..3]);
+            }
+        }
+    }
+
+    // Encodes a table column by column.
+    fn table(&mut self, columns: &[i32], rows: usize, cell: &dyn Fn(usize, usize) -> Cell) -> Vec<u8>
+    {
+        let mut output = Vec::new();
+        for (column, bits) in columns.iter().enumerate()
+        {
+            for row in 0..rows
+            {
+                self.cell(&mut output, cell(column, row), *bits);
+            }
+        }
+
+        output
+    }
+}
+
+fn build_package() -> PathBuf
+{
+    let path = std::env::temp_dir().join(format!("msi-reader-bench-{}.msi", std::process::id()));
+    let file = OpenOptions::new().read(true).write(true).create(true).truncate(true).open(&path).unwrap();
+    msi::Package::create(msi::PackageType::Installer, file).unwrap().flush().unwrap();
+
+    let mut encoder = Encoder::default();
+    let catalog: Vec<(&str, usize, &str, i32)> = [("File", &FILE_COLUMNS[..]), ("_Validation", &VALIDATION_COLUMNS[..])].iter()
+        .flat_map(|(table, columns)| columns.iter().enumerate().map(move |(index, (name, bits))| (*table, index + 1, *name, *bits)))
+        .collect();
+    let tables = encoder.table(&[0x2d40], 2, &|_, row| Cell::Str(["File", "_Validation"][row].to_string()));
+    let columns = encoder.table(&[0x2d40, 0x2502, 0x2d40, 0x0502], catalog.len(), &|column, row| match column
+    {
+        0 => Cell::Str(catalog[row].0.to_string()),
+        1 => Cell::Int(catalog[row].1 as i32),
+        2 => Cell::Str(catalog[row].2.to_string()),
+        _ => Cell::Int(catalog[row].3)
+    });
+    let bits: Vec<i32> = FILE_COLUMNS.iter().map(|(_, bits)| *bits).collect();
+    let files = encoder.table(&bits, ROWS, &file_cell);
+
+    let mut pool = (65001u32 | 0x8000_0000).to_le_bytes().to_vec();
+    pool.extend_from_slice(&encoder.pool);
+    write_streams(&path, &[
+        ("_StringPool", &pool),
+        ("_StringData", &encoder.data),
+        ("_Tables", &tables),
+        ("_Columns", &columns),
+        ("_Validation", &[]),
+        ("File", &files)
+    ]);
+    path
+}
+
+fn write_streams(path: &Path, streams: &[(&str, &[u8])])
+{
+    let mut compound = cfb::open_rw(path).unwrap();
+    for (name, data) in streams
+    {
+        compound.create_stream(streamname::encode(name, true)).unwrap().write_all(data).unwrap();
+    }
+    compound.flush().unwrap();
+}
+
+fn tables(criterion: &mut Criterion)
+{
+    let path = build_package();
+    let package = MsiPackage::open(&path).unwrap();
+    assert_eq!(package.table("File").unwrap().len(), ROWS);
+
+    let mut group = criterion.benchmark_group("file-table-500k");
+    group.sample_size(10);
+    group.bench_function("decode", |bencher| bencher.iter(|| package.table("File").unwrap().len()));
+    group.bench_function("decode-and-iterate", |bencher| bencher.iter(|| {
+        package.table("File").unwrap().rows().map(|row| row.int("FileSize").unwrap_or(0) as i64).sum::<i64>()
+    }));
+    group.bench_function("decode-projected", |bencher| bencher.iter(|| {
+        package.table_columns("File", &["FileSize"]).unwrap().rows().map(|row| row.int("FileSize").unwrap_or(0) as i64).sum::<i64>()
+    }));
+    group.bench_function("content-hash", |bencher| bencher.iter(|| package.table("File").unwrap().content_hash()));
+    group.finish();
+
+    // the `msi` crate reading the same table, as a baseline
+    let mut baseline = msi::open(&path).unwrap();
+    assert_eq!(baseline.select_rows(msi::Select::table("File")).unwrap().len(), ROWS);
+
+    let mut group = criterion.benchmark_group("file-table-500k-baseline");
+    group.sample_size(10);
+    group.bench_function("msi-select-rows", |bencher| bencher.iter(|| baseline.select_rows(msi::Select::table("File")).unwrap().len()));
+    group.bench_function("msi-select-rows-and-iterate", |bencher| bencher.iter(|| {
+        baseline.select_rows(msi::Select::table("File")).unwrap().map(|row| row["FileSize"].as_int().unwrap_or(0) as i64).sum::<i64>()
+    }));
+    group.bench_function("msi-select-projected", |bencher| bencher.iter(|| {
+        baseline.select_rows(msi::Select::table("File").columns(&["FileSize"])).unwrap().map(|row| row[0].as_int().unwrap_or(0) as i64).sum::<i64>()
+    }));
+    group.finish();
+
+    let _ = std::fs::remove_file(&path);
+}
+
+criterion_group!(benches, tables);
+criterion_main!(benches);
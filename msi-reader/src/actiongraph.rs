@@ -0,0 +1,283 @@
+use std::collections::{ HashMap, HashSet };
+use std::fmt::Display;
+
+use crate::customaction::{ CustomActionRow, CustomActionTable, Scheduling };
+use crate::error::Result;
+use crate::package::MsiPackage;
+use crate::sequence::{ ScheduledAction, Sequence, SequenceTable };
+
+// The standard actions of Windows Installer that may appear in a sequence table.
+const STANDARD_ACTIONS: [&str; 76] = [
+    "AllocateRegistrySpace", "AppSearch", "BindImage", "CCPSearch", "CostFinalize", "CostInitialize", "CreateFolders",
+    "CreateShortcuts", "DeleteServices", "DisableRollback", "DuplicateFiles", "ExecuteAction", "FileCost",
+    "FindRelatedProducts", "ForceReboot", "InstallAdminPackage", "InstallExecute", "InstallExecuteAgain", "InstallFiles",
+    "InstallFinalize", "InstallInitialize", "InstallODBC", "InstallServices", "InstallSFPCatalogFile", "InstallValidate",
+    "IsolateComponents", "LaunchConditions", "MigrateFeatureStates", "MoveFiles", "MsiConfigureServices",
+    "MsiPublishAssemblies", "MsiUnpublishAssemblies", "PatchFiles", "ProcessComponents", "PublishComponents",
+    "PublishFeatures", "PublishProduct", "RegisterClassInfo", "RegisterComPlus", "RegisterExtensionInfo", "RegisterFonts",
+    "RegisterMIMEInfo", "RegisterProduct", "RegisterProgIdInfo", "RegisterTypeLibraries", "RegisterUser",
+    "RemoveDuplicateFiles", "RemoveEnvironmentStrings", "RemoveExistingProducts", "RemoveFiles", "RemoveFolders",
+    "RemoveIniValues", "RemoveODBC", "RemoveRegistryValues", "RemoveShortcuts", "ResolveSource", "RMCCPSearch",
+    "ScheduleReboot", "SelfRegModules", "SelfUnregModules", "SetODBCFolders", "StartServices", "StopServices",
+    "UnpublishComponents", "UnpublishFeatures", "UnregisterClassInfo", "UnregisterComPlus", "UnregisterExtensionInfo",
+    "UnregisterFonts", "UnregisterMIMEInfo", "UnregisterProgIdInfo", "UnregisterTypeLibraries", "ValidateProductID",
+    "WriteEnvironmentStrings", "WriteIniValues", "WriteRegistryValues"
+];
+
+#[doc = "What a scheduled action refers to."]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ActionNodeKind {
+    #[doc = "A standard action built into Windows Installer."]
+    Standard,
+    #[doc = "A row of the CustomAction table."]
+    Custom,
+    #[doc = "A row of the Dialog table, shown by the UI sequence."]
+    Dialog,
+    #[doc = "None of the above; Windows Installer fails when it reaches such an action."]
+    Unknown
+}
+
+impl Display for ActionNodeKind {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self
+        {
+            ActionNodeKind::Standard => write!(fmt, "standard"),
+            ActionNodeKind::Custom => write!(fmt, "custom"),
+            ActionNodeKind::Dialog => write!(fmt, "dialog"),
+            ActionNodeKind::Unknown => write!(fmt, "unknown")
+        }
+    }
+}
+
+#[doc = "An action at its place in the execution order, with the sequence row scheduling it and, for custom actions, the CustomAction row."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ActionNode {
+    sequence: Sequence,
+    scheduled: ScheduledAction,
+    kind: ActionNodeKind,
+    custom: Option<CustomActionRow>
+}
+
+impl ActionNode {
+
+    #[doc = "Returns the name of the action."]
+    pub fn action(&self) -> &str {
+        self.scheduled.action()
+    }
+
+    #[doc = "Returns the sequence table the action is scheduled in."]
+    pub fn sequence(&self) -> Sequence {
+        self.sequence
+    }
+
+    #[doc = "Returns the sequence row scheduling the action."]
+    pub fn scheduled(&self) -> &ScheduledAction {
+        &self.scheduled
+    }
+
+    #[doc = "Returns the condition under which the action runs, if any."]
+    pub fn condition(&self) -> Option<&str> {
+        self.scheduled.condition()
+    }
+
+    #[doc = "Returns what the action refers to."]
+    pub fn kind(&self) -> ActionNodeKind {
+        self.kind
+    }
+
+    #[doc = "Returns the CustomAction row of a custom action."]
+    pub fn custom(&self) -> Option<&CustomActionRow> {
+        self.custom.as_ref()
+    }
+
+    #[doc = "Returns when a custom action runs relative to the installation script; `None` for other actions."]
+    pub fn scheduling(&self) -> Option<Scheduling> {
+        self.custom.as_ref().map(|custom| custom.action_type().scheduling())
+    }
+}
+
+#[doc = "The actions of a package in the order Windows Installer reaches them, standard, custom and dialog actions alike. An action scheduled in two sequences, such as AppSearch, appears once for each."]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ActionGraph {
+    nodes: Vec<ActionNode>,
+    positions: HashMap<String, usize>
+}
+
+impl ActionGraph {
+
+    #[doc = "Builds the order of a full installation with UI: the InstallUISequence up to ExecuteAction, the whole InstallExecuteSequence, then the rest of the UI sequence. Without ExecuteAction the execute sequence follows the UI sequence. Exit dialogs and actions that never run are left out."]
+    pub fn build(package: &MsiPackage) -> Result<ActionGraph>
+    {
+        let ui = SequenceTable::read(package, Sequence::InstallUI)?;
+        let execute = SequenceTable::read(package, Sequence::InstallExecute)?;
+        let split = ui.actions().iter().position(|scheduled| scheduled.action() == "ExecuteAction").map(|index| index + 1).unwrap_or(ui.actions().len());
+
+        let timeline = ui.actions()[..split].iter().map(|scheduled| (Sequence::InstallUI, scheduled))
+            .chain(execute.actions().iter().map(|scheduled| (Sequence::InstallExecute, scheduled)))
+            .chain(ui.actions()[split..].iter().map(|scheduled| (Sequence::InstallUI, scheduled)));
+        ActionGraph::from_timeline(package, timeline)
+    }
+
+    #[doc = "Builds the order of a single sequence table, e.g. for administrative or advertising installations."]
+    pub fn build_sequence(package: &MsiPackage, sequence: Sequence) -> Result<ActionGraph>
+    {
+        let table = SequenceTable::read(package, sequence)?;
+        ActionGraph::from_timeline(package, table.actions().iter().map(|scheduled| (sequence, scheduled)))
+    }
+
+    fn from_timeline<'a>(package: &MsiPackage, timeline: impl Iterator<Item = (Sequence, &'a ScheduledAction)>) -> Result<ActionGraph>
+    {
+        let custom_actions = CustomActionTable::read(package)?;
+        let dialogs: HashSet<String> = match package.optional_table("Dialog")?
+        {
+            Some(table) => table.rows().filter_map(|row| row.str("Dialog").map(|name| name.to_string())).collect(),
+            None => HashSet::new()
+        };
+
+        let nodes: Vec<ActionNode> = timeline
+            .map(|(sequence, scheduled)| {
+                let custom = custom_actions.get(scheduled.action()).cloned();
+                let kind = if custom.is_some()
+                {
+                    ActionNodeKind::Custom
+                }
+                else if STANDARD_ACTIONS.contains(&scheduled.action())
+                {
+                    ActionNodeKind::Standard
+                }
+                else if dialogs.contains(scheduled.action())
+                {
+                    ActionNodeKind::Dialog
+                }
+                else
+                {
+                    ActionNodeKind::Unknown
+                };
+
+                ActionNode {
+                    sequence,
+                    scheduled: scheduled.clone(),
+                    kind,
+                    custom
+                }
+            })
+            .collect();
+
+        let mut positions = HashMap::new();
+        for (index, node) in nodes.iter().enumerate()
+        {
+            positions.entry(node.action().to_string()).or_insert(index);
+        }
+
+        Ok(ActionGraph {
+            nodes,
+            positions
+        })
+    }
+
+    #[doc = "Returns all actions in execution order."]
+    pub fn nodes(&self) -> &[ActionNode] {
+        &self.nodes
+    }
+
+    #[doc = "Returns the position of the first occurrence of an action."]
+    pub fn position(&self, action: &str) -> Option<usize> {
+        self.positions.get(action).copied()
+    }
+
+    #[doc = "Returns the first occurrence of an action."]
+    pub fn get(&self, action: &str) -> Option<&ActionNode> {
+        self.position(action).map(|index| &self.nodes[index])
+    }
+
+    #[doc = "Returns the actions reached after the first occurrence of `action`; none if it is not scheduled."]
+    pub fn after(&self, action: &str) -> &[ActionNode] {
+        self.position(action).map(|index| &self.nodes[index + 1..]).unwrap_or_default()
+    }
+
+    #[doc = "Returns the actions reached before the first occurrence of `action`; none if it is not scheduled."]
+    pub fn before(&self, action: &str) -> &[ActionNode] {
+        self.position(action).map(|index| &self.nodes[..index]).unwrap_or_default()
+    }
+
+    #[doc = "Returns the custom actions in execution order."]
+    pub fn custom_actions(&self) -> impl Iterator<Item = &ActionNode> {
+        self.nodes.iter().filter(|node| node.kind == ActionNodeKind::Custom)
+    }
+
+    #[doc = "Returns the custom actions with the given scheduling that are reached after `action`, e.g. the deferred ones after InstallFiles."]
+    pub fn custom_actions_after<'a>(&'a self, action: &str, scheduling: Scheduling) -> impl Iterator<Item = &'a ActionNode> {
+        self.after(action).iter().filter(move |node| node.scheduling() == Some(scheduling))
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::testutil::TestPackage;
+
+    #[test]
+    fn test_action_graph()
+    {
+        let package = TestPackage::new("action-graph", |builder| {
+            let columns = || vec![
+                msi::Column::build("Action").primary_key().id_string(72),
+                msi::Column::build("Condition").nullable().string(255),
+                msi::Column::build("Sequence").nullable().int16()
+            ];
+            let action = |name: &str, condition: Option<&str>, sequence: i32| vec![
+                msi::Value::from(name), condition.map(msi::Value::from).unwrap_or(msi::Value::Null), msi::Value::Int(sequence)
+            ];
+            builder.table("InstallUISequence", columns(), vec![
+                action("AppSearch", None, 50),
+                action("WelcomeDlg", Some("NOT Installed"), 1230),
+                action("ExecuteAction", None, 1300),
+                action("ShowReadme", None, 1400),
+                action("ExitDlg", None, -1)
+            ]);
+            builder.table("InstallExecuteSequence", columns(), vec![
+                action("AppSearch", None, 50),
+                action("SetPath", None, 100),
+                action("InstallInitialize", None, 1500),
+                action("ConfigureEarly", None, 3000),
+                action("InstallFiles", None, 4000),
+                action("Configure", Some("NOT REMOVE"), 4100),
+                action("UndoConfigure", None, 4050),
+                action("Mystery", None, 4200),
+                action("InstallFinalize", None, 6600)
+            ]);
+            builder.table("CustomAction", vec![
+                msi::Column::build("Action").primary_key().id_string(72),
+                msi::Column::build("Type").int16(),
+                msi::Column::build("Source").nullable().string(72),
+                msi::Column::build("Target").nullable().formatted_string(255)
+            ], vec![
+                vec![msi::Value::from("SetPath"), msi::Value::Int(51), msi::Value::from("INSTALLDIR"), msi::Value::from("C:\\App")],
+                vec![msi::Value::from("ConfigureEarly"), msi::Value::Int(1025), msi::Value::from("Helper"), msi::Value::from("Configure")],
+                vec![msi::Value::from("Configure"), msi::Value::Int(3073), msi::Value::from("Helper"), msi::Value::from("Configure")],
+                vec![msi::Value::from("UndoConfigure"), msi::Value::Int(1281), msi::Value::from("Helper"), msi::Value::from("Unconfigure")],
+                vec![msi::Value::from("ShowReadme"), msi::Value::Int(226), msi::Value::from("INSTALLDIR"), msi::Value::from("notepad readme.txt")]
+            ]);
+            builder.table("Dialog", vec![msi::Column::build("Dialog").primary_key().id_string(72)], vec![vec![msi::Value::from("WelcomeDlg")]]);
+        });
+
+        let graph = ActionGraph::build(&MsiPackage::open(package.path()).unwrap()).unwrap();
+        let names: Vec<&str> = graph.nodes().iter().map(|node| node.action()).collect();
+        assert_eq!(names, ["AppSearch", "WelcomeDlg", "ExecuteAction", "AppSearch", "SetPath", "InstallInitialize", "ConfigureEarly",
+            "InstallFiles", "UndoConfigure", "Configure", "Mystery", "InstallFinalize", "ShowReadme"]);
+
+        let deferred: Vec<&str> = graph.custom_actions_after("InstallFiles", Scheduling::Deferred).map(|node| node.action()).collect();
+        assert_eq!(deferred, ["Configure"]);
+        assert_eq!(graph.custom_actions_after("InstallFiles", Scheduling::Rollback).count(), 1);
+        assert_eq!(graph.custom_actions().count(), 5);
+
+        let welcome = graph.get("WelcomeDlg").unwrap();
+        assert_eq!((welcome.kind(), welcome.condition(), welcome.sequence()), (ActionNodeKind::Dialog, Some("NOT Installed"), Sequence::InstallUI));
+        assert_eq!(graph.get("Configure").unwrap().condition(), Some("NOT REMOVE"));
+        assert_eq!(graph.get("Mystery").unwrap().kind(), ActionNodeKind::Unknown);
+        assert_eq!(graph.get("InstallFiles").unwrap().kind(), ActionNodeKind::Standard);
+        assert_eq!(graph.before("SetPath").len(), 4);
+        assert!(graph.after("Missing").is_empty());
+    }
+}
@@ -0,0 +1,129 @@
+use std::io::SeekFrom;
+use std::path::Path;
+use std::sync::{ Arc, Mutex };
+
+use tokio::io::{ AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt };
+
+use crate::error::{ Error, Result };
+use crate::package::MsiPackage;
+
+// MsiPackage is not Send, as it may read from any Read + Seek source. The packages built here only
+// read from a Vec or a File, which are, and the rest of a package is Send.
+struct SendPackage(MsiPackage);
+
+unsafe impl Send for SendPackage {}
+
+#[doc = "A package opened or read from an async source, for services that fetch packages from object storage or the network."]
+#[doc = ""]
+#[doc = "Every operation runs as a task of its own on the runtime's blocking pool (`spawn_blocking`) and holds the package while it runs, so operations on the same package take turns and no thread is held between them. An operation that panics fails with an error and leaves the package usable. Clones share the package."]
+#[derive(Clone)]
+pub struct AsyncMsiPackage {
+    package: Arc<Mutex<SendPackage>>
+}
+
+impl AsyncMsiPackage {
+
+    #[doc = "Opens a package file like `MsiPackage::open`, on the blocking pool. Tables and streams are read from the file as they are needed. Must be called from within a Tokio runtime."]
+    pub async fn open<P: AsRef<Path>>(path: P) -> Result<AsyncMsiPackage>
+    {
+        let path = path.as_ref().to_path_buf();
+        Self::load(move || MsiPackage::open(path)).await
+    }
+
+    #[doc = "Reads the whole source from its start into memory without blocking the runtime, then parses it like `MsiPackage::parse_untrusted`. The bytes are kept for the lifetime of the package; use `open` for files on disk. Must be called from within a Tokio runtime."]
+    pub async fn from_reader<R: AsyncRead + AsyncSeek + Unpin>(mut reader: R) -> Result<AsyncMsiPackage>
+    {
+        reader.seek(SeekFrom::Start(0)).await?;
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data).await?;
+        Self::parse_untrusted(data).await
+    }
+
+    #[doc = "Parses a package from bytes like `MsiPackage::parse_untrusted`, on the blocking pool. Must be called from within a Tokio runtime."]
+    pub async fn parse_untrusted(data: Vec<u8>) -> Result<AsyncMsiPackage> {
+        Self::load(move || MsiPackage::parse_untrusted(data)).await
+    }
+
+    #[doc = "Runs a read against the package on the blocking pool and returns the result, e.g. `package.run(|package| package.table(\"File\")).await`."]
+    pub async fn run<T, F>(&self, read: F) -> Result<T>
+        where T: Send + 'static, F: FnOnce(&MsiPackage) -> Result<T> + Send + 'static
+    {
+        let package = Arc::clone(&self.package);
+        blocking(move || {
+            // a read that panicked holds no borrows of the package any more
+            let package = package.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            read(&package.0)
+        }).await
+    }
+
+    #[doc = "Returns the value of a property from the Property table, like `MsiPackage::property`."]
+    pub async fn property(&self, name: &str) -> Result<Option<String>>
+    {
+        let name = name.to_string();
+        self.run(move |package| package.property(&name)).await
+    }
+
+    #[doc = "Reads a stream by its decoded name, like `MsiPackage::read_stream`."]
+    pub async fn read_stream(&self, name: &str) -> Result<Vec<u8>>
+    {
+        let name = name.to_string();
+        self.run(move |package| package.read_stream(&name)).await
+    }
+
+    // Builds the package on the blocking pool.
+    async fn load<F: FnOnce() -> Result<MsiPackage> + Send + 'static>(build: F) -> Result<AsyncMsiPackage>
+    {
+        let package = blocking(move || build().map(SendPackage)).await?;
+        Ok(AsyncMsiPackage {
+            package: Arc::new(Mutex::new(package))
+        })
+    }
+}
+
+// Runs an operation on the blocking pool, reporting a panic in it as an error.
+async fn blocking<T: Send + 'static, F: FnOnce() -> Result<T> + Send + 'static>(operation: F) -> Result<T>
+{
+    tokio::task::spawn_blocking(operation).await
+        .unwrap_or_else(|error| Err(Error::Io(std::io::Error::other(format!("the package operation failed: {}", error)))))
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::testutil::TestPackage;
+
+    #[test]
+    fn test_async_package()
+    {
+        let package = TestPackage::new("async", |builder| {
+            builder.table("Property", vec![
+                msi::Column::build("Property").primary_key().id_string(72),
+                msi::Column::build("Value").localizable().text_string(0)
+            ], vec![
+                vec![msi::Value::from("ProductName"), msi::Value::from("Alpha")]
+            ]);
+            builder.stream("Binary.data", b"async");
+        });
+        let data = std::fs::read(package.path()).unwrap();
+
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        runtime.block_on(async {
+            let read = AsyncMsiPackage::from_reader(std::io::Cursor::new(data.clone())).await.unwrap();
+            assert_eq!(read.property("ProductName").await.unwrap().as_deref(), Some("Alpha"));
+            assert_eq!(read.read_stream("Binary.data").await.unwrap(), b"async");
+            assert!(read.run(|package| package.stream_names()).await.unwrap().contains(&"Binary.data".to_string()));
+            assert!(read.read_stream("Binary.missing").await.is_err());
+
+            // a panicking read fails on its own, and the package stays usable
+            assert!(read.run(|_| -> Result<()> { panic!("read failed") }).await.is_err());
+            assert_eq!(read.clone().read_stream("Binary.data").await.unwrap(), b"async");
+
+            let opened = AsyncMsiPackage::open(package.path()).await.unwrap();
+            assert_eq!(opened.property("ProductName").await.unwrap().as_deref(), Some("Alpha"));
+
+            assert!(AsyncMsiPackage::parse_untrusted(data[..512].to_vec()).await.is_err());
+            assert!(AsyncMsiPackage::open(package.path().with_extension("missing")).await.is_err());
+        });
+    }
+}
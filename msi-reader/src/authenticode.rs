@@ -0,0 +1,695 @@
+use std::fmt::Display;
+use std::io::Read;
+use std::path::Path;
+use std::str::FromStr;
+
+use sha2::digest::DynDigest;
+
+use crate::compound;
+use crate::der::{ self, Element, Reader };
+use crate::error::{ Error, Result };
+use crate::package::{ CompoundFile, MsiPackage };
+
+#[doc = "Name of the stream holding the PKCS#7 signature of a signed package."]
+pub const DIGITAL_SIGNATURE_STREAM: &str = "\u{5}DigitalSignature";
+#[doc = "Name of the stream holding the hash of the storage metadata, present when a package was signed with extended (metadata) hashing."]
+pub const DIGITAL_SIGNATURE_EX_STREAM: &str = "\u{5}MsiDigitalSignatureEx";
+
+const OID_SIGNED_DATA: &str = "1.2.840.113549.1.7.2";
+const OID_SIGNING_TIME: &str = "1.2.840.113549.1.9.5";
+const OID_COUNTER_SIGNATURE: &str = "1.2.840.113549.1.9.6";
+const OID_RFC3161_TIMESTAMP: &str = "1.3.6.1.4.1.311.3.3.1";
+
+#[doc = "The hash algorithm used for the signed content digest."]
+#[derive(Clone, Debug, PartialEq)]
+pub enum DigestAlgorithm {
+    Md5,
+    Sha1,
+    Sha256,
+    Sha384,
+    Sha512,
+    #[doc = "An algorithm not known to this crate, by OID."]
+    Other(String)
+}
+
+impl DigestAlgorithm {
+
+    fn from_oid(oid: &str) -> DigestAlgorithm
+    {
+        match oid
+        {
+            "1.2.840.113549.2.5" => DigestAlgorithm::Md5,
+            "1.3.14.3.2.26" => DigestAlgorithm::Sha1,
+            "2.16.840.1.101.3.4.2.1" => DigestAlgorithm::Sha256,
+            "2.16.840.1.101.3.4.2.2" => DigestAlgorithm::Sha384,
+            "2.16.840.1.101.3.4.2.3" => DigestAlgorithm::Sha512,
+            other => DigestAlgorithm::Other(other.to_string())
+        }
+    }
+
+    fn hasher(&self) -> Option<Box<dyn DynDigest>>
+    {
+        match self
+        {
+            DigestAlgorithm::Sha1 => Some(Box::new(sha1::Sha1::default())),
+            DigestAlgorithm::Sha256 => Some(Box::new(sha2::Sha256::default())),
+            DigestAlgorithm::Sha384 => Some(Box::new(sha2::Sha384::default())),
+            DigestAlgorithm::Sha512 => Some(Box::new(sha2::Sha512::default())),
+            DigestAlgorithm::Md5 | DigestAlgorithm::Other(_) => None
+        }
+    }
+}
+
+impl FromStr for DigestAlgorithm {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<DigestAlgorithm>
+    {
+        match value.to_ascii_lowercase().replace('-', "").as_str()
+        {
+            "md5" => Ok(DigestAlgorithm::Md5),
+            "sha1" => Ok(DigestAlgorithm::Sha1),
+            "sha256" => Ok(DigestAlgorithm::Sha256),
+            "sha384" => Ok(DigestAlgorithm::Sha384),
+            "sha512" => Ok(DigestAlgorithm::Sha512),
+            _ => Err(Error::NotFound(format!("digest algorithm '{}'", value)))
+        }
+    }
+}
+
+impl Display for DigestAlgorithm {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self
+        {
+            DigestAlgorithm::Md5 => write!(fmt, "MD5"),
+            DigestAlgorithm::Sha1 => write!(fmt, "SHA-1"),
+            DigestAlgorithm::Sha256 => write!(fmt, "SHA-256"),
+            DigestAlgorithm::Sha384 => write!(fmt, "SHA-384"),
+            DigestAlgorithm::Sha512 => write!(fmt, "SHA-512"),
+            DigestAlgorithm::Other(oid) => write!(fmt, "{}", oid)
+        }
+    }
+}
+
+#[doc = "An X.509 certificate embedded in a signature."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Certificate {
+    der: Vec<u8>,
+    serial_number: Vec<u8>,
+    issuer_der: Vec<u8>,
+    issuer: String,
+    subject: String,
+    not_before: String,
+    not_after: String
+}
+
+impl Certificate {
+
+    fn parse(der: &[u8]) -> Result<Certificate>
+    {
+        let certificate = Reader::new(der).read_tag(der::TAG_SEQUENCE)?;
+        let tbs = certificate.children().read_tag(der::TAG_SEQUENCE)?;
+        let mut fields = tbs.children();
+        fields.read_optional(der::TAG_CONTEXT_0)?;
+        let serial_number = fields.read_tag(der::TAG_INTEGER)?;
+        fields.read_tag(der::TAG_SEQUENCE)?;
+        let issuer = fields.read_tag(der::TAG_SEQUENCE)?;
+        let validity = fields.read_tag(der::TAG_SEQUENCE)?;
+        let subject = fields.read_tag(der::TAG_SEQUENCE)?;
+
+        let mut times = validity.children();
+        Ok(Certificate {
+            der: der.to_vec(),
+            serial_number: serial_number.content().to_vec(),
+            issuer_der: issuer.raw().to_vec(),
+            issuer: format_name(&issuer)?,
+            subject: format_name(&subject)?,
+            not_before: times.read()?.time()?,
+            not_after: times.read()?.time()?
+        })
+    }
+
+    #[doc = "Returns the subject as a distinguished name, e.g. `CN=Contoso, O=Contoso Ltd, C=US`."]
+    pub fn subject(&self) -> &str {
+        &self.subject
+    }
+
+    #[doc = "Returns the issuer as a distinguished name."]
+    pub fn issuer(&self) -> &str {
+        &self.issuer
+    }
+
+    #[doc = "Returns the serial number as upper-case hex."]
+    pub fn serial_number(&self) -> String {
+        hex(&self.serial_number)
+    }
+
+    #[doc = "Returns the start of the validity period."]
+    pub fn not_before(&self) -> &str {
+        &self.not_before
+    }
+
+    #[doc = "Returns the end of the validity period."]
+    pub fn not_after(&self) -> &str {
+        &self.not_after
+    }
+
+    #[doc = "Returns the SHA-1 thumbprint, as shown by Windows."]
+    pub fn thumbprint_sha1(&self) -> String {
+        use sha1::Digest;
+        hex(&sha1::Sha1::digest(&self.der))
+    }
+
+    #[doc = "Returns the SHA-256 thumbprint."]
+    pub fn thumbprint_sha256(&self) -> String {
+        use sha2::Digest;
+        hex(&sha2::Sha256::digest(&self.der))
+    }
+
+    #[doc = "Returns the DER encoding of the certificate."]
+    pub fn der(&self) -> &[u8] {
+        &self.der
+    }
+}
+
+#[doc = "The outcome of checking the signed digest against the package content."]
+#[derive(Clone, Debug, PartialEq)]
+pub enum Verification {
+    #[doc = "The digest of the package content matches the signed digest. The signer's signature over it and the certificate chain are not checked."]
+    DigestMatches,
+    #[doc = "The package was modified after signing."]
+    DigestMismatch,
+    #[doc = "The signature cannot be checked by this crate, for the given reason."]
+    Unsupported(String)
+}
+
+impl Display for Verification {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self
+        {
+            Verification::DigestMatches => write!(fmt, "content digest matches (signature not verified)"),
+            Verification::DigestMismatch => write!(fmt, "invalid (the package was modified after signing)"),
+            Verification::Unsupported(reason) => write!(fmt, "not verified ({})", reason)
+        }
+    }
+}
+
+#[doc = "The Authenticode signature of a package, decoded from its `\\u{5}DigitalSignature` stream."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Signature {
+    digest_algorithm: DigestAlgorithm,
+    digest: Vec<u8>,
+    certificates: Vec<Certificate>,
+    signer: Option<usize>,
+    timestamp: Option<String>,
+    extended: bool
+}
+
+impl Signature {
+
+    #[doc = "Reads the signature of the package, or returns `None` if it is not signed."]
+    pub fn read(package: &MsiPackage) -> Result<Option<Signature>>
+    {
+        let names = package.raw_stream_names()?;
+        if !names.iter().any(|name| name == DIGITAL_SIGNATURE_STREAM)
+        {
+            return Ok(None);
+        }
+
+        let mut signature = Signature::parse(&package.read_raw_stream(DIGITAL_SIGNATURE_STREAM)?)?;
+        signature.extended = names.iter().any(|name| name == DIGITAL_SIGNATURE_EX_STREAM);
+        Ok(Some(signature))
+    }
+
+    #[doc = "Parses a DER-encoded PKCS#7 SignedData structure holding an Authenticode signature."]
+    pub fn parse(data: &[u8]) -> Result<Signature>
+    {
+        let content_info = Reader::new(data).read_tag(der::TAG_SEQUENCE)?;
+        let mut content_info = content_info.children();
+        if content_info.read()?.oid()? != OID_SIGNED_DATA
+        {
+            return Err(Error::invalid("the signature is not PKCS#7 signed data"));
+        }
+
+        let signed_data = content_info.read_tag(der::TAG_CONTEXT_0)?.children().read_tag(der::TAG_SEQUENCE)?;
+        let mut fields = signed_data.children();
+        fields.read_tag(der::TAG_INTEGER)?;
+        fields.read_tag(der::TAG_SET)?;
+
+        // SpcIndirectDataContent: { SpcAttributeTypeAndOptionalValue, DigestInfo }
+        let mut encapsulated = fields.read_tag(der::TAG_SEQUENCE)?.children();
+        encapsulated.read()?;
+        let indirect = encapsulated.read_tag(der::TAG_CONTEXT_0)?.children().read_tag(der::TAG_SEQUENCE)?;
+        let mut indirect = indirect.children();
+        indirect.read_tag(der::TAG_SEQUENCE)?;
+        let mut digest_info = indirect.read_tag(der::TAG_SEQUENCE)?.children();
+        let digest_algorithm = DigestAlgorithm::from_oid(&digest_info.read_tag(der::TAG_SEQUENCE)?.children().read()?.oid()?);
+        let digest = digest_info.read_tag(der::TAG_OCTET_STRING)?.content().to_vec();
+
+        let mut certificates = Vec::new();
+        if let Some(set) = fields.read_optional(der::TAG_CONTEXT_0)?
+        {
+            let mut set = set.children();
+            while !set.is_empty()
+            {
+                let element = set.read()?;
+                // other certificate formats (tagged choices) are skipped
+                if element.tag() == der::TAG_SEQUENCE
+                {
+                    certificates.push(Certificate::parse(element.raw())?);
+                }
+            }
+        }
+        fields.read_optional(der::TAG_CONTEXT_1)?;
+
+        let mut signer = None;
+        let mut timestamp = None;
+        let signer_infos = fields.read_tag(der::TAG_SET)?;
+        if let Some(signer_info) = signer_infos.children().read_optional(der::TAG_SEQUENCE)?
+        {
+            let mut fields = signer_info.children();
+            fields.read_tag(der::TAG_INTEGER)?;
+            let mut issuer_and_serial = fields.read_tag(der::TAG_SEQUENCE)?.children();
+            let issuer = issuer_and_serial.read_tag(der::TAG_SEQUENCE)?;
+            let serial_number = issuer_and_serial.read_tag(der::TAG_INTEGER)?;
+            signer = certificates.iter().position(|certificate| certificate.issuer_der == issuer.raw() && certificate.serial_number == serial_number.content());
+
+            fields.read_tag(der::TAG_SEQUENCE)?;
+            fields.read_optional(der::TAG_CONTEXT_0)?;
+            fields.read_tag(der::TAG_SEQUENCE)?;
+            fields.read_tag(der::TAG_OCTET_STRING)?;
+            if let Some(unauthenticated) = fields.read_optional(der::TAG_CONTEXT_1)?
+            {
+                timestamp = read_timestamp(&unauthenticated)?;
+            }
+        }
+
+        Ok(Signature {
+            digest_algorithm,
+            digest,
+            certificates,
+            signer,
+            timestamp,
+            extended: false
+        })
+    }
+
+    #[doc = "Returns the algorithm of the signed content digest."]
+    pub fn digest_algorithm(&self) -> &DigestAlgorithm {
+        &self.digest_algorithm
+    }
+
+    #[doc = "Returns the signed digest of the package content."]
+    pub fn digest(&self) -> &[u8] {
+        &self.digest
+    }
+
+    #[doc = "Returns all certificates embedded in the signature."]
+    pub fn certificates(&self) -> &[Certificate] {
+        &self.certificates
+    }
+
+    #[doc = "Returns the certificate of the signer, if it is embedded."]
+    pub fn signer(&self) -> Option<&Certificate> {
+        self.signer.map(|index| &self.certificates[index])
+    }
+
+    #[doc = "Returns the time of the counter-signature or RFC 3161 timestamp, if the signature is timestamped."]
+    pub fn timestamp(&self) -> Option<&str> {
+        self.timestamp.as_deref()
+    }
+
+    #[doc = "Returns a boolean value indicating whether the package also carries an `MsiDigitalSignatureEx` metadata hash."]
+    pub fn is_extended(&self) -> bool {
+        self.extended
+    }
+
+    #[doc = "Recomputes the content digest of the package and compares it with the signed one. This detects any modification of the package; it does not validate the certificate chain or the signer's cryptographic signature."]
+    pub fn verify(&self, package: &MsiPackage) -> Result<Verification>
+    {
+        if self.extended
+        {
+            return Ok(Verification::Unsupported("extended metadata hashing (MsiDigitalSignatureEx) is not supported".to_string()));
+        }
+
+        match content_digest(package, &self.digest_algorithm)?
+        {
+            Some(digest) if digest == self.digest => Ok(Verification::DigestMatches),
+            Some(_) => Ok(Verification::DigestMismatch),
+            None => Ok(Verification::Unsupported(format!("digest algorithm {} is not supported", self.digest_algorithm)))
+        }
+    }
+}
+
+#[doc = "Computes the Authenticode digest of a package: the content of every stream except the signature streams, visited storage by storage in the order Windows uses, followed by each storage's CLSID. Returns `None` for unsupported algorithms."]
+pub fn content_digest(package: &MsiPackage, algorithm: &DigestAlgorithm) -> Result<Option<Vec<u8>>>
+{
+    let mut hasher = match algorithm.hasher()
+    {
+        Some(hasher) => hasher,
+        None => return Ok(None)
+    };
+
+    package.with_compound(|compound| {
+        let root = compound.root_entry();
+        hash_storage(compound, root.path(), *root.clsid().as_bytes(), hasher.as_mut(), true)
+    })?;
+
+    Ok(Some(hasher.finalize().into_vec()))
+}
+
+#[doc = "Removes the signature streams from the package file, e.g. before editing it for re-signing. Returns a boolean value indicating whether the package was signed."]
+pub fn remove_signature<P: AsRef<Path>>(path: P) -> Result<bool>
+{
+    let mut compound = compound::open_rw(path)?;
+    let mut removed = false;
+    for stream in [DIGITAL_SIGNATURE_STREAM, DIGITAL_SIGNATURE_EX_STREAM].iter()
+    {
+        if compound.is_stream(stream)
+        {
+            compound.remove_stream(stream)?;
+            removed = true;
+        }
+    }
+
+    compound.flush()?;
+    Ok(removed)
+}
+
+fn hash_storage(compound: &mut CompoundFile, path: &std::path::Path, clsid: [u8; 16], hasher: &mut dyn DynDigest, is_root: bool) -> Result<()>
+{
+    let mut entries: Vec<(Vec<u8>, cfb::Entry)> = compound.read_storage(path)?
+        .filter(|entry| !is_root || (entry.name() != DIGITAL_SIGNATURE_STREAM && entry.name() != DIGITAL_SIGNATURE_EX_STREAM))
+        .map(|entry| (entry.name().encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect(), entry))
+        .collect();
+
+    entries.sort_by(|(first, _), (second, _)| hash_order(first, second));
+
+    for (_, entry) in entries
+    {
+        if entry.is_stream()
+        {
+            let mut data = Vec::new();
+            compound.open_stream(entry.path())?.read_to_end(&mut data)?;
+            hasher.update(&data);
+        }
+        else if entry.is_storage()
+        {
+            hash_storage(compound, entry.path(), *entry.clsid().as_bytes(), hasher, false)?;
+        }
+    }
+
+    hasher.update(&clsid_bytes(clsid));
+    Ok(())
+}
+
+// Orders entries by the raw bytes of their UTF-16LE names like signtool does: when one name is a prefix of the other,
+// the longer name comes first.
+fn hash_order(first: &[u8], second: &[u8]) -> std::cmp::Ordering
+{
+    let length = first.len().min(second.len());
+    first[..length].cmp(&second[..length]).then_with(|| second.len().cmp(&first.len()))
+}
+
+// Converts a CLSID from RFC 4122 byte order to the mixed-endian order it is stored in.
+fn clsid_bytes(clsid: [u8; 16]) -> [u8; 16]
+{
+    let mut bytes = clsid;
+    bytes[0..4].reverse();
+    bytes[4..6].reverse();
+    bytes[6..8].reverse();
+    bytes
+}
+
+fn read_timestamp(attributes: &Element) -> Result<Option<String>>
+{
+    let mut attributes = attributes.children();
+    while !attributes.is_empty()
+    {
+        let mut attribute = attributes.read_tag(der::TAG_SEQUENCE)?.children();
+        let oid = attribute.read()?.oid()?;
+        let values = attribute.read_tag(der::TAG_SET)?;
+        match oid.as_str()
+        {
+            OID_COUNTER_SIGNATURE => {
+                let counter_signer = values.children().read_tag(der::TAG_SEQUENCE)?;
+                let mut fields = counter_signer.children();
+                fields.read_tag(der::TAG_INTEGER)?;
+                fields.read_tag(der::TAG_SEQUENCE)?;
+                fields.read_tag(der::TAG_SEQUENCE)?;
+                if let Some(signed) = fields.read_optional(der::TAG_CONTEXT_0)?
+                {
+                    let mut signed = signed.children();
+                    while !signed.is_empty()
+                    {
+                        let mut attribute = signed.read_tag(der::TAG_SEQUENCE)?.children();
+                        if attribute.read()?.oid()? == OID_SIGNING_TIME
+                        {
+                            return Ok(Some(attribute.read_tag(der::TAG_SET)?.children().read()?.time()?));
+                        }
+                    }
+                }
+            },
+            OID_RFC3161_TIMESTAMP => {
+                // ContentInfo { signedData, [0] SignedData { version, digestAlgorithms, { tstInfo, [0] OCTET STRING TSTInfo } } }
+                let mut content_info = values.children().read_tag(der::TAG_SEQUENCE)?.children();
+                content_info.read()?;
+                let mut signed_data = content_info.read_tag(der::TAG_CONTEXT_0)?.children().read_tag(der::TAG_SEQUENCE)?.children();
+                signed_data.read_tag(der::TAG_INTEGER)?;
+                signed_data.read_tag(der::TAG_SET)?;
+                let mut encapsulated = signed_data.read_tag(der::TAG_SEQUENCE)?.children();
+                encapsulated.read()?;
+                let tst_info = encapsulated.read_tag(der::TAG_CONTEXT_0)?.children().read_tag(der::TAG_OCTET_STRING)?;
+
+                // TSTInfo { version, policy, messageImprint, serialNumber, genTime, ... }
+                let mut fields = Reader::new(tst_info.content()).read_tag(der::TAG_SEQUENCE)?.children();
+                for _ in 0..4
+                {
+                    fields.read()?;
+                }
+                return Ok(Some(fields.read_tag(der::TAG_GENERALIZED_TIME)?.time()?));
+            },
+            _ => {}
+        }
+    }
+
+    Ok(None)
+}
+
+fn format_name(name: &Element) -> Result<String>
+{
+    let mut parts = Vec::new();
+    let mut sets = name.children();
+    while !sets.is_empty()
+    {
+        let mut attributes = sets.read_tag(der::TAG_SET)?.children();
+        while !attributes.is_empty()
+        {
+            let mut attribute = attributes.read_tag(der::TAG_SEQUENCE)?.children();
+            let oid = attribute.read()?.oid()?;
+            let value = attribute.read()?.string();
+            let label = match oid.as_str()
+            {
+                "2.5.4.3" => "CN",
+                "2.5.4.5" => "SERIALNUMBER",
+                "2.5.4.6" => "C",
+                "2.5.4.7" => "L",
+                "2.5.4.8" => "S",
+                "2.5.4.9" => "STREET",
+                "2.5.4.10" => "O",
+                "2.5.4.11" => "OU",
+                "1.2.840.113549.1.9.1" => "E",
+                other => other
+            };
+            parts.push(format!("{}={}", label, value));
+        }
+    }
+
+    // Windows lists the most specific component first
+    parts.reverse();
+    Ok(parts.join(", "))
+}
+
+fn hex(bytes: &[u8]) -> String
+{
+    bytes.iter().map(|byte| format!("{:02X}", byte)).collect()
+}
+
+#[cfg(test)]
+pub(crate) mod tests
+{
+    use super::*;
+    use crate::der::tests::{ constructed, encode, oid };
+    use crate::testutil::TestPackage;
+
+    fn name(common_name: &str) -> Vec<u8>
+    {
+        constructed(der::TAG_SEQUENCE, &[
+            constructed(der::TAG_SET, &[constructed(der::TAG_SEQUENCE, &[oid("2.5.4.6"), encode(0x13, b"US")])]),
+            constructed(der::TAG_SET, &[constructed(der::TAG_SEQUENCE, &[oid("2.5.4.3"), encode(0x0c, common_name.as_bytes())])])
+        ])
+    }
+
+    #[doc = "Builds a minimal Authenticode PKCS#7 structure with a SHA-256 content digest, one certificate and a counter-signature timestamp."]
+    pub(crate) fn build_signature(digest: &[u8]) -> Vec<u8>
+    {
+        let algorithm = constructed(der::TAG_SEQUENCE, &[oid("2.16.840.1.101.3.4.2.1"), encode(0x05, &[])]);
+        let certificate = constructed(der::TAG_SEQUENCE, &[
+            constructed(der::TAG_SEQUENCE, &[
+                constructed(der::TAG_CONTEXT_0, &[encode(der::TAG_INTEGER, &[2])]),
+                encode(der::TAG_INTEGER, &[0x0a, 0xbc]),
+                constructed(der::TAG_SEQUENCE, &[oid("1.2.840.113549.1.1.11")]),
+                name("Test Root"),
+                constructed(der::TAG_SEQUENCE, &[encode(der::TAG_UTC_TIME, b"240101000000Z"), encode(der::TAG_UTC_TIME, b"340101000000Z")]),
+                name("Contoso")
+            ]),
+            constructed(der::TAG_SEQUENCE, &[oid("1.2.840.113549.1.1.11")]),
+            encode(0x03, &[0, 1, 2, 3])
+        ]);
+        let indirect = constructed(der::TAG_SEQUENCE, &[
+            constructed(der::TAG_SEQUENCE, &[oid("1.3.6.1.4.1.311.2.1.30")]),
+            constructed(der::TAG_SEQUENCE, &[algorithm.clone(), encode(der::TAG_OCTET_STRING, digest)])
+        ]);
+        let counter_signature = constructed(der::TAG_SEQUENCE, &[
+            encode(der::TAG_INTEGER, &[1]),
+            constructed(der::TAG_SEQUENCE, &[name("Timestamper"), encode(der::TAG_INTEGER, &[1])]),
+            algorithm.clone(),
+            constructed(der::TAG_CONTEXT_0, &[constructed(der::TAG_SEQUENCE, &[
+                oid(OID_SIGNING_TIME),
+                constructed(der::TAG_SET, &[encode(der::TAG_UTC_TIME, b"240315093000Z")])
+            ])])
+        ]);
+        let signer_info = constructed(der::TAG_SEQUENCE, &[
+            encode(der::TAG_INTEGER, &[1]),
+            constructed(der::TAG_SEQUENCE, &[name("Test Root"), encode(der::TAG_INTEGER, &[0x0a, 0xbc])]),
+            algorithm.clone(),
+            constructed(der::TAG_SEQUENCE, &[oid("1.2.840.113549.1.1.1")]),
+            encode(der::TAG_OCTET_STRING, &[9; 16]),
+            constructed(der::TAG_CONTEXT_1, &[constructed(der::TAG_SEQUENCE, &[
+                oid(OID_COUNTER_SIGNATURE),
+                constructed(der::TAG_SET, &[counter_signature])
+            ])])
+        ]);
+
+        constructed(der::TAG_SEQUENCE, &[
+            oid(OID_SIGNED_DATA),
+            constructed(der::TAG_CONTEXT_0, &[constructed(der::TAG_SEQUENCE, &[
+                encode(der::TAG_INTEGER, &[1]),
+                constructed(der::TAG_SET, &[algorithm]),
+                constructed(der::TAG_SEQUENCE, &[oid("1.3.6.1.4.1.311.2.1.4"), constructed(der::TAG_CONTEXT_0, &[indirect])]),
+                constructed(der::TAG_CONTEXT_0, &[certificate]),
+                constructed(der::TAG_SET, &[signer_info])
+            ])])
+        ])
+    }
+
+    #[doc = "Writes a signature stream into an existing package file."]
+    pub(crate) fn sign(path: &std::path::Path, signature: &[u8])
+    {
+        use std::io::Write;
+        let mut compound = cfb::open_rw(path).unwrap();
+        compound.create_stream(DIGITAL_SIGNATURE_STREAM).unwrap().write_all(signature).unwrap();
+        compound.flush().unwrap();
+    }
+
+    #[test]
+    fn test_hash_order()
+    {
+        let mut names: Vec<Vec<u8>> = ["Bin", "Binary", "Binary.Icon"].iter()
+            .map(|name| crate::streamname::encode(name, false).encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect())
+            .collect();
+        names.sort_by(|first, second| hash_order(first, second));
+        let names: Vec<String> = names.iter().map(|name| {
+            let units: Vec<u16> = name.chunks(2).map(|pair| u16::from_le_bytes([pair[0], pair[1]])).collect();
+            crate::streamname::decode(&String::from_utf16(&units).unwrap()).0
+        }).collect();
+        assert_eq!(names, vec!["Binary.Icon", "Binary", "Bin"]);
+    }
+
+    #[test]
+    fn test_content_digest_prefixed_names()
+    {
+        let package = TestPackage::new("digest_prefixed_names", |builder| {
+            builder.stream("Binary", b"short");
+            builder.stream("Binary.Icon", b"long");
+        });
+
+        // plain byte order puts Binary before Binary.Icon; signtool hashes the longer name first
+        let mut compound = cfb::open(package.path()).unwrap();
+        let mut names: Vec<String> = compound.read_storage("/").unwrap().filter(|entry| entry.is_stream()).map(|entry| entry.name().to_string()).collect();
+        names.sort_by_key(|name| name.encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect::<Vec<u8>>());
+        let short = names.iter().position(|name| *name == crate::streamname::encode("Binary", false)).unwrap();
+        let long = names.iter().position(|name| *name == crate::streamname::encode("Binary.Icon", false)).unwrap();
+        assert_eq!(long, short + 1);
+        names.swap(short, long);
+
+        let mut hasher = sha2::Sha256::default();
+        for name in &names
+        {
+            let mut data = Vec::new();
+            compound.open_stream(format!("/{}", name)).unwrap().read_to_end(&mut data).unwrap();
+            hasher.update(&data);
+        }
+        hasher.update(&clsid_bytes(*compound.root_entry().clsid().as_bytes()));
+
+        let digest = content_digest(&MsiPackage::open(package.path()).unwrap(), &DigestAlgorithm::Sha256).unwrap().unwrap();
+        assert_eq!(digest, hasher.finalize_reset().to_vec());
+    }
+
+    #[test]
+    fn test_signature()
+    {
+        let package = TestPackage::new("signature", |builder| {
+            builder.table("Property", vec![
+                msi::Column::build("Property").primary_key().id_string(72),
+                msi::Column::build("Value").localizable().text_string(0)
+            ], vec![
+                vec![msi::Value::from("ProductName"), msi::Value::from("Alpha")]
+            ]);
+        });
+
+        assert!(Signature::read(&MsiPackage::open(package.path()).unwrap()).unwrap().is_none());
+        let digest = content_digest(&MsiPackage::open(package.path()).unwrap(), &DigestAlgorithm::Sha256).unwrap().unwrap();
+        sign(package.path(), &build_signature(&digest));
+
+        let opened = MsiPackage::open(package.path()).unwrap();
+        let signature = Signature::read(&opened).unwrap().unwrap();
+        assert_eq!(*signature.digest_algorithm(), DigestAlgorithm::Sha256);
+        assert_eq!(signature.timestamp(), Some("2024-03-15 09:30:00 UTC"));
+        let signer = signature.signer().unwrap();
+        assert_eq!(signer.subject(), "CN=Contoso, C=US");
+        assert_eq!(signer.issuer(), "CN=Test Root, C=US");
+        assert_eq!(signer.serial_number(), "0ABC");
+        assert_eq!(signer.not_after(), "2034-01-01 00:00:00 UTC");
+        assert_eq!(signer.thumbprint_sha1().len(), 40);
+        assert_eq!(signature.verify(&opened).unwrap(), Verification::DigestMatches);
+
+        let tampered = Signature::parse(&build_signature(&[0; 32])).unwrap();
+        assert_eq!(tampered.verify(&opened).unwrap(), Verification::DigestMismatch);
+
+        assert!(remove_signature(package.path()).unwrap());
+        assert!(!remove_signature(package.path()).unwrap());
+        let unsigned = MsiPackage::open(package.path()).unwrap();
+        assert!(unsigned.signature().unwrap().is_none());
+        assert_eq!(content_digest(&unsigned, &"sha-256".parse().unwrap()).unwrap().unwrap(), digest);
+    }
+
+    #[test]
+    fn test_parse_malformed()
+    {
+        let signature = build_signature(&[7; 32]);
+        for length in 0..signature.len()
+        {
+            assert!(Signature::parse(&signature[..length]).is_err());
+        }
+        for offset in 0..signature.len()
+        {
+            let mut corrupt = signature.clone();
+            corrupt[offset] ^= 0xff;
+            if let Ok(corrupt) = Signature::parse(&corrupt)
+            {
+                let _ = corrupt.signer().map(|signer| (signer.subject(), signer.not_after()));
+            }
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use crate::error::{ Error, Result };
+
+#[doc = "A bounds-checked little-endian cursor over a byte slice. Every read past the end yields an `Err` instead of panicking."]
+pub(crate) struct ByteReader<'a> {
+    data: &'a [u8],
+    position: usize
+}
+
+impl<'a> ByteReader<'a> {
+
+    pub(crate) fn new(data: &'a [u8]) -> Self
+    {
+        ByteReader {
+            data,
+            position: 0
+        }
+    }
+
+    pub(crate) fn remaining(&self) -> usize
+    {
+        self.data.len() - self.position
+    }
+
+    pub(crate) fn seek(&mut self, position: usize) -> Result<()>
+    {
+        if position > self.data.len()
+        {
+            return Err(Error::invalid(format!("offset {} is beyond the end of the data ({} bytes)", position, self.data.len())));
+        }
+
+        self.position = position;
+        Ok(())
+    }
+
+    pub(crate) fn skip(&mut self, count: usize) -> Result<()>
+    {
+        self.read_bytes(count).map(|_| ())
+    }
+
+    pub(crate) fn read_bytes(&mut self, count: usize) -> Result<&'a [u8]>
+    {
+        if count > self.remaining()
+        {
+            return Err(Error::invalid(format!("unexpected end of data at offset {} (wanted {} bytes, {} left)", self.position, count, self.remaining())));
+        }
+
+        let bytes = &self.data[self.position..self.position + count];
+        self.position += count;
+        Ok(bytes)
+    }
+
+    pub(crate) fn peek_rest(&self) -> &'a [u8]
+    {
+        &self.data[self.position..]
+    }
+
+    pub(crate) fn read_u8(&mut self) -> Result<u8>
+    {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    pub(crate) fn read_u16(&mut self) -> Result<u16>
+    {
+        let bytes = self.read_bytes(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    pub(crate) fn read_i16(&mut self) -> Result<i16>
+    {
+        Ok(self.read_u16()? as i16)
+    }
+
+    pub(crate) fn read_u32(&mut self) -> Result<u32>
+    {
+        let bytes = self.read_bytes(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    pub(crate) fn read_i32(&mut self) -> Result<i32>
+    {
+        Ok(self.read_u32()? as i32)
+    }
+
+    pub(crate) fn read_u64(&mut self) -> Result<u64>
+    {
+        let low = self.read_u32()? as u64;
+        let high = self.read_u32()? as u64;
+        Ok(low | (high << 32))
+    }
+}
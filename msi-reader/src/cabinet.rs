@@ -0,0 +1,804 @@
+use std::borrow::Cow;
+use std::io::{ self, Read };
+
+use crate::bytes::ByteReader;
+use crate::error::{ Error, Result };
+use crate::lzx;
+use crate::mszip;
+
+const SIGNATURE: &[u8] = b"MSCF";
+
+const FLAG_PREV_CABINET: u16 = 0x0001;
+const FLAG_NEXT_CABINET: u16 = 0x0002;
+const FLAG_RESERVE_PRESENT: u16 = 0x0004;
+
+const ATTRIBUTE_NAME_IS_UTF: u16 = 0x0080;
+
+// folder indexes of members spanning cabinets
+const FOLDER_CONTINUED_FROM_PREV: u16 = 0xfffd;
+const FOLDER_CONTINUED_TO_NEXT: u16 = 0xfffe;
+const FOLDER_CONTINUED_PREV_AND_NEXT: u16 = 0xffff;
+
+#[doc = "The compression method used by a cabinet folder."]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Compression {
+    None,
+    MsZip,
+    Quantum,
+    #[doc = "LZX with the given window size (in bits)."]
+    Lzx(u8),
+    Unknown(u16)
+}
+
+impl From<u16> for Compression {
+    fn from(value: u16) -> Self
+    {
+        match value & 0x000f
+        {
+            0 => Compression::None,
+            1 => Compression::MsZip,
+            2 => Compression::Quantum,
+            3 => Compression::Lzx(((value >> 8) & 0x1f) as u8),
+            _ => Compression::Unknown(value)
+        }
+    }
+}
+
+#[doc = "A folder (compression unit) of a cabinet."]
+#[derive(Clone, Debug)]
+pub struct CabFolder {
+    data_offset: u32,
+    data_blocks: u16,
+    compression: Compression
+}
+
+impl CabFolder {
+
+    #[doc = "Returns the compression method of the folder."]
+    pub fn compression(&self) -> Compression {
+        self.compression
+    }
+
+    #[doc = "Returns the number of data blocks stored in the folder."]
+    pub fn data_blocks(&self) -> u16 {
+        self.data_blocks
+    }
+}
+
+#[doc = "A file member of a cabinet."]
+#[derive(Clone, Debug)]
+pub struct CabFile {
+    name: String,
+    size: u32,
+    folder_offset: u32,
+    folder: u16,
+    attributes: u16
+}
+
+impl CabFile {
+
+    #[doc = "Returns the name of the member. For Windows Installer cabinets this is the File table key."]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[doc = "Returns the uncompressed size of the member."]
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    #[doc = "Returns the offset of the member within its uncompressed folder."]
+    pub fn folder_offset(&self) -> u32 {
+        self.folder_offset
+    }
+
+    #[doc = "Returns the index of the folder holding the member. Members spanning cabinets use 0xFFFD (continued from the previous cabinet), 0xFFFE (continued in the next one) or 0xFFFF (both) in a single cabinet, and the index of the merged folder in a `CabinetSet`."]
+    pub fn folder(&self) -> u16 {
+        self.folder
+    }
+
+    #[doc = "Returns a boolean value indicating whether the member starts in a previous cabinet."]
+    pub fn is_continued_from_previous(&self) -> bool {
+        self.folder == FOLDER_CONTINUED_FROM_PREV || self.folder == FOLDER_CONTINUED_PREV_AND_NEXT
+    }
+
+    #[doc = "Returns a boolean value indicating whether the member ends in a following cabinet."]
+    pub fn is_continued_in_next(&self) -> bool {
+        self.folder == FOLDER_CONTINUED_TO_NEXT || self.folder == FOLDER_CONTINUED_PREV_AND_NEXT
+    }
+
+    #[doc = "Returns the raw file attributes of the member."]
+    pub fn attributes(&self) -> u16 {
+        self.attributes
+    }
+}
+
+#[doc = "An in-memory Microsoft cabinet (.cab) archive."]
+pub struct Cabinet {
+    data: Vec<u8>,
+    data_reserve: usize,
+    previous_cabinet: Option<String>,
+    next_cabinet: Option<String>,
+    folders: Vec<CabFolder>,
+    files: Vec<CabFile>
+}
+
+impl Cabinet {
+
+    #[doc = "Returns a boolean value indicating whether the data starts with a cabinet signature."]
+    pub fn is_cabinet(data: &[u8]) -> bool {
+        data.starts_with(SIGNATURE)
+    }
+
+    #[doc = "Parses the cabinet header, folder and file entries."]
+    pub fn parse(data: Vec<u8>) -> Result<Cabinet>
+    {
+        let mut reader = ByteReader::new(&data);
+        if reader.read_bytes(4)? != SIGNATURE
+        {
+            return Err(Error::invalid("data is not a cabinet (missing MSCF signature)"));
+        }
+
+        // reserved1, cbCabinet, reserved2
+        reader.skip(12)?;
+        let files_offset = reader.read_u32()? as usize;
+        // reserved3, versionMinor, versionMajor
+        reader.skip(6)?;
+        let folder_count = reader.read_u16()?;
+        let file_count = reader.read_u16()?;
+        let flags = reader.read_u16()?;
+        // setID, iCabinet
+        reader.skip(4)?;
+
+        let mut folder_reserve = 0;
+        let mut data_reserve = 0;
+        if flags & FLAG_RESERVE_PRESENT != 0
+        {
+            let header_reserve = reader.read_u16()? as usize;
+            folder_reserve = reader.read_u8()? as usize;
+            data_reserve = reader.read_u8()? as usize;
+            reader.skip(header_reserve)?;
+        }
+
+        // the cabinet file name, then the prompt of the disk holding it
+        let mut previous_cabinet = None;
+        if flags & FLAG_PREV_CABINET != 0
+        {
+            previous_cabinet = Some(String::from_utf8_lossy(read_string(&mut reader)?).into_owned());
+            read_string(&mut reader)?;
+        }
+
+        let mut next_cabinet = None;
+        if flags & FLAG_NEXT_CABINET != 0
+        {
+            next_cabinet = Some(String::from_utf8_lossy(read_string(&mut reader)?).into_owned());
+            read_string(&mut reader)?;
+        }
+
+        let mut folders = Vec::with_capacity(folder_count as usize);
+        for _ in 0..folder_count
+        {
+            folders.push(CabFolder {
+                data_offset: reader.read_u32()?,
+                data_blocks: reader.read_u16()?,
+                compression: Compression::from(reader.read_u16()?)
+            });
+            reader.skip(folder_reserve)?;
+        }
+
+        reader.seek(files_offset)?;
+        let mut files = Vec::with_capacity(file_count as usize);
+        for _ in 0..file_count
+        {
+            let size = reader.read_u32()?;
+            let folder_offset = reader.read_u32()?;
+            let folder = reader.read_u16()?;
+            // date, time
+            reader.skip(4)?;
+            let attributes = reader.read_u16()?;
+            let name = read_string(&mut reader)?;
+            let name = if attributes & ATTRIBUTE_NAME_IS_UTF != 0
+            {
+                String::from_utf8_lossy(name).into_owned()
+            }
+            else
+            {
+                name.iter().map(|b| *b as char).collect()
+            };
+
+            files.push(CabFile {
+                name,
+                size,
+                folder_offset,
+                folder,
+                attributes
+            });
+        }
+
+        Ok(Cabinet {
+            data,
+            data_reserve,
+            previous_cabinet,
+            next_cabinet,
+            folders,
+            files
+        })
+    }
+
+    #[doc = "Returns the file name of the previous cabinet of a set spanning several cabinets."]
+    pub fn previous_cabinet(&self) -> Option<&str> {
+        self.previous_cabinet.as_deref()
+    }
+
+    #[doc = "Returns the file name of the next cabinet of a set spanning several cabinets."]
+    pub fn next_cabinet(&self) -> Option<&str> {
+        self.next_cabinet.as_deref()
+    }
+
+    #[doc = "Returns a boolean value indicating whether the last folder continues in the next cabinet."]
+    pub fn is_continued(&self) -> bool {
+        self.next_cabinet.is_some() && self.files.iter().any(|file| file.is_continued_in_next())
+    }
+
+    #[doc = "Returns the folders of the cabinet."]
+    pub fn folders(&self) -> &[CabFolder] {
+        &self.folders
+    }
+
+    #[doc = "Returns the file members of the cabinet."]
+    pub fn files(&self) -> &[CabFile] {
+        &self.files
+    }
+
+    #[doc = "Returns the member with the given name."]
+    pub fn file(&self, name: &str) -> Option<&CabFile> {
+        self.files.iter().find(|file| file.name == name)
+    }
+
+    #[doc = "Returns the uncompressed contents of the whole folder with the given index. Folders stored without compression, MSZIP folders and LZX folders with windows of 15 to 21 bits can be read."]
+    pub fn read_folder(&self, index: usize) -> Result<Vec<u8>>
+    {
+        self.open_folder(index)?.read_all()
+    }
+
+    #[doc = "Opens the folder with the given index for reading, decompressing one data block at a time as it is read."]
+    pub fn open_folder(&self, index: usize) -> Result<FolderReader<'_>>
+    {
+        let folder = self.folders.get(index)
+            .ok_or_else(|| Error::invalid(format!("cabinet has no folder {}", index)))?;
+        let blocks = self.folder_blocks(folder)?.into_iter().map(|(block, size)| (Cow::Borrowed(block), size)).collect();
+        FolderReader::new(folder.compression, blocks)
+    }
+
+    #[doc = "Returns the uncompressed contents of the given member. Members spanning cabinets can only be read from a `CabinetSet`."]
+    pub fn read_file(&self, file: &CabFile) -> Result<Vec<u8>>
+    {
+        member(file, &self.read_folder(self.folder_index(file))?)
+    }
+
+    #[doc = "Opens the given member for reading, decompressing its folder as it is read. The part of the folder in front of the member is decompressed and skipped here."]
+    pub fn open_file(&self, file: &CabFile) -> Result<io::Take<FolderReader<'_>>> {
+        self.open_folder(self.folder_index(file))?.into_member(file)
+    }
+
+    // Returns the index of the folder holding a member, taking spanning members to the first or last one.
+    fn folder_index(&self, file: &CabFile) -> usize {
+        match file.folder
+        {
+            FOLDER_CONTINUED_TO_NEXT => self.folders.len().saturating_sub(1),
+            FOLDER_CONTINUED_FROM_PREV | FOLDER_CONTINUED_PREV_AND_NEXT => 0,
+            folder => folder as usize
+        }
+    }
+
+    // Returns the compressed data blocks of a folder with their uncompressed sizes.
+    fn folder_blocks(&self, folder: &CabFolder) -> Result<Vec<(&[u8], usize)>>
+    {
+        let mut reader = ByteReader::new(&self.data);
+        reader.seek(folder.data_offset as usize)?;
+        let mut blocks = Vec::with_capacity(folder.data_blocks as usize);
+        for _ in 0..folder.data_blocks
+        {
+            let _checksum = reader.read_u32()?;
+            let compressed_size = reader.read_u16()? as usize;
+            let uncompressed_size = reader.read_u16()? as usize;
+            reader.skip(self.data_reserve)?;
+            blocks.push((reader.read_bytes(compressed_size)?, uncompressed_size));
+        }
+
+        Ok(blocks)
+    }
+}
+
+#[doc = "The cabinets of a set spanning several of them, in order, read as one: a folder continued in the next cabinet is merged with its continuation, and every member is listed once."]
+pub struct CabinetSet {
+    cabinets: Vec<Cabinet>,
+    // the pieces (cabinet and folder index) every folder of the set is stored in
+    folders: Vec<Vec<(usize, usize)>>,
+    files: Vec<CabFile>
+}
+
+impl CabinetSet {
+
+    #[doc = "Joins cabinets given in the order of the set. The first folder of a cabinet continues the last folder of the one before if members are continued from there. Members continued from a cabinet before the first one are left out."]
+    pub fn new(cabinets: Vec<Cabinet>) -> Result<CabinetSet>
+    {
+        let mut folders: Vec<Vec<(usize, usize)>> = Vec::new();
+        let mut files = Vec::new();
+        for (index, cabinet) in cabinets.iter().enumerate()
+        {
+            let continues = index > 0 && cabinet.files.iter().any(|file| file.is_continued_from_previous());
+            let mut first = folders.len();
+            for (folder_index, folder) in cabinet.folders.iter().enumerate()
+            {
+                match folders.last_mut()
+                {
+                    Some(pieces) if folder_index == 0 && continues => {
+                        let (previous, previous_folder) = pieces[0];
+                        if cabinets[previous].folders[previous_folder].compression != folder.compression
+                        {
+                            return Err(Error::invalid("continued cabinet folder changes its compression"));
+                        }
+
+                        pieces.push((index, folder_index));
+                        first -= 1;
+                    },
+                    _ => folders.push(vec![(index, folder_index)])
+                }
+            }
+
+            // members continued from the previous cabinet were listed by it already
+            for file in cabinet.files.iter().filter(|file| !file.is_continued_from_previous())
+            {
+                let folder = match file.folder
+                {
+                    FOLDER_CONTINUED_TO_NEXT => folders.len().saturating_sub(1),
+                    folder => first + folder as usize
+                };
+                files.push(CabFile {
+                    folder: folder as u16,
+                    ..file.clone()
+                });
+            }
+        }
+
+        Ok(CabinetSet {
+            cabinets,
+            folders,
+            files
+        })
+    }
+
+    #[doc = "Returns the cabinets of the set, in order."]
+    pub fn cabinets(&self) -> &[Cabinet] {
+        &self.cabinets
+    }
+
+    #[doc = "Returns the file members of all cabinets, with folder indexes of the merged folders."]
+    pub fn files(&self) -> &[CabFile] {
+        &self.files
+    }
+
+    #[doc = "Returns the member with the given name."]
+    pub fn file(&self, name: &str) -> Option<&CabFile> {
+        self.files.iter().find(|file| file.name == name)
+    }
+
+    #[doc = "Returns the uncompressed contents of the merged folder with the given index."]
+    pub fn read_folder(&self, index: usize) -> Result<Vec<u8>> {
+        self.open_folder(index)?.read_all()
+    }
+
+    #[doc = "Opens the merged folder with the given index for reading, decompressing one data block at a time as it is read."]
+    pub fn open_folder(&self, index: usize) -> Result<FolderReader<'_>>
+    {
+        let pieces = self.folders.get(index)
+            .ok_or_else(|| Error::invalid(format!("cabinet set has no folder {}", index)))?;
+
+        let mut blocks: Vec<(Cow<[u8]>, usize)> = Vec::new();
+        // a data block split between cabinets has an uncompressed size of 0 in the first one
+        let mut split = false;
+        for (cabinet, folder) in pieces
+        {
+            let cabinet = &self.cabinets[*cabinet];
+            for (block, uncompressed_size) in cabinet.folder_blocks(&cabinet.folders[*folder])?
+            {
+                match blocks.last_mut()
+                {
+                    Some((previous, size)) if split => {
+                        previous.to_mut().extend_from_slice(block);
+                        *size = uncompressed_size;
+                    },
+                    _ => blocks.push((Cow::Borrowed(block), uncompressed_size))
+                }
+                split = uncompressed_size == 0;
+            }
+        }
+
+        let (cabinet, folder) = pieces[0];
+        FolderReader::new(self.cabinets[cabinet].folders[folder].compression, blocks)
+    }
+
+    #[doc = "Returns the uncompressed contents of the given member."]
+    pub fn read_file(&self, file: &CabFile) -> Result<Vec<u8>> {
+        member(file, &self.read_folder(file.folder as usize)?)
+    }
+
+    #[doc = "Opens the given member for reading, like `Cabinet::open_file`."]
+    pub fn open_file(&self, file: &CabFile) -> Result<io::Take<FolderReader<'_>>> {
+        self.open_folder(file.folder as usize)?.into_member(file)
+    }
+}
+
+// How a folder reader turns data blocks into output.
+enum Decompressor {
+    Stored,
+    MsZip,
+    Lzx(Box<lzx::Decoder>)
+}
+
+#[doc = "Reads the uncompressed contents of a folder, decompressing a data block whenever the output of the previous one has been read. The reader borrows the compressed blocks from its cabinet, which is held in memory as a whole, or owns a copy of them after `into_owned`; besides them only the output of one block and the window of the compression are held."]
+pub struct FolderReader<'a> {
+    blocks: Vec<(Cow<'a, [u8]>, usize)>,
+    next: usize,
+    decompressor: Decompressor,
+    // the output of the last block, after the history MSZIP refers back to
+    output: Vec<u8>,
+    position: usize
+}
+
+impl<'a> FolderReader<'a> {
+
+    fn new(compression: Compression, blocks: Vec<(Cow<'a, [u8]>, usize)>) -> Result<FolderReader<'a>>
+    {
+        let decompressor = match compression
+        {
+            Compression::None => Decompressor::Stored,
+            Compression::MsZip => Decompressor::MsZip,
+            Compression::Lzx(window_bits) => Decompressor::Lzx(Box::new(lzx::Decoder::new(window_bits)?)),
+            other => return Err(Error::invalid(format!("cabinet compression {:?} is not supported", other)))
+        };
+
+        Ok(FolderReader {
+            blocks,
+            next: 0,
+            decompressor,
+            output: Vec::new(),
+            position: 0
+        })
+    }
+
+    #[doc = "Turns a reader borrowing its cabinet into one holding a copy of the compressed blocks, so it can outlive the cabinet."]
+    pub fn into_owned(self) -> FolderReader<'static>
+    {
+        FolderReader {
+            blocks: self.blocks.into_iter().map(|(block, size)| (Cow::Owned(block.into_owned()), size)).collect(),
+            next: self.next,
+            decompressor: self.decompressor,
+            output: self.output,
+            position: self.position
+        }
+    }
+
+    // Decompresses the next data block, returning false after the last one.
+    fn next_block(&mut self) -> Result<bool>
+    {
+        let (block, uncompressed_size) = match self.blocks.get(self.next)
+        {
+            Some(block) => block,
+            None => return Ok(false)
+        };
+        self.next += 1;
+
+        match &mut self.decompressor
+        {
+            Decompressor::Stored => {
+                if block.len() != *uncompressed_size
+                {
+                    return Err(Error::invalid("uncompressed cabinet block has mismatching sizes"));
+                }
+
+                self.output.clear();
+                self.output.extend_from_slice(block);
+                self.position = 0;
+            },
+            Decompressor::MsZip => {
+                // keep the window the next block may refer back to
+                self.output.drain(..self.output.len().saturating_sub(mszip::WINDOW_SIZE));
+                self.position = self.output.len();
+                mszip::inflate_block(block, &mut self.output, *uncompressed_size)?;
+            },
+            Decompressor::Lzx(decoder) => {
+                self.output.clear();
+                self.position = 0;
+                decoder.decode_frame(block, *uncompressed_size, &mut self.output)?;
+            }
+        }
+
+        Ok(true)
+    }
+
+    fn read_all(mut self) -> Result<Vec<u8>>
+    {
+        let mut output = Vec::new();
+        while self.next_block()?
+        {
+            output.extend_from_slice(&self.output[self.position..]);
+        }
+
+        Ok(output)
+    }
+
+    // Skips to the start of a member and limits the reader to its size.
+    fn into_member(mut self, file: &CabFile) -> Result<io::Take<FolderReader<'a>>>
+    {
+        let skipped = io::copy(&mut (&mut self).take(file.folder_offset as u64), &mut io::sink())?;
+        if skipped != file.folder_offset as u64
+        {
+            return Err(Error::invalid(format!("cabinet member '{}' extends beyond its folder", file.name)));
+        }
+
+        Ok(self.take(file.size as u64))
+    }
+}
+
+impl Read for FolderReader<'_> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize>
+    {
+        while self.position == self.output.len()
+        {
+            if !self.next_block().map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?
+            {
+                return Ok(0);
+            }
+        }
+
+        let count = buffer.len().min(self.output.len() - self.position);
+        buffer[..count].copy_from_slice(&self.output[self.position..self.position + count]);
+        self.position += count;
+        Ok(count)
+    }
+}
+
+// Cuts a member out of its uncompressed folder.
+fn member(file: &CabFile, folder: &[u8]) -> Result<Vec<u8>>
+{
+    let start = file.folder_offset as usize;
+    let end = start.saturating_add(file.size as usize);
+    if end > folder.len()
+    {
+        return Err(Error::invalid(format!("cabinet member '{}' extends beyond its folder", file.name)));
+    }
+
+    Ok(folder[start..end].to_vec())
+}
+
+fn read_string<'a>(reader: &mut ByteReader<'a>) -> Result<&'a [u8]>
+{
+    let bytes = reader.peek_rest();
+    let length = bytes.iter().position(|b| *b == 0)
+        .ok_or_else(|| Error::invalid("unterminated string in cabinet"))?;
+    reader.skip(length + 1)?;
+    Ok(&bytes[..length])
+}
+
+#[cfg(test)]
+pub(crate) mod tests
+{
+    use super::*;
+
+    // Builds a single-folder cabinet storing all members without compression.
+    pub(crate) fn build_cab(files: &[(&str, &[u8])]) -> Vec<u8>
+    {
+        let payload: Vec<u8> = files.iter().flat_map(|(_, data)| data.iter().copied()).collect();
+        build_compressed_cab(files, 0, &[(&payload, payload.len())])
+    }
+
+    // Builds a single-folder cabinet from already compressed data blocks and their uncompressed sizes.
+    pub(crate) fn build_compressed_cab(files: &[(&str, &[u8])], compression: u16, blocks: &[(&[u8], usize)]) -> Vec<u8>
+    {
+        let mut members = Vec::new();
+        let mut offset = 0;
+        for (name, data) in files
+        {
+            members.push((*name, data.len(), offset, 0));
+            offset += data.len();
+        }
+        build_spanned_cab((None, None), &members, compression, blocks)
+    }
+
+    // Builds a single-folder cabinet of a set linked to the previous and next cabinet, with members given
+    // as name, size, folder offset and folder index.
+    pub(crate) fn build_spanned_cab(links: (Option<&str>, Option<&str>), members: &[(&str, usize, usize, u16)], compression: u16,
+        blocks: &[(&[u8], usize)]) -> Vec<u8>
+    {
+        let mut flags = 0;
+        let mut names = Vec::new();
+        for (link, flag) in [(links.0, FLAG_PREV_CABINET), (links.1, FLAG_NEXT_CABINET)]
+        {
+            if let Some(name) = link
+            {
+                flags |= flag;
+                names.extend_from_slice(name.as_bytes());
+                names.extend_from_slice(&[0, 0]);
+            }
+        }
+
+        let mut entries = Vec::new();
+        for (name, size, offset, folder) in members
+        {
+            entries.extend_from_slice(&(*size as u32).to_le_bytes());
+            entries.extend_from_slice(&(*offset as u32).to_le_bytes());
+            entries.extend_from_slice(&folder.to_le_bytes());
+            entries.extend_from_slice(&[0, 0, 0, 0, 0x20, 0]);
+            entries.extend_from_slice(name.as_bytes());
+            entries.push(0);
+        }
+        let mut payload = Vec::new();
+        for (block, size) in blocks
+        {
+            payload.extend_from_slice(&[0; 4]);
+            payload.extend_from_slice(&(block.len() as u16).to_le_bytes());
+            payload.extend_from_slice(&(*size as u16).to_le_bytes());
+            payload.extend_from_slice(block);
+        }
+
+        let files_offset = 36 + names.len() + 8;
+        let data_offset = files_offset + entries.len();
+        let mut cab = Vec::new();
+        cab.extend_from_slice(SIGNATURE);
+        cab.extend_from_slice(&[0; 4]);
+        cab.extend_from_slice(&((data_offset + payload.len()) as u32).to_le_bytes());
+        cab.extend_from_slice(&[0; 4]);
+        cab.extend_from_slice(&(files_offset as u32).to_le_bytes());
+        cab.extend_from_slice(&[0, 0, 0, 0, 3, 1]);
+        cab.extend_from_slice(&1u16.to_le_bytes());
+        cab.extend_from_slice(&(members.len() as u16).to_le_bytes());
+        cab.extend_from_slice(&flags.to_le_bytes());
+        cab.extend_from_slice(&[0; 4]);
+        cab.extend_from_slice(&names);
+        cab.extend_from_slice(&(data_offset as u32).to_le_bytes());
+        cab.extend_from_slice(&(blocks.len() as u16).to_le_bytes());
+        cab.extend_from_slice(&compression.to_le_bytes());
+        cab.extend_from_slice(&entries);
+        cab.extend_from_slice(&payload);
+        cab
+    }
+
+    #[test]
+    fn test_uncompressed_members()
+    {
+        let cab = Cabinet::parse(build_cab(&[("first.txt", b"hello"), ("second.txt", b"world!")])).unwrap();
+
+        assert_eq!(cab.folders().len(), 1);
+        assert_eq!(cab.folders()[0].compression(), Compression::None);
+        assert_eq!(cab.files().len(), 2);
+
+        let second = cab.file("second.txt").unwrap();
+        assert_eq!(second.size(), 6);
+        assert_eq!(cab.read_file(second).unwrap(), b"world!");
+        assert!(cab.file("third.txt").is_none());
+
+        assert!(Cabinet::parse(b"MSCF".to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_mszip_members()
+    {
+        use crate::mszip::tests::{ FIRST, FIRST_BLOCK, SECOND, SECOND_BLOCK };
+
+        // the second member starts in the first block and ends in the second
+        let (first, second) = FIRST.split_at(40);
+        let second = [second, SECOND].concat();
+        let cab = Cabinet::parse(build_compressed_cab(&[("first.txt", first), ("second.txt", &second)], 1,
+            &[(FIRST_BLOCK, FIRST.len()), (SECOND_BLOCK, SECOND.len())])).unwrap();
+
+        assert_eq!(cab.folders()[0].compression(), Compression::MsZip);
+        assert_eq!(cab.read_file(cab.file("first.txt").unwrap()).unwrap(), first);
+        assert_eq!(cab.read_file(cab.file("second.txt").unwrap()).unwrap(), second);
+
+        // streamed in small pieces, the block boundary falls within a read
+        let mut reader = cab.open_file(cab.file("second.txt").unwrap()).unwrap();
+        let mut streamed = Vec::new();
+        let mut buffer = [0; 5];
+        loop
+        {
+            match reader.read(&mut buffer).unwrap()
+            {
+                0 => break,
+                count => streamed.extend_from_slice(&buffer[..count])
+            }
+        }
+        assert_eq!(streamed, second);
+
+        let corrupt = Cabinet::parse(build_compressed_cab(&[("first.txt", FIRST)], 1, &[(&FIRST_BLOCK[..20], FIRST.len())])).unwrap();
+        assert!(corrupt.read_folder(0).is_err());
+    }
+    #[test]
+    fn test_lzx_members()
+    {
+        use crate::lzx::tests::{ verbatim_block, VERBATIM };
+
+        let block = verbatim_block();
+        let cab = Cabinet::parse(build_compressed_cab(&[("abc.txt", &VERBATIM[..3]), ("abcabc.txt", &VERBATIM[3..])], 0x0f03,
+            &[(&block, VERBATIM.len())])).unwrap();
+
+        assert_eq!(cab.folders()[0].compression(), Compression::Lzx(15));
+        assert_eq!(cab.read_file(cab.file("abc.txt").unwrap()).unwrap(), b"abc");
+        assert_eq!(cab.read_file(cab.file("abcabc.txt").unwrap()).unwrap(), b"abcabc");
+
+        let unsupported = Cabinet::parse(build_compressed_cab(&[("abc.txt", VERBATIM)], 0x1603, &[(&block, VERBATIM.len())])).unwrap();
+        assert!(unsupported.read_folder(0).is_err());
+    }
+    #[test]
+    fn test_cabinet_set()
+    {
+        // "b.txt" spans both cabinets, and so does the data block holding "world!"
+        let first = Cabinet::parse(build_spanned_cab((None, Some("two.cab")), &[("a.txt", 6, 0, 0), ("b.txt", 6, 6, 0xfffe)], 0,
+            &[(b"hello ", 6), (b"wor", 0)])).unwrap();
+        let second = Cabinet::parse(build_spanned_cab((Some("one.cab"), None), &[("b.txt", 6, 6, 0xfffd), ("c.txt", 2, 12, 0)], 0,
+            &[(b"ld!", 6), (b"cc", 2)])).unwrap();
+
+        assert_eq!(first.next_cabinet(), Some("two.cab"));
+        assert_eq!(second.previous_cabinet(), Some("one.cab"));
+        assert!(first.is_continued());
+        assert!(!second.is_continued());
+        assert!(first.file("b.txt").unwrap().is_continued_in_next());
+        assert!(first.read_file(first.file("b.txt").unwrap()).is_err());
+
+        let set = CabinetSet::new(vec![first, second]).unwrap();
+        assert_eq!(set.files().iter().map(|file| file.name()).collect::<Vec<_>>(), ["a.txt", "b.txt", "c.txt"]);
+        assert!(set.files().iter().all(|file| file.folder() == 0));
+        assert_eq!(set.read_file(set.file("a.txt").unwrap()).unwrap(), b"hello ");
+        assert_eq!(set.read_file(set.file("b.txt").unwrap()).unwrap(), b"world!");
+        assert_eq!(set.read_file(set.file("c.txt").unwrap()).unwrap(), b"cc");
+
+        // without the first cabinet the continued member is left out
+        let second = Cabinet::parse(build_spanned_cab((Some("one.cab"), None), &[("b.txt", 6, 6, 0xfffd), ("c.txt", 2, 12, 0)], 0,
+            &[(b"ld!", 6), (b"cc", 2)])).unwrap();
+        let set = CabinetSet::new(vec![second]).unwrap();
+        assert_eq!(set.files().len(), 1);
+    }
+
+    #[test]
+    fn test_parse_malformed()
+    {
+        use crate::lzx::tests::{ verbatim_block, VERBATIM };
+        use crate::mszip::tests::{ FIRST, FIRST_BLOCK };
+
+        let block = verbatim_block();
+        for cab in [
+            build_cab(&[("first.txt", b"hello"), ("second.txt", b"world!")]),
+            build_compressed_cab(&[("first.txt", FIRST)], 1, &[(FIRST_BLOCK, FIRST.len())]),
+            build_compressed_cab(&[("abc.txt", VERBATIM)], 0x0f03, &[(&block, VERBATIM.len())])
+        ]
+        {
+            for length in 0..cab.len()
+            {
+                if let Ok(truncated) = Cabinet::parse(cab[..length].to_vec())
+                {
+                    assert!(truncated.read_folder(0).is_err(), "{}", length);
+                }
+            }
+            for offset in 0..cab.len()
+            {
+                let mut corrupt = cab.clone();
+                corrupt[offset] ^= 0xff;
+                if let Ok(corrupt) = Cabinet::parse(corrupt)
+                {
+                    for file in corrupt.files()
+                    {
+                        let _ = corrupt.read_file(file);
+                    }
+                    if let Ok(set) = CabinetSet::new(vec![corrupt])
+                    {
+                        for file in set.files()
+                        {
+                            let _ = set.read_file(file);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
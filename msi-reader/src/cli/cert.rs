@@ -0,0 +1,37 @@
+use msi_reader::{ Error, MsiPackage };
+
+use crate::cli::{ Args, Result };
+
+pub const USAGE: &str = "cert <package>                 show the Authenticode signature and verify it";
+
+#[doc = "Prints the signer, certificates, digest algorithm, timestamp and verification status of a signed package."]
+pub fn run(mut args: Args) -> Result<()>
+{
+    let path = args.positional("package")?;
+    args.finish()?;
+
+    let package = MsiPackage::open(&path)?;
+    let signature = package.signature()?
+        .ok_or_else(|| Error::NotFound(format!("digital signature in {}", path)))?;
+
+    match signature.signer()
+    {
+        Some(signer) => {
+            println!("Subject:            {}", signer.subject());
+            println!("Issuer:             {}", signer.issuer());
+            println!("Serial number:      {}", signer.serial_number());
+            println!("Valid from:         {}", signer.not_before());
+            println!("Valid to:           {}", signer.not_after());
+            println!("SHA-1 thumbprint:   {}", signer.thumbprint_sha1());
+            println!("SHA-256 thumbprint: {}", signer.thumbprint_sha256());
+        },
+        None => println!("Subject:            (signer certificate not embedded)")
+    }
+
+    println!("Digest algorithm:   {}", signature.digest_algorithm());
+    println!("Timestamp:          {}", signature.timestamp().unwrap_or("(none)"));
+    println!("Certificates:       {}", signature.certificates().len());
+    println!("Digest check:       {}", signature.verify(&package)?);
+
+    Ok(())
+}
@@ -0,0 +1,35 @@
+use msi_reader::MsiPackage;
+use msi_reader::codepage::CodepageConversion;
+
+use crate::cli::{ Args, Failure, Result };
+
+pub const USAGE: &str = "codepage <package> --to <codepage> [--check] [--lossy]
+                                   re-encode the string pool in another codepage, listing strings that would
+                                   change; lossy conversions are only written with --lossy";
+
+#[doc = "Converts the string pool of a package in place, or only reports the conversion with `--check`."]
+pub fn run(mut args: Args) -> Result<()>
+{
+    let to = args.option(&["--to"])?.ok_or_else(|| Failure::Usage("missing --to <codepage>".to_string()))?;
+    let check = args.flag(&["--check"]);
+    let lossy = args.flag(&["--lossy"]);
+    let path = args.positional("package")?;
+    args.finish()?;
+    let to: u32 = to.parse().map_err(|_| Failure::Usage(format!("'{}' is not a codepage number", to)))?;
+
+    let conversion = CodepageConversion::new(&MsiPackage::open(&path)?, to)?;
+    for string in conversion.lossy()
+    {
+        println!("{:>8}  {} -> {}", string.id(), string.original(), string.converted());
+    }
+    println!("{} of {} strings change converting codepage {} to {}", conversion.lossy().len(), conversion.strings(), conversion.from(), conversion.to());
+
+    if check
+    {
+        return if conversion.is_lossless() { Ok(()) } else { Err(Failure::Status(3)) };
+    }
+
+    conversion.write(&path, lossy)?;
+    println!("Wrote {}", path);
+    Ok(())
+}
@@ -0,0 +1,25 @@
+use msi_reader::MsiPackage;
+use msi_reader::cost::{ feature_costs, Attribution };
+
+use crate::cli::{ Args, Result };
+
+pub const USAGE: &str = "cost <package> [--attribution full|divided|first-owner]
+                                   show the installed size of every feature; shared components are charged
+                                   fully to each feature, divided between them, or to the first one";
+
+#[doc = "Prints the feature tree with the size of each feature and of its subtree."]
+pub fn run(mut args: Args) -> Result<()>
+{
+    let attribution: Attribution = args.option(&["--attribution"])?.as_deref().unwrap_or("full").parse()?;
+    let path = args.positional("package")?;
+    args.finish()?;
+
+    println!("{:>14} {:>14}  Feature", "Size", "Total");
+    for cost in feature_costs(&MsiPackage::open(&path)?, attribution)?
+    {
+        let title = cost.title().filter(|title| !title.is_empty()).map(|title| format!(" ({})", title)).unwrap_or_default();
+        println!("{:>14} {:>14}  {}{}{}", cost.size(), cost.total_size(), "  ".repeat(cost.depth()), cost.feature(), title);
+    }
+
+    Ok(())
+}
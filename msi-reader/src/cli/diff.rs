@@ -0,0 +1,57 @@
+use msi_reader::MsiPackage;
+use msi_reader::diff::PackageDiff;
+
+use crate::cli::{ Args, Failure, Result };
+
+pub const USAGE: &str = "diff <old> <new>               list the tables, rows, cells and streams that differ between two packages;
+                                   exits with 3 if they differ (see export diff for JSON)";
+
+#[doc = "Prints the differences between two packages, one line per table, stream, row or cell."]
+pub fn run(mut args: Args) -> Result<()>
+{
+    let old = args.positional("old package")?;
+    let new = args.positional("new package")?;
+    args.finish()?;
+
+    let diff = PackageDiff::compare(&MsiPackage::open(&old)?, &MsiPackage::open(&new)?)?;
+    for table in diff.added_tables()
+    {
+        println!("+ table {}", table);
+    }
+    for table in diff.removed_tables()
+    {
+        println!("- table {}", table);
+    }
+    for table in diff.tables()
+    {
+        for row in table.added()
+        {
+            println!("+ {}", row);
+        }
+        for row in table.removed()
+        {
+            println!("- {}", row);
+        }
+        for row in table.modified()
+        {
+            for change in row.changes()
+            {
+                println!("~ {}.{} [{}]: {} -> {}", table.table(), change.column(), row.key(), change.old_value(), change.new_value());
+            }
+        }
+    }
+    for stream in diff.added_streams()
+    {
+        println!("+ stream {}", stream);
+    }
+    for stream in diff.removed_streams()
+    {
+        println!("- stream {}", stream);
+    }
+    for stream in diff.modified_streams()
+    {
+        println!("~ stream {}", stream);
+    }
+
+    if diff.is_empty() { Ok(()) } else { Err(Failure::Status(3)) }
+}
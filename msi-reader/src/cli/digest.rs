@@ -0,0 +1,52 @@
+use msi_reader::{ Error, MsiPackage };
+use msi_reader::authenticode::{ content_digest, DigestAlgorithm };
+
+use crate::cli::{ Args, Result };
+
+pub const USAGE: &str = "digest <package> [--algorithm sha1|sha256|sha384|sha512]
+                                   print the hex digest to be signed (default: sha256)";
+
+#[doc = "Prints the Authenticode content digest of a package, which an external signing service turns into the signature."]
+pub fn run(mut args: Args) -> Result<()>
+{
+    let algorithm: DigestAlgorithm = args.option(&["--algorithm"])?.as_deref().unwrap_or("sha256").parse()?;
+    let path = args.positional("package")?;
+    args.finish()?;
+
+    println!("{}", hex_digest(&path, &algorithm)?);
+
+    Ok(())
+}
+
+// Computes the content digest of the package at the given path as lowercase hex.
+fn hex_digest(path: &str, algorithm: &DigestAlgorithm) -> Result<String>
+{
+    let digest = content_digest(&MsiPackage::open(path)?, algorithm)?
+        .ok_or_else(|| Error::NotFound(format!("support for {} digests", algorithm)))?;
+    Ok(digest.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_hex_digest()
+    {
+        // Binary is a prefix of Binary.Icon, which is hashed first
+        let path = std::env::temp_dir().join(format!("msi-reader-cli-digest-{}.msi", std::process::id()));
+        {
+            let file = std::fs::OpenOptions::new().read(true).write(true).create(true).truncate(true).open(&path).unwrap();
+            let mut package = msi::Package::create(msi::PackageType::Installer, file).unwrap();
+            package.write_stream("Binary").unwrap().write_all(b"short").unwrap();
+            package.write_stream("Binary.Icon").unwrap().write_all(b"long").unwrap();
+            package.flush().unwrap();
+        }
+
+        let digest = hex_digest(path.to_str().unwrap(), &DigestAlgorithm::Sha256);
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(digest.unwrap(), "011192294a4fb05fdc3f238be674a91a6b877187f80cb2ca319883bf8380e619");
+    }
+}
@@ -0,0 +1,33 @@
+use std::io::{ self, Write };
+
+use msi_reader::MsiPackage;
+use msi_reader::export::{ self, OutputOrder };
+
+use crate::cli::{ Args, Failure, Result };
+
+pub const USAGE: &str = "dump <package> <table> [--format table|json|csv] [--sorted]
+                                   print the rows of a table in aligned columns (default), as a JSON array of
+                                   objects or as CSV; --sorted orders them by primary key";
+
+#[doc = "Prints a table in the chosen format."]
+pub fn run(mut args: Args) -> Result<()>
+{
+    let format = args.option(&["--format"])?.unwrap_or_else(|| "table".to_string());
+    let order = if args.flag(&["--sorted"]) { OutputOrder::Sorted } else { OutputOrder::Stored };
+    let path = args.positional("package")?;
+    let name = args.positional("table")?;
+    args.finish()?;
+
+    let table = MsiPackage::open(&path)?.table(&name)?;
+    let stdout = io::stdout();
+    let mut output = stdout.lock();
+    match format.as_str()
+    {
+        "table" => output.write_all(export::table_text(&table, order).as_bytes())?,
+        "json" => output.write_all(export::table_json(&table, order).as_bytes())?,
+        "csv" => table.write_csv(order, &mut output)?,
+        other => return Err(Failure::Usage(format!("unknown format '{}'", other)))
+    }
+
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use msi_reader::edit::{ apply_edits, PackageEdit };
+use msi_reader::summary::{ PropertyValue, PID_TEMPLATE };
+
+use crate::cli::{ Args, Failure, Result };
+
+pub const USAGE: &str = "edit <package> [--set-property <name>=<value>]... [--delete-row <table>:<key>]...
+         [--template <platform;languages>] [--new-package-code]
+                                   change the package in place";
+
+#[doc = "Applies property changes, row deletions and summary information changes to a package in place; nothing is written unless all of them succeed."]
+pub fn run(mut args: Args) -> Result<()>
+{
+    let mut edits = Vec::new();
+    while let Some(assignment) = args.option(&["--set-property"])?
+    {
+        let (name, value) = assignment.split_once('=')
+            .ok_or_else(|| Failure::Usage(format!("expected <name>=<value>, got '{}'", assignment)))?;
+        edits.push(PackageEdit::SetProperty { name: name.to_string(), value: value.to_string() });
+    }
+    while let Some(row) = args.option(&["--delete-row"])?
+    {
+        let (table, key) = row.split_once(':')
+            .ok_or_else(|| Failure::Usage(format!("expected <table>:<key>, got '{}'", row)))?;
+        edits.push(PackageEdit::DeleteRow { table: table.to_string(), key: key.to_string() });
+    }
+    if let Some(template) = args.option(&["--template"])?
+    {
+        edits.push(PackageEdit::SetSummary { id: PID_TEMPLATE, value: PropertyValue::Str(template) });
+    }
+    if args.flag(&["--new-package-code"])
+    {
+        edits.push(PackageEdit::RegeneratePackageCode);
+    }
+    let path = args.positional("package")?;
+    args.finish()?;
+
+    if edits.is_empty()
+    {
+        return Err(Failure::Usage("nothing to edit".to_string()));
+    }
+
+    apply_edits(&path, &edits)?;
+    for edit in &edits
+    {
+        println!("{}", edit);
+    }
+    println!("Saved {}", path);
+
+    Ok(())
+}
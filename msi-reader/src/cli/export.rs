@@ -0,0 +1,68 @@
+use msi_reader::MsiPackage;
+use msi_reader::diff::PackageDiff;
+use msi_reader::export::{ self, OutputOrder, TextMode };
+use msi_reader::schema;
+use msi_reader::table::CellCoercion;
+
+use crate::cli::{ Args, Failure, Result };
+
+pub const USAGE: &str = "export dot-features|dot-directories <package> [-o <file>] [--sorted]
+                                   write the feature-component graph or the directory tree as Graphviz DOT
+    export mermaid-sequence <package> [--table <sequence>] [-o <file>] [--sorted]
+                                   write a sequence table (default: InstallExecuteSequence) as a Mermaid flowchart
+    export json <package> [-o <file>] [--sorted] [--escape] | export json --schema
+                                   write all tables as JSON, or print the JSON Schema of the output;
+                                   --escape writes undecodable bytes as \\xNN and lists the affected cells
+    export diff <old> <new> [-o <file>] | export diff --schema
+                                   write the differences between two packages as JSON, or print its schema;
+                                   --sorted lists rows by primary key for reproducible output;
+                                   --lenient reads cells that do not fit their column type as integers";
+
+#[doc = "Renders the package in another format and prints it or writes it to a file."]
+pub fn run(mut args: Args) -> Result<()>
+{
+    let output = args.option(&["-o", "--output"])?;
+    let table = args.option(&["--table"])?;
+    let print_schema = args.flag(&["--schema"]);
+    let order = if args.flag(&["--sorted"]) { OutputOrder::Sorted } else { OutputOrder::Stored };
+    let text = if args.flag(&["--escape"]) { TextMode::Escaped } else { TextMode::Replaced };
+    let coercion = if args.flag(&["--lenient"]) { CellCoercion::Lenient } else { CellCoercion::Strict };
+    let subcommand = args.positional("subcommand (dot-features, dot-directories, mermaid-sequence, json or diff)")?;
+
+    if print_schema
+    {
+        let schema = match subcommand.as_str()
+        {
+            "json" => schema::DATABASE,
+            "diff" => schema::DIFF,
+            other => return Err(Failure::Usage(format!("'{}' has no JSON Schema", other)))
+        };
+        args.finish()?;
+        print!("{}", schema.text());
+        return Ok(());
+    }
+
+    let path = args.positional("package")?;
+    let package = MsiPackage::open(&path)?.with_coercion(coercion);
+    let rendered = match subcommand.as_str()
+    {
+        "dot-features" => export::dot_features(&package, order)?,
+        "dot-directories" => export::dot_directories(&package, order)?,
+        "mermaid-sequence" => export::mermaid_sequence(&package, table.as_deref().unwrap_or("InstallExecuteSequence"), order)?,
+        "json" => export::json_database(&package, order, text)?,
+        "diff" => PackageDiff::compare(&package, &MsiPackage::open(args.positional("new package")?)?.with_coercion(coercion))?.to_json(),
+        other => return Err(Failure::Usage(format!("unknown export subcommand '{}'", other)))
+    };
+    args.finish()?;
+
+    match output
+    {
+        Some(output) => {
+            std::fs::write(&output, rendered)?;
+            println!("Wrote {}", output);
+        },
+        None => print!("{}", rendered)
+    }
+
+    Ok(())
+}
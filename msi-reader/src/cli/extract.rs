@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use msi_reader::MsiPackage;
+use msi_reader::cabinet::CabinetSet;
+use msi_reader::file::FileTable;
+use msi_reader::layout::DirectoryResolver;
+use msi_reader::media::MediaTable;
+
+use crate::cli::{ Args, Failure, Result };
+
+pub const USAGE: &str = "extract <package> [-o <directory>] [--source <directory>] [--list]
+                                   write the files of the cabinets to the source layout below the
+                                   directory (default: the current one), or only list the File rows;
+                                   external cabinets are read from the source directory (default: the
+                                   directory of the package)";
+
+#[doc = "Lists the files of the package with their cabinet and source path, and unless `--list` is given writes those stored in cabinets, embedded or external. Files that cannot be written are reported and make the command exit with 3."]
+pub fn run(mut args: Args) -> Result<()>
+{
+    let output = args.option(&["-o", "--output"])?.unwrap_or_else(|| ".".to_string());
+    let source = args.option(&["--source"])?;
+    let list = args.flag(&["--list"]);
+    let path = args.positional("package")?;
+    args.finish()?;
+
+    let mut package = MsiPackage::open(&path)?;
+    if let Some(source) = source
+    {
+        package = package.with_source_root(source);
+    }
+    let media = MediaTable::read(&package)?;
+    let resolver = DirectoryResolver::read(&package)?;
+    let mut files = FileTable::read(&package)?.rows().to_vec();
+    files.sort_by_key(|file| file.sequence());
+
+    let mut cabinets: HashMap<String, CabinetSet> = HashMap::new();
+    let mut failed = 0;
+    for file in &files
+    {
+        let cabinet = media.cabinet_for_sequence(file.sequence());
+        let source = resolver.file_source_path(file.file()).unwrap_or_else(|| file.file().to_string());
+        println!("{:<40} {:<16} {}", file.file(), cabinet.map(|cabinet| cabinet.to_string()).unwrap_or_default(), source);
+        if list
+        {
+            continue;
+        }
+
+        let cabinet = match cabinet
+        {
+            Some(cabinet) => cabinet,
+            None => {
+                eprintln!("error: {}: only files in cabinets can be extracted", file.file());
+                failed += 1;
+                continue;
+            }
+        };
+        let key = cabinet.to_string();
+        if !cabinets.contains_key(&key)
+        {
+            match package.read_cabinet(cabinet)
+            {
+                Ok(set) => {
+                    cabinets.insert(key.clone(), set);
+                },
+                Err(error) => {
+                    eprintln!("error: {}: {}", file.file(), error);
+                    failed += 1;
+                    continue;
+                }
+            }
+        }
+
+        let cabinet = &cabinets[&key];
+        let data = cabinet.file(file.file())
+            .ok_or_else(|| msi_reader::Error::NotFound(format!("cabinet member '{}'", file.file())))
+            .and_then(|member| cabinet.read_file(member));
+        match data
+        {
+            Ok(data) => {
+                let target = source.split('\\').fold(Path::new(&output).to_path_buf(), |target, part| target.join(part));
+                if let Some(parent) = target.parent()
+                {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(&target, data)?;
+            },
+            Err(error) => {
+                eprintln!("error: {}: {}", file.file(), error);
+                failed += 1;
+            }
+        }
+    }
+
+    if failed > 0
+    {
+        return Err(Failure::Status(3));
+    }
+    if !list
+    {
+        println!("Extracted {} files to {}", files.len(), output);
+    }
+
+    Ok(())
+}
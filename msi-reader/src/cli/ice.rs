@@ -0,0 +1,79 @@
+use msi_reader::MsiPackage;
+use msi_reader::ice::{ findings_json, sort_messages, validate, RuleFilter, Severity };
+use msi_reader::schema;
+
+use crate::cli::{ Args, Failure, Result };
+
+pub const USAGE: &str = "ice <package> [--rules <ids>] [--exclude <ids>] [--fail-on info|warning|error|never] [--format text|json] [--sorted]
+                                   run validation rules; exits with 3, 4 or 5 if info, warnings or errors
+                                   at or above the --fail-on severity (default: error) were reported;
+                                   --sorted orders messages by rule, location and message
+    ice --schema                   print the JSON Schema of the findings written with --format json";
+
+#[doc = "Runs the validation engine and prints its messages, one per line."]
+pub fn run(mut args: Args) -> Result<()>
+{
+    let rules = args.option(&["--rules"])?;
+    let exclude = args.option(&["--exclude"])?;
+    let fail_on = match args.option(&["--fail-on"])?.as_deref()
+    {
+        None => Some(Severity::Error),
+        Some("never") => None,
+        Some(severity) => Some(severity.parse()?)
+    };
+    let json = match args.option(&["--format"])?.as_deref()
+    {
+        None | Some("text") => false,
+        Some("json") => true,
+        Some(other) => return Err(Failure::Usage(format!("unknown format '{}'", other)))
+    };
+    let sorted = args.flag(&["--sorted"]);
+    if args.flag(&["--schema"])
+    {
+        args.finish()?;
+        print!("{}", schema::FINDINGS.text());
+        return Ok(());
+    }
+    let path = args.positional("package")?;
+    args.finish()?;
+
+    let mut filter = RuleFilter::default();
+    if let Some(rules) = &rules
+    {
+        filter = filter.include(rules.split(','));
+    }
+    if let Some(exclude) = &exclude
+    {
+        filter = filter.exclude(exclude.split(','));
+    }
+
+    let mut messages = validate(&MsiPackage::open(&path)?, &filter)?;
+    if sorted
+    {
+        sort_messages(&mut messages);
+    }
+    if json
+    {
+        print!("{}", findings_json(&messages));
+    }
+    else
+    {
+        for message in &messages
+        {
+            let location = message.location().map(|location| location.to_string()).unwrap_or_default();
+            println!("{:<6} {:<8} {:<40} {}", message.rule(), message.severity(), location, message.message());
+        }
+    }
+
+    let worst = messages.iter().map(|message| message.severity()).max();
+    match (worst, fail_on)
+    {
+        (Some(worst), Some(threshold)) if worst >= threshold => Err(Failure::Status(match worst
+        {
+            Severity::Info => 3,
+            Severity::Warning => 4,
+            Severity::Error => 5
+        })),
+        _ => Ok(())
+    }
+}
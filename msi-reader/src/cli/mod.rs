@@ -0,0 +1,185 @@
+use std::fmt::Display;
+use std::io;
+
+pub mod cert;
+pub mod codepage;
+pub mod cost;
+pub mod diff;
+pub mod digest;
+pub mod dump;
+pub mod edit;
+pub mod export;
+pub mod extract;
+pub mod ice;
+pub mod patch;
+pub mod props;
+pub mod report;
+pub mod sbom;
+pub mod scripts;
+pub mod split_languages;
+pub mod suite;
+pub mod summary;
+pub mod tables;
+pub mod transform;
+pub mod tree;
+pub mod unsign;
+pub mod upgrades;
+pub mod validate;
+#[cfg(all(windows, feature = "windows"))]
+pub mod verify_installed;
+
+#[doc = "Why a command did not complete."]
+#[derive(Debug)]
+pub enum Failure {
+    #[doc = "The command line is not valid; the usage text is printed after the message."]
+    Usage(String),
+    #[doc = "The command failed."]
+    Error(msi_reader::Error),
+    #[doc = "The command ran but ends with the given exit code, e.g. because it found problems."]
+    Status(i32)
+}
+
+#[doc = "A result type whose error is a command `Failure`."]
+pub type Result<T> = std::result::Result<T, Failure>;
+
+impl Failure {
+
+    #[doc = "Returns the process exit code for this failure."]
+    pub fn exit_code(&self) -> i32
+    {
+        match self
+        {
+            Failure::Usage(_) => 2,
+            Failure::Error(_) => 1,
+            Failure::Status(code) => *code
+        }
+    }
+}
+
+impl From<msi_reader::Error> for Failure {
+    fn from(error: msi_reader::Error) -> Self
+    {
+        Failure::Error(error)
+    }
+}
+
+impl From<io::Error> for Failure {
+    fn from(error: io::Error) -> Self
+    {
+        Failure::Error(msi_reader::Error::Io(error))
+    }
+}
+
+impl Display for Failure {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self
+        {
+            Failure::Usage(message) => write!(fmt, "{}", message),
+            Failure::Error(error) => write!(fmt, "{}", error),
+            Failure::Status(code) => write!(fmt, "exited with status {}", code)
+        }
+    }
+}
+
+#[doc = "The arguments of a command. Commands take the arguments they accept and `finish` rejects anything left over."]
+pub struct Args {
+    items: Vec<String>
+}
+
+impl Args {
+
+    pub fn new<I: IntoIterator<Item = String>>(items: I) -> Self
+    {
+        Args {
+            items: items.into_iter().collect()
+        }
+    }
+
+    #[doc = "Takes an option with a value, written as `--name value` or `--name=value`. Any of the given names is accepted."]
+    pub fn option(&mut self, names: &[&str]) -> Result<Option<String>>
+    {
+        for index in 0..self.items.len()
+        {
+            let item = &self.items[index];
+            if names.contains(&item.as_str())
+            {
+                if index + 1 >= self.items.len()
+                {
+                    return Err(Failure::Usage(format!("option {} requires a value", item)));
+                }
+
+                let value = self.items.remove(index + 1);
+                self.items.remove(index);
+                return Ok(Some(value));
+            }
+
+            if let Some((name, value)) = item.split_once('=')
+            {
+                if names.contains(&name)
+                {
+                    let value = value.to_string();
+                    self.items.remove(index);
+                    return Ok(Some(value));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    #[doc = "Takes a flag without a value and returns a boolean value indicating whether it was given. Any of the given names is accepted."]
+    pub fn flag(&mut self, names: &[&str]) -> bool
+    {
+        match self.items.iter().position(|item| names.contains(&item.as_str()))
+        {
+            Some(index) => {
+                self.items.remove(index);
+                true
+            },
+            None => false
+        }
+    }
+
+    #[doc = "Takes the next positional argument, described by `what` in the error if it is missing."]
+    pub fn positional(&mut self, what: &str) -> Result<String>
+    {
+        match self.items.iter().position(|item| !item.starts_with('-'))
+        {
+            Some(index) => Ok(self.items.remove(index)),
+            None => Err(Failure::Usage(format!("missing {}", what)))
+        }
+    }
+
+    #[doc = "Fails if any argument was not taken by the command."]
+    pub fn finish(self) -> Result<()>
+    {
+        match self.items.first()
+        {
+            Some(item) => Err(Failure::Usage(format!("unexpected argument '{}'", item))),
+            None => Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn test_args()
+    {
+        let mut args = Args::new(vec!["foo.msi", "-o", "out.json", "--format=cyclonedx"].into_iter().map(String::from));
+        assert_eq!(args.option(&["--format"]).unwrap().as_deref(), Some("cyclonedx"));
+        assert_eq!(args.option(&["-o", "--output"]).unwrap().as_deref(), Some("out.json"));
+        assert_eq!(args.option(&["--rules"]).unwrap(), None);
+        assert!(!args.flag(&["--schema"]));
+        assert_eq!(args.positional("package").unwrap(), "foo.msi");
+        assert!(args.positional("package").is_err());
+        assert!(args.finish().is_ok());
+
+        assert!(Args::new(vec!["--output".to_string()]).option(&["--output"]).is_err());
+        assert!(Args::new(vec!["--bogus".to_string()]).finish().is_err());
+        assert!(Args::new(vec!["--schema".to_string()]).flag(&["--schema"]));
+    }
+}
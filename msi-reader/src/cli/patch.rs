@@ -0,0 +1,88 @@
+use msi_reader::patch::{ MsiPatch, PayloadKind };
+
+use crate::cli::{ Args, Failure, Result };
+
+pub const USAGE: &str = "patch info <patch>             show target products, sequencing and contained files
+    patch apply <patch> <target> -o <output>
+                                   write a copy of the target with the patch's table changes applied";
+
+#[doc = "Runs the `info` or `apply` subcommand."]
+pub fn run(mut args: Args) -> Result<()>
+{
+    let output = args.option(&["-o", "--output"])?;
+    let subcommand = args.positional("subcommand (info or apply)")?;
+    match subcommand.as_str()
+    {
+        "info" => {
+            let path = args.positional("patch")?;
+            args.finish()?;
+            if output.is_some()
+            {
+                return Err(Failure::Usage("patch info does not write an output file".to_string()));
+            }
+
+            info(&mut MsiPatch::open(&path)?)
+        },
+        "apply" => {
+            let path = args.positional("patch")?;
+            let target = args.positional("target package")?;
+            args.finish()?;
+            let output = output.ok_or_else(|| Failure::Usage("missing -o <output>".to_string()))?;
+
+            let applied = MsiPatch::open(&path)?.apply(&target, &output)?;
+            println!("Applied transforms {}", applied.join(", "));
+            println!("Wrote {} (file payloads are not patched)", output);
+            Ok(())
+        },
+        other => Err(Failure::Usage(format!("unknown patch subcommand '{}'", other)))
+    }
+}
+
+fn info(patch: &mut MsiPatch) -> Result<()>
+{
+    let targets = patch.targets()?;
+    println!("Patch code:         {}", targets.patch_code());
+    println!("Obsoletes:          {}", list(targets.obsoleted_patches()));
+    println!("Target products:    {}", list(targets.target_products()));
+    println!("Transforms:         {}", list(targets.transforms()));
+
+    for (property, value) in patch.metadata()?
+    {
+        println!("{:<19} {}", format!("{}:", property), value);
+    }
+
+    let sequences = patch.sequences()?;
+    if !sequences.is_empty()
+    {
+        println!();
+        println!("{:<32} {:<16} {:<40} Supersedes", "Patch family", "Sequence", "Product");
+        for sequence in &sequences
+        {
+            println!("{:<32} {:<16} {:<40} {}", sequence.family(), sequence.sequence(), sequence.product_code().unwrap_or("(all)"),
+                if sequence.supersedes_earlier() { "yes" } else { "no" });
+        }
+    }
+
+    for cabinet in patch.cabinets()?
+    {
+        println!();
+        println!("Cabinet {}:", cabinet.name());
+        for file in cabinet.files()
+        {
+            let kind = match file.kind()
+            {
+                PayloadKind::Whole => "whole file",
+                PayloadKind::Delta => "delta",
+                PayloadKind::Unknown => "unknown"
+            };
+            println!("    {:<40} {:>10} {}", file.name(), file.size(), kind);
+        }
+    }
+
+    Ok(())
+}
+
+fn list(items: &[String]) -> String
+{
+    if items.is_empty() { "(none)".to_string() } else { items.join(", ") }
+}
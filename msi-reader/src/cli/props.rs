@@ -0,0 +1,31 @@
+use msi_reader::MsiPackage;
+
+use crate::cli::{ Args, Failure, Result };
+
+pub const USAGE: &str = "props <package> [<name>]       print the Property table as name=value lines, or the value of one property";
+
+#[doc = "Prints the properties of the package, or a single one; a missing property exits with 1."]
+pub fn run(mut args: Args) -> Result<()>
+{
+    let path = args.positional("package")?;
+    let name = args.positional("property").ok();
+    args.finish()?;
+
+    let properties = MsiPackage::open(&path)?.properties()?;
+    match name
+    {
+        Some(name) => match properties.get(&name)
+        {
+            Some(value) => println!("{}", value),
+            None => return Err(Failure::Error(msi_reader::Error::NotFound(format!("property '{}'", name))))
+        },
+        None => {
+            for (name, value) in properties.iter()
+            {
+                println!("{}={}", name, value);
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use std::path::Path;
+
+use msi_reader::MsiPackage;
+use msi_reader::diff::PackageDiff;
+use msi_reader::export::OutputOrder;
+use msi_reader::report::HtmlReport;
+
+use crate::cli::{ Args, Result };
+
+pub const USAGE: &str = "report <package> [-o <file>] [--compare <other>] [--sorted]
+                                   write an HTML report, optionally with the differences to another package;
+                                   --sorted orders findings for reproducible output";
+
+#[doc = "Writes the HTML report of a package, by default next to it."]
+pub fn run(mut args: Args) -> Result<()>
+{
+    let output = args.option(&["-o", "--output"])?;
+    let compare = args.option(&["--compare"])?;
+    let order = if args.flag(&["--sorted"]) { OutputOrder::Sorted } else { OutputOrder::Stored };
+    let path = args.positional("package")?;
+    args.finish()?;
+
+    let output = output.unwrap_or_else(|| Path::new(&path).with_extension("html").to_string_lossy().into_owned());
+    let package = MsiPackage::open(&path)?;
+    let mut report = HtmlReport::new(&package, file_name(&path)).with_order(order);
+    if let Some(other) = compare
+    {
+        let diff = PackageDiff::compare(&MsiPackage::open(&other)?, &package)?;
+        report = report.with_comparison(file_name(&other), diff);
+    }
+
+    std::fs::write(&output, report.render()?)?;
+    println!("Wrote {}", output);
+
+    Ok(())
+}
+
+fn file_name(path: &str) -> String
+{
+    Path::new(path).file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_else(|| path.to_string())
+}
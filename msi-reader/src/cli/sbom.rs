@@ -0,0 +1,25 @@
+use std::path::Path;
+
+use msi_reader::MsiPackage;
+use msi_reader::sbom::{ Sbom, SbomFormat };
+
+use crate::cli::{ Args, Result };
+
+pub const USAGE: &str = "sbom <package> [--format cyclonedx] [-o <file>]
+                                   write a bill of materials, by default next to the package";
+
+#[doc = "Generates the bill of materials of a package and writes it to a file."]
+pub fn run(mut args: Args) -> Result<()>
+{
+    let format: SbomFormat = args.option(&["--format"])?.as_deref().unwrap_or("cyclonedx").parse()?;
+    let output = args.option(&["-o", "--output"])?;
+    let path = args.positional("package")?;
+    args.finish()?;
+
+    let output = output.unwrap_or_else(|| Path::new(&path).with_extension("cdx.json").to_string_lossy().into_owned());
+    let sbom = Sbom::generate(&MsiPackage::open(&path)?)?;
+    std::fs::write(&output, sbom.render(format))?;
+    println!("Wrote {} ({} files) to {}", format, sbom.files().len(), output);
+
+    Ok(())
+}
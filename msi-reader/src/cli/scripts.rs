@@ -0,0 +1,80 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use msi_reader::{ Error, MsiPackage };
+use msi_reader::customaction::find_scripts;
+
+use crate::cli::{ Args, Result };
+
+pub const USAGE: &str = "scripts <package> [--out <dir>]
+                                   list script custom actions and extract the embedded ones";
+
+#[doc = "Lists the JScript and VBScript custom actions of a package and optionally writes their code to a directory, one `<action>.js` or `<action>.vbs` per script."]
+pub fn run(mut args: Args) -> Result<()>
+{
+    let out = args.option(&["--out"])?;
+    let path = args.positional("package")?;
+    args.finish()?;
+
+    let scripts = find_scripts(&MsiPackage::open(&path)?)?;
+    if let Some(out) = &out
+    {
+        std::fs::create_dir_all(out)?;
+    }
+
+    let mut written = HashSet::new();
+    for script in &scripts
+    {
+        let size = match script.content()
+        {
+            Some(content) => format!("{} bytes", content.len()),
+            None => "not embedded".to_string()
+        };
+        println!("{:<32} {:<8} {:<24} {:<16} {}", script.action(), script.language(), script.location().to_string(), script.function().unwrap_or("-"), size);
+
+        if let (Some(out), Some(content)) = (&out, script.content())
+        {
+            let name = file_name(script.action(), script.language().extension())?;
+            // file systems on Windows and macOS ignore case, so actions differing only in case would overwrite each other
+            if !written.insert(name.to_lowercase())
+            {
+                return Err(Error::InvalidData(format!("custom actions write the same script file '{}'", name)).into());
+            }
+            std::fs::write(Path::new(out).join(name), content)?;
+        }
+    }
+
+    if let Some(out) = out
+    {
+        println!("Extracted {} of {} scripts to {}", scripts.iter().filter(|script| script.content().is_some()).count(), scripts.len(), out);
+    }
+
+    Ok(())
+}
+
+// Names the file of a script after its action, which must not leave the output directory.
+fn file_name(action: &str, extension: &str) -> Result<String>
+{
+    if action.is_empty() || action == "." || action == ".." || action.contains(['\\', '/', ':'])
+    {
+        return Err(Error::InvalidData(format!("custom action name '{}' is not a valid file name", action)).into());
+    }
+
+    Ok(format!("{}.{}", action, extension))
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn test_file_name()
+    {
+        assert_eq!(file_name("RunCheck", "vbs").unwrap(), "RunCheck.vbs");
+        assert!(file_name("..", "js").is_err());
+        assert!(file_name("..\\..\\evil", "js").is_err());
+        assert!(file_name("../evil", "js").is_err());
+        assert!(file_name("C:evil", "js").is_err());
+    }
+}
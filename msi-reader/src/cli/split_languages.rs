@@ -0,0 +1,22 @@
+use msi_reader::langpack::split_languages;
+
+use crate::cli::{ Args, Failure, Result };
+
+pub const USAGE: &str = "split-languages <package> -o <dir>
+                                   write one single-language package per embedded language transform";
+
+#[doc = "Writes the base package and every embedded language transform applied to it as separate packages."]
+pub fn run(mut args: Args) -> Result<()>
+{
+    let output = args.option(&["-o", "--output"])?;
+    let path = args.positional("package")?;
+    args.finish()?;
+    let output = output.ok_or_else(|| Failure::Usage("missing -o <dir>".to_string()))?;
+
+    for pack in split_languages(&path, &output)?
+    {
+        println!("{:>5}  {:<12} {}{}", pack.language().value(), pack.language(), pack.path().display(), if pack.is_transformed() { "" } else { " (base language)" });
+    }
+
+    Ok(())
+}
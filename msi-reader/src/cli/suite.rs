@@ -0,0 +1,66 @@
+use std::path::Path;
+
+use msi_reader::MsiPackage;
+use msi_reader::suite::{ SharedItem, SuiteAnalysis };
+
+use crate::cli::{ Args, Failure, Result };
+
+pub const USAGE: &str = "suite <package> <package>...
+                                   report directories, components, files and registry values shared by the
+                                   packages of a suite; exits with 3 on component collisions or conflicting
+                                   registry values";
+
+#[doc = "Analyzes the packages of a suite and prints what they share, section by section."]
+pub fn run(mut args: Args) -> Result<()>
+{
+    let mut paths = vec![args.positional("package")?, args.positional("second package")?];
+    while let Ok(path) = args.positional("package")
+    {
+        paths.push(path);
+    }
+    args.finish()?;
+
+    let mut packages = Vec::new();
+    for path in &paths
+    {
+        let name = Path::new(path).file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_else(|| path.clone());
+        packages.push((name, MsiPackage::open(path)?));
+    }
+    let packages: Vec<(&str, &MsiPackage)> = packages.iter().map(|(name, package)| (name.as_str(), package)).collect();
+    let analysis = SuiteAnalysis::analyze(&packages)?;
+
+    print_items("Shared directories", analysis.shared_directories());
+    print_items("Shared components", analysis.shared_components());
+    print_items("Overlapping files", analysis.overlapping_files());
+
+    println!("Conflicting registry values ({})", analysis.registry_conflicts().len());
+    for conflict in analysis.registry_conflicts()
+    {
+        println!("    {}", conflict.path());
+        for (package, value) in conflict.values()
+        {
+            println!("        {}: {}", package, value);
+        }
+    }
+
+    println!("Component collisions ({})", analysis.component_collisions().len());
+    for collision in analysis.component_collisions()
+    {
+        println!("    {}", collision.guid());
+        for instance in collision.instances()
+        {
+            println!("        {}: {} in {}, key path {}", instance.package(), instance.component(), instance.directory(), instance.key_path());
+        }
+    }
+
+    if analysis.component_collisions().is_empty() && analysis.registry_conflicts().is_empty() { Ok(()) } else { Err(Failure::Status(3)) }
+}
+
+fn print_items(title: &str, items: &[SharedItem])
+{
+    println!("{} ({})", title, items.len());
+    for item in items
+    {
+        println!("    {} ({})", item.item(), item.packages().join(", "));
+    }
+}
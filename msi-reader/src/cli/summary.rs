@@ -0,0 +1,72 @@
+use std::time::SystemTime;
+
+use msi_reader::MsiPackage;
+use msi_reader::summary::*;
+
+use crate::cli::{ Args, Result };
+
+pub const USAGE: &str = "summary <package>              print the summary information properties";
+
+const NAMES: [(u32, &str); 17] = [
+    (PID_CODEPAGE, "Codepage"),
+    (PID_TITLE, "Title"),
+    (PID_SUBJECT, "Subject"),
+    (PID_AUTHOR, "Author"),
+    (PID_KEYWORDS, "Keywords"),
+    (PID_COMMENTS, "Comments"),
+    (PID_TEMPLATE, "Template"),
+    (PID_LASTAUTHOR, "Last Saved By"),
+    (PID_REVNUMBER, "Revision Number"),
+    (PID_LASTPRINTED, "Last Printed"),
+    (PID_CREATE_DTM, "Create Time"),
+    (PID_LASTSAVE_DTM, "Last Save Time"),
+    (PID_PAGECOUNT, "Page Count"),
+    (PID_WORDCOUNT, "Word Count"),
+    (PID_CHARCOUNT, "Character Count"),
+    (PID_APPNAME, "Creating Application"),
+    (PID_SECURITY, "Security")
+];
+
+#[doc = "Prints each summary information property by name, with times in UTC."]
+pub fn run(mut args: Args) -> Result<()>
+{
+    let path = args.positional("package")?;
+    args.finish()?;
+
+    let package = MsiPackage::open(&path)?;
+    let summary = package.summary();
+    for (id, value) in summary.properties()
+    {
+        let name = NAMES.iter().find(|(candidate, _)| *candidate == id).map(|(_, name)| name.to_string()).unwrap_or_else(|| format!("Property {}", id));
+        let text = match value
+        {
+            PropertyValue::FileTime(_) => summary.get_time(id).map(format_time).unwrap_or_default(),
+            // codepages above 32767, such as 65001, are stored as negative 16-bit integers
+            PropertyValue::I2(codepage) if id == PID_CODEPAGE => (*codepage as u16).to_string(),
+            _ => value.to_string()
+        };
+        println!("{:<22} {}", name, text);
+    }
+
+    Ok(())
+}
+
+// Writes the time as `yyyy-mm-dd hh:mm:ss UTC`; times before 1970 are written as the epoch.
+fn format_time(time: SystemTime) -> String
+{
+    let seconds = time.duration_since(SystemTime::UNIX_EPOCH).map(|elapsed| elapsed.as_secs()).unwrap_or_default() as i64;
+    let (days, seconds) = (seconds.div_euclid(86_400), seconds.rem_euclid(86_400));
+
+    // civil_from_days of Howard Hinnant's date algorithms
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC", year, month, day, seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
@@ -0,0 +1,22 @@
+use msi_reader::MsiPackage;
+
+use crate::cli::{ Args, Result };
+
+pub const USAGE: &str = "tables <package>               list the tables with their number of columns and rows";
+
+#[doc = "Prints every table of the package with its column and row counts."]
+pub fn run(mut args: Args) -> Result<()>
+{
+    let path = args.positional("package")?;
+    args.finish()?;
+
+    let package = MsiPackage::open(&path)?;
+    println!("{:<32} {:>8} {:>8}", "Table", "Columns", "Rows");
+    for name in package.table_names()
+    {
+        let table = package.table(name)?;
+        println!("{:<32} {:>8} {:>8}", name, table.columns().len(), table.len());
+    }
+
+    Ok(())
+}
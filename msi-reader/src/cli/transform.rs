@@ -0,0 +1,49 @@
+use msi_reader::MsiPackage;
+use msi_reader::transform::{ MsiTransform, RowOperation };
+
+use crate::cli::{ Args, Failure, Result };
+
+pub const USAGE: &str = "transform apply <base> <transform> -o <output>
+                                   write a copy of the base package with the transform applied
+    transform generate <old> <new> -o <output>
+                                   write the transform turning the old package into the new one";
+
+#[doc = "Runs the `apply` or `generate` subcommand."]
+pub fn run(mut args: Args) -> Result<()>
+{
+    let output = args.option(&["-o", "--output"])?;
+    let subcommand = args.positional("subcommand (apply or generate)")?;
+    let output = || output.clone().ok_or_else(|| Failure::Usage("missing -o <output>".to_string()));
+    match subcommand.as_str()
+    {
+        "apply" => {
+            let base = args.positional("base package")?;
+            let transform = args.positional("transform")?;
+            args.finish()?;
+
+            let output = output()?;
+            MsiTransform::open(&transform, &MsiPackage::open(&base)?)?.apply(&base, &output)?;
+            println!("Wrote {}", output);
+        },
+        "generate" => {
+            let old = args.positional("old package")?;
+            let new = args.positional("new package")?;
+            args.finish()?;
+
+            let output = output()?;
+            let transform = MsiTransform::generate(&MsiPackage::open(&old)?, &MsiPackage::open(&new)?)?;
+            for table in transform.tables()
+            {
+                let count = |operation: RowOperation| table.rows().iter().filter(|row| row.operation() == operation).count();
+                println!("{:<32} {:<10} +{} ~{} -{}", table.name(), format!("{:?}", table.operation()).to_lowercase(),
+                    count(RowOperation::Insert), count(RowOperation::Update), count(RowOperation::Delete));
+            }
+
+            transform.write(&output)?;
+            println!("Wrote {}", output);
+        },
+        other => return Err(Failure::Usage(format!("unknown transform subcommand '{}'", other)))
+    }
+
+    Ok(())
+}
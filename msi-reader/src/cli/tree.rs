@@ -0,0 +1,32 @@
+use msi_reader::MsiPackage;
+use msi_reader::layout::{ directory_tree, directory_tree_json };
+
+use crate::cli::{ Args, Result };
+
+pub const USAGE: &str = "tree <package> [--json]        show the directory hierarchy with target and source names, the number of
+                                   components in each directory and the size of its files and subdirectories";
+
+#[doc = "Prints the directory tree, one directory per line indented below its parent, or as JSON."]
+pub fn run(mut args: Args) -> Result<()>
+{
+    let json = args.flag(&["--json"]);
+    let path = args.positional("package")?;
+    args.finish()?;
+
+    let nodes = directory_tree(&MsiPackage::open(&path)?)?;
+    if json
+    {
+        print!("{}", directory_tree_json(&nodes));
+        return Ok(());
+    }
+
+    println!("{:>10} {:>14}  Directory", "Components", "Size");
+    for node in &nodes
+    {
+        let source = if node.source_name() != node.target_name() { format!(", source {}", node.source_name()) } else { String::new() };
+        println!("{:>10} {:>14}  {}{} ({}{}) {}", node.components(), node.total_size(), "  ".repeat(node.depth()), node.directory(), node.target_name(), source,
+            node.target_path().unwrap_or_default());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,23 @@
+use msi_reader::authenticode::remove_signature;
+
+use crate::cli::{ Args, Result };
+
+pub const USAGE: &str = "unsign <package>               remove the digital signature so the package can be edited";
+
+#[doc = "Strips the signature streams from a package in place."]
+pub fn run(mut args: Args) -> Result<()>
+{
+    let path = args.positional("package")?;
+    args.finish()?;
+
+    if remove_signature(&path)?
+    {
+        println!("Removed the signature of {}", path);
+    }
+    else
+    {
+        println!("{} is not signed", path);
+    }
+
+    Ok(())
+}
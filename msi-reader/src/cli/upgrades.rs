@@ -0,0 +1,59 @@
+use std::path::Path;
+
+use msi_reader::MsiPackage;
+use msi_reader::upgrade::UpgradeGraph;
+
+use crate::cli::{ Args, Result };
+
+pub const USAGE: &str = "upgrades <folder> [-o <file>]  show the upgrade lineage of the packages in a folder, optionally
+                                   writing it as a Graphviz DOT graph";
+
+#[doc = "Groups the packages of a folder by UpgradeCode and prints which versions each one removes."]
+pub fn run(mut args: Args) -> Result<()>
+{
+    let output = args.option(&["-o", "--output"])?;
+    let folder = args.positional("folder")?;
+    args.finish()?;
+
+    let mut packages = Vec::new();
+    let mut entries: Vec<_> = std::fs::read_dir(&folder)?.collect::<std::io::Result<_>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries
+    {
+        let path = entry.path();
+        if path.is_file() && path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("msi"))
+        {
+            packages.push((entry.file_name().to_string_lossy().into_owned(), MsiPackage::open(&path)?));
+        }
+    }
+    let packages: Vec<(&str, &MsiPackage)> = packages.iter().map(|(name, package)| (name.as_str(), package)).collect();
+    let graph = UpgradeGraph::build(&packages)?;
+
+    let mut upgrade_code = None;
+    for (index, node) in graph.nodes().iter().enumerate()
+    {
+        if index == 0 || node.upgrade_code() != upgrade_code
+        {
+            upgrade_code = node.upgrade_code();
+            println!("{}", upgrade_code.unwrap_or("(no UpgradeCode)"));
+        }
+
+        let matched: Vec<String> = graph.matched_by(index)
+            .map(|edge| format!("{}{}", graph.nodes()[edge.to()].name(), if edge.only_detect() { " (detect)" } else { "" }))
+            .collect();
+        print!("    {} {}", node.name(), node.version().unwrap_or("(no version)"));
+        if !matched.is_empty()
+        {
+            print!(" -> {}", matched.join(", "));
+        }
+        println!();
+    }
+
+    if let Some(output) = output
+    {
+        std::fs::write(&output, graph.to_dot())?;
+        println!("Wrote {}", Path::new(&output).display());
+    }
+
+    Ok(())
+}
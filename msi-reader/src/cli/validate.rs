@@ -0,0 +1,40 @@
+use msi_reader::MsiPackage;
+use msi_reader::validation::{ find_broken_directory_references, find_broken_formatted_references, find_dangling_foreign_keys,
+    find_invalid_filenames, find_undefined_properties };
+
+use crate::cli::{ Args, Failure, Result };
+
+pub const USAGE: &str = "validate <package>             check references between tables, formatted fields and file names;
+                                   exits with 3 if problems were found";
+
+#[doc = "Runs the reference and name checks of the validation module and prints one line per problem."]
+pub fn run(mut args: Args) -> Result<()>
+{
+    let path = args.positional("package")?;
+    args.finish()?;
+
+    let package = MsiPackage::open(&path)?;
+    let mut problems = Vec::new();
+    for undefined in find_undefined_properties(&package)?
+    {
+        problems.push((undefined.location().to_string(), format!("property '{}' is not defined", undefined.property())));
+    }
+    for dangling in find_broken_formatted_references(&package)?.into_iter()
+        .chain(find_broken_directory_references(&package)?)
+        .chain(find_dangling_foreign_keys(&package)?)
+    {
+        problems.push((dangling.location().to_string(), format!("'{}' is not a key of table {}", dangling.value(), dangling.target())));
+    }
+    for invalid in find_invalid_filenames(&package)?
+    {
+        problems.push((invalid.location().to_string(), format!("'{}' is not a valid file name: {}", invalid.name(), invalid.error())));
+    }
+
+    for (location, message) in &problems
+    {
+        println!("{:<40} {}", location, message);
+    }
+    println!("{} problems found", problems.len());
+
+    if problems.is_empty() { Ok(()) } else { Err(Failure::Status(3)) }
+}
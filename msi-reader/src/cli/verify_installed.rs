@@ -0,0 +1,25 @@
+use msi_reader::MsiPackage;
+use msi_reader::installed::verify_installed;
+
+use crate::cli::{ Args, Failure, Result };
+
+pub const USAGE: &str = "verify-installed <package>     compare the installed product with the package (Windows only);
+                                   exits with 3 if files or registry values are missing or modified";
+
+#[doc = "Checks the files and registry values of the installed product against the package."]
+pub fn run(mut args: Args) -> Result<()>
+{
+    let path = args.positional("package")?;
+    args.finish()?;
+
+    let package = MsiPackage::open(&path)?;
+    let report = verify_installed(&package)?;
+    for difference in report.differences()
+    {
+        println!("{}", difference);
+    }
+
+    println!("{} {}: checked {} files and {} registry values, {} differences",
+        report.product_code(), report.installed_version(), report.files(), report.registry_values(), report.differences().len());
+    if report.differences().is_empty() { Ok(()) } else { Err(Failure::Status(3)) }
+}
@@ -0,0 +1,211 @@
+use std::io::Write;
+use std::path::Path;
+
+use encoding::{ DecoderTrap, EncoderTrap, EncodingRef };
+
+use crate::compound;
+use crate::error::{ Error, Result };
+use crate::package::{ MsiPackage, STRING_DATA_STREAM, STRING_POOL_STREAM };
+use crate::streamname;
+use crate::stringpool;
+
+#[doc = "A string of the pool that does not survive the conversion unchanged."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct LossyString {
+    id: u32,
+    original: String,
+    converted: String
+}
+
+impl LossyString {
+
+    #[doc = "Returns the 1-based string id."]
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    #[doc = "Returns the string decoded with the source codepage; bytes that are undefined there show as U+FFFD."]
+    pub fn original(&self) -> &str {
+        &self.original
+    }
+
+    #[doc = "Returns the string as it reads after the conversion, with unrepresentable characters replaced."]
+    pub fn converted(&self) -> &str {
+        &self.converted
+    }
+}
+
+#[doc = "The string pool of a database re-encoded in another codepage, ready to be written back."]
+pub struct CodepageConversion {
+    from: u32,
+    to: u32,
+    strings: usize,
+    lossy: Vec<LossyString>,
+    pool: Vec<u8>,
+    data: Vec<u8>
+}
+
+impl CodepageConversion {
+
+    #[doc = "Re-encodes the string pool of the package from its codepage to `to`. String ids and reference counts are kept, so the tables remain valid."]
+    pub fn new(package: &MsiPackage, to: u32) -> Result<CodepageConversion>
+    {
+        let (header, entries) = stringpool::read_entries(
+            &package.read_raw_stream(&streamname::encode(STRING_POOL_STREAM, true))?,
+            &package.read_raw_stream(&streamname::encode(STRING_DATA_STREAM, true))?)?;
+        let from = stringpool::header_codepage(header);
+        let source = codepage(from)?;
+        let target = codepage(to)?;
+
+        let mut lossy = Vec::new();
+        let mut converted = Vec::with_capacity(entries.len());
+        for (index, (bytes, refcount)) in entries.into_iter().enumerate()
+        {
+            let original = source.decode(&bytes, DecoderTrap::Replace).unwrap_or_default();
+            let encoded = target.encode(&original, EncoderTrap::Replace).unwrap_or_default();
+            let decoded = target.decode(&encoded, DecoderTrap::Replace).unwrap_or_default();
+            if decoded != original || original.contains('\u{fffd}')
+            {
+                lossy.push(LossyString {
+                    id: index as u32 + 1,
+                    original,
+                    converted: decoded
+                });
+            }
+            converted.push((encoded, refcount));
+        }
+
+        let (pool, data) = stringpool::write_entries(stringpool::with_codepage(header, to), &converted);
+        Ok(CodepageConversion {
+            from,
+            to,
+            strings: converted.len(),
+            lossy,
+            pool,
+            data
+        })
+    }
+
+    #[doc = "Returns the codepage of the package's string pool."]
+    pub fn from(&self) -> u32 {
+        self.from
+    }
+
+    #[doc = "Returns the codepage the strings are converted to."]
+    pub fn to(&self) -> u32 {
+        self.to
+    }
+
+    #[doc = "Returns the number of entries in the string pool."]
+    pub fn strings(&self) -> usize {
+        self.strings
+    }
+
+    #[doc = "Returns the strings that are undecodable in the source codepage or unrepresentable in the target one."]
+    pub fn lossy(&self) -> &[LossyString] {
+        &self.lossy
+    }
+
+    #[doc = "Returns a boolean value indicating whether every string converts without loss."]
+    pub fn is_lossless(&self) -> bool {
+        self.lossy.is_empty()
+    }
+
+    #[doc = "Replaces the string pool of the package at `path` with the converted one. Fails for a lossy conversion unless `allow_lossy` is set."]
+    pub fn write<P: AsRef<Path>>(&self, path: P, allow_lossy: bool) -> Result<()>
+    {
+        if !allow_lossy && !self.is_lossless()
+        {
+            return Err(Error::invalid(format!("{} strings cannot be converted from codepage {} to {} without loss", self.lossy.len(), self.from, self.to)));
+        }
+
+        let mut compound = compound::open_rw(path)?;
+        compound.create_stream(streamname::encode(STRING_POOL_STREAM, true))?.write_all(&self.pool)?;
+        compound.create_stream(streamname::encode(STRING_DATA_STREAM, true))?.write_all(&self.data)?;
+        compound.flush()?;
+        Ok(())
+    }
+}
+
+#[doc = "Decodes the bytes of a string stored in the given codepage; bytes that are undefined there become U+FFFD. Returns `None` for an unsupported codepage."]
+pub fn decode(codepage: u32, bytes: &[u8]) -> Option<String> {
+    encoding(codepage).map(|encoding| encoding.decode(bytes, DecoderTrap::Replace).unwrap_or_default())
+}
+
+#[doc = "Encodes a string in the given codepage, replacing characters it cannot represent with `?`. Returns `None` for an unsupported codepage."]
+pub fn encode(codepage: u32, text: &str) -> Option<Vec<u8>> {
+    encoding(codepage).map(|encoding| encoding.encode(text, EncoderTrap::Replace).unwrap_or_default())
+}
+
+#[doc = "Returns the name of the character set of a codepage, e.g. `windows-1252` or `windows-31j`, or `None` for an unsupported codepage."]
+pub fn encoding_name(codepage: u32) -> Option<&'static str> {
+    encoding(codepage).map(|encoding| encoding.name())
+}
+
+// The Windows ANSI and OEM codepages, the double-byte ones of East Asian databases (932, 936, 949, 950)
+// and UTF-8. Codepage 0 marks a language-neutral database, read as UTF-8 like the rest of the crate does.
+pub(crate) fn encoding(codepage: u32) -> Option<EncodingRef>
+{
+    if codepage == 0
+    {
+        return Some(encoding::all::UTF_8);
+    }
+
+    encoding::label::encoding_from_windows_code_page(codepage as usize)
+}
+
+fn codepage(id: u32) -> Result<EncodingRef>
+{
+    encoding(id).ok_or_else(|| Error::invalid(format!("codepage {} is not supported", id)))
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::testutil::TestPackage;
+
+    #[test]
+    fn test_codepage_conversion()
+    {
+        let package = TestPackage::new("codepage", |builder| {
+            builder.table("Property", vec![
+                msi::Column::build("Property").primary_key().id_string(72),
+                msi::Column::build("Value").localizable().text_string(0)
+            ], vec![
+                vec![msi::Value::from("ProductName"), msi::Value::from("Société Générale")],
+                vec![msi::Value::from("Greeting"), msi::Value::from("Привет")]
+            ]);
+        });
+        let path = package.path().with_extension("1252.msi");
+
+        let conversion = CodepageConversion::new(&MsiPackage::open(package.path()).unwrap(), 1252).unwrap();
+        assert_eq!((conversion.from(), conversion.to()), (65001, 1252));
+        let lossy: Vec<(&str, &str)> = conversion.lossy().iter().map(|string| (string.original(), string.converted())).collect();
+        assert_eq!(lossy, [("Привет", "??????")]);
+
+        std::fs::copy(package.path(), &path).unwrap();
+        assert!(conversion.write(&path, false).is_err());
+        conversion.write(&path, true).unwrap();
+        let converted = MsiPackage::open(&path).unwrap();
+        let reverted = CodepageConversion::new(&converted, 65001).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(reverted.from(), 1252);
+        assert!(reverted.is_lossless());
+        assert!(CodepageConversion::new(&converted, 932).is_ok());
+        assert!(CodepageConversion::new(&converted, 42).is_err());
+    }
+
+    #[test]
+    fn test_decode()
+    {
+        assert_eq!(decode(1252, b"Soci\xE9t\xE9").as_deref(), Some("Société"));
+        assert_eq!(decode(1251, b"\xCF\xF0\xE8\xE2\xE5\xF2").as_deref(), Some("Привет"));
+        assert_eq!(decode(932, b"\x93\xFA\x96\x7B").as_deref(), Some("日本"));
+        assert_eq!(decode(0, "Société".as_bytes()).as_deref(), Some("Société"));
+        assert_eq!(decode(42, b"A"), None);
+        assert_eq!(encode(1252, "Société Привет").unwrap(), b"Soci\xE9t\xE9 ??????");
+        assert_eq!((encoding_name(1252), encoding_name(932)), (Some("windows-1252"), Some("windows-31j")));
+    }
+}
@@ -0,0 +1,289 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use crate::error::Result;
+use crate::guid::MsiGuid;
+use crate::layout::DirectoryResolver;
+use crate::package::MsiPackage;
+use crate::table::RowOrigin;
+
+const ATTRIBUTE_RUN_MASK: i32 = 0x0003;
+const ATTRIBUTE_REGISTRY_KEY_PATH: i32 = 0x0004;
+const ATTRIBUTE_SHARED_DLL_REF_COUNT: i32 = 0x0008;
+const ATTRIBUTE_PERMANENT: i32 = 0x0010;
+const ATTRIBUTE_ODBC_DATA_SOURCE: i32 = 0x0020;
+const ATTRIBUTE_TRANSITIVE: i32 = 0x0040;
+const ATTRIBUTE_NEVER_OVERWRITE: i32 = 0x0080;
+const ATTRIBUTE_64BIT: i32 = 0x0100;
+const ATTRIBUTE_DISABLE_REGISTRY_REFLECTION: i32 = 0x0200;
+const ATTRIBUTE_UNINSTALL_ON_SUPERSEDENCE: i32 = 0x0400;
+const ATTRIBUTE_SHARED: i32 = 0x0800;
+
+#[doc = "Where the resources of a component run from."]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RunLocation {
+    #[doc = "Installed on the local computer only."]
+    Local,
+    #[doc = "Run from the source media only."]
+    Source,
+    #[doc = "Either, as chosen for the features installing the component."]
+    Optional
+}
+
+impl Display for RunLocation {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self
+        {
+            RunLocation::Local => write!(fmt, "local"),
+            RunLocation::Source => write!(fmt, "source"),
+            RunLocation::Optional => write!(fmt, "optional")
+        }
+    }
+}
+
+#[doc = "The Attributes bit field of a Component row."]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ComponentAttributes(i32);
+
+impl ComponentAttributes {
+
+    #[doc = "Returns the raw bits."]
+    pub fn bits(&self) -> i32 {
+        self.0
+    }
+
+    #[doc = "Returns where the component runs from."]
+    pub fn run_location(&self) -> RunLocation {
+        match self.0 & ATTRIBUTE_RUN_MASK
+        {
+            0 => RunLocation::Local,
+            1 => RunLocation::Source,
+            _ => RunLocation::Optional
+        }
+    }
+
+    #[doc = "Returns a boolean value indicating whether the KeyPath column refers to a Registry row rather than a File row."]
+    pub fn is_registry_key_path(&self) -> bool {
+        self.0 & ATTRIBUTE_REGISTRY_KEY_PATH != 0
+    }
+
+    #[doc = "Returns a boolean value indicating whether the key file's SharedDLL reference count is incremented."]
+    pub fn is_shared_dll_ref_count(&self) -> bool {
+        self.0 & ATTRIBUTE_SHARED_DLL_REF_COUNT != 0
+    }
+
+    #[doc = "Returns a boolean value indicating whether the component is never removed on uninstall."]
+    pub fn is_permanent(&self) -> bool {
+        self.0 & ATTRIBUTE_PERMANENT != 0
+    }
+
+    #[doc = "Returns a boolean value indicating whether the KeyPath column refers to an ODBCDataSource row."]
+    pub fn is_odbc_data_source_key_path(&self) -> bool {
+        self.0 & ATTRIBUTE_ODBC_DATA_SOURCE != 0
+    }
+
+    #[doc = "Returns a boolean value indicating whether the condition of the component is re-evaluated on reinstall."]
+    pub fn is_transitive(&self) -> bool {
+        self.0 & ATTRIBUTE_TRANSITIVE != 0
+    }
+
+    #[doc = "Returns a boolean value indicating whether an existing key path resource prevents the component from being installed."]
+    pub fn is_never_overwrite(&self) -> bool {
+        self.0 & ATTRIBUTE_NEVER_OVERWRITE != 0
+    }
+
+    #[doc = "Returns a boolean value indicating whether the component is 64-bit."]
+    pub fn is_64bit(&self) -> bool {
+        self.0 & ATTRIBUTE_64BIT != 0
+    }
+
+    #[doc = "Returns a boolean value indicating whether registry reflection is disabled for the component's keys."]
+    pub fn is_registry_reflection_disabled(&self) -> bool {
+        self.0 & ATTRIBUTE_DISABLE_REGISTRY_REFLECTION != 0
+    }
+
+    #[doc = "Returns a boolean value indicating whether the component is removed when a patch superseding it is installed."]
+    pub fn is_uninstall_on_supersedence(&self) -> bool {
+        self.0 & ATTRIBUTE_UNINSTALL_ON_SUPERSEDENCE != 0
+    }
+
+    #[doc = "Returns a boolean value indicating whether the component is shared by several products and its files are versioned across them."]
+    pub fn is_shared(&self) -> bool {
+        self.0 & ATTRIBUTE_SHARED != 0
+    }
+}
+
+impl From<i32> for ComponentAttributes {
+    fn from(bits: i32) -> Self {
+        ComponentAttributes(bits)
+    }
+}
+
+#[doc = "A row of the Component table."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ComponentRow {
+    component: String,
+    component_id: Option<String>,
+    directory: String,
+    directory_path: Option<String>,
+    attributes: ComponentAttributes,
+    condition: Option<String>,
+    key_path: Option<String>,
+    origin: RowOrigin
+}
+
+impl ComponentRow {
+
+    #[doc = "Returns the primary key of the component."]
+    pub fn component(&self) -> &str {
+        &self.component
+    }
+
+    #[doc = "Returns the ComponentId column as stored; `None` for components that are not registered."]
+    pub fn component_id(&self) -> Option<&str> {
+        self.component_id.as_deref()
+    }
+
+    #[doc = "Returns the component code, or `None` if the ComponentId column is empty or not a valid GUID."]
+    pub fn guid(&self) -> Option<MsiGuid> {
+        self.component_id.as_deref().and_then(|id| MsiGuid::parse(id).ok())
+    }
+
+    #[doc = "Returns the key of the component's directory."]
+    pub fn directory(&self) -> &str {
+        &self.directory
+    }
+
+    #[doc = "Returns the full target path of the component's directory, e.g. `C:\\Program Files (x86)\\Contoso\\`, or `None` if it does not resolve, see `DirectoryResolver::target_path`."]
+    pub fn directory_path(&self) -> Option<&str> {
+        self.directory_path.as_deref()
+    }
+
+    #[doc = "Returns the attributes of the component."]
+    pub fn attributes(&self) -> ComponentAttributes {
+        self.attributes
+    }
+
+    #[doc = "Returns the condition under which the component is installed, if any."]
+    pub fn condition(&self) -> Option<&str> {
+        self.condition.as_deref()
+    }
+
+    #[doc = "Returns the key of the File, Registry or ODBCDataSource row that is the key path, as selected by the attributes; `None` if the directory is the key path."]
+    pub fn key_path(&self) -> Option<&str> {
+        self.key_path.as_deref()
+    }
+
+    #[doc = "Returns the Component row the component was read from."]
+    pub fn origin(&self) -> &RowOrigin {
+        &self.origin
+    }
+}
+
+#[doc = "The typed rows of the Component table, in storage order, with their directories resolved."]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ComponentTable {
+    rows: Vec<ComponentRow>,
+    index: HashMap<String, usize>
+}
+
+impl ComponentTable {
+
+    #[doc = "Reads the Component table of the package; a package without one yields an empty table."]
+    pub fn read(package: &MsiPackage) -> Result<ComponentTable>
+    {
+        let table = match package.optional_table("Component")?
+        {
+            Some(table) => table,
+            None => return Ok(ComponentTable::default())
+        };
+
+        let directories = DirectoryResolver::read(package)?;
+        let rows: Vec<ComponentRow> = table.rows()
+            .map(|row| {
+                let directory = row.str("Directory_").unwrap_or_default();
+                ComponentRow {
+                    component: row.str("Component").unwrap_or_default().to_string(),
+                    component_id: row.str("ComponentId").filter(|id| !id.is_empty()).map(|id| id.to_string()),
+                    directory: directory.to_string(),
+                    directory_path: directories.target_path(directory),
+                    attributes: ComponentAttributes(row.int("Attributes").unwrap_or(0)),
+                    condition: row.str("Condition").map(|condition| condition.trim().to_string()).filter(|condition| !condition.is_empty()),
+                    key_path: row.str("KeyPath").filter(|key| !key.is_empty()).map(|key| key.to_string()),
+                    origin: row.origin()
+                }
+            })
+            .collect();
+        let index = rows.iter().enumerate().map(|(index, row)| (row.component.clone(), index)).collect();
+
+        Ok(ComponentTable {
+            rows,
+            index
+        })
+    }
+
+    #[doc = "Returns all components in storage order."]
+    pub fn rows(&self) -> &[ComponentRow] {
+        &self.rows
+    }
+
+    #[doc = "Returns the component with the given key."]
+    pub fn get(&self, component: &str) -> Option<&ComponentRow> {
+        self.index.get(component).map(|index| &self.rows[*index])
+    }
+
+    pub(crate) fn position(&self, component: &str) -> Option<usize> {
+        self.index.get(component).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::testutil::TestPackage;
+
+    #[test]
+    fn test_read_components()
+    {
+        let package = TestPackage::new("component-table", |builder| {
+            builder.table("Component", vec![
+                msi::Column::build("Component").primary_key().id_string(72),
+                msi::Column::build("ComponentId").nullable().string(38),
+                msi::Column::build("Directory_").id_string(72),
+                msi::Column::build("Attributes").int16(),
+                msi::Column::build("Condition").nullable().string(255),
+                msi::Column::build("KeyPath").nullable().id_string(72)
+            ], vec![
+                vec![msi::Value::from("Main"), msi::Value::from("{8B3A5A8E-1F5C-4E4D-9C1B-2F0F3B7A6D10}"), msi::Value::from("INSTALLDIR"),
+                    msi::Value::Int(0x0114), msi::Value::from("VersionNT64"), msi::Value::from("Version")],
+                vec![msi::Value::from("Unregistered"), msi::Value::Null, msi::Value::from("Missing"), msi::Value::Int(2), msi::Value::Null, msi::Value::Null]
+            ]);
+            builder.table("Directory", vec![
+                msi::Column::build("Directory").primary_key().id_string(72),
+                msi::Column::build("Directory_Parent").nullable().id_string(72),
+                msi::Column::build("DefaultDir").category(msi::Category::DefaultDir).string(255)
+            ], vec![
+                vec![msi::Value::from("TARGETDIR"), msi::Value::Null, msi::Value::from("SourceDir")],
+                vec![msi::Value::from("ProgramFilesFolder"), msi::Value::from("TARGETDIR"), msi::Value::from(".")],
+                vec![msi::Value::from("INSTALLDIR"), msi::Value::from("ProgramFilesFolder"), msi::Value::from("Contoso")]
+            ]);
+        });
+
+        let components = ComponentTable::read(&MsiPackage::open(package.path()).unwrap()).unwrap();
+        let main = components.get("Main").unwrap();
+        assert_eq!(main.guid().unwrap().to_string(), "{8B3A5A8E-1F5C-4E4D-9C1B-2F0F3B7A6D10}");
+        assert_eq!(main.directory_path(), Some("C:\\Program Files (x86)\\Contoso\\"));
+        assert_eq!((main.condition(), main.key_path()), (Some("VersionNT64"), Some("Version")));
+        let attributes = main.attributes();
+        assert_eq!(attributes.run_location(), RunLocation::Local);
+        assert!(attributes.is_registry_key_path() && attributes.is_permanent() && attributes.is_64bit());
+        assert!(!attributes.is_shared());
+
+        let unregistered = components.get("Unregistered").unwrap();
+        assert_eq!((unregistered.component_id(), unregistered.guid()), (None, None));
+        assert_eq!(unregistered.directory_path(), None);
+        assert_eq!(unregistered.attributes().run_location(), RunLocation::Optional);
+        assert_eq!(components.rows().len(), 2);
+    }
+}
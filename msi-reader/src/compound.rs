@@ -0,0 +1,440 @@
+// Checks the sector chains of a compound file before the cfb crate opens it. cfb validates the FAT itself,
+// but follows the chains of the directory, the MiniFAT, the mini stream and every stream without checking
+// that they stay within the FAT and end where the data says, and panics when they do not.
+use std::fs::{ File, OpenOptions };
+use std::io::{ Read, Seek, SeekFrom };
+use std::ops::Range;
+use std::panic::{ self, AssertUnwindSafe };
+use std::path::Path;
+
+use crate::bytes::ByteReader;
+use crate::error::{ Error, Result };
+
+const HEADER_LEN: usize = 512;
+const HEADER_DIFAT_ENTRIES: usize = 109;
+const DIR_ENTRY_LEN: usize = 128;
+const MINI_SECTOR_LEN: u64 = 64;
+const MINI_STREAM_CUTOFF: u64 = 4096;
+const MAX_REGULAR_SECTOR: u32 = 0xffff_fffa;
+const END_OF_CHAIN: u32 = 0xffff_fffe;
+const FREE_SECTOR: u32 = 0xffff_ffff;
+const NO_STREAM: u32 = 0xffff_ffff;
+const OBJ_TYPE_STREAM: u8 = 2;
+const OBJ_TYPE_ROOT: u8 = 5;
+
+#[doc = "Opens a compound file for reading once its chains have been checked. Every file the crate opens goes through here or `open_rw`, never through `cfb::open` directly."]
+pub(crate) fn open<P: AsRef<Path>>(path: P) -> Result<cfb::CompoundFile<File>>
+{
+    let mut file = File::open(path)?;
+    check_chains(&mut file)?;
+    guarded(|| Ok(cfb::CompoundFile::open(file)?))
+}
+
+#[doc = "Opens a compound file for reading and writing once its chains have been checked."]
+pub(crate) fn open_rw<P: AsRef<Path>>(path: P) -> Result<cfb::CompoundFile<File>>
+{
+    let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+    check_chains(&mut file)?;
+    guarded(|| Ok(cfb::CompoundFile::open(file)?))
+}
+
+#[doc = "Runs an operation of the cfb crate and reports a panic in it as a corrupt compound file. This is a last-resort backstop and guarantees nothing: `check_chains` is what rejects corrupt files, a panic is not caught when the binary is built with `panic = \"abort\"`, and state touched by the operation may be left inconsistent."]
+pub(crate) fn guarded<T, F: FnOnce() -> Result<T>>(operation: F) -> Result<T>
+{
+    panic::catch_unwind(AssertUnwindSafe(operation))
+        .unwrap_or_else(|_| Err(Error::invalid("the compound file structure is corrupt")))
+}
+
+#[doc = "Fails if a chain of the compound file leaves its allocation table, loops or is shorter than the data it holds. Structures the chains do not depend on are left to cfb to validate."]
+pub(crate) fn check_chains<R: Read + Seek + ?Sized>(source: &mut R) -> Result<()> {
+    Layout::read(source)?.check()
+}
+
+#[doc = "The allocation tables and the directory of a compound file, which locate the sectors of its streams."]
+pub(crate) struct Layout {
+    sector_len: usize,
+    fat: Vec<u32>,
+    minifat: Vec<u32>,
+    directory: Vec<u8>
+}
+
+impl Layout {
+
+    #[doc = "Reads the allocation tables and the directory, failing if their own chains are corrupt."]
+    pub(crate) fn read<R: Read + Seek + ?Sized>(source: &mut R) -> Result<Layout>
+    {
+        let length = source.seek(SeekFrom::End(0))?;
+        let mut header = [0u8; HEADER_LEN];
+        source.seek(SeekFrom::Start(0))?;
+        source.read_exact(&mut header)?;
+
+        let mut reader = ByteReader::new(&header);
+        reader.seek(30)?;
+        let sector_len = match reader.read_u16()?
+        {
+            9 => 512,
+            12 => 4096,
+            shift => return Err(Error::invalid(format!("the compound file has an invalid sector shift of {}", shift)))
+        };
+        if length < sector_len as u64
+        {
+            return Err(Error::invalid("the compound file is shorter than its header"));
+        }
+        // the header takes the place of the first sector
+        let sector_count = (length - 1) / sector_len as u64;
+
+        reader.seek(48)?;
+        let first_directory = reader.read_u32()?;
+        reader.seek(60)?;
+        let first_minifat = reader.read_u32()?;
+        reader.skip(4)?;
+        let mut next_difat = reader.read_u32()?;
+        reader.skip(4)?;
+
+        let mut fat_sectors = Vec::new();
+        for _ in 0..HEADER_DIFAT_ENTRIES
+        {
+            match reader.read_u32()?
+            {
+                FREE_SECTOR => break,
+                sector => fat_sectors.push(sector)
+            }
+        }
+        let mut difat_sectors = 0;
+        while next_difat <= MAX_REGULAR_SECTOR
+        {
+            difat_sectors += 1;
+            if difat_sectors > sector_count
+            {
+                return Err(Error::invalid("the DIFAT chain of the compound file loops"));
+            }
+
+            let sector = read_sector(source, sector_len, sector_count, next_difat)?;
+            let mut entries = ByteReader::new(&sector);
+            for _ in 0..sector_len / 4 - 1
+            {
+                fat_sectors.push(entries.read_u32()?);
+            }
+            next_difat = entries.read_u32()?;
+        }
+        while fat_sectors.last() == Some(&FREE_SECTOR)
+        {
+            fat_sectors.pop();
+        }
+
+        let mut fat = Vec::new();
+        for sector in fat_sectors
+        {
+            fat.extend(read_entries(&read_sector(source, sector_len, sector_count, sector)?)?);
+        }
+        while fat.last() == Some(&FREE_SECTOR)
+        {
+            fat.pop();
+        }
+
+        let mut directory = Vec::new();
+        for sector in chain(&fat, first_directory, None, "directory")?
+        {
+            directory.extend(read_sector(source, sector_len, sector_count, sector)?);
+        }
+        let mut minifat = Vec::new();
+        for sector in chain(&fat, first_minifat, None, "MiniFAT")?
+        {
+            minifat.extend(read_entries(&read_sector(source, sector_len, sector_count, sector)?)?);
+        }
+        while minifat.last() == Some(&FREE_SECTOR)
+        {
+            minifat.pop();
+        }
+
+        Ok(Layout {
+            sector_len,
+            fat,
+            minifat,
+            directory
+        })
+    }
+
+    // Fails if the chain of a stream or of the mini stream is corrupt.
+    fn check(&self) -> Result<()>
+    {
+        for index in 0..self.directory.len() / DIR_ENTRY_LEN
+        {
+            let entry = self.entry(index)?;
+            if entry.object_type == OBJ_TYPE_ROOT && index == 0 && !self.minifat.is_empty()
+            {
+                // the mini stream must hold every mini sector of the MiniFAT
+                let sectors = chain(&self.fat, entry.start, None, "mini stream")?.len() as u64;
+                if sectors * (self.sector_len as u64) < self.minifat.len() as u64 * MINI_SECTOR_LEN
+                {
+                    return Err(Error::invalid("the mini stream of the compound file is shorter than its MiniFAT"));
+                }
+            }
+            else if entry.object_type == OBJ_TYPE_STREAM && entry.size > 0
+            {
+                if entry.size < MINI_STREAM_CUTOFF
+                {
+                    chain(&self.minifat, entry.start, Some(entry.size.div_ceil(MINI_SECTOR_LEN) as usize), "stream")?;
+                }
+                else
+                {
+                    chain(&self.fat, entry.start, None, "stream")?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    #[doc = "Returns where the stream with the given raw name in the root storage is stored in the file, if its sectors follow each other there. Returns `None` for fragmented streams and for streams that do not exist."]
+    pub(crate) fn contiguous_stream(&self, raw_name: &str) -> Result<Option<Range<usize>>>
+    {
+        let entry = match self.root_stream(raw_name)?
+        {
+            Some(entry) => entry,
+            None => return Ok(None)
+        };
+        let size = entry.size as usize;
+        if size == 0
+        {
+            return Ok(Some(0..0));
+        }
+
+        if entry.size >= MINI_STREAM_CUTOFF
+        {
+            let sectors = chain(&self.fat, entry.start, None, "stream")?;
+            if sectors.len() * self.sector_len < size || !is_contiguous(&sectors)
+            {
+                return Ok(None);
+            }
+
+            let start = (sectors[0] as usize + 1) * self.sector_len;
+            return Ok(Some(start..start + size));
+        }
+
+        // a small stream is contiguous if its mini sectors are, and so are the sectors of the mini stream holding them
+        let mini_sectors = chain(&self.minifat, entry.start, Some(size.div_ceil(MINI_SECTOR_LEN as usize)), "stream")?;
+        if !is_contiguous(&mini_sectors)
+        {
+            return Ok(None);
+        }
+        let offset = mini_sectors[0] as usize * MINI_SECTOR_LEN as usize;
+        let mini_stream = chain(&self.fat, self.entry(0)?.start, None, "mini stream")?;
+        let sectors = match mini_stream.get(offset / self.sector_len..=(offset + size - 1) / self.sector_len)
+        {
+            Some(sectors) if is_contiguous(sectors) => sectors,
+            _ => return Ok(None)
+        };
+
+        let start = (sectors[0] as usize + 1) * self.sector_len + offset % self.sector_len;
+        Ok(Some(start..start + size))
+    }
+
+    // Finds a stream of the root storage by walking the tree of its children.
+    fn root_stream(&self, raw_name: &str) -> Result<Option<DirEntry>>
+    {
+        let name: Vec<u16> = raw_name.encode_utf16().collect();
+        let count = self.directory.len() / DIR_ENTRY_LEN;
+        let mut pending = vec![self.entry(0)?.child];
+        let mut visited = 0;
+        while let Some(index) = pending.pop()
+        {
+            if index == NO_STREAM || index as usize >= count
+            {
+                continue;
+            }
+            visited += 1;
+            if visited > count
+            {
+                return Err(Error::invalid("the directory of the compound file loops"));
+            }
+
+            let entry = self.entry(index as usize)?;
+            if entry.object_type == OBJ_TYPE_STREAM && entry.name == name
+            {
+                return Ok(Some(entry));
+            }
+            pending.push(entry.left);
+            pending.push(entry.right);
+        }
+
+        Ok(None)
+    }
+
+    fn entry(&self, index: usize) -> Result<DirEntry>
+    {
+        let mut reader = ByteReader::new(&self.directory[index * DIR_ENTRY_LEN..(index + 1) * DIR_ENTRY_LEN]);
+        let mut name = Vec::new();
+        for _ in 0..32
+        {
+            name.push(reader.read_u16()?);
+        }
+        // the length in bytes counts the terminating zero
+        let name_len = (reader.read_u16()? as usize / 2).saturating_sub(1).min(32);
+        name.truncate(name_len);
+
+        let object_type = reader.read_u8()?;
+        reader.skip(1)?;
+        let left = reader.read_u32()?;
+        let right = reader.read_u32()?;
+        let child = reader.read_u32()?;
+        reader.seek(116)?;
+        let start = reader.read_u32()?;
+        let size = match self.sector_len
+        {
+            512 => reader.read_u32()? as u64,
+            _ => reader.read_u64()?
+        };
+
+        Ok(DirEntry {
+            name,
+            object_type,
+            left,
+            right,
+            child,
+            start,
+            size
+        })
+    }
+}
+
+// The fields of a directory entry the layout needs.
+struct DirEntry {
+    name: Vec<u16>,
+    object_type: u8,
+    left: u32,
+    right: u32,
+    child: u32,
+    start: u32,
+    size: u64
+}
+
+// Returns a boolean value indicating whether every sector follows the one before.
+fn is_contiguous(sectors: &[u32]) -> bool {
+    sectors.windows(2).all(|pair| pair[1] == pair[0].wrapping_add(1))
+}
+
+// Follows a chain through an allocation table, up to its end or only as far as the given number of sectors.
+fn chain(table: &[u32], start: u32, count: Option<usize>, what: &str) -> Result<Vec<u32>>
+{
+    let mut sectors = Vec::new();
+    let mut current = start;
+    while count.map_or(current != END_OF_CHAIN, |count| sectors.len() < count)
+    {
+        if current as usize >= table.len()
+        {
+            return Err(Error::invalid(format!("a {} chain of the compound file refers to sector {}, beyond its {} allocated sectors", what, current, table.len())));
+        }
+        if sectors.len() == table.len()
+        {
+            return Err(Error::invalid(format!("a {} chain of the compound file loops", what)));
+        }
+
+        sectors.push(current);
+        current = table[current as usize];
+    }
+
+    Ok(sectors)
+}
+
+fn read_sector<R: Read + Seek + ?Sized>(source: &mut R, sector_len: usize, sector_count: u64, sector: u32) -> Result<Vec<u8>>
+{
+    if sector as u64 >= sector_count
+    {
+        return Err(Error::invalid(format!("the compound file refers to sector {}, but has only {} sectors", sector, sector_count)));
+    }
+
+    let mut data = vec![0; sector_len];
+    source.seek(SeekFrom::Start((sector as u64 + 1) * sector_len as u64))?;
+    source.read_exact(&mut data)?;
+    Ok(data)
+}
+
+fn read_entries(sector: &[u8]) -> Result<Vec<u32>>
+{
+    let mut reader = ByteReader::new(sector);
+    (0..sector.len() / 4).map(|_| reader.read_u32()).collect()
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::testutil::TestPackage;
+    use crate::MsiPackage;
+
+    // Returns the offset of a sector in a compound file.
+    fn sector_offset(data: &[u8], sector: u32) -> usize
+    {
+        (sector as usize + 1) << u16::from_le_bytes([data[30], data[31]])
+    }
+
+    // Returns a value of the header.
+    fn header_u32(data: &[u8], offset: usize) -> u32
+    {
+        u32::from_le_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]])
+    }
+
+    #[test]
+    fn test_check_chains()
+    {
+        let package = TestPackage::new("chains", |builder| {
+            builder.stream("Binary.small", b"small");
+            builder.stream("Binary.large", &[7; 5000]);
+        });
+        let data = std::fs::read(package.path()).unwrap();
+        assert!(check_chains(&mut std::io::Cursor::new(&data)).is_ok());
+
+        // every stream, in the MiniFAT or the FAT, pointed beyond its table or at a free sector
+        // the directory of the test package fits into its first sector
+        let directory = sector_offset(&data, header_u32(&data, 48));
+        let streams: Vec<usize> = (directory..sector_offset(&data, header_u32(&data, 48) + 1)).step_by(DIR_ENTRY_LEN)
+            .filter(|entry| data[entry + 66] == OBJ_TYPE_STREAM)
+            .collect();
+        assert!(streams.len() >= 2);
+        for entry in streams
+        {
+            for start in [0x00ff_ffff, FREE_SECTOR]
+            {
+                let mut corrupt = data.clone();
+                corrupt[entry + 116..entry + 120].copy_from_slice(&start.to_le_bytes());
+                let error = check_chains(&mut std::io::Cursor::new(&corrupt)).err().unwrap();
+                assert!(error.to_string().contains("chain of the compound file refers to sector"), "{}", error);
+                assert!(MsiPackage::parse_untrusted(corrupt).is_err());
+            }
+        }
+
+        // a directory chain that loops back to itself
+        let mut corrupt = data.clone();
+        let first = header_u32(&data, 48);
+        let entry = sector_offset(&data, header_u32(&data, 76)) + first as usize * 4;
+        corrupt[entry..entry + 4].copy_from_slice(&first.to_le_bytes());
+        assert!(check_chains(&mut std::io::Cursor::new(&corrupt)).is_err());
+        assert!(MsiPackage::parse_untrusted(corrupt).is_err());
+
+        assert!(check_chains(&mut std::io::Cursor::new(&data[..300])).is_err());
+        assert!(check_chains(&mut std::io::Cursor::new(&data[..directory])).is_err());
+    }
+
+    #[test]
+    fn test_open_checks_chains()
+    {
+        let package = TestPackage::new("open-chains", |_| {});
+        assert!(open(package.path()).is_ok());
+        assert!(open_rw(package.path()).is_ok());
+
+        // a directory chain that loops back to itself, written back to the file
+        let mut data = std::fs::read(package.path()).unwrap();
+        let first = header_u32(&data, 48);
+        let entry = sector_offset(&data, header_u32(&data, 76)) + first as usize * 4;
+        data[entry..entry + 4].copy_from_slice(&first.to_le_bytes());
+        std::fs::write(package.path(), &data).unwrap();
+
+        assert!(open(package.path()).is_err());
+        assert!(open_rw(package.path()).is_err());
+        assert!(crate::patch::MsiPatch::open(package.path()).is_err());
+        assert!(crate::authenticode::remove_signature(package.path()).is_err());
+        assert!(crate::summary::SummaryInfo::default().write(package.path()).is_err());
+        assert_eq!(std::fs::read(package.path()).unwrap(), data);
+    }
+}
@@ -0,0 +1,761 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use crate::error::{ Error, Result };
+use crate::package::MsiPackage;
+use crate::property::Properties;
+use crate::sequence::{ Sequence, SequenceTable };
+use crate::table::RowOrigin;
+
+#[doc = "A value in a conditional expression."]
+#[derive(Clone, Debug, PartialEq)]
+pub enum Term {
+    #[doc = "The value of a property, e.g. `VersionNT`."]
+    Property(String),
+    #[doc = "The value of an environment variable, e.g. `%PROCESSOR_ARCHITECTURE`."]
+    Environment(String),
+    #[doc = "The action state of a component, e.g. `$Main`."]
+    ComponentAction(String),
+    #[doc = "The installed state of a component, e.g. `?Main`."]
+    ComponentInstalled(String),
+    #[doc = "The action state of a feature, e.g. `&Complete`."]
+    FeatureAction(String),
+    #[doc = "The installed state of a feature, e.g. `!Complete`."]
+    FeatureInstalled(String),
+    #[doc = "A string literal in double quotes."]
+    String(String),
+    #[doc = "An integer literal."]
+    Integer(i32)
+}
+
+impl Display for Term {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self
+        {
+            Term::Property(name) => write!(fmt, "{}", name),
+            Term::Environment(name) => write!(fmt, "%{}", name),
+            Term::ComponentAction(name) => write!(fmt, "${}", name),
+            Term::ComponentInstalled(name) => write!(fmt, "?{}", name),
+            Term::FeatureAction(name) => write!(fmt, "&{}", name),
+            Term::FeatureInstalled(name) => write!(fmt, "!{}", name),
+            Term::String(value) => write!(fmt, "\"{}\"", value),
+            Term::Integer(value) => write!(fmt, "{}", value)
+        }
+    }
+}
+
+#[doc = "A comparison operator. Each can be prefixed with `~` to compare strings case-insensitively."]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+    #[doc = "`><`: the integers have a bit in common, or the left string contains the right one."]
+    Contains,
+    #[doc = "`<<`: the left string starts with the right one; for integers, the high 16 bits of the left equal the right."]
+    StartsWith,
+    #[doc = "`>>`: the left string ends with the right one; for integers, the low 16 bits of the left equal the right."]
+    EndsWith
+}
+
+impl Comparison {
+
+    #[doc = "Returns the operator as written in a condition, without `~`."]
+    pub fn symbol(&self) -> &'static str {
+        match self
+        {
+            Comparison::Equal => "=",
+            Comparison::NotEqual => "<>",
+            Comparison::Greater => ">",
+            Comparison::GreaterOrEqual => ">=",
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Contains => "><",
+            Comparison::StartsWith => "<<",
+            Comparison::EndsWith => ">>"
+        }
+    }
+}
+
+// Longest operators first so that `>=` is not read as `>` followed by `=`.
+const COMPARISONS: [Comparison; 9] = [
+    Comparison::GreaterOrEqual, Comparison::LessOrEqual, Comparison::NotEqual, Comparison::Contains,
+    Comparison::StartsWith, Comparison::EndsWith, Comparison::Equal, Comparison::Greater, Comparison::Less
+];
+
+#[doc = "The abstract syntax tree of a conditional expression. Logical operators bind in the order NOT, AND, OR, XOR, EQV, IMP, and all binary ones associate to the left."]
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expression {
+    #[doc = "A single value, which holds if it is a non-empty string or a non-zero integer."]
+    Value(Term),
+    Compare {
+        left: Term,
+        operator: Comparison,
+        ignore_case: bool,
+        right: Term
+    },
+    Not(Box<Expression>),
+    And(Box<Expression>, Box<Expression>),
+    Or(Box<Expression>, Box<Expression>),
+    Xor(Box<Expression>, Box<Expression>),
+    Eqv(Box<Expression>, Box<Expression>),
+    Imp(Box<Expression>, Box<Expression>)
+}
+
+impl Expression {
+
+    #[doc = "Parses a conditional expression. Returns `None` for an empty condition, which always holds."]
+    pub fn parse(text: &str) -> Result<Option<Expression>>
+    {
+        let tokens = tokenize(text)?;
+        if tokens.is_empty()
+        {
+            return Ok(None);
+        }
+
+        let mut parser = Parser {
+            text,
+            tokens,
+            position: 0
+        };
+        let expression = parser.implication()?;
+        match parser.tokens.get(parser.position)
+        {
+            Some((_, offset)) => Err(parser.error(*offset, "unexpected token")),
+            None => Ok(Some(expression))
+        }
+    }
+
+    #[doc = "Returns every property, environment variable, component and feature the expression refers to, in order of appearance."]
+    pub fn references(&self) -> Vec<&Term>
+    {
+        let mut references = Vec::new();
+        self.collect_references(&mut references);
+        references
+    }
+
+    #[doc = "Evaluates the expression against the context, the way the installer would."]
+    pub fn evaluate(&self, context: &ConditionContext) -> bool
+    {
+        match self
+        {
+            Expression::Value(term) => match context.resolve(term)
+            {
+                Value::String(value) => !value.is_empty(),
+                Value::Integer(value) => value != 0
+            },
+            Expression::Compare { left, operator, ignore_case, right } => compare(context.resolve(left), *operator, *ignore_case, context.resolve(right)),
+            Expression::Not(inner) => !inner.evaluate(context),
+            Expression::And(left, right) => left.evaluate(context) && right.evaluate(context),
+            Expression::Or(left, right) => left.evaluate(context) || right.evaluate(context),
+            Expression::Xor(left, right) => left.evaluate(context) != right.evaluate(context),
+            Expression::Eqv(left, right) => left.evaluate(context) == right.evaluate(context),
+            Expression::Imp(left, right) => !left.evaluate(context) || right.evaluate(context)
+        }
+    }
+
+    fn collect_references<'a>(&'a self, references: &mut Vec<&'a Term>)
+    {
+        let mut add = |term: &'a Term| {
+            if !matches!(term, Term::String(_) | Term::Integer(_))
+            {
+                references.push(term);
+            }
+        };
+
+        match self
+        {
+            Expression::Value(term) => add(term),
+            Expression::Compare { left, right, .. } => {
+                add(left);
+                add(right);
+            },
+            Expression::Not(inner) => inner.collect_references(references),
+            Expression::And(left, right) | Expression::Or(left, right) | Expression::Xor(left, right)
+                | Expression::Eqv(left, right) | Expression::Imp(left, right) => {
+                left.collect_references(references);
+                right.collect_references(references);
+            }
+        }
+    }
+}
+
+impl Display for Expression {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self
+        {
+            Expression::Value(term) => write!(fmt, "{}", term),
+            Expression::Compare { left, operator, ignore_case, right } => write!(fmt, "{} {}{} {}", left, if *ignore_case { "~" } else { "" }, operator.symbol(), right),
+            Expression::Not(inner) => write!(fmt, "NOT {}", inner),
+            Expression::And(left, right) => write!(fmt, "({} AND {})", left, right),
+            Expression::Or(left, right) => write!(fmt, "({} OR {})", left, right),
+            Expression::Xor(left, right) => write!(fmt, "({} XOR {})", left, right),
+            Expression::Eqv(left, right) => write!(fmt, "({} EQV {})", left, right),
+            Expression::Imp(left, right) => write!(fmt, "({} IMP {})", left, right)
+        }
+    }
+}
+
+#[doc = "The installed or requested state of a feature or component."]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InstallState {
+    Unknown,
+    Advertised,
+    Absent,
+    Local,
+    Source
+}
+
+impl InstallState {
+
+    #[doc = "Returns the INSTALLSTATE value the state compares as, e.g. 3 for `Local`."]
+    pub fn value(&self) -> i32 {
+        match self
+        {
+            InstallState::Unknown => -1,
+            InstallState::Advertised => 1,
+            InstallState::Absent => 2,
+            InstallState::Local => 3,
+            InstallState::Source => 4
+        }
+    }
+}
+
+#[doc = "The properties, environment variables and feature and component states a condition is evaluated against. Anything not set reads as an empty value."]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ConditionContext {
+    properties: HashMap<String, String>,
+    environment: HashMap<String, String>,
+    components: HashMap<String, (InstallState, InstallState)>,
+    features: HashMap<String, (InstallState, InstallState)>
+}
+
+impl ConditionContext {
+
+    #[doc = "Creates an empty context."]
+    pub fn new() -> ConditionContext {
+        ConditionContext::default()
+    }
+
+    #[doc = "Creates a context holding the properties of a package, before any is set on the command line or by an action."]
+    pub fn from_properties(properties: &Properties) -> ConditionContext
+    {
+        let mut context = ConditionContext::new();
+        for (name, value) in properties.iter()
+        {
+            context.set_property(name, value);
+        }
+
+        context
+    }
+
+    #[doc = "Returns the value of a property."]
+    pub fn property(&self, name: &str) -> Option<&str> {
+        self.properties.get(name).map(String::as_str)
+    }
+
+    #[doc = "Sets a property; setting it to an empty string removes it, as the installer does."]
+    pub fn set_property(&mut self, name: &str, value: &str)
+    {
+        if value.is_empty()
+        {
+            self.properties.remove(name);
+        }
+        else
+        {
+            self.properties.insert(name.to_string(), value.to_string());
+        }
+    }
+
+    #[doc = "Sets an environment variable. Names are case-insensitive."]
+    pub fn set_environment(&mut self, name: &str, value: &str) {
+        self.environment.insert(name.to_ascii_uppercase(), value.to_string());
+    }
+
+    #[doc = "Sets the installed state (`?`) and action state (`$`) of a component."]
+    pub fn set_component_state(&mut self, component: &str, installed: InstallState, action: InstallState) {
+        self.components.insert(component.to_string(), (installed, action));
+    }
+
+    #[doc = "Sets the installed state (`!`) and action state (`&`) of a feature."]
+    pub fn set_feature_state(&mut self, feature: &str, installed: InstallState, action: InstallState) {
+        self.features.insert(feature.to_string(), (installed, action));
+    }
+
+    fn resolve<'a>(&'a self, term: &'a Term) -> Value<'a>
+    {
+        let state = |states: &HashMap<String, (InstallState, InstallState)>, name: &str, action: bool| match states.get(name)
+        {
+            Some((installed, requested)) => Value::Integer(if action { requested.value() } else { installed.value() }),
+            None => Value::String("")
+        };
+
+        match term
+        {
+            Term::Property(name) => Value::String(self.property(name).unwrap_or_default()),
+            Term::Environment(name) => Value::String(self.environment.get(&name.to_ascii_uppercase()).map(String::as_str).unwrap_or_default()),
+            Term::ComponentInstalled(name) => state(&self.components, name, false),
+            Term::ComponentAction(name) => state(&self.components, name, true),
+            Term::FeatureInstalled(name) => state(&self.features, name, false),
+            Term::FeatureAction(name) => state(&self.features, name, true),
+            Term::String(value) => Value::String(value),
+            Term::Integer(value) => Value::Integer(*value)
+        }
+    }
+}
+
+#[doc = "Parses and evaluates a condition against the context; an empty condition holds."]
+pub fn evaluate(condition: &str, context: &ConditionContext) -> Result<bool> {
+    Ok(Expression::parse(condition)?.is_none_or(|expression| expression.evaluate(context)))
+}
+
+#[doc = "The result of evaluating one condition of a package."]
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConditionOutcome {
+    True,
+    False,
+    #[doc = "The condition could not be parsed, which the installer treats as an error."]
+    Invalid(String)
+}
+
+#[doc = "A condition of a package together with its outcome."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConditionCheck {
+    subject: String,
+    condition: String,
+    outcome: ConditionOutcome,
+    origin: RowOrigin
+}
+
+impl ConditionCheck {
+
+    fn new(subject: String, condition: &str, context: &ConditionContext, origin: RowOrigin) -> ConditionCheck
+    {
+        let outcome = match evaluate(condition, context)
+        {
+            Ok(true) => ConditionOutcome::True,
+            Ok(false) => ConditionOutcome::False,
+            Err(error) => ConditionOutcome::Invalid(error.to_string())
+        };
+
+        ConditionCheck {
+            subject,
+            condition: condition.to_string(),
+            outcome,
+            origin
+        }
+    }
+
+    #[doc = "Returns what the condition guards: the message of a launch condition, `Dialog.Control Action` for a control condition, or the action of a sequence."]
+    pub fn subject(&self) -> &str {
+        &self.subject
+    }
+
+    #[doc = "Returns the condition as written; empty if the row has none."]
+    pub fn condition(&self) -> &str {
+        &self.condition
+    }
+
+    #[doc = "Returns the result of the evaluation."]
+    pub fn outcome(&self) -> &ConditionOutcome {
+        &self.outcome
+    }
+
+    #[doc = "Returns a boolean value indicating whether the condition holds."]
+    pub fn holds(&self) -> bool {
+        self.outcome == ConditionOutcome::True
+    }
+
+    #[doc = "Returns the row the condition was read from."]
+    pub fn origin(&self) -> &RowOrigin {
+        &self.origin
+    }
+}
+
+#[doc = "Evaluates the LaunchCondition table. The installation stops with the message of the first condition that does not hold."]
+pub fn check_launch_conditions(package: &MsiPackage, context: &ConditionContext) -> Result<Vec<ConditionCheck>>
+{
+    let table = match package.optional_table("LaunchCondition")?
+    {
+        Some(table) => table,
+        None => return Ok(Vec::new())
+    };
+
+    Ok(table.rows()
+        .map(|row| ConditionCheck::new(row.str("Description").unwrap_or_default().to_string(), row.str("Condition").unwrap_or_default(), context, row.origin()))
+        .collect())
+}
+
+#[doc = "Evaluates the ControlCondition table; each action that holds is applied to its control when the dialog is shown."]
+pub fn check_control_conditions(package: &MsiPackage, context: &ConditionContext) -> Result<Vec<ConditionCheck>>
+{
+    let table = match package.optional_table("ControlCondition")?
+    {
+        Some(table) => table,
+        None => return Ok(Vec::new())
+    };
+
+    Ok(table.rows()
+        .map(|row| {
+            let subject = format!("{}.{} {}", row.str("Dialog_").unwrap_or_default(), row.str("Control_").unwrap_or_default(), row.str("Action").unwrap_or_default());
+            ConditionCheck::new(subject, row.str("Condition").unwrap_or_default(), context, row.origin())
+        })
+        .collect())
+}
+
+#[doc = "Evaluates the conditions of the scheduled actions of a sequence, in run order; the actions whose condition holds are the ones that would run."]
+pub fn check_sequence(package: &MsiPackage, sequence: Sequence, context: &ConditionContext) -> Result<Vec<ConditionCheck>>
+{
+    Ok(SequenceTable::read(package, sequence)?
+        .actions()
+        .iter()
+        .map(|action| ConditionCheck::new(action.action().to_string(), action.condition().unwrap_or_default(), context, action.origin().clone()))
+        .collect())
+}
+
+enum Value<'a> {
+    String(&'a str),
+    Integer(i32)
+}
+
+// Strings compare as integers when both hold one; an integer and a string that does not only differ.
+fn compare(left: Value, operator: Comparison, ignore_case: bool, right: Value) -> bool
+{
+    let as_integer = |value: &Value| match value
+    {
+        Value::String(text) => text.parse::<i32>().ok(),
+        Value::Integer(number) => Some(*number)
+    };
+
+    if let (Some(left), Some(right)) = (as_integer(&left), as_integer(&right))
+    {
+        return match operator
+        {
+            Comparison::Equal => left == right,
+            Comparison::NotEqual => left != right,
+            Comparison::Greater => left > right,
+            Comparison::GreaterOrEqual => left >= right,
+            Comparison::Less => left < right,
+            Comparison::LessOrEqual => left <= right,
+            Comparison::Contains => left & right != 0,
+            Comparison::StartsWith => ((left as u32) >> 16) as i32 == right,
+            Comparison::EndsWith => left & 0xFFFF == right
+        };
+    }
+
+    let (left, right) = match (left, right)
+    {
+        (Value::String(left), Value::String(right)) => (left, right),
+        _ => return operator == Comparison::NotEqual
+    };
+
+    let (left, right) = if ignore_case { (left.to_lowercase(), right.to_lowercase()) } else { (left.to_string(), right.to_string()) };
+    match operator
+    {
+        Comparison::Equal => left == right,
+        Comparison::NotEqual => left != right,
+        Comparison::Greater => left > right,
+        Comparison::GreaterOrEqual => left >= right,
+        Comparison::Less => left < right,
+        Comparison::LessOrEqual => left <= right,
+        Comparison::Contains => left.contains(&right),
+        Comparison::StartsWith => left.starts_with(&right),
+        Comparison::EndsWith => left.ends_with(&right)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Term(Term),
+    Compare(Comparison, bool),
+    Open,
+    Close,
+    Not,
+    And,
+    Or,
+    Xor,
+    Eqv,
+    Imp
+}
+
+fn tokenize(text: &str) -> Result<Vec<(Token, usize)>>
+{
+    let bytes = text.as_bytes();
+    let identifier_end = |start: usize| start + bytes[start..].iter().take_while(|c| c.is_ascii_alphanumeric() || **c == b'_' || **c == b'.').count();
+    let error = |offset: usize, message: &str| Error::invalid(format!("condition '{}': {} at offset {}", text, message, offset));
+
+    let mut tokens = Vec::new();
+    let mut index = 0;
+    while index < bytes.len()
+    {
+        let start = index;
+        let c = bytes[index];
+        let token = if c.is_ascii_whitespace()
+        {
+            index += 1;
+            continue;
+        }
+        else if c == b'('
+        {
+            index += 1;
+            Token::Open
+        }
+        else if c == b')'
+        {
+            index += 1;
+            Token::Close
+        }
+        else if c == b'"'
+        {
+            let end = bytes[index + 1..].iter().position(|c| *c == b'"').ok_or_else(|| error(start, "unterminated string"))?;
+            index += end + 2;
+            Token::Term(Term::String(text[start + 1..start + 1 + end].to_string()))
+        }
+        else if c.is_ascii_digit() || (c == b'-' && bytes.get(index + 1).is_some_and(u8::is_ascii_digit))
+        {
+            index += 1 + bytes[index + 1..].iter().take_while(|c| c.is_ascii_digit()).count();
+            Token::Term(Term::Integer(text[start..index].parse().map_err(|_| error(start, "integer out of range"))?))
+        }
+        else if matches!(c, b'%' | b'$' | b'?' | b'&' | b'!')
+        {
+            index = identifier_end(index + 1);
+            let name = text[start + 1..index].to_string();
+            if name.is_empty()
+            {
+                return Err(error(start, "expected a name"));
+            }
+
+            Token::Term(match c
+            {
+                b'%' => Term::Environment(name),
+                b'$' => Term::ComponentAction(name),
+                b'?' => Term::ComponentInstalled(name),
+                b'&' => Term::FeatureAction(name),
+                _ => Term::FeatureInstalled(name)
+            })
+        }
+        else if c.is_ascii_alphabetic() || c == b'_'
+        {
+            index = identifier_end(index);
+            let word = &text[start..index];
+            match word.to_ascii_uppercase().as_str()
+            {
+                "NOT" => Token::Not,
+                "AND" => Token::And,
+                "OR" => Token::Or,
+                "XOR" => Token::Xor,
+                "EQV" => Token::Eqv,
+                "IMP" => Token::Imp,
+                _ => Token::Term(Term::Property(word.to_string()))
+            }
+        }
+        else
+        {
+            let ignore_case = c == b'~';
+            let operators = &text[if ignore_case { index + 1 } else { index }..];
+            let operator = COMPARISONS.iter().find(|operator| operators.starts_with(operator.symbol())).ok_or_else(|| error(start, "unexpected character"))?;
+            index += operator.symbol().len() + if ignore_case { 1 } else { 0 };
+            Token::Compare(*operator, ignore_case)
+        };
+
+        tokens.push((token, start));
+    }
+
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    tokens: Vec<(Token, usize)>,
+    position: usize
+}
+
+impl Parser<'_> {
+
+    fn error(&self, offset: usize, message: &str) -> Error {
+        Error::invalid(format!("condition '{}': {} at offset {}", self.text, message, offset))
+    }
+
+    fn accept(&mut self, token: &Token) -> bool
+    {
+        if self.tokens.get(self.position).is_some_and(|(candidate, _)| candidate == token)
+        {
+            self.position += 1;
+            true
+        }
+        else
+        {
+            false
+        }
+    }
+
+    // Parses a left-associative chain of `operator`, with operands parsed by `operand`.
+    fn chain(&mut self, operator: Token, operand: fn(&mut Self) -> Result<Expression>, combine: fn(Box<Expression>, Box<Expression>) -> Expression) -> Result<Expression>
+    {
+        let mut expression = operand(self)?;
+        while self.accept(&operator)
+        {
+            expression = combine(Box::new(expression), Box::new(operand(self)?));
+        }
+
+        Ok(expression)
+    }
+
+    fn implication(&mut self) -> Result<Expression> {
+        self.chain(Token::Imp, Parser::equivalence, Expression::Imp)
+    }
+
+    fn equivalence(&mut self) -> Result<Expression> {
+        self.chain(Token::Eqv, Parser::exclusive, Expression::Eqv)
+    }
+
+    fn exclusive(&mut self) -> Result<Expression> {
+        self.chain(Token::Xor, Parser::disjunction, Expression::Xor)
+    }
+
+    fn disjunction(&mut self) -> Result<Expression> {
+        self.chain(Token::Or, Parser::conjunction, Expression::Or)
+    }
+
+    fn conjunction(&mut self) -> Result<Expression> {
+        self.chain(Token::And, Parser::negation, Expression::And)
+    }
+
+    fn negation(&mut self) -> Result<Expression>
+    {
+        if self.accept(&Token::Not)
+        {
+            return Ok(Expression::Not(Box::new(self.negation()?)));
+        }
+
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expression>
+    {
+        let end = self.text.len();
+        let (token, offset) = self.tokens.get(self.position).cloned().unwrap_or((Token::Close, end));
+        self.position += 1;
+        let left = match token
+        {
+            Token::Open => {
+                let inner = self.implication()?;
+                if !self.accept(&Token::Close)
+                {
+                    let offset = self.tokens.get(self.position).map(|(_, offset)| *offset).unwrap_or(end);
+                    return Err(self.error(offset, "expected ')'"));
+                }
+                return Ok(inner);
+            },
+            Token::Term(term) => term,
+            _ => return Err(self.error(offset, "expected a value"))
+        };
+
+        match self.tokens.get(self.position).cloned()
+        {
+            Some((Token::Compare(operator, ignore_case), _)) => {
+                self.position += 1;
+                match self.tokens.get(self.position).cloned()
+                {
+                    Some((Token::Term(right), _)) => {
+                        self.position += 1;
+                        Ok(Expression::Compare { left, operator, ignore_case, right })
+                    },
+                    Some((_, offset)) => Err(self.error(offset, "expected a value")),
+                    None => Err(self.error(end, "expected a value"))
+                }
+            },
+            _ => Ok(Expression::Value(left))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::testutil::TestPackage;
+
+    #[test]
+    fn test_parse_condition()
+    {
+        let parse = |text: &str| Expression::parse(text).unwrap().unwrap().to_string();
+        assert_eq!(parse("NOT Installed AND VersionNT >= 601 OR REMOVE~=\"all\""), "((NOT Installed AND VersionNT >= 601) OR REMOVE ~= \"all\")");
+        assert_eq!(parse("a imp b eqv c xor d or e"), "(a IMP (b EQV (c XOR (d OR e))))");
+        assert_eq!(parse("(A OR B) AND NOT (C)"), "((A OR B) AND NOT C)");
+        assert_eq!(parse("$Main=3 AND ?Main<>-1 AND &Complete>2 AND !Complete<<1"), "((($Main = 3 AND ?Main <> -1) AND &Complete > 2) AND !Complete << 1)");
+        assert_eq!(parse("%PROCESSOR_ARCHITECTURE~><\"64\" AND MsiNTProductType>>1"), "(%PROCESSOR_ARCHITECTURE ~>< \"64\" AND MsiNTProductType >> 1)");
+
+        let expression = Expression::parse("Installed OR (SOURCE=\"x\" AND %TEMP AND $Comp)").unwrap().unwrap();
+        let references: Vec<String> = expression.references().iter().map(|term| term.to_string()).collect();
+        assert_eq!(references, ["Installed", "SOURCE", "%TEMP", "$Comp"]);
+
+        assert_eq!(Expression::parse("  ").unwrap(), None);
+        for invalid in ["A AND", "(A", "A = ", "\"open", "A B", "A # 1", "$ = 1", "99999999999"]
+        {
+            assert!(Expression::parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_evaluate_condition()
+    {
+        let mut context = ConditionContext::new();
+        context.set_property("VersionNT", "601");
+        context.set_property("ProductName", "Contoso App");
+        context.set_property("Zero", "0");
+        context.set_environment("Processor_Architecture", "AMD64");
+        context.set_component_state("Main", InstallState::Absent, InstallState::Local);
+        context.set_feature_state("Complete", InstallState::Local, InstallState::Absent);
+
+        let holds = |condition: &str| evaluate(condition, &context).unwrap();
+        assert!(holds(""));
+        assert!(holds("VersionNT >= 600 AND VersionNT < \"700\" AND NOT Installed"));
+        assert!(holds("Zero") && !holds("Missing") && !holds("0") && holds("-1"));
+        assert!(holds("ProductName ~<< \"contoso\" AND ProductName >> \"App\" AND NOT ProductName << \"contoso\""));
+        assert!(holds("%PROCESSOR_ARCHITECTURE ~= \"amd64\" AND %processor_architecture >< \"64\""));
+        assert!(holds("$Main = 3 AND ?Main = 2 AND &Complete = 2 AND !Complete = 3 AND $Other = \"\""));
+        assert!(holds("ProductName <> 1") && !holds("ProductName = 1") && !holds("ProductName > 1"));
+        assert!(holds("196609 << 3 AND 196609 >> 1 AND 6 >< 2"));
+        assert!(holds("Missing IMP Anything") && holds("Zero XOR Missing") && holds("Missing EQV Other"));
+        assert!(evaluate("VersionNT >=", &context).is_err());
+
+        let package = TestPackage::new("condition-checks", |builder| {
+            builder.table("LaunchCondition", vec![
+                msi::Column::build("Condition").primary_key().category(msi::Category::Condition).string(255),
+                msi::Column::build("Description").localizable().formatted_string(255)
+            ], vec![
+                vec![msi::Value::from("VersionNT >= 601"), msi::Value::from("Requires Windows 7.")],
+                vec![msi::Value::from("Installed OR NOT OLDER_FOUND"), msi::Value::from("A newer version is installed.")],
+                vec![msi::Value::from("VersionNT >"), msi::Value::from("Broken.")]
+            ]);
+            builder.table("InstallExecuteSequence", vec![
+                msi::Column::build("Action").primary_key().id_string(72),
+                msi::Column::build("Condition").nullable().category(msi::Category::Condition).string(255),
+                msi::Column::build("Sequence").nullable().int16()
+            ], vec![
+                vec![msi::Value::from("CostInitialize"), msi::Value::Null, msi::Value::Int(800)],
+                vec![msi::Value::from("RemoveFiles"), msi::Value::from("REMOVE~=\"ALL\""), msi::Value::Int(3500)]
+            ]);
+        });
+
+        let package = MsiPackage::open(package.path()).unwrap();
+        let mut context = ConditionContext::new();
+        context.set_property("VersionNT", "601");
+        context.set_property("OLDER_FOUND", "{00000000-0000-0000-0000-000000000001}");
+        let mut launch = check_launch_conditions(&package, &context).unwrap();
+        launch.sort_by(|left, right| left.subject().cmp(right.subject()));
+        assert_eq!(launch[0].subject(), "A newer version is installed.");
+        assert!(!launch[0].holds());
+        assert!(matches!(launch[1].outcome(), ConditionOutcome::Invalid(_)));
+        assert!(launch[2].holds());
+
+        context.set_property("REMOVE", "all");
+        let checks = check_sequence(&package, Sequence::InstallExecute, &context).unwrap();
+        let sequence: Vec<(&str, bool)> = checks.iter().map(|check| (check.subject(), check.holds())).collect();
+        assert_eq!(sequence, [("CostInitialize", true), ("RemoveFiles", true)]);
+    }
+}
@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::ptr;
+
+use crate::error::Result;
+use crate::package::MsiPackage;
+use crate::table::{ ColumnType, Table, Value };
+use crate::win32::{ check, read_string, wide, Handle, ERROR_NO_MORE_ITEMS };
+
+const MSI_NULL_INTEGER: i32 = i32::MIN;
+
+#[allow(non_snake_case)]
+#[link(name = "msi")]
+extern "system" {
+    fn MsiOpenDatabaseW(szDatabasePath: *const u16, szPersist: *const u16, phDatabase: *mut u32) -> u32;
+    fn MsiDatabaseOpenViewW(hDatabase: u32, szQuery: *const u16, phView: *mut u32) -> u32;
+    fn MsiViewExecute(hView: u32, hRecord: u32) -> u32;
+    fn MsiViewFetch(hView: u32, phRecord: *mut u32) -> u32;
+    fn MsiRecordIsNull(hRecord: u32, iField: u32) -> i32;
+    fn MsiRecordGetInteger(hRecord: u32, iField: u32) -> i32;
+    fn MsiRecordGetStringW(hRecord: u32, iField: u32, szValueBuf: *mut u16, pcchValueBuf: *mut u32) -> u32;
+}
+
+#[doc = "A difference between this crate's decoding of a table and the one returned by msi.dll."]
+#[derive(Clone, Debug, PartialEq)]
+pub enum Mismatch {
+    #[doc = "The tables have a different number of rows (ours, msi.dll)."]
+    RowCount(usize, usize),
+    #[doc = "A row with the given key is only returned by one side; the flag tells whether it is ours."]
+    MissingRow(String, bool),
+    #[doc = "A cell differs: row key, column, our value, the value of msi.dll."]
+    Value(String, String, Value, Value)
+}
+
+#[doc = "The result of comparing one table."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct TableComparison {
+    table: String,
+    rows: usize,
+    mismatches: Vec<Mismatch>
+}
+
+impl TableComparison {
+
+    #[doc = "Returns the name of the table."]
+    pub fn table(&self) -> &str {
+        &self.table
+    }
+
+    #[doc = "Returns the number of rows this crate decoded."]
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    #[doc = "Returns the differences found, empty if both sides agree."]
+    pub fn mismatches(&self) -> &[Mismatch] {
+        &self.mismatches
+    }
+}
+
+#[doc = "Opens the package with both this crate and the Win32 MSI API and compares every table, row by row and cell by cell. Binary cells are skipped, as msi.dll exposes them as streams."]
+pub fn cross_check<P: AsRef<Path>>(path: P) -> Result<Vec<TableComparison>>
+{
+    let package = MsiPackage::open(path.as_ref())?;
+
+    let mut database = Handle(0);
+    let path = wide(path.as_ref());
+    check(unsafe { MsiOpenDatabaseW(path.as_ptr(), ptr::null(), &mut database.0) })?;
+
+    let mut comparisons = Vec::new();
+    for name in package.table_names()
+    {
+        let ours = package.table(name)?;
+        let theirs = query_table(&database, &ours)?;
+        comparisons.push(compare(&ours, theirs));
+    }
+
+    Ok(comparisons)
+}
+
+fn query_table(database: &Handle, table: &Table) -> Result<Vec<Vec<Value>>>
+{
+    let query = wide(&format!("SELECT * FROM `{}`", table.name()));
+    let mut view = Handle(0);
+    check(unsafe { MsiDatabaseOpenViewW(database.0, query.as_ptr(), &mut view.0) })?;
+    check(unsafe { MsiViewExecute(view.0, 0) })?;
+
+    let mut rows = Vec::new();
+    loop
+    {
+        let mut record = Handle(0);
+        match unsafe { MsiViewFetch(view.0, &mut record.0) }
+        {
+            ERROR_NO_MORE_ITEMS => break,
+            status => check(status)?
+        }
+
+        let mut row = Vec::with_capacity(table.columns().len());
+        for (index, column) in table.columns().iter().enumerate()
+        {
+            let field = index as u32 + 1;
+            let value = if unsafe { MsiRecordIsNull(record.0, field) } != 0
+            {
+                Value::Null
+            }
+            else
+            {
+                match column.column_type()
+                {
+                    ColumnType::Int16 | ColumnType::Int32 => match unsafe { MsiRecordGetInteger(record.0, field) }
+                    {
+                        MSI_NULL_INTEGER => Value::Null,
+                        value => Value::Int(value)
+                    },
+                    ColumnType::Str(_) => Value::from(record_string(&record, field)?),
+                    ColumnType::Binary => Value::Null
+                }
+            };
+            row.push(value);
+        }
+
+        rows.push(row);
+    }
+
+    Ok(rows)
+}
+
+fn record_string(record: &Handle, field: u32) -> Result<String>
+{
+    read_string(|buffer, length| unsafe { MsiRecordGetStringW(record.0, field, buffer, length) })
+}
+
+fn compare(ours: &Table, theirs: Vec<Vec<Value>>) -> TableComparison
+{
+    let mut mismatches = Vec::new();
+    if ours.len() != theirs.len()
+    {
+        mismatches.push(Mismatch::RowCount(ours.len(), theirs.len()));
+    }
+
+    let key_of = |row: &[Value]| ours.columns().iter()
+        .zip(row)
+        .filter(|(column, _)| column.is_primary_key())
+        .map(|(_, value)| value.to_string())
+        .collect::<Vec<String>>()
+        .join(".");
+
+    let mut theirs: HashMap<String, Vec<Value>> = theirs.into_iter().map(|row| (key_of(&row), row)).collect();
+    for row in ours.rows()
+    {
+        let key = row.key();
+        match theirs.remove(&key)
+        {
+            Some(other) => {
+                for ((column, value), other) in ours.columns().iter().zip(row.values()).zip(other)
+                {
+                    if column.column_type() != ColumnType::Binary && *value != other
+                    {
+                        mismatches.push(Mismatch::Value(key.clone(), column.name().to_string(), value.clone(), other));
+                    }
+                }
+            },
+            None => mismatches.push(Mismatch::MissingRow(key, true))
+        }
+    }
+
+    let mut remaining: Vec<String> = theirs.into_keys().collect();
+    remaining.sort();
+    mismatches.extend(remaining.into_iter().map(|key| Mismatch::MissingRow(key, false)));
+
+    TableComparison {
+        table: ours.name().to_string(),
+        rows: ours.len(),
+        mismatches
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::testutil::TestPackage;
+
+    #[test]
+    fn test_cross_check()
+    {
+        let package = TestPackage::new("conformance", |builder| {
+            builder.table("Property", vec![
+                msi::Column::build("Property").primary_key().id_string(72),
+                msi::Column::build("Value").localizable().text_string(0)
+            ], vec![
+                vec![msi::Value::from("ProductName"), msi::Value::from("Alpha")],
+                vec![msi::Value::from("ALLUSERS"), msi::Value::from("1")]
+            ]);
+        });
+
+        let comparisons = cross_check(package.path()).unwrap();
+        assert!(comparisons.iter().any(|comparison| comparison.table() == "Property" && comparison.rows() == 2));
+        assert!(comparisons.iter().all(|comparison| comparison.mismatches().is_empty()));
+    }
+}
@@ -0,0 +1,304 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::error::{ Error, Result };
+use crate::package::MsiPackage;
+use crate::table::RowOrigin;
+
+#[doc = "How the size of a component installed by several features is attributed to them."]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Attribution {
+    #[doc = "Every feature is charged the full size, which is what a user selecting only that feature needs."]
+    Full,
+    #[doc = "The size is split evenly between the features, so the sizes of all features add up to the size of the package."]
+    Divided,
+    #[doc = "The size is charged to the first feature, in Feature table order, that installs the component."]
+    FirstOwner
+}
+
+impl FromStr for Attribution {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Attribution>
+    {
+        match value.to_ascii_lowercase().as_str()
+        {
+            "full" => Ok(Attribution::Full),
+            "divided" => Ok(Attribution::Divided),
+            "first-owner" => Ok(Attribution::FirstOwner),
+            other => Err(Error::NotFound(format!("attribution policy '{}'", other)))
+        }
+    }
+}
+
+impl Display for Attribution {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self
+        {
+            Attribution::Full => write!(fmt, "full"),
+            Attribution::Divided => write!(fmt, "divided"),
+            Attribution::FirstOwner => write!(fmt, "first-owner")
+        }
+    }
+}
+
+#[doc = "The installed size of a feature."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct FeatureCost {
+    feature: String,
+    parent: Option<String>,
+    title: Option<String>,
+    depth: usize,
+    components: usize,
+    size: u64,
+    total_size: u64,
+    origin: RowOrigin
+}
+
+impl FeatureCost {
+
+    #[doc = "Returns the key of the feature."]
+    pub fn feature(&self) -> &str {
+        &self.feature
+    }
+
+    #[doc = "Returns the parent feature, if any."]
+    pub fn parent(&self) -> Option<&str> {
+        self.parent.as_deref()
+    }
+
+    #[doc = "Returns the title shown in the feature selection dialog."]
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    #[doc = "Returns the number of ancestors of the feature; top-level features have depth 0."]
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    #[doc = "Returns the number of components the feature installs directly."]
+    pub fn components(&self) -> usize {
+        self.components
+    }
+
+    #[doc = "Returns the size in bytes of the files of the feature's own components, as attributed by the policy."]
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    #[doc = "Returns the size in bytes of the feature and all its descendants. A component installed by several of them is counted once, at no more than its full size."]
+    pub fn total_size(&self) -> u64 {
+        self.total_size
+    }
+
+    #[doc = "Returns the Feature row the feature was read from."]
+    pub fn origin(&self) -> &RowOrigin {
+        &self.origin
+    }
+}
+
+#[doc = "Computes the installed size of every feature from the FileSize column of the files of its components. Features are returned depth-first in Feature table order, so children follow their parent."]
+pub fn feature_costs(package: &MsiPackage, attribution: Attribution) -> Result<Vec<FeatureCost>>
+{
+    let features: Vec<(String, Option<String>, Option<String>, RowOrigin)> = match package.optional_table("Feature")?
+    {
+        Some(table) => table.rows()
+            .filter_map(|row| {
+                let feature = row.str("Feature")?;
+                let parent = row.str("Feature_Parent").filter(|parent| !parent.is_empty() && *parent != feature);
+                Some((feature.to_string(), parent.map(str::to_string), row.str("Title").map(str::to_string), row.origin()))
+            })
+            .collect(),
+        None => return Ok(Vec::new())
+    };
+    let position: HashMap<&str, usize> = features.iter().enumerate().map(|(index, (feature, _, _, _))| (feature.as_str(), index)).collect();
+
+    let mut component_sizes: HashMap<String, u64> = HashMap::new();
+    if let Some(table) = package.optional_table("File")?
+    {
+        for row in table.rows()
+        {
+            if let Some(component) = row.str("Component_")
+            {
+                *component_sizes.entry(component.to_string()).or_default() += row.int("FileSize").unwrap_or(0).max(0) as u64;
+            }
+        }
+    }
+
+    // owners of every component, in Feature table order
+    let mut owners: HashMap<String, Vec<usize>> = HashMap::new();
+    if let Some(table) = package.optional_table("FeatureComponents")?
+    {
+        for row in table.rows()
+        {
+            if let (Some(index), Some(component)) = (row.str("Feature_").and_then(|feature| position.get(feature)), row.str("Component_"))
+            {
+                let list = owners.entry(component.to_string()).or_default();
+                if !list.contains(index)
+                {
+                    list.push(*index);
+                }
+            }
+        }
+    }
+
+    let mut sizes = vec![0u64; features.len()];
+    let mut components = vec![0usize; features.len()];
+    // the share of every component each feature is charged, by component index
+    let mut shares: Vec<Vec<(usize, u64)>> = vec![Vec::new(); features.len()];
+    let mut full_sizes = Vec::with_capacity(owners.len());
+    for (component, list) in owners.iter_mut()
+    {
+        list.sort_unstable();
+        let size = component_sizes.get(component).copied().unwrap_or(0);
+        for (nth, index) in list.iter().enumerate()
+        {
+            let share = match attribution
+            {
+                Attribution::Full => size,
+                // the remainder goes to the first owner so the shares add up to the size
+                Attribution::Divided => size / list.len() as u64 + if nth == 0 { size % list.len() as u64 } else { 0 },
+                Attribution::FirstOwner => if nth == 0 { size } else { 0 }
+            };
+            components[*index] += 1;
+            sizes[*index] += share;
+            shares[*index].push((full_sizes.len(), share));
+        }
+        full_sizes.push(size);
+    }
+
+    let mut children: Vec<Vec<usize>> = vec![Vec::new(); features.len()];
+    let mut roots = Vec::new();
+    for (index, (_, parent, _, _)) in features.iter().enumerate()
+    {
+        match parent.as_deref().and_then(|parent| position.get(parent))
+        {
+            Some(parent) => children[*parent].push(index),
+            None => roots.push(index)
+        }
+    }
+
+    let mut costs: Vec<FeatureCost> = features.into_iter().enumerate()
+        .map(|(index, (feature, parent, title, origin))| FeatureCost { feature, parent, title, depth: 0, components: components[index], size: sizes[index], total_size: 0, origin })
+        .collect();
+    let mut tree = Tree {
+        children: &children,
+        shares: &shares,
+        full_sizes: &full_sizes,
+        visited: vec![false; costs.len()],
+        order: Vec::new()
+    };
+    for root in roots
+    {
+        tree.visit(root, 0, &mut costs);
+    }
+    // features whose parents form a cycle have no root; they follow, each cycle from its first feature
+    for index in 0..costs.len()
+    {
+        tree.visit(index, 0, &mut costs);
+    }
+
+    Ok(tree.order.into_iter().map(|index| costs[index].clone()).collect())
+}
+
+// The feature tree walked by feature_costs.
+struct Tree<'a> {
+    children: &'a [Vec<usize>],
+    shares: &'a [Vec<(usize, u64)>],
+    full_sizes: &'a [u64],
+    visited: Vec<bool>,
+    order: Vec<usize>
+}
+
+impl Tree<'_> {
+
+    // Walks the tree depth-first, filling in depths and subtree sizes; features in a cycle are visited once.
+    // Returns the shares charged to the subtree by component, so that a component installed by several
+    // features of the subtree is counted at most at its full size.
+    fn visit(&mut self, index: usize, depth: usize, costs: &mut [FeatureCost]) -> HashMap<usize, u64>
+    {
+        let mut subtree = HashMap::new();
+        if self.visited[index]
+        {
+            return subtree;
+        }
+        self.visited[index] = true;
+        self.order.push(index);
+
+        for (component, share) in &self.shares[index]
+        {
+            *subtree.entry(*component).or_default() += share;
+        }
+        for child in &self.children[index]
+        {
+            for (component, share) in self.visit(*child, depth + 1, costs)
+            {
+                *subtree.entry(component).or_default() += share;
+            }
+        }
+
+        costs[index].depth = depth;
+        costs[index].total_size = subtree.iter().map(|(component, share)| (*share).min(self.full_sizes[*component])).sum();
+        subtree
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::testutil::TestPackage;
+
+    #[test]
+    fn test_feature_costs()
+    {
+        let package = TestPackage::new("cost", |builder| {
+            builder.table("Feature", vec![
+                msi::Column::build("Feature").primary_key().id_string(38),
+                msi::Column::build("Feature_Parent").nullable().id_string(38),
+                msi::Column::build("Title").nullable().localizable().text_string(64)
+            ], vec![
+                vec![msi::Value::from("Complete"), msi::Value::Null, msi::Value::from("Contoso")],
+                vec![msi::Value::from("Docs"), msi::Value::from("Complete"), msi::Value::Null],
+                vec![msi::Value::from("Tools"), msi::Value::from("Complete"), msi::Value::Null],
+                vec![msi::Value::from("Loop"), msi::Value::from("Back"), msi::Value::Null],
+                vec![msi::Value::from("Back"), msi::Value::from("Loop"), msi::Value::Null]
+            ]);
+            builder.table("FeatureComponents", vec![
+                msi::Column::build("Feature_").primary_key().id_string(38),
+                msi::Column::build("Component_").primary_key().id_string(72)
+            ], vec![
+                vec![msi::Value::from("Complete"), msi::Value::from("Main")],
+                vec![msi::Value::from("Docs"), msi::Value::from("Shared")],
+                vec![msi::Value::from("Tools"), msi::Value::from("Shared")],
+                vec![msi::Value::from("Back"), msi::Value::from("Shared")]
+            ]);
+            builder.table("File", vec![
+                msi::Column::build("File").primary_key().id_string(72),
+                msi::Column::build("Component_").id_string(72),
+                msi::Column::build("FileSize").int32()
+            ], vec![
+                vec![msi::Value::from("app.exe"), msi::Value::from("Main"), msi::Value::from(1000)],
+                vec![msi::Value::from("a.dll"), msi::Value::from("Shared"), msi::Value::from(200)],
+                vec![msi::Value::from("b.dll"), msi::Value::from("Shared"), msi::Value::from(101)]
+            ]);
+        });
+        let package = MsiPackage::open(package.path()).unwrap();
+
+        let sizes = |attribution| feature_costs(&package, attribution).unwrap().iter().map(|cost| (cost.size(), cost.total_size())).collect::<Vec<_>>();
+        // Shared is counted once in the total of Complete, which installs it through both children
+        assert_eq!(sizes(Attribution::Full), [(1000, 1301), (301, 301), (301, 301), (301, 301), (0, 0)]);
+        assert_eq!(sizes(Attribution::Divided), [(1000, 1200), (100, 100), (100, 100), (101, 101), (0, 0)]);
+        assert_eq!(sizes(Attribution::FirstOwner), [(1000, 1000), (0, 0), (0, 0), (301, 301), (0, 0)]);
+
+        let costs = feature_costs(&package, Attribution::Full).unwrap();
+        assert_eq!(costs[1].feature(), "Docs");
+        assert_eq!(costs[1].depth(), 1);
+        // the cycle of Back and Loop follows the tree of Complete
+        let cycle: Vec<(&str, usize)> = costs[3..].iter().map(|cost| (cost.feature(), cost.depth())).collect();
+        assert_eq!(cycle, [("Back", 0), ("Loop", 1)]);
+        assert_eq!("first-owner".parse::<Attribution>().unwrap(), Attribution::FirstOwner);
+    }
+}
@@ -0,0 +1,43 @@
+use crate::table::Value;
+
+const BASE64_DIGITS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+#[doc = "Renders a string as a quoted CSV field, doubling embedded quotes."]
+pub(crate) fn string(value: &str) -> String
+{
+    format!("\"{}\"", value.replace('"', "\"\""))
+}
+
+#[doc = "Renders a cell as an empty field for null, a bare number or a quoted string, so the three stay distinguishable."]
+pub(crate) fn value(value: &Value) -> String
+{
+    match value
+    {
+        Value::Null => String::new(),
+        Value::Int(value) => value.to_string(),
+        Value::Str(value) => string(value)
+    }
+}
+
+#[doc = "Encodes binary data as padded standard base64."]
+pub(crate) fn base64(data: &[u8]) -> String
+{
+    let mut output = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3)
+    {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (index, byte)| bits | (*byte as u32) << (16 - 8 * index));
+        for index in 0..4
+        {
+            if index <= chunk.len()
+            {
+                output.push(BASE64_DIGITS[(bits >> (18 - 6 * index) & 0x3f) as usize] as char);
+            }
+            else
+            {
+                output.push('=');
+            }
+        }
+    }
+
+    output
+}
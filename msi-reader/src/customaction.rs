@@ -0,0 +1,791 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use crate::error::Result;
+use crate::package::MsiPackage;
+use crate::sequence::{ read_actions, ScheduledAction };
+use crate::table::RowOrigin;
+
+const TYPE_ROLLBACK: i32 = 0x0100;
+const TYPE_COMMIT: i32 = 0x0200;
+const TYPE_IN_SCRIPT: i32 = 0x0400;
+const TYPE_NO_IMPERSONATE: i32 = 0x0800;
+const TYPE_KIND_MASK: i32 = 0x07;
+const TYPE_SOURCE_MASK: i32 = 0x30;
+const KIND_JSCRIPT: i32 = 5;
+const KIND_VBSCRIPT: i32 = 6;
+const SOURCE_BINARY: i32 = 0x00;
+const SOURCE_FILE: i32 = 0x10;
+const SOURCE_TEXT: i32 = 0x20;
+const SOURCE_PROPERTY: i32 = 0x30;
+const KIND_DLL: i32 = 1;
+const KIND_EXE: i32 = 2;
+const KIND_TEXT: i32 = 3;
+const KIND_INSTALL: i32 = 7;
+const TYPE_CONTINUE: i32 = 0x0040;
+const TYPE_ASYNC: i32 = 0x0080;
+const TYPE_64BIT_SCRIPT: i32 = 0x1000;
+const TYPE_HIDE_TARGET: i32 = 0x2000;
+const TYPE_TS_AWARE: i32 = 0x4000;
+const TYPE_PATCH_UNINSTALL: i32 = 0x8000;
+
+#[doc = "When a custom action runs relative to the installation script."]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Scheduling {
+    #[doc = "Runs when the sequence reaches it, before the script is executed."]
+    Immediate,
+    #[doc = "Written to the installation script and run while it executes."]
+    Deferred,
+    #[doc = "Written to the rollback script and run only if the installation fails."]
+    Rollback,
+    #[doc = "Run after the installation script completed successfully."]
+    Commit
+}
+
+impl Scheduling {
+
+    #[doc = "Decodes the scheduling bits of a CustomAction Type value."]
+    pub fn from_type(custom_action_type: i32) -> Scheduling {
+        if custom_action_type & TYPE_IN_SCRIPT == 0
+        {
+            Scheduling::Immediate
+        }
+        else if custom_action_type & TYPE_ROLLBACK != 0
+        {
+            Scheduling::Rollback
+        }
+        else if custom_action_type & TYPE_COMMIT != 0
+        {
+            Scheduling::Commit
+        }
+        else
+        {
+            Scheduling::Deferred
+        }
+    }
+}
+
+impl Display for Scheduling {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name = match self
+        {
+            Scheduling::Immediate => "immediate",
+            Scheduling::Deferred => "deferred",
+            Scheduling::Rollback => "rollback",
+            Scheduling::Commit => "commit"
+        };
+
+        write!(fmt, "{}", name)
+    }
+}
+
+#[doc = "The security context a custom action runs in."]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Impersonation {
+    #[doc = "Runs with the rights of the user who started the installation."]
+    User,
+    #[doc = "A deferred action marked NoImpersonate, running as LocalSystem in a per-machine installation."]
+    System
+}
+
+impl Impersonation {
+
+    #[doc = "Decodes the impersonation bit of a CustomAction Type value. Only in-script actions can run without impersonation."]
+    pub fn from_type(custom_action_type: i32) -> Impersonation {
+        if custom_action_type & TYPE_IN_SCRIPT != 0 && custom_action_type & TYPE_NO_IMPERSONATE != 0
+        {
+            Impersonation::System
+        }
+        else
+        {
+            Impersonation::User
+        }
+    }
+}
+
+impl Display for Impersonation {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self
+        {
+            Impersonation::User => write!(fmt, "user"),
+            Impersonation::System => write!(fmt, "system")
+        }
+    }
+}
+
+#[doc = "What a custom action does, decoded from the kind and source bits of its Type."]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ActionKind {
+    #[doc = "Calls an exported function of a DLL (types 1, 17, 49 with the source bits)."]
+    Dll,
+    #[doc = "Launches an executable (types 2, 18, 34, 50 with the source bits)."]
+    Exe,
+    #[doc = "Runs JScript code."]
+    JScript,
+    #[doc = "Runs VBScript code."]
+    VBScript,
+    #[doc = "Type 19: displays the message in Target and fails the installation."]
+    Error,
+    #[doc = "Type 35: sets the Directory in Source to the formatted Target."]
+    SetDirectory,
+    #[doc = "Type 51: sets the property in Source to the formatted Target."]
+    SetProperty,
+    #[doc = "Types 7, 23 and 39: installs a nested product."]
+    NestedInstall,
+    #[doc = "A combination of kind and source bits Windows Installer does not define."]
+    Unknown(i32)
+}
+
+impl Display for ActionKind {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self
+        {
+            ActionKind::Dll => write!(fmt, "dll"),
+            ActionKind::Exe => write!(fmt, "exe"),
+            ActionKind::JScript => write!(fmt, "jscript"),
+            ActionKind::VBScript => write!(fmt, "vbscript"),
+            ActionKind::Error => write!(fmt, "error"),
+            ActionKind::SetDirectory => write!(fmt, "set directory"),
+            ActionKind::SetProperty => write!(fmt, "set property"),
+            ActionKind::NestedInstall => write!(fmt, "nested install"),
+            ActionKind::Unknown(bits) => write!(fmt, "unknown ({})", bits)
+        }
+    }
+}
+
+#[doc = "What the Source column of a DLL, EXE or script custom action refers to."]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ActionSource {
+    #[doc = "A stream of the Binary table."]
+    Binary,
+    #[doc = "A file installed by the package."]
+    File,
+    #[doc = "For executables, the Directory used as working directory, with the command line in Target; for scripts, the code is in Target itself."]
+    Directory,
+    #[doc = "A property holding the path of the executable or the code of the script."]
+    Property
+}
+
+#[doc = "The Type bit field of a CustomAction row."]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CustomActionType(i32);
+
+impl CustomActionType {
+
+    #[doc = "Returns the raw bits."]
+    pub fn bits(&self) -> i32 {
+        self.0
+    }
+
+    #[doc = "Returns what the custom action does."]
+    pub fn kind(&self) -> ActionKind {
+        match (self.0 & TYPE_KIND_MASK, self.0 & TYPE_SOURCE_MASK)
+        {
+            (KIND_DLL, _) => ActionKind::Dll,
+            (KIND_EXE, _) => ActionKind::Exe,
+            (KIND_JSCRIPT, _) => ActionKind::JScript,
+            (KIND_VBSCRIPT, _) => ActionKind::VBScript,
+            (KIND_TEXT, SOURCE_FILE) => ActionKind::Error,
+            (KIND_TEXT, SOURCE_TEXT) => ActionKind::SetDirectory,
+            (KIND_TEXT, SOURCE_PROPERTY) => ActionKind::SetProperty,
+            (KIND_INSTALL, SOURCE_BINARY) | (KIND_INSTALL, SOURCE_FILE) | (KIND_INSTALL, SOURCE_TEXT) => ActionKind::NestedInstall,
+            _ => ActionKind::Unknown(self.0 & (TYPE_KIND_MASK | TYPE_SOURCE_MASK))
+        }
+    }
+
+    #[doc = "Returns what the Source column refers to, or `None` for actions other than DLL, EXE and script ones."]
+    pub fn source(&self) -> Option<ActionSource> {
+        match self.kind()
+        {
+            ActionKind::Dll | ActionKind::Exe | ActionKind::JScript | ActionKind::VBScript => match self.0 & TYPE_SOURCE_MASK
+            {
+                SOURCE_BINARY => Some(ActionSource::Binary),
+                SOURCE_FILE => Some(ActionSource::File),
+                SOURCE_TEXT => Some(ActionSource::Directory),
+                _ => Some(ActionSource::Property)
+            },
+            _ => None
+        }
+    }
+
+    #[doc = "Returns when the action runs relative to the installation script."]
+    pub fn scheduling(&self) -> Scheduling {
+        Scheduling::from_type(self.0)
+    }
+
+    #[doc = "Returns the security context the action runs in."]
+    pub fn impersonation(&self) -> Impersonation {
+        Impersonation::from_type(self.0)
+    }
+
+    #[doc = "Returns a boolean value indicating whether a failure of the action is ignored."]
+    pub fn ignores_return(&self) -> bool {
+        self.0 & TYPE_CONTINUE != 0
+    }
+
+    #[doc = "Returns a boolean value indicating whether the action runs asynchronously."]
+    pub fn is_async(&self) -> bool {
+        self.0 & TYPE_ASYNC != 0
+    }
+
+    #[doc = "Returns a boolean value indicating whether the action data is kept out of the log."]
+    pub fn is_target_hidden(&self) -> bool {
+        self.0 & TYPE_HIDE_TARGET != 0
+    }
+
+    #[doc = "Returns a boolean value indicating whether a script action runs in a 64-bit host."]
+    pub fn is_64bit_script(&self) -> bool {
+        self.0 & TYPE_64BIT_SCRIPT != 0
+    }
+
+    #[doc = "Returns a boolean value indicating whether a deferred action impersonates the user on a Terminal Server per-machine installation."]
+    pub fn is_ts_aware(&self) -> bool {
+        self.0 & TYPE_IN_SCRIPT != 0 && self.0 & TYPE_TS_AWARE != 0
+    }
+
+    #[doc = "Returns a boolean value indicating whether the action runs only when a patch is uninstalled."]
+    pub fn is_patch_uninstall(&self) -> bool {
+        self.0 & TYPE_PATCH_UNINSTALL != 0
+    }
+}
+
+impl From<i32> for CustomActionType {
+    fn from(bits: i32) -> Self {
+        CustomActionType(bits)
+    }
+}
+
+#[doc = "A row of the CustomAction table."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct CustomActionRow {
+    action: String,
+    action_type: CustomActionType,
+    source: Option<String>,
+    target: Option<String>,
+    origin: RowOrigin
+}
+
+impl CustomActionRow {
+
+    #[doc = "Returns the name of the custom action."]
+    pub fn action(&self) -> &str {
+        &self.action
+    }
+
+    #[doc = "Returns the decoded Type of the custom action."]
+    pub fn action_type(&self) -> CustomActionType {
+        self.action_type
+    }
+
+    #[doc = "Returns the Source column, whose meaning depends on the type."]
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    #[doc = "Returns the Target column, whose meaning depends on the type."]
+    pub fn target(&self) -> Option<&str> {
+        self.target.as_deref()
+    }
+
+    #[doc = "Returns the CustomAction row the action was read from."]
+    pub fn origin(&self) -> &RowOrigin {
+        &self.origin
+    }
+}
+
+#[doc = "The typed rows of the CustomAction table, in storage order."]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CustomActionTable {
+    rows: Vec<CustomActionRow>,
+    index: HashMap<String, usize>
+}
+
+impl CustomActionTable {
+
+    #[doc = "Reads the CustomAction table of the package; a package without one yields an empty table."]
+    pub fn read(package: &MsiPackage) -> Result<CustomActionTable>
+    {
+        let table = match package.optional_table("CustomAction")?
+        {
+            Some(table) => table,
+            None => return Ok(CustomActionTable::default())
+        };
+
+        let rows: Vec<CustomActionRow> = table.rows()
+            .map(|row| CustomActionRow {
+                action: row.str("Action").unwrap_or_default().to_string(),
+                action_type: CustomActionType(row.int("Type").unwrap_or(0)),
+                source: row.str("Source").map(|source| source.to_string()),
+                target: row.str("Target").map(|target| target.to_string()),
+                origin: row.origin()
+            })
+            .collect();
+        let index = rows.iter().enumerate().map(|(index, row)| (row.action.clone(), index)).collect();
+
+        Ok(CustomActionTable {
+            rows,
+            index
+        })
+    }
+
+    #[doc = "Returns all custom actions in storage order."]
+    pub fn rows(&self) -> &[CustomActionRow] {
+        &self.rows
+    }
+
+    #[doc = "Returns the custom action with the given name."]
+    pub fn get(&self, action: &str) -> Option<&CustomActionRow> {
+        self.index.get(action).map(|index| &self.rows[*index])
+    }
+}
+
+#[doc = "A custom action together with where and how it is scheduled."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct CustomActionEntry {
+    action: String,
+    action_type: CustomActionType,
+    source: Option<String>,
+    target: Option<String>,
+    ui: Option<ScheduledAction>,
+    execute: Option<ScheduledAction>,
+    origin: RowOrigin
+}
+
+impl CustomActionEntry {
+
+    #[doc = "Returns the name of the custom action."]
+    pub fn action(&self) -> &str {
+        &self.action
+    }
+
+    #[doc = "Returns the raw Type value of the custom action."]
+    pub fn custom_action_type(&self) -> i32 {
+        self.action_type.bits()
+    }
+
+    #[doc = "Returns the decoded Type value of the custom action."]
+    pub fn action_type(&self) -> CustomActionType {
+        self.action_type
+    }
+
+    #[doc = "Returns the Source column of the custom action."]
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    #[doc = "Returns the Target column of the custom action."]
+    pub fn target(&self) -> Option<&str> {
+        self.target.as_deref()
+    }
+
+    #[doc = "Returns when the action runs relative to the installation script."]
+    pub fn scheduling(&self) -> Scheduling {
+        self.action_type.scheduling()
+    }
+
+    #[doc = "Returns the security context the action runs in."]
+    pub fn impersonation(&self) -> Impersonation {
+        self.action_type.impersonation()
+    }
+
+    #[doc = "Returns the InstallUISequence row scheduling the action, if any."]
+    pub fn ui(&self) -> Option<&ScheduledAction> {
+        self.ui.as_ref()
+    }
+
+    #[doc = "Returns the InstallExecuteSequence row scheduling the action, if any."]
+    pub fn execute(&self) -> Option<&ScheduledAction> {
+        self.execute.as_ref()
+    }
+
+    #[doc = "Returns the CustomAction row the entry was read from."]
+    pub fn origin(&self) -> &RowOrigin {
+        &self.origin
+    }
+}
+
+#[doc = "Every custom action of a package with its scheduling, impersonation, conditions and sequence positions."]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CustomActionMatrix {
+    entries: Vec<CustomActionEntry>
+}
+
+impl CustomActionMatrix {
+
+    #[doc = "Builds the matrix from the CustomAction table and both install sequences."]
+    pub fn build(package: &MsiPackage) -> Result<CustomActionMatrix>
+    {
+        let actions = CustomActionTable::read(package)?;
+        if actions.rows().is_empty()
+        {
+            return Ok(CustomActionMatrix::default());
+        }
+
+        let index = |actions: Vec<ScheduledAction>| -> HashMap<String, ScheduledAction> {
+            actions.into_iter().map(|scheduled| (scheduled.action().to_string(), scheduled)).collect()
+        };
+        let mut ui = index(read_actions(package, "InstallUISequence")?);
+        let mut execute = index(read_actions(package, "InstallExecuteSequence")?);
+
+        let mut entries: Vec<CustomActionEntry> = actions.rows.into_iter()
+            .map(|row| CustomActionEntry {
+                ui: ui.remove(&row.action),
+                execute: execute.remove(&row.action),
+                action: row.action,
+                action_type: row.action_type,
+                source: row.source,
+                target: row.target,
+                origin: row.origin
+            })
+            .collect();
+
+        // scheduled actions first, in execution order; unscheduled ones by name
+        entries.sort_by_key(|entry| {
+            let position = entry.execute.as_ref().or(entry.ui.as_ref()).and_then(|scheduled| scheduled.sequence());
+            (position.is_none(), position, entry.action.clone())
+        });
+
+        Ok(CustomActionMatrix {
+            entries
+        })
+    }
+
+    #[doc = "Returns all entries, scheduled actions first in sequence order."]
+    pub fn entries(&self) -> &[CustomActionEntry] {
+        &self.entries
+    }
+
+    #[doc = "Returns the entry of the given custom action."]
+    pub fn get(&self, action: &str) -> Option<&CustomActionEntry> {
+        self.entries.iter().find(|entry| entry.action == action)
+    }
+}
+
+impl Display for CustomActionMatrix {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let cell = |scheduled: Option<&ScheduledAction>| match scheduled
+        {
+            Some(scheduled) => (
+                scheduled.sequence().map(|sequence| sequence.to_string()).unwrap_or_default(),
+                scheduled.condition().unwrap_or("").to_string()),
+            None => ("-".to_string(), String::new())
+        };
+
+        let mut rows = vec![[
+            "Action".to_string(), "Scheduling".to_string(), "Context".to_string(),
+            "UI".to_string(), "UI condition".to_string(), "Execute".to_string(), "Execute condition".to_string()
+        ]];
+        for entry in &self.entries
+        {
+            let (ui, ui_condition) = cell(entry.ui());
+            let (execute, execute_condition) = cell(entry.execute());
+            rows.push([
+                entry.action.clone(), entry.scheduling().to_string(), entry.impersonation().to_string(),
+                ui, ui_condition, execute, execute_condition
+            ]);
+        }
+
+        let mut widths = [0usize; 7];
+        for row in &rows
+        {
+            for (width, value) in widths.iter_mut().zip(row.iter())
+            {
+                *width = (*width).max(value.chars().count());
+            }
+        }
+
+        for row in &rows
+        {
+            let line: Vec<String> = row.iter().zip(widths.iter()).map(|(value, width)| format!("{:<1$}", value, width)).collect();
+            writeln!(fmt, "{}", line.join("  ").trim_end())?;
+        }
+
+        Ok(())
+    }
+}
+
+#[doc = "The language of a script custom action."]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScriptLanguage {
+    JScript,
+    VBScript
+}
+
+impl ScriptLanguage {
+
+    #[doc = "Returns the language of a CustomAction Type value, or `None` if it is not a script action."]
+    pub fn from_type(custom_action_type: i32) -> Option<ScriptLanguage> {
+        match CustomActionType(custom_action_type).kind()
+        {
+            ActionKind::JScript => Some(ScriptLanguage::JScript),
+            ActionKind::VBScript => Some(ScriptLanguage::VBScript),
+            _ => None
+        }
+    }
+
+    #[doc = "Returns the usual file extension of scripts in this language."]
+    pub fn extension(&self) -> &'static str {
+        match self
+        {
+            ScriptLanguage::JScript => "js",
+            ScriptLanguage::VBScript => "vbs"
+        }
+    }
+}
+
+impl Display for ScriptLanguage {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self
+        {
+            ScriptLanguage::JScript => write!(fmt, "JScript"),
+            ScriptLanguage::VBScript => write!(fmt, "VBScript")
+        }
+    }
+}
+
+#[doc = "Where the code of a script custom action is stored."]
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScriptLocation {
+    #[doc = "A stream of the Binary table, by key."]
+    Binary(String),
+    #[doc = "A file installed by the package, by File key; its content is not part of the database."]
+    File(String),
+    #[doc = "The Target column of the custom action itself."]
+    Inline,
+    #[doc = "The value of the given property."]
+    Property(String)
+}
+
+impl Display for ScriptLocation {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self
+        {
+            ScriptLocation::Binary(key) => write!(fmt, "Binary.{}", key),
+            ScriptLocation::File(key) => write!(fmt, "File.{}", key),
+            ScriptLocation::Inline => write!(fmt, "inline"),
+            ScriptLocation::Property(name) => write!(fmt, "property {}", name)
+        }
+    }
+}
+
+#[doc = "A script custom action and, if it is embedded in the database, its code."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct EmbeddedScript {
+    action: String,
+    language: ScriptLanguage,
+    location: ScriptLocation,
+    function: Option<String>,
+    content: Option<Vec<u8>>,
+    origin: RowOrigin
+}
+
+impl EmbeddedScript {
+
+    #[doc = "Returns the name of the custom action."]
+    pub fn action(&self) -> &str {
+        &self.action
+    }
+
+    #[doc = "Returns the script language."]
+    pub fn language(&self) -> ScriptLanguage {
+        self.language
+    }
+
+    #[doc = "Returns where the script is stored."]
+    pub fn location(&self) -> &ScriptLocation {
+        &self.location
+    }
+
+    #[doc = "Returns the function called in the script, for scripts stored in the Binary table or a file."]
+    pub fn function(&self) -> Option<&str> {
+        self.function.as_deref()
+    }
+
+    #[doc = "Returns the code of the script as stored, or `None` if it is an installed file or the referenced stream or property does not exist."]
+    pub fn content(&self) -> Option<&[u8]> {
+        self.content.as_deref()
+    }
+
+    #[doc = "Returns the CustomAction row the script was found in."]
+    pub fn origin(&self) -> &RowOrigin {
+        &self.origin
+    }
+}
+
+#[doc = "Finds all JScript and VBScript custom actions and reads the code of the ones embedded in the package."]
+pub fn find_scripts(package: &MsiPackage) -> Result<Vec<EmbeddedScript>>
+{
+    let mut scripts = Vec::new();
+    for row in CustomActionTable::read(package)?.rows
+    {
+        let language = match ScriptLanguage::from_type(row.action_type.bits())
+        {
+            Some(language) => language,
+            None => continue
+        };
+
+        let source = row.source.unwrap_or_default();
+        let (location, function, content) = match row.action_type.source()
+        {
+            Some(ActionSource::Binary) => {
+                let content = package.read_stream(&format!("Binary.{}", source)).ok();
+                (ScriptLocation::Binary(source), row.target, content)
+            },
+            Some(ActionSource::File) => (ScriptLocation::File(source), row.target, None),
+            Some(ActionSource::Directory) => (ScriptLocation::Inline, None, row.target.map(|target| target.into_bytes())),
+            _ => {
+                let content = package.property(&source)?.map(|value| value.into_bytes());
+                (ScriptLocation::Property(source), None, content)
+            }
+        };
+
+        scripts.push(EmbeddedScript {
+            action: row.action,
+            language,
+            location,
+            function,
+            content,
+            origin: row.origin
+        });
+    }
+
+    scripts.sort_by(|first, second| first.action.cmp(&second.action));
+    Ok(scripts)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::testutil::TestPackage;
+
+    // The columns of the CustomAction table.
+    fn custom_action_columns() -> Vec<msi::Column>
+    {
+        vec![
+            msi::Column::build("Action").primary_key().id_string(72),
+            msi::Column::build("Type").int16(),
+            msi::Column::build("Source").nullable().string(72),
+            msi::Column::build("Target").nullable().formatted_string(255)
+        ]
+    }
+
+    #[test]
+    fn test_matrix()
+    {
+        let package = TestPackage::new("custom-actions", |builder| {
+            builder.table("CustomAction", custom_action_columns(), vec![
+                vec![msi::Value::from("SetPath"), msi::Value::Int(51), msi::Value::from("INSTALLDIR"), msi::Value::from("[ProgramFilesFolder]App")],
+                vec![msi::Value::from("RegisterService"), msi::Value::Int(3073), msi::Value::from("Helper"), msi::Value::from("Register")],
+                vec![msi::Value::from("UndoRegister"), msi::Value::Int(1281), msi::Value::from("Helper"), msi::Value::from("Unregister")],
+                vec![msi::Value::from("Unused"), msi::Value::Int(1), msi::Value::from("Helper"), msi::Value::from("Nothing")]
+            ]);
+            let columns = || vec![
+                msi::Column::build("Action").primary_key().id_string(72),
+                msi::Column::build("Condition").nullable().string(255),
+                msi::Column::build("Sequence").nullable().int16()
+            ];
+            builder.table("InstallUISequence", columns(), vec![
+                vec![msi::Value::from("SetPath"), msi::Value::from("NOT INSTALLDIR"), msi::Value::Int(100)]
+            ]);
+            builder.table("InstallExecuteSequence", columns(), vec![
+                vec![msi::Value::from("SetPath"), msi::Value::Null, msi::Value::Int(100)],
+                vec![msi::Value::from("UndoRegister"), msi::Value::Null, msi::Value::Int(4999)],
+                vec![msi::Value::from("RegisterService"), msi::Value::from("NOT Installed"), msi::Value::Int(5000)]
+            ]);
+        });
+
+        let matrix = CustomActionMatrix::build(&MsiPackage::open(package.path()).unwrap()).unwrap();
+        let names: Vec<&str> = matrix.entries().iter().map(|entry| entry.action()).collect();
+        assert_eq!(names, ["SetPath", "UndoRegister", "RegisterService", "Unused"]);
+
+        let set_path = matrix.get("SetPath").unwrap();
+        assert_eq!(set_path.scheduling(), Scheduling::Immediate);
+        assert_eq!(set_path.ui().unwrap().condition(), Some("NOT INSTALLDIR"));
+        assert_eq!(set_path.execute().unwrap().condition(), None);
+
+        let register = matrix.get("RegisterService").unwrap();
+        assert_eq!(register.scheduling(), Scheduling::Deferred);
+        assert_eq!(register.impersonation(), Impersonation::System);
+        assert_eq!((register.action_type().kind(), register.custom_action_type()), (ActionKind::Dll, 3073));
+        assert!(register.ui().is_none());
+        assert_eq!(register.execute().unwrap().sequence(), Some(5000));
+        assert_eq!(register.execute().unwrap().origin().table(), "InstallExecuteSequence");
+        assert_eq!((register.origin().table(), register.origin().key()), ("CustomAction", "RegisterService"));
+
+        let undo = matrix.get("UndoRegister").unwrap();
+        assert_eq!(undo.scheduling(), Scheduling::Rollback);
+        assert_eq!(undo.impersonation(), Impersonation::User);
+
+        let text = matrix.to_string();
+        assert!(text.lines().nth(3).unwrap().starts_with("RegisterService  deferred    system"));
+    }
+
+    #[test]
+    fn test_custom_action_types()
+    {
+        let package = TestPackage::new("custom-action-table", |builder| {
+            builder.table("CustomAction", custom_action_columns(), vec![
+                vec![msi::Value::from("SetPath"), msi::Value::Int(51), msi::Value::from("INSTALLDIR"), msi::Value::from("[ProgramFilesFolder]App")],
+                vec![msi::Value::from("Fail"), msi::Value::Int(19), msi::Value::Null, msi::Value::from("Unsupported")],
+                vec![msi::Value::from("Launch"), msi::Value::Int(34 | 0x40 | 0x80), msi::Value::from("INSTALLDIR"), msi::Value::from("app.exe /setup")],
+                vec![msi::Value::from("Configure"), msi::Value::Int(1 | 0x0400 | 0x0800 | 0x2000), msi::Value::from("Helper"), msi::Value::from("Configure")],
+                vec![msi::Value::from("Script"), msi::Value::Int(0x10 | 5 | 0x1000), msi::Value::from("Tool.js"), msi::Value::from("Main")],
+                vec![msi::Value::from("Odd"), msi::Value::Int(0x30 | 7), msi::Value::Null, msi::Value::Null]
+            ]);
+        });
+
+        let actions = CustomActionTable::read(&MsiPackage::open(package.path()).unwrap()).unwrap();
+        let action_type = |action: &str| actions.get(action).unwrap().action_type();
+        assert_eq!((action_type("SetPath").kind(), action_type("SetPath").source()), (ActionKind::SetProperty, None));
+        assert_eq!(action_type("Fail").kind(), ActionKind::Error);
+        assert_eq!(action_type("Odd").kind(), ActionKind::Unknown(0x37));
+
+        let launch = action_type("Launch");
+        assert_eq!((launch.kind(), launch.source(), launch.scheduling()), (ActionKind::Exe, Some(ActionSource::Directory), Scheduling::Immediate));
+        assert!(launch.ignores_return() && launch.is_async() && !launch.is_target_hidden());
+
+        let configure = action_type("Configure");
+        assert_eq!((configure.kind(), configure.source()), (ActionKind::Dll, Some(ActionSource::Binary)));
+        assert_eq!((configure.scheduling(), configure.impersonation()), (Scheduling::Deferred, Impersonation::System));
+        assert!(configure.is_target_hidden() && !configure.is_ts_aware());
+
+        let script = action_type("Script");
+        assert_eq!((script.kind(), script.source()), (ActionKind::JScript, Some(ActionSource::File)));
+        assert!(script.is_64bit_script());
+        assert_eq!(actions.get("Script").unwrap().target(), Some("Main"));
+        assert_eq!(actions.rows().len(), 6);
+    }
+
+    #[test]
+    fn test_find_scripts()
+    {
+        let package = TestPackage::new("scripts", |builder| {
+            builder.table("CustomAction", custom_action_columns(), vec![
+                vec![msi::Value::from("Inline"), msi::Value::Int(38), msi::Value::Null, msi::Value::from("MsgBox \"hi\"")],
+                vec![msi::Value::from("FromProperty"), msi::Value::Int(53), msi::Value::from("SCRIPT"), msi::Value::Null],
+                vec![msi::Value::from("FromBinary"), msi::Value::Int(6), msi::Value::from("Missing"), msi::Value::from("Main")],
+                vec![msi::Value::from("SetPath"), msi::Value::Int(51), msi::Value::from("INSTALLDIR"), msi::Value::from("C:\\")]
+            ]);
+            builder.table("Property", vec![
+                msi::Column::build("Property").primary_key().id_string(72),
+                msi::Column::build("Value").localizable().text_string(0)
+            ], vec![
+                vec![msi::Value::from("SCRIPT"), msi::Value::from("Session.Property(\"X\") = 1;")]
+            ]);
+        });
+
+        let scripts = find_scripts(&MsiPackage::open(package.path()).unwrap()).unwrap();
+        let names: Vec<&str> = scripts.iter().map(|script| script.action()).collect();
+        assert_eq!(names, ["FromBinary", "FromProperty", "Inline"]);
+
+        assert_eq!(scripts[0].location(), &ScriptLocation::Binary("Missing".to_string()));
+        assert_eq!(scripts[0].function(), Some("Main"));
+        assert_eq!(scripts[0].origin().key(), "FromBinary");
+        assert!(scripts[0].content().is_none());
+        assert_eq!(scripts[1].language(), ScriptLanguage::JScript);
+        assert_eq!(scripts[1].content(), Some(&b"Session.Property(\"X\") = 1;"[..]));
+        assert_eq!(scripts[2].language().extension(), "vbs");
+        assert_eq!(scripts[2].content(), Some(&b"MsgBox \"hi\""[..]));
+    }
+}
@@ -0,0 +1,260 @@
+use crate::error::{ Error, Result };
+
+pub(crate) const TAG_INTEGER: u8 = 0x02;
+pub(crate) const TAG_OCTET_STRING: u8 = 0x04;
+pub(crate) const TAG_OID: u8 = 0x06;
+pub(crate) const TAG_UTC_TIME: u8 = 0x17;
+pub(crate) const TAG_GENERALIZED_TIME: u8 = 0x18;
+pub(crate) const TAG_SEQUENCE: u8 = 0x30;
+pub(crate) const TAG_SET: u8 = 0x31;
+pub(crate) const TAG_CONTEXT_0: u8 = 0xa0;
+pub(crate) const TAG_CONTEXT_1: u8 = 0xa1;
+
+#[doc = "A single DER element: its tag, its content and the complete encoding including the header."]
+#[derive(Clone, Copy)]
+pub(crate) struct Element<'a> {
+    tag: u8,
+    content: &'a [u8],
+    raw: &'a [u8]
+}
+
+impl<'a> Element<'a> {
+
+    pub(crate) fn tag(&self) -> u8
+    {
+        self.tag
+    }
+
+    pub(crate) fn content(&self) -> &'a [u8]
+    {
+        self.content
+    }
+
+    pub(crate) fn raw(&self) -> &'a [u8]
+    {
+        self.raw
+    }
+
+    #[doc = "Returns a reader over the children of a constructed element."]
+    pub(crate) fn children(&self) -> Reader<'a>
+    {
+        Reader::new(self.content)
+    }
+
+    #[doc = "Decodes an OBJECT IDENTIFIER into its dotted form."]
+    pub(crate) fn oid(&self) -> Result<String>
+    {
+        self.expect(TAG_OID)?;
+        let (first, rest) = self.content.split_first()
+            .ok_or_else(|| Error::invalid("empty object identifier"))?;
+
+        let mut arcs = vec![(*first / 40).min(2) as u64, (*first as u64) - 40 * (*first / 40).min(2) as u64];
+        let mut value: u64 = 0;
+        for byte in rest
+        {
+            value = value.checked_mul(128).ok_or_else(|| Error::invalid("object identifier arc is too large"))? | (byte & 0x7f) as u64;
+            if byte & 0x80 == 0
+            {
+                arcs.push(value);
+                value = 0;
+            }
+        }
+
+        Ok(arcs.iter().map(|arc| arc.to_string()).collect::<Vec<String>>().join("."))
+    }
+
+    #[doc = "Renders a UTCTime or GeneralizedTime as `YYYY-MM-DD HH:MM:SS UTC`."]
+    pub(crate) fn time(&self) -> Result<String>
+    {
+        let text = std::str::from_utf8(self.content).map_err(|_| Error::invalid("time is not ASCII"))?;
+        let digits: String = text.chars().take_while(|c| c.is_ascii_digit()).collect();
+        let full = match self.tag
+        {
+            TAG_UTC_TIME if digits.len() >= 12 => {
+                // two-digit years 50..99 belong to the 20th century
+                let century = if &digits[..2] >= "50" { "19" } else { "20" };
+                format!("{}{}", century, digits)
+            },
+            TAG_GENERALIZED_TIME if digits.len() >= 14 => digits,
+            _ => return Err(Error::invalid(format!("'{}' is not a valid time", text)))
+        };
+
+        Ok(format!("{}-{}-{} {}:{}:{} UTC", &full[..4], &full[4..6], &full[6..8], &full[8..10], &full[10..12], &full[12..14]))
+    }
+
+    #[doc = "Decodes one of the ASN.1 string types."]
+    pub(crate) fn string(&self) -> String
+    {
+        match self.tag
+        {
+            // BMPString
+            0x1e => {
+                let units: Vec<u16> = self.content.chunks_exact(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]])).collect();
+                String::from_utf16_lossy(&units)
+            },
+            // UniversalString
+            0x1c => self.content.chunks_exact(4)
+                .map(|quad| std::char::from_u32(u32::from_be_bytes([quad[0], quad[1], quad[2], quad[3]])).unwrap_or('\u{fffd}'))
+                .collect(),
+            _ => String::from_utf8_lossy(self.content).into_owned()
+        }
+    }
+
+    pub(crate) fn expect(&self, tag: u8) -> Result<()>
+    {
+        if self.tag == tag
+        {
+            Ok(())
+        }
+        else
+        {
+            Err(Error::invalid(format!("expected DER tag 0x{:02x}, found 0x{:02x}", tag, self.tag)))
+        }
+    }
+}
+
+#[doc = "Reads consecutive DER elements from a byte slice. Malformed or truncated input yields an `Err`."]
+pub(crate) struct Reader<'a> {
+    data: &'a [u8],
+    position: usize
+}
+
+impl<'a> Reader<'a> {
+
+    pub(crate) fn new(data: &'a [u8]) -> Self
+    {
+        Reader {
+            data,
+            position: 0
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool
+    {
+        self.position >= self.data.len()
+    }
+
+    pub(crate) fn peek_tag(&self) -> Option<u8>
+    {
+        self.data.get(self.position).copied()
+    }
+
+    pub(crate) fn read(&mut self) -> Result<Element<'a>>
+    {
+        let start = self.position;
+        let truncated = || Error::invalid("truncated DER element");
+        let tag = *self.data.get(start).ok_or_else(truncated)?;
+        let first = *self.data.get(start + 1).ok_or_else(truncated)?;
+
+        let mut header = 2;
+        let length = if first & 0x80 == 0
+        {
+            first as usize
+        }
+        else
+        {
+            let count = (first & 0x7f) as usize;
+            if count == 0 || count > 4
+            {
+                return Err(Error::invalid("unsupported DER length encoding"));
+            }
+
+            let bytes = self.data.get(start + 2..start + 2 + count).ok_or_else(truncated)?;
+            header += count;
+            bytes.iter().fold(0usize, |length, byte| (length << 8) | *byte as usize)
+        };
+
+        let end = (start + header).checked_add(length).ok_or_else(truncated)?;
+        let content = self.data.get(start + header..end).ok_or_else(truncated)?;
+        self.position = end;
+        Ok(Element {
+            tag,
+            content,
+            raw: &self.data[start..end]
+        })
+    }
+
+    pub(crate) fn read_tag(&mut self, tag: u8) -> Result<Element<'a>>
+    {
+        let element = self.read()?;
+        element.expect(tag)?;
+        Ok(element)
+    }
+
+    #[doc = "Reads the next element if it has the given tag, as used for OPTIONAL fields."]
+    pub(crate) fn read_optional(&mut self, tag: u8) -> Result<Option<Element<'a>>>
+    {
+        if self.peek_tag() == Some(tag)
+        {
+            self.read().map(Some)
+        }
+        else
+        {
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests
+{
+    use super::*;
+
+    pub(crate) fn encode(tag: u8, content: &[u8]) -> Vec<u8>
+    {
+        let mut output = vec![tag];
+        let length = content.len();
+        if length < 0x80
+        {
+            output.push(length as u8);
+        }
+        else
+        {
+            let bytes: Vec<u8> = length.to_be_bytes().iter().copied().skip_while(|byte| *byte == 0).collect();
+            output.push(0x80 | bytes.len() as u8);
+            output.extend_from_slice(&bytes);
+        }
+
+        output.extend_from_slice(content);
+        output
+    }
+
+    pub(crate) fn constructed(tag: u8, children: &[Vec<u8>]) -> Vec<u8>
+    {
+        encode(tag, &children.concat())
+    }
+
+    pub(crate) fn oid(dotted: &str) -> Vec<u8>
+    {
+        let arcs: Vec<u64> = dotted.split('.').map(|arc| arc.parse().unwrap()).collect();
+        let mut content = vec![(arcs[0] * 40 + arcs[1]) as u8];
+        for arc in &arcs[2..]
+        {
+            let mut groups = vec![(arc & 0x7f) as u8];
+            let mut rest = arc >> 7;
+            while rest > 0
+            {
+                groups.push((rest & 0x7f) as u8 | 0x80);
+                rest >>= 7;
+            }
+            content.extend(groups.iter().rev());
+        }
+
+        encode(TAG_OID, &content)
+    }
+
+    #[test]
+    fn test_reader()
+    {
+        let data = constructed(TAG_SEQUENCE, &[oid("1.2.840.113549.1.7.2"), encode(TAG_UTC_TIME, b"240131120000Z"), encode(TAG_OCTET_STRING, &[7u8; 200])]);
+        let sequence = Reader::new(&data).read_tag(TAG_SEQUENCE).unwrap();
+        let mut children = sequence.children();
+        assert_eq!(children.read().unwrap().oid().unwrap(), "1.2.840.113549.1.7.2");
+        assert_eq!(children.read().unwrap().time().unwrap(), "2024-01-31 12:00:00 UTC");
+        assert_eq!(children.read_optional(TAG_OCTET_STRING).unwrap().unwrap().content().len(), 200);
+        assert!(children.is_empty());
+
+        assert!(Reader::new(&data[..data.len() - 1]).read().is_err());
+        assert!(Reader::new(&[0x30, 0x85, 1, 1, 1, 1, 1]).read().is_err());
+    }
+}
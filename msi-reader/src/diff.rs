@@ -0,0 +1,302 @@
+use std::collections::{ BTreeMap, BTreeSet };
+
+use crate::error::Result;
+use crate::json;
+use crate::package::MsiPackage;
+use crate::schema;
+use crate::table::{ RowOrigin, Table, Value };
+
+#[doc = "A cell whose value differs between the two packages."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct CellChange {
+    column: String,
+    old_value: Value,
+    new_value: Value
+}
+
+impl CellChange {
+
+    #[doc = "Returns the name of the column."]
+    pub fn column(&self) -> &str {
+        &self.column
+    }
+
+    #[doc = "Returns the value in the old package; `Null` if the column does not exist there."]
+    pub fn old_value(&self) -> &Value {
+        &self.old_value
+    }
+
+    #[doc = "Returns the value in the new package; `Null` if the column does not exist there."]
+    pub fn new_value(&self) -> &Value {
+        &self.new_value
+    }
+}
+
+#[doc = "A row present in both packages with different content."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ModifiedRow {
+    old: RowOrigin,
+    new: RowOrigin,
+    changes: Vec<CellChange>
+}
+
+impl ModifiedRow {
+
+    #[doc = "Returns the primary key of the row."]
+    pub fn key(&self) -> &str {
+        self.new.key()
+    }
+
+    #[doc = "Returns the row in the old package."]
+    pub fn old_origin(&self) -> &RowOrigin {
+        &self.old
+    }
+
+    #[doc = "Returns the row in the new package."]
+    pub fn new_origin(&self) -> &RowOrigin {
+        &self.new
+    }
+
+    #[doc = "Returns the changed cells."]
+    pub fn changes(&self) -> &[CellChange] {
+        &self.changes
+    }
+}
+
+#[doc = "The differences of a table present in both packages, by primary key."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct TableDiff {
+    table: String,
+    added: Vec<RowOrigin>,
+    removed: Vec<RowOrigin>,
+    modified: Vec<ModifiedRow>
+}
+
+impl TableDiff {
+
+    fn compare(old: &Table, new: &Table) -> TableDiff
+    {
+        let old_rows: BTreeMap<String, _> = old.rows().map(|row| (row.key(), row)).collect();
+        let new_rows: BTreeMap<String, _> = new.rows().map(|row| (row.key(), row)).collect();
+        let columns: BTreeSet<&str> = old.columns().iter().chain(new.columns()).map(|column| column.name()).collect();
+
+        let mut modified = Vec::new();
+        for (key, old_row) in &old_rows
+        {
+            if let Some(new_row) = new_rows.get(key)
+            {
+                let changes: Vec<CellChange> = columns.iter()
+                    .filter_map(|column| {
+                        let old = old_row.get(column).cloned().unwrap_or(Value::Null);
+                        let new = new_row.get(column).cloned().unwrap_or(Value::Null);
+                        if old != new { Some(CellChange { column: column.to_string(), old_value: old, new_value: new }) } else { None }
+                    })
+                    .collect();
+                if !changes.is_empty()
+                {
+                    modified.push(ModifiedRow {
+                        old: old_row.origin(),
+                        new: new_row.origin(),
+                        changes
+                    });
+                }
+            }
+        }
+
+        TableDiff {
+            table: new.name().to_string(),
+            added: new_rows.iter().filter(|(key, _)| !old_rows.contains_key(*key)).map(|(_, row)| row.origin()).collect(),
+            removed: old_rows.iter().filter(|(key, _)| !new_rows.contains_key(*key)).map(|(_, row)| row.origin()).collect(),
+            modified
+        }
+    }
+
+    #[doc = "Returns the name of the table."]
+    pub fn table(&self) -> &str {
+        &self.table
+    }
+
+    #[doc = "Returns the rows only present in the new package, ordered by primary key."]
+    pub fn added(&self) -> &[RowOrigin] {
+        &self.added
+    }
+
+    #[doc = "Returns the rows only present in the old package, ordered by primary key."]
+    pub fn removed(&self) -> &[RowOrigin] {
+        &self.removed
+    }
+
+    #[doc = "Returns the rows present in both packages with different content."]
+    pub fn modified(&self) -> &[ModifiedRow] {
+        &self.modified
+    }
+
+    #[doc = "Returns a boolean value indicating whether the table is unchanged."]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+#[doc = "The differences between two packages: tables, rows and cells, and non-table streams by content."]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PackageDiff {
+    added_tables: Vec<String>,
+    removed_tables: Vec<String>,
+    tables: Vec<TableDiff>,
+    added_streams: Vec<String>,
+    removed_streams: Vec<String>,
+    modified_streams: Vec<String>
+}
+
+impl PackageDiff {
+
+    #[doc = "Compares two packages. Tables with identical content hashes are skipped without comparing their rows."]
+    pub fn compare(old: &MsiPackage, new: &MsiPackage) -> Result<PackageDiff>
+    {
+        let mut diff = PackageDiff::default();
+        let old_tables: BTreeSet<&str> = old.table_names().collect();
+        let new_tables: BTreeSet<&str> = new.table_names().collect();
+        diff.added_tables = new_tables.difference(&old_tables).map(|name| name.to_string()).collect();
+        diff.removed_tables = old_tables.difference(&new_tables).map(|name| name.to_string()).collect();
+
+        for name in old_tables.intersection(&new_tables)
+        {
+            let old_table = old.table(name)?;
+            let new_table = new.table(name)?;
+            if old_table.content_hash() == new_table.content_hash()
+            {
+                continue;
+            }
+
+            let table = TableDiff::compare(&old_table, &new_table);
+            if !table.is_empty()
+            {
+                diff.tables.push(table);
+            }
+        }
+
+        let old_streams = old.digests()?.streams();
+        let new_streams = new.digests()?.streams();
+        for (name, digest) in new_streams
+        {
+            match old_streams.get(name)
+            {
+                None => diff.added_streams.push(name.clone()),
+                Some(old_digest) if old_digest != digest => diff.modified_streams.push(name.clone()),
+                Some(_) => {}
+            }
+        }
+        diff.removed_streams = old_streams.keys().filter(|name| !new_streams.contains_key(*name)).cloned().collect();
+
+        Ok(diff)
+    }
+
+    #[doc = "Returns the tables only present in the new package."]
+    pub fn added_tables(&self) -> &[String] {
+        &self.added_tables
+    }
+
+    #[doc = "Returns the tables only present in the old package."]
+    pub fn removed_tables(&self) -> &[String] {
+        &self.removed_tables
+    }
+
+    #[doc = "Returns the row differences of the tables present in both packages, leaving out unchanged tables."]
+    pub fn tables(&self) -> &[TableDiff] {
+        &self.tables
+    }
+
+    #[doc = "Returns the non-table streams only present in the new package."]
+    pub fn added_streams(&self) -> &[String] {
+        &self.added_streams
+    }
+
+    #[doc = "Returns the non-table streams only present in the old package."]
+    pub fn removed_streams(&self) -> &[String] {
+        &self.removed_streams
+    }
+
+    #[doc = "Returns the non-table streams whose content differs."]
+    pub fn modified_streams(&self) -> &[String] {
+        &self.modified_streams
+    }
+
+    #[doc = "Returns a boolean value indicating whether the packages have the same content."]
+    pub fn is_empty(&self) -> bool {
+        self.added_tables.is_empty() && self.removed_tables.is_empty() && self.tables.is_empty()
+            && self.added_streams.is_empty() && self.removed_streams.is_empty() && self.modified_streams.is_empty()
+    }
+
+    #[doc = "Renders the differences as a JSON document following `schema::DIFF`."]
+    pub fn to_json(&self) -> String
+    {
+        let tables: Vec<String> = self.tables.iter().map(|table| {
+            let modified: Vec<String> = table.modified.iter().map(|row| {
+                let changes = json::array(row.changes.iter().map(|change| format!("{{ \"column\": {}, \"old\": {}, \"new\": {} }}",
+                    json::string(&change.column), json::value(&change.old_value), json::value(&change.new_value))));
+                format!("{{ \"key\": {}, \"changes\": {} }}", json::string(row.key()), changes)
+            }).collect();
+            format!("    {{\n      \"name\": {},\n      \"added\": {},\n      \"removed\": {},\n      \"modified\": [{}]\n    }}",
+                json::string(&table.table), keys(&table.added), keys(&table.removed),
+                if modified.is_empty() { String::new() } else { format!("\n        {}\n      ", modified.join(",\n        ")) })
+        }).collect();
+
+        format!("{{\n  \"$schema\": {},\n  \"addedTables\": {},\n  \"removedTables\": {},\n  \"tables\": [{}],\n  \"addedStreams\": {},\n  \"removedStreams\": {},\n  \"modifiedStreams\": {}\n}}\n",
+            json::string(schema::DIFF.id()), json::strings(&self.added_tables), json::strings(&self.removed_tables),
+            if tables.is_empty() { String::new() } else { format!("\n{}\n  ", tables.join(",\n")) },
+            json::strings(&self.added_streams), json::strings(&self.removed_streams), json::strings(&self.modified_streams))
+    }
+}
+
+fn keys(rows: &[RowOrigin]) -> String
+{
+    json::array(rows.iter().map(|row| json::string(row.key())))
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::testutil::TestPackage;
+
+    fn property_package(tag: &str, rows: &[(&str, &str)]) -> TestPackage
+    {
+        let rows = rows.iter().map(|(name, value)| vec![msi::Value::from(*name), msi::Value::from(*value)]).collect();
+        TestPackage::new(tag, |builder| {
+            builder.table("Property", vec![
+                msi::Column::build("Property").primary_key().id_string(72),
+                msi::Column::build("Value").localizable().text_string(0)
+            ], rows);
+        })
+    }
+
+    #[test]
+    fn test_compare()
+    {
+        let old = property_package("diff-old", &[("ProductName", "Alpha"), ("ProductVersion", "1.0"), ("ARPNOREPAIR", "1")]);
+        let new = property_package("diff-new", &[("ProductName", "Alpha"), ("ProductVersion", "2.0"), ("ALLUSERS", "1")]);
+        let old = MsiPackage::open(old.path()).unwrap();
+        let new = MsiPackage::open(new.path()).unwrap();
+
+        assert!(PackageDiff::compare(&old, &old).unwrap().is_empty());
+
+        let diff = PackageDiff::compare(&old, &new).unwrap();
+        assert_eq!(diff.tables().len(), 1);
+        let table = &diff.tables()[0];
+        let keys = |rows: &[RowOrigin]| rows.iter().map(|row| row.key().to_string()).collect::<Vec<String>>();
+        assert_eq!(keys(table.added()), ["ALLUSERS"]);
+        assert_eq!(keys(table.removed()), ["ARPNOREPAIR"]);
+        assert_eq!(table.added()[0].table(), "Property");
+        let position = |package: &MsiPackage| package.table("Property").unwrap().rows().position(|row| row.key() == "ProductVersion");
+        assert_eq!(Some(table.modified()[0].old_origin().index()), position(&old));
+        assert_eq!(Some(table.modified()[0].new_origin().index()), position(&new));
+        assert_eq!(table.modified().len(), 1);
+        assert_eq!(table.modified()[0].key(), "ProductVersion");
+        assert_eq!(table.modified()[0].changes()[0].new_value(), &Value::from("2.0"));
+
+        let json = diff.to_json();
+        assert!(json.contains("\"$schema\": \"urn:msi-reader:diff:v1\""));
+        assert!(json.contains("\"added\": [\"ALLUSERS\"]"));
+        assert!(json.contains("{ \"key\": \"ProductVersion\", \"changes\": [{ \"column\": \"Value\", \"old\": \"1.0\", \"new\": \"2.0\" }] }"));
+    }
+}
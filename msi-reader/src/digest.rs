@@ -0,0 +1,191 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use sha2::{ Digest as _, Sha256 };
+
+use crate::cabinet::Cabinet;
+use crate::error::Result;
+use crate::package::MsiPackage;
+use crate::streamname;
+
+#[doc = "A SHA-256 digest of a stream or file."]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Digest([u8; 32]);
+
+impl Digest {
+
+    #[doc = "Computes the digest of the given data."]
+    pub fn of(data: &[u8]) -> Digest {
+        Digest(Sha256::digest(data).into())
+    }
+
+    pub(crate) fn from_bytes(bytes: [u8; 32]) -> Digest {
+        Digest(bytes)
+    }
+
+    #[doc = "Returns the raw digest bytes."]
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl Display for Digest {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for byte in &self.0
+        {
+            write!(fmt, "{:02x}", byte)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[doc = "The digest and size of a single stream or cabinet member."]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ContentDigest {
+    digest: Digest,
+    size: u64
+}
+
+impl ContentDigest {
+
+    fn of(data: &[u8]) -> ContentDigest
+    {
+        ContentDigest {
+            digest: Digest::of(data),
+            size: data.len() as u64
+        }
+    }
+
+    #[doc = "Returns the SHA-256 digest of the content."]
+    pub fn digest(&self) -> Digest {
+        self.digest
+    }
+
+    #[doc = "Returns the size of the content in bytes."]
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+}
+
+#[doc = "Digests of every stream of a package and every member of its embedded cabinets, computed in a single pass so that verification, deduplication, diffing and SBOM generation do not have to re-read payloads."]
+#[derive(Clone, Debug, Default)]
+pub struct ContentDigests {
+    tables: BTreeMap<String, ContentDigest>,
+    streams: BTreeMap<String, ContentDigest>,
+    cabinet_files: BTreeMap<(String, String), ContentDigest>
+}
+
+impl ContentDigests {
+
+    pub(crate) fn compute(package: &MsiPackage) -> Result<ContentDigests>
+    {
+        let mut digests = ContentDigests::default();
+        for raw_name in package.raw_stream_names()?
+        {
+            let data = package.read_raw_stream(&raw_name)?;
+            let (name, is_table) = streamname::decode(&raw_name);
+            if is_table
+            {
+                digests.tables.insert(name, ContentDigest::of(&data));
+                continue;
+            }
+
+            digests.streams.insert(name.clone(), ContentDigest::of(&data));
+            if Cabinet::is_cabinet(&data)
+            {
+                digests.add_cabinet(&name, Cabinet::parse(data)?);
+            }
+        }
+
+        Ok(digests)
+    }
+
+    fn add_cabinet(&mut self, name: &str, cabinet: Cabinet)
+    {
+        // each folder is decompressed once; members of folders that cannot be read are left out
+        for (index, _) in cabinet.folders().iter().enumerate()
+        {
+            let folder = match cabinet.read_folder(index)
+            {
+                Ok(folder) => folder,
+                Err(_) => continue
+            };
+
+            for file in cabinet.files().iter().filter(|file| file.folder() as usize == index)
+            {
+                let start = file.folder_offset() as usize;
+                if let Some(data) = folder.get(start..start.saturating_add(file.size() as usize))
+                {
+                    self.cabinet_files.insert((name.to_string(), file.name().to_string()), ContentDigest::of(data));
+                }
+            }
+        }
+    }
+
+    #[doc = "Returns the digests of the table streams, keyed by table name."]
+    pub fn tables(&self) -> &BTreeMap<String, ContentDigest> {
+        &self.tables
+    }
+
+    #[doc = "Returns the digests of all other streams, such as Binary, Icon and cabinet streams, keyed by decoded stream name."]
+    pub fn streams(&self) -> &BTreeMap<String, ContentDigest> {
+        &self.streams
+    }
+
+    #[doc = "Returns the digests of the members of embedded cabinets, keyed by cabinet stream name and member name."]
+    pub fn cabinet_files(&self) -> &BTreeMap<(String, String), ContentDigest> {
+        &self.cabinet_files
+    }
+
+    #[doc = "Returns the digest of the non-table stream with the given name."]
+    pub fn stream(&self, name: &str) -> Option<&ContentDigest> {
+        self.streams.get(name)
+    }
+
+    #[doc = "Returns the digest of a member of an embedded cabinet."]
+    pub fn cabinet_file(&self, cabinet: &str, file: &str) -> Option<&ContentDigest> {
+        self.cabinet_files.get(&(cabinet.to_string(), file.to_string()))
+    }
+
+    #[doc = "Groups non-table streams and cabinet members with identical content. Cabinet members are named `cabinet/member`. Only groups of two or more entries are returned."]
+    pub fn duplicates(&self) -> Vec<(ContentDigest, Vec<String>)>
+    {
+        let mut groups: BTreeMap<Digest, (ContentDigest, Vec<String>)> = BTreeMap::new();
+        let entries = self.streams.iter().map(|(name, digest)| (name.clone(), digest))
+            .chain(self.cabinet_files.iter().map(|((cabinet, file), digest)| (format!("{}/{}", cabinet, file), digest)));
+        for (name, digest) in entries
+        {
+            groups.entry(digest.digest).or_insert_with(|| (*digest, Vec::new())).1.push(name);
+        }
+
+        groups.into_values().filter(|(_, names)| names.len() > 1).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn test_digest()
+    {
+        assert_eq!(Digest::of(b"abc").to_string(), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+    }
+
+    #[test]
+    fn test_duplicates()
+    {
+        let mut digests = ContentDigests::default();
+        digests.streams.insert("Binary.Logo".to_string(), ContentDigest::of(b"image"));
+        digests.streams.insert("Icon.App".to_string(), ContentDigest::of(b"icon"));
+        digests.add_cabinet("cab1.cab", Cabinet::parse(crate::cabinet::tests::build_cab(&[("logo.bmp", b"image"), ("readme.txt", b"text")])).unwrap());
+
+        assert_eq!(digests.cabinet_file("cab1.cab", "readme.txt").unwrap().size(), 4);
+        let duplicates = digests.duplicates();
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].1, ["Binary.Logo", "cab1.cab/logo.bmp"]);
+        assert_eq!(duplicates[0].0.digest(), Digest::of(b"image"));
+    }
+}
@@ -0,0 +1,150 @@
+use std::fmt::Display;
+use std::path::Path;
+
+use crate::error::{ Error, Result };
+use crate::package::MsiPackage;
+use crate::summary::PropertyValue;
+use crate::table::Value;
+use crate::writer;
+
+#[doc = "A change made in place to a package by `apply_edits`."]
+#[derive(Clone, Debug, PartialEq)]
+pub enum PackageEdit {
+    #[doc = "Sets a property in the Property table, adding the row (and the table) if needed."]
+    SetProperty { name: String, value: String },
+    #[doc = "Deletes the row of a table with the given primary key, multiple key columns joined by '.'."]
+    DeleteRow { table: String, key: String },
+    #[doc = "Sets a property of the summary information stream, such as `PID_TEMPLATE`."]
+    SetSummary { id: u32, value: PropertyValue },
+    #[doc = "Gives the package a new random package code."]
+    RegeneratePackageCode
+}
+
+impl Display for PackageEdit {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self
+        {
+            PackageEdit::SetProperty { name, value } => write!(fmt, "set property {}={}", name, value),
+            PackageEdit::DeleteRow { table, key } => write!(fmt, "delete {} [{}]", table, key),
+            PackageEdit::SetSummary { id, value } => write!(fmt, "set summary property {}={}", id, value),
+            PackageEdit::RegeneratePackageCode => write!(fmt, "regenerate package code")
+        }
+    }
+}
+
+#[doc = "Applies the edits to the package at `path` in order and commits them. Nothing is written if an edit fails. An existing digital signature no longer matches the edited package."]
+pub fn apply_edits<P: AsRef<Path>>(path: P, edits: &[PackageEdit]) -> Result<()>
+{
+    // rows are located with the reader, which knows the catalog, before anything is changed
+    let package = MsiPackage::open(&path)?;
+    let mut deletions = Vec::new();
+    for edit in edits
+    {
+        if let PackageEdit::DeleteRow { table: name, key } = edit
+        {
+            let table = package.table(name)?;
+            let row = table.rows().find(|row| row.key() == *key)
+                .ok_or_else(|| Error::NotFound(format!("row [{}] in table '{}'", key, name)))?;
+            let keys: Vec<Value> = table.columns().iter().zip(row.values())
+                .filter(|(column, _)| column.is_primary_key())
+                .map(|(_, value)| value.clone())
+                .collect();
+            deletions.push((table.columns().to_vec(), keys));
+        }
+    }
+    let has_property_table = package.has_table("Property");
+    let mut summary = package.summary().clone();
+    drop(package);
+
+    let mut writer = writer::open(&path)?;
+    let mut deletions = deletions.into_iter();
+    let mut created = false;
+    for edit in edits
+    {
+        match edit
+        {
+            PackageEdit::SetProperty { name, value } => {
+                if !has_property_table && !created
+                {
+                    writer.create_table("Property", vec![
+                        msi::Column::build("Property").primary_key().id_string(72),
+                        msi::Column::build("Value").localizable().text_string(0)
+                    ])?;
+                    created = true;
+                }
+
+                let condition = || msi::Expr::col("Property").eq(msi::Expr::string(name.as_str()));
+                if writer.select_rows(msi::Select::table("Property").with(condition()))?.next().is_some()
+                {
+                    writer.update_rows(msi::Update::table("Property").set("Value", msi::Value::from(value.as_str())).with(condition()))?;
+                }
+                else
+                {
+                    writer.insert_rows(msi::Insert::into("Property").row(vec![msi::Value::from(name.as_str()), msi::Value::from(value.as_str())]))?;
+                }
+            },
+            PackageEdit::DeleteRow { table, key } => {
+                let (columns, keys) = deletions.next()
+                    .ok_or_else(|| Error::invalid("deletions out of step with the edits"))?;
+                let key_columns = columns.iter().filter(|column| column.is_primary_key());
+                writer::delete_row(&mut writer, table, &columns, key_columns.zip(&keys), key)?;
+            },
+            PackageEdit::SetSummary { id, value } => summary.set(*id, value.clone()),
+            PackageEdit::RegeneratePackageCode => {
+                summary.regenerate_package_code();
+            }
+        }
+    }
+
+    writer.flush()?;
+    drop(writer);
+
+    // the writer leaves the summary information alone, so it is replaced as a whole afterwards
+    if edits.iter().any(|edit| matches!(edit, PackageEdit::SetSummary { .. } | PackageEdit::RegeneratePackageCode))
+    {
+        summary.write(&path)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::summary::PID_TEMPLATE;
+    use crate::testutil::TestPackage;
+
+    #[test]
+    fn test_apply_edits()
+    {
+        let package = TestPackage::new("edit", |builder| {
+            builder.table("Property", vec![
+                msi::Column::build("Property").primary_key().id_string(72),
+                msi::Column::build("Value").localizable().text_string(0)
+            ], vec![
+                vec![msi::Value::from("ProductVersion"), msi::Value::from("1.0")],
+                vec![msi::Value::from("ARPNOREPAIR"), msi::Value::from("1")]
+            ]);
+        });
+
+        apply_edits(package.path(), &[
+            PackageEdit::SetProperty { name: "ProductVersion".to_string(), value: "1.1".to_string() },
+            PackageEdit::SetProperty { name: "ALLUSERS".to_string(), value: "1".to_string() },
+            PackageEdit::DeleteRow { table: "Property".to_string(), key: "ARPNOREPAIR".to_string() },
+            PackageEdit::SetSummary { id: PID_TEMPLATE, value: PropertyValue::Str("x64;1033".to_string()) },
+            PackageEdit::RegeneratePackageCode
+        ]).unwrap();
+
+        let edited = MsiPackage::open(package.path()).unwrap();
+        assert_eq!(edited.property("ProductVersion").unwrap().as_deref(), Some("1.1"));
+        assert_eq!(edited.property("ALLUSERS").unwrap().as_deref(), Some("1"));
+        assert_eq!(edited.property("ARPNOREPAIR").unwrap(), None);
+        assert_eq!(edited.summary().template(), Some("x64;1033"));
+        assert_eq!(edited.summary().title(), Some("Installation Database"));
+        assert!(edited.summary().package_code().is_some());
+
+        let missing = PackageEdit::DeleteRow { table: "Property".to_string(), key: "Missing".to_string() };
+        assert!(apply_edits(package.path(), &[missing]).is_err());
+    }
+}
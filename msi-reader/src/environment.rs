@@ -0,0 +1,200 @@
+use std::fmt::Display;
+
+use crate::error::Result;
+use crate::package::MsiPackage;
+use crate::table::RowOrigin;
+
+const VALUE_SEPARATOR: &str = "[~]";
+
+#[doc = "What happens to an environment variable when its component is installed."]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EnvironmentAction {
+    #[doc = "The variable is created or overwritten (`=`, or no action prefix)."]
+    Set,
+    #[doc = "The variable is created only if it does not exist yet (`+`)."]
+    SetIfAbsent,
+    #[doc = "The variable is removed (`!`); with a value, only that value is removed from it."]
+    Remove
+}
+
+impl Display for EnvironmentAction {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self
+        {
+            EnvironmentAction::Set => write!(fmt, "set"),
+            EnvironmentAction::SetIfAbsent => write!(fmt, "set if absent"),
+            EnvironmentAction::Remove => write!(fmt, "remove")
+        }
+    }
+}
+
+#[doc = "Whose environment a variable is written to."]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EnvironmentScope {
+    #[doc = "The environment of the installing user."]
+    User,
+    #[doc = "The system environment, selected by the `*` prefix."]
+    Machine
+}
+
+#[doc = "How the value combines with the current value of the variable."]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ValueMode {
+    #[doc = "The value replaces the current value."]
+    Replace,
+    #[doc = "The value is appended to the current value (`[~];value`)."]
+    Append,
+    #[doc = "The value is prepended to the current value (`value;[~]`)."]
+    Prepend
+}
+
+#[doc = "A row of the Environment table with the prefix characters of its Name decoded."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct EnvironmentRow {
+    environment: String,
+    name: String,
+    action: EnvironmentAction,
+    remove_on_uninstall: bool,
+    scope: EnvironmentScope,
+    value: Option<String>,
+    component: String,
+    origin: RowOrigin
+}
+
+impl EnvironmentRow {
+
+    #[doc = "Returns the primary key of the row."]
+    pub fn environment(&self) -> &str {
+        &self.environment
+    }
+
+    #[doc = "Returns the name of the variable without its prefix characters."]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[doc = "Returns what happens to the variable on install."]
+    pub fn action(&self) -> EnvironmentAction {
+        self.action
+    }
+
+    #[doc = "Returns a boolean value indicating whether the variable, or the value added to it, is removed on uninstall (`-`, or no action prefix)."]
+    pub fn removes_on_uninstall(&self) -> bool {
+        self.remove_on_uninstall
+    }
+
+    #[doc = "Returns whose environment the variable is written to."]
+    pub fn scope(&self) -> EnvironmentScope {
+        self.scope
+    }
+
+    #[doc = "Returns the value without the `[~]` marker, including the separator next to it, e.g. `;[INSTALLDIR]bin`."]
+    pub fn value(&self) -> Option<&str> {
+        self.value.as_deref().map(|value| value.strip_prefix(VALUE_SEPARATOR).or_else(|| value.strip_suffix(VALUE_SEPARATOR)).unwrap_or(value))
+    }
+
+    #[doc = "Returns how the value combines with the current value of the variable."]
+    pub fn mode(&self) -> ValueMode {
+        match self.value.as_deref()
+        {
+            Some(value) if value.len() > VALUE_SEPARATOR.len() && value.starts_with(VALUE_SEPARATOR) => ValueMode::Append,
+            Some(value) if value.len() > VALUE_SEPARATOR.len() && value.ends_with(VALUE_SEPARATOR) => ValueMode::Prepend,
+            _ => ValueMode::Replace
+        }
+    }
+
+    #[doc = "Returns the component that writes the variable."]
+    pub fn component(&self) -> &str {
+        &self.component
+    }
+
+    #[doc = "Returns the Environment row the variable was read from."]
+    pub fn origin(&self) -> &RowOrigin {
+        &self.origin
+    }
+}
+
+#[doc = "Reads the Environment table of the package, in storage order; a package without one yields no rows."]
+pub fn read_environment(package: &MsiPackage) -> Result<Vec<EnvironmentRow>>
+{
+    let table = match package.optional_table("Environment")?
+    {
+        Some(table) => table,
+        None => return Ok(Vec::new())
+    };
+
+    Ok(table.rows()
+        .map(|row| {
+            let raw_name = row.str("Name").unwrap_or_default();
+            let name = raw_name.trim_start_matches(['=', '+', '-', '!', '*']);
+            let prefix = &raw_name[..raw_name.len() - name.len()];
+            let action = if prefix.contains('!')
+            {
+                EnvironmentAction::Remove
+            }
+            else if prefix.contains('+')
+            {
+                EnvironmentAction::SetIfAbsent
+            }
+            else
+            {
+                EnvironmentAction::Set
+            };
+
+            EnvironmentRow {
+                environment: row.str("Environment").unwrap_or_default().to_string(),
+                name: name.to_string(),
+                action,
+                remove_on_uninstall: prefix.contains('-') || !prefix.contains(['=', '+', '!']),
+                scope: if prefix.contains('*') { EnvironmentScope::Machine } else { EnvironmentScope::User },
+                value: row.str("Value").filter(|value| !value.is_empty()).map(|value| value.to_string()),
+                component: row.str("Component_").unwrap_or_default().to_string(),
+                origin: row.origin()
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::testutil::TestPackage;
+
+    #[test]
+    fn test_read_environment()
+    {
+        let package = TestPackage::new("environment-table", |builder| {
+            builder.table("Environment", vec![
+                msi::Column::build("Environment").primary_key().id_string(72),
+                msi::Column::build("Name").localizable().text_string(255),
+                msi::Column::build("Value").nullable().localizable().formatted_string(255),
+                msi::Column::build("Component_").id_string(72)
+            ], vec![
+                vec![msi::Value::from("Path"), msi::Value::from("=-*PATH"), msi::Value::from("[~];[INSTALLDIR]bin"), msi::Value::from("Main")],
+                vec![msi::Value::from("Home"), msi::Value::from("+CONTOSO_HOME"), msi::Value::from("[INSTALLDIR]"), msi::Value::from("Main")],
+                vec![msi::Value::from("Old"), msi::Value::from("!LEGACY"), msi::Value::Null, msi::Value::from("Main")],
+                vec![msi::Value::from("Plain"), msi::Value::from("CONTOSO_MODE"), msi::Value::from("[MODE];[~]"), msi::Value::from("Main")]
+            ]);
+        });
+
+        let rows = read_environment(&MsiPackage::open(package.path()).unwrap()).unwrap();
+        let row = |key: &str| rows.iter().find(|row| row.environment() == key).unwrap();
+
+        let path = row("Path");
+        assert_eq!((path.name(), path.action(), path.scope()), ("PATH", EnvironmentAction::Set, EnvironmentScope::Machine));
+        assert_eq!((path.mode(), path.value()), (ValueMode::Append, Some(";[INSTALLDIR]bin")));
+        assert!(path.removes_on_uninstall());
+
+        let home = row("Home");
+        assert_eq!((home.action(), home.scope(), home.mode()), (EnvironmentAction::SetIfAbsent, EnvironmentScope::User, ValueMode::Replace));
+        assert!(!home.removes_on_uninstall());
+
+        let old = row("Old");
+        assert_eq!((old.name(), old.action(), old.value()), ("LEGACY", EnvironmentAction::Remove, None));
+
+        let plain = row("Plain");
+        assert_eq!((plain.action(), plain.mode(), plain.value()), (EnvironmentAction::Set, ValueMode::Prepend, Some("[MODE];")));
+        assert!(plain.removes_on_uninstall());
+    }
+}
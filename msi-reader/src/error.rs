@@ -0,0 +1,53 @@
+use std::fmt::Display;
+use std::io;
+
+#[doc = "Errors produced while reading Windows Installer packages and their contents."]
+#[derive(Debug)]
+pub enum Error {
+    #[doc = "The underlying file or stream could not be read."]
+    Io(io::Error),
+    #[doc = "The data was read but does not follow the expected format."]
+    InvalidData(String),
+    #[doc = "A requested table, stream or row does not exist."]
+    NotFound(String)
+}
+
+#[doc = "A result type whose error is the crate's `Error`."]
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    pub(crate) fn invalid<S: Into<String>>(message: S) -> Self
+    {
+        Error::InvalidData(message.into())
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self
+    {
+        Error::Io(error)
+    }
+}
+
+impl Display for Error
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self
+        {
+            Error::Io(error) => write!(f, "I/O error: {}", error),
+            Error::InvalidData(message) => write!(f, "invalid data: {}", message),
+            Error::NotFound(what) => write!(f, "{} not found", what)
+        }
+    }
+}
+
+impl std::error::Error for Error
+{
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self
+        {
+            Error::Io(error) => Some(error),
+            _ => None
+        }
+    }
+}
@@ -0,0 +1,505 @@
+use std::collections::{ BTreeSet, HashMap, HashSet };
+use std::fmt::Write;
+use std::io;
+
+use crate::directory::{ MsiDirectoryName, NameFormat };
+use crate::error::Result;
+use crate::json;
+use crate::layout::DirectoryResolver;
+use crate::package::MsiPackage;
+use crate::schema;
+use crate::sequence::read_actions;
+use crate::summary::PropertyValue;
+use crate::table::{ ColumnType, Row, Table, Value };
+
+const NULL_MARKER: &str = "<null>";
+
+#[doc = "The order in which exports and reports list rows and findings. Tables are always listed by name."]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum OutputOrder {
+    #[doc = "Rows in storage order and findings in the order they are found."]
+    #[default]
+    Stored,
+    #[doc = "Rows sorted by primary key and findings by rule, location and message, so the output only depends on the content of the package and is identical across runs, tools and platforms."]
+    Sorted
+}
+
+#[doc = "How the database export writes string cells whose bytes cannot be decoded, as found in packages authored in an ANSI codepage."]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TextMode {
+    #[doc = "Undecodable bytes read as U+FFFD, as everywhere else in the library."]
+    #[default]
+    Replaced,
+    #[doc = "Undecodable bytes are written as `\\xNN` and backslashes in the affected cells are doubled, so the original bytes can be restored; the affected cells are listed under `escapedCells`."]
+    Escaped
+}
+
+impl OutputOrder {
+
+    pub(crate) fn rows<'a>(&self, table: &'a Table) -> Vec<Row<'a>>
+    {
+        match self
+        {
+            OutputOrder::Stored => table.rows().collect(),
+            OutputOrder::Sorted => table.sorted_rows()
+        }
+    }
+}
+
+#[doc = "Renders the features, components and their mapping as a Graphviz DOT graph. Features are boxes labelled with their title, linked to their parent feature; components are ellipses linked from every feature that installs them through FeatureComponents."]
+pub fn dot_features(package: &MsiPackage, order: OutputOrder) -> Result<String>
+{
+    let mut dot = String::from("digraph features {\n    rankdir=LR;\n");
+    let mut components = BTreeSet::new();
+    let mut edges = Vec::new();
+
+    if let Some(table) = package.optional_table("Feature")?
+    {
+        for row in order.rows(&table)
+        {
+            let feature = match row.str("Feature")
+            {
+                Some(feature) => feature,
+                None => continue
+            };
+            let label = match row.str("Title").filter(|title| !title.is_empty() && *title != feature)
+            {
+                Some(title) => format!("{}\n{}", title, feature),
+                None => feature.to_string()
+            };
+            let _ = writeln!(dot, "    {} [shape=box, label={}];", node("feature", feature), json::string(&label));
+            if let Some(parent) = row.str("Feature_Parent").filter(|parent| !parent.is_empty() && *parent != feature)
+            {
+                edges.push(format!("    {} -> {};", node("feature", parent), node("feature", feature)));
+            }
+        }
+    }
+
+    if let Some(table) = package.optional_table("FeatureComponents")?
+    {
+        for row in order.rows(&table)
+        {
+            if let (Some(feature), Some(component)) = (row.str("Feature_"), row.str("Component_"))
+            {
+                components.insert(component.to_string());
+                edges.push(format!("    {} -> {} [style=dashed];", node("feature", feature), node("component", component)));
+            }
+        }
+    }
+
+    for component in &components
+    {
+        let _ = writeln!(dot, "    {} [shape=ellipse, label={}];", node("component", component), json::string(component));
+    }
+    for edge in edges
+    {
+        dot.push_str(&edge);
+        dot.push('\n');
+    }
+
+    dot.push_str("}\n");
+    Ok(dot)
+}
+
+#[doc = "Renders the Directory table as a Graphviz DOT tree. Each directory is labelled with its key and long target name, plus the source name where `DefaultDir` gives a different one; the resolved target path is set as the tooltip."]
+pub fn dot_directories(package: &MsiPackage, order: OutputOrder) -> Result<String>
+{
+    let mut dot = String::from("digraph directories {\n    rankdir=LR;\n    node [shape=folder];\n");
+    let paths = DirectoryResolver::read(package)?.target_paths();
+    let mut edges = Vec::new();
+
+    if let Some(table) = package.optional_table("Directory")?
+    {
+        for row in order.rows(&table)
+        {
+            let directory = match row.str("Directory")
+            {
+                Some(directory) => directory,
+                None => continue
+            };
+            let name = MsiDirectoryName::from(row.str("DefaultDir").unwrap_or("."));
+            let mut label = format!("{}\n{}", directory, name.format(NameFormat::Long));
+            if let Some(source) = name.source().map(|source| source.format(NameFormat::Long)).filter(|source| *source != name.format(NameFormat::Long))
+            {
+                let _ = write!(label, "\nsource: {}", source);
+            }
+
+            let path = paths.get(directory).map(String::as_str).unwrap_or_default();
+            let _ = writeln!(dot, "    {} [label={}, tooltip={}];", json::string(directory), json::string(&label), json::string(path));
+            if let Some(parent) = row.str("Directory_Parent").filter(|parent| !parent.is_empty() && *parent != directory)
+            {
+                edges.push(format!("    {} -> {};", json::string(parent), json::string(directory)));
+            }
+        }
+    }
+
+    for edge in edges
+    {
+        dot.push_str(&edge);
+        dot.push('\n');
+    }
+
+    dot.push_str("}\n");
+    Ok(dot)
+}
+
+#[doc = "Renders a sequence table, such as InstallExecuteSequence, as a Mermaid flowchart in the order the installer runs it. Actions without a positive sequence number, such as exit dialogs, are left out. Custom actions are drawn as hexagons, and conditions label the edge into the action they guard."]
+pub fn mermaid_sequence(package: &MsiPackage, table: &str, order: OutputOrder) -> Result<String>
+{
+    let custom_actions: HashSet<String> = match package.optional_table("CustomAction")?
+    {
+        Some(table) => table.rows().filter_map(|row| row.str("Action").map(str::to_string)).collect(),
+        None => HashSet::new()
+    };
+    let mut actions: Vec<_> = read_actions(package, table)?.into_iter().filter(|action| action.sequence().is_some_and(|sequence| sequence > 0)).collect();
+    match order
+    {
+        OutputOrder::Stored => actions.sort_by_key(|action| action.sequence()),
+        // the installer runs actions with equal numbers in an unspecified order
+        OutputOrder::Sorted => actions.sort_by(|first, second| (first.sequence(), first.action()).cmp(&(second.sequence(), second.action())))
+    }
+
+    let mut mermaid = String::from("flowchart TD\n");
+    for (index, action) in actions.iter().enumerate()
+    {
+        let label = format!("\"{}<br/>{}\"", mermaid_escape(action.action()), action.sequence().unwrap_or_default());
+        if custom_actions.contains(action.action())
+        {
+            let _ = writeln!(mermaid, "    a{}{{{{{}}}}}:::custom", index, label);
+        }
+        else
+        {
+            let _ = writeln!(mermaid, "    a{}[{}]", index, label);
+        }
+
+        if index > 0
+        {
+            match action.condition()
+            {
+                Some(condition) => { let _ = writeln!(mermaid, "    a{} -->|\"{}\"| a{}", index - 1, mermaid_escape(condition), index); },
+                None => { let _ = writeln!(mermaid, "    a{} --> a{}", index - 1, index); }
+            }
+        }
+    }
+
+    mermaid.push_str("    classDef custom fill:#fde8c8,stroke:#c77700\n");
+    Ok(mermaid)
+}
+
+// Mermaid labels are quoted; quotes and angle brackets are written as entity codes.
+fn mermaid_escape(text: &str) -> String
+{
+    text.replace('#', "#35;").replace('"', "#quot;").replace('<', "#lt;").replace('>', "#gt;")
+}
+
+#[doc = "Renders every table of the package, with its columns and rows, and the summary information as a JSON document following `schema::DATABASE`. The output is always valid UTF-8; `text` selects how cells with undecodable bytes are written."]
+pub fn json_database(package: &MsiPackage, order: OutputOrder, text: TextMode) -> Result<String>
+{
+    let mut output = Vec::new();
+    write_json_database(package, order, text, &mut output)?;
+    Ok(String::from_utf8(output).unwrap_or_default())
+}
+
+#[doc = "Writes the same document as `json_database` to `output` one table at a time, so that only a single table is held in memory."]
+pub fn write_json_database<W: io::Write>(package: &MsiPackage, order: OutputOrder, text: TextMode, output: &mut W) -> Result<()>
+{
+    write!(output, "{{\n  \"$schema\": {},\n  \"tables\": [", json::string(schema::DATABASE.id()))?;
+    let mut escaped_cells = Vec::new();
+    for (index, name) in package.table_names().enumerate()
+    {
+        let table = package.table(name)?;
+        let columns: Vec<String> = table.columns().iter().map(|column| {
+            let (column_type, size) = match column.column_type()
+            {
+                ColumnType::Int16 => ("int16", None),
+                ColumnType::Int32 => ("int32", None),
+                ColumnType::Str(size) => ("string", Some(size)),
+                ColumnType::Binary => ("binary", None)
+            };
+            format!("{{ \"name\": {}, \"type\": \"{}\", {}\"nullable\": {}, \"primaryKey\": {}, \"localizable\": {} }}",
+                json::string(column.name()), column_type, size.map(|size| format!("\"size\": {}, ", size)).unwrap_or_default(),
+                column.is_nullable(), column.is_primary_key(), column.is_localizable())
+        }).collect();
+
+        let escaped: HashMap<(usize, usize), &str> = match text
+        {
+            TextMode::Replaced => HashMap::new(),
+            TextMode::Escaped => table.escaped_cells().iter().map(|cell| ((cell.row(), cell.column()), cell.text())).collect()
+        };
+        let mut rows = Vec::new();
+        for row in order.rows(&table)
+        {
+            rows.push(json::array(row.values().iter().enumerate().map(|(column, value)| match escaped.get(&(row.index(), column))
+            {
+                Some(text) => {
+                    escaped_cells.push(format!("{{ \"table\": {}, \"row\": {}, \"column\": {} }}",
+                        json::string(table.name()), json::string(&row.key()), json::string(table.columns()[column].name())));
+                    json::string(text)
+                },
+                None => json::value(value)
+            })));
+        }
+
+        write!(output, "{}\n    {{\n      \"name\": {},\n      \"columns\": [\n        {}\n      ],\n      \"rows\": [{}]\n    }}",
+            if index == 0 { "" } else { "," }, json::string(table.name()), columns.join(",\n        "),
+            if rows.is_empty() { String::new() } else { format!("\n        {}\n      ", rows.join(",\n        ")) })?;
+    }
+    write!(output, "{}]", if package.table_names().next().is_some() { "\n  " } else { "" })?;
+
+    let summary: Vec<String> = package.summary().properties().map(|(id, value)| {
+        let (value_type, value) = match value
+        {
+            PropertyValue::Empty => ("empty", "null".to_string()),
+            PropertyValue::Null => ("null", "null".to_string()),
+            PropertyValue::I2(value) => ("int16", value.to_string()),
+            PropertyValue::I4(value) => ("int32", value.to_string()),
+            PropertyValue::Str(value) => ("string", json::string(value)),
+            PropertyValue::FileTime(value) => ("filetime", value.to_string())
+        };
+        format!("{{ \"id\": {}, \"type\": \"{}\", \"value\": {} }}", id, value_type, value)
+    }).collect();
+    write!(output, ",\n  \"summary\": [{}]", if summary.is_empty() { String::new() } else { format!("\n    {}\n  ", summary.join(",\n    ")) })?;
+
+    match text
+    {
+        TextMode::Replaced => {},
+        TextMode::Escaped if escaped_cells.is_empty() => write!(output, ",\n  \"escapedCells\": []")?,
+        TextMode::Escaped => write!(output, ",\n  \"escapedCells\": [\n    {}\n  ]", escaped_cells.join(",\n    "))?
+    }
+    writeln!(output, "\n}}")?;
+    Ok(())
+}
+
+#[doc = "Renders the rows of a table as a JSON array of objects mapping column names to cells: null, a number for integer columns or a string. Binary cells hold the name of their stream."]
+pub fn table_json(table: &Table, order: OutputOrder) -> String
+{
+    let rows: Vec<String> = order.rows(table).iter().map(|row| {
+        let cells: Vec<String> = table.columns().iter().zip(row.values())
+            .map(|(column, value)| format!("{}: {}", json::string(column.name()), json::value(value)))
+            .collect();
+        format!("  {{ {} }}", cells.join(", "))
+    }).collect();
+
+    if rows.is_empty() { "[]\n".to_string() } else { format!("[\n{}\n]\n", rows.join(",\n")) }
+}
+
+#[doc = "Renders a table as text with a header and one line per row, in columns padded to their widest cell. Integers are right-aligned and null cells written as `<null>`; line breaks and tabs in strings are written as `\\n`, `\\r` and `\\t` so every row stays on one line."]
+pub fn table_text(table: &Table, order: OutputOrder) -> String
+{
+    let numeric: Vec<bool> = table.columns().iter().map(|column| matches!(column.column_type(), ColumnType::Int16 | ColumnType::Int32)).collect();
+    let rows: Vec<Vec<String>> = order.rows(table).iter()
+        .map(|row| row.values().iter().map(|value| match value
+        {
+            Value::Null => NULL_MARKER.to_string(),
+            Value::Int(value) => value.to_string(),
+            Value::Str(text) => text.replace('\n', "\\n").replace('\r', "\\r").replace('\t', "\\t")
+        }).collect())
+        .collect();
+
+    let header: Vec<String> = table.columns().iter().map(|column| column.name().to_string()).collect();
+    let widths: Vec<usize> = (0..header.len())
+        .map(|column| rows.iter().map(|row| row[column].chars().count()).chain(Some(header[column].chars().count())).max().unwrap_or_default())
+        .collect();
+    let line = |cells: &[String]| {
+        let padded: Vec<String> = cells.iter().enumerate()
+            .map(|(column, cell)| if numeric[column] { format!("{:>1$}", cell, widths[column]) } else { format!("{:<1$}", cell, widths[column]) })
+            .collect();
+        format!("{}\n", padded.join("  ").trim_end())
+    };
+
+    let mut text = line(&header);
+    text.push_str(&line(&widths.iter().map(|width| "-".repeat(*width)).collect::<Vec<_>>()));
+    for row in &rows
+    {
+        text.push_str(&line(row));
+    }
+
+    text
+}
+
+// Features and components have separate key spaces, so node ids carry the kind.
+fn node(kind: &str, key: &str) -> String
+{
+    json::string(&format!("{}:{}", kind, key))
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::testutil::TestPackage;
+
+    #[test]
+    fn test_dot_features()
+    {
+        let package = TestPackage::new("export-features", |builder| {
+            builder.table("Feature", vec![
+                msi::Column::build("Feature").primary_key().id_string(38),
+                msi::Column::build("Feature_Parent").nullable().id_string(38),
+                msi::Column::build("Title").nullable().localizable().text_string(64)
+            ], vec![
+                vec![msi::Value::from("Complete"), msi::Value::Null, msi::Value::from("Contoso \"App\"")],
+                vec![msi::Value::from("Docs"), msi::Value::from("Complete"), msi::Value::Null]
+            ]);
+            builder.table("FeatureComponents", vec![
+                msi::Column::build("Feature_").primary_key().id_string(38),
+                msi::Column::build("Component_").primary_key().id_string(72)
+            ], vec![
+                vec![msi::Value::from("Complete"), msi::Value::from("Main")],
+                vec![msi::Value::from("Docs"), msi::Value::from("Main")]
+            ]);
+        });
+
+        let dot = dot_features(&MsiPackage::open(package.path()).unwrap(), OutputOrder::Stored).unwrap();
+        assert!(dot.starts_with("digraph features {"));
+        assert!(dot.contains("\"feature:Complete\" [shape=box, label=\"Contoso \\\"App\\\"\\nComplete\"];"));
+        assert!(dot.contains("\"feature:Complete\" -> \"feature:Docs\";"));
+        assert!(dot.contains("\"feature:Docs\" -> \"component:Main\" [style=dashed];"));
+        assert_eq!(dot.matches("[shape=ellipse").count(), 1);
+    }
+
+    #[test]
+    fn test_dot_directories()
+    {
+        let package = TestPackage::new("export-directories", |builder| {
+            builder.table("Directory", vec![
+                msi::Column::build("Directory").primary_key().id_string(72),
+                msi::Column::build("Directory_Parent").nullable().id_string(72),
+                msi::Column::build("DefaultDir").text_string(255)
+            ], vec![
+                vec![msi::Value::from("TARGETDIR"), msi::Value::Null, msi::Value::from("SourceDir")],
+                vec![msi::Value::from("ProgramFilesFolder"), msi::Value::from("TARGETDIR"), msi::Value::from(".")],
+                vec![msi::Value::from("INSTALLDIR"), msi::Value::from("ProgramFilesFolder"), msi::Value::from("APP|Application:CONTOSO|Contoso")]
+            ]);
+        });
+
+        let dot = dot_directories(&MsiPackage::open(package.path()).unwrap(), OutputOrder::Stored).unwrap();
+        assert!(dot.contains("\"INSTALLDIR\" [label=\"INSTALLDIR\\nContoso\\nsource: Application\", tooltip=\"C:\\\\Program Files (x86)\\\\Contoso\\\\\"];"));
+        assert!(dot.contains("\"TARGETDIR\" -> \"ProgramFilesFolder\";"));
+        assert!(!dot.contains("-> \"TARGETDIR\""));
+    }
+
+    #[test]
+    fn test_mermaid_sequence()
+    {
+        let package = TestPackage::new("export-mermaid", |builder| {
+            builder.table("InstallExecuteSequence", vec![
+                msi::Column::build("Action").primary_key().id_string(72),
+                msi::Column::build("Condition").nullable().string(255),
+                msi::Column::build("Sequence").nullable().int16()
+            ], vec![
+                vec![msi::Value::from("InstallFinalize"), msi::Value::Null, msi::Value::Int(6600)],
+                vec![msi::Value::from("SetVersion"), msi::Value::from("VersionNT >= 600 AND NOT \"Installed\""), msi::Value::Int(1500)],
+                vec![msi::Value::from("CostInitialize"), msi::Value::Null, msi::Value::Int(800)],
+                vec![msi::Value::from("FatalError"), msi::Value::Null, msi::Value::Int(-3)]
+            ]);
+            builder.table("CustomAction", vec![
+                msi::Column::build("Action").primary_key().id_string(72),
+                msi::Column::build("Type").int16(),
+                msi::Column::build("Source").nullable().id_string(72),
+                msi::Column::build("Target").nullable().text_string(255)
+            ], vec![
+                vec![msi::Value::from("SetVersion"), msi::Value::Int(51), msi::Value::from("VERSION"), msi::Value::from("1")]
+            ]);
+        });
+
+        let mermaid = mermaid_sequence(&MsiPackage::open(package.path()).unwrap(), "InstallExecuteSequence", OutputOrder::Stored).unwrap();
+        let lines: Vec<&str> = mermaid.lines().collect();
+        assert_eq!(lines[0], "flowchart TD");
+        assert_eq!(lines[1], "    a0[\"CostInitialize<br/>800\"]");
+        assert_eq!(lines[2], "    a1{{\"SetVersion<br/>1500\"}}:::custom");
+        assert_eq!(lines[3], "    a0 -->|\"VersionNT #gt;= 600 AND NOT #quot;Installed#quot;\"| a1");
+        assert_eq!(lines[5], "    a1 --> a2");
+        assert!(!mermaid.contains("FatalError"));
+    }
+
+    #[test]
+    fn test_json_database()
+    {
+        let package = TestPackage::new("export-json", |builder| {
+            builder.table("Property", vec![
+                msi::Column::build("Property").primary_key().id_string(72),
+                msi::Column::build("Value").localizable().text_string(0)
+            ], vec![
+                vec![msi::Value::from("ProductName"), msi::Value::from("Alpha \"Pro\"")],
+                vec![msi::Value::from("ALLUSERS"), msi::Value::from("1")]
+            ]);
+        });
+
+        let sorted = json_database(&MsiPackage::open(package.path()).unwrap(), OutputOrder::Sorted, TextMode::Replaced).unwrap();
+        assert!(sorted.find("ALLUSERS").unwrap() < sorted.find("ProductName").unwrap());
+
+        let json = json_database(&MsiPackage::open(package.path()).unwrap(), OutputOrder::Stored, TextMode::Replaced).unwrap();
+        assert!(json.starts_with("{\n  \"$schema\": \"urn:msi-reader:database:v1\","));
+        assert!(json.contains("{ \"name\": \"Property\", \"type\": \"string\", \"size\": 72, \"nullable\": false, \"primaryKey\": true, \"localizable\": false }"));
+        assert!(json.contains("[\"ProductName\", \"Alpha \\\"Pro\\\"\"]"));
+        assert!(!json.contains("escapedCells"));
+
+        let package = MsiPackage::open(package.path()).unwrap();
+        let mut streamed = Vec::new();
+        package.write_json(&mut streamed).unwrap();
+        assert_eq!((package.to_json().unwrap(), String::from_utf8(streamed).unwrap()), (json.clone(), json.clone()));
+        let document: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let summary = document["summary"].as_array().unwrap();
+        assert!(summary.iter().any(|property| property["id"] == 1 && property["type"] == "int16"), "{}", json);
+    }
+
+    #[test]
+    fn test_json_database_escaped()
+    {
+        let package = TestPackage::new("export-json-ansi", |builder| {
+            builder.codepage(msi::CodePage::Windows1252);
+            builder.table("Property", vec![
+                msi::Column::build("Property").primary_key().id_string(72),
+                msi::Column::build("Value").localizable().text_string(0)
+            ], vec![
+                vec![msi::Value::from("ProductName"), msi::Value::from("Société\\App")],
+                vec![msi::Value::from("ALLUSERS"), msi::Value::from("1")]
+            ]);
+        });
+        let decoded = json_database(&MsiPackage::open(package.path()).unwrap(), OutputOrder::Stored, TextMode::Escaped).unwrap();
+        assert!(decoded.contains("\"Société\\\\App\"") && decoded.contains("\"escapedCells\": []"), "{}", decoded);
+
+        // The same bytes under a UTF-8 header no longer decode.
+        {
+            use std::io::{ Read, Seek, SeekFrom, Write };
+            let mut compound = cfb::open_rw(package.path()).unwrap();
+            let mut stream = compound.open_stream(crate::streamname::encode(crate::package::STRING_POOL_STREAM, true)).unwrap();
+            let mut header = [0u8; 4];
+            stream.read_exact(&mut header).unwrap();
+            let header = crate::stringpool::with_codepage(u32::from_le_bytes(header), 65001);
+            stream.seek(SeekFrom::Start(0)).unwrap();
+            stream.write_all(&header.to_le_bytes()).unwrap();
+        }
+        let package = MsiPackage::open(package.path()).unwrap();
+
+        let replaced = json_database(&package, OutputOrder::Stored, TextMode::Replaced).unwrap();
+        assert!(replaced.contains("\"Soci\u{fffd}t\u{fffd}\\\\App\""));
+
+        let escaped = json_database(&package, OutputOrder::Stored, TextMode::Escaped).unwrap();
+        assert!(escaped.contains("[\"ProductName\", \"Soci\\\\xE9t\\\\xE9\\\\\\\\App\"]"), "{}", escaped);
+        assert!(escaped.contains("\"escapedCells\": [\n    { \"table\": \"Property\", \"row\": \"ProductName\", \"column\": \"Value\" }\n  ]"));
+    }
+
+    #[test]
+    fn test_table_formats()
+    {
+        let package = TestPackage::new("export-table", |builder| {
+            builder.table("File", vec![
+                msi::Column::build("File").primary_key().id_string(72),
+                msi::Column::build("Version").nullable().string(72),
+                msi::Column::build("Sequence").int16()
+            ], vec![
+                vec![msi::Value::from("b.dll"), msi::Value::from("1.0\tbeta"), msi::Value::Int(2)],
+                vec![msi::Value::from("a.exe"), msi::Value::Null, msi::Value::Int(10)]
+            ]);
+        });
+        let table = MsiPackage::open(package.path()).unwrap().table("File").unwrap();
+
+        assert_eq!(table_text(&table, OutputOrder::Stored), "File   Version    Sequence\n-----  ---------  --------\na.exe  <null>           10\nb.dll  1.0\\tbeta         2\n");
+        let json = table_json(&table, OutputOrder::Sorted);
+        assert_eq!(json, "[\n  { \"File\": \"a.exe\", \"Version\": null, \"Sequence\": 10 },\n  { \"File\": \"b.dll\", \"Version\": \"1.0\\tbeta\", \"Sequence\": 2 }\n]\n");
+        let rows: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(rows[1]["Version"], "1.0\tbeta");
+    }
+}
@@ -0,0 +1,386 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{ self, Read, Write };
+use std::path::{ Path, PathBuf };
+
+use crate::cabinet::{ CabFile, CabinetSet, FolderReader };
+use crate::error::{ Error, Result };
+use crate::file::{ FileRow, FileTable };
+use crate::layout::DirectoryResolver;
+use crate::media::{ MediaCabinet, MediaTable };
+use crate::package::MsiPackage;
+
+#[doc = "A file written by `extract_all`."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExtractedFile {
+    file: String,
+    path: PathBuf,
+    size: u64
+}
+
+impl ExtractedFile {
+
+    #[doc = "Returns the File table key of the file."]
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    #[doc = "Returns the path the file was written to."]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    #[doc = "Returns the number of bytes written."]
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+}
+
+#[doc = "Reads the contents of a file of the package, decompressing its cabinet folder as it is read. Returned by `open_file`."]
+pub struct FileReader {
+    source: FileSource
+}
+
+enum FileSource {
+    Cabinet(io::Take<FolderReader<'static>>),
+    Image(File)
+}
+
+impl Read for FileReader {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        match &mut self.source
+        {
+            FileSource::Cabinet(reader) => reader.read(buffer),
+            FileSource::Image(file) => file.read(buffer)
+        }
+    }
+}
+
+// The files of a package in sequence order, with the media and directories that locate them.
+struct Layout {
+    files: Vec<FileRow>,
+    media: MediaTable,
+    // lays out the source image as the package describes it
+    image: DirectoryResolver,
+    // lays out the extracted files with long names
+    long: DirectoryResolver,
+    compressed: bool
+}
+
+impl Layout {
+
+    fn read(package: &MsiPackage) -> Result<Layout>
+    {
+        let mut files = FileTable::read(package)?.rows().to_vec();
+        files.sort_by_key(|file| file.sequence());
+        let image = DirectoryResolver::read(package)?;
+        let mut long = image.clone();
+        long.set_short_source_names(false);
+
+        Ok(Layout {
+            files,
+            media: MediaTable::read(package)?,
+            image,
+            long,
+            compressed: package.summary().word_count().is_some_and(|word_count| word_count.is_compressed())
+        })
+    }
+
+    fn get(&self, file: &str) -> Result<&FileRow> {
+        self.files.iter().find(|row| row.file() == file).ok_or_else(|| Error::NotFound(format!("file '{}'", file)))
+    }
+
+    // Returns the cabinet holding a file, or `None` if it is stored uncompressed on the source media.
+    fn cabinet(&self, file: &FileRow) -> Option<MediaCabinet<'_>> {
+        self.media.cabinet_for_sequence(file.sequence())
+            .filter(|_| file.attributes().compressed().unwrap_or(self.compressed))
+    }
+
+    // Returns the member holding a file: the one named by its key, else the one at the position of the
+    // file among the compressed files of its disk, as cabinets store them in sequence order.
+    fn member<'a>(&self, file: &FileRow, cabinets: &'a CabinetSet) -> Result<&'a CabFile>
+    {
+        if let Some(member) = cabinets.file(file.file())
+        {
+            return Ok(member);
+        }
+
+        let disk = self.media.media_for_sequence(file.sequence()).map(|row| row.disk_id());
+        self.files.iter()
+            .filter(|other| self.cabinet(other).is_some() && self.media.media_for_sequence(other.sequence()).map(|row| row.disk_id()) == disk)
+            .position(|other| other.file() == file.file())
+            .and_then(|position| cabinets.files().get(position))
+            .ok_or_else(|| Error::NotFound(format!("cabinet member of file '{}'", file.file())))
+    }
+
+    // Opens a file stored uncompressed below the source root.
+    fn open_uncompressed(&self, root: Option<&Path>, file: &FileRow) -> Result<File>
+    {
+        let root = root
+            .ok_or_else(|| Error::NotFound(format!("source root to look for file '{}' in", file.file())))?;
+        let path = join(root, &self.source_path(&self.image, file)?)?;
+        if !path.is_file()
+        {
+            return Err(Error::NotFound(format!("source file '{}'", path.display())));
+        }
+
+        Ok(File::open(path)?)
+    }
+
+    fn source_path(&self, resolver: &DirectoryResolver, file: &FileRow) -> Result<String> {
+        resolver.file_source_path(file.file()).ok_or_else(|| Error::invalid(format!("the directory of file '{}' does not resolve", file.file())))
+    }
+}
+
+#[doc = "Opens the file with the given File key for reading. The disk of the file is found by its Sequence; compressed files are read from the cabinet of the disk, embedded or below the source root, and others from the source image below the source root. The file is decompressed as it is read, but not the cabinet: it is read into memory as a whole while the file is opened, and the compressed data blocks of the file's folder are kept until the reader is dropped."]
+pub fn open_file(package: &MsiPackage, file: &str) -> Result<FileReader>
+{
+    let layout = Layout::read(package)?;
+    let row = layout.get(file)?;
+    let source = match layout.cabinet(row)
+    {
+        Some(cabinet) => {
+            let cabinets = package.read_cabinet(cabinet)?;
+            let member = layout.member(row, &cabinets)?;
+            let size = member.size() as u64;
+            FileSource::Cabinet(cabinets.open_file(member)?.into_inner().into_owned().take(size))
+        },
+        None => FileSource::Image(layout.open_uncompressed(package.source_root(), row)?)
+    };
+
+    Ok(FileReader {
+        source
+    })
+}
+
+#[doc = "Writes the contents of the file with the given File key to `output` and returns its size, reading it as `open_file` does."]
+pub fn extract_file<W: Write>(package: &MsiPackage, file: &str, output: &mut W) -> Result<u64> {
+    Ok(io::copy(&mut open_file(package, file)?, output)?)
+}
+
+// Files to extract together: the members of one cabinet folder, or a file of the source image.
+enum Job<'a> {
+    Folder(&'a CabinetSet, u16, Vec<(usize, &'a CabFile)>),
+    Image(usize)
+}
+
+#[doc = "Writes every file of the package below `destination`, recreating the source layout with long file and directory names, e.g. `destination/Contoso/App/Application.exe`. Files are streamed to disk, and every folder of a cabinet is decompressed once; with the `rayon` feature folders are decompressed on multiple threads. Fails on the first file that cannot be extracted; the files written are returned in sequence order either way."]
+pub fn extract_all<P: AsRef<Path>>(package: &MsiPackage, destination: P) -> Result<Vec<ExtractedFile>>
+{
+    let layout = Layout::read(package)?;
+    let mut cabinets: HashMap<String, CabinetSet> = HashMap::new();
+    for file in &layout.files
+    {
+        if let Some(cabinet) = layout.cabinet(file).filter(|cabinet| !cabinets.contains_key(&cabinet.to_string()))
+        {
+            cabinets.insert(cabinet.to_string(), package.read_cabinet(cabinet)?);
+        }
+    }
+
+    let mut jobs = Vec::new();
+    let mut folders: HashMap<(String, u16), usize> = HashMap::new();
+    for (index, file) in layout.files.iter().enumerate()
+    {
+        let cabinet = match layout.cabinet(file)
+        {
+            Some(cabinet) => cabinet.to_string(),
+            None => {
+                jobs.push(Job::Image(index));
+                continue;
+            }
+        };
+
+        let set = &cabinets[&cabinet];
+        let member = layout.member(file, set)?;
+        let job = *folders.entry((cabinet, member.folder())).or_insert_with(|| {
+            jobs.push(Job::Folder(set, member.folder(), Vec::new()));
+            jobs.len() - 1
+        });
+        if let Job::Folder(_, _, members) = &mut jobs[job]
+        {
+            members.push((index, member));
+        }
+    }
+
+    // jobs run in any order, their results are collected in the order of the jobs
+    let (root, destination) = (package.source_root(), destination.as_ref());
+    #[cfg(feature = "rayon")]
+    let results: Vec<_> = {
+        use rayon::prelude::*;
+        jobs.into_par_iter().map(|job| run(root, &layout, destination, job)).collect()
+    };
+    #[cfg(not(feature = "rayon"))]
+    let results: Vec<_> = jobs.into_iter().map(|job| run(root, &layout, destination, job)).collect();
+
+    let mut extracted = Vec::with_capacity(layout.files.len());
+    for result in results
+    {
+        extracted.extend(result?);
+    }
+    extracted.sort_by_key(|(index, _)| *index);
+
+    Ok(extracted.into_iter().map(|(_, file)| file).collect())
+}
+
+// Extracts the files of a job, returning them with their index in the layout.
+fn run(root: Option<&Path>, layout: &Layout, destination: &Path, job: Job<'_>) -> Result<Vec<(usize, ExtractedFile)>>
+{
+    let (cabinets, folder, mut members) = match job
+    {
+        Job::Folder(cabinets, folder, members) => (cabinets, folder, members),
+        Job::Image(index) => {
+            let mut source = layout.open_uncompressed(root, &layout.files[index])?;
+            return Ok(vec![(index, write(layout, destination, index, &mut source)?)]);
+        }
+    };
+    members.sort_by_key(|(_, member)| member.folder_offset());
+
+    let mut extracted = Vec::with_capacity(members.len());
+    let mut reader = cabinets.open_folder(folder as usize)?;
+    let mut position = 0;
+    for (index, member) in members
+    {
+        let offset = member.folder_offset() as u64;
+        // members sharing data start over
+        if offset < position
+        {
+            reader = cabinets.open_folder(folder as usize)?;
+            position = 0;
+        }
+        position += io::copy(&mut (&mut reader).take(offset - position), &mut io::sink())?;
+
+        let file = write(layout, destination, index, &mut (&mut reader).take(member.size() as u64))?;
+        position += file.size;
+        if position != offset + member.size() as u64
+        {
+            return Err(Error::invalid(format!("cabinet member '{}' extends beyond its folder", member.name())));
+        }
+        extracted.push((index, file));
+    }
+
+    Ok(extracted)
+}
+
+// Writes a file of the layout below the destination.
+fn write<R: Read>(layout: &Layout, destination: &Path, index: usize, source: &mut R) -> Result<ExtractedFile>
+{
+    let file = &layout.files[index];
+    let path = join(destination, &layout.source_path(&layout.long, file)?)?;
+    if let Some(parent) = path.parent()
+    {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let size = io::copy(source, &mut File::create(&path)?)?;
+    Ok(ExtractedFile {
+        file: file.file().to_string(),
+        path,
+        size
+    })
+}
+
+// Appends a source path with backslashes to a directory, refusing components that would leave it.
+fn join(root: &Path, source_path: &str) -> Result<PathBuf>
+{
+    let mut path = root.to_path_buf();
+    for part in source_path.split('\\').filter(|part| !part.is_empty() && *part != ".")
+    {
+        if part == ".." || part.contains(['/', ':'])
+        {
+            return Err(Error::invalid(format!("source path '{}' leaves the destination directory", source_path)));
+        }
+        path.push(part);
+    }
+
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::cabinet::tests::build_cab;
+    use crate::testutil::TestPackage;
+
+    #[test]
+    fn test_extract()
+    {
+        let package = TestPackage::new("extract", |builder| {
+            builder.table("Directory", vec![
+                msi::Column::build("Directory").primary_key().id_string(72),
+                msi::Column::build("Directory_Parent").nullable().id_string(72),
+                msi::Column::build("DefaultDir").text_string(255)
+            ], vec![
+                vec![msi::Value::from("TARGETDIR"), msi::Value::Null, msi::Value::from("SourceDir")],
+                vec![msi::Value::from("INSTALLDIR"), msi::Value::from("TARGETDIR"), msi::Value::from("CONTOSO|Contoso App")],
+                vec![msi::Value::from("DOCS"), msi::Value::from("TARGETDIR"), msi::Value::from("Docs")]
+            ]);
+            builder.table("Component", vec![
+                msi::Column::build("Component").primary_key().id_string(72),
+                msi::Column::build("ComponentId").nullable().string(38),
+                msi::Column::build("Directory_").id_string(72),
+                msi::Column::build("Attributes").int16(),
+                msi::Column::build("Condition").nullable().string(255),
+                msi::Column::build("KeyPath").nullable().id_string(72)
+            ], vec![
+                vec![msi::Value::from("Main"), msi::Value::Null, msi::Value::from("INSTALLDIR"), msi::Value::Int(0), msi::Value::Null, msi::Value::Null],
+                vec![msi::Value::from("Docs"), msi::Value::Null, msi::Value::from("DOCS"), msi::Value::Int(0), msi::Value::Null, msi::Value::Null]
+            ]);
+            builder.table("File", vec![
+                msi::Column::build("File").primary_key().id_string(72),
+                msi::Column::build("Component_").id_string(72),
+                msi::Column::build("FileName").text_string(255),
+                msi::Column::build("FileSize").int32(),
+                msi::Column::build("Version").nullable().string(72),
+                msi::Column::build("Language").nullable().string(20),
+                msi::Column::build("Attributes").nullable().int16(),
+                msi::Column::build("Sequence").int16()
+            ], vec![
+                vec![msi::Value::from("App"), msi::Value::from("Main"), msi::Value::from("APP.EXE|Application.exe"), msi::Value::Int(5),
+                    msi::Value::Null, msi::Value::Null, msi::Value::Int(0x4000), msi::Value::Int(1)],
+                vec![msi::Value::from("Readme"), msi::Value::from("Main"), msi::Value::from("README.TXT|Read me.txt"), msi::Value::Int(6),
+                    msi::Value::Null, msi::Value::Null, msi::Value::Int(0x4000), msi::Value::Int(2)],
+                vec![msi::Value::from("Notes"), msi::Value::from("Docs"), msi::Value::from("notes.txt"), msi::Value::Int(5),
+                    msi::Value::Null, msi::Value::Null, msi::Value::Int(0x2000), msi::Value::Int(3)]
+            ]);
+            builder.table("Media", vec![
+                msi::Column::build("DiskId").primary_key().int16(),
+                msi::Column::build("LastSequence").int32(),
+                msi::Column::build("DiskPrompt").nullable().localizable().text_string(64),
+                msi::Column::build("Cabinet").nullable().category(msi::Category::Cabinet).string(255),
+                msi::Column::build("VolumeLabel").nullable().text_string(32),
+                msi::Column::build("Source").nullable().category(msi::Category::Property).string(72)
+            ], vec![
+                vec![msi::Value::Int(1), msi::Value::Int(3), msi::Value::Null, msi::Value::from("#data.cab"), msi::Value::Null, msi::Value::Null]
+            ]);
+            // the second member is not named by its key, it is matched by its position
+            builder.stream("data.cab", &build_cab(&[("App", b"hello"), ("readme.txt", b"world!")]));
+        });
+        // the uncompressed file is read from the source image
+        let source = std::env::temp_dir().join(format!("msi-reader-extract-source-{}", std::process::id()));
+        std::fs::create_dir_all(source.join("Docs")).unwrap();
+        std::fs::write(source.join("Docs").join("notes.txt"), b"notes").unwrap();
+        let package = MsiPackage::open(package.path()).unwrap().with_source_root(&source);
+
+        let mut output = Vec::new();
+        assert_eq!(package.extract_file("App", &mut output).unwrap(), 5);
+        assert_eq!(output, b"hello");
+        let mut output = String::new();
+        package.open_file("Readme").unwrap().read_to_string(&mut output).unwrap();
+        assert_eq!(output, "world!");
+        assert!(matches!(package.open_file("Missing"), Err(Error::NotFound(_))));
+
+        let destination = std::env::temp_dir().join(format!("msi-reader-extract-{}", std::process::id()));
+        let extracted = package.extract_all(&destination).unwrap();
+        assert_eq!(extracted.iter().map(|file| file.file()).collect::<Vec<_>>(), ["App", "Readme", "Notes"]);
+        assert_eq!(extracted[1].path(), destination.join("Contoso App").join("Read me.txt"));
+        assert_eq!(std::fs::read(destination.join("Contoso App").join("Application.exe")).unwrap(), b"hello");
+        assert_eq!(std::fs::read(extracted[1].path()).unwrap(), b"world!");
+        assert_eq!(std::fs::read(destination.join("Docs").join("notes.txt")).unwrap(), b"notes");
+        std::fs::remove_dir_all(&destination).unwrap();
+        std::fs::remove_dir_all(&source).unwrap();
+
+        assert!(join(Path::new("out"), "Contoso\\..\\..\\etc").is_err());
+        assert_eq!(join(Path::new("out"), "Contoso\\.\\App").unwrap(), Path::new("out").join("Contoso").join("App"));
+    }
+}
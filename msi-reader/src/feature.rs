@@ -0,0 +1,465 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use crate::component::{ ComponentRow, ComponentTable };
+use crate::error::Result;
+use crate::package::MsiPackage;
+use crate::table::RowOrigin;
+
+const ATTRIBUTE_FAVOR_SOURCE: i32 = 0x0001;
+const ATTRIBUTE_FOLLOW_PARENT: i32 = 0x0002;
+const ATTRIBUTE_FAVOR_ADVERTISE: i32 = 0x0004;
+const ATTRIBUTE_DISALLOW_ADVERTISE: i32 = 0x0008;
+const ATTRIBUTE_UI_DISALLOW_ABSENT: i32 = 0x0010;
+const ATTRIBUTE_NO_UNSUPPORTED_ADVERTISE: i32 = 0x0020;
+
+#[doc = "The Attributes bit field of a Feature row."]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FeatureAttributes(i32);
+
+impl FeatureAttributes {
+
+    #[doc = "Returns the raw bits."]
+    pub fn bits(&self) -> i32 {
+        self.0
+    }
+
+    #[doc = "Returns a boolean value indicating whether the feature's components run from source by default instead of being installed locally."]
+    pub fn is_favor_source(&self) -> bool {
+        self.0 & ATTRIBUTE_FAVOR_SOURCE != 0
+    }
+
+    #[doc = "Returns a boolean value indicating whether the feature takes the install state of its parent."]
+    pub fn is_follow_parent(&self) -> bool {
+        self.0 & ATTRIBUTE_FOLLOW_PARENT != 0
+    }
+
+    #[doc = "Returns a boolean value indicating whether the feature is advertised by default."]
+    pub fn is_favor_advertise(&self) -> bool {
+        self.0 & ATTRIBUTE_FAVOR_ADVERTISE != 0
+    }
+
+    #[doc = "Returns a boolean value indicating whether the feature cannot be advertised."]
+    pub fn is_advertise_disallowed(&self) -> bool {
+        self.0 & ATTRIBUTE_DISALLOW_ADVERTISE != 0
+    }
+
+    #[doc = "Returns a boolean value indicating whether the user interface does not offer to leave the feature absent."]
+    pub fn is_absent_disallowed(&self) -> bool {
+        self.0 & ATTRIBUTE_UI_DISALLOW_ABSENT != 0
+    }
+
+    #[doc = "Returns a boolean value indicating whether the feature is only advertised on systems that support it."]
+    pub fn is_no_unsupported_advertise(&self) -> bool {
+        self.0 & ATTRIBUTE_NO_UNSUPPORTED_ADVERTISE != 0
+    }
+}
+
+impl From<i32> for FeatureAttributes {
+    fn from(bits: i32) -> Self {
+        FeatureAttributes(bits)
+    }
+}
+
+#[doc = "A row of the Feature table."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct FeatureRow {
+    feature: String,
+    parent: Option<String>,
+    title: Option<String>,
+    description: Option<String>,
+    display: Option<i32>,
+    level: i32,
+    directory: Option<String>,
+    attributes: FeatureAttributes,
+    origin: RowOrigin
+}
+
+impl FeatureRow {
+
+    #[doc = "Returns the primary key of the feature."]
+    pub fn feature(&self) -> &str {
+        &self.feature
+    }
+
+    #[doc = "Returns the parent feature; `None` for top-level features, including those that name themselves as parent."]
+    pub fn parent(&self) -> Option<&str> {
+        self.parent.as_deref()
+    }
+
+    #[doc = "Returns the title shown in the feature selection dialog."]
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    #[doc = "Returns the description shown in the feature selection dialog."]
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    #[doc = "Returns the display order; 0 or `None` hides the feature, and odd values show it expanded."]
+    pub fn display(&self) -> Option<i32> {
+        self.display
+    }
+
+    #[doc = "Returns a boolean value indicating whether the feature is shown in the selection tree."]
+    pub fn is_visible(&self) -> bool {
+        self.display.is_some_and(|display| display != 0)
+    }
+
+    #[doc = "Returns the install level; the feature is installed by default when it does not exceed INSTALLLEVEL, and never when it is 0."]
+    pub fn level(&self) -> i32 {
+        self.level
+    }
+
+    #[doc = "Returns the directory the user may change from the selection dialog."]
+    pub fn directory(&self) -> Option<&str> {
+        self.directory.as_deref()
+    }
+
+    #[doc = "Returns the attributes of the feature."]
+    pub fn attributes(&self) -> FeatureAttributes {
+        self.attributes
+    }
+
+    #[doc = "Returns the Feature row the feature was read from."]
+    pub fn origin(&self) -> &RowOrigin {
+        &self.origin
+    }
+}
+
+impl Display for FeatureRow {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self.title
+        {
+            Some(title) if !title.is_empty() => write!(fmt, "{} ({})", title, self.feature),
+            _ => write!(fmt, "{}", self.feature)
+        }
+    }
+}
+
+#[doc = "The typed rows of the Feature table, in storage order."]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FeatureTable {
+    rows: Vec<FeatureRow>,
+    index: HashMap<String, usize>
+}
+
+impl FeatureTable {
+
+    #[doc = "Reads the Feature table of the package; a package without one yields an empty table."]
+    pub fn read(package: &MsiPackage) -> Result<FeatureTable>
+    {
+        let table = match package.optional_table("Feature")?
+        {
+            Some(table) => table,
+            None => return Ok(FeatureTable::default())
+        };
+
+        let rows: Vec<FeatureRow> = table.rows()
+            .map(|row| {
+                let feature = row.str("Feature").unwrap_or_default();
+                FeatureRow {
+                    feature: feature.to_string(),
+                    parent: row.str("Feature_Parent").filter(|parent| !parent.is_empty() && *parent != feature).map(|parent| parent.to_string()),
+                    title: row.str("Title").map(|title| title.to_string()),
+                    description: row.str("Description").map(|description| description.to_string()),
+                    display: row.int("Display"),
+                    level: row.int("Level").unwrap_or(0),
+                    directory: row.str("Directory_").map(|directory| directory.to_string()),
+                    attributes: FeatureAttributes(row.int("Attributes").unwrap_or(0)),
+                    origin: row.origin()
+                }
+            })
+            .collect();
+        let index = rows.iter().enumerate().map(|(index, row)| (row.feature.clone(), index)).collect();
+
+        Ok(FeatureTable {
+            rows,
+            index
+        })
+    }
+
+    #[doc = "Returns all features in storage order."]
+    pub fn rows(&self) -> &[FeatureRow] {
+        &self.rows
+    }
+
+    #[doc = "Returns the feature with the given key."]
+    pub fn get(&self, feature: &str) -> Option<&FeatureRow> {
+        self.index.get(feature).map(|index| &self.rows[*index])
+    }
+
+    #[doc = "Links every feature to its parent, consuming the table."]
+    pub fn into_tree(self) -> FeatureTree {
+        FeatureTree::build(self)
+    }
+}
+
+#[doc = "The feature hierarchy of a package. Siblings are ordered as the selection dialog shows them: by Display value, with hidden features last, then by storage order. Features whose parent does not exist, and features in a parent cycle, become roots."]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FeatureTree {
+    features: FeatureTable,
+    roots: Vec<usize>,
+    children: Vec<Vec<usize>>
+}
+
+impl FeatureTree {
+
+    #[doc = "Reads the Feature table of the package and builds its tree."]
+    pub fn read(package: &MsiPackage) -> Result<FeatureTree> {
+        Ok(FeatureTable::read(package)?.into_tree())
+    }
+
+    fn build(features: FeatureTable) -> FeatureTree
+    {
+        let count = features.rows.len();
+        let mut children = vec![Vec::new(); count];
+        let mut roots = Vec::new();
+        for (index, row) in features.rows.iter().enumerate()
+        {
+            match row.parent.as_deref().and_then(|parent| features.index.get(parent)).filter(|parent| !is_ancestor(&features, index, **parent))
+            {
+                Some(parent) => children[*parent].push(index),
+                None => roots.push(index)
+            }
+        }
+
+        let order = |index: &usize| {
+            let display = features.rows[*index].display.filter(|display| *display != 0);
+            (display.is_none(), display, *index)
+        };
+        roots.sort_by_key(order);
+        for list in children.iter_mut()
+        {
+            list.sort_by_key(order);
+        }
+
+        FeatureTree {
+            features,
+            roots,
+            children
+        }
+    }
+
+    #[doc = "Returns the underlying Feature table."]
+    pub fn features(&self) -> &FeatureTable {
+        &self.features
+    }
+
+    #[doc = "Returns the top-level features."]
+    pub fn roots(&self) -> impl Iterator<Item = &FeatureRow> {
+        self.roots.iter().map(move |index| &self.features.rows[*index])
+    }
+
+    #[doc = "Returns the direct children of a feature; none for unknown features."]
+    pub fn children(&self, feature: &str) -> impl Iterator<Item = &FeatureRow> {
+        let children = self.features.index.get(feature).map(|index| self.children[*index].as_slice()).unwrap_or_default();
+        children.iter().map(move |index| &self.features.rows[*index])
+    }
+
+    #[doc = "Returns the parent of a feature in the tree, which is `None` for roots."]
+    pub fn parent(&self, feature: &str) -> Option<&FeatureRow> {
+        let index = *self.features.index.get(feature)?;
+        self.children.iter().position(|children| children.contains(&index)).map(|parent| &self.features.rows[parent])
+    }
+
+    #[doc = "Returns every feature depth-first with its depth, roots having depth 0, so that children follow their parent."]
+    pub fn walk(&self) -> Vec<(usize, &FeatureRow)>
+    {
+        let mut output = Vec::with_capacity(self.features.rows.len());
+        let mut stack: Vec<(usize, usize)> = self.roots.iter().rev().map(|index| (0, *index)).collect();
+        while let Some((depth, index)) = stack.pop()
+        {
+            output.push((depth, &self.features.rows[index]));
+            stack.extend(self.children[index].iter().rev().map(|child| (depth + 1, *child)));
+        }
+
+        output
+    }
+}
+
+#[doc = "The FeatureComponents table joined to the Feature and Component tables, recording which components each feature installs."]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FeatureComponents {
+    features: FeatureTable,
+    components: ComponentTable,
+    links: Vec<(usize, usize)>,
+    unresolved: Vec<RowOrigin>
+}
+
+impl FeatureComponents {
+
+    #[doc = "Reads the FeatureComponents table of the package and resolves both sides of every row; a package without one yields no links."]
+    pub fn read(package: &MsiPackage) -> Result<FeatureComponents>
+    {
+        let features = FeatureTable::read(package)?;
+        let components = ComponentTable::read(package)?;
+        let mut links = Vec::new();
+        let mut unresolved = Vec::new();
+        if let Some(table) = package.optional_table("FeatureComponents")?
+        {
+            for row in table.rows()
+            {
+                let feature = row.str("Feature_").and_then(|feature| features.index.get(feature));
+                let component = row.str("Component_").and_then(|component| components.position(component));
+                match (feature, component)
+                {
+                    (Some(feature), Some(component)) => links.push((*feature, component)),
+                    _ => unresolved.push(row.origin())
+                }
+            }
+        }
+
+        Ok(FeatureComponents {
+            features,
+            components,
+            links,
+            unresolved
+        })
+    }
+
+    #[doc = "Returns the Feature table the links were resolved against."]
+    pub fn features(&self) -> &FeatureTable {
+        &self.features
+    }
+
+    #[doc = "Returns the Component table the links were resolved against."]
+    pub fn components(&self) -> &ComponentTable {
+        &self.components
+    }
+
+    #[doc = "Returns every resolved feature and component pair in FeatureComponents order."]
+    pub fn pairs(&self) -> impl Iterator<Item = (&FeatureRow, &ComponentRow)> {
+        self.links.iter().map(move |(feature, component)| (&self.features.rows[*feature], &self.components.rows()[*component]))
+    }
+
+    #[doc = "Returns the components installed by a feature."]
+    pub fn components_of<'a>(&'a self, feature: &'a str) -> impl Iterator<Item = &'a ComponentRow> {
+        self.pairs().filter(move |(row, _)| row.feature() == feature).map(|(_, component)| component)
+    }
+
+    #[doc = "Returns the features that install a component."]
+    pub fn features_of<'a>(&'a self, component: &'a str) -> impl Iterator<Item = &'a FeatureRow> {
+        self.pairs().filter(move |(_, row)| row.component() == component).map(|(feature, _)| feature)
+    }
+
+    #[doc = "Returns the components no feature installs; Windows Installer never installs them."]
+    pub fn orphans(&self) -> impl Iterator<Item = &ComponentRow> {
+        self.components.rows().iter().enumerate()
+            .filter(move |(index, _)| !self.links.iter().any(|(_, component)| component == index))
+            .map(|(_, component)| component)
+    }
+
+    #[doc = "Returns the FeatureComponents rows whose feature or component does not exist."]
+    pub fn unresolved(&self) -> &[RowOrigin] {
+        &self.unresolved
+    }
+}
+
+// Whether `feature` is an ancestor of (or the same as) `parent`, in which case linking them would close a cycle.
+fn is_ancestor(features: &FeatureTable, feature: usize, parent: usize) -> bool
+{
+    let mut current = Some(parent);
+    for _ in 0..=features.rows.len()
+    {
+        match current
+        {
+            Some(index) if index == feature => return true,
+            Some(index) => current = features.rows[index].parent.as_deref().and_then(|parent| features.index.get(parent)).copied(),
+            None => return false
+        }
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::testutil::TestPackage;
+
+    #[test]
+    fn test_feature_tree()
+    {
+        let package = TestPackage::new("feature-tree", |builder| {
+            builder.table("Feature", vec![
+                msi::Column::build("Feature").primary_key().id_string(38),
+                msi::Column::build("Feature_Parent").nullable().id_string(38),
+                msi::Column::build("Title").nullable().localizable().text_string(64),
+                msi::Column::build("Description").nullable().localizable().text_string(255),
+                msi::Column::build("Display").nullable().int16(),
+                msi::Column::build("Level").int16(),
+                msi::Column::build("Directory_").nullable().id_string(72),
+                msi::Column::build("Attributes").int16()
+            ], vec![
+                vec![msi::Value::from("Complete"), msi::Value::Null, msi::Value::from("Contoso"), msi::Value::Null, msi::Value::Int(1), msi::Value::Int(1), msi::Value::from("INSTALLDIR"), msi::Value::Int(0x10)],
+                vec![msi::Value::from("Docs"), msi::Value::from("Complete"), msi::Value::from("Documentation"), msi::Value::Null, msi::Value::Int(4), msi::Value::Int(3), msi::Value::Null, msi::Value::Int(2)],
+                vec![msi::Value::from("Core"), msi::Value::from("Complete"), msi::Value::from("Core files"), msi::Value::Null, msi::Value::Int(2), msi::Value::Int(1), msi::Value::Null, msi::Value::Int(0)],
+                vec![msi::Value::from("Hidden"), msi::Value::from("Core"), msi::Value::Null, msi::Value::Null, msi::Value::Int(0), msi::Value::Int(1), msi::Value::Null, msi::Value::Int(0)],
+                vec![msi::Value::from("LoopA"), msi::Value::from("LoopB"), msi::Value::Null, msi::Value::Null, msi::Value::Int(9), msi::Value::Int(1), msi::Value::Null, msi::Value::Int(0)],
+                vec![msi::Value::from("LoopB"), msi::Value::from("LoopA"), msi::Value::Null, msi::Value::Null, msi::Value::Int(8), msi::Value::Int(1), msi::Value::Null, msi::Value::Int(0)]
+            ]);
+        });
+
+        let tree = FeatureTree::read(&MsiPackage::open(package.path()).unwrap()).unwrap();
+        let walk: Vec<(usize, &str)> = tree.walk().into_iter().map(|(depth, feature)| (depth, feature.feature())).collect();
+        let complete = walk.iter().position(|(_, feature)| *feature == "Complete").unwrap();
+        assert_eq!(&walk[complete..complete + 4], [(0, "Complete"), (1, "Core"), (2, "Hidden"), (1, "Docs")]);
+        assert_eq!(walk.len(), 6);
+        assert_eq!(tree.roots().map(|feature| feature.feature()).collect::<Vec<_>>(), ["Complete", "LoopB", "LoopA"]);
+
+        assert_eq!(tree.parent("Core").map(|feature| feature.feature()), Some("Complete"));
+        assert!(tree.parent("Complete").is_none());
+        assert_eq!(tree.children("Missing").count(), 0);
+
+        let complete = tree.features().get("Complete").unwrap();
+        assert_eq!(complete.to_string(), "Contoso (Complete)");
+        assert_eq!((complete.level(), complete.directory()), (1, Some("INSTALLDIR")));
+        assert!(complete.attributes().is_absent_disallowed());
+        assert!(tree.features().get("Docs").unwrap().attributes().is_follow_parent());
+        assert!(!tree.features().get("Hidden").unwrap().is_visible());
+    }
+
+    #[test]
+    fn test_feature_components()
+    {
+        let package = TestPackage::new("feature-components", |builder| {
+            builder.table("Feature", vec![
+                msi::Column::build("Feature").primary_key().id_string(38),
+                msi::Column::build("Feature_Parent").nullable().id_string(38),
+                msi::Column::build("Level").int16()
+            ], vec![
+                vec![msi::Value::from("Complete"), msi::Value::Null, msi::Value::Int(1)],
+                vec![msi::Value::from("Docs"), msi::Value::from("Complete"), msi::Value::Int(1)]
+            ]);
+            builder.table("Component", vec![
+                msi::Column::build("Component").primary_key().id_string(72),
+                msi::Column::build("ComponentId").nullable().string(38),
+                msi::Column::build("Directory_").id_string(72),
+                msi::Column::build("Attributes").int16()
+            ], vec![
+                vec![msi::Value::from("Main"), msi::Value::Null, msi::Value::from("INSTALLDIR"), msi::Value::Int(0)],
+                vec![msi::Value::from("Manual"), msi::Value::Null, msi::Value::from("INSTALLDIR"), msi::Value::Int(0)],
+                vec![msi::Value::from("Unused"), msi::Value::Null, msi::Value::from("INSTALLDIR"), msi::Value::Int(0)]
+            ]);
+            builder.table("FeatureComponents", vec![
+                msi::Column::build("Feature_").primary_key().id_string(38),
+                msi::Column::build("Component_").primary_key().id_string(72)
+            ], vec![
+                vec![msi::Value::from("Complete"), msi::Value::from("Main")],
+                vec![msi::Value::from("Complete"), msi::Value::from("Manual")],
+                vec![msi::Value::from("Docs"), msi::Value::from("Manual")],
+                vec![msi::Value::from("Docs"), msi::Value::from("Removed")]
+            ]);
+        });
+
+        let links = MsiPackage::open(package.path()).unwrap().feature_components().unwrap();
+        assert_eq!(links.pairs().count(), 3);
+        assert_eq!(links.components_of("Complete").map(|component| component.component()).collect::<Vec<_>>(), ["Main", "Manual"]);
+        assert_eq!(links.features_of("Manual").map(|feature| feature.feature()).collect::<Vec<_>>(), ["Complete", "Docs"]);
+        assert_eq!(links.orphans().map(|component| component.component()).collect::<Vec<_>>(), ["Unused"]);
+        assert_eq!(links.unresolved().iter().map(|origin| origin.to_string()).collect::<Vec<_>>(), ["FeatureComponents [Docs.Removed] (row 3)"]);
+    }
+}
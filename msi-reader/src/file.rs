@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+
+use crate::directory::MsiName;
+use crate::error::Result;
+use crate::language::MsiLanguage;
+use crate::package::MsiPackage;
+use crate::table::RowOrigin;
+
+const ATTRIBUTE_READ_ONLY: i32 = 0x0001;
+const ATTRIBUTE_HIDDEN: i32 = 0x0002;
+const ATTRIBUTE_SYSTEM: i32 = 0x0004;
+const ATTRIBUTE_VITAL: i32 = 0x0200;
+const ATTRIBUTE_CHECKSUM: i32 = 0x0400;
+const ATTRIBUTE_PATCH_ADDED: i32 = 0x1000;
+const ATTRIBUTE_NONCOMPRESSED: i32 = 0x2000;
+const ATTRIBUTE_COMPRESSED: i32 = 0x4000;
+
+#[doc = "The Attributes bit field of a File row."]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FileAttributes(i32);
+
+impl FileAttributes {
+
+    #[doc = "Returns the raw bits."]
+    pub fn bits(&self) -> i32 {
+        self.0
+    }
+
+    #[doc = "Returns a boolean value indicating whether the file is installed read-only."]
+    pub fn is_read_only(&self) -> bool {
+        self.0 & ATTRIBUTE_READ_ONLY != 0
+    }
+
+    #[doc = "Returns a boolean value indicating whether the file is installed hidden."]
+    pub fn is_hidden(&self) -> bool {
+        self.0 & ATTRIBUTE_HIDDEN != 0
+    }
+
+    #[doc = "Returns a boolean value indicating whether the file is installed as a system file."]
+    pub fn is_system(&self) -> bool {
+        self.0 & ATTRIBUTE_SYSTEM != 0
+    }
+
+    #[doc = "Returns a boolean value indicating whether the installation fails if the file cannot be installed."]
+    pub fn is_vital(&self) -> bool {
+        self.0 & ATTRIBUTE_VITAL != 0
+    }
+
+    #[doc = "Returns a boolean value indicating whether the file has a valid checksum that is verified on repair."]
+    pub fn has_checksum(&self) -> bool {
+        self.0 & ATTRIBUTE_CHECKSUM != 0
+    }
+
+    #[doc = "Returns a boolean value indicating whether the file was added by a patch."]
+    pub fn is_patch_added(&self) -> bool {
+        self.0 & ATTRIBUTE_PATCH_ADDED != 0
+    }
+
+    #[doc = "Returns the compression of the file's source, overriding the Word Count of the summary information: `Some(true)` for compressed, `Some(false)` for uncompressed and `None` if the package default applies."]
+    pub fn compressed(&self) -> Option<bool> {
+        if self.0 & ATTRIBUTE_COMPRESSED != 0
+        {
+            Some(true)
+        }
+        else if self.0 & ATTRIBUTE_NONCOMPRESSED != 0
+        {
+            Some(false)
+        }
+        else
+        {
+            None
+        }
+    }
+}
+
+impl From<i32> for FileAttributes {
+    fn from(bits: i32) -> Self {
+        FileAttributes(bits)
+    }
+}
+
+#[doc = "A row of the File table."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct FileRow {
+    file: String,
+    component: String,
+    file_name: String,
+    size: i32,
+    version: Option<String>,
+    language: Option<String>,
+    attributes: FileAttributes,
+    sequence: i32,
+    origin: RowOrigin
+}
+
+impl FileRow {
+
+    #[doc = "Returns the primary key of the file."]
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    #[doc = "Returns the component the file belongs to."]
+    pub fn component(&self) -> &str {
+        &self.component
+    }
+
+    #[doc = "Returns the file name, split into its optional short and long parts."]
+    pub fn file_name(&self) -> MsiName<'_> {
+        MsiName::from(self.file_name.as_str())
+    }
+
+    #[doc = "Returns the size of the file in bytes."]
+    pub fn size(&self) -> i32 {
+        self.size
+    }
+
+    #[doc = "Returns the version of a versioned file. Companion files store the key of another file instead, returned by `companion`."]
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref().filter(|version| is_version(version))
+    }
+
+    #[doc = "Returns the key of the file whose version this companion file shares."]
+    pub fn companion(&self) -> Option<&str> {
+        self.version.as_deref().filter(|version| !is_version(version))
+    }
+
+    #[doc = "Returns the comma-separated language ids of the file."]
+    pub fn language(&self) -> Option<&str> {
+        self.language.as_deref()
+    }
+
+    #[doc = "Returns the languages of the file; an empty list for language-neutral files."]
+    pub fn languages(&self) -> Vec<MsiLanguage> {
+        self.language.as_deref().map(MsiLanguage::parse_list).unwrap_or_default()
+    }
+
+    #[doc = "Returns the attributes of the file."]
+    pub fn attributes(&self) -> FileAttributes {
+        self.attributes
+    }
+
+    #[doc = "Returns the position of the file in the media of the package."]
+    pub fn sequence(&self) -> i32 {
+        self.sequence
+    }
+
+    #[doc = "Returns the File row the file was read from."]
+    pub fn origin(&self) -> &RowOrigin {
+        &self.origin
+    }
+}
+
+#[doc = "The typed rows of the File table, in storage order."]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FileTable {
+    rows: Vec<FileRow>,
+    index: HashMap<String, usize>
+}
+
+impl FileTable {
+
+    #[doc = "Reads the File table of the package; a package without one yields an empty table."]
+    pub fn read(package: &MsiPackage) -> Result<FileTable>
+    {
+        let table = match package.optional_table("File")?
+        {
+            Some(table) => table,
+            None => return Ok(FileTable::default())
+        };
+
+        let rows: Vec<FileRow> = table.rows()
+            .map(|row| FileRow {
+                file: row.str("File").unwrap_or_default().to_string(),
+                component: row.str("Component_").unwrap_or_default().to_string(),
+                file_name: row.str("FileName").unwrap_or_default().to_string(),
+                size: row.int("FileSize").unwrap_or(0),
+                version: row.str("Version").map(|version| version.to_string()),
+                language: row.str("Language").map(|language| language.to_string()),
+                attributes: FileAttributes(row.int("Attributes").unwrap_or(0)),
+                sequence: row.int("Sequence").unwrap_or(0),
+                origin: row.origin()
+            })
+            .collect();
+        let index = rows.iter().enumerate().map(|(index, row)| (row.file.clone(), index)).collect();
+
+        Ok(FileTable {
+            rows,
+            index
+        })
+    }
+
+    #[doc = "Returns all files in storage order."]
+    pub fn rows(&self) -> &[FileRow] {
+        &self.rows
+    }
+
+    #[doc = "Returns the file with the given key."]
+    pub fn get(&self, file: &str) -> Option<&FileRow> {
+        self.index.get(file).map(|index| &self.rows[*index])
+    }
+
+    #[doc = "Returns the files of the given component."]
+    pub fn by_component<'a>(&'a self, component: &'a str) -> impl Iterator<Item = &'a FileRow> {
+        self.rows.iter().filter(move |row| row.component == component)
+    }
+}
+
+// Versions start with a digit; anything else in the Version column is the key of a companion's parent file.
+fn is_version(value: &str) -> bool
+{
+    value.starts_with(|c: char| c.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::directory::NameFormat;
+    use crate::testutil::TestPackage;
+
+    #[test]
+    fn test_read_files()
+    {
+        let package = TestPackage::new("file-table", |builder| {
+            builder.table("File", vec![
+                msi::Column::build("File").primary_key().id_string(72),
+                msi::Column::build("Component_").id_string(72),
+                msi::Column::build("FileName").category(msi::Category::Filename).string(255),
+                msi::Column::build("FileSize").int32(),
+                msi::Column::build("Version").nullable().string(72),
+                msi::Column::build("Language").nullable().string(20),
+                msi::Column::build("Attributes").nullable().int16(),
+                msi::Column::build("Sequence").int16()
+            ], vec![
+                vec![msi::Value::from("App.exe"), msi::Value::from("Main"), msi::Value::from("APP~1.EXE|Application.exe"), msi::Value::Int(1024),
+                    msi::Value::from("1.2.3.4"), msi::Value::from("1033"), msi::Value::Int(0x4201), msi::Value::Int(1)],
+                vec![msi::Value::from("App.cfg"), msi::Value::from("Main"), msi::Value::from("app.cfg"), msi::Value::Int(10),
+                    msi::Value::from("App.exe"), msi::Value::Null, msi::Value::Null, msi::Value::Int(2)]
+            ]);
+        });
+
+        let files = FileTable::read(&MsiPackage::open(package.path()).unwrap()).unwrap();
+        assert_eq!(files.rows().len(), 2);
+        assert_eq!(files.by_component("Main").count(), 2);
+
+        let exe = files.get("App.exe").unwrap();
+        assert_eq!(exe.file_name().short(), Some("APP~1.EXE"));
+        assert_eq!(exe.file_name().format(NameFormat::Long), "Application.exe");
+        assert_eq!((exe.size(), exe.version(), exe.language()), (1024, Some("1.2.3.4"), Some("1033")));
+        assert_eq!(exe.languages(), [MsiLanguage::from(1033)]);
+        assert!(exe.attributes().is_read_only() && exe.attributes().is_vital());
+        assert_eq!(exe.attributes().compressed(), Some(true));
+        assert_eq!(exe.origin().table(), "File");
+
+        let config = files.get("App.cfg").unwrap();
+        assert_eq!((config.version(), config.companion()), (None, Some("App.exe")));
+        assert_eq!(config.attributes().compressed(), None);
+        assert!(files.get("Missing").is_none());
+    }
+}
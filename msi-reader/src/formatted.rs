@@ -0,0 +1,499 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use crate::component::ComponentTable;
+use crate::directory::NameFormat;
+use crate::error::Result;
+use crate::file::FileTable;
+use crate::layout::DirectoryResolver;
+use crate::package::MsiPackage;
+use crate::property::Properties;
+
+#[doc = "A bracketed reference of a formatted string."]
+#[derive(Clone, Debug, PartialEq)]
+pub enum Reference {
+    #[doc = "`[PROPERTY]`: the value of a property."]
+    Property(String),
+    #[doc = "`[%VAR]`: the value of an environment variable."]
+    Environment(String),
+    #[doc = "`[#File]`: the full path of a file once installed."]
+    FilePath(String),
+    #[doc = "`[!File]`: the short (8.3) path of a file once installed."]
+    FileShortPath(String),
+    #[doc = "`[$Component]`: the directory a component is installed to."]
+    ComponentDirectory(String),
+    #[doc = "A reference whose name contains references itself, such as `[[PropertyName]]`. The inner segments are resolved first and the result read as a reference."]
+    Indirect(Vec<Segment>)
+}
+
+impl Reference {
+
+    fn classify(name: &str) -> Reference
+    {
+        let mut chars = name.chars();
+        let prefix = chars.next();
+        let key = chars.as_str().to_string();
+        match prefix
+        {
+            Some('%') => Reference::Environment(key),
+            Some('#') => Reference::FilePath(key),
+            Some('!') => Reference::FileShortPath(key),
+            Some('$') => Reference::ComponentDirectory(key),
+            _ => Reference::Property(name.to_string())
+        }
+    }
+
+    #[doc = "Returns the name of the property, variable, file or component referenced, without its prefix; `None` for indirect references."]
+    pub fn key(&self) -> Option<&str> {
+        match self
+        {
+            Reference::Property(key) | Reference::Environment(key) | Reference::FilePath(key)
+                | Reference::FileShortPath(key) | Reference::ComponentDirectory(key) => Some(key),
+            Reference::Indirect(_) => None
+        }
+    }
+}
+
+impl Display for Reference {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self
+        {
+            Reference::Property(name) => write!(fmt, "[{}]", name),
+            Reference::Environment(name) => write!(fmt, "[%{}]", name),
+            Reference::FilePath(name) => write!(fmt, "[#{}]", name),
+            Reference::FileShortPath(name) => write!(fmt, "[!{}]", name),
+            Reference::ComponentDirectory(name) => write!(fmt, "[${}]", name),
+            Reference::Indirect(segments) => {
+                write!(fmt, "[")?;
+                segments.iter().try_for_each(|segment| write!(fmt, "{}", segment))?;
+                write!(fmt, "]")
+            }
+        }
+    }
+}
+
+#[doc = "A piece of a formatted string."]
+#[derive(Clone, Debug, PartialEq)]
+pub enum Segment {
+    #[doc = "Text copied as is."]
+    Text(String),
+    #[doc = "`[\\x]`: a single character copied as is, typically a bracket or brace."]
+    Escape(char),
+    #[doc = "`[~]`: a null character, which separates the values of a REG_MULTI_SZ."]
+    Null,
+    Reference(Reference),
+    #[doc = "`{...}` around references: the group is dropped entirely if any of its properties is not set, otherwise it is replaced by its contents."]
+    Group(Vec<Segment>)
+}
+
+impl Display for Segment {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self
+        {
+            Segment::Text(text) => write!(fmt, "{}", text),
+            Segment::Escape(c) => write!(fmt, "[\\{}]", c),
+            Segment::Null => write!(fmt, "[~]"),
+            Segment::Reference(reference) => write!(fmt, "{}", reference),
+            Segment::Group(segments) => {
+                write!(fmt, "{{")?;
+                segments.iter().try_for_each(|segment| write!(fmt, "{}", segment))?;
+                write!(fmt, "}}")
+            }
+        }
+    }
+}
+
+#[doc = "A Windows Installer formatted string split into text and references. Parsing never fails: brackets and braces that do not form a reference are kept as text, as the installer does."]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Formatted {
+    segments: Vec<Segment>
+}
+
+impl Formatted {
+
+    #[doc = "Parses a formatted string."]
+    pub fn parse(text: &str) -> Formatted {
+        Formatted {
+            segments: parse_segments(text)
+        }
+    }
+
+    #[doc = "Returns the segments of the string in order."]
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    #[doc = "Returns every reference, including those inside groups and the innermost ones of indirect references, in order of appearance."]
+    pub fn references(&self) -> Vec<&Reference>
+    {
+        let mut references = Vec::new();
+        collect_references(&self.segments, &mut references);
+        references
+    }
+
+    #[doc = "Returns a boolean value indicating whether the string contains no references, so that it reads the same at install time."]
+    pub fn is_literal(&self) -> bool {
+        self.references().is_empty()
+    }
+}
+
+impl Display for Formatted {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        self.segments.iter().try_for_each(|segment| write!(fmt, "{}", segment))
+    }
+}
+
+#[doc = "The text a formatted string resolves to, and the references that could not be resolved and were replaced by an empty string."]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Resolution {
+    text: String,
+    unresolved: Vec<Reference>
+}
+
+impl Resolution {
+
+    #[doc = "Returns the resolved text."]
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    #[doc = "Returns the references that could not be resolved, in order of appearance. References inside a `{...}` group that was dropped are not included."]
+    pub fn unresolved(&self) -> &[Reference] {
+        &self.unresolved
+    }
+
+    #[doc = "Returns a boolean value indicating whether every reference was resolved."]
+    pub fn is_complete(&self) -> bool {
+        self.unresolved.is_empty()
+    }
+}
+
+#[doc = "Resolves formatted strings against the properties, directories, components and files of a package. Directories resolve to full paths as `DirectoryResolver` lays them out, so well-known folders such as ProgramFilesFolder start at their default locations unless that property is set."]
+#[derive(Clone, Debug, Default)]
+pub struct FormattedResolver {
+    properties: HashMap<String, String>,
+    environment: HashMap<String, String>,
+    directories: DirectoryResolver,
+    components: HashMap<String, String>,
+    files: HashMap<String, (String, String, String)>
+}
+
+impl FormattedResolver {
+
+    #[doc = "Reads the Property, Directory, Component and File tables of the package."]
+    pub fn read(package: &MsiPackage) -> Result<FormattedResolver>
+    {
+        let properties = Properties::read(package)?.iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        let components: HashMap<String, String> = ComponentTable::read(package)?.rows().iter()
+            .map(|component| (component.component().to_string(), component.directory().to_string()))
+            .collect();
+        let files = FileTable::read(package)?.rows().iter()
+            .map(|file| {
+                let name = file.file_name();
+                let long = name.format(NameFormat::Long);
+                let short = name.short().map(|short| short.to_string()).unwrap_or_else(|| long.clone());
+                (file.file().to_string(), (file.component().to_string(), long, short))
+            })
+            .collect();
+
+        Ok(FormattedResolver {
+            properties,
+            environment: HashMap::new(),
+            directories: DirectoryResolver::read(package)?,
+            components,
+            files
+        })
+    }
+
+    #[doc = "Sets a property, such as one passed on the command line; an empty value removes it. Directory keys set this way move that directory and everything below it."]
+    pub fn set_property(&mut self, name: &str, value: &str)
+    {
+        self.directories.set_property(name, value);
+        if value.is_empty()
+        {
+            self.properties.remove(name);
+        }
+        else
+        {
+            self.properties.insert(name.to_string(), value.to_string());
+        }
+    }
+
+    #[doc = "Sets an environment variable. Names are case-insensitive."]
+    pub fn set_environment(&mut self, name: &str, value: &str) {
+        self.environment.insert(name.to_ascii_uppercase(), value.to_string());
+    }
+
+    #[doc = "Resolves a formatted string."]
+    pub fn resolve(&self, formatted: &Formatted) -> Resolution
+    {
+        let mut resolution = Resolution::default();
+        self.resolve_segments(formatted.segments(), &mut resolution);
+        resolution
+    }
+
+    fn resolve_segments(&self, segments: &[Segment], resolution: &mut Resolution)
+    {
+        for segment in segments
+        {
+            match segment
+            {
+                Segment::Text(text) => resolution.text.push_str(text),
+                Segment::Escape(c) => resolution.text.push(*c),
+                Segment::Null => resolution.text.push('\0'),
+                Segment::Reference(reference) => match self.reference(reference)
+                {
+                    Some(value) => resolution.text.push_str(&value),
+                    None => resolution.unresolved.push(reference.clone())
+                },
+                Segment::Group(inner) => {
+                    let mut group = Resolution::default();
+                    self.resolve_segments(inner, &mut group);
+                    if group.unresolved.is_empty()
+                    {
+                        resolution.text.push_str(&group.text);
+                    }
+                }
+            }
+        }
+    }
+
+    fn reference(&self, reference: &Reference) -> Option<String>
+    {
+        match reference
+        {
+            Reference::Property(name) => self.properties.get(name).cloned().or_else(|| self.directories.target_path(name)),
+            Reference::Environment(name) => self.environment.get(&name.to_ascii_uppercase()).cloned(),
+            Reference::FilePath(file) => {
+                let (component, long, _) = self.files.get(file)?;
+                Some(format!("{}{}", self.directories.target_path(self.components.get(component)?)?, long))
+            },
+            Reference::FileShortPath(file) => {
+                let (component, _, short) = self.files.get(file)?;
+                Some(format!("{}{}", self.directories.short_target_path(self.components.get(component)?)?, short))
+            },
+            Reference::ComponentDirectory(component) => self.directories.target_path(self.components.get(component)?),
+            Reference::Indirect(inner) => {
+                let mut name = Resolution::default();
+                self.resolve_segments(inner, &mut name);
+                if name.text.is_empty() || !name.unresolved.is_empty()
+                {
+                    return None;
+                }
+
+                self.reference(&Reference::classify(&name.text))
+            }
+        }
+    }
+}
+
+fn collect_references<'a>(segments: &'a [Segment], references: &mut Vec<&'a Reference>)
+{
+    for segment in segments
+    {
+        match segment
+        {
+            Segment::Reference(Reference::Indirect(inner)) | Segment::Group(inner) => collect_references(inner, references),
+            Segment::Reference(reference) => references.push(reference),
+            _ => {}
+        }
+    }
+}
+
+// Returns the offset of the bracket closing the one at `start`, allowing nested brackets.
+fn closing_bracket(text: &str, start: usize) -> Option<usize>
+{
+    let mut depth = 0;
+    for (index, c) in text[start..].char_indices()
+    {
+        match c
+        {
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0
+                {
+                    return Some(start + index);
+                }
+            },
+            _ => {}
+        }
+    }
+
+    None
+}
+
+fn parse_segments(text: &str) -> Vec<Segment>
+{
+    let mut segments = Vec::new();
+    let mut literal = String::new();
+    let mut index = 0;
+    while let Some(c) = text[index..].chars().next()
+    {
+        let rest = &text[index..];
+        let parsed = if let Some(escaped) = rest.strip_prefix("[\\")
+        {
+            // [\x] escapes a single character, which may itself be a bracket
+            let mut chars = escaped.chars();
+            match (chars.next(), chars.next())
+            {
+                (Some(escaped), Some(']')) => Some((Segment::Escape(escaped), 3 + escaped.len_utf8())),
+                _ => None
+            }
+        }
+        else if rest.starts_with("[~]")
+        {
+            Some((Segment::Null, 3))
+        }
+        else if c == '['
+        {
+            closing_bracket(text, index).filter(|end| *end > index + 1).map(|end| {
+                let name = &text[index + 1..end];
+                let reference = if name.contains('[')
+                {
+                    Reference::Indirect(parse_segments(name))
+                }
+                else
+                {
+                    Reference::classify(name)
+                };
+                (Segment::Reference(reference), end + 1 - index)
+            })
+        }
+        else if c == '{'
+        {
+            // braces around plain text are not a group and stay in the output
+            rest.find('}').and_then(|end| {
+                let inner = parse_segments(&rest[1..end]);
+                if inner.iter().any(|segment| matches!(segment, Segment::Reference(_)))
+                {
+                    Some((Segment::Group(inner), end + 1))
+                }
+                else
+                {
+                    None
+                }
+            })
+        }
+        else
+        {
+            None
+        };
+
+        match parsed
+        {
+            Some((segment, length)) => {
+                if !literal.is_empty()
+                {
+                    segments.push(Segment::Text(std::mem::take(&mut literal)));
+                }
+                segments.push(segment);
+                index += length;
+            },
+            None => {
+                literal.push(c);
+                index += c.len_utf8();
+            }
+        }
+    }
+
+    if !literal.is_empty()
+    {
+        segments.push(Segment::Text(literal));
+    }
+
+    segments
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::testutil::TestPackage;
+
+    #[test]
+    fn test_parse_formatted()
+    {
+        let text = "[INSTALLDIR]bin\\[#App.exe] [\\[]x[\\]] [[Indirect]][~]{-[%TEMP]} {plain} [] [open";
+        let formatted = Formatted::parse(text);
+        assert_eq!(formatted.to_string(), text);
+        assert_eq!(formatted.segments()[0], Segment::Reference(Reference::Property("INSTALLDIR".to_string())));
+        assert_eq!(formatted.segments()[4], Segment::Escape('['));
+
+        let references: Vec<String> = formatted.references().iter().map(|reference| reference.to_string()).collect();
+        assert_eq!(references, ["[INSTALLDIR]", "[#App.exe]", "[Indirect]", "[%TEMP]"]);
+        assert!(formatted.segments().contains(&Segment::Group(vec![Segment::Text("-".to_string()), Segment::Reference(Reference::Environment("TEMP".to_string()))])));
+        assert!(formatted.segments().iter().any(|segment| *segment == Segment::Text(" {plain} [] [open".to_string())));
+
+        let components = Formatted::parse("[$Main][!Short.dll][#[FILE_KEY]]");
+        let keys: Vec<Option<&str>> = components.references().iter().map(|reference| reference.key()).collect();
+        assert_eq!(keys, [Some("Main"), Some("Short.dll"), Some("FILE_KEY")]);
+        assert!(matches!(&components.segments()[2], Segment::Reference(Reference::Indirect(inner)) if inner.len() == 2));
+        assert!(Formatted::parse("C:\\Temp\\{x}").is_literal());
+    }
+
+    #[test]
+    fn test_resolve_formatted()
+    {
+        let package = TestPackage::new("formatted-resolver", |builder| {
+            builder.table("Property", vec![
+                msi::Column::build("Property").primary_key().id_string(72),
+                msi::Column::build("Value").localizable().text_string(0)
+            ], vec![
+                vec![msi::Value::from("ProductName"), msi::Value::from("Contoso App")],
+                vec![msi::Value::from("TARGET_PROPERTY"), msi::Value::from("ProductName")]
+            ]);
+            builder.table("Directory", vec![
+                msi::Column::build("Directory").primary_key().id_string(72),
+                msi::Column::build("Directory_Parent").nullable().id_string(72),
+                msi::Column::build("DefaultDir").text_string(255)
+            ], vec![
+                vec![msi::Value::from("TARGETDIR"), msi::Value::Null, msi::Value::from("SourceDir")],
+                vec![msi::Value::from("ProgramFilesFolder"), msi::Value::from("TARGETDIR"), msi::Value::from(".")],
+                vec![msi::Value::from("INSTALLDIR"), msi::Value::from("ProgramFilesFolder"), msi::Value::from("CONTOSO|Contoso")]
+            ]);
+            builder.table("Component", vec![
+                msi::Column::build("Component").primary_key().id_string(72),
+                msi::Column::build("ComponentId").nullable().string(38),
+                msi::Column::build("Directory_").id_string(72),
+                msi::Column::build("Attributes").int16(),
+                msi::Column::build("Condition").nullable().string(255),
+                msi::Column::build("KeyPath").nullable().id_string(72)
+            ], vec![
+                vec![msi::Value::from("Main"), msi::Value::Null, msi::Value::from("INSTALLDIR"), msi::Value::Int(0), msi::Value::Null, msi::Value::from("App.exe")]
+            ]);
+            builder.table("File", vec![
+                msi::Column::build("File").primary_key().id_string(72),
+                msi::Column::build("Component_").id_string(72),
+                msi::Column::build("FileName").category(msi::Category::Filename).string(255),
+                msi::Column::build("FileSize").int32(),
+                msi::Column::build("Version").nullable().string(72),
+                msi::Column::build("Language").nullable().string(20),
+                msi::Column::build("Attributes").nullable().int16(),
+                msi::Column::build("Sequence").int16()
+            ], vec![
+                vec![msi::Value::from("App.exe"), msi::Value::from("Main"), msi::Value::from("APP~1.EXE|Application.exe"), msi::Value::Int(1024),
+                    msi::Value::Null, msi::Value::Null, msi::Value::Null, msi::Value::Int(1)]
+            ]);
+        });
+
+        let mut resolver = FormattedResolver::read(&MsiPackage::open(package.path()).unwrap()).unwrap();
+        let resolve = |resolver: &FormattedResolver, text: &str| resolver.resolve(&Formatted::parse(text));
+
+        let resolution = resolve(&resolver, "\"[#App.exe]\" /name \"[[TARGET_PROPERTY]]\"");
+        assert_eq!(resolution.text(), "\"C:\\Program Files (x86)\\Contoso\\Application.exe\" /name \"Contoso App\"");
+        assert!(resolution.is_complete());
+
+        resolver.set_property("ProgramFilesFolder", "C:\\Program Files\\");
+        resolver.set_environment("Temp", "C:\\Temp");
+        let resolution = resolve(&resolver, "[!App.exe];[$Main]bin;[INSTALLDIR];[%TEMP][\\[]x[\\]]{ --log [LOGFILE]}[~]");
+        assert_eq!(resolution.text(), "C:\\Program Files\\CONTOSO\\APP~1.EXE;C:\\Program Files\\Contoso\\bin;C:\\Program Files\\Contoso\\;C:\\Temp[x]\0");
+        assert!(resolution.is_complete());
+
+        let resolution = resolve(&resolver, "[MISSING]-[#Gone.dll]-[$Other]");
+        assert_eq!(resolution.text(), "--");
+        assert_eq!(resolution.unresolved(), [Reference::Property("MISSING".to_string()), Reference::FilePath("Gone.dll".to_string()), Reference::ComponentDirectory("Other".to_string())]);
+    }
+}
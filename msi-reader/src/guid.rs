@@ -0,0 +1,194 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::error::{ Error, Result };
+
+// The alphabet of compressed GUIDs, in order of digit value.
+const BASE85_DIGITS: &[u8; 85] = b"!$%&'()*+,-.0123456789=?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[]^_`abcdefghijklmnopqrstuvwxyz{}~";
+
+#[doc = "A GUID in the registry format Windows Installer uses for product, package, upgrade and component codes, e.g. `{8B3A5A8E-1F5C-4E4D-9C1B-2F0F3B7A6D10}`."]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MsiGuid(uuid::Uuid);
+
+impl MsiGuid {
+
+    #[doc = "Parses a GUID enclosed in braces with hyphens between its groups. Lower-case digits are accepted, although Windows Installer requires upper case; see `is_canonical`."]
+    pub fn parse(text: &str) -> Result<MsiGuid>
+    {
+        let inner = text.strip_prefix('{').and_then(|text| text.strip_suffix('}'))
+            .filter(|inner| inner.len() == 36)
+            .ok_or_else(|| Error::invalid(format!("'{}' is not a GUID in braces", text)))?;
+        let groups: Vec<&str> = inner.split('-').collect();
+        let lengths: Vec<usize> = groups.iter().map(|group| group.len()).collect();
+        if lengths != [8, 4, 4, 4, 12] || !groups.iter().all(|group| group.chars().all(|c| c.is_ascii_hexdigit()))
+        {
+            return Err(Error::invalid(format!("'{}' is not a GUID in braces", text)));
+        }
+
+        let value = u128::from_str_radix(&groups.concat(), 16).map_err(|_| Error::invalid(format!("'{}' is not a GUID in braces", text)))?;
+        Ok(MsiGuid(uuid::Uuid::from_u128(value)))
+    }
+
+    #[doc = "Generates a new random (version 4) GUID, e.g. for a package code."]
+    pub fn generate() -> MsiGuid {
+        MsiGuid(uuid::Uuid::new_v4())
+    }
+
+    #[doc = "Returns a boolean value indicating whether `text` is a GUID written exactly as Windows Installer requires: in braces and upper case."]
+    pub fn is_canonical(text: &str) -> bool {
+        MsiGuid::parse(text).is_ok_and(|guid| guid.to_string() == text)
+    }
+
+    #[doc = "Returns the packed form of the GUID the installer uses in registry keys, e.g. `E8A5A3B8C5F1D4E4C9B1F2F0B3A7D601`: 32 digits with the first three groups reversed and the digits of every following byte swapped."]
+    pub fn to_packed(&self) -> String {
+        pack(&format!("{:032X}", self.0.as_u128()))
+    }
+
+    #[doc = "Parses the packed form of a GUID, as found in the registry keys of installed products and components."]
+    pub fn from_packed(text: &str) -> Result<MsiGuid>
+    {
+        if text.len() != 32 || !text.chars().all(|c| c.is_ascii_hexdigit())
+        {
+            return Err(Error::invalid(format!("'{}' is not a packed GUID", text)));
+        }
+
+        let value = u128::from_str_radix(&pack(text), 16).map_err(|_| Error::invalid(format!("'{}' is not a packed GUID", text)))?;
+        Ok(MsiGuid(uuid::Uuid::from_u128(value)))
+    }
+
+    #[doc = "Returns the 20-character compressed form of the GUID used in Darwin descriptors, e.g. `''WhT?o+2Attms'[gk?(`: each of the four little-endian double words of the GUID in five base-85 digits, least significant first."]
+    pub fn to_compressed(&self) -> String
+    {
+        let mut compressed = String::with_capacity(20);
+        for chunk in self.memory_bytes().chunks(4)
+        {
+            let mut value = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+            for _ in 0..5
+            {
+                compressed.push(BASE85_DIGITS[(value % 85) as usize] as char);
+                value /= 85;
+            }
+        }
+
+        compressed
+    }
+
+    #[doc = "Parses the compressed form of a GUID, as the product and component codes of a Darwin descriptor are written."]
+    pub fn from_compressed(text: &str) -> Result<MsiGuid>
+    {
+        let invalid = || Error::invalid(format!("'{}' is not a compressed GUID", text));
+        if text.len() != 20
+        {
+            return Err(invalid());
+        }
+
+        let mut bytes = [0u8; 16];
+        for (index, chunk) in text.as_bytes().chunks(5).enumerate()
+        {
+            let mut value = 0u64;
+            for digit in chunk.iter().rev()
+            {
+                let digit = BASE85_DIGITS.iter().position(|candidate| candidate == digit).ok_or_else(invalid)?;
+                value = value * 85 + digit as u64;
+            }
+            if value > u32::MAX as u64
+            {
+                return Err(invalid());
+            }
+            bytes[index * 4..index * 4 + 4].copy_from_slice(&(value as u32).to_le_bytes());
+        }
+
+        Ok(MsiGuid::from_memory_bytes(bytes))
+    }
+
+    // The GUID as laid out in memory on Windows: the first three fields little-endian, the last eight bytes as they are.
+    fn memory_bytes(&self) -> [u8; 16]
+    {
+        let value = self.0.as_u128();
+        let mut bytes = value.to_be_bytes();
+        bytes[..4].reverse();
+        bytes[4..6].reverse();
+        bytes[6..8].reverse();
+        bytes
+    }
+
+    fn from_memory_bytes(mut bytes: [u8; 16]) -> MsiGuid
+    {
+        bytes[..4].reverse();
+        bytes[4..6].reverse();
+        bytes[6..8].reverse();
+        MsiGuid(uuid::Uuid::from_u128(u128::from_be_bytes(bytes)))
+    }
+
+    #[doc = "Returns the GUID as a UUID."]
+    pub fn as_uuid(&self) -> &uuid::Uuid {
+        &self.0
+    }
+}
+
+// Reorders 32 hex digits between the plain and the packed form; the reordering is its own inverse.
+fn pack(digits: &str) -> String
+{
+    let mut packed: String = digits[..8].chars().rev().collect();
+    packed.extend(digits[8..12].chars().rev());
+    packed.extend(digits[12..16].chars().rev());
+    for pair in digits.as_bytes()[16..].chunks(2)
+    {
+        packed.push(pair[1] as char);
+        packed.push(pair[0] as char);
+    }
+
+    packed
+}
+
+impl FromStr for MsiGuid {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<MsiGuid>
+    {
+        MsiGuid::parse(text)
+    }
+}
+
+impl Display for MsiGuid {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "{{{}}}", self.0.to_hyphenated_ref().to_string().to_ascii_uppercase())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn test_parse_guid()
+    {
+        let guid = MsiGuid::parse("{8b3a5a8e-1f5c-4e4d-9c1b-2f0f3b7a6d10}").unwrap();
+        assert_eq!(guid.to_string(), "{8B3A5A8E-1F5C-4E4D-9C1B-2F0F3B7A6D10}");
+        assert_eq!(guid, "{8B3A5A8E-1F5C-4E4D-9C1B-2F0F3B7A6D10}".parse().unwrap());
+        assert_eq!(guid.as_uuid().as_u128() >> 96, 0x8b3a5a8e);
+
+        assert!(MsiGuid::is_canonical("{8B3A5A8E-1F5C-4E4D-9C1B-2F0F3B7A6D10}"));
+        assert!(!MsiGuid::is_canonical("{8b3a5a8e-1f5c-4e4d-9c1b-2f0f3b7a6d10}"));
+        for invalid in ["8B3A5A8E-1F5C-4E4D-9C1B-2F0F3B7A6D10", "{8B3A5A8E1F5C-4E4D-9C1B-2F0F3B7A6D10-}", "{8B3A5A8E-1F5C-4E4D-9C1B-2F0F3B7A6D1G}", ""]
+        {
+            assert!(MsiGuid::parse(invalid).is_err(), "{}", invalid);
+        }
+
+        assert_eq!(guid.to_packed(), "E8A5A3B8C5F1D4E4C9B1F2F0B3A7D601");
+        assert_eq!(MsiGuid::from_packed("e8a5a3b8c5f1d4e4c9b1f2f0b3a7d601").unwrap(), guid);
+        assert!(MsiGuid::from_packed("E8A5A3B8C5F1D4E4C9B1F2F0B3A7D6").is_err());
+        assert_eq!(guid.to_compressed(), "''WhT?o+2Attms'[gk?(");
+        assert_eq!(MsiGuid::from_compressed("''WhT?o+2Attms'[gk?(").unwrap(), guid);
+        assert!(MsiGuid::from_compressed("''WhT?o+2Attms'[gk?").is_err());
+        assert!(MsiGuid::from_compressed("~~~~~o+2Attms'[gk?(").is_err());
+
+        let generated = MsiGuid::generate();
+        assert_ne!(generated, MsiGuid::generate());
+        assert_eq!(generated.as_uuid().get_version_num(), 4);
+        assert!(MsiGuid::is_canonical(&generated.to_string()));
+        assert_eq!(MsiGuid::from_compressed(&generated.to_compressed()).unwrap(), generated);
+        assert_eq!(MsiGuid::from_packed(&generated.to_packed()).unwrap(), generated);
+    }
+}
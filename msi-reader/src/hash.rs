@@ -0,0 +1,59 @@
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+#[doc = "A 64-bit FNV-1a hasher. Unlike `DefaultHasher` its output is fixed, so hashes can be stored and compared across runs and builds."]
+pub(crate) struct Fnv64 {
+    state: u64
+}
+
+impl Fnv64 {
+
+    pub(crate) fn new() -> Self
+    {
+        Fnv64 {
+            state: FNV_OFFSET_BASIS
+        }
+    }
+
+    pub(crate) fn write(&mut self, bytes: &[u8])
+    {
+        for byte in bytes
+        {
+            self.state ^= *byte as u64;
+            self.state = self.state.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    pub(crate) fn write_u64(&mut self, value: u64)
+    {
+        self.write(&value.to_le_bytes());
+    }
+
+    #[doc = "Writes a length-prefixed string, so that adjacent strings cannot run into each other."]
+    pub(crate) fn write_str(&mut self, value: &str)
+    {
+        self.write_u64(value.len() as u64);
+        self.write(value.as_bytes());
+    }
+
+    pub(crate) fn finish(&self) -> u64
+    {
+        self.state
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn test_fnv64()
+    {
+        assert_eq!(Fnv64::new().finish(), 0xcbf2_9ce4_8422_2325);
+
+        let mut hasher = Fnv64::new();
+        hasher.write(b"a");
+        assert_eq!(hasher.finish(), 0xaf63_dc4c_8601_ec8c);
+    }
+}
@@ -0,0 +1,75 @@
+use crate::error::{ Error, Result };
+
+// LZX codes are up to 16 bits long, DEFLATE codes up to 15.
+const MAX_BITS: usize = 16;
+
+#[doc = "A canonical Huffman code as DEFLATE and LZX store it, given by the code length of every symbol. Symbols are decoded one bit at a time, first bit first."]
+pub(crate) struct Huffman {
+    counts: [u16; MAX_BITS + 1],
+    symbols: Vec<u16>
+}
+
+impl Huffman {
+
+    #[doc = "Builds the code from the code length of every symbol, 0 for symbols that do not occur. Incomplete codes are accepted, as both formats write a single distance code with one bit; over-subscribed ones are not."]
+    pub(crate) fn new(lengths: &[u8]) -> Result<Huffman>
+    {
+        let mut counts = [0u16; MAX_BITS + 1];
+        for length in lengths
+        {
+            if *length as usize > MAX_BITS
+            {
+                return Err(Error::invalid(format!("Huffman code length {} is longer than {} bits", length, MAX_BITS)));
+            }
+            counts[*length as usize] += 1;
+        }
+        counts[0] = 0;
+
+        let mut left: i32 = 1;
+        for count in &counts[1..]
+        {
+            left = (left << 1) - *count as i32;
+            if left < 0
+            {
+                return Err(Error::invalid("compressed data has an over-subscribed Huffman code"));
+            }
+        }
+
+        let mut offsets = [0u16; MAX_BITS + 2];
+        for length in 1..=MAX_BITS
+        {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+        let mut symbols = vec![0u16; offsets[MAX_BITS + 1] as usize];
+        for (symbol, length) in lengths.iter().enumerate().filter(|(_, length)| **length != 0)
+        {
+            symbols[offsets[*length as usize] as usize] = symbol as u16;
+            offsets[*length as usize] += 1;
+        }
+
+        Ok(Huffman {
+            counts,
+            symbols
+        })
+    }
+
+    #[doc = "Decodes one symbol, taking the bits of its code from `bit` in order."]
+    pub(crate) fn decode<F: FnMut() -> Result<u32>>(&self, mut bit: F) -> Result<u16>
+    {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for length in 1..=MAX_BITS
+        {
+            code |= bit()? as i32;
+            let count = self.counts[length] as i32;
+            if code - first < count
+            {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+
+        Err(Error::invalid("compressed data holds an invalid Huffman code"))
+    }
+}
@@ -0,0 +1,359 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::error::{ Error, Result };
+use crate::json;
+use crate::package::MsiPackage;
+use crate::schema;
+use crate::sequence::{ self, AnomalyKind };
+use crate::validation::{ self, CellLocation };
+
+#[doc = "The severity of a validation message, ordered from least to most severe."]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error
+}
+
+impl FromStr for Severity {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Severity>
+    {
+        match value.to_ascii_lowercase().as_str()
+        {
+            "info" => Ok(Severity::Info),
+            "warning" => Ok(Severity::Warning),
+            "error" => Ok(Severity::Error),
+            other => Err(Error::NotFound(format!("severity '{}'", other)))
+        }
+    }
+}
+
+impl Display for Severity {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self
+        {
+            Severity::Info => write!(fmt, "info"),
+            Severity::Warning => write!(fmt, "warning"),
+            Severity::Error => write!(fmt, "error")
+        }
+    }
+}
+
+#[doc = "A message produced by a validation rule."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct IceMessage {
+    rule: &'static str,
+    severity: Severity,
+    message: String,
+    location: Option<CellLocation>
+}
+
+impl IceMessage {
+
+    fn new(rule: &'static str, severity: Severity, message: String, location: Option<CellLocation>) -> IceMessage
+    {
+        IceMessage {
+            rule,
+            severity,
+            message,
+            location
+        }
+    }
+
+    #[doc = "Returns the id of the rule that produced the message."]
+    pub fn rule(&self) -> &str {
+        self.rule
+    }
+
+    #[doc = "Returns the severity of the message."]
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    #[doc = "Returns the description of the problem."]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    #[doc = "Returns the offending cell, if the problem is tied to one."]
+    pub fn location(&self) -> Option<&CellLocation> {
+        self.location.as_ref()
+    }
+}
+
+impl Display for IceMessage {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "{} {}: {}", self.rule, self.severity, self.message)?;
+        if let Some(location) = &self.location
+        {
+            write!(fmt, " ({})", location)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[doc = "A validation rule: an ICE (Internal Consistency Evaluator) equivalent, or a check specific to this crate prefixed `MR`."]
+pub struct IceRule {
+    id: &'static str,
+    description: &'static str,
+    check: fn(&MsiPackage) -> Result<Vec<IceMessage>>
+}
+
+impl IceRule {
+
+    #[doc = "Returns the id of the rule, e.g. `ICE03`."]
+    pub fn id(&self) -> &'static str {
+        self.id
+    }
+
+    #[doc = "Returns a one-line description of what the rule checks."]
+    pub fn description(&self) -> &'static str {
+        self.description
+    }
+
+    #[doc = "Runs the rule against a package."]
+    pub fn check(&self, package: &MsiPackage) -> Result<Vec<IceMessage>> {
+        (self.check)(package)
+    }
+}
+
+#[doc = "All rules known to the validation engine, in the order they run."]
+pub const RULES: &[IceRule] = &[
+    IceRule { id: "ICE03", description: "foreign keys and filenames match the _Validation table", check: ice03 },
+    IceRule { id: "ICE08", description: "component GUIDs are unique", check: ice08 },
+    IceRule { id: "ICE27", description: "standard actions are present and correctly ordered", check: ice27 },
+    IceRule { id: "ICE82", description: "actions do not share sequence numbers", check: ice82 },
+    IceRule { id: "MR001", description: "properties used in conditions and formatted fields are defined", check: mr001 },
+    IceRule { id: "MR002", description: "formatted [#File], [!File] and [$Component] references resolve", check: mr002 },
+    IceRule { id: "MR003", description: "directory references resolve", check: mr003 }
+];
+
+#[doc = "Selects which rules run. Rule ids are matched case-insensitively; an empty include list selects every rule."]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RuleFilter {
+    include: Vec<String>,
+    exclude: Vec<String>
+}
+
+impl RuleFilter {
+
+    #[doc = "Runs only the given rules."]
+    pub fn include<I: IntoIterator<Item = S>, S: AsRef<str>>(mut self, rules: I) -> Self
+    {
+        self.include.extend(rules.into_iter().map(|rule| rule.as_ref().trim().to_ascii_uppercase()));
+        self
+    }
+
+    #[doc = "Skips the given rules."]
+    pub fn exclude<I: IntoIterator<Item = S>, S: AsRef<str>>(mut self, rules: I) -> Self
+    {
+        self.exclude.extend(rules.into_iter().map(|rule| rule.as_ref().trim().to_ascii_uppercase()));
+        self
+    }
+
+    #[doc = "Returns a boolean value indicating whether the rule with the given id is selected."]
+    pub fn matches(&self, id: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|rule| rule == id)) && !self.exclude.iter().any(|rule| rule == id)
+    }
+
+    #[doc = "Fails if the filter names a rule that does not exist, which is usually a typo."]
+    pub fn check_rules(&self) -> Result<()>
+    {
+        match self.include.iter().chain(&self.exclude).find(|rule| !RULES.iter().any(|known| known.id == rule.as_str()))
+        {
+            Some(unknown) => Err(Error::NotFound(format!("validation rule '{}'", unknown))),
+            None => Ok(())
+        }
+    }
+}
+
+#[doc = "Runs the selected rules against a package and returns their messages in rule order."]
+pub fn validate(package: &MsiPackage, filter: &RuleFilter) -> Result<Vec<IceMessage>>
+{
+    filter.check_rules()?;
+
+    let mut messages = Vec::new();
+    for rule in RULES.iter().filter(|rule| filter.matches(rule.id))
+    {
+        messages.extend(rule.check(package)?);
+    }
+
+    Ok(messages)
+}
+
+#[doc = "Sorts messages by rule, location and message, so the order no longer depends on the storage order of the rows that produced them."]
+pub fn sort_messages(messages: &mut [IceMessage])
+{
+    messages.sort_by(|first, second| (first.rule, &first.location, &first.message).cmp(&(second.rule, &second.location, &second.message)));
+}
+
+#[doc = "Renders validation messages as a JSON document following `schema::FINDINGS`."]
+pub fn findings_json(messages: &[IceMessage]) -> String
+{
+    let findings: Vec<String> = messages.iter().map(|message| {
+        let location = match &message.location
+        {
+            Some(location) => format!("{{ \"table\": {}, \"column\": {}, \"key\": {}, \"row\": {} }}",
+                json::string(location.table()), json::string(location.column()), json::string(location.key()), location.row()),
+            None => "null".to_string()
+        };
+        format!("    {{ \"rule\": {}, \"severity\": \"{}\", \"message\": {}, \"location\": {} }}",
+            json::string(message.rule), message.severity, json::string(&message.message), location)
+    }).collect();
+
+    format!("{{\n  \"$schema\": {},\n  \"findings\": [{}]\n}}\n", json::string(schema::FINDINGS.id()),
+        if findings.is_empty() { String::new() } else { format!("\n{}\n  ", findings.join(",\n")) })
+}
+
+fn ice03(package: &MsiPackage) -> Result<Vec<IceMessage>>
+{
+    let mut messages: Vec<IceMessage> = validation::find_dangling_foreign_keys(package)?.into_iter()
+        .map(|reference| IceMessage::new("ICE03", Severity::Error,
+            format!("'{}' not found in {}", reference.value(), reference.target()), Some(reference.location().clone())))
+        .collect();
+    messages.extend(validation::find_invalid_filenames(package)?.into_iter()
+        .map(|invalid| IceMessage::new("ICE03", Severity::Error,
+            format!("invalid file name '{}': {}", invalid.name(), invalid.error()), Some(invalid.location().clone()))));
+
+    Ok(messages)
+}
+
+fn ice08(package: &MsiPackage) -> Result<Vec<IceMessage>>
+{
+    let table = match package.optional_table("Component")?
+    {
+        Some(table) => table,
+        None => return Ok(Vec::new())
+    };
+
+    let mut first: HashMap<String, String> = HashMap::new();
+    let mut messages = Vec::new();
+    for row in table.rows()
+    {
+        let (component, guid) = match (row.str("Component"), row.str("ComponentId"))
+        {
+            (Some(component), Some(guid)) => (component, guid),
+            _ => continue
+        };
+
+        match first.get(&guid.to_ascii_uppercase())
+        {
+            Some(other) => messages.push(IceMessage::new("ICE08", Severity::Error,
+                format!("component GUID {} is also used by component {}", guid, other), Some(CellLocation::of(&row, "ComponentId")))),
+            None => { first.insert(guid.to_ascii_uppercase(), component.to_string()); }
+        }
+    }
+
+    Ok(messages)
+}
+
+fn ice27(package: &MsiPackage) -> Result<Vec<IceMessage>>
+{
+    Ok(sequence::find_anomalies(package)?.into_iter()
+        .filter(|anomaly| !matches!(anomaly.kind(), AnomalyKind::DuplicateSequence(_)))
+        .map(|anomaly| {
+            let severity = match anomaly.kind()
+            {
+                AnomalyKind::AfterInstallFinalize => Severity::Warning,
+                _ => Severity::Error
+            };
+            match anomaly.origin()
+            {
+                Some(origin) => IceMessage::new("ICE27", severity, format!("{}: {}", anomaly.action(), anomaly.kind()), Some(CellLocation::new(origin.clone(), "Action"))),
+                None => IceMessage::new("ICE27", severity, format!("{}: {} in {}", anomaly.action(), anomaly.kind(), anomaly.table()), None)
+            }
+        })
+        .collect())
+}
+
+fn ice82(package: &MsiPackage) -> Result<Vec<IceMessage>>
+{
+    Ok(sequence::find_anomalies(package)?.into_iter()
+        .filter(|anomaly| matches!(anomaly.kind(), AnomalyKind::DuplicateSequence(_)))
+        .map(|anomaly| IceMessage::new("ICE82", Severity::Warning,
+            format!("{}: {}", anomaly.action(), anomaly.kind()), anomaly.origin().map(|origin| CellLocation::new(origin.clone(), "Sequence"))))
+        .collect())
+}
+
+fn mr001(package: &MsiPackage) -> Result<Vec<IceMessage>>
+{
+    Ok(validation::find_undefined_properties(package)?.into_iter()
+        .map(|undefined| IceMessage::new("MR001", Severity::Warning,
+            format!("property '{}' is never defined", undefined.property()), Some(undefined.location().clone())))
+        .collect())
+}
+
+fn mr002(package: &MsiPackage) -> Result<Vec<IceMessage>>
+{
+    Ok(validation::find_broken_formatted_references(package)?.into_iter()
+        .map(|reference| IceMessage::new("MR002", Severity::Error,
+            format!("'{}' not found in {}", reference.value(), reference.target()), Some(reference.location().clone())))
+        .collect())
+}
+
+fn mr003(package: &MsiPackage) -> Result<Vec<IceMessage>>
+{
+    Ok(validation::find_broken_directory_references(package)?.into_iter()
+        .map(|reference| IceMessage::new("MR003", Severity::Error,
+            format!("directory '{}' not found", reference.value()), Some(reference.location().clone())))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::testutil::TestPackage;
+
+    #[test]
+    fn test_validate()
+    {
+        let package = TestPackage::new("ice", |builder| {
+            builder.table("Component", vec![
+                msi::Column::build("Component").primary_key().id_string(72),
+                msi::Column::build("ComponentId").nullable().string(38),
+                msi::Column::build("Directory_").id_string(72),
+                msi::Column::build("Attributes").int16(),
+                msi::Column::build("Condition").nullable().string(255),
+                msi::Column::build("KeyPath").nullable().id_string(72)
+            ], vec![
+                vec![msi::Value::from("Main"), msi::Value::from("{11111111-2222-3333-4444-555555555555}"), msi::Value::from("TARGETDIR"), msi::Value::Int(0), msi::Value::Null, msi::Value::Null],
+                vec![msi::Value::from("Copy"), msi::Value::from("{11111111-2222-3333-4444-555555555555}"), msi::Value::from("TARGETDIR"), msi::Value::Int(0), msi::Value::from("NOT FOO"), msi::Value::Null]
+            ]);
+            builder.table("Directory", vec![
+                msi::Column::build("Directory").primary_key().id_string(72),
+                msi::Column::build("Directory_Parent").nullable().id_string(72),
+                msi::Column::build("DefaultDir").text_string(255)
+            ], vec![
+                vec![msi::Value::from("TARGETDIR"), msi::Value::Null, msi::Value::from("SourceDir")]
+            ]);
+        });
+        let package = MsiPackage::open(package.path()).unwrap();
+
+        let messages = validate(&package, &RuleFilter::default().include(["ice08", "MR001"])).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].rule(), "ICE08");
+        assert_eq!(messages[0].severity(), Severity::Error);
+        assert_eq!(messages[0].location().unwrap().key(), "Main");
+        assert_eq!(messages[0].location().unwrap().row(), 1);
+        assert_eq!(messages[1].to_string(), "MR001 warning: property 'FOO' is never defined (Component.Condition [Copy])");
+
+        assert!(validate(&package, &RuleFilter::default().exclude(["ICE08", "MR001"])).unwrap().iter().all(|message| message.rule() != "ICE08"));
+        assert!(validate(&package, &RuleFilter::default().include(["ICE99"])).is_err());
+        assert!(Severity::Warning < Severity::Error);
+
+        let mut sorted = messages.clone();
+        sort_messages(&mut sorted);
+        assert_eq!(sorted[0].rule(), "ICE08");
+
+        let json = findings_json(&messages);
+        assert!(json.contains("\"$schema\": \"urn:msi-reader:findings:v1\""));
+        assert!(json.contains("{ \"rule\": \"ICE08\", \"severity\": \"error\", \"message\": "));
+        assert!(json.contains("\"location\": { \"table\": \"Component\", \"column\": \"Condition\", \"key\": \"Copy\", \"row\": 0 }"));
+    }
+}
@@ -0,0 +1,606 @@
+use std::collections::HashSet;
+use std::fs::{ self, OpenOptions };
+use std::io::Write;
+use std::path::{ Path, PathBuf };
+
+use crate::codepage;
+use crate::error::{ Error, Result };
+use crate::package::MsiPackage;
+use crate::summary::{ PropertyValue, PID_CHARCOUNT, PID_CODEPAGE, PID_CREATE_DTM, PID_LASTPRINTED, PID_LASTSAVE_DTM, PID_PAGECOUNT, PID_SECURITY, PID_WORDCOUNT };
+use crate::table::{ Column, ColumnType, Value };
+use crate::writer;
+
+#[doc = "The name of the pseudo-table whose archive sets the codepage of the database, e.g. `_ForceCodepage.idt`."]
+pub const FORCE_CODEPAGE: &str = "_ForceCodepage";
+#[doc = "The name of the pseudo-table whose archive holds the summary information, one property per row."]
+pub const SUMMARY_INFORMATION: &str = "_SummaryInformation";
+
+const VALIDATION: &str = "_Validation";
+const EXTENSION: &str = "idt";
+const BINARY_EXTENSION: &str = "ibd";
+const UTF8_CODEPAGE: u32 = 65001;
+
+// Characters that would break the line and field structure are stored as control characters.
+const ESCAPES: [(char, char); 3] = [('\t', '\u{15}'), ('\r', '\u{11}'), ('\n', '\u{19}')];
+
+// Seconds between 1601-01-01, the FILETIME epoch, and 1970-01-01.
+const FILETIME_UNIX_SECONDS: i64 = 11_644_473_600;
+const FILETIME_TICKS_PER_SECOND: u64 = 10_000_000;
+
+#[doc = "A table read from an `.idt` archive file, the tab-delimited text format of `msidb.exe` and `MsiDatabaseImport`."]
+#[doc = ""]
+#[doc = "The first line holds the column names, the second their types (`s72`, `l0`, `i2`, `i4`, `v0`; upper case when nullable) and the third the table name followed by its primary key columns, prefixed by the codepage of the file when it contains text beyond ASCII. Cells of binary columns name a file in the subdirectory named after the table."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct IdtTable {
+    name: String,
+    codepage: Option<u32>,
+    columns: Vec<Column>,
+    rows: Vec<Vec<Value>>
+}
+
+impl IdtTable {
+
+    #[doc = "Parses the contents of an archive file. Text is decoded with the codepage named in the file, or `codepage` if it names none."]
+    pub fn parse(data: &[u8], codepage: u32) -> Result<IdtTable>
+    {
+        let (codepage_field, _) = header(data)?;
+        let text = codepage::decode(codepage_field.unwrap_or(codepage), data)
+            .ok_or_else(|| Error::invalid(format!("codepage {} is not supported", codepage_field.unwrap_or(codepage))))?;
+
+        let mut lines = text.split('\n').map(|line| line.strip_suffix('\r').unwrap_or(line));
+        let names: Vec<&str> = lines.next().unwrap_or_default().split('\t').collect();
+        let types: Vec<&str> = lines.next().unwrap_or_default().split('\t').collect();
+        let mut keys = lines.next().unwrap_or_default().split('\t');
+        if codepage_field.is_some()
+        {
+            keys.next();
+        }
+        let name = keys.next().unwrap_or_default().to_string();
+        let keys: Vec<&str> = keys.collect();
+
+        if name == FORCE_CODEPAGE
+        {
+            return Ok(IdtTable {
+                name,
+                codepage: codepage_field,
+                columns: Vec::new(),
+                rows: Vec::new()
+            });
+        }
+        if name.is_empty() || names.len() != types.len()
+        {
+            return Err(Error::invalid(format!("'{}' does not have the three header lines of an archive file", name)));
+        }
+        if let Some(key) = keys.iter().find(|key| !names.contains(key))
+        {
+            return Err(Error::invalid(format!("primary key '{}' of table '{}' is not a column", key, name)));
+        }
+
+        let columns = names.iter().zip(&types)
+            .map(|(column, column_type)| parse_column(column, column_type, keys.contains(column)))
+            .collect::<Result<Vec<Column>>>()?;
+
+        let mut rows = Vec::new();
+        for (index, line) in lines.enumerate().filter(|(_, line)| !line.is_empty())
+        {
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() != columns.len()
+            {
+                return Err(Error::invalid(format!("row {} of table '{}' has {} fields instead of {}", index + 1, name, fields.len(), columns.len())));
+            }
+            rows.push(columns.iter().zip(fields).map(|(column, field)| parse_value(column, field)).collect::<Result<Vec<Value>>>()?);
+        }
+
+        Ok(IdtTable {
+            name,
+            codepage: codepage_field,
+            columns,
+            rows
+        })
+    }
+
+    #[doc = "Reads the archive file at `path`, see `parse`."]
+    pub fn read<P: AsRef<Path>>(path: P, codepage: u32) -> Result<IdtTable>
+    {
+        IdtTable::parse(&fs::read(path)?, codepage)
+    }
+
+    #[doc = "Returns the name of the table, taken from the third header line rather than the file name, which `msidb.exe` may shorten."]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[doc = "Returns the codepage named in the file, or the one `_ForceCodepage` sets."]
+    pub fn codepage(&self) -> Option<u32> {
+        self.codepage
+    }
+
+    #[doc = "Returns the columns of the table; `_ForceCodepage` has none."]
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    #[doc = "Returns the rows in file order. Cells of binary columns hold the name of their data file."]
+    pub fn rows(&self) -> &[Vec<Value>] {
+        &self.rows
+    }
+}
+
+#[doc = "Writes the table to `<table>.idt` in `directory` and returns its path, with rows sorted by primary key. The data of binary cells is written to `<table>/<key>.ibd`."]
+pub fn export_table<P: AsRef<Path>>(package: &MsiPackage, name: &str, directory: P) -> Result<PathBuf>
+{
+    let directory = directory.as_ref();
+    let table = package.table(name)?;
+    let streams: HashSet<String> = package.stream_names()?.into_iter().collect();
+
+    let mut body = String::new();
+    for row in table.sorted_rows()
+    {
+        let mut fields = Vec::with_capacity(table.columns().len());
+        for (column, value) in table.columns().iter().zip(row.values())
+        {
+            match value
+            {
+                Value::Str(stream) if column.column_type() == ColumnType::Binary => {
+                    // a cell whose stream is missing is written as null, as there is no data to refer to
+                    if streams.contains(&**stream)
+                    {
+                        let file = format!("{}.{}", row.key(), BINARY_EXTENSION);
+                        fs::create_dir_all(directory.join(name))?;
+                        fs::write(directory.join(name).join(&file), package.read_stream(stream)?)?;
+                        fields.push(file);
+                    }
+                    else
+                    {
+                        fields.push(String::new());
+                    }
+                },
+                value => fields.push(escape(&value.to_string()))
+            }
+        }
+        body.push_str(&fields.join("\t"));
+        body.push_str("\r\n");
+    }
+
+    let names: Vec<&str> = table.columns().iter().map(|column| column.name()).collect();
+    let types: Vec<String> = table.columns().iter().map(column_type).collect();
+    let keys: Vec<&str> = table.columns().iter().filter(|column| column.is_primary_key()).map(|column| column.name()).collect();
+    write_archive(directory, name, &names, &types, &keys, &body, package.strings().codepage())
+}
+
+#[doc = "Writes the summary information to `_SummaryInformation.idt` in `directory` and returns its path. Times are written as `yyyy/mm/dd hh:mm:ss` in UTC."]
+pub fn export_summary<P: AsRef<Path>>(package: &MsiPackage, directory: P) -> Result<PathBuf>
+{
+    let mut body = String::new();
+    for (id, value) in package.summary().properties()
+    {
+        let value = match value
+        {
+            PropertyValue::Empty | PropertyValue::Null => continue,
+            PropertyValue::I2(value) => value.to_string(),
+            PropertyValue::I4(value) => value.to_string(),
+            PropertyValue::Str(value) => escape(value),
+            PropertyValue::FileTime(value) => format_filetime(*value)
+        };
+        body.push_str(&format!("{}\t{}\r\n", id, value));
+    }
+
+    write_archive(directory.as_ref(), SUMMARY_INFORMATION, &["PropertyId", "Value"], &["i2".to_string(), "l255".to_string()], &["PropertyId"], &body, package.strings().codepage())
+}
+
+#[doc = "Writes `_ForceCodepage.idt`, which sets the codepage of the database on import, to `directory` and returns its path."]
+pub fn export_codepage<P: AsRef<Path>>(package: &MsiPackage, directory: P) -> Result<PathBuf>
+{
+    let path = directory.as_ref().join(format!("{}.{}", FORCE_CODEPAGE, EXTENSION));
+    fs::write(&path, format!("\r\n\r\n{}\t{}\r\n", package.strings().codepage(), FORCE_CODEPAGE))?;
+    Ok(path)
+}
+
+#[doc = "Exports every table, the summary information and the codepage to `directory`, as `msidb.exe -e *` together with `_SummaryInformation` and `_ForceCodepage` does, and returns the paths of the archive files."]
+pub fn export<P: AsRef<Path>>(package: &MsiPackage, directory: P) -> Result<Vec<PathBuf>>
+{
+    let directory = directory.as_ref();
+    fs::create_dir_all(directory)?;
+
+    let mut paths = Vec::new();
+    for name in package.table_names()
+    {
+        paths.push(export_table(package, name, directory)?);
+    }
+    paths.push(export_summary(package, directory)?);
+    paths.push(export_codepage(package, directory)?);
+    Ok(paths)
+}
+
+#[doc = "Imports archive files into the package at `package`, replacing tables of the same name, and returns the names of the imported tables."]
+#[doc = ""]
+#[doc = "`_ForceCodepage` is applied first and sets the codepage used for files that name none. A table file naming a codepage sets the codepage of a neutral database, as `msidb.exe` does. `_Validation` replaces the rows of the existing catalog after all other tables, and `_SummaryInformation` sets the listed properties."]
+pub fn import<P: AsRef<Path>, Q: AsRef<Path>>(package: P, files: &[Q]) -> Result<Vec<String>>
+{
+    let mut archives = Vec::with_capacity(files.len());
+    for file in files
+    {
+        let data = fs::read(file)?;
+        let (_, name) = header(&data)?;
+        let rank = match name.as_str()
+        {
+            FORCE_CODEPAGE => 0,
+            VALIDATION => 2,
+            SUMMARY_INFORMATION => 3,
+            _ => 1
+        };
+        archives.push((rank, file.as_ref().parent().map(Path::to_path_buf).unwrap_or_default(), data));
+    }
+    archives.sort_by_key(|(rank, _, _)| *rank);
+
+    let mut writer = writer::open(&package)?;
+    let mut imported = Vec::new();
+    let mut summary = Vec::new();
+    for (rank, directory, data) in archives
+    {
+        let table = IdtTable::parse(&data, writer.database_codepage().id() as u32)?;
+        if let Some(codepage) = table.codepage.filter(|_| rank == 0 || writer.database_codepage().id() == 0)
+        {
+            writer.set_database_codepage(msi::CodePage::from_id(codepage as i32)
+                .ok_or_else(|| Error::invalid(format!("codepage {} is not supported", codepage)))?);
+        }
+
+        match rank
+        {
+            0 => {},
+            2 => {
+                writer.delete_rows(msi::Delete::from(VALIDATION))?;
+                insert_rows(&mut writer, &table, &directory)?;
+            },
+            3 => summary = table.rows,
+            _ => {
+                if writer.has_table(&table.name)
+                {
+                    writer.drop_table(&table.name)?;
+                    let prefix = format!("{}.", table.name);
+                    let streams: Vec<String> = writer.streams().filter(|stream| stream.starts_with(&prefix)).collect();
+                    for stream in streams
+                    {
+                        writer.remove_stream(&stream)?;
+                    }
+                }
+                writer.create_table(table.name.as_str(), table.columns.iter().map(writer::column).collect())?;
+                insert_rows(&mut writer, &table, &directory)?;
+            }
+        }
+        imported.push(table.name);
+    }
+    writer.flush()?;
+    drop(writer);
+
+    // the writer rewrites the summary information when the codepage changes, so the imported properties are applied after the flush
+    if imported.iter().any(|name| name == SUMMARY_INFORMATION)
+    {
+        let mut info = MsiPackage::open(&package)?.summary().clone();
+        for row in summary
+        {
+            let id = match row.first().and_then(Value::as_int)
+            {
+                Some(id) if id > 0 => id as u32,
+                _ => return Err(Error::invalid("_SummaryInformation has a row without a property id"))
+            };
+            match row.get(1).and_then(Value::as_str)
+            {
+                Some(text) => info.set(id, summary_value(id, text)?),
+                None => {
+                    info.remove(id);
+                }
+            }
+        }
+        info.write(&package)?;
+    }
+
+    Ok(imported)
+}
+
+#[doc = "Creates an installer package at `path` from every `.idt` file in `directory`, as `msidb.exe -i *` into a new database does, and returns the names of the imported tables."]
+pub fn create<P: AsRef<Path>, Q: AsRef<Path>>(path: P, directory: Q) -> Result<Vec<String>>
+{
+    let mut files = Vec::new();
+    for entry in fs::read_dir(directory)?
+    {
+        let file = entry?.path();
+        if file.extension().is_some_and(|extension| extension.eq_ignore_ascii_case(EXTENSION))
+        {
+            files.push(file);
+        }
+    }
+    files.sort();
+
+    let file = OpenOptions::new().read(true).write(true).create(true).truncate(true).open(&path)?;
+    msi::Package::create(msi::PackageType::Installer, file)?.flush()?;
+    import(path, &files)
+}
+
+// Returns the codepage and table name of the third header line, which are ASCII in any codepage.
+fn header(data: &[u8]) -> Result<(Option<u32>, String)>
+{
+    let line = data.split(|byte| *byte == b'\n').nth(2)
+        .ok_or_else(|| Error::invalid("archive file has fewer than three header lines"))?;
+    let line = String::from_utf8_lossy(line);
+    let mut fields = line.trim_end_matches('\r').split('\t');
+    let first = fields.next().unwrap_or_default();
+    match first.parse::<u32>()
+    {
+        Ok(codepage) => Ok((Some(codepage), fields.next().unwrap_or_default().to_string())),
+        Err(_) => Ok((None, first.to_string()))
+    }
+}
+
+fn parse_column(name: &str, column_type: &str, primary_key: bool) -> Result<Column>
+{
+    let invalid = || Error::invalid(format!("column '{}' has an invalid type '{}'", name, column_type));
+    let mut characters = column_type.chars();
+    let kind = characters.next().ok_or_else(invalid)?;
+    let size: usize = characters.as_str().parse().map_err(|_| invalid())?;
+
+    let column_type = match (kind.to_ascii_lowercase(), size)
+    {
+        ('s', size) | ('l', size) if size <= 255 => ColumnType::Str(size),
+        ('i', 1) | ('i', 2) => ColumnType::Int16,
+        ('i', 4) => ColumnType::Int32,
+        ('v', 0) => ColumnType::Binary,
+        _ => return Err(invalid())
+    };
+    Ok(Column::new(name, column_type, kind.is_ascii_uppercase(), primary_key, kind.eq_ignore_ascii_case(&'l')))
+}
+
+fn column_type(column: &Column) -> String
+{
+    let code = match column.column_type()
+    {
+        ColumnType::Int16 => "i2".to_string(),
+        ColumnType::Int32 => "i4".to_string(),
+        ColumnType::Str(size) if column.is_localizable() => format!("l{}", size),
+        ColumnType::Str(size) => format!("s{}", size),
+        ColumnType::Binary => "v0".to_string()
+    };
+
+    if column.is_nullable()
+    {
+        code.to_ascii_uppercase()
+    }
+    else
+    {
+        code
+    }
+}
+
+// Empty fields are null; the format does not distinguish null and empty strings, and neither does the database.
+fn parse_value(column: &Column, field: &str) -> Result<Value>
+{
+    if field.is_empty()
+    {
+        return Ok(Value::Null);
+    }
+
+    match column.column_type()
+    {
+        ColumnType::Int16 | ColumnType::Int32 => field.parse().map(Value::Int)
+            .map_err(|_| Error::invalid(format!("'{}' in column '{}' is not an integer", field, column.name()))),
+        ColumnType::Str(_) | ColumnType::Binary => Ok(Value::from(unescape(field)))
+    }
+}
+
+fn escape(text: &str) -> String
+{
+    ESCAPES.iter().fold(text.to_string(), |text, (character, escaped)| text.replace(*character, &escaped.to_string()))
+}
+
+fn unescape(text: &str) -> String
+{
+    ESCAPES.iter().fold(text.to_string(), |text, (character, escaped)| text.replace(*escaped, &character.to_string()))
+}
+
+fn write_archive(directory: &Path, name: &str, names: &[&str], types: &[String], keys: &[&str], body: &str, codepage: u32) -> Result<PathBuf>
+{
+    let columns = format!("{}\r\n{}\r\n", names.join("\t"), types.join("\t"));
+    let keys = format!("{}\t{}\r\n", name, keys.join("\t"));
+
+    // plain ASCII reads the same in every codepage; other text names the codepage it is encoded in
+    let data = if columns.is_ascii() && body.is_ascii()
+    {
+        format!("{}{}{}", columns, keys, body).into_bytes()
+    }
+    else
+    {
+        let codepage = if codepage == 0 { UTF8_CODEPAGE } else { codepage };
+        codepage::encode(codepage, &format!("{}{}\t{}{}", columns, codepage, keys, body))
+            .ok_or_else(|| Error::invalid(format!("codepage {} is not supported", codepage)))?
+    };
+
+    let path = directory.join(format!("{}.{}", name, EXTENSION));
+    fs::write(&path, data)?;
+    Ok(path)
+}
+
+fn insert_rows(writer: &mut writer::Writer, table: &IdtTable, directory: &Path) -> Result<()>
+{
+    let mut rows = Vec::with_capacity(table.rows.len());
+    for row in &table.rows
+    {
+        let mut values = Vec::with_capacity(row.len());
+        for (column, value) in table.columns.iter().zip(row)
+        {
+            match value
+            {
+                Value::Str(file) if column.column_type() == ColumnType::Binary => {
+                    let key: Vec<String> = table.columns.iter().zip(row)
+                        .filter(|(column, _)| column.is_primary_key())
+                        .map(|(_, value)| value.to_string())
+                        .collect();
+                    let stream = format!("{}.{}", table.name, key.join("."));
+                    writer.write_stream(&stream)?.write_all(&fs::read(directory.join(&table.name).join(&**file))?)?;
+                    values.push(msi::Value::Str(stream));
+                },
+                value => values.push(writer::value(value))
+            }
+        }
+        rows.push(values);
+    }
+
+    if !rows.is_empty()
+    {
+        writer.insert_rows(msi::Insert::into(table.name.as_str()).rows(rows))?;
+    }
+    Ok(())
+}
+
+fn summary_value(id: u32, text: &str) -> Result<PropertyValue>
+{
+    let integer = || text.parse::<i32>().map_err(|_| Error::invalid(format!("summary property {} is not an integer: '{}'", id, text)));
+    match id
+    {
+        PID_CODEPAGE => Ok(PropertyValue::I2(integer()? as i16)),
+        PID_PAGECOUNT | PID_WORDCOUNT | PID_CHARCOUNT | PID_SECURITY => Ok(PropertyValue::I4(integer()?)),
+        PID_LASTPRINTED | PID_CREATE_DTM | PID_LASTSAVE_DTM => parse_filetime(text).map(PropertyValue::FileTime)
+            .ok_or_else(|| Error::invalid(format!("summary property {} is not a time: '{}'", id, text))),
+        _ => Ok(PropertyValue::Str(text.to_string()))
+    }
+}
+
+fn format_filetime(filetime: u64) -> String
+{
+    let seconds = (filetime / FILETIME_TICKS_PER_SECOND) as i64 - FILETIME_UNIX_SECONDS;
+    let (days, time) = (seconds.div_euclid(86400), seconds.rem_euclid(86400));
+
+    // civil date from days since 1970-01-01, after Howard Hinnant's algorithm
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}/{:02}/{:02} {:02}:{:02}:{:02}", year, month, day, time / 3600, time / 60 % 60, time % 60)
+}
+
+fn parse_filetime(text: &str) -> Option<u64>
+{
+    let (date, time) = text.trim().split_once(' ').unwrap_or((text.trim(), "0:0:0"));
+    let date: Vec<i64> = date.split('/').map(|field| field.parse().ok()).collect::<Option<Vec<i64>>>()?;
+    let time: Vec<i64> = time.split(':').map(|field| field.parse().ok()).collect::<Option<Vec<i64>>>()?;
+    let (year, month, day) = match date[..]
+    {
+        [year, month, day] if (1..=12).contains(&month) && (1..=31).contains(&day) => (year, month, day),
+        _ => return None
+    };
+    let (hour, minute, second) = match time[..]
+    {
+        [hour, minute, second] if hour < 24 && minute < 60 && second < 60 => (hour, minute, second),
+        _ => return None
+    };
+
+    // days since 1970-01-01 from a civil date, the inverse of format_filetime
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    let seconds = days * 86400 + hour * 3600 + minute * 60 + second + FILETIME_UNIX_SECONDS;
+    if seconds < 0
+    {
+        return None;
+    }
+    Some(seconds as u64 * FILETIME_TICKS_PER_SECOND)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::testutil::TestPackage;
+
+    #[test]
+    fn test_parse_archive()
+    {
+        let data = b"Property\tValue\r\ns72\tL0\r\nProperty\tProperty\r\nProductName\tTwo\x15Lines\x19Here\r\nEmpty\t\r\n";
+        let table = IdtTable::parse(data, 0).unwrap();
+        assert_eq!(table.name(), "Property");
+        assert_eq!(table.codepage(), None);
+        assert!(table.columns()[0].is_primary_key() && !table.columns()[0].is_nullable());
+        assert!(table.columns()[1].is_nullable() && table.columns()[1].is_localizable());
+        assert_eq!(table.rows(), [
+            vec![Value::from("ProductName"), Value::from("Two\tLines\nHere")],
+            vec![Value::from("Empty"), Value::Null]
+        ]);
+
+        let table = IdtTable::parse(b"Name\r\ns10\r\n1252\tWord\tName\r\nCaf\xe9\r\n", 0).unwrap();
+        assert_eq!((table.codepage(), table.rows()[0][0].as_str()), (Some(1252), Some("Café")));
+        assert_eq!(IdtTable::parse(b"\r\n\r\n932\t_ForceCodepage\r\n", 0).unwrap().codepage(), Some(932));
+
+        assert!(IdtTable::parse(b"A\r\nx3\r\nT\tA\r\n", 0).is_err());
+        assert!(IdtTable::parse(b"A\tB\r\ns1\ti2\r\nT\tA\r\nx\tnot\r\n", 0).is_err());
+        assert!(IdtTable::parse(b"A\r\ns1\r\nT\tB\r\n", 0).is_err());
+    }
+
+    #[test]
+    fn test_round_trip()
+    {
+        let package = TestPackage::new("idt-round-trip", |builder| {
+            builder.codepage(msi::CodePage::Windows1252);
+            builder.table("Property", vec![
+                msi::Column::build("Property").primary_key().id_string(72),
+                msi::Column::build("Value").nullable().localizable().text_string(0)
+            ], vec![
+                vec![msi::Value::from("ProductName"), msi::Value::from("Café\tDeluxe")],
+                vec![msi::Value::from("Empty"), msi::Value::Null]
+            ]);
+            builder.table("Binary", vec![
+                msi::Column::build("Name").primary_key().id_string(72),
+                msi::Column::build("Data").binary()
+            ], vec![
+                vec![msi::Value::from("Icon"), msi::Value::from("Binary.Icon")]
+            ]);
+            builder.stream("Binary.Icon", b"icon");
+        });
+        let source = MsiPackage::open(package.path()).unwrap();
+
+        let directory = std::env::temp_dir().join(format!("msi-reader-idt-{}", std::process::id()));
+        let paths = export(&source, &directory).unwrap();
+        assert!(paths.contains(&directory.join("_ForceCodepage.idt")));
+        assert_eq!(fs::read(directory.join("Binary").join("Icon.ibd")).unwrap(), b"icon");
+        assert_eq!(fs::read(directory.join("Property.idt")).unwrap(),
+            b"Property\tValue\r\ns72\tL0\r\n1252\tProperty\tProperty\r\nEmpty\t\r\nProductName\tCaf\xe9\x15Deluxe\r\n");
+
+        let path = directory.join("round-trip.msi");
+        let imported = create(&path, &directory).unwrap();
+        assert_eq!(imported.first().map(String::as_str), Some(FORCE_CODEPAGE));
+        assert_eq!(imported.last().map(String::as_str), Some(SUMMARY_INFORMATION));
+
+        let copy = MsiPackage::open(&path).unwrap();
+        assert_eq!(copy.strings().codepage(), 1252);
+        for name in ["Property", "Binary", "_Validation"]
+        {
+            let (table, original) = (copy.table(name).unwrap(), source.table(name).unwrap());
+            assert_eq!(table.columns(), original.columns(), "{}", name);
+            assert_eq!(table.sorted_rows().iter().map(|row| row.values()).collect::<Vec<_>>(), original.sorted_rows().iter().map(|row| row.values()).collect::<Vec<_>>(), "{}", name);
+        }
+        assert_eq!(copy.read_stream("Binary.Icon").unwrap(), b"icon");
+        assert_eq!(copy.summary().package_code(), source.summary().package_code());
+        assert_eq!(copy.summary().create_time().map(|time| time.duration_since(std::time::UNIX_EPOCH).unwrap().as_secs()),
+            source.summary().create_time().map(|time| time.duration_since(std::time::UNIX_EPOCH).unwrap().as_secs()));
+
+        let _ = fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn test_filetime_text()
+    {
+        assert_eq!(format_filetime(0), "1601/01/01 00:00:00");
+        assert_eq!(format_filetime(132_223_104_000_000_000), "2020/01/01 00:00:00");
+        assert_eq!(parse_filetime("2020/01/01 00:00:00"), Some(132_223_104_000_000_000));
+        assert_eq!(parse_filetime("2024/02/29 13:45:07").map(format_filetime).as_deref(), Some("2024/02/29 13:45:07"));
+        assert_eq!(parse_filetime("2024/13/01 00:00:00"), None);
+    }
+}
@@ -0,0 +1,420 @@
+use std::collections::HashMap;
+use std::ffi::c_void;
+use std::fmt::Display;
+use std::path::{ Path, PathBuf };
+use std::ptr;
+
+use crate::directory::{ MsiName, NameFormat };
+use crate::error::{ Error, Result };
+use crate::package::MsiPackage;
+use crate::win32::{ check, read_string, wide, ERROR_NO_MORE_ITEMS };
+
+const ERROR_UNKNOWN_PRODUCT: u32 = 1605;
+
+const INSTALLSTATE_MOREDATA: i32 = -3;
+const INSTALLSTATE_LOCAL: i32 = 3;
+
+const HKEY_CLASSES_ROOT: isize = 0x8000_0000u32 as i32 as isize;
+const HKEY_CURRENT_USER: isize = 0x8000_0001u32 as i32 as isize;
+const HKEY_LOCAL_MACHINE: isize = 0x8000_0002u32 as i32 as isize;
+const HKEY_USERS: isize = 0x8000_0003u32 as i32 as isize;
+
+const RRF_RT_ANY: u32 = 0xffff;
+const RRF_SUBKEY_WOW6464KEY: u32 = 0x0001_0000;
+const RRF_SUBKEY_WOW6432KEY: u32 = 0x0002_0000;
+const RRF_NOEXPAND: u32 = 0x1000_0000;
+const REG_SZ: u32 = 1;
+const REG_EXPAND_SZ: u32 = 2;
+const REG_DWORD: u32 = 4;
+
+// msidbComponentAttributes64bit
+const COMPONENT_64BIT: i32 = 0x100;
+
+#[repr(C)]
+struct FileHashInfo {
+    size: u32,
+    data: [u32; 4]
+}
+
+#[allow(non_snake_case)]
+#[link(name = "msi")]
+extern "system" {
+    fn MsiEnumProductsW(iProductIndex: u32, lpProductBuf: *mut u16) -> u32;
+    fn MsiGetProductInfoW(szProduct: *const u16, szAttribute: *const u16, lpValueBuf: *mut u16, pcchValueBuf: *mut u32) -> u32;
+    fn MsiGetComponentPathW(szProduct: *const u16, szComponent: *const u16, lpPathBuf: *mut u16, pcchBuf: *mut u32) -> i32;
+    fn MsiGetFileHashW(szFilePath: *const u16, dwOptions: u32, pHash: *mut FileHashInfo) -> u32;
+    fn MsiGetFileVersionW(szFilePath: *const u16, lpVersionBuf: *mut u16, pcchVersionBuf: *mut u32, lpLangBuf: *mut u16, pcchLangBuf: *mut u32) -> u32;
+}
+
+#[allow(non_snake_case)]
+#[link(name = "advapi32")]
+extern "system" {
+    fn RegGetValueW(hkey: isize, lpSubKey: *const u16, lpValue: *const u16, dwFlags: u32, pdwType: *mut u32, pvData: *mut c_void, pcbData: *mut u32) -> i32;
+}
+
+#[doc = "A product registered with Windows Installer on this machine."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct InstalledProduct {
+    product_code: String,
+    name: String,
+    version: String,
+    install_location: Option<String>,
+    local_package: Option<PathBuf>
+}
+
+impl InstalledProduct {
+
+    fn query(product_code: &str) -> Result<InstalledProduct>
+    {
+        let product = wide(product_code);
+        let optional = |attribute| product_info(&product, attribute).ok().filter(|value| !value.is_empty());
+        Ok(InstalledProduct {
+            product_code: product_code.to_string(),
+            name: optional("InstalledProductName").unwrap_or_default(),
+            version: product_info(&product, "VersionString")?,
+            install_location: optional("InstallLocation"),
+            local_package: optional("LocalPackage").map(PathBuf::from)
+        })
+    }
+
+    #[doc = "Returns the ProductCode of the product."]
+    pub fn product_code(&self) -> &str {
+        &self.product_code
+    }
+
+    #[doc = "Returns the name of the product, empty if none is registered."]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[doc = "Returns the installed version, e.g. `1.2.3`."]
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    #[doc = "Returns the install location, if the package set the ARPINSTALLLOCATION property."]
+    pub fn install_location(&self) -> Option<&str> {
+        self.install_location.as_deref()
+    }
+
+    #[doc = "Returns the path of the package cached by Windows Installer, e.g. `C:\\Windows\\Installer\\1a2b3c.msi`."]
+    pub fn local_package(&self) -> Option<&Path> {
+        self.local_package.as_deref()
+    }
+
+    #[doc = "Returns a boolean value indicating whether the package has the ProductCode of this product."]
+    pub fn matches(&self, package: &MsiPackage) -> Result<bool> {
+        Ok(package.property("ProductCode")?.is_some_and(|code| code.eq_ignore_ascii_case(&self.product_code)))
+    }
+}
+
+#[doc = "Enumerates the products installed on this machine, visible to the current user, in the order Windows Installer reports them."]
+pub fn installed_products() -> Result<Vec<InstalledProduct>>
+{
+    let mut products = Vec::new();
+    let mut buffer = [0u16; 39];
+    for index in 0..
+    {
+        match unsafe { MsiEnumProductsW(index, buffer.as_mut_ptr()) }
+        {
+            ERROR_NO_MORE_ITEMS => break,
+            status => check(status)?
+        }
+
+        let product_code = String::from_utf16_lossy(&buffer[..38]);
+        // products can be removed while enumerating
+        match InstalledProduct::query(&product_code)
+        {
+            Err(Error::Io(error)) if error.raw_os_error() == Some(ERROR_UNKNOWN_PRODUCT as i32) => continue,
+            result => products.push(result?)
+        }
+    }
+
+    Ok(products)
+}
+
+#[doc = "Returns the installed product with the given ProductCode, or `None` if it is not installed."]
+pub fn installed_product(product_code: &str) -> Result<Option<InstalledProduct>>
+{
+    match InstalledProduct::query(product_code)
+    {
+        Err(Error::Io(error)) if error.raw_os_error() == Some(ERROR_UNKNOWN_PRODUCT as i32) => Ok(None),
+        result => result.map(Some)
+    }
+}
+
+#[doc = "A difference between a package and the installed product."]
+#[derive(Clone, Debug, PartialEq)]
+pub enum InstalledDifference {
+    #[doc = "A file of an installed component is missing: File key, expected path."]
+    MissingFile(String, PathBuf),
+    #[doc = "A file differs from the package: File key, path, what differs."]
+    ModifiedFile(String, PathBuf, String),
+    #[doc = "A registry value of an installed component is missing: Registry key, registry path."]
+    MissingRegistryValue(String, String),
+    #[doc = "A registry value differs from the package: Registry key, registry path, expected value, actual value."]
+    ModifiedRegistryValue(String, String, String, String)
+}
+
+impl Display for InstalledDifference {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self
+        {
+            InstalledDifference::MissingFile(file, path) => write!(fmt, "File [{}] missing: {}", file, path.display()),
+            InstalledDifference::ModifiedFile(file, path, reason) => write!(fmt, "File [{}] modified: {} ({})", file, path.display(), reason),
+            InstalledDifference::MissingRegistryValue(registry, path) => write!(fmt, "Registry [{}] missing: {}", registry, path),
+            InstalledDifference::ModifiedRegistryValue(registry, path, expected, actual) =>
+                write!(fmt, "Registry [{}] modified: {} is '{}', expected '{}'", registry, path, actual, expected)
+        }
+    }
+}
+
+#[doc = "The result of comparing a package with the installed product it describes."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct InstalledReport {
+    product_code: String,
+    installed_version: String,
+    files: usize,
+    registry_values: usize,
+    differences: Vec<InstalledDifference>
+}
+
+impl InstalledReport {
+
+    #[doc = "Returns the ProductCode of the product."]
+    pub fn product_code(&self) -> &str {
+        &self.product_code
+    }
+
+    #[doc = "Returns the version registered for the installed product."]
+    pub fn installed_version(&self) -> &str {
+        &self.installed_version
+    }
+
+    #[doc = "Returns the number of files checked."]
+    pub fn files(&self) -> usize {
+        self.files
+    }
+
+    #[doc = "Returns the number of registry values checked."]
+    pub fn registry_values(&self) -> usize {
+        self.registry_values
+    }
+
+    #[doc = "Returns the missing and modified files and registry values."]
+    pub fn differences(&self) -> &[InstalledDifference] {
+        &self.differences
+    }
+}
+
+#[doc = "Compares a package with the registration of the installed product of the same ProductCode. Only components installed locally are checked. Files are compared by version, or by size and MsiFileHash for unversioned files; registry values are compared when they contain no formatted references."]
+pub fn verify_installed(package: &MsiPackage) -> Result<InstalledReport>
+{
+    let product_code = package.property("ProductCode")?
+        .ok_or_else(|| Error::NotFound("ProductCode property".to_string()))?;
+    let product = wide(&product_code);
+    let installed_version = installed_product(&product_code)?
+        .ok_or_else(|| Error::NotFound(format!("installed product {}", product_code)))?
+        .version;
+    let per_machine = product_info(&product, "AssignmentType")? == "1";
+
+    // component key: (key path, attributes) of components installed locally
+    let mut components: HashMap<String, (String, i32)> = HashMap::new();
+    if let Some(table) = package.optional_table("Component")?
+    {
+        for row in table.rows()
+        {
+            if let (Some(component), Some(guid)) = (row.str("Component"), row.str("ComponentId"))
+            {
+                if let Some(path) = component_path(&product, guid)
+                {
+                    components.insert(component.to_string(), (path, row.int("Attributes").unwrap_or(0)));
+                }
+            }
+        }
+    }
+
+    let hashes: HashMap<String, [u32; 4]> = match package.optional_table("MsiFileHash")?
+    {
+        Some(table) => table.rows()
+            .filter_map(|row| Some((row.str("File_")?.to_string(), [
+                row.int("HashPart1")? as u32, row.int("HashPart2")? as u32, row.int("HashPart3")? as u32, row.int("HashPart4")? as u32
+            ])))
+            .collect(),
+        None => HashMap::new()
+    };
+
+    let mut report = InstalledReport {
+        product_code,
+        installed_version,
+        files: 0,
+        registry_values: 0,
+        differences: Vec::new()
+    };
+
+    if let Some(table) = package.optional_table("File")?
+    {
+        for row in table.rows()
+        {
+            let key = row.str("File").unwrap_or_default();
+            // files live next to the key path of their component, unless that is a registry key
+            let directory = match row.str("Component_").and_then(|component| components.get(component))
+            {
+                Some((path, _)) if !is_registry_path(path) => if path.ends_with('\\') { PathBuf::from(path) } else { Path::new(path).parent().map(Path::to_path_buf).unwrap_or_default() },
+                _ => continue
+            };
+
+            let path = directory.join(MsiName::from(row.str("FileName").unwrap_or_default()).format(NameFormat::Long));
+            report.files += 1;
+            if let Some(reason) = compare_file(&path, row.int("FileSize"), row.str("Version"), hashes.get(key))
+            {
+                report.differences.push(match reason
+                {
+                    None => InstalledDifference::MissingFile(key.to_string(), path),
+                    Some(reason) => InstalledDifference::ModifiedFile(key.to_string(), path, reason)
+                });
+            }
+        }
+    }
+
+    if let Some(table) = package.optional_table("Registry")?
+    {
+        for row in table.rows()
+        {
+            let (key, value, name) = (row.str("Key").unwrap_or_default(), row.str("Value"), row.str("Name"));
+            let attributes = match row.str("Component_").and_then(|component| components.get(component))
+            {
+                Some((_, attributes)) => *attributes,
+                None => continue
+            };
+            // key markers (+, -, *) and values with formatted references or special types cannot be compared offline
+            let expected = match value
+            {
+                Some(value) if ![key, value, name.unwrap_or_default()].iter().any(|text| text.contains('[')) && !value.starts_with("#x") => value,
+                _ => continue
+            };
+            if matches!(name, Some("+") | Some("-") | Some("*"))
+            {
+                continue;
+            }
+
+            let root = match row.int("Root")
+            {
+                Some(0) => HKEY_CLASSES_ROOT,
+                Some(1) => HKEY_CURRENT_USER,
+                Some(2) => HKEY_LOCAL_MACHINE,
+                Some(3) => HKEY_USERS,
+                _ if per_machine => HKEY_LOCAL_MACHINE,
+                _ => HKEY_CURRENT_USER
+            };
+            let view = if attributes & COMPONENT_64BIT != 0 { RRF_SUBKEY_WOW6464KEY } else { RRF_SUBKEY_WOW6432KEY };
+            let registry = row.str("Registry").unwrap_or_default().to_string();
+            let location = format!("{}\\{}", key, name.unwrap_or("(Default)"));
+
+            report.registry_values += 1;
+            match registry_value(root, key, name, view)
+            {
+                None => report.differences.push(InstalledDifference::MissingRegistryValue(registry, location)),
+                Some(actual) if actual != expected.trim_start_matches("#%") =>
+                    report.differences.push(InstalledDifference::ModifiedRegistryValue(registry, location, expected.to_string(), actual)),
+                Some(_) => {}
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+fn product_info(product: &[u16], attribute: &str) -> Result<String>
+{
+    let attribute = wide(attribute);
+    read_string(|buffer, length| unsafe { MsiGetProductInfoW(product.as_ptr(), attribute.as_ptr(), buffer, length) })
+}
+
+fn component_path(product: &[u16], component: &str) -> Option<String>
+{
+    let component = wide(component);
+    let mut buffer = vec![0u16; 260];
+    loop
+    {
+        let mut length = buffer.len() as u32;
+        match unsafe { MsiGetComponentPathW(product.as_ptr(), component.as_ptr(), buffer.as_mut_ptr(), &mut length) }
+        {
+            INSTALLSTATE_LOCAL => return Some(String::from_utf16_lossy(&buffer[..length as usize])),
+            INSTALLSTATE_MOREDATA => buffer.resize(length as usize + 1, 0),
+            _ => return None
+        }
+    }
+}
+
+fn is_registry_path(path: &str) -> bool {
+    // registry key paths are reported as e.g. "02:\Software\..."
+    path.as_bytes().get(2) == Some(&b':') && path.as_bytes()[..2].iter().all(u8::is_ascii_digit)
+}
+
+#[doc = "Returns `None` if the file matches, `Some(None)` if it is missing and `Some(Some(reason))` if it differs."]
+fn compare_file(path: &Path, size: Option<i32>, version: Option<&str>, hash: Option<&[u32; 4]>) -> Option<Option<String>>
+{
+    let metadata = match std::fs::metadata(path)
+    {
+        Ok(metadata) => metadata,
+        Err(_) => return Some(None)
+    };
+    let path = wide(path);
+
+    // companion files name another file key instead of a version
+    if let Some(expected) = version.filter(|version| version.starts_with(|c: char| c.is_ascii_digit()))
+    {
+        let actual = read_string(|buffer, length| unsafe { MsiGetFileVersionW(path.as_ptr(), buffer, length, ptr::null_mut(), ptr::null_mut()) }).unwrap_or_default();
+        return if actual == expected { None } else { Some(Some(format!("version {}, expected {}", actual, expected))) };
+    }
+
+    if let Some(expected) = size.filter(|size| *size as u64 != metadata.len())
+    {
+        return Some(Some(format!("size {}, expected {}", metadata.len(), expected)));
+    }
+
+    if let Some(expected) = hash
+    {
+        let mut info = FileHashInfo { size: std::mem::size_of::<FileHashInfo>() as u32, data: [0; 4] };
+        if check(unsafe { MsiGetFileHashW(path.as_ptr(), 0, &mut info) }).is_ok() && info.data != *expected
+        {
+            return Some(Some("content hash differs".to_string()));
+        }
+    }
+
+    None
+}
+
+fn registry_value(root: isize, key: &str, name: Option<&str>, view: u32) -> Option<String>
+{
+    let key = wide(key);
+    let name = name.map(wide);
+    let name_pointer = name.as_ref().map(|name| name.as_ptr()).unwrap_or(ptr::null());
+
+    let mut value_type = 0u32;
+    let mut size = 0u32;
+    let flags = RRF_RT_ANY | RRF_NOEXPAND | view;
+    let status = unsafe { RegGetValueW(root, key.as_ptr(), name_pointer, flags, &mut value_type, ptr::null_mut(), &mut size) };
+    if status != 0
+    {
+        return None;
+    }
+
+    let mut data = vec![0u8; size as usize];
+    if unsafe { RegGetValueW(root, key.as_ptr(), name_pointer, flags, &mut value_type, data.as_mut_ptr() as *mut c_void, &mut size) } != 0
+    {
+        return None;
+    }
+    data.truncate(size as usize);
+
+    Some(match value_type
+    {
+        REG_SZ | REG_EXPAND_SZ => {
+            let units: Vec<u16> = data.chunks_exact(2).map(|pair| u16::from_le_bytes([pair[0], pair[1]])).take_while(|unit| *unit != 0).collect();
+            String::from_utf16_lossy(&units)
+        },
+        REG_DWORD if data.len() >= 4 => format!("#{}", i32::from_le_bytes([data[0], data[1], data[2], data[3]])),
+        _ => data.iter().map(|byte| format!("{:02x}", byte)).collect()
+    })
+}
@@ -0,0 +1,62 @@
+use std::fmt::Write;
+
+use crate::table::Value;
+
+#[doc = "Renders a string as a quoted JSON string literal."]
+pub(crate) fn string(value: &str) -> String
+{
+    let mut output = String::with_capacity(value.len() + 2);
+    output.push('"');
+    for c in value.chars()
+    {
+        match c
+        {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if (c as u32) < 0x20 => { let _ = write!(output, "\\u{:04x}", c as u32); },
+            c => output.push(c)
+        }
+    }
+
+    output.push('"');
+    output
+}
+
+#[doc = "Renders a cell as a JSON null, number or string."]
+pub(crate) fn value(value: &Value) -> String
+{
+    match value
+    {
+        Value::Null => "null".to_string(),
+        Value::Int(value) => value.to_string(),
+        Value::Str(value) => string(value)
+    }
+}
+
+#[doc = "Renders already rendered items as a JSON array on one line."]
+pub(crate) fn array<I: IntoIterator<Item = String>>(items: I) -> String
+{
+    format!("[{}]", items.into_iter().collect::<Vec<_>>().join(", "))
+}
+
+#[doc = "Renders a list of strings as a JSON array on one line."]
+pub(crate) fn strings<S: AsRef<str>>(items: &[S]) -> String
+{
+    array(items.iter().map(|item| string(item.as_ref())))
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn test_string()
+    {
+        assert_eq!(string("C:\\Program Files\\\"App\"\n\u{1}"), "\"C:\\\\Program Files\\\\\\\"App\\\"\\n\\u0001\"");
+        assert_eq!(array(vec![value(&Value::Null), value(&Value::Int(-1)), value(&Value::from("a"))]), "[null, -1, \"a\"]");
+    }
+}
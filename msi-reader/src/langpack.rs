@@ -0,0 +1,184 @@
+use std::path::{ Path, PathBuf };
+
+use crate::compound;
+use crate::error::{ Error, Result };
+use crate::language::MsiLanguage;
+use crate::package::MsiPackage;
+use crate::transform::MsiTransform;
+use crate::writer;
+
+#[doc = "A single-language package written by `split_languages`."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct LanguagePack {
+    language: MsiLanguage,
+    path: PathBuf,
+    transformed: bool
+}
+
+impl LanguagePack {
+
+    #[doc = "Returns the language of the package."]
+    pub fn language(&self) -> MsiLanguage {
+        self.language
+    }
+
+    #[doc = "Returns the path the package was written to."]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    #[doc = "Returns a boolean value indicating whether an embedded language transform was applied, rather than the base language being kept."]
+    pub fn is_transformed(&self) -> bool {
+        self.transformed
+    }
+}
+
+#[doc = "Returns the languages of the embedded language transforms, which are substorages of the package named by their decimal LCID."]
+pub fn embedded_languages(package: &MsiPackage) -> Result<Vec<MsiLanguage>>
+{
+    let mut languages: Vec<MsiLanguage> = package.with_compound(|compound| Ok(compound.read_storage("/")?
+        .filter(|entry| entry.is_storage())
+        .filter_map(|entry| entry.name().parse::<u16>().ok())
+        .map(MsiLanguage::from)
+        .collect()))?;
+    languages.sort_unstable();
+    Ok(languages)
+}
+
+#[doc = "Writes one package per language into `output_dir`: the base language as is and every embedded language transform applied to a copy of the base. Each copy is named `<name>.<lcid>.msi`, loses the embedded transforms and declares only its own language in the summary information."]
+pub fn split_languages<P: AsRef<Path>, Q: AsRef<Path>>(path: P, output_dir: Q) -> Result<Vec<LanguagePack>>
+{
+    let path = path.as_ref();
+    let base = MsiPackage::open(path)?;
+    let languages = embedded_languages(&base)?;
+    if languages.is_empty()
+    {
+        return Err(Error::NotFound("embedded language transforms".to_string()));
+    }
+
+    let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("package");
+    std::fs::create_dir_all(&output_dir)?;
+
+    // the transforms are read through a second handle, as reading one also decodes tables of the base
+    let mut compound = compound::open(path)?;
+    let mut packs = Vec::new();
+    if let Some(language) = base_language(&base)?.filter(|language| !languages.contains(language))
+    {
+        let output = output_dir.as_ref().join(format!("{}.{}.msi", stem, language.value()));
+        std::fs::copy(path, &output)?;
+        packs.push(LanguagePack {
+            language,
+            path: output,
+            transformed: false
+        });
+    }
+
+    for &language in &languages
+    {
+        let transform = MsiTransform::read(&mut compound, &Path::new("/").join(language.value().to_string()), &base)?;
+        let output = output_dir.as_ref().join(format!("{}.{}.msi", stem, language.value()));
+        std::fs::copy(path, &output)?;
+        transform.apply_in_place(&output)?;
+        packs.push(LanguagePack {
+            language,
+            path: output,
+            transformed: true
+        });
+    }
+
+    for pack in &packs
+    {
+        finish(pack, &languages)?;
+    }
+
+    packs.sort_by_key(|pack| pack.language);
+    Ok(packs)
+}
+
+// The ProductLanguage property, falling back to the first language of the summary Template.
+fn base_language(package: &MsiPackage) -> Result<Option<MsiLanguage>>
+{
+    if let Some(language) = package.properties()?.product_language()
+    {
+        return Ok(Some(language));
+    }
+
+    Ok(package.languages().first().copied())
+}
+
+// Removes the embedded transforms from a written package and narrows its summary Template to the package's language.
+fn finish(pack: &LanguagePack, languages: &[MsiLanguage]) -> Result<()>
+{
+    {
+        let mut compound = compound::open_rw(&pack.path)?;
+        for language in languages
+        {
+            compound.remove_storage_all(Path::new("/").join(language.value().to_string()))?;
+        }
+        compound.flush()?;
+    }
+
+    let mut writer = writer::open(&pack.path)?;
+    writer.summary_info_mut().set_languages(&[msi::Language::from_code(pack.language.value())]);
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::testutil::TestPackage;
+    use std::io::{ Read, Write };
+
+    #[test]
+    fn test_split_languages()
+    {
+        let build = |tag: &str, language: &str, name: &str| TestPackage::new(tag, |builder| {
+            builder.table("Property", vec![
+                msi::Column::build("Property").primary_key().id_string(72),
+                msi::Column::build("Value").localizable().text_string(0)
+            ], vec![
+                vec![msi::Value::from("ProductLanguage"), msi::Value::from(language)],
+                vec![msi::Value::from("ProductName"), msi::Value::from(name)]
+            ]);
+        });
+        let base = build("langpack-base", "1033", "Contoso App");
+        let german = build("langpack-german", "1031", "Contoso Anwendung");
+
+        let transform_path = std::env::temp_dir().join(format!("msi-reader-langpack-{}.mst", std::process::id()));
+        MsiTransform::generate(&MsiPackage::open(base.path()).unwrap(), &MsiPackage::open(german.path()).unwrap()).unwrap()
+            .write(&transform_path).unwrap();
+        {
+            let mut compound = cfb::open_rw(base.path()).unwrap();
+            compound.create_storage("/1031").unwrap();
+            let mut transform = cfb::open(&transform_path).unwrap();
+            let names: Vec<String> = transform.read_storage("/").unwrap().map(|entry| entry.name().to_string()).collect();
+            for name in names
+            {
+                let mut data = Vec::new();
+                transform.open_stream(Path::new("/").join(&name)).unwrap().read_to_end(&mut data).unwrap();
+                compound.create_stream(Path::new("/1031").join(&name)).unwrap().write_all(&data).unwrap();
+            }
+            compound.flush().unwrap();
+        }
+        std::fs::remove_file(&transform_path).unwrap();
+
+        assert_eq!(embedded_languages(&MsiPackage::open(base.path()).unwrap()).unwrap(), [MsiLanguage::from(1031)]);
+
+        let output_dir = std::env::temp_dir().join(format!("msi-reader-langpack-{}", std::process::id()));
+        let packs = split_languages(base.path(), &output_dir).unwrap();
+        let opened: Vec<MsiPackage> = packs.iter().map(|pack| MsiPackage::open(pack.path()).unwrap()).collect();
+        std::fs::remove_dir_all(&output_dir).unwrap();
+
+        let languages: Vec<(u16, bool)> = packs.iter().map(|pack| (pack.language().value(), pack.is_transformed())).collect();
+        assert_eq!(languages, [(1031, true), (1033, false)]);
+        assert_eq!(opened[0].property("ProductName").unwrap().as_deref(), Some("Contoso Anwendung"));
+        assert_eq!(opened[1].property("ProductName").unwrap().as_deref(), Some("Contoso App"));
+        assert!(opened[0].summary().template().unwrap().ends_with(";1031"));
+        assert!(opened[1].summary().template().unwrap().ends_with(";1033"));
+        assert!(opened.iter().all(|package| embedded_languages(package).unwrap().is_empty()));
+
+        assert!(split_languages(german.path(), &output_dir).is_err());
+    }
+}
@@ -0,0 +1,170 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::error::{ Error, Result };
+
+// LCID, BCP-47 tag and English name of the languages packages commonly ship in.
+const LANGUAGES: [(u16, &str, &str); 52] = [
+    (1025, "ar-SA", "Arabic (Saudi Arabia)"),
+    (1026, "bg-BG", "Bulgarian"),
+    (1027, "ca-ES", "Catalan"),
+    (1028, "zh-TW", "Chinese (Traditional, Taiwan)"),
+    (1029, "cs-CZ", "Czech"),
+    (1030, "da-DK", "Danish"),
+    (1031, "de-DE", "German (Germany)"),
+    (1032, "el-GR", "Greek"),
+    (1033, "en-US", "English (United States)"),
+    (1034, "es-ES_tradnl", "Spanish (Spain, Traditional Sort)"),
+    (1035, "fi-FI", "Finnish"),
+    (1036, "fr-FR", "French (France)"),
+    (1037, "he-IL", "Hebrew"),
+    (1038, "hu-HU", "Hungarian"),
+    (1039, "is-IS", "Icelandic"),
+    (1040, "it-IT", "Italian (Italy)"),
+    (1041, "ja-JP", "Japanese"),
+    (1042, "ko-KR", "Korean"),
+    (1043, "nl-NL", "Dutch (Netherlands)"),
+    (1044, "nb-NO", "Norwegian (Bokmål)"),
+    (1045, "pl-PL", "Polish"),
+    (1046, "pt-BR", "Portuguese (Brazil)"),
+    (1048, "ro-RO", "Romanian"),
+    (1049, "ru-RU", "Russian"),
+    (1050, "hr-HR", "Croatian"),
+    (1051, "sk-SK", "Slovak"),
+    (1053, "sv-SE", "Swedish"),
+    (1054, "th-TH", "Thai"),
+    (1055, "tr-TR", "Turkish"),
+    (1057, "id-ID", "Indonesian"),
+    (1058, "uk-UA", "Ukrainian"),
+    (1059, "be-BY", "Belarusian"),
+    (1060, "sl-SI", "Slovenian"),
+    (1061, "et-EE", "Estonian"),
+    (1062, "lv-LV", "Latvian"),
+    (1063, "lt-LT", "Lithuanian"),
+    (1066, "vi-VN", "Vietnamese"),
+    (1081, "hi-IN", "Hindi"),
+    (1086, "ms-MY", "Malay"),
+    (2052, "zh-CN", "Chinese (Simplified, China)"),
+    (2055, "de-CH", "German (Switzerland)"),
+    (2057, "en-GB", "English (United Kingdom)"),
+    (2058, "es-MX", "Spanish (Mexico)"),
+    (2060, "fr-BE", "French (Belgium)"),
+    (2067, "nl-BE", "Dutch (Belgium)"),
+    (2070, "pt-PT", "Portuguese (Portugal)"),
+    (3076, "zh-HK", "Chinese (Traditional, Hong Kong)"),
+    (3079, "de-AT", "German (Austria)"),
+    (3081, "en-AU", "English (Australia)"),
+    (3082, "es-ES", "Spanish (Spain)"),
+    (3084, "fr-CA", "French (Canada)"),
+    (4105, "en-CA", "English (Canada)")
+];
+
+const PRIMARY_LANGUAGE_MASK: u16 = 0x3ff;
+
+#[doc = "A Windows language identifier (LCID) as used by File.Language, ProductLanguage and the summary Template, e.g. 1033 for English (United States)."]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MsiLanguage(u16);
+
+impl MsiLanguage {
+
+    #[doc = "The language-neutral identifier 0."]
+    pub const NEUTRAL: MsiLanguage = MsiLanguage(0);
+
+    #[doc = "Returns the numeric identifier."]
+    pub fn value(&self) -> u16 {
+        self.0
+    }
+
+    #[doc = "Returns the primary language, the low 10 bits, which is shared by the regional variants of a language."]
+    pub fn primary(&self) -> u16 {
+        self.0 & PRIMARY_LANGUAGE_MASK
+    }
+
+    #[doc = "Returns the sublanguage, the high 6 bits, which usually selects the region."]
+    pub fn sublanguage(&self) -> u16 {
+        self.0 >> 10
+    }
+
+    #[doc = "Returns a boolean value indicating whether the identifier is language-neutral, i.e. has no primary language."]
+    pub fn is_neutral(&self) -> bool {
+        self.primary() == 0
+    }
+
+    #[doc = "Returns a boolean value indicating whether both identifiers are variants of the same language, such as en-US and en-GB."]
+    pub fn is_same_language(&self, other: MsiLanguage) -> bool {
+        self.primary() == other.primary()
+    }
+
+    #[doc = "Returns the BCP-47 tag of the language, e.g. `en-US`, if it is a known one."]
+    pub fn tag(&self) -> Option<&'static str> {
+        LANGUAGES.iter().find(|(lcid, _, _)| *lcid == self.0).map(|(_, tag, _)| *tag)
+    }
+
+    #[doc = "Returns the English name of the language, e.g. `English (United States)`, if it is a known one."]
+    pub fn name(&self) -> Option<&'static str> {
+        LANGUAGES.iter().find(|(lcid, _, _)| *lcid == self.0).map(|(_, _, name)| *name)
+    }
+
+    #[doc = "Returns the known language with the given BCP-47 tag, compared case-insensitively."]
+    pub fn from_tag(tag: &str) -> Option<MsiLanguage> {
+        LANGUAGES.iter().find(|(_, candidate, _)| candidate.eq_ignore_ascii_case(tag)).map(|(lcid, _, _)| MsiLanguage(*lcid))
+    }
+
+    #[doc = "Parses a comma-separated list of identifiers such as `1033,1031`, as stored in File.Language, Upgrade.Language and the Template. Entries that are not numbers are skipped."]
+    pub fn parse_list(text: &str) -> Vec<MsiLanguage> {
+        text.split(',').filter_map(|language| language.trim().parse().ok()).map(MsiLanguage).collect()
+    }
+}
+
+impl From<u16> for MsiLanguage {
+    fn from(lcid: u16) -> Self {
+        MsiLanguage(lcid)
+    }
+}
+
+impl FromStr for MsiLanguage {
+    type Err = Error;
+
+    #[doc = "Parses a numeric identifier or a known BCP-47 tag."]
+    fn from_str(text: &str) -> Result<MsiLanguage>
+    {
+        let text = text.trim();
+        text.parse().map(MsiLanguage).ok()
+            .or_else(|| MsiLanguage::from_tag(text))
+            .ok_or_else(|| Error::invalid(format!("'{}' is not a language identifier", text)))
+    }
+}
+
+impl Display for MsiLanguage {
+    #[doc = "Writes the BCP-47 tag of known languages and the number of any other."]
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.tag()
+        {
+            Some(tag) => fmt.pad(tag),
+            None => fmt.pad(&self.0.to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn test_language()
+    {
+        let english = MsiLanguage::from(1033);
+        assert_eq!((english.tag(), english.name()), (Some("en-US"), Some("English (United States)")));
+        assert_eq!((english.primary(), english.sublanguage()), (9, 1));
+        assert!(english.is_same_language("en-GB".parse().unwrap()));
+        assert!(!english.is_same_language(MsiLanguage::from(1031)));
+
+        assert_eq!(format!("{:<6}|{}", english, MsiLanguage::from(5146)), "en-US |5146");
+        assert_eq!("3082".parse::<MsiLanguage>().unwrap(), MsiLanguage::from_tag("ES-es").unwrap());
+        assert!("Klingon".parse::<MsiLanguage>().is_err());
+        assert!(MsiLanguage::NEUTRAL.is_neutral() && MsiLanguage::from(1024).is_neutral());
+        assert_eq!(MsiLanguage::parse_list("1033, 1031,,x"), [MsiLanguage::from(1033), MsiLanguage::from(1031)]);
+    }
+}
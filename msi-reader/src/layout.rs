@@ -0,0 +1,588 @@
+use std::collections::{ BTreeMap, HashMap };
+
+use crate::component::ComponentTable;
+use crate::directory::{ MsiDirectoryName, MsiName, NameFormat };
+use crate::error::Result;
+use crate::file::FileTable;
+use crate::json;
+use crate::package::MsiPackage;
+use crate::property::Properties;
+use crate::table::RowOrigin;
+
+// Where the installer points the well-known folders on 64-bit Windows installed to C:, for a per-machine installation.
+const SYSTEM_FOLDERS: [(&str, &str); 14] = [
+    ("CommonAppDataFolder", "C:\\ProgramData\\"),
+    ("CommonFiles64Folder", "C:\\Program Files\\Common Files\\"),
+    ("CommonFilesFolder", "C:\\Program Files (x86)\\Common Files\\"),
+    ("DesktopFolder", "C:\\Users\\Public\\Desktop\\"),
+    ("FontsFolder", "C:\\Windows\\Fonts\\"),
+    ("ProgramFiles64Folder", "C:\\Program Files\\"),
+    ("ProgramFilesFolder", "C:\\Program Files (x86)\\"),
+    ("ProgramMenuFolder", "C:\\ProgramData\\Microsoft\\Windows\\Start Menu\\Programs\\"),
+    ("StartMenuFolder", "C:\\ProgramData\\Microsoft\\Windows\\Start Menu\\"),
+    ("StartupFolder", "C:\\ProgramData\\Microsoft\\Windows\\Start Menu\\Programs\\Startup\\"),
+    ("System64Folder", "C:\\Windows\\System32\\"),
+    ("SystemFolder", "C:\\Windows\\SysWOW64\\"),
+    ("WindowsFolder", "C:\\Windows\\"),
+    ("WindowsVolume", "C:\\")
+];
+const DEFAULT_ROOT_DRIVE: &str = "C:\\";
+
+#[doc = "Resolves directories to the full paths they are installed to, as the installer does during costing: a directory whose key is set as a property, such as a well-known folder or an INSTALLDIR passed on the command line, takes that value; other directories append their target name to the path of their parent, and roots start at ROOTDRIVE."]
+#[doc = ""]
+#[doc = "It also lays out the source image: where each directory and file is found below SourceDir on an uncompressed medium or administrative image."]
+#[derive(Clone, Debug, Default)]
+pub struct DirectoryResolver {
+    directories: HashMap<String, (Option<String>, String)>,
+    properties: HashMap<String, String>,
+    files: HashMap<String, (String, String)>,
+    short_source_names: bool
+}
+
+impl DirectoryResolver {
+
+    #[doc = "Reads the Directory, Property, Component and File tables of the package, and whether its source uses short names from the summary Word Count. Well-known folders such as ProgramFilesFolder and CommonAppDataFolder start at their default locations on 64-bit Windows."]
+    pub fn read(package: &MsiPackage) -> Result<DirectoryResolver>
+    {
+        let directories = match package.optional_table("Directory")?
+        {
+            Some(table) => table.rows()
+                .filter_map(|row| Some((row.str("Directory")?.to_string(), (row.str("Directory_Parent").map(str::to_string), row.str("DefaultDir").unwrap_or(".").to_string()))))
+                .collect(),
+            None => HashMap::new()
+        };
+
+        // read the rows directly, as ComponentTable resolves its directories with this resolver
+        let components: HashMap<String, String> = match package.optional_table("Component")?
+        {
+            Some(table) => table.rows()
+                .filter_map(|row| Some((row.str("Component")?.to_string(), row.str("Directory_")?.to_string())))
+                .collect(),
+            None => HashMap::new()
+        };
+        let files = FileTable::read(package)?.rows().iter()
+            .filter_map(|file| Some((file.file().to_string(), (components.get(file.component())?.clone(), file.file_name().combined().to_string()))))
+            .collect();
+
+        let mut resolver = DirectoryResolver {
+            directories,
+            properties: SYSTEM_FOLDERS.iter().map(|(folder, path)| (folder.to_string(), path.to_string())).collect(),
+            files,
+            short_source_names: package.summary().word_count().is_some_and(|word_count| word_count.has_short_names())
+        };
+        for (name, value) in Properties::read(package)?.iter()
+        {
+            resolver.set_property(name, value);
+        }
+
+        Ok(resolver)
+    }
+
+    #[doc = "Sets a property, such as a folder passed on the command line; an empty value removes it."]
+    pub fn set_property(&mut self, name: &str, value: &str)
+    {
+        if value.is_empty()
+        {
+            self.properties.remove(name);
+        }
+        else
+        {
+            self.properties.insert(name.to_string(), value.to_string());
+        }
+    }
+
+    #[doc = "Returns the full path of a directory, ending with a backslash like the directory properties of the installer, e.g. `C:\\Program Files (x86)\\Contoso\\`. Returns `None` for keys missing from the Directory table and for directories whose parent chain is broken or circular."]
+    pub fn target_path(&self, directory: &str) -> Option<String> {
+        self.resolve_target(directory, self.directories.len(), false)
+    }
+
+    #[doc = "Returns the path of a directory like `target_path`, but with the short name of each directory in the Directory table where it has one, e.g. `C:\\Program Files (x86)\\CONTOSO\\`. Paths set as properties, and the defaults of well-known folders, are used as they are."]
+    pub fn short_target_path(&self, directory: &str) -> Option<String> {
+        self.resolve_target(directory, self.directories.len(), true)
+    }
+
+    #[doc = "Returns the full path of every directory that resolves, by key."]
+    pub fn target_paths(&self) -> BTreeMap<String, String> {
+        self.directories.keys().filter_map(|key| Some((key.clone(), self.target_path(key)?))).collect()
+    }
+
+    // Whether a directory is a root or takes its path from a property, like the well-known folders, rather than being laid out by the package.
+    pub(crate) fn is_predefined(&self, directory: &str) -> bool
+    {
+        self.properties.contains_key(directory)
+            || self.directories.get(directory).is_some_and(|(parent, _)| parent.as_deref().is_none_or(|parent| parent == directory))
+    }
+
+    #[doc = "Returns a boolean value indicating whether the source image uses short names, as the Word Count of the package says."]
+    pub fn short_source_names(&self) -> bool {
+        self.short_source_names
+    }
+
+    #[doc = "Overrides whether the source image uses short or long names."]
+    pub fn set_short_source_names(&mut self, short_source_names: bool) {
+        self.short_source_names = short_source_names;
+    }
+
+    #[doc = "Returns the path of a directory in the source image relative to SourceDir, e.g. `Contoso\\App`, or an empty string for a root. The source part of DefaultDir is used where there is one, and its short or long name depending on the layout of the image. Returns `None` like `target_path` does."]
+    pub fn source_path(&self, directory: &str) -> Option<String> {
+        self.resolve_source(directory, self.directories.len())
+    }
+
+    #[doc = "Returns the path of a file in the source image relative to SourceDir, e.g. `Contoso\\App\\app.exe`, or `None` if the file, its component or its directory is missing."]
+    pub fn file_source_path(&self, file: &str) -> Option<String>
+    {
+        let (directory, file_name) = self.files.get(file)?;
+        let base = self.source_path(directory)?;
+        Some(append(&base, &self.source_name(&MsiName::from(file_name.as_str()))))
+    }
+
+    #[doc = "Returns the source path of every file that resolves, by File key."]
+    pub fn file_source_paths(&self) -> BTreeMap<String, String> {
+        self.files.keys().filter_map(|key| Some((key.clone(), self.file_source_path(key)?))).collect()
+    }
+
+    fn resolve_source(&self, directory: &str, depth: usize) -> Option<String>
+    {
+        let (parent, default_dir) = self.directories.get(directory)?;
+        match parent.as_deref().filter(|parent| *parent != directory)
+        {
+            None => Some(String::new()),
+            Some(_) if depth == 0 => None,
+            Some(parent) => {
+                let base = self.resolve_source(parent, depth - 1)?;
+                let name = MsiDirectoryName::from(default_dir.as_str());
+                let source = name.source().unwrap_or_else(|| name.target());
+                if source.is_located_at_parent()
+                {
+                    Some(base)
+                }
+                else
+                {
+                    Some(append(&base, &self.source_name(&source)))
+                }
+            }
+        }
+    }
+
+    fn source_name(&self, name: &MsiName) -> String
+    {
+        match name.short().filter(|_| self.short_source_names)
+        {
+            Some(short) => short.to_string(),
+            None => name.format(NameFormat::Long)
+        }
+    }
+
+    fn resolve_target(&self, directory: &str, depth: usize, short: bool) -> Option<String>
+    {
+        let (parent, default_dir) = self.directories.get(directory)?;
+        if let Some(value) = self.properties.get(directory)
+        {
+            return Some(with_separator(value));
+        }
+
+        match parent.as_deref().filter(|parent| *parent != directory)
+        {
+            None => Some(with_separator(self.properties.get("ROOTDRIVE").map(String::as_str).unwrap_or(DEFAULT_ROOT_DRIVE))),
+            Some(_) if depth == 0 => None,
+            Some(parent) => {
+                let base = self.resolve_target(parent, depth - 1, short)?;
+                let name = MsiDirectoryName::from(default_dir.as_str());
+                let target = name.target();
+                if target.is_located_at_parent()
+                {
+                    Some(base)
+                }
+                else
+                {
+                    let name = match target.short().filter(|_| short)
+                    {
+                        Some(short) => short.to_string(),
+                        None => target.format(NameFormat::Long)
+                    };
+                    Some(format!("{}{}\\", base, name))
+                }
+            }
+        }
+    }
+}
+
+fn append(base: &str, name: &str) -> String
+{
+    if base.is_empty() { name.to_string() } else { format!("{}\\{}", base, name) }
+}
+
+fn with_separator(path: &str) -> String
+{
+    if path.ends_with('\\') { path.to_string() } else { format!("{}\\", path) }
+}
+
+#[doc = "A directory of the tree `directory_tree` returns, with what is installed into it."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct DirectoryNode {
+    directory: String,
+    parent: Option<String>,
+    depth: usize,
+    target_name: String,
+    source_name: String,
+    target_path: Option<String>,
+    source_path: Option<String>,
+    components: usize,
+    files: usize,
+    size: u64,
+    total_size: u64,
+    origin: RowOrigin
+}
+
+impl DirectoryNode {
+
+    #[doc = "Returns the key of the directory."]
+    pub fn directory(&self) -> &str {
+        &self.directory
+    }
+
+    #[doc = "Returns the parent directory; `None` for a root."]
+    pub fn parent(&self) -> Option<&str> {
+        self.parent.as_deref()
+    }
+
+    #[doc = "Returns the number of ancestors of the directory; roots have depth 0."]
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    #[doc = "Returns the long target name from DefaultDir, `.` for a directory located at its parent."]
+    pub fn target_name(&self) -> &str {
+        &self.target_name
+    }
+
+    #[doc = "Returns the long source name from DefaultDir, which is the target name unless a separate source name is given."]
+    pub fn source_name(&self) -> &str {
+        &self.source_name
+    }
+
+    #[doc = "Returns the full target path, as `DirectoryResolver::target_path` resolves it."]
+    pub fn target_path(&self) -> Option<&str> {
+        self.target_path.as_deref()
+    }
+
+    #[doc = "Returns the path in the source image relative to SourceDir, as `DirectoryResolver::source_path` resolves it."]
+    pub fn source_path(&self) -> Option<&str> {
+        self.source_path.as_deref()
+    }
+
+    #[doc = "Returns the number of components installed into the directory itself."]
+    pub fn components(&self) -> usize {
+        self.components
+    }
+
+    #[doc = "Returns the number of files of those components."]
+    pub fn files(&self) -> usize {
+        self.files
+    }
+
+    #[doc = "Returns the size in bytes of those files, from the FileSize column."]
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    #[doc = "Returns the size in bytes of the files of the directory and all its descendants."]
+    pub fn total_size(&self) -> u64 {
+        self.total_size
+    }
+
+    #[doc = "Returns the Directory row the directory was read from."]
+    pub fn origin(&self) -> &RowOrigin {
+        &self.origin
+    }
+}
+
+#[doc = "Builds the directory hierarchy with the resolved names and paths of every directory and the components, files and sizes installed into it. Directories are returned depth-first in Directory table order, so children follow their parent; directories whose parent chain is circular are left out."]
+pub fn directory_tree(package: &MsiPackage) -> Result<Vec<DirectoryNode>>
+{
+    let table = match package.optional_table("Directory")?
+    {
+        Some(table) => table,
+        None => return Ok(Vec::new())
+    };
+    let resolver = DirectoryResolver::read(package)?;
+
+    let mut contents: HashMap<String, (usize, usize, u64)> = HashMap::new();
+    let components = ComponentTable::read(package)?;
+    let files = FileTable::read(package)?;
+    for component in components.rows()
+    {
+        let entry = contents.entry(component.directory().to_string()).or_default();
+        entry.0 += 1;
+        for file in files.by_component(component.component())
+        {
+            entry.1 += 1;
+            entry.2 += file.size().max(0) as u64;
+        }
+    }
+
+    let mut nodes: Vec<DirectoryNode> = table.rows()
+        .filter_map(|row| {
+            let directory = row.str("Directory")?;
+            let name = MsiDirectoryName::from(row.str("DefaultDir").unwrap_or("."));
+            let (components, files, size) = contents.get(directory).copied().unwrap_or_default();
+            Some(DirectoryNode {
+                directory: directory.to_string(),
+                parent: row.str("Directory_Parent").filter(|parent| !parent.is_empty() && *parent != directory).map(str::to_string),
+                depth: 0,
+                target_name: name.target().format(NameFormat::Long),
+                source_name: name.source().unwrap_or_else(|| name.target()).format(NameFormat::Long),
+                target_path: resolver.target_path(directory),
+                source_path: resolver.source_path(directory),
+                components,
+                files,
+                size,
+                total_size: 0,
+                origin: row.origin()
+            })
+        })
+        .collect();
+    let position: HashMap<String, usize> = nodes.iter().enumerate().map(|(index, node)| (node.directory.clone(), index)).collect();
+
+    let mut children: Vec<Vec<usize>> = vec![Vec::new(); nodes.len()];
+    let mut roots = Vec::new();
+    for (index, node) in nodes.iter().enumerate()
+    {
+        match node.parent.as_deref().and_then(|parent| position.get(parent))
+        {
+            Some(parent) => children[*parent].push(index),
+            None => roots.push(index)
+        }
+    }
+
+    let mut order = Vec::new();
+    let mut visited = vec![false; nodes.len()];
+    for root in roots
+    {
+        visit(root, 0, &children, &mut nodes, &mut visited, &mut order);
+    }
+
+    Ok(order.into_iter().map(|index| nodes[index].clone()).collect())
+}
+
+// Walks the directory tree depth-first, filling in depths and subtree sizes.
+fn visit(index: usize, depth: usize, children: &[Vec<usize>], nodes: &mut [DirectoryNode], visited: &mut [bool], order: &mut Vec<usize>) -> u64
+{
+    if visited[index]
+    {
+        return 0;
+    }
+    visited[index] = true;
+    order.push(index);
+
+    let mut total = nodes[index].size;
+    for child in &children[index]
+    {
+        total += visit(*child, depth + 1, children, nodes, visited, order);
+    }
+
+    nodes[index].depth = depth;
+    nodes[index].total_size = total;
+    total
+}
+
+#[doc = "Renders the tree `directory_tree` returns as JSON: an object with the root directories, each with its names, paths, counts and sizes and its `children`."]
+pub fn directory_tree_json(nodes: &[DirectoryNode]) -> String
+{
+    let mut index = 0;
+    let mut roots = Vec::new();
+    while index < nodes.len()
+    {
+        let (rendered, next) = render_node(nodes, index, 2);
+        roots.push(rendered);
+        index = next;
+    }
+
+    format!("{{\n  \"directories\": [{}]\n}}\n", if roots.is_empty() { String::new() } else { format!("\n{}\n  ", roots.join(",\n")) })
+}
+
+// Renders the node at `index` with its descendants, which follow it with a greater depth; returns the index after them.
+fn render_node(nodes: &[DirectoryNode], index: usize, indent: usize) -> (String, usize)
+{
+    let node = &nodes[index];
+    let mut children = Vec::new();
+    let mut next = index + 1;
+    while next < nodes.len() && nodes[next].depth > node.depth
+    {
+        let (rendered, after) = render_node(nodes, next, indent + 2);
+        children.push(rendered);
+        next = after;
+    }
+
+    let optional = |value: Option<&str>| value.map(json::string).unwrap_or_else(|| "null".to_string());
+    let padding = " ".repeat(indent * 2);
+    let rendered = format!("{}{{ \"directory\": {}, \"targetName\": {}, \"sourceName\": {}, \"targetPath\": {}, \"sourcePath\": {}, \"components\": {}, \"files\": {}, \"size\": {}, \"totalSize\": {}, \"children\": [{}] }}",
+        padding, json::string(&node.directory), json::string(&node.target_name), json::string(&node.source_name), optional(node.target_path()), optional(node.source_path()),
+        node.components, node.files, node.size, node.total_size,
+        if children.is_empty() { String::new() } else { format!("\n{}\n{}", children.join(",\n"), padding) });
+    (rendered, next)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::testutil::TestPackage;
+
+    #[test]
+    fn test_target_paths()
+    {
+        let package = TestPackage::new("layout-target", |builder| {
+            builder.table("Directory", vec![
+                msi::Column::build("Directory").primary_key().id_string(72),
+                msi::Column::build("Directory_Parent").nullable().id_string(72),
+                msi::Column::build("DefaultDir").text_string(255)
+            ], vec![
+                vec![msi::Value::from("TARGETDIR"), msi::Value::Null, msi::Value::from("SourceDir")],
+                vec![msi::Value::from("ProgramFilesFolder"), msi::Value::from("TARGETDIR"), msi::Value::from(".")],
+                vec![msi::Value::from("VENDOR"), msi::Value::from("ProgramFilesFolder"), msi::Value::from("CONTOSO|Contoso")],
+                vec![msi::Value::from("INSTALLDIR"), msi::Value::from("VENDOR"), msi::Value::from("App:APP|Application")],
+                vec![msi::Value::from("BIN"), msi::Value::from("INSTALLDIR"), msi::Value::from(".")],
+                vec![msi::Value::from("CommonAppDataFolder"), msi::Value::from("TARGETDIR"), msi::Value::from(".")],
+                vec![msi::Value::from("DATA"), msi::Value::from("CommonAppDataFolder"), msi::Value::from("Contoso")],
+                vec![msi::Value::from("LOOP1"), msi::Value::from("LOOP2"), msi::Value::from("One")],
+                vec![msi::Value::from("LOOP2"), msi::Value::from("LOOP1"), msi::Value::from("Two")]
+            ]);
+            builder.table("Property", vec![
+                msi::Column::build("Property").primary_key().id_string(72),
+                msi::Column::build("Value").localizable().text_string(0)
+            ], vec![
+                vec![msi::Value::from("ROOTDRIVE"), msi::Value::from("D:\\")]
+            ]);
+        });
+
+        let mut resolver = DirectoryResolver::read(&MsiPackage::open(package.path()).unwrap()).unwrap();
+        assert_eq!(resolver.target_path("TARGETDIR").as_deref(), Some("D:\\"));
+        assert_eq!(resolver.target_path("INSTALLDIR").as_deref(), Some("C:\\Program Files (x86)\\Contoso\\Application\\"));
+        assert_eq!(resolver.target_path("BIN"), resolver.target_path("INSTALLDIR"));
+        assert_eq!(resolver.target_path("DATA").as_deref(), Some("C:\\ProgramData\\Contoso\\"));
+        assert_eq!((resolver.target_path("LOOP1"), resolver.target_path("MISSING")), (None, None));
+        assert!(resolver.is_predefined("TARGETDIR") && resolver.is_predefined("ProgramFilesFolder"));
+        assert!(!resolver.is_predefined("VENDOR") && !resolver.is_predefined("INSTALLDIR"));
+
+        resolver.set_property("INSTALLDIR", "E:\\Apps\\Contoso");
+        resolver.set_property("ProgramFilesFolder", "");
+        assert_eq!(resolver.target_path("BIN").as_deref(), Some("E:\\Apps\\Contoso\\"));
+        assert_eq!(resolver.target_path("VENDOR").as_deref(), Some("D:\\Contoso\\"));
+        assert!(resolver.is_predefined("INSTALLDIR"));
+        assert_eq!(resolver.target_paths().len(), 7);
+    }
+
+    #[test]
+    fn test_source_paths()
+    {
+        let package = TestPackage::new("layout-source", |builder| {
+            builder.table("Directory", vec![
+                msi::Column::build("Directory").primary_key().id_string(72),
+                msi::Column::build("Directory_Parent").nullable().id_string(72),
+                msi::Column::build("DefaultDir").text_string(255)
+            ], vec![
+                vec![msi::Value::from("TARGETDIR"), msi::Value::Null, msi::Value::from("SourceDir")],
+                vec![msi::Value::from("ProgramFilesFolder"), msi::Value::from("TARGETDIR"), msi::Value::from(".")],
+                vec![msi::Value::from("VENDOR"), msi::Value::from("ProgramFilesFolder"), msi::Value::from("CONTOSO|Contoso")],
+                vec![msi::Value::from("INSTALLDIR"), msi::Value::from("VENDOR"), msi::Value::from("SRC|Sources:APP|Application")],
+                vec![msi::Value::from("BIN"), msi::Value::from("INSTALLDIR"), msi::Value::from(".:Bin")]
+            ]);
+            builder.table("Component", vec![
+                msi::Column::build("Component").primary_key().id_string(72),
+                msi::Column::build("ComponentId").nullable().string(38),
+                msi::Column::build("Directory_").id_string(72),
+                msi::Column::build("Attributes").int16(),
+                msi::Column::build("Condition").nullable().string(255),
+                msi::Column::build("KeyPath").nullable().id_string(72)
+            ], vec![
+                vec![msi::Value::from("Main"), msi::Value::Null, msi::Value::from("INSTALLDIR"), msi::Value::Int(0), msi::Value::Null, msi::Value::Null]
+            ]);
+            builder.table("File", vec![
+                msi::Column::build("File").primary_key().id_string(72),
+                msi::Column::build("Component_").id_string(72),
+                msi::Column::build("FileName").text_string(255),
+                msi::Column::build("FileSize").int32(),
+                msi::Column::build("Version").nullable().string(72),
+                msi::Column::build("Language").nullable().string(20),
+                msi::Column::build("Attributes").nullable().int16(),
+                msi::Column::build("Sequence").int16()
+            ], vec![
+                vec![msi::Value::from("App"), msi::Value::from("Main"), msi::Value::from("APPLIC~1.EXE|Application.exe"), msi::Value::Int(1), msi::Value::Null, msi::Value::Null, msi::Value::Null, msi::Value::Int(1)],
+                vec![msi::Value::from("Orphan"), msi::Value::from("Missing"), msi::Value::from("orphan.txt"), msi::Value::Int(1), msi::Value::Null, msi::Value::Null, msi::Value::Null, msi::Value::Int(2)]
+            ]);
+        });
+
+        let mut resolver = DirectoryResolver::read(&MsiPackage::open(package.path()).unwrap()).unwrap();
+        assert!(!resolver.short_source_names());
+        assert_eq!(resolver.source_path("TARGETDIR").as_deref(), Some(""));
+        assert_eq!(resolver.source_path("INSTALLDIR").as_deref(), Some("Contoso\\Sources"));
+        assert_eq!(resolver.source_path("BIN"), resolver.source_path("INSTALLDIR"));
+        assert_eq!(resolver.target_path("BIN").as_deref(), Some("C:\\Program Files (x86)\\Contoso\\Application\\Bin\\"));
+        assert_eq!(resolver.file_source_path("App").as_deref(), Some("Contoso\\Sources\\Application.exe"));
+        assert_eq!(resolver.file_source_path("Orphan"), None);
+
+        resolver.set_short_source_names(true);
+        assert_eq!(resolver.file_source_path("App").as_deref(), Some("CONTOSO\\SRC\\APPLIC~1.EXE"));
+        assert_eq!(resolver.file_source_paths().len(), 1);
+    }
+
+    #[test]
+    fn test_directory_tree()
+    {
+        let package = TestPackage::new("layout-tree", |builder| {
+            builder.table("Directory", vec![
+                msi::Column::build("Directory").primary_key().id_string(72),
+                msi::Column::build("Directory_Parent").nullable().id_string(72),
+                msi::Column::build("DefaultDir").text_string(255)
+            ], vec![
+                vec![msi::Value::from("TARGETDIR"), msi::Value::Null, msi::Value::from("SourceDir")],
+                vec![msi::Value::from("ProgramFilesFolder"), msi::Value::from("TARGETDIR"), msi::Value::from(".")],
+                vec![msi::Value::from("INSTALLDIR"), msi::Value::from("ProgramFilesFolder"), msi::Value::from("Sources:APP|Application")],
+                vec![msi::Value::from("BIN"), msi::Value::from("INSTALLDIR"), msi::Value::from("Bin")],
+                vec![msi::Value::from("LOOP1"), msi::Value::from("LOOP2"), msi::Value::from("One")],
+                vec![msi::Value::from("LOOP2"), msi::Value::from("LOOP1"), msi::Value::from("Two")]
+            ]);
+            builder.table("Component", vec![
+                msi::Column::build("Component").primary_key().id_string(72),
+                msi::Column::build("ComponentId").nullable().string(38),
+                msi::Column::build("Directory_").id_string(72),
+                msi::Column::build("Attributes").int16(),
+                msi::Column::build("Condition").nullable().string(255),
+                msi::Column::build("KeyPath").nullable().id_string(72)
+            ], vec![
+                vec![msi::Value::from("Main"), msi::Value::Null, msi::Value::from("INSTALLDIR"), msi::Value::Int(0), msi::Value::Null, msi::Value::Null],
+                vec![msi::Value::from("Tools"), msi::Value::Null, msi::Value::from("BIN"), msi::Value::Int(0), msi::Value::Null, msi::Value::Null]
+            ]);
+            builder.table("File", vec![
+                msi::Column::build("File").primary_key().id_string(72),
+                msi::Column::build("Component_").id_string(72),
+                msi::Column::build("FileName").text_string(255),
+                msi::Column::build("FileSize").int32(),
+                msi::Column::build("Version").nullable().string(72),
+                msi::Column::build("Language").nullable().string(20),
+                msi::Column::build("Attributes").nullable().int16(),
+                msi::Column::build("Sequence").int16()
+            ], vec![
+                vec![msi::Value::from("App"), msi::Value::from("Main"), msi::Value::from("app.exe"), msi::Value::Int(1000), msi::Value::Null, msi::Value::Null, msi::Value::Null, msi::Value::Int(1)],
+                vec![msi::Value::from("Tool"), msi::Value::from("Tools"), msi::Value::from("tool.exe"), msi::Value::Int(200), msi::Value::Null, msi::Value::Null, msi::Value::Null, msi::Value::Int(2)],
+                vec![msi::Value::from("Help"), msi::Value::from("Tools"), msi::Value::from("tool.chm"), msi::Value::Int(30), msi::Value::Null, msi::Value::Null, msi::Value::Null, msi::Value::Int(3)]
+            ]);
+        });
+
+        let nodes = directory_tree(&MsiPackage::open(package.path()).unwrap()).unwrap();
+        assert_eq!(nodes.iter().map(|node| (node.directory(), node.depth())).collect::<Vec<_>>(),
+            [("TARGETDIR", 0), ("ProgramFilesFolder", 1), ("INSTALLDIR", 2), ("BIN", 3)]);
+        let install = &nodes[2];
+        assert_eq!((install.target_name(), install.source_name(), install.parent()), ("Application", "Sources", Some("ProgramFilesFolder")));
+        assert_eq!(install.target_path(), Some("C:\\Program Files (x86)\\Application\\"));
+        assert_eq!((install.components(), install.files(), install.size(), install.total_size()), (1, 1, 1000, 1230));
+        assert_eq!((nodes[3].files(), nodes[0].total_size()), (2, 1230));
+
+        let json = directory_tree_json(&nodes);
+        assert!(json.starts_with("{\n  \"directories\": [\n    { \"directory\": \"TARGETDIR\""));
+        assert!(json.contains("\"directory\": \"BIN\", \"targetName\": \"Bin\", \"sourceName\": \"Bin\""));
+        assert_eq!(json.matches("\"children\": []").count(), 1);
+        assert_eq!(directory_tree_json(&[]), "{\n  \"directories\": []\n}\n");
+    }
+}
@@ -1,7 +1,136 @@
+mod bytes;
+mod compound;
+mod csv;
+mod der;
+mod hash;
+mod huffman;
+mod json;
+mod lzx;
+mod mszip;
+#[cfg(feature = "serde")]
+mod serialize;
+mod streamname;
+#[cfg(test)]
+mod testutil;
+#[cfg(all(windows, feature = "windows"))]
+mod win32;
+mod writer;
+pub mod actiongraph;
+#[cfg(feature = "tokio")]
+pub mod asyncpackage;
+pub mod authenticode;
+pub mod cabinet;
+pub mod codepage;
+pub mod component;
+pub mod condition;
+#[cfg(all(windows, feature = "windows"))]
+pub mod conformance;
+pub mod cost;
+pub mod customaction;
+pub mod diff;
+pub mod digest;
+pub mod edit;
+pub mod environment;
+pub mod error;
+pub mod export;
+pub mod extract;
+pub mod feature;
+pub mod file;
+pub mod formatted;
+pub mod guid;
+pub mod ice;
+pub mod idt;
+#[cfg(all(windows, feature = "windows"))]
+pub mod installed;
+pub mod langpack;
+pub mod language;
+pub mod layout;
+pub mod media;
+pub mod package;
+pub mod patch;
+pub mod property;
+pub mod registry;
+pub mod report;
+pub mod sbom;
+pub mod schema;
+pub mod sequence;
+pub mod service;
+pub mod shortcut;
+pub mod snapshot;
+pub mod sql;
+pub mod stringpool;
+pub mod suite;
+pub mod summary;
+pub mod table;
+pub mod transform;
+pub mod upgrade;
+pub mod validation;
+pub mod version;
+
+pub use error::{ Error, Result };
+pub use package::MsiPackage;
+
 pub mod directory
 {
+    use std::borrow::Borrow;
+    use std::collections::HashSet;
     use std::fmt::{ Debug, Display };
 
+    const INVALID_LONG_CHARACTERS: [char; 9] = ['\\', '/', ':', '*', '?', '"', '<', '>', '|'];
+    const INVALID_SHORT_CHARACTERS: [char; 18] = ['\\', '/', ':', '*', '?', '"', '<', '>', '|', '+', ',', ';', '=', '[', ']', ' ', '.', '~'];
+    const SHORT_STEM_LENGTH: usize = 8;
+    const SHORT_EXTENSION_LENGTH: usize = 3;
+    const LONG_NAME_LENGTH: usize = 255;
+    const RESERVED_NAMES: [&str; 22] = [
+        "CON", "PRN", "AUX", "NUL",
+        "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9",
+        "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9"
+    ];
+
+    #[doc = "The reason a file or directory name is not valid on Windows."]
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum NameError {
+        #[doc = "The name is empty."]
+        Empty,
+        #[doc = "The name contains a character Windows does not allow in file names."]
+        InvalidCharacter(char),
+        #[doc = "The name, ignoring any extension, is a reserved device name such as `CON` or `LPT1`."]
+        ReservedName(String),
+        #[doc = "The given separator occurs more often than the format allows."]
+        TooManySeparators(char),
+        #[doc = "A part on either side of a separator is empty."]
+        EmptyComponent,
+        #[doc = "A part of the name is longer than the given number of characters, such as the 8 of a short name's stem."]
+        TooLong(usize)
+    }
+
+    impl Display for NameError {
+        fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+            match self
+            {
+                NameError::Empty => write!(fmt, "the name is empty"),
+                NameError::InvalidCharacter(c) => write!(fmt, "the name contains the invalid character {:?}", c),
+                NameError::ReservedName(name) => write!(fmt, "'{}' is a reserved device name", name),
+                NameError::TooManySeparators(separator) => write!(fmt, "the name contains more than one '{}' separator", separator),
+                NameError::EmptyComponent => write!(fmt, "the name has an empty component"),
+                NameError::TooLong(length) => write!(fmt, "a part of the name is longer than {} characters", length)
+            }
+        }
+    }
+
+    impl std::error::Error for NameError {}
+
+    #[doc = "How a name is rendered as text."]
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum NameFormat {
+        #[doc = "The combined form exactly as stored, e.g. `PROGRA~1|Program Files`."]
+        Raw,
+        #[doc = "Only the long name; for directory names the long name of the target."]
+        Long,
+        #[doc = "The labelled form used by `Display`, e.g. `short = PROGRA~1, long = Program Files`."]
+        Annotated
+    }
+
     #[doc = "A struct representing a directory name, consisting of a target name and (optionally) a different source name."]
     pub struct MsiDirectoryName<'a> {
         combined: &'a str
@@ -22,6 +151,32 @@ pub mod directory
     }
     
     impl<'a> MsiDirectoryName<'a> {
+
+        #[doc = "Parses a DefaultDir value strictly, rejecting more than one ':' separator, empty source or target parts and malformed names on either side. Unlike `from`, garbage such as `a:b:c` yields an error instead of a misleading split."]
+        pub fn parse(combined: &'a str) -> Result<Self, NameError>
+        {
+            if combined.is_empty()
+            {
+                return Err(NameError::Empty);
+            }
+
+            if combined.matches(':').count() > 1
+            {
+                return Err(NameError::TooManySeparators(':'));
+            }
+
+            for part in combined.split(':')
+            {
+                if part.is_empty()
+                {
+                    return Err(NameError::EmptyComponent);
+                }
+
+                MsiName::parse(part)?;
+            }
+
+            Ok(MsiDirectoryName::from(combined))
+        }
         
         pub fn source(&self) -> Option<MsiName>
         {
@@ -50,6 +205,21 @@ pub mod directory
         pub fn combined(&self) -> &str {
             self.combined
         }
+
+        #[doc = "Returns an owned copy of the name."]
+        pub fn to_buf(&self) -> MsiDirectoryNameBuf {
+            MsiDirectoryNameBuf::from(self.combined)
+        }
+
+        #[doc = "Renders the directory name in the given format."]
+        pub fn format(&self, format: NameFormat) -> String {
+            match format
+            {
+                NameFormat::Raw => self.combined.to_string(),
+                NameFormat::Long => self.target().long().to_string(),
+                NameFormat::Annotated => self.to_string()
+            }
+        }
     }
     
     impl<'a> Debug for MsiDirectoryName<'a>
@@ -102,6 +272,27 @@ pub mod directory
     }
     
     impl<'a> MsiName<'a> {
+
+        #[doc = "Parses a name strictly, rejecting more than one '|' separator and empty short or long parts. Unlike `from`, `short|long|extra` yields an error instead of a misleading split."]
+        pub fn parse(combined: &'a str) -> Result<Self, NameError>
+        {
+            if combined.is_empty()
+            {
+                return Err(NameError::Empty);
+            }
+
+            if combined.matches('|').count() > 1
+            {
+                return Err(NameError::TooManySeparators('|'));
+            }
+
+            if combined.split('|').any(|part| part.is_empty())
+            {
+                return Err(NameError::EmptyComponent);
+            }
+
+            Ok(MsiName::from(combined))
+        }
     
         #[doc = "Returns the long name of the path."]
         fn long(&self) -> &str {
@@ -132,6 +323,81 @@ pub mod directory
             &self.combined
         }
     
+        #[doc = "Returns an owned copy of the name."]
+        pub fn to_buf(&self) -> MsiNameBuf {
+            MsiNameBuf::from(self.combined)
+        }
+
+        #[doc = "Renders the name in the given format."]
+        pub fn format(&self, format: NameFormat) -> String {
+            match format
+            {
+                NameFormat::Raw => self.combined.to_string(),
+                NameFormat::Long => self.long().to_string(),
+                NameFormat::Annotated => self.to_string()
+            }
+        }
+
+        #[doc = "Returns the canonical combined form of the name: both parts are trimmed, and a short name identical to the long name is dropped."]
+        pub fn normalize(&self) -> String {
+            let long = self.long().trim();
+            match self.short().map(|short| short.trim())
+            {
+                Some(short) if !short.is_empty() && short != long => format!("{}|{}", short, long),
+                _ => long.to_string()
+            }
+        }
+
+        #[doc = "Returns a boolean value indicating whether two names are equal after normalization."]
+        pub fn normalized_eq(&self, other: &MsiName) -> bool {
+            self.normalize() == other.normalize()
+        }
+
+        #[doc = "Checks that the long name is a valid Windows file name: not empty, free of control characters and of `\\ / : * ? \" < > |`, and not a reserved device name."]
+        pub fn validate_long(&self) -> Result<(), NameError> {
+            let long = self.long();
+            if long.is_empty()
+            {
+                return Err(NameError::Empty);
+            }
+
+            if let Some(c) = long.chars().find(|c| c.is_control() || INVALID_LONG_CHARACTERS.contains(c))
+            {
+                return Err(NameError::InvalidCharacter(c));
+            }
+
+            let stem = long.split('.').next().unwrap_or(long).trim_end();
+            if RESERVED_NAMES.iter().any(|reserved| reserved.eq_ignore_ascii_case(stem))
+            {
+                return Err(NameError::ReservedName(stem.to_string()));
+            }
+
+            Ok(())
+        }
+
+        #[doc = "Checks the name against the rules of the Filename column: at most one `|`, no empty part, a short part that is a valid 8.3 name and a long part that is a valid Windows file name of at most 255 characters. A name without `|` is only checked as a long name."]
+        pub fn validate(&self) -> Result<(), NameError>
+        {
+            MsiName::parse(self.combined)?;
+            if let Some(short) = self.short()
+            {
+                validate_short_name(short)?;
+            }
+
+            self.validate_long()?;
+            if self.long().chars().count() > LONG_NAME_LENGTH
+            {
+                return Err(NameError::TooLong(LONG_NAME_LENGTH));
+            }
+
+            Ok(())
+        }
+
+        #[doc = "Checks that the short name, or the name itself if it has no short part, is a valid 8.3 name; see `validate_short_name`."]
+        pub fn validate_short(&self) -> Result<(), NameError> {
+            validate_short_name(self.short().unwrap_or(self.combined))
+        }
+
         #[doc = "Returns a boolean value indicating whether the directory is located ar parent's location."]
         pub fn is_located_at_parent(&self) -> bool {
             if self.long() == "." 
@@ -148,6 +414,301 @@ pub mod directory
             }
         }
     }
+
+    #[doc = "An owned `MsiDirectoryName`, for names that outlive the buffer they were read from. `as_name` borrows it back."]
+    #[derive(Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+    pub struct MsiDirectoryNameBuf {
+        combined: String
+    }
+
+    impl MsiDirectoryNameBuf {
+
+        #[doc = "Builds the combined DefaultDir form of a target name and an optional different source name, each either a plain name or `short|long`, e.g. `new(\"APP|Application\", Some(\"src\"))` gives `src:APP|Application`. Fails if a part is empty or contains a separator it may not hold. A source identical to the target is left out."]
+        pub fn new(target: &str, source: Option<&str>) -> Result<MsiDirectoryNameBuf, NameError>
+        {
+            for part in std::iter::once(target).chain(source)
+            {
+                if part.contains(':')
+                {
+                    return Err(NameError::TooManySeparators(':'));
+                }
+
+                MsiName::parse(part)?;
+            }
+
+            Ok(match source.filter(|source| *source != target)
+            {
+                Some(source) => MsiDirectoryNameBuf::from(format!("{}:{}", source, target)),
+                None => MsiDirectoryNameBuf::from(target)
+            })
+        }
+
+        #[doc = "Returns the name as a borrowed `MsiDirectoryName`."]
+        pub fn as_name(&self) -> MsiDirectoryName<'_> {
+            MsiDirectoryName::from(self.combined.as_str())
+        }
+
+        #[doc = "Returns the combined form, e.g. `SRCDIR|SourceDir:Alpha`."]
+        pub fn combined(&self) -> &str {
+            &self.combined
+        }
+
+        #[doc = "Returns the combined form, consuming the name."]
+        pub fn into_string(self) -> String {
+            self.combined
+        }
+    }
+
+    impl From<String> for MsiDirectoryNameBuf {
+        fn from(combined: String) -> Self {
+            MsiDirectoryNameBuf {
+                combined
+            }
+        }
+    }
+
+    impl From<&str> for MsiDirectoryNameBuf {
+        fn from(combined: &str) -> Self {
+            MsiDirectoryNameBuf::from(combined.to_string())
+        }
+    }
+
+    impl<'a> From<MsiDirectoryName<'a>> for MsiDirectoryNameBuf {
+        fn from(name: MsiDirectoryName<'a>) -> Self {
+            name.to_buf()
+        }
+    }
+
+    impl Borrow<str> for MsiDirectoryNameBuf {
+        fn borrow(&self) -> &str {
+            &self.combined
+        }
+    }
+
+    impl AsRef<str> for MsiDirectoryNameBuf {
+        fn as_ref(&self) -> &str {
+            &self.combined
+        }
+    }
+
+    impl Debug for MsiDirectoryNameBuf {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{}", self.combined)
+        }
+    }
+
+    impl Display for MsiDirectoryNameBuf {
+        fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+            Display::fmt(&self.as_name(), fmt)
+        }
+    }
+
+    #[doc = "An owned `MsiName`, for names that outlive the buffer they were read from. `as_name` borrows it back."]
+    #[derive(Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+    pub struct MsiNameBuf {
+        combined: String
+    }
+
+    impl MsiNameBuf {
+
+        #[doc = "Builds the combined `short|long` form of a long name and an optional short name, e.g. `new(\"Program Files\", Some(\"PROGRA~1\"))` gives `PROGRA~1|Program Files`. Fails if a part is empty or contains `|`. A short name identical to the long name is left out."]
+        pub fn new(long: &str, short: Option<&str>) -> Result<MsiNameBuf, NameError>
+        {
+            for part in std::iter::once(long).chain(short)
+            {
+                if part.is_empty()
+                {
+                    return Err(NameError::EmptyComponent);
+                }
+
+                if part.contains('|')
+                {
+                    return Err(NameError::TooManySeparators('|'));
+                }
+            }
+
+            Ok(match short.filter(|short| *short != long)
+            {
+                Some(short) => MsiNameBuf::from(format!("{}|{}", short, long)),
+                None => MsiNameBuf::from(long)
+            })
+        }
+
+        #[doc = "Returns the name as a borrowed `MsiName`."]
+        pub fn as_name(&self) -> MsiName<'_> {
+            MsiName::from(self.combined.as_str())
+        }
+
+        #[doc = "Returns the combined form, e.g. `PROGRA~1|Program Files`."]
+        pub fn combined(&self) -> &str {
+            &self.combined
+        }
+
+        #[doc = "Returns the combined form, consuming the name."]
+        pub fn into_string(self) -> String {
+            self.combined
+        }
+    }
+
+    impl From<String> for MsiNameBuf {
+        fn from(combined: String) -> Self {
+            MsiNameBuf {
+                combined
+            }
+        }
+    }
+
+    impl From<&str> for MsiNameBuf {
+        fn from(combined: &str) -> Self {
+            MsiNameBuf::from(combined.to_string())
+        }
+    }
+
+    impl<'a> From<MsiName<'a>> for MsiNameBuf {
+        fn from(name: MsiName<'a>) -> Self {
+            name.to_buf()
+        }
+    }
+
+    impl Borrow<str> for MsiNameBuf {
+        fn borrow(&self) -> &str {
+            &self.combined
+        }
+    }
+
+    impl AsRef<str> for MsiNameBuf {
+        fn as_ref(&self) -> &str {
+            &self.combined
+        }
+    }
+
+    impl Debug for MsiNameBuf {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{}", self.combined)
+        }
+    }
+
+    impl Display for MsiNameBuf {
+        fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+            Display::fmt(&self.as_name(), fmt)
+        }
+    }
+
+    #[doc = "Checks a short (8.3) name against the rules of the Filename column: a stem of 1 to 8 characters, optionally followed by a dot and an extension of 1 to 3, without spaces, control characters or any of `\\ / : * ? \" < > | + , ; = [ ]`. Both `~` and lower-case letters are allowed, so generated names such as `PROGRA~1` pass."]
+    pub fn validate_short_name(name: &str) -> Result<(), NameError>
+    {
+        if name.is_empty()
+        {
+            return Err(NameError::Empty);
+        }
+
+        if name == "." || name == ".."
+        {
+            return Ok(());
+        }
+
+        if name.matches('.').count() > 1
+        {
+            return Err(NameError::TooManySeparators('.'));
+        }
+
+        let (stem, extension) = match name.find('.')
+        {
+            Some(index) => (&name[..index], Some(&name[index + 1..])),
+            None => (name, None)
+        };
+        if stem.is_empty() || extension == Some("")
+        {
+            return Err(NameError::EmptyComponent);
+        }
+
+        if let Some(c) = name.chars().find(|c| c.is_control() || (*c != '.' && *c != '~' && INVALID_SHORT_CHARACTERS.contains(c)))
+        {
+            return Err(NameError::InvalidCharacter(c));
+        }
+
+        if stem.chars().count() > SHORT_STEM_LENGTH
+        {
+            return Err(NameError::TooLong(SHORT_STEM_LENGTH));
+        }
+
+        if extension.is_some_and(|extension| extension.chars().count() > SHORT_EXTENSION_LENGTH)
+        {
+            return Err(NameError::TooLong(SHORT_EXTENSION_LENGTH));
+        }
+
+        Ok(())
+    }
+
+    #[doc = "Generates short names for long ones, numbering them `NAME~1.EXT`, `NAME~2.EXT`, ... so that no two names it hands out, or names registered with `reserve`, collide. Names are compared case-insensitively, as the file system does; a directory needs a generator of its own."]
+    #[derive(Clone, Debug, Default)]
+    pub struct ShortNameGenerator {
+        taken: HashSet<String>
+    }
+
+    impl ShortNameGenerator {
+
+        #[doc = "Creates a generator with no names taken."]
+        pub fn new() -> ShortNameGenerator {
+            ShortNameGenerator::default()
+        }
+
+        #[doc = "Marks a name as taken, such as the short name of an existing file in the same directory."]
+        pub fn reserve(&mut self, name: &str) {
+            self.taken.insert(name.to_uppercase());
+        }
+
+        #[doc = "Returns a boolean value indicating whether the name is taken."]
+        pub fn is_taken(&self, name: &str) -> bool {
+            self.taken.contains(&name.to_uppercase())
+        }
+
+        #[doc = "Returns a short name for the long name and marks it as taken. A long name that is already a valid short name is kept, in upper case, if it is free; otherwise the stem is stripped of spaces, dots and characters short names do not allow, upper-cased and cut to make room for a `~N` suffix, and the extension is cut to three characters."]
+        pub fn generate(&mut self, long: &str) -> String
+        {
+            let upper = long.trim().to_uppercase();
+            if validate_short_name(&upper).is_ok() && !upper.contains('~') && !self.is_taken(&upper)
+            {
+                self.taken.insert(upper.clone());
+                return upper;
+            }
+
+            let clean = |text: &str| -> String {
+                text.chars()
+                    .filter(|c| !c.is_control() && !matches!(c, ' ' | '.'))
+                    .map(|c| if INVALID_SHORT_CHARACTERS.contains(&c) { '_' } else { c })
+                    .collect()
+            };
+            let (stem, extension) = match upper.rfind('.').filter(|index| *index > 0)
+            {
+                Some(index) => (clean(&upper[..index]), clean(&upper[index + 1..])),
+                None => (clean(&upper), String::new())
+            };
+            let stem = if stem.is_empty() { "_".to_string() } else { stem };
+            let extension: String = extension.chars().take(SHORT_EXTENSION_LENGTH).collect();
+
+            let mut number = 1usize;
+            loop
+            {
+                let suffix = format!("~{}", number);
+                let mut candidate: String = stem.chars().take(SHORT_STEM_LENGTH.saturating_sub(suffix.len())).collect();
+                candidate.push_str(&suffix);
+                if !extension.is_empty()
+                {
+                    candidate.push('.');
+                    candidate.push_str(&extension);
+                }
+
+                if !self.is_taken(&candidate)
+                {
+                    self.taken.insert(candidate.clone());
+                    return candidate;
+                }
+                number += 1;
+            }
+        }
+    }
+
     #[cfg(test)]
     mod tests
     {
@@ -209,5 +770,151 @@ pub mod directory
             assert_eq!(dir4_tgt.is_located_at_parent(), false);
             assert_eq!(dir4_tgt.short().is_none(), true)
         }
+
+        #[test]
+        fn test_strict_directory_parse()
+        {
+            assert_eq!(MsiDirectoryName::parse("SRCDIR|SourceDir:Alpha").unwrap().target().long(), "Alpha");
+            assert!(MsiDirectoryName::parse("TARGETDIR").is_ok());
+            assert_eq!(MsiDirectoryName::parse("a:b:c").unwrap_err(), NameError::TooManySeparators(':'));
+            assert_eq!(MsiDirectoryName::parse(":Alpha").unwrap_err(), NameError::EmptyComponent);
+            assert_eq!(MsiDirectoryName::parse("Alpha:").unwrap_err(), NameError::EmptyComponent);
+            assert_eq!(MsiDirectoryName::parse("").unwrap_err(), NameError::Empty);
+            assert_eq!(MsiDirectoryName::parse("a|b|c:Alpha").unwrap_err(), NameError::TooManySeparators('|'));
+        }
+
+        #[test]
+        fn test_strict_name_parse()
+        {
+            assert_eq!(MsiName::parse("PROGRA~1|Program Files").unwrap().short(), Some("PROGRA~1"));
+            assert_eq!(MsiName::parse("Alpha").unwrap().short(), None);
+            assert_eq!(MsiName::parse("short|long|extra").unwrap_err(), NameError::TooManySeparators('|'));
+            assert_eq!(MsiName::parse("|long").unwrap_err(), NameError::EmptyComponent);
+            assert_eq!(MsiName::parse("short|").unwrap_err(), NameError::EmptyComponent);
+        }
+
+        #[test]
+        fn test_validate_long()
+        {
+            assert!(MsiName::from("APP~1.EXE|Application.exe").validate_long().is_ok());
+            assert!(MsiName::from(".").validate_long().is_ok());
+            assert_eq!(MsiName::from("").validate_long(), Err(NameError::Empty));
+            assert_eq!(MsiName::from("a|What?.txt").validate_long(), Err(NameError::InvalidCharacter('?')));
+            assert_eq!(MsiName::from("a\\b").validate_long(), Err(NameError::InvalidCharacter('\\')));
+            assert_eq!(MsiName::from("com1.log").validate_long(), Err(NameError::ReservedName("com1".to_string())));
+            assert!(MsiName::from("CONSOLE").validate_long().is_ok());
+        }
+
+        #[test]
+        fn test_short_names()
+        {
+            for valid in ["PROGRA~1", "README.TXT", "a", "setup.exe", "."]
+            {
+                assert!(validate_short_name(valid).is_ok(), "{}", valid);
+            }
+            assert_eq!(validate_short_name("Program Files"), Err(NameError::InvalidCharacter(' ')));
+            assert_eq!(validate_short_name("LONGSTEMS.TXT"), Err(NameError::TooLong(8)));
+            assert_eq!(validate_short_name("README.TEXT"), Err(NameError::TooLong(3)));
+            assert_eq!(validate_short_name("A.B.C"), Err(NameError::TooManySeparators('.')));
+            assert_eq!(validate_short_name(".TXT"), Err(NameError::EmptyComponent));
+            assert_eq!(validate_short_name("A+B"), Err(NameError::InvalidCharacter('+')));
+            assert!(MsiName::from("APPLIC~1.EXE|Application.exe").validate_short().is_ok());
+            assert!(MsiName::from("Application.exe").validate_short().is_err());
+
+            let mut generator = ShortNameGenerator::new();
+            generator.reserve("progra~1");
+            assert_eq!(generator.generate("Program Files"), "PROGRA~2");
+            assert_eq!(generator.generate("Program Files (x86)"), "PROGRA~3");
+            assert_eq!(generator.generate("readme.txt"), "README.TXT");
+            assert_eq!(generator.generate("README.TXT"), "README~1.TXT");
+            assert_eq!(generator.generate("My Application.config"), "MYAPPL~1.CON");
+            assert_eq!(generator.generate(".gitignore"), "GITIGN~1");
+            assert_eq!(generator.generate("a+b=c.tar.gz"), "A_B_CT~1.GZ");
+            for _ in 0..8
+            {
+                generator.generate("Program Files");
+            }
+            assert_eq!(generator.generate("Program Files"), "PROGR~12");
+            assert!(generator.is_taken("progr~12"));
+        }
+
+        #[test]
+        fn test_validate()
+        {
+            assert!(MsiName::from("APPLIC~1.EXE|Application.exe").validate().is_ok());
+            assert!(MsiName::from("Program Files (x86)").validate().is_ok());
+            assert!(MsiName::from(".").validate().is_ok());
+            assert_eq!(MsiName::from("").validate(), Err(NameError::Empty));
+            assert_eq!(MsiName::from("A|B|C").validate(), Err(NameError::TooManySeparators('|')));
+            assert_eq!(MsiName::from("|Application.exe").validate(), Err(NameError::EmptyComponent));
+            assert_eq!(MsiName::from("APPLICATION.EXE|Application.exe").validate(), Err(NameError::TooLong(8)));
+            assert_eq!(MsiName::from("APP.EXE|App?.exe").validate(), Err(NameError::InvalidCharacter('?')));
+            assert_eq!(MsiName::from("NUL.TXT|nul.txt").validate(), Err(NameError::ReservedName("nul".to_string())));
+            assert_eq!(MsiName::from("x".repeat(256).as_str()).validate(), Err(NameError::TooLong(255)));
+        }
+
+        #[test]
+        fn test_owned_names()
+        {
+            let owned = {
+                let buffer = String::from("PROGRA~1|Program Files");
+                MsiName::from(buffer.as_str()).to_buf()
+            };
+            assert_eq!(owned.as_name().short(), Some("PROGRA~1"));
+            assert_eq!(owned.as_name().format(NameFormat::Long), "Program Files");
+            assert_eq!(owned.to_string(), "short = PROGRA~1, long = Program Files");
+            assert_eq!(format!("{:?}", owned), "PROGRA~1|Program Files");
+
+            let mut names = HashSet::new();
+            names.insert(MsiNameBuf::from("README.TXT|ReadMe.txt"));
+            assert!(names.contains("README.TXT|ReadMe.txt"));
+
+            let directory: MsiDirectoryNameBuf = MsiDirectoryName::from("SRCDIR|SourceDir:Alpha").into();
+            assert_eq!(directory.as_name().target().format(NameFormat::Long), "Alpha");
+            assert_eq!(directory.as_name().source().map(|source| source.to_buf()), Some(MsiNameBuf::from("SRCDIR|SourceDir")));
+            assert_eq!(directory.into_string(), "SRCDIR|SourceDir:Alpha");
+        }
+
+        #[test]
+        fn test_new_names()
+        {
+            assert_eq!(MsiNameBuf::new("Program Files", Some("PROGRA~1")).unwrap().combined(), "PROGRA~1|Program Files");
+            assert_eq!(MsiNameBuf::new("README.TXT", Some("README.TXT")).unwrap().combined(), "README.TXT");
+            assert_eq!(MsiNameBuf::new("Alpha", None).unwrap().combined(), "Alpha");
+            assert_eq!(MsiNameBuf::new("a|b", None), Err(NameError::TooManySeparators('|')));
+            assert_eq!(MsiNameBuf::new("Alpha", Some("")), Err(NameError::EmptyComponent));
+
+            let short = MsiNameBuf::new("Application", Some("APP")).unwrap();
+            let directory = MsiDirectoryNameBuf::new(short.combined(), Some("src")).unwrap();
+            assert_eq!(directory.combined(), "src:APP|Application");
+            assert_eq!(directory.as_name().target().short(), Some("APP"));
+            assert_eq!(MsiDirectoryNameBuf::new(".", None).unwrap().combined(), ".");
+            assert_eq!(MsiDirectoryNameBuf::new("a:b", None), Err(NameError::TooManySeparators(':')));
+            assert_eq!(MsiDirectoryNameBuf::new("Alpha", Some("x|y|z")), Err(NameError::TooManySeparators('|')));
+        }
+
+        #[test]
+        fn test_format()
+        {
+            let name = MsiName::from("PROGRA~1|Program Files");
+            assert_eq!(name.format(NameFormat::Raw), "PROGRA~1|Program Files");
+            assert_eq!(name.format(NameFormat::Long), "Program Files");
+            assert_eq!(name.format(NameFormat::Annotated), "short = PROGRA~1, long = Program Files");
+
+            let directory = MsiDirectoryName::from("SRCDIR|SourceDir:Alpha");
+            assert_eq!(directory.format(NameFormat::Raw), "SRCDIR|SourceDir:Alpha");
+            assert_eq!(directory.format(NameFormat::Long), "Alpha");
+            assert_eq!(directory.format(NameFormat::Annotated), "source = [short = SRCDIR, long = SourceDir], target = [Alpha]");
+        }
+
+        #[test]
+        fn test_normalize()
+        {
+            assert_eq!(MsiName::from("Alpha|Alpha").normalize(), "Alpha");
+            assert_eq!(MsiName::from(" PROGRA~1 | Program Files ").normalize(), "PROGRA~1|Program Files");
+            assert_eq!(MsiName::from("|Alpha").normalize(), "Alpha");
+            assert!(MsiName::from("Alpha").normalized_eq(&MsiName::from("Alpha |Alpha")));
+            assert!(!MsiName::from("ALPHA~1|Alpha").normalized_eq(&MsiName::from("Alpha")));
+        }
     }
 }
\ No newline at end of file
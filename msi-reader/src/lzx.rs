@@ -0,0 +1,639 @@
+// LZX decompression: the compressed data blocks of a folder form one bitstream of 16-bit little-endian
+// words, read most significant bit first, that decodes to frames of (at most) 32 KB, one per data block.
+// Every frame ends on a word boundary at the end of its data block, so the blocks are fed to the decoder
+// one at a time.
+use crate::error::{ Error, Result };
+use crate::huffman::Huffman;
+
+const MIN_WINDOW_BITS: u8 = 15;
+const MAX_WINDOW_BITS: u8 = 21;
+
+const LITERALS: usize = 256;
+const LENGTHS: usize = 249;
+const ALIGNED: usize = 8;
+const PRETREE: usize = 20;
+const MIN_MATCH: usize = 2;
+// matches whose length header is all ones read the rest of their length from the length tree
+const PRIMARY_LENGTHS: usize = 7;
+// E8 translation stops after the first 32768 frames (1 GB of output)
+const MAX_TRANSLATED_FRAMES: usize = 32768;
+
+#[derive(Clone, Copy, PartialEq)]
+enum BlockKind {
+    Verbatim,
+    Aligned,
+    Uncompressed
+}
+
+// The Huffman trees of the current verbatim or aligned block.
+struct Trees {
+    main: Huffman,
+    length: Huffman,
+    aligned: Option<Huffman>
+}
+
+#[doc = "Decodes the LZX bitstream of one cabinet folder, frame by frame."]
+pub(crate) struct Decoder {
+    bits: BitReader,
+    window_size: usize,
+    // the untranslated output, always holding at least the last window
+    history: Vec<u8>,
+    main_lengths: Vec<u8>,
+    length_lengths: [u8; LENGTHS],
+    trees: Option<Trees>,
+    kind: BlockKind,
+    block_length: usize,
+    block_remaining: usize,
+    // bytes of the last match of a block that ran into the next one, taken off that block once its header is read
+    overrun: usize,
+    repeats: [usize; 3],
+    header_read: bool,
+    intel_started: bool,
+    intel_size: i32,
+    intel_position: i32,
+    frame: usize
+}
+
+impl Decoder {
+
+    #[doc = "Creates a decoder for the compressed data blocks of a folder using a window of 2^`window_bits` bytes."]
+    pub(crate) fn new(window_bits: u8) -> Result<Decoder>
+    {
+        let slots = match window_bits
+        {
+            MIN_WINDOW_BITS..=MAX_WINDOW_BITS => position_slots(window_bits),
+            _ => return Err(Error::invalid(format!("LZX window size of {} bits is not supported", window_bits)))
+        };
+
+        Ok(Decoder {
+            bits: BitReader::new(),
+            window_size: 1 << window_bits,
+            history: Vec::new(),
+            main_lengths: vec![0; LITERALS + slots * 8],
+            length_lengths: [0; LENGTHS],
+            trees: None,
+            kind: BlockKind::Verbatim,
+            block_length: 0,
+            block_remaining: 0,
+            overrun: 0,
+            repeats: [1; 3],
+            header_read: false,
+            intel_started: false,
+            intel_size: 0,
+            intel_position: 0,
+            frame: 0
+        })
+    }
+
+    #[doc = "Decodes the frame of `frame_size` bytes held by the next data block of the folder and appends it to `output`."]
+    pub(crate) fn decode_frame(&mut self, block: &[u8], frame_size: usize, output: &mut Vec<u8>) -> Result<()>
+    {
+        self.bits.feed(block);
+        if !self.header_read
+        {
+            if self.bits.read(1)? == 1
+            {
+                let high = self.bits.read(16)?;
+                self.intel_size = (high << 16 | self.bits.read(16)?) as i32;
+            }
+            self.header_read = true;
+        }
+
+        let start = self.history.len();
+        let mut todo = frame_size;
+        while todo > 0
+        {
+            if self.block_remaining == 0
+            {
+                self.read_block_header()?;
+            }
+
+            let run = self.block_remaining.min(todo);
+            let decoded = match self.kind
+            {
+                BlockKind::Uncompressed => {
+                    let bytes = self.bits.read_bytes(run)?;
+                    self.history.extend_from_slice(bytes);
+                    run
+                },
+                _ => self.decode_run(run, todo)?
+            };
+            self.block_remaining -= run;
+            self.overrun = decoded - run;
+            todo -= decoded;
+        }
+        self.bits.realign();
+
+        let first = output.len();
+        output.extend_from_slice(&self.history[start..]);
+        if self.intel_started && self.intel_size != 0 && self.frame < MAX_TRANSLATED_FRAMES && frame_size > 10
+        {
+            translate(&mut output[first..], self.intel_position, self.intel_size);
+        }
+        self.intel_position = self.intel_position.wrapping_add(frame_size as i32);
+        self.frame += 1;
+
+        // keep the history bounded, matches only reach one window back
+        if self.history.len() >= 2 * self.window_size
+        {
+            self.history.drain(..self.history.len() - self.window_size);
+        }
+
+        Ok(())
+    }
+
+    fn read_block_header(&mut self) -> Result<()>
+    {
+        // an uncompressed block of odd length is followed by a padding byte
+        if self.kind == BlockKind::Uncompressed && self.block_length % 2 == 1
+        {
+            self.bits.read_bytes(1)?;
+        }
+
+        let kind = self.bits.read(3)?;
+        let high = self.bits.read(16)?;
+        self.block_length = (high << 8 | self.bits.read(8)?) as usize;
+        self.block_remaining = self.block_length.checked_sub(self.overrun)
+            .ok_or_else(|| Error::invalid("LZX match runs past the end of the following block"))?;
+        self.overrun = 0;
+
+        match kind
+        {
+            1 | 2 => {
+                let aligned = match kind
+                {
+                    2 => {
+                        let mut lengths = [0u8; ALIGNED];
+                        for length in lengths.iter_mut()
+                        {
+                            *length = self.bits.read(3)? as u8;
+                        }
+                        Some(Huffman::new(&lengths)?)
+                    },
+                    _ => None
+                };
+
+                read_lengths(&mut self.bits, &mut self.main_lengths[..LITERALS])?;
+                read_lengths(&mut self.bits, &mut self.main_lengths[LITERALS..])?;
+                if self.main_lengths[0xe8] != 0
+                {
+                    self.intel_started = true;
+                }
+                read_lengths(&mut self.bits, &mut self.length_lengths)?;
+
+                self.kind = if aligned.is_some() { BlockKind::Aligned } else { BlockKind::Verbatim };
+                self.trees = Some(Trees {
+                    main: Huffman::new(&self.main_lengths)?,
+                    length: Huffman::new(&self.length_lengths)?,
+                    aligned
+                });
+            },
+            3 => {
+                self.intel_started = true;
+                self.bits.align();
+                for repeat in self.repeats.iter_mut()
+                {
+                    let bytes = self.bits.read_bytes(4)?;
+                    *repeat = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize;
+                }
+                self.kind = BlockKind::Uncompressed;
+                self.trees = None;
+            },
+            _ => return Err(Error::invalid(format!("LZX stream uses the invalid block type {}", kind)))
+        }
+
+        Ok(())
+    }
+
+    // Decodes at least `run` bytes of a verbatim or aligned block into the history and returns how many. The last match
+    // may run on into the next block, as encoders emit it, but not past the `limit` bytes left in the frame.
+    fn decode_run(&mut self, run: usize, limit: usize) -> Result<usize>
+    {
+        let trees = self.trees.as_ref().ok_or_else(|| Error::invalid("LZX block has no Huffman trees"))?;
+        let bits = &mut self.bits;
+        let start = self.history.len();
+        let end = start + run;
+        while self.history.len() < end
+        {
+            let symbol = trees.main.decode(|| bits.read(1))? as usize;
+            if symbol < LITERALS
+            {
+                self.history.push(symbol as u8);
+                continue;
+            }
+
+            let symbol = symbol - LITERALS;
+            let mut length = symbol & PRIMARY_LENGTHS;
+            if length == PRIMARY_LENGTHS
+            {
+                length += trees.length.decode(|| bits.read(1))? as usize;
+            }
+            let length = length + MIN_MATCH;
+
+            let offset = match symbol >> 3
+            {
+                0 => self.repeats[0],
+                slot @ 1..=2 => {
+                    self.repeats.swap(0, slot);
+                    self.repeats[0]
+                },
+                slot => {
+                    let extra = extra_bits(slot);
+                    let base = position_base(slot) - 2;
+                    let offset = match &trees.aligned
+                    {
+                        Some(aligned) if extra >= 3 => {
+                            let verbatim = bits.read(extra - 3)? as usize;
+                            base + (verbatim << 3) + aligned.decode(|| bits.read(1))? as usize
+                        },
+                        _ => base + bits.read(extra)? as usize
+                    };
+                    self.repeats = [offset, self.repeats[0], self.repeats[1]];
+                    offset
+                }
+            };
+
+            if self.history.len() + length > start + limit
+            {
+                return Err(Error::invalid("LZX match runs past the end of its frame"));
+            }
+            if offset == 0 || offset > self.history.len() || offset > self.window_size
+            {
+                return Err(Error::invalid(format!("LZX stream refers {} bytes back, outside of the window", offset)));
+            }
+
+            let from = self.history.len() - offset;
+            for index in 0..length
+            {
+                let byte = self.history[from + index];
+                self.history.push(byte);
+            }
+        }
+
+        Ok(self.history.len() - start)
+    }
+}
+
+// Returns the number of position slots of a window size.
+fn position_slots(window_bits: u8) -> usize
+{
+    match window_bits
+    {
+        20 => 42,
+        21 => 50,
+        bits => bits as usize * 2
+    }
+}
+
+// Returns the number of extra offset bits of a position slot.
+fn extra_bits(slot: usize) -> u8 {
+    (slot.saturating_sub(2) / 2).min(17) as u8
+}
+
+// Returns the smallest offset (plus two) encoded by a position slot.
+fn position_base(slot: usize) -> usize {
+    (0..slot).map(|slot| 1 << extra_bits(slot)).sum()
+}
+
+// Reads code lengths as differences to the previous ones, coded with a pretree stored in front of them.
+fn read_lengths(bits: &mut BitReader, lengths: &mut [u8]) -> Result<()>
+{
+    let mut pretree = [0u8; PRETREE];
+    for length in pretree.iter_mut()
+    {
+        *length = bits.read(4)? as u8;
+    }
+    let pretree = Huffman::new(&pretree)?;
+
+    let mut index = 0;
+    while index < lengths.len()
+    {
+        let (length, repeat) = match pretree.decode(|| bits.read(1))?
+        {
+            17 => (0, 4 + bits.read(4)? as usize),
+            18 => (0, 20 + bits.read(5)? as usize),
+            19 => {
+                let repeat = 4 + bits.read(1)? as usize;
+                let delta = pretree.decode(|| bits.read(1))?;
+                if delta > 16
+                {
+                    return Err(Error::invalid("LZX pretree repeats an invalid length"));
+                }
+                ((lengths[index] + 17 - delta as u8) % 17, repeat)
+            },
+            delta => ((lengths[index] + 17 - delta as u8) % 17, 1)
+        };
+        if index + repeat > lengths.len()
+        {
+            return Err(Error::invalid("LZX pretree repeats lengths beyond the number of codes"));
+        }
+        lengths[index..index + repeat].iter_mut().for_each(|value| *value = length);
+        index += repeat;
+    }
+
+    Ok(())
+}
+
+// Undoes the encoder's conversion of the relative targets of x86 CALL instructions (0xE8) into absolute ones.
+fn translate(data: &mut [u8], position: i32, size: i32)
+{
+    let mut index = 0;
+    let mut current = position;
+    while index + 10 < data.len()
+    {
+        if data[index] != 0xe8
+        {
+            index += 1;
+            current = current.wrapping_add(1);
+            continue;
+        }
+
+        let absolute = i32::from_le_bytes([data[index + 1], data[index + 2], data[index + 3], data[index + 4]]);
+        if absolute >= current.wrapping_neg() && absolute < size
+        {
+            let relative = if absolute >= 0 { absolute - current } else { absolute + size };
+            data[index + 1..index + 5].copy_from_slice(&relative.to_le_bytes());
+        }
+        index += 5;
+        current = current.wrapping_add(5);
+    }
+}
+
+// Reads 16-bit little-endian words most significant bit first, as LZX packs them.
+struct BitReader {
+    // the data block being read, with what is left of the ones before
+    data: Vec<u8>,
+    position: usize,
+    buffer: u64,
+    count: u8,
+    // zero words supplied past the end of the input, a frame may end within them
+    padding: u8
+}
+
+impl BitReader {
+
+    fn new() -> Self
+    {
+        BitReader {
+            data: Vec::new(),
+            position: 0,
+            buffer: 0,
+            count: 0,
+            padding: 0
+        }
+    }
+
+    // Drops the bytes read so far and appends the next data block.
+    fn feed(&mut self, block: &[u8])
+    {
+        self.data.drain(..self.position.min(self.data.len()));
+        self.data.extend_from_slice(block);
+        self.position = 0;
+        self.padding = 0;
+    }
+
+    fn fill(&mut self, count: u8) -> Result<()>
+    {
+        while self.count < count
+        {
+            let word = match self.data.get(self.position..self.position + 2)
+            {
+                Some(word) => {
+                    self.position += 2;
+                    u16::from_le_bytes([word[0], word[1]])
+                },
+                None if self.padding < 2 => {
+                    self.padding += 1;
+                    0
+                },
+                None => return Err(Error::invalid("LZX stream ends unexpectedly"))
+            };
+            self.buffer = self.buffer << 16 | word as u64;
+            self.count += 16;
+        }
+
+        Ok(())
+    }
+
+    fn read(&mut self, count: u8) -> Result<u32>
+    {
+        self.fill(count)?;
+        self.count -= count;
+        let value = (self.buffer >> self.count) & ((1u64 << count) - 1);
+        self.buffer &= (1u64 << self.count) - 1;
+        Ok(value as u32)
+    }
+
+    // Drops the bits left in the current word after a frame, without reading into the next data block.
+    fn realign(&mut self)
+    {
+        self.count -= self.count % 16;
+        self.buffer &= (1u64 << self.count) - 1;
+    }
+
+    // Moves to the byte stream of an uncompressed block, a word of padding is skipped if the bits are aligned.
+    fn align(&mut self)
+    {
+        if self.count == 0
+        {
+            self.position += 2;
+        }
+        self.buffer = 0;
+        self.count = 0;
+    }
+
+    fn read_bytes(&mut self, count: usize) -> Result<&[u8]>
+    {
+        let bytes = self.data.get(self.position..self.position + count).ok_or_else(|| Error::invalid("uncompressed LZX block ends unexpectedly"))?;
+        self.position += count;
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests
+{
+    use super::*;
+
+    // Writes 16-bit little-endian words most significant bit first.
+    struct BitWriter {
+        bytes: Vec<u8>,
+        buffer: u32,
+        count: u8
+    }
+
+    impl BitWriter {
+
+        fn new() -> Self
+        {
+            BitWriter {
+                bytes: Vec::new(),
+                buffer: 0,
+                count: 0
+            }
+        }
+
+        fn write(&mut self, value: u32, count: u8)
+        {
+            for bit in (0..count).rev()
+            {
+                self.buffer = self.buffer << 1 | (value >> bit & 1);
+                self.count += 1;
+                if self.count == 16
+                {
+                    self.bytes.extend_from_slice(&(self.buffer as u16).to_le_bytes());
+                    self.buffer = 0;
+                    self.count = 0;
+                }
+            }
+        }
+
+        fn finish(mut self) -> Vec<u8>
+        {
+            if self.count > 0
+            {
+                self.write(0, 16 - self.count);
+            }
+            self.bytes
+        }
+    }
+
+    // Writes lengths with a pretree giving the two-bit codes 00, 01, 10 and 11 to the symbols 0, 15, 17 and 18.
+    fn write_lengths(writer: &mut BitWriter, symbols: &[(u32, u32)])
+    {
+        for symbol in 0..PRETREE as u32
+        {
+            writer.write(if [0, 15, 17, 18].contains(&symbol) { 2 } else { 0 }, 4);
+        }
+        for (symbol, extra) in symbols
+        {
+            match symbol
+            {
+                0 => writer.write(0, 2),
+                15 => writer.write(1, 2),
+                17 => {
+                    writer.write(2, 2);
+                    writer.write(extra - 4, 4);
+                },
+                _ => {
+                    writer.write(3, 2);
+                    writer.write(extra - 20, 5);
+                }
+            }
+        }
+    }
+
+    pub(crate) const VERBATIM: &[u8] = b"abcabcabc";
+
+    // Writes a verbatim block (window of 15 bits) of the given length holding "abc" and a match of six bytes three bytes back,
+    // the four main symbols all get two-bit codes.
+    fn write_verbatim_block(writer: &mut BitWriter, length: u32)
+    {
+        writer.write(1, 3);
+        writer.write(0, 16);
+        writer.write(length, 8);
+
+        write_lengths(writer, &[(18, 51), (18, 46), (15, 0), (15, 0), (15, 0), (18, 51), (18, 51), (18, 34), (18, 20)]);
+        write_lengths(writer, &[(18, 36), (15, 0), (18, 51), (18, 51), (18, 51), (18, 50)]);
+        write_lengths(writer, &[(18, 51), (18, 51), (18, 51), (18, 51), (18, 45)]);
+
+        // 'a', 'b', 'c', then slot 4 with length header 4 and one extra bit
+        writer.write(0b00, 2);
+        writer.write(0b01, 2);
+        writer.write(0b10, 2);
+        writer.write(0b11, 2);
+        writer.write(1, 1);
+    }
+
+    // The verbatim block alone, decoding to `VERBATIM`.
+    pub(crate) fn verbatim_block() -> Vec<u8>
+    {
+        let mut writer = BitWriter::new();
+        writer.write(0, 1);
+        write_verbatim_block(&mut writer, VERBATIM.len() as u32);
+        writer.finish()
+    }
+
+    #[test]
+    fn test_decode_frame()
+    {
+        let mut output = Vec::new();
+        let mut decoder = Decoder::new(15).unwrap();
+        decoder.decode_frame(&verbatim_block(), VERBATIM.len(), &mut output).unwrap();
+        assert_eq!(output, VERBATIM);
+
+        // an uncompressed block after an E8 translation header, the call target is turned back into a relative one
+        let mut writer = BitWriter::new();
+        writer.write(1, 1);
+        writer.write(0x0010, 16);
+        writer.write(0, 16);
+        writer.write(3, 3);
+        writer.write(0, 16);
+        writer.write(17, 8);
+        let mut input = writer.finish();
+        input.extend_from_slice(&[1, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0]);
+        input.extend_from_slice(b"ABCD\xe8\x10\x00\x00\x00EFGHIJKL");
+
+        let mut output = Vec::new();
+        Decoder::new(16).unwrap().decode_frame(&input, 17, &mut output).unwrap();
+        assert_eq!(output, b"ABCD\xe8\x0c\x00\x00\x00EFGHIJKL");
+
+        // the block holds nine bytes only and the window sizes are limited
+        assert!(Decoder::new(15).unwrap().decode_frame(&verbatim_block(), 10, &mut Vec::new()).is_err());
+        assert!(Decoder::new(15).unwrap().decode_frame(&verbatim_block()[..8], 9, &mut Vec::new()).is_err());
+        assert!(Decoder::new(14).is_err());
+        assert!(Decoder::new(22).is_err());
+    }
+
+    #[test]
+    fn test_frames_by_block()
+    {
+        // the verbatim block in the first data block, an uncompressed block referring to nothing before in the second
+        let mut writer = BitWriter::new();
+        writer.write(3, 3);
+        writer.write(0, 16);
+        writer.write(4, 8);
+        let mut second = writer.finish();
+        second.extend_from_slice(&[1, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0]);
+        second.extend_from_slice(b"wxyz");
+
+        let mut output = Vec::new();
+        let mut decoder = Decoder::new(15).unwrap();
+        decoder.decode_frame(&verbatim_block(), VERBATIM.len(), &mut output).unwrap();
+        decoder.decode_frame(&second, 4, &mut output).unwrap();
+        assert_eq!(output, b"abcabcabcwxyz");
+
+        // a frame cannot read on into the data block of the next one
+        let mut decoder = Decoder::new(15).unwrap();
+        assert!(decoder.decode_frame(&verbatim_block()[..8], 9, &mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_match_across_blocks()
+    {
+        // a block of six bytes whose final match runs three bytes into the next block, which keeps the
+        // code lengths and holds "abc" in its remaining three bytes
+        let mut writer = BitWriter::new();
+        writer.write(0, 1);
+        write_verbatim_block(&mut writer, 6);
+        writer.write(1, 3);
+        writer.write(0, 16);
+        writer.write(6, 8);
+        for count in [LITERALS, position_slots(15) * 8, LENGTHS]
+        {
+            write_lengths(&mut writer, &vec![(0, 0); count]);
+        }
+        writer.write(0b00, 2);
+        writer.write(0b01, 2);
+        writer.write(0b10, 2);
+        let input = writer.finish();
+
+        let mut output = Vec::new();
+        Decoder::new(15).unwrap().decode_frame(&input, 12, &mut output).unwrap();
+        assert_eq!(output, b"abcabcabcabc");
+
+        // the same match may not cross the end of a frame
+        let error = Decoder::new(15).unwrap().decode_frame(&input, 6, &mut Vec::new()).err().unwrap();
+        assert!(error.to_string().contains("past the end of its frame"), "{}", error);
+    }
+}
@@ -0,0 +1,104 @@
+mod cli;
+
+use cli::{ Args, Failure };
+
+const COMMANDS: &[&str] = &[
+    cli::cert::USAGE,
+    cli::codepage::USAGE,
+    cli::cost::USAGE,
+    cli::diff::USAGE,
+    cli::digest::USAGE,
+    cli::dump::USAGE,
+    cli::edit::USAGE,
+    cli::export::USAGE,
+    cli::extract::USAGE,
+    cli::ice::USAGE,
+    cli::patch::USAGE,
+    cli::props::USAGE,
+    cli::report::USAGE,
+    cli::sbom::USAGE,
+    cli::scripts::USAGE,
+    cli::split_languages::USAGE,
+    cli::suite::USAGE,
+    cli::summary::USAGE,
+    cli::tables::USAGE,
+    cli::transform::USAGE,
+    cli::tree::USAGE,
+    cli::unsign::USAGE,
+    cli::upgrades::USAGE,
+    cli::validate::USAGE,
+    #[cfg(all(windows, feature = "windows"))]
+    cli::verify_installed::USAGE
+];
+
+fn usage() -> String
+{
+    let mut text = String::from("usage: msi-reader <command> [arguments]\n\ncommands:\n");
+    for command in COMMANDS
+    {
+        text.push_str("    ");
+        text.push_str(command);
+        text.push('\n');
+    }
+
+    text
+}
+
+fn run(command: &str, args: Args) -> cli::Result<()>
+{
+    match command
+    {
+        "cert" => cli::cert::run(args),
+        "codepage" => cli::codepage::run(args),
+        "cost" => cli::cost::run(args),
+        "diff" => cli::diff::run(args),
+        "digest" => cli::digest::run(args),
+        "dump" => cli::dump::run(args),
+        "edit" => cli::edit::run(args),
+        "export" => cli::export::run(args),
+        "extract" => cli::extract::run(args),
+        "ice" => cli::ice::run(args),
+        "patch" => cli::patch::run(args),
+        "props" => cli::props::run(args),
+        "report" => cli::report::run(args),
+        "sbom" => cli::sbom::run(args),
+        "scripts" => cli::scripts::run(args),
+        "split-languages" => cli::split_languages::run(args),
+        "suite" => cli::suite::run(args),
+        "summary" => cli::summary::run(args),
+        "tables" => cli::tables::run(args),
+        "transform" => cli::transform::run(args),
+        "tree" => cli::tree::run(args),
+        "unsign" => cli::unsign::run(args),
+        "upgrades" => cli::upgrades::run(args),
+        "validate" => cli::validate::run(args),
+        #[cfg(all(windows, feature = "windows"))]
+        "verify-installed" => cli::verify_installed::run(args),
+        other => Err(Failure::Usage(format!("unknown command '{}'", other)))
+    }
+}
+
+fn main()
+{
+    let mut arguments = std::env::args().skip(1);
+    let command = match arguments.next()
+    {
+        Some(command) if command != "help" && command != "--help" && command != "-h" => command,
+        _ => {
+            print!("{}", usage());
+            return;
+        }
+    };
+
+    if let Err(failure) = run(&command, Args::new(arguments))
+    {
+        match &failure
+        {
+            Failure::Usage(message) => eprint!("error: {}\n\n{}", message, usage()),
+            Failure::Error(error) => eprintln!("error: {}", error),
+            Failure::Status(_) => {}
+        }
+
+        std::process::exit(failure.exit_code());
+    }
+}
@@ -0,0 +1,256 @@
+use std::fmt::Display;
+
+use crate::cabinet::{ Cabinet, CabinetSet };
+use crate::error::{ Error, Result };
+use crate::package::MsiPackage;
+use crate::table::RowOrigin;
+
+#[doc = "The cabinet of a Media row."]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MediaCabinet<'a> {
+    #[doc = "A cabinet stored as a stream of the package, named without the leading `#`."]
+    Embedded(&'a str),
+    #[doc = "A cabinet file next to the package on the source media."]
+    External(&'a str)
+}
+
+impl MediaCabinet<'_> {
+
+    #[doc = "Returns the name of the stream or file."]
+    pub fn name(&self) -> &str {
+        match self
+        {
+            MediaCabinet::Embedded(name) | MediaCabinet::External(name) => name
+        }
+    }
+}
+
+impl Display for MediaCabinet<'_> {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self
+        {
+            MediaCabinet::Embedded(name) => write!(fmt, "#{}", name),
+            MediaCabinet::External(name) => write!(fmt, "{}", name)
+        }
+    }
+}
+
+#[doc = "A row of the Media table."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct MediaRow {
+    disk_id: i32,
+    last_sequence: i32,
+    disk_prompt: Option<String>,
+    cabinet: Option<String>,
+    volume_label: Option<String>,
+    source: Option<String>,
+    origin: RowOrigin
+}
+
+impl MediaRow {
+
+    #[doc = "Returns the primary key of the disk."]
+    pub fn disk_id(&self) -> i32 {
+        self.disk_id
+    }
+
+    #[doc = "Returns the sequence number of the last file on the disk."]
+    pub fn last_sequence(&self) -> i32 {
+        self.last_sequence
+    }
+
+    #[doc = "Returns the text used to ask for the disk."]
+    pub fn disk_prompt(&self) -> Option<&str> {
+        self.disk_prompt.as_deref()
+    }
+
+    #[doc = "Returns the cabinet holding the files of the disk, or `None` if they are stored uncompressed on the source media."]
+    pub fn cabinet(&self) -> Option<MediaCabinet<'_>> {
+        self.cabinet.as_deref().map(|cabinet| match cabinet.strip_prefix('#')
+        {
+            Some(stream) => MediaCabinet::Embedded(stream),
+            None => MediaCabinet::External(cabinet)
+        })
+    }
+
+    #[doc = "Returns the volume label of the disk."]
+    pub fn volume_label(&self) -> Option<&str> {
+        self.volume_label.as_deref()
+    }
+
+    #[doc = "Returns the property holding the source of a patch's media."]
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    #[doc = "Returns the Media row the disk was read from."]
+    pub fn origin(&self) -> &RowOrigin {
+        &self.origin
+    }
+}
+
+#[doc = "The typed rows of the Media table, in storage order."]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MediaTable {
+    rows: Vec<MediaRow>
+}
+
+impl MediaTable {
+
+    #[doc = "Reads the Media table of the package; a package without one yields an empty table."]
+    pub fn read(package: &MsiPackage) -> Result<MediaTable>
+    {
+        let table = match package.optional_table("Media")?
+        {
+            Some(table) => table,
+            None => return Ok(MediaTable::default())
+        };
+
+        let rows = table.rows()
+            .map(|row| MediaRow {
+                disk_id: row.int("DiskId").unwrap_or(0),
+                last_sequence: row.int("LastSequence").unwrap_or(0),
+                disk_prompt: row.str("DiskPrompt").map(|prompt| prompt.to_string()),
+                cabinet: row.str("Cabinet").filter(|cabinet| !cabinet.is_empty()).map(|cabinet| cabinet.to_string()),
+                volume_label: row.str("VolumeLabel").map(|label| label.to_string()),
+                source: row.str("Source").map(|source| source.to_string()),
+                origin: row.origin()
+            })
+            .collect();
+
+        Ok(MediaTable {
+            rows
+        })
+    }
+
+    #[doc = "Returns all disks in storage order."]
+    pub fn rows(&self) -> &[MediaRow] {
+        &self.rows
+    }
+
+    #[doc = "Returns the disk with the given id."]
+    pub fn get(&self, disk_id: i32) -> Option<&MediaRow> {
+        self.rows.iter().find(|row| row.disk_id == disk_id)
+    }
+
+    #[doc = "Returns the disk holding the file with the given File.Sequence: the one with the lowest LastSequence that is not below it."]
+    pub fn media_for_sequence(&self, sequence: i32) -> Option<&MediaRow> {
+        self.rows.iter()
+            .filter(|row| sequence >= 1 && row.last_sequence >= sequence)
+            .min_by_key(|row| (row.last_sequence, row.disk_id))
+    }
+
+    #[doc = "Returns the cabinet holding the file with the given File.Sequence, or `None` if no disk covers it or the file is stored uncompressed."]
+    pub fn cabinet_for_sequence(&self, sequence: i32) -> Option<MediaCabinet<'_>> {
+        self.media_for_sequence(sequence).and_then(|row| row.cabinet())
+    }
+}
+
+#[doc = "Reads a cabinet of the package and, while the last one read continues its last folder, the next cabinet named in its header, joined into one set. Embedded cabinets continue in other streams and external ones in other files of the source root."]
+pub fn read_cabinets(package: &MsiPackage, cabinet: MediaCabinet<'_>) -> Result<CabinetSet>
+{
+    let mut names = vec![cabinet.name().to_string()];
+    let mut cabinets = vec![read_cabinet(package, cabinet)?];
+    while let Some(next) = cabinets.last().filter(|cabinet| cabinet.is_continued()).and_then(|cabinet| cabinet.next_cabinet())
+    {
+        if names.iter().any(|name| name.eq_ignore_ascii_case(next))
+        {
+            return Err(Error::invalid(format!("cabinet '{}' continues in itself", next)));
+        }
+
+        let next = next.to_string();
+        let cabinet = match cabinet
+        {
+            MediaCabinet::Embedded(_) => read_cabinet(package, MediaCabinet::Embedded(&next))?,
+            MediaCabinet::External(_) => read_cabinet(package, MediaCabinet::External(&next))?
+        };
+        names.push(next);
+        cabinets.push(cabinet);
+    }
+
+    CabinetSet::new(cabinets)
+}
+
+// Reads a single cabinet from its stream or file.
+fn read_cabinet(package: &MsiPackage, cabinet: MediaCabinet<'_>) -> Result<Cabinet>
+{
+    let data = match cabinet
+    {
+        MediaCabinet::Embedded(stream) => package.read_stream(stream)?,
+        MediaCabinet::External(name) => {
+            let root = package.source_root()
+                .ok_or_else(|| Error::NotFound(format!("source root to look for cabinet '{}' in", name)))?;
+            let path = root.join(name);
+            if !path.is_file()
+            {
+                return Err(Error::NotFound(format!("cabinet '{}'", path.display())));
+            }
+            std::fs::read(path)?
+        }
+    };
+
+    Cabinet::parse(data)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::testutil::TestPackage;
+
+    #[test]
+    fn test_cabinet_for_sequence()
+    {
+        let package = TestPackage::new("media-table", |builder| {
+            builder.table("Media", vec![
+                msi::Column::build("DiskId").primary_key().int16(),
+                msi::Column::build("LastSequence").int32(),
+                msi::Column::build("DiskPrompt").nullable().localizable().text_string(64),
+                msi::Column::build("Cabinet").nullable().category(msi::Category::Cabinet).string(255),
+                msi::Column::build("VolumeLabel").nullable().text_string(32),
+                msi::Column::build("Source").nullable().category(msi::Category::Property).string(72)
+            ], vec![
+                vec![msi::Value::Int(1), msi::Value::Int(10), msi::Value::Null, msi::Value::from("#cab1.cab"), msi::Value::Null, msi::Value::Null],
+                vec![msi::Value::Int(2), msi::Value::Int(25), msi::Value::from("Disk 2"), msi::Value::from("data2.cab"), msi::Value::from("DISK2"), msi::Value::Null],
+                vec![msi::Value::Int(3), msi::Value::Int(30), msi::Value::Null, msi::Value::Null, msi::Value::Null, msi::Value::Null]
+            ]);
+        });
+
+        let media = MediaTable::read(&MsiPackage::open(package.path()).unwrap()).unwrap();
+        assert_eq!(media.cabinet_for_sequence(1), Some(MediaCabinet::Embedded("cab1.cab")));
+        assert_eq!(media.cabinet_for_sequence(10).map(|cabinet| cabinet.to_string()).as_deref(), Some("#cab1.cab"));
+        assert_eq!(media.cabinet_for_sequence(11), Some(MediaCabinet::External("data2.cab")));
+        assert_eq!(media.media_for_sequence(26).map(|row| row.disk_id()), Some(3));
+        assert_eq!((media.cabinet_for_sequence(26), media.cabinet_for_sequence(31), media.cabinet_for_sequence(0)), (None, None, None));
+        assert_eq!(media.get(2).unwrap().volume_label(), Some("DISK2"));
+    }
+    #[test]
+    fn test_read_cabinets()
+    {
+        use crate::cabinet::tests::{ build_cab, build_spanned_cab };
+
+        let package = TestPackage::new("media-cabinets", |builder| {
+            builder.stream("inner.cab", &build_cab(&[("inner.txt", b"inside")]));
+        });
+        let root = std::env::temp_dir().join(format!("msi-reader-media-cabinets-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("one.cab"), build_spanned_cab((None, Some("two.cab")), &[("a.txt", 6, 0, 0), ("b.txt", 6, 6, 0xfffe)], 0,
+            &[(b"hello ", 6), (b"wor", 0)])).unwrap();
+        std::fs::write(root.join("two.cab"), build_spanned_cab((Some("one.cab"), None), &[("b.txt", 6, 6, 0xfffd)], 0,
+            &[(b"ld!", 6)])).unwrap();
+
+        let opened = MsiPackage::open(package.path()).unwrap();
+        assert_eq!(opened.source_root(), package.path().parent());
+        let set = opened.read_cabinet(MediaCabinet::Embedded("inner.cab")).unwrap();
+        assert_eq!(set.read_file(set.file("inner.txt").unwrap()).unwrap(), b"inside");
+        assert!(matches!(opened.read_cabinet(MediaCabinet::External("one.cab")), Err(Error::NotFound(_))));
+
+        let opened = opened.with_source_root(&root);
+        let set = opened.read_cabinet(MediaCabinet::External("one.cab")).unwrap();
+        assert_eq!(set.cabinets().len(), 2);
+        assert_eq!(set.read_file(set.file("b.txt").unwrap()).unwrap(), b"world!");
+        assert_eq!(opened.read_cabinet(MediaCabinet::External("two.cab")).unwrap().files().len(), 0);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
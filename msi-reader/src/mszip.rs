@@ -0,0 +1,269 @@
+// MSZIP decompression: every cabinet data block holds "CK" and a raw DEFLATE stream (RFC 1951) whose
+// back-references may reach into the previous 32 KB of output of the folder.
+use crate::error::{ Error, Result };
+use crate::huffman::Huffman;
+
+const SIGNATURE: &[u8] = b"CK";
+
+#[doc = "How far back DEFLATE references may reach."]
+pub(crate) const WINDOW_SIZE: usize = 32768;
+
+const LENGTH_BASE: [u16; 29] = [3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258];
+const LENGTH_EXTRA: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+const DISTANCE_BASE: [u16; 30] = [1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577];
+const DISTANCE_EXTRA: [u8; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+// order in which the code length code lengths of a dynamic block are stored
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+#[doc = "Decompresses one MSZIP data block and appends it to `output`, which holds the output of the previous blocks of the folder. Fails unless the block decompresses to exactly `uncompressed_size` bytes, and stops as soon as it would write more."]
+pub(crate) fn inflate_block(block: &[u8], output: &mut Vec<u8>, uncompressed_size: usize) -> Result<()>
+{
+    if !block.starts_with(SIGNATURE)
+    {
+        return Err(Error::invalid("MSZIP block does not start with the CK signature"));
+    }
+
+    let start = output.len();
+    let limit = start + uncompressed_size;
+    let mut bits = BitReader::new(&block[SIGNATURE.len()..]);
+    loop
+    {
+        let last = bits.read(1)? == 1;
+        match bits.read(2)?
+        {
+            0 => stored(&mut bits, output, limit)?,
+            1 => compressed(&mut bits, output, limit, &fixed_literals(), &fixed_distances())?,
+            2 => {
+                let (literals, distances) = dynamic_tables(&mut bits)?;
+                compressed(&mut bits, output, limit, &literals, &distances)?;
+            },
+            _ => return Err(Error::invalid("MSZIP block uses the reserved DEFLATE block type"))
+        }
+
+        if last
+        {
+            break;
+        }
+    }
+
+    if output.len() - start != uncompressed_size
+    {
+        return Err(Error::invalid(format!("MSZIP block decompresses to {} bytes instead of {}", output.len() - start, uncompressed_size)));
+    }
+
+    Ok(())
+}
+
+fn stored(bits: &mut BitReader, output: &mut Vec<u8>, limit: usize) -> Result<()>
+{
+    bits.align();
+    let length = bits.read(16)?;
+    if length != !bits.read(16)? & 0xffff
+    {
+        return Err(Error::invalid("stored DEFLATE block has a corrupt length"));
+    }
+    check_limit(output, length as usize, limit)?;
+
+    output.extend_from_slice(bits.read_bytes(length as usize)?);
+    Ok(())
+}
+
+fn compressed(bits: &mut BitReader, output: &mut Vec<u8>, limit: usize, literals: &Huffman, distances: &Huffman) -> Result<()>
+{
+    loop
+    {
+        let symbol = literals.decode(|| bits.read(1))?;
+        match symbol
+        {
+            0..=255 => {
+                check_limit(output, 1, limit)?;
+                output.push(symbol as u8);
+            },
+            256 => return Ok(()),
+            257..=285 => {
+                let index = (symbol - 257) as usize;
+                let length = LENGTH_BASE[index] as usize + bits.read(LENGTH_EXTRA[index])? as usize;
+                let index = distances.decode(|| bits.read(1))? as usize;
+                if index >= DISTANCE_BASE.len()
+                {
+                    return Err(Error::invalid("DEFLATE stream uses an invalid distance code"));
+                }
+
+                let distance = DISTANCE_BASE[index] as usize + bits.read(DISTANCE_EXTRA[index])? as usize;
+                // references may reach back into the previous blocks of the folder
+                if distance > output.len()
+                {
+                    return Err(Error::invalid(format!("DEFLATE stream refers {} bytes back, before the start of the folder", distance)));
+                }
+
+                check_limit(output, length, limit)?;
+                let from = output.len() - distance;
+                for offset in 0..length
+                {
+                    let byte = output[from + offset];
+                    output.push(byte);
+                }
+            },
+            _ => return Err(Error::invalid("DEFLATE stream uses an invalid length code"))
+        }
+    }
+}
+
+// Fails before `count` more bytes would take the output of the block past `limit`, so a
+// small block cannot expand into an arbitrarily large buffer.
+fn check_limit(output: &[u8], count: usize, limit: usize) -> Result<()>
+{
+    if output.len() + count > limit
+    {
+        return Err(Error::invalid("MSZIP block decompresses to more than its uncompressed size"));
+    }
+    Ok(())
+}
+
+fn dynamic_tables(bits: &mut BitReader) -> Result<(Huffman, Huffman)>
+{
+    let literal_count = bits.read(5)? as usize + 257;
+    let distance_count = bits.read(5)? as usize + 1;
+    let code_length_count = bits.read(4)? as usize + 4;
+    if literal_count > 286 || distance_count > 30
+    {
+        return Err(Error::invalid("dynamic DEFLATE block has too many codes"));
+    }
+
+    let mut code_lengths = [0u8; 19];
+    for index in CODE_LENGTH_ORDER.iter().take(code_length_count)
+    {
+        code_lengths[*index] = bits.read(3)? as u8;
+    }
+    let code_lengths = Huffman::new(&code_lengths)?;
+
+    let mut lengths = Vec::with_capacity(literal_count + distance_count);
+    while lengths.len() < literal_count + distance_count
+    {
+        let (length, repeat) = match code_lengths.decode(|| bits.read(1))?
+        {
+            symbol @ 0..=15 => (symbol as u8, 1),
+            16 => match lengths.last()
+            {
+                Some(previous) => (*previous, 3 + bits.read(2)?),
+                None => return Err(Error::invalid("dynamic DEFLATE block repeats a length before the first one"))
+            },
+            17 => (0, 3 + bits.read(3)?),
+            _ => (0, 11 + bits.read(7)?)
+        };
+        if lengths.len() + repeat as usize > literal_count + distance_count
+        {
+            return Err(Error::invalid("dynamic DEFLATE block repeats lengths beyond the number of codes"));
+        }
+        lengths.extend(std::iter::repeat_n(length, repeat as usize));
+    }
+
+    if lengths[256] == 0
+    {
+        return Err(Error::invalid("dynamic DEFLATE block has no end-of-block code"));
+    }
+
+    Ok((Huffman::new(&lengths[..literal_count])?, Huffman::new(&lengths[literal_count..])?))
+}
+
+fn fixed_literals() -> Huffman
+{
+    let mut lengths = [8u8; 288];
+    lengths[144..256].iter_mut().for_each(|length| *length = 9);
+    lengths[256..280].iter_mut().for_each(|length| *length = 7);
+    Huffman::new(&lengths).unwrap_or_else(|_| unreachable!("the fixed literal code is complete"))
+}
+
+fn fixed_distances() -> Huffman {
+    Huffman::new(&[5u8; 30]).unwrap_or_else(|_| unreachable!("the fixed distance code is complete"))
+}
+
+// Reads bits least significant first, as DEFLATE packs them.
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    buffer: u32,
+    count: u8
+}
+
+impl<'a> BitReader<'a> {
+
+    fn new(data: &'a [u8]) -> Self
+    {
+        BitReader {
+            data,
+            position: 0,
+            buffer: 0,
+            count: 0
+        }
+    }
+
+    fn read(&mut self, count: u8) -> Result<u32>
+    {
+        while self.count < count
+        {
+            let byte = *self.data.get(self.position).ok_or_else(|| Error::invalid("DEFLATE stream ends unexpectedly"))?;
+            self.position += 1;
+            self.buffer |= (byte as u32) << self.count;
+            self.count += 8;
+        }
+
+        let value = self.buffer & ((1u64 << count) - 1) as u32;
+        self.buffer >>= count;
+        self.count -= count;
+        Ok(value)
+    }
+
+    fn align(&mut self)
+    {
+        self.buffer = 0;
+        self.count = 0;
+    }
+
+    fn read_bytes(&mut self, count: usize) -> Result<&'a [u8]>
+    {
+        let bytes = self.data.get(self.position..self.position + count).ok_or_else(|| Error::invalid("stored DEFLATE block ends unexpectedly"))?;
+        self.position += count;
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests
+{
+    use super::*;
+
+    // A dynamic Huffman block, then a fixed Huffman block copying from the first, as zlib writes them.
+    pub(crate) const FIRST: &[u8] = b"TCCAGGGTTCTCGGCGTGCCGTTACGAACCCTTCCAGGGTTCTCGGCG";
+    pub(crate) const FIRST_BLOCK: &[u8] = b"CK\x55\xc9\xa1\x11\x00\x30\x10\x84\xc0\xda\x6e\x10\x34\x40\xff\xb5\xe4\x6d\x0c\x62\x09\xa6\x16\xa1\x98\x5c\x1a\x6e\xc0\xf9\xff\x1f";
+    pub(crate) const SECOND: &[u8] = b"TCTCGGCGTGCCGTTACGAACCCTTCCAGGGTACGT";
+    pub(crate) const SECOND_BLOCK: &[u8] = b"CK\x23\xa4\x0e\x28\x10\x02\x00";
+
+    #[test]
+    fn test_inflate_block()
+    {
+        let mut output = Vec::new();
+        inflate_block(FIRST_BLOCK, &mut output, FIRST.len()).unwrap();
+        inflate_block(SECOND_BLOCK, &mut output, SECOND.len()).unwrap();
+        assert_eq!(output, [FIRST, SECOND].concat());
+
+        let mut output = Vec::new();
+        inflate_block(b"CK\x01\x06\x00\xf9\xffstored", &mut output, 6).unwrap();
+        assert_eq!(output, b"stored");
+
+        // the second block refers back into the first, and sizes must match
+        assert!(inflate_block(SECOND_BLOCK, &mut Vec::new(), SECOND.len()).is_err());
+        assert!(inflate_block(FIRST_BLOCK, &mut Vec::new(), FIRST.len() - 1).is_err());
+        assert!(inflate_block(&FIRST_BLOCK[..20], &mut Vec::new(), FIRST.len()).is_err());
+        assert!(inflate_block(b"CK\x01\x06\x00\x00\x00stored", &mut Vec::new(), 6).is_err());
+        assert!(inflate_block(&FIRST_BLOCK[2..], &mut Vec::new(), FIRST.len()).is_err());
+
+        // decoding stops at the declared size instead of after the DEFLATE block
+        let mut output = b"previous".to_vec();
+        assert!(inflate_block(FIRST_BLOCK, &mut output, 10).is_err());
+        assert!(output.len() <= 18);
+        let mut output = Vec::new();
+        assert!(inflate_block(b"CK\x01\x06\x00\xf9\xffstored", &mut output, 5).is_err());
+        assert!(output.is_empty());
+    }
+}
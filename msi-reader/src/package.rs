@@ -0,0 +1,615 @@
+use std::borrow::Cow;
+use std::cell::{ OnceCell, RefCell };
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{ Cursor, Read, Seek, Write };
+use std::path::{ Path, PathBuf };
+use std::sync::Arc;
+
+use memmap2::Mmap;
+
+use crate::authenticode::Signature;
+use crate::cabinet::CabinetSet;
+use crate::compound::{ self, guarded };
+use crate::digest::ContentDigests;
+use crate::error::{ Error, Result };
+use crate::export::{ self, OutputOrder, TextMode };
+use crate::extract::{ self, ExtractedFile, FileReader };
+use crate::feature::FeatureComponents;
+use crate::language::MsiLanguage;
+use crate::media::{ self, MediaCabinet };
+#[cfg(any(unix, windows))]
+use crate::property::Properties;
+use crate::sql::{ self, QueryResult };
+use crate::streamname;
+use crate::stringpool::StringPool;
+use crate::summary::{ Platform, SummaryInfo, SUMMARY_INFO_STREAM };
+use crate::table::{ CellCoercion, Column, Table, TableSchema, Value };
+
+pub(crate) const STRING_POOL_STREAM: &str = "_StringPool";
+pub(crate) const STRING_DATA_STREAM: &str = "_StringData";
+pub(crate) const TABLES_TABLE: &str = "_Tables";
+pub(crate) const COLUMNS_TABLE: &str = "_Columns";
+
+// Anything a compound file can be read from.
+pub(crate) trait Source: Read + Seek {}
+
+impl<T: Read + Seek> Source for T {}
+
+pub(crate) type CompoundFile = cfb::CompoundFile<Box<dyn Source>>;
+type Compound = RefCell<CompoundFile>;
+
+// A package file mapped into memory, shared by the cursor cfb reads and by `read_stream_slice`.
+#[derive(Clone)]
+struct MappedFile(Arc<Mmap>);
+
+impl AsRef<[u8]> for MappedFile {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+#[doc = "A Windows Installer database (.msi, .msm) opened from disk or memory."]
+#[doc = ""]
+#[doc = "Reading is blocking; async services use `asyncpackage::AsyncMsiPackage` with the `tokio` feature."]
+pub struct MsiPackage {
+    compound: Compound,
+    summary: SummaryInfo,
+    strings: StringPool,
+    tables: BTreeMap<String, Vec<Column>>,
+    coercion: CellCoercion,
+    digests: OnceCell<ContentDigests>,
+    source_root: Option<PathBuf>,
+    // the map of a package opened by `open_mmap`, with the layout locating its streams
+    mapped: Option<(MappedFile, compound::Layout)>
+}
+
+impl MsiPackage {
+
+    #[doc = "Opens a package, reading its summary information, string pool and table catalog. External cabinets are looked for in the directory of the package."]
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<MsiPackage>
+    {
+        let file: Box<dyn Source> = Box::new(File::open(path.as_ref())?);
+        let mut package = Self::load(file)?;
+        package.source_root = directory_of(path.as_ref());
+        Ok(package)
+    }
+
+    #[doc = "Opens a package like `open`, but maps the file into memory instead of reading it through the file handle. Pages are loaded by the system as the compound file is read and can be dropped again under memory pressure. `read_stream_slice` borrows streams from the map instead of copying them, and so are the table streams decoded by `table`, as long as their sectors follow each other in the file. On platforms without memory maps this is `open`."]
+    #[doc = ""]
+    #[doc = "# Safety"]
+    #[doc = ""]
+    #[doc = "The file must not be truncated or modified, by this or any other process, while the package is open. Otherwise reads see the changed bytes without synchronization, and reading beyond a truncated end of the file terminates the process with `SIGBUS` on Unix."]
+    pub unsafe fn open_mmap<P: AsRef<Path>>(path: P) -> Result<MsiPackage>
+    {
+        #[cfg(any(unix, windows))]
+        {
+            let map = MappedFile(Arc::new(unsafe { Mmap::map(&File::open(path.as_ref())?)? }));
+            let mut package = Self::load(Box::new(Cursor::new(map.clone())))?;
+            let layout = compound::Layout::read(&mut Cursor::new(map.as_ref()))?;
+            package.mapped = Some((map, layout));
+            package.source_root = directory_of(path.as_ref());
+            Ok(package)
+        }
+        #[cfg(not(any(unix, windows)))]
+        Self::open(path)
+    }
+
+    #[doc = "Parses a package from bytes of unknown origin, such as files handed to a scanner. The sector chains of the compound file are checked before cfb reads them and every table and stream is decoded with bounds checks, so malformed input yields an `Err`. A panic cfb might still raise on a structure those checks miss is caught as a last resort, which does not help when the binary is built with `panic = \"abort\"`."]
+    pub fn parse_untrusted(data: Vec<u8>) -> Result<MsiPackage>
+    {
+        Self::from_reader(Cursor::new(data))
+    }
+
+    #[doc = "Reads a package from any seekable reader, such as a `Cursor` over bytes, a member of an archive or a buffered network stream, without touching the filesystem. The reader is kept for reading tables and streams later on. External cabinets are only found after `with_source_root`."]
+    pub fn from_reader<R: Read + Seek + 'static>(reader: R) -> Result<MsiPackage>
+    {
+        let source: Box<dyn Source> = Box::new(reader);
+        Self::load(source)
+    }
+
+    fn load(mut source: Box<dyn Source>) -> Result<MsiPackage>
+    {
+        compound::check_chains(&mut source)?;
+        let compound = RefCell::new(guarded(|| Ok(cfb::CompoundFile::open(source)?))?);
+        let summary = SummaryInfo::parse(&read_stream(&compound, SUMMARY_INFO_STREAM)?)?;
+        let strings = StringPool::parse(
+            &read_stream(&compound, &streamname::encode(STRING_POOL_STREAM, true))?,
+            &read_stream(&compound, &streamname::encode(STRING_DATA_STREAM, true))?)?;
+
+        let mut package = MsiPackage {
+            compound,
+            summary,
+            strings,
+            tables: BTreeMap::new(),
+            coercion: CellCoercion::Strict,
+            digests: OnceCell::new(),
+            source_root: None,
+            mapped: None
+        };
+
+        let tables = package.decode_table(TABLES_TABLE, vec![Column::from_bits("Name", 0x2d40)?], None)?;
+        for row in tables.rows()
+        {
+            if let Some(name) = row.str("Name")
+            {
+                package.tables.insert(name.to_string(), Vec::new());
+            }
+        }
+
+        let columns = package.decode_table(COLUMNS_TABLE, vec![
+            Column::from_bits("Table", 0x2d40)?,
+            Column::from_bits("Number", 0x2502)?,
+            Column::from_bits("Name", 0x2d40)?,
+            Column::from_bits("Type", 0x0502)?
+        ], None)?;
+
+        let mut catalog: BTreeMap<String, BTreeMap<i32, Column>> = BTreeMap::new();
+        for row in columns.rows()
+        {
+            let (table, number, name, bits) = match (row.str("Table"), row.int("Number"), row.str("Name"), row.int("Type"))
+            {
+                (Some(table), Some(number), Some(name), Some(bits)) => (table, number, name, bits),
+                _ => return Err(Error::invalid(format!("_Columns row {} is incomplete", row.index())))
+            };
+
+            catalog.entry(table.to_string()).or_default().insert(number, Column::from_bits(name, bits)?);
+        }
+
+        for (table, columns) in catalog
+        {
+            if let Some(entry) = package.tables.get_mut(&table)
+            {
+                *entry = columns.into_values().collect();
+            }
+        }
+
+        Ok(package)
+    }
+
+    #[doc = "Sets how tables read from now on treat cells that do not fit the declared type of their column. The system tables read by `open` are always decoded strictly."]
+    pub fn with_coercion(mut self, coercion: CellCoercion) -> Self {
+        self.coercion = coercion;
+        self
+    }
+
+    #[doc = "Sets the directory external cabinets are looked for in, such as the root of an installation image the package was copied out of."]
+    pub fn with_source_root<P: Into<PathBuf>>(mut self, root: P) -> Self {
+        self.source_root = Some(root.into());
+        self
+    }
+
+    #[doc = "Returns the directory external cabinets are looked for in: the one set by `with_source_root`, else the directory of a package opened from disk."]
+    pub fn source_root(&self) -> Option<&Path> {
+        self.source_root.as_deref()
+    }
+
+    #[doc = "Returns the summary information of the package."]
+    pub fn summary(&self) -> &SummaryInfo {
+        &self.summary
+    }
+
+    #[doc = "Returns the platform the package targets, from the Template summary property."]
+    pub fn platform(&self) -> Option<Platform> {
+        self.summary.platform()
+    }
+
+    #[doc = "Returns the languages the package supports, from the Template summary property."]
+    pub fn languages(&self) -> Vec<MsiLanguage> {
+        self.summary.languages()
+    }
+
+    #[doc = "Returns the string pool that table cells refer to."]
+    pub fn strings(&self) -> &StringPool {
+        &self.strings
+    }
+
+    #[doc = "Returns the names of all tables declared in the catalog."]
+    pub fn table_names(&self) -> impl Iterator<Item = &str> {
+        self.tables.keys().map(|name| name.as_str())
+    }
+
+    #[doc = "Returns the schemas of all tables declared in the catalog, ordered by name."]
+    pub fn tables(&self) -> impl Iterator<Item = TableSchema<'_>> {
+        self.tables.iter().map(|(name, columns)| TableSchema::new(name, columns))
+    }
+
+    #[doc = "Returns the schema of the table with the given name, without reading its rows."]
+    pub fn schema(&self, name: &str) -> Option<TableSchema<'_>> {
+        self.tables.get_key_value(name).map(|(name, columns)| TableSchema::new(name, columns))
+    }
+
+    #[doc = "Returns a boolean value indicating whether the package declares a table with the given name."]
+    pub fn has_table(&self, name: &str) -> bool {
+        self.tables.contains_key(name)
+    }
+
+    #[doc = "Reads and decodes the table with the given name."]
+    pub fn table(&self, name: &str) -> Result<Table>
+    {
+        let columns = self.tables.get(name)
+            .ok_or_else(|| Error::NotFound(format!("table '{}'", name)))?;
+        self.decode_table(name, columns.clone(), None)
+    }
+
+    #[doc = "Reads the table with the given name, decoding only the named columns and the primary key columns, in their declared order. The other columns are skipped, which saves time and memory on large tables."]
+    pub fn table_columns(&self, name: &str, selection: &[&str]) -> Result<Table>
+    {
+        let columns = self.tables.get(name)
+            .ok_or_else(|| Error::NotFound(format!("table '{}'", name)))?;
+        if let Some(missing) = selection.iter().find(|selected| !columns.iter().any(|column| column.name() == **selected))
+        {
+            return Err(Error::NotFound(format!("column '{}' of table '{}'", missing, name)));
+        }
+
+        let selected: Vec<bool> = columns.iter().map(|column| column.is_primary_key() || selection.contains(&column.name())).collect();
+        self.decode_table(name, columns.clone(), Some(&selected))
+    }
+
+    #[doc = "Reads the table with the given name, or returns `None` if the package does not declare it."]
+    pub fn optional_table(&self, name: &str) -> Result<Option<Table>> {
+        if self.has_table(name)
+        {
+            self.table(name).map(Some)
+        }
+        else
+        {
+            Ok(None)
+        }
+    }
+
+    #[doc = "Returns the value of a property from the Property table, or `None` if the table or the property does not exist."]
+    pub fn property(&self, name: &str) -> Result<Option<String>>
+    {
+        let table = match self.optional_table("Property")?
+        {
+            Some(table) => table,
+            None => return Ok(None)
+        };
+
+        let value = table.rows()
+            .find(|row| row.str("Property") == Some(name))
+            .and_then(|row| row.str("Value").map(|value| value.to_string()));
+        Ok(value)
+    }
+
+    #[doc = "Returns the sorted names of all streams that are not tables, such as `Binary.Icon`, embedded cabinets and the summary information. Each can be read with `read_stream`."]
+    pub fn stream_names(&self) -> Result<Vec<String>>
+    {
+        let mut names: Vec<String> = self.raw_stream_names()?.iter()
+            .map(|raw_name| streamname::decode(raw_name))
+            .filter(|(_, is_table)| !is_table)
+            .map(|(name, _)| name)
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+
+    #[doc = "Reads the raw contents of a non-table stream, such as a `Binary` or cabinet stream."]
+    pub fn read_stream(&self, name: &str) -> Result<Vec<u8>> {
+        read_stream(&self.compound, &streamname::encode(name, false))
+    }
+
+    #[doc = "Reads a non-table stream like `read_stream`, but borrows it from the map of a package opened with `open_mmap` if its sectors follow each other in the file. Fragmented streams, and every stream of a package opened otherwise, are copied."]
+    pub fn read_stream_slice(&self, name: &str) -> Result<Cow<'_, [u8]>> {
+        self.raw_stream_slice(&streamname::encode(name, false))
+    }
+
+    #[doc = "Reads a cabinet of the Media table, from a stream or from the source root, together with the cabinets its last folder continues in; see `media::read_cabinets`."]
+    pub fn read_cabinet(&self, cabinet: MediaCabinet<'_>) -> Result<CabinetSet> {
+        media::read_cabinets(self, cabinet)
+    }
+
+    #[doc = "Opens the file with the given File key for reading, decompressing it as it is read; see `extract::open_file`."]
+    pub fn open_file(&self, file: &str) -> Result<FileReader> {
+        extract::open_file(self, file)
+    }
+
+    #[doc = "Writes the contents of the file with the given File key to `output`, from its cabinet or the source image, and returns its size; see `extract::extract_file`."]
+    pub fn extract_file<W: Write>(&self, file: &str, output: &mut W) -> Result<u64> {
+        extract::extract_file(self, file, output)
+    }
+
+    #[doc = "Writes every file of the package below `destination` in the source layout with long names; see `extract::extract_all`."]
+    pub fn extract_all<P: AsRef<Path>>(&self, destination: P) -> Result<Vec<ExtractedFile>> {
+        extract::extract_all(self, destination)
+    }
+
+    #[doc = "Reads the Property table as a map of property names to values."]
+    pub fn properties(&self) -> Result<Properties> {
+        Properties::read(self)
+    }
+
+    #[doc = "Returns the SHA-256 digests of every stream and cabinet member. They are computed on first use and shared by all later callers."]
+    pub fn digests(&self) -> Result<&ContentDigests>
+    {
+        if let Some(digests) = self.digests.get()
+        {
+            return Ok(digests);
+        }
+
+        let digests = ContentDigests::compute(self)?;
+        Ok(self.digests.get_or_init(|| digests))
+    }
+
+    #[doc = "Returns the Authenticode signature of the package, or `None` if it is not signed."]
+    pub fn signature(&self) -> Result<Option<Signature>> {
+        Signature::read(self)
+    }
+
+    #[doc = "Returns which components each feature installs, with both sides resolved to their Feature and Component rows."]
+    pub fn feature_components(&self) -> Result<FeatureComponents> {
+        FeatureComponents::read(self)
+    }
+
+    #[doc = "Renders every table with its schema and rows, and the summary information, as a single JSON document following `schema::DATABASE`. Rows are in storage order and undecodable bytes read as U+FFFD; `export::json_database` offers the other options."]
+    pub fn to_json(&self) -> Result<String> {
+        export::json_database(self, OutputOrder::default(), TextMode::default())
+    }
+
+    #[doc = "Writes the document of `to_json` to `output` one table at a time, so that only a single table is held in memory."]
+    pub fn write_json<W: Write>(&self, output: &mut W) -> Result<()> {
+        export::write_json_database(self, OutputOrder::default(), TextMode::default(), output)
+    }
+
+    #[doc = "Runs a query in the SQL dialect of Windows Installer, e.g. `SELECT File, FileName FROM File WHERE Component_ = ?`; see `sql::Statement`."]
+    pub fn query(&self, sql: &str, parameters: &[Value]) -> Result<QueryResult> {
+        sql::query(self, sql, parameters)
+    }
+
+    pub(crate) fn raw_stream_names(&self) -> Result<Vec<String>>
+    {
+        guarded(|| Ok(self.compound.borrow().read_storage("/")?
+            .filter(|entry| entry.is_stream())
+            .map(|entry| entry.name().to_string())
+            .collect()))
+    }
+
+    pub(crate) fn read_raw_stream(&self, raw_name: &str) -> Result<Vec<u8>>
+    {
+        read_stream(&self.compound, raw_name)
+    }
+
+    // Borrows a stream from the map of the package where it can, and reads it through cfb otherwise.
+    fn raw_stream_slice(&self, raw_name: &str) -> Result<Cow<'_, [u8]>>
+    {
+        if let Some((map, layout)) = &self.mapped
+        {
+            if let Some(data) = layout.contiguous_stream(raw_name)?.and_then(|range| map.as_ref().get(range))
+            {
+                return Ok(Cow::Borrowed(data));
+            }
+        }
+
+        read_stream(&self.compound, raw_name).map(Cow::Owned)
+    }
+
+    #[doc = "Gives direct access to the underlying compound file, for operations such as signature hashing that walk the raw storage tree."]
+    pub(crate) fn with_compound<T, F: FnOnce(&mut CompoundFile) -> Result<T>>(&self, operation: F) -> Result<T>
+    {
+        guarded(|| operation(&mut self.compound.borrow_mut()))
+    }
+
+    fn decode_table(&self, name: &str, columns: Vec<Column>, selected: Option<&[bool]>) -> Result<Table>
+    {
+        let stream = streamname::encode(name, true);
+        let data = if guarded(|| Ok(self.compound.borrow().is_stream(&stream)))?
+        {
+            self.raw_stream_slice(&stream)?
+        }
+        else
+        {
+            Cow::Borrowed(&[][..])
+        };
+
+        match selected
+        {
+            Some(selected) => Table::decode_columns(name, columns, selected, &data, &self.strings, self.coercion),
+            None => Table::decode(name, columns, &data, &self.strings, self.coercion)
+        }
+    }
+}
+
+fn read_stream(compound: &Compound, name: &str) -> Result<Vec<u8>>
+{
+    guarded(|| {
+        let mut compound = compound.borrow_mut();
+        if !compound.is_stream(name)
+        {
+            return Err(Error::NotFound(format!("stream '{}'", streamname::decode(name).0)));
+        }
+
+        let mut data = Vec::new();
+        compound.open_stream(name)?.read_to_end(&mut data)?;
+        Ok(data)
+    })
+}
+
+// Returns the directory of a package file, where its external cabinets are looked for.
+fn directory_of(path: &Path) -> Option<PathBuf>
+{
+    path.parent().map(|parent| if parent.as_os_str().is_empty() { Path::new(".") } else { parent }.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::table::{ ColumnType, TypedValue, Value };
+    use crate::testutil::TestPackage;
+
+    #[test]
+    fn test_read_tables()
+    {
+        let package = TestPackage::new("package", |builder| {
+            builder.table("Property", vec![
+                msi::Column::build("Property").primary_key().id_string(72),
+                msi::Column::build("Value").localizable().text_string(0)
+            ], vec![
+                vec![msi::Value::from("ProductName"), msi::Value::from("Alpha")],
+                vec![msi::Value::from("ALLUSERS"), msi::Value::from("1")]
+            ]);
+            builder.table("Media", vec![
+                msi::Column::build("DiskId").primary_key().range(1, 32767).int16(),
+                msi::Column::build("LastSequence").int32(),
+                msi::Column::build("Cabinet").nullable().string(255)
+            ], vec![
+                vec![msi::Value::Int(1), msi::Value::Int(70000), msi::Value::from("#cab1.cab")],
+                vec![msi::Value::Int(2), msi::Value::Int(-5), msi::Value::Null]
+            ]);
+            builder.table("Binary", vec![
+                msi::Column::build("Name").primary_key().id_string(72),
+                msi::Column::build("Data").binary()
+            ], vec![
+                vec![msi::Value::from("Icon"), msi::Value::from("Binary.Icon")]
+            ]);
+            builder.stream("Binary.Icon", b"icon");
+        });
+
+        let package = MsiPackage::open(package.path()).unwrap();
+        assert!(package.has_table("Property"));
+        assert_eq!(package.stream_names().unwrap(), ["\u{5}SummaryInformation", "Binary.Icon"]);
+        assert_eq!(package.read_stream("Binary.Icon").unwrap(), b"icon");
+        assert!(package.table("Missing").is_err());
+
+        let names: Vec<&str> = package.tables().map(|schema| schema.name()).collect();
+        assert_eq!(names, ["Binary", "Media", "Property", "_Validation"]);
+        let schema = package.schema("Media").unwrap();
+        let keys: Vec<&str> = schema.primary_keys().map(|column| column.name()).collect();
+        assert_eq!(keys, ["DiskId"]);
+        assert!(schema.column("Cabinet").unwrap().is_nullable());
+        assert_eq!(schema.column("Cabinet").unwrap().column_type(), ColumnType::Str(255));
+        assert!(package.schema("Missing").is_none());
+
+        let media = package.table("Media").unwrap();
+        assert_eq!(media.columns().len(), 3);
+        assert_eq!(media.columns()[0].column_type(), ColumnType::Int16);
+        assert!(media.columns()[0].is_primary_key());
+        assert_eq!(media.columns()[1].column_type(), ColumnType::Int32);
+        assert!(media.columns()[2].is_nullable());
+
+        let rows: Vec<_> = media.rows().collect();
+        assert_eq!(rows.len(), 2);
+        let second = rows.iter().find(|row| row.int("DiskId") == Some(2)).unwrap();
+        assert_eq!(second.int("LastSequence"), Some(-5));
+        assert_eq!(second.get("Cabinet"), Some(&Value::Null));
+        let first = rows.iter().find(|row| row.int("DiskId") == Some(1)).unwrap();
+        assert_eq!(first.int("LastSequence"), Some(70000));
+        assert_eq!(first.str("Cabinet"), Some("#cab1.cab"));
+        assert_eq!(first.key(), "1");
+        let typed: Vec<TypedValue> = second.typed_values().collect();
+        assert_eq!(typed, [TypedValue::Int16(2), TypedValue::Int32(-5), TypedValue::Null]);
+        assert_eq!(first.typed("Cabinet"), Some(TypedValue::Str("#cab1.cab")));
+        let binary = package.table("Binary").unwrap();
+        assert_eq!(binary.row(0).typed("Data"), Some(TypedValue::Stream("Binary.Icon")));
+
+        let properties = package.table("Property").unwrap();
+        assert!(properties.rows().any(|row| row.str("Property") == Some("ProductName") && row.str("Value") == Some("Alpha")));
+
+        let projected = package.table_columns("Media", &["Cabinet"]).unwrap();
+        let names: Vec<&str> = projected.columns().iter().map(|column| column.name()).collect();
+        assert_eq!(names, ["DiskId", "Cabinet"]);
+        let cabinets: Vec<(Option<i32>, Option<&str>)> = projected.rows().map(|row| (row.int("DiskId"), row.str("Cabinet"))).collect();
+        assert!(cabinets.contains(&(Some(1), Some("#cab1.cab"))) && cabinets.contains(&(Some(2), None)));
+        assert!(package.table_columns("Media", &["Missing"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_untrusted()
+    {
+        let package = TestPackage::new("untrusted", |builder| {
+            builder.table("Property", vec![
+                msi::Column::build("Property").primary_key().id_string(72),
+                msi::Column::build("Value").localizable().text_string(0)
+            ], vec![
+                vec![msi::Value::from("ProductName"), msi::Value::from("Alpha")]
+            ]);
+        });
+
+        let data = std::fs::read(package.path()).unwrap();
+        assert_eq!(MsiPackage::parse_untrusted(data.clone()).unwrap().table("Property").unwrap().len(), 1);
+        assert!(MsiPackage::parse_untrusted(Vec::new()).is_err());
+
+        // truncated and corrupted copies must fail (or succeed) without panicking
+        for length in (0..data.len()).step_by(509)
+        {
+            let _ = MsiPackage::parse_untrusted(data[..length].to_vec());
+        }
+        for offset in (0..data.len()).step_by(97)
+        {
+            let mut corrupt = data.clone();
+            corrupt[offset] ^= 0xff;
+            if let Ok(package) = MsiPackage::parse_untrusted(corrupt)
+            {
+                let names: Vec<String> = package.table_names().map(|name| name.to_string()).collect();
+                for name in names
+                {
+                    let _ = package.table(&name);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_open_mmap()
+    {
+        let package = TestPackage::new("mmap", |builder| {
+            builder.table("Property", vec![
+                msi::Column::build("Property").primary_key().id_string(72),
+                msi::Column::build("Value").localizable().text_string(0)
+            ], vec![
+                vec![msi::Value::from("ProductName"), msi::Value::from("Alpha")]
+            ]);
+            builder.stream("Binary.data", b"mapped");
+            builder.stream("Binary.fragmented", &[1; 5000]);
+            builder.stream("Binary.large", &[3; 9000]);
+        });
+        // appending to the first large stream puts its new sectors after those of the second
+        {
+            let mut compound = cfb::open_rw(package.path()).unwrap();
+            let mut stream = compound.open_stream(streamname::encode("Binary.fragmented", false)).unwrap();
+            stream.seek(std::io::SeekFrom::End(0)).unwrap();
+            stream.write_all(&[2; 5000]).unwrap();
+        }
+
+        // the package file is not changed while it is open
+        let mapped = unsafe { MsiPackage::open_mmap(package.path()) }.unwrap();
+        assert_eq!(mapped.property("ProductName").unwrap().as_deref(), Some("Alpha"));
+        assert_eq!(mapped.read_stream("Binary.data").unwrap(), b"mapped");
+        assert_eq!(mapped.source_root(), package.path().parent());
+
+        // streams in the mini stream and in regular sectors are borrowed from the map, unless fragmented
+        for name in ["Binary.data", "Binary.large"]
+        {
+            let slice = mapped.read_stream_slice(name).unwrap();
+            assert!(matches!(slice, Cow::Borrowed(_)), "{}", name);
+            assert_eq!(slice.as_ref(), mapped.read_stream(name).unwrap().as_slice());
+        }
+        assert!(matches!(mapped.read_stream_slice("Binary.fragmented").unwrap(), Cow::Owned(_)));
+        assert_eq!(mapped.read_stream_slice("Binary.fragmented").unwrap().as_ref(), [[1u8; 5000], [2u8; 5000]].concat().as_slice());
+        assert!(matches!(mapped.read_stream_slice("Binary.missing"), Err(Error::NotFound(_))));
+        assert!(matches!(MsiPackage::open(package.path()).unwrap().read_stream_slice("Binary.data").unwrap(), Cow::Owned(_)));
+
+        let empty = std::env::temp_dir().join(format!("msi-reader-mmap-empty-{}.msi", std::process::id()));
+        std::fs::write(&empty, b"").unwrap();
+        assert!(unsafe { MsiPackage::open_mmap(&empty) }.is_err());
+        std::fs::remove_file(&empty).unwrap();
+    }
+
+    #[test]
+    fn test_from_reader()
+    {
+        let package = TestPackage::new("reader", |builder| {
+            builder.stream("Binary.data", b"read");
+        });
+
+        let data = std::fs::read(package.path()).unwrap();
+        let read = MsiPackage::from_reader(Cursor::new(data.clone())).unwrap();
+        assert_eq!(read.read_stream("Binary.data").unwrap(), b"read");
+        assert_eq!(read.source_root(), None);
+
+        let read = MsiPackage::from_reader(std::io::BufReader::new(File::open(package.path()).unwrap())).unwrap();
+        assert!(read.stream_names().unwrap().contains(&"Binary.data".to_string()));
+        assert!(MsiPackage::from_reader(Cursor::new(data[..512].to_vec())).is_err());
+    }
+}
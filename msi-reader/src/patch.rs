@@ -0,0 +1,521 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::{ Path, PathBuf };
+
+use crate::cabinet::Cabinet;
+use crate::compound;
+use crate::error::{ Error, Result };
+use crate::package::MsiPackage;
+use crate::streamname;
+use crate::summary::{ SummaryInfo, SUMMARY_INFO_STREAM };
+use crate::table::RowOrigin;
+use crate::transform::MsiTransform;
+
+// msidbPatchSequenceSupersedeEarlier
+const SEQUENCE_SUPERSEDE_EARLIER: i32 = 1;
+
+#[doc = "A Windows Installer patch (.msp) opened from disk."]
+pub struct MsiPatch {
+    path: PathBuf,
+    compound: cfb::CompoundFile<File>,
+    summary: SummaryInfo
+}
+
+impl MsiPatch {
+
+    #[doc = "Opens a patch file and reads its summary information."]
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<MsiPatch>
+    {
+        let mut compound = compound::open(&path)?;
+        let mut data = Vec::new();
+        compound.open_stream(SUMMARY_INFO_STREAM)?.read_to_end(&mut data)?;
+
+        Ok(MsiPatch {
+            path: path.as_ref().to_path_buf(),
+            compound,
+            summary: SummaryInfo::parse(&data)?
+        })
+    }
+
+    #[doc = "Returns the summary information of the patch."]
+    pub fn summary(&self) -> &SummaryInfo {
+        &self.summary
+    }
+
+    #[doc = "Returns the products and transforms this patch applies to, as declared in its summary information."]
+    pub fn targets(&self) -> Result<PatchTargets> {
+        PatchTargets::from_summary(&self.summary)
+    }
+
+    #[doc = "Returns the names of the transform substorages embedded in the patch."]
+    pub fn storages(&self) -> Result<Vec<String>> {
+        Ok(self.compound.read_storage("/")?
+            .filter(|entry| entry.is_storage())
+            .map(|entry| entry.name().to_string())
+            .collect())
+    }
+
+    #[doc = "Loads every cabinet stream embedded in the patch (the PATCH cabinets referenced by its transforms' Media tables)."]
+    pub fn cabinets(&mut self) -> Result<Vec<PatchCabinet>>
+    {
+        let names: Vec<String> = self.compound.read_storage("/")?
+            .filter(|entry| entry.is_stream())
+            .map(|entry| entry.name().to_string())
+            .collect();
+
+        let mut cabinets = Vec::new();
+        for name in names
+        {
+            let mut data = Vec::new();
+            self.compound.open_stream(&name)?.read_to_end(&mut data)?;
+            if Cabinet::is_cabinet(&data)
+            {
+                cabinets.push(PatchCabinet {
+                    name: streamname::decode(&name).0,
+                    cabinet: Cabinet::parse(data)?
+                });
+            }
+        }
+
+        Ok(cabinets)
+    }
+
+    #[doc = "Reads the MsiPatchSequence table, which orders this patch within its patch families."]
+    pub fn sequences(&self) -> Result<Vec<PatchSequence>>
+    {
+        let table = match MsiPackage::open(&self.path)?.optional_table("MsiPatchSequence")?
+        {
+            Some(table) => table,
+            None => return Ok(Vec::new())
+        };
+
+        Ok(table.rows().map(|row| PatchSequence {
+            family: row.str("PatchFamily").unwrap_or_default().to_string(),
+            product_code: row.str("ProductCode").map(|code| code.to_string()),
+            sequence: row.str("Sequence").unwrap_or_default().to_string(),
+            attributes: row.int("Attributes").unwrap_or(0),
+            origin: row.origin()
+        }).collect())
+    }
+
+    #[doc = "Reads the MsiPatchMetadata table as property and value pairs, such as `DisplayName` or `Classification`."]
+    pub fn metadata(&self) -> Result<Vec<(String, String)>>
+    {
+        let table = match MsiPackage::open(&self.path)?.optional_table("MsiPatchMetadata")?
+        {
+            Some(table) => table,
+            None => return Ok(Vec::new())
+        };
+
+        Ok(table.rows()
+            .filter_map(|row| Some((row.str("Property")?.to_string(), row.str("Value").unwrap_or_default().to_string())))
+            .collect())
+    }
+
+    #[doc = "Reads the transform in the given substorage, e.g. `RTM.1` or `#RTM.1`, against the package it will be applied to."]
+    pub fn transform(&mut self, storage: &str, base: &MsiPackage) -> Result<MsiTransform> {
+        MsiTransform::read(&mut self.compound, &Path::new("/").join(storage), base)
+    }
+
+    #[doc = "Writes a copy of the target package with the patch's transforms for its product applied, and returns their names. This is an offline view of the patched tables: file payloads, including binary deltas, are not applied."]
+    pub fn apply<P: AsRef<Path>, Q: AsRef<Path>>(&mut self, target: P, output: Q) -> Result<Vec<String>>
+    {
+        let product_code = MsiPackage::open(&target)?.property("ProductCode")?
+            .ok_or_else(|| Error::NotFound("ProductCode property of the target".to_string()))?;
+        let targets = self.targets()?;
+        if !targets.targets_product(&product_code)
+        {
+            return Err(Error::invalid(format!("the patch does not target product {}", product_code)));
+        }
+
+        // the Revision Number of a transform starts with the ProductCode of the package it applies to
+        let mut applicable = Vec::new();
+        for name in targets.transforms()
+        {
+            let mut data = Vec::new();
+            self.compound.open_stream(Path::new("/").join(name).join(SUMMARY_INFO_STREAM))?.read_to_end(&mut data)?;
+            let summary = SummaryInfo::parse(&data)?;
+            if summary.revision().and_then(|revision| revision.get(..38)).is_some_and(|code| code.eq_ignore_ascii_case(&product_code))
+            {
+                applicable.push(name.clone());
+            }
+        }
+        if applicable.is_empty()
+        {
+            return Err(Error::NotFound(format!("transform for product {}", product_code)));
+        }
+
+        std::fs::copy(target, &output)?;
+        for name in &applicable
+        {
+            // later transforms may depend on tables added by earlier ones
+            let transform = self.transform(name, &MsiPackage::open(&output)?)?;
+            transform.apply_in_place(&output)?;
+        }
+
+        Ok(applicable)
+    }
+}
+
+#[doc = "A row of the MsiPatchSequence table."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PatchSequence {
+    family: String,
+    product_code: Option<String>,
+    sequence: String,
+    attributes: i32,
+    origin: RowOrigin
+}
+
+impl PatchSequence {
+
+    #[doc = "Returns the name of the patch family."]
+    pub fn family(&self) -> &str {
+        &self.family
+    }
+
+    #[doc = "Returns the ProductCode the sequence applies to, or `None` for all targets."]
+    pub fn product_code(&self) -> Option<&str> {
+        self.product_code.as_deref()
+    }
+
+    #[doc = "Returns the version-like sequence of the patch within its family."]
+    pub fn sequence(&self) -> &str {
+        &self.sequence
+    }
+
+    #[doc = "Returns a boolean value indicating whether the patch supersedes earlier patches of its family."]
+    pub fn supersedes_earlier(&self) -> bool {
+        self.attributes & SEQUENCE_SUPERSEDE_EARLIER != 0
+    }
+
+    #[doc = "Returns the MsiPatchSequence row of the patch's metadata database."]
+    pub fn origin(&self) -> &RowOrigin {
+        &self.origin
+    }
+}
+
+#[doc = "Whether a patch payload is a complete file or a binary delta against the installed file."]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PayloadKind {
+    #[doc = "A complete new or replacement file that can be inspected as-is."]
+    Whole,
+    #[doc = "A binary delta (PA19 or PA30/PA31) which only makes sense applied to the installed file."]
+    Delta,
+    #[doc = "The payload could not be read, for example because its folder uses an unsupported compression."]
+    Unknown
+}
+
+#[doc = "A file member of a patch cabinet."]
+#[derive(Clone, Debug)]
+pub struct PatchFile {
+    name: String,
+    size: u32,
+    kind: PayloadKind
+}
+
+impl PatchFile {
+
+    #[doc = "Returns the File table key of the member."]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[doc = "Returns the size of the stored payload."]
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    #[doc = "Returns whether the payload is a whole file or a delta."]
+    pub fn kind(&self) -> PayloadKind {
+        self.kind
+    }
+}
+
+#[doc = "A cabinet stream embedded in a patch."]
+pub struct PatchCabinet {
+    name: String,
+    cabinet: Cabinet
+}
+
+impl PatchCabinet {
+
+    #[doc = "Returns the decoded name of the stream holding the cabinet."]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[doc = "Returns the parsed cabinet."]
+    pub fn cabinet(&self) -> &Cabinet {
+        &self.cabinet
+    }
+
+    #[doc = "Lists the members of the cabinet, flagging which of them are binary deltas."]
+    pub fn files(&self) -> Vec<PatchFile>
+    {
+        let folders: Vec<Option<Vec<u8>>> = (0..self.cabinet.folders().len())
+            .map(|index| self.cabinet.read_folder(index).ok())
+            .collect();
+
+        self.cabinet.files().iter().map(|file| {
+            let start = file.folder_offset() as usize;
+            let payload = folders.get(file.folder() as usize)
+                .and_then(|folder| folder.as_ref())
+                .and_then(|folder| folder.get(start..start.saturating_add(file.size() as usize)));
+
+            PatchFile {
+                name: file.name().to_string(),
+                size: file.size(),
+                kind: payload.map(classify_payload).unwrap_or(PayloadKind::Unknown)
+            }
+        }).collect()
+    }
+
+    #[doc = "Returns the payload of the member with the given name, whether it is a whole file or a delta."]
+    pub fn read_file(&self, name: &str) -> Result<Vec<u8>>
+    {
+        let file = self.cabinet.file(name)
+            .ok_or_else(|| Error::invalid(format!("cabinet '{}' has no member '{}'", self.name, name)))?;
+        self.cabinet.read_file(file)
+    }
+}
+
+fn classify_payload(data: &[u8]) -> PayloadKind
+{
+    // mspatcha deltas start with PA19; msdelta deltas with PA30/PA31, optionally behind a 4-byte CRC.
+    let is_delta = |header: Option<&[u8]>| matches!(header, Some(b"PA19") | Some(b"PA30") | Some(b"PA31"));
+    if is_delta(data.get(0..4)) || is_delta(data.get(4..8))
+    {
+        PayloadKind::Delta
+    }
+    else
+    {
+        PayloadKind::Whole
+    }
+}
+
+#[doc = "Products, patch codes and transforms declared by a patch's summary information."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PatchTargets {
+    patch_code: String,
+    obsoleted_patches: Vec<String>,
+    target_products: Vec<String>,
+    transforms: Vec<String>
+}
+
+impl PatchTargets {
+
+    #[doc = "Interprets the Template (target ProductCodes), Revision Number (patch code followed by obsoleted patch codes) and Last Saved By (transform list) properties of a patch."]
+    pub fn from_summary(summary: &SummaryInfo) -> Result<PatchTargets>
+    {
+        let revision = summary.revision()
+            .ok_or_else(|| Error::invalid("patch summary information has no Revision Number"))?;
+        let mut codes = split_guids(revision)?.into_iter();
+        let patch_code = codes.next()
+            .ok_or_else(|| Error::invalid("patch Revision Number does not contain a patch code"))?;
+
+        let target_products = split_list(summary.template().unwrap_or(""));
+        for product in &target_products
+        {
+            if !is_guid(product)
+            {
+                return Err(Error::invalid(format!("patch target '{}' is not a ProductCode", product)));
+            }
+        }
+
+        Ok(PatchTargets {
+            patch_code,
+            obsoleted_patches: codes.collect(),
+            target_products,
+            transforms: split_list(summary.last_saved_by().unwrap_or(""))
+        })
+    }
+
+    #[doc = "Returns the GUID identifying this patch."]
+    pub fn patch_code(&self) -> &str {
+        &self.patch_code
+    }
+
+    #[doc = "Returns the patch codes this patch declares obsolete."]
+    pub fn obsoleted_patches(&self) -> &[String] {
+        &self.obsoleted_patches
+    }
+
+    #[doc = "Returns the ProductCodes of the products this patch can be applied to."]
+    pub fn target_products(&self) -> &[String] {
+        &self.target_products
+    }
+
+    #[doc = "Returns the transform substorage names in the order they are applied. Names starting with '#' are patch transforms."]
+    pub fn transforms(&self) -> &[String] {
+        &self.transforms
+    }
+
+    #[doc = "Returns a boolean value indicating whether the given ProductCode is targeted by this patch (compared case-insensitively)."]
+    pub fn targets_product(&self, product_code: &str) -> bool {
+        self.target_products.iter().any(|product| product.eq_ignore_ascii_case(product_code))
+    }
+}
+
+fn split_list(value: &str) -> Vec<String>
+{
+    value.split(';')
+        .map(|item| item.trim())
+        .filter(|item| !item.is_empty())
+        .map(|item| item.to_string())
+        .collect()
+}
+
+fn split_guids(value: &str) -> Result<Vec<String>>
+{
+    let mut guids = Vec::new();
+    let mut rest = value.trim();
+    while !rest.is_empty()
+    {
+        let end = rest.find('}').map(|index| index + 1).unwrap_or(rest.len());
+        let guid = &rest[..end];
+        if !is_guid(guid)
+        {
+            return Err(Error::invalid(format!("'{}' is not a valid GUID", guid)));
+        }
+
+        guids.push(guid.to_string());
+        rest = rest[end..].trim_start_matches(|c: char| c == ';' || c.is_whitespace());
+    }
+
+    Ok(guids)
+}
+
+fn is_guid(value: &str) -> bool
+{
+    let bytes = value.as_bytes();
+    bytes.len() == 38 && bytes[0] == b'{' && bytes[37] == b'}' && bytes[1..37].iter().enumerate().all(|(index, b)| {
+        match index
+        {
+            8 | 13 | 18 | 23 => *b == b'-',
+            _ => b.is_ascii_hexdigit()
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::summary::{ PID_LASTAUTHOR, PID_REVNUMBER, PID_TEMPLATE };
+    use crate::summary::tests::build_summary;
+    use crate::cabinet::tests::build_cab;
+    use crate::testutil::TestPackage;
+    use std::io::Write;
+
+    #[test]
+    fn test_targets_from_summary()
+    {
+        let data = build_summary(&[
+            (PID_TEMPLATE, "{11111111-2222-3333-4444-555555555555};{AAAAAAAA-BBBB-CCCC-DDDD-EEEEEEEEEEEE}"),
+            (PID_REVNUMBER, "{01234567-89AB-CDEF-0123-456789ABCDEF}{FEDCBA98-7654-3210-FEDC-BA9876543210}"),
+            (PID_LASTAUTHOR, "RTM.1;#RTM.1")
+        ]);
+        let targets = PatchTargets::from_summary(&SummaryInfo::parse(&data).unwrap()).unwrap();
+
+        assert_eq!(targets.patch_code(), "{01234567-89AB-CDEF-0123-456789ABCDEF}");
+        assert_eq!(targets.obsoleted_patches(), ["{FEDCBA98-7654-3210-FEDC-BA9876543210}"]);
+        assert_eq!(targets.target_products().len(), 2);
+        assert!(targets.targets_product("{aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee}"));
+        assert!(!targets.targets_product("{00000000-0000-0000-0000-000000000000}"));
+        assert_eq!(targets.transforms(), ["RTM.1", "#RTM.1"]);
+
+        let data = build_summary(&[(PID_TEMPLATE, "x64;1033"), (PID_REVNUMBER, "{01234567-89AB-CDEF-0123-456789ABCDEF}")]);
+        assert!(PatchTargets::from_summary(&SummaryInfo::parse(&data).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_patch_cabinet_files()
+    {
+        let path = std::env::temp_dir().join(format!("msi-reader-patch-{}.msp", std::process::id()));
+        {
+            let mut compound = cfb::create(&path).unwrap();
+            compound.create_stream(SUMMARY_INFO_STREAM).unwrap()
+                .write_all(&build_summary(&[(PID_REVNUMBER, "{01234567-89AB-CDEF-0123-456789ABCDEF}")])).unwrap();
+            compound.create_stream("\u{3b19}\u{4820}").unwrap()
+                .write_all(&build_cab(&[("app.exe", b"MZ\x90\x00"), ("lib.dll", b"PA19\x01\x02")])).unwrap();
+            compound.flush().unwrap();
+        }
+
+        let mut patch = MsiPatch::open(&path).unwrap();
+        let cabinets = patch.cabinets().unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(cabinets.len(), 1);
+        assert_eq!(cabinets[0].name(), "PCW");
+        let files = cabinets[0].files();
+        assert_eq!(files[0].name(), "app.exe");
+        assert_eq!(files[0].kind(), PayloadKind::Whole);
+        assert_eq!(files[1].kind(), PayloadKind::Delta);
+        assert_eq!(cabinets[0].read_file("app.exe").unwrap(), b"MZ\x90\x00");
+    }
+
+    #[test]
+    fn test_apply()
+    {
+        const PRODUCT_CODE: &str = "{11111111-2222-3333-4444-555555555555}";
+        let build = |tag: &str, version: &str| TestPackage::new(tag, |builder| {
+            builder.table("Property", vec![
+                msi::Column::build("Property").primary_key().id_string(72),
+                msi::Column::build("Value").localizable().text_string(0)
+            ], vec![
+                vec![msi::Value::from("ProductCode"), msi::Value::from(PRODUCT_CODE)],
+                vec![msi::Value::from("ProductVersion"), msi::Value::from(version)]
+            ]);
+        });
+        let target = build("patch-target", "1.0");
+        let patched = build("patch-patched", "1.1");
+
+        let transform_path = std::env::temp_dir().join(format!("msi-reader-patch-{}.mst", std::process::id()));
+        MsiTransform::generate(&MsiPackage::open(target.path()).unwrap(), &MsiPackage::open(patched.path()).unwrap()).unwrap()
+            .write(&transform_path).unwrap();
+
+        let patch = TestPackage::with_type("patch-msp", msi::PackageType::Patch, |builder| {
+            builder.table("MsiPatchSequence", vec![
+                msi::Column::build("PatchFamily").primary_key().id_string(72),
+                msi::Column::build("ProductCode").primary_key().nullable().string(38),
+                msi::Column::build("Sequence").string(72),
+                msi::Column::build("Attributes").nullable().int16()
+            ], vec![vec![msi::Value::from("Hotfix"), msi::Value::Null, msi::Value::from("1.1.0"), msi::Value::Int(1)]]);
+        });
+        {
+            let mut compound = cfb::open_rw(patch.path()).unwrap();
+            compound.create_stream(SUMMARY_INFO_STREAM).unwrap().write_all(&build_summary(&[
+                (PID_TEMPLATE, PRODUCT_CODE),
+                (PID_REVNUMBER, "{01234567-89AB-CDEF-0123-456789ABCDEF}"),
+                (PID_LASTAUTHOR, "RTM.1")
+            ])).unwrap();
+            compound.create_storage("/RTM.1").unwrap();
+
+            let mut transform = cfb::open(&transform_path).unwrap();
+            let names: Vec<String> = transform.read_storage("/").unwrap().map(|entry| entry.name().to_string()).collect();
+            for name in names
+            {
+                let mut data = Vec::new();
+                transform.open_stream(Path::new("/").join(&name)).unwrap().read_to_end(&mut data).unwrap();
+                compound.create_stream(Path::new("/RTM.1").join(&name)).unwrap().write_all(&data).unwrap();
+            }
+            compound.flush().unwrap();
+        }
+        std::fs::remove_file(&transform_path).unwrap();
+
+        let mut patch_file = MsiPatch::open(patch.path()).unwrap();
+        let sequences = patch_file.sequences().unwrap();
+        assert_eq!(sequences[0].family(), "Hotfix");
+        assert_eq!(sequences[0].sequence(), "1.1.0");
+        assert!(sequences[0].supersedes_earlier());
+
+        let output = target.path().with_extension("patched.msi");
+        assert_eq!(patch_file.apply(target.path(), &output).unwrap(), ["RTM.1"]);
+        let version = MsiPackage::open(&output).unwrap().property("ProductVersion").unwrap();
+        std::fs::remove_file(&output).unwrap();
+        assert_eq!(version.as_deref(), Some("1.1"));
+
+        assert!(patch_file.apply(patched.path().with_extension("missing.msi"), &output).is_err());
+    }
+}
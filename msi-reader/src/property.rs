@@ -0,0 +1,132 @@
+use std::collections::BTreeMap;
+
+use crate::error::Result;
+use crate::guid::MsiGuid;
+use crate::language::MsiLanguage;
+use crate::package::MsiPackage;
+use crate::table::RowOrigin;
+use crate::version::MsiVersion;
+
+#[doc = "The Property table of a package as a map of property names to values, ordered by name."]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Properties {
+    values: BTreeMap<String, String>,
+    origins: BTreeMap<String, RowOrigin>
+}
+
+impl Properties {
+
+    #[doc = "Reads the Property table of the package; a package without one yields no properties. Rows without a value read as empty strings."]
+    pub fn read(package: &MsiPackage) -> Result<Properties>
+    {
+        let table = match package.optional_table("Property")?
+        {
+            Some(table) => table,
+            None => return Ok(Properties::default())
+        };
+
+        let mut properties = Properties::default();
+        for row in table.rows()
+        {
+            if let Some(name) = row.str("Property")
+            {
+                properties.values.insert(name.to_string(), row.str("Value").unwrap_or_default().to_string());
+                properties.origins.insert(name.to_string(), row.origin());
+            }
+        }
+
+        Ok(properties)
+    }
+
+    #[doc = "Returns the value of a property."]
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(String::as_str)
+    }
+
+    #[doc = "Returns the Property row a property was read from."]
+    pub fn origin(&self, name: &str) -> Option<&RowOrigin> {
+        self.origins.get(name)
+    }
+
+    #[doc = "Returns all properties ordered by name."]
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.values.iter().map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    #[doc = "Returns the number of properties."]
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    #[doc = "Returns a boolean value indicating whether the package defines no properties."]
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    #[doc = "Returns the ProductCode, or `None` if it is missing or not a valid GUID."]
+    pub fn product_code(&self) -> Option<MsiGuid> {
+        self.guid("ProductCode")
+    }
+
+    #[doc = "Returns the UpgradeCode, or `None` if it is missing or not a valid GUID."]
+    pub fn upgrade_code(&self) -> Option<MsiGuid> {
+        self.guid("UpgradeCode")
+    }
+
+    #[doc = "Returns the ProductVersion, e.g. `1.2.300`, or `None` if it is missing or not a valid version."]
+    pub fn product_version(&self) -> Option<MsiVersion> {
+        self.get("ProductVersion").and_then(|value| MsiVersion::parse(value).ok())
+    }
+
+    #[doc = "Returns the ProductLanguage, or `None` if it is missing or not a number."]
+    pub fn product_language(&self) -> Option<MsiLanguage> {
+        self.get("ProductLanguage").and_then(|value| value.parse::<u16>().ok()).map(MsiLanguage::from)
+    }
+
+    #[doc = "Returns the ProductName."]
+    pub fn product_name(&self) -> Option<&str> {
+        self.get("ProductName")
+    }
+
+    #[doc = "Returns the Manufacturer."]
+    pub fn manufacturer(&self) -> Option<&str> {
+        self.get("Manufacturer")
+    }
+
+    fn guid(&self, name: &str) -> Option<MsiGuid> {
+        self.get(name).and_then(|value| MsiGuid::parse(value).ok())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::testutil::TestPackage;
+
+    #[test]
+    fn test_read_properties()
+    {
+        let package = TestPackage::new("property-table", |builder| {
+            builder.table("Property", vec![
+                msi::Column::build("Property").primary_key().id_string(72),
+                msi::Column::build("Value").nullable().localizable().text_string(0)
+            ], vec![
+                vec![msi::Value::from("ProductVersion"), msi::Value::from("1.2.300")],
+                vec![msi::Value::from("ProductCode"), msi::Value::from("{8B3A5A8E-1F5C-4E4D-9C1B-2F0F3B7A6D10}")],
+                vec![msi::Value::from("UpgradeCode"), msi::Value::from("not a guid")],
+                vec![msi::Value::from("Manufacturer"), msi::Value::from("Contoso")],
+                vec![msi::Value::from("ProductLanguage"), msi::Value::from("1031")]
+            ]);
+        });
+
+        let properties = MsiPackage::open(package.path()).unwrap().properties().unwrap();
+        assert_eq!(properties.iter().map(|(name, _)| name).collect::<Vec<_>>(), ["Manufacturer", "ProductCode", "ProductLanguage", "ProductVersion", "UpgradeCode"]);
+        assert_eq!(properties.product_code().unwrap().to_string(), "{8B3A5A8E-1F5C-4E4D-9C1B-2F0F3B7A6D10}");
+        assert_eq!((properties.upgrade_code(), properties.get("UpgradeCode")), (None, Some("not a guid")));
+        assert_eq!((properties.product_version().map(|version| version.to_string()), properties.manufacturer(), properties.product_name()), (Some("1.2.300".to_string()), Some("Contoso"), None));
+        assert_eq!(properties.product_language().and_then(|language| language.tag()), Some("de-DE"));
+        assert_eq!(properties.origin("ProductCode").map(|origin| (origin.key(), origin.index())), Some(("ProductCode", 1)));
+        assert_eq!(properties.len(), 5);
+    }
+}
@@ -0,0 +1,307 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use crate::error::Result;
+use crate::package::MsiPackage;
+use crate::table::RowOrigin;
+
+const MULTI_STRING_SEPARATOR: &str = "[~]";
+
+#[doc = "The predefined key a Registry row is written under."]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RegistryRoot {
+    #[doc = "-1: HKEY_CURRENT_USER for per-user installations and HKEY_LOCAL_MACHINE for per-machine ones."]
+    UserOrMachine,
+    #[doc = "0: HKEY_CLASSES_ROOT."]
+    ClassesRoot,
+    #[doc = "1: HKEY_CURRENT_USER."]
+    CurrentUser,
+    #[doc = "2: HKEY_LOCAL_MACHINE."]
+    LocalMachine,
+    #[doc = "3: HKEY_USERS."]
+    Users
+}
+
+impl RegistryRoot {
+
+    #[doc = "Returns the root for a value of the Root column, or `None` if it is not one of -1 to 3."]
+    pub fn from_value(value: i32) -> Option<RegistryRoot> {
+        match value
+        {
+            -1 => Some(RegistryRoot::UserOrMachine),
+            0 => Some(RegistryRoot::ClassesRoot),
+            1 => Some(RegistryRoot::CurrentUser),
+            2 => Some(RegistryRoot::LocalMachine),
+            3 => Some(RegistryRoot::Users),
+            _ => None
+        }
+    }
+}
+
+impl Display for RegistryRoot {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self
+        {
+            RegistryRoot::UserOrMachine => write!(fmt, "HKMU"),
+            RegistryRoot::ClassesRoot => write!(fmt, "HKCR"),
+            RegistryRoot::CurrentUser => write!(fmt, "HKCU"),
+            RegistryRoot::LocalMachine => write!(fmt, "HKLM"),
+            RegistryRoot::Users => write!(fmt, "HKU")
+        }
+    }
+}
+
+#[doc = "How a multi-string value combines with the value already in the registry."]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MultiStringMode {
+    #[doc = "The strings replace the existing value."]
+    Replace,
+    #[doc = "The strings are appended to the existing value (`[~]a[~]b`)."]
+    Append,
+    #[doc = "The strings are prepended to the existing value (`a[~]b[~]`)."]
+    Prepend
+}
+
+#[doc = "The decoded Value column of a Registry row."]
+#[derive(Clone, Debug, PartialEq)]
+pub enum RegistryValue {
+    #[doc = "A REG_SZ value. A leading `##` in the column escapes a single `#`."]
+    String(String),
+    #[doc = "A REG_EXPAND_SZ value, stored with a `#%` prefix."]
+    ExpandString(String),
+    #[doc = "A REG_DWORD value, stored with a `#` prefix."]
+    Integer(i32),
+    #[doc = "A REG_BINARY value, stored as hexadecimal digits with a `#x` prefix."]
+    Binary(Vec<u8>),
+    #[doc = "A REG_MULTI_SZ value, stored as strings separated by `[~]`; empty strings are dropped."]
+    MultiString {
+        values: Vec<String>,
+        mode: MultiStringMode
+    },
+    #[doc = "An integer or binary value whose data is not a literal, usually because it references properties that are only resolved at install time. Holds the column text."]
+    Formatted(String)
+}
+
+impl RegistryValue {
+
+    #[doc = "Decodes the text of a Value column."]
+    pub fn parse(text: &str) -> RegistryValue
+    {
+        if let Some(escaped) = text.strip_prefix("##")
+        {
+            RegistryValue::String(format!("#{}", escaped))
+        }
+        else if let Some(expand) = text.strip_prefix("#%")
+        {
+            RegistryValue::ExpandString(expand.to_string())
+        }
+        else if let Some(hex) = text.strip_prefix("#x").or_else(|| text.strip_prefix("#X"))
+        {
+            parse_hex(hex).map(RegistryValue::Binary).unwrap_or_else(|| RegistryValue::Formatted(text.to_string()))
+        }
+        else if let Some(integer) = text.strip_prefix('#')
+        {
+            integer.parse().map(RegistryValue::Integer).unwrap_or_else(|_| RegistryValue::Formatted(text.to_string()))
+        }
+        else if text.contains(MULTI_STRING_SEPARATOR)
+        {
+            let starts = text.starts_with(MULTI_STRING_SEPARATOR);
+            let ends = text.len() > MULTI_STRING_SEPARATOR.len() && text.ends_with(MULTI_STRING_SEPARATOR);
+            let mode = match (starts, ends)
+            {
+                (true, false) => MultiStringMode::Append,
+                (false, true) => MultiStringMode::Prepend,
+                _ => MultiStringMode::Replace
+            };
+            RegistryValue::MultiString {
+                values: text.split(MULTI_STRING_SEPARATOR).filter(|value| !value.is_empty()).map(|value| value.to_string()).collect(),
+                mode
+            }
+        }
+        else
+        {
+            RegistryValue::String(text.to_string())
+        }
+    }
+}
+
+#[doc = "A row of the Registry table."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct RegistryRow {
+    registry: String,
+    root: i32,
+    key: String,
+    name: Option<String>,
+    value: Option<String>,
+    component: String,
+    origin: RowOrigin
+}
+
+impl RegistryRow {
+
+    #[doc = "Returns the primary key of the row."]
+    pub fn registry(&self) -> &str {
+        &self.registry
+    }
+
+    #[doc = "Returns the predefined key the value is written under, or `None` if the Root column is not a known root."]
+    pub fn root(&self) -> Option<RegistryRoot> {
+        RegistryRoot::from_value(self.root)
+    }
+
+    #[doc = "Returns the Root column as stored."]
+    pub fn root_value(&self) -> i32 {
+        self.root
+    }
+
+    #[doc = "Returns the path of the key below the root."]
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    #[doc = "Returns the name of the value; `None` for the default value. Without a value, `+`, `-` and `*` create the key, remove it on uninstall, or both."]
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    #[doc = "Returns the Value column as stored."]
+    pub fn raw_value(&self) -> Option<&str> {
+        self.value.as_deref()
+    }
+
+    #[doc = "Returns the decoded value, or `None` if the row only creates the key."]
+    pub fn value(&self) -> Option<RegistryValue> {
+        self.value.as_deref().map(RegistryValue::parse)
+    }
+
+    #[doc = "Returns the component that writes the value."]
+    pub fn component(&self) -> &str {
+        &self.component
+    }
+
+    #[doc = "Returns the full path of the value, e.g. `HKLM\\Software\\Contoso\\Version`, with `(Default)` for the default value."]
+    pub fn path(&self) -> String {
+        registry_path(self.root, &self.key, self.name.as_deref())
+    }
+
+    #[doc = "Returns the Registry row the value was read from."]
+    pub fn origin(&self) -> &RowOrigin {
+        &self.origin
+    }
+}
+
+#[doc = "The typed rows of the Registry table, in storage order."]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RegistryTable {
+    rows: Vec<RegistryRow>,
+    index: HashMap<String, usize>
+}
+
+impl RegistryTable {
+
+    #[doc = "Reads the Registry table of the package; a package without one yields an empty table."]
+    pub fn read(package: &MsiPackage) -> Result<RegistryTable>
+    {
+        let table = match package.optional_table("Registry")?
+        {
+            Some(table) => table,
+            None => return Ok(RegistryTable::default())
+        };
+
+        let rows: Vec<RegistryRow> = table.rows()
+            .map(|row| RegistryRow {
+                registry: row.str("Registry").unwrap_or_default().to_string(),
+                root: row.int("Root").unwrap_or(-1),
+                key: row.str("Key").unwrap_or_default().to_string(),
+                name: row.str("Name").filter(|name| !name.is_empty()).map(|name| name.to_string()),
+                value: row.str("Value").map(|value| value.to_string()),
+                component: row.str("Component_").unwrap_or_default().to_string(),
+                origin: row.origin()
+            })
+            .collect();
+        let index = rows.iter().enumerate().map(|(index, row)| (row.registry.clone(), index)).collect();
+
+        Ok(RegistryTable {
+            rows,
+            index
+        })
+    }
+
+    #[doc = "Returns all rows in storage order."]
+    pub fn rows(&self) -> &[RegistryRow] {
+        &self.rows
+    }
+
+    #[doc = "Returns the row with the given key."]
+    pub fn get(&self, registry: &str) -> Option<&RegistryRow> {
+        self.index.get(registry).map(|index| &self.rows[*index])
+    }
+
+    #[doc = "Returns the rows written by the given component."]
+    pub fn by_component<'a>(&'a self, component: &'a str) -> impl Iterator<Item = &'a RegistryRow> {
+        self.rows.iter().filter(move |row| row.component == component)
+    }
+}
+
+// Formats the path of a value with the abbreviated root, writing unknown roots as HKMU.
+pub(crate) fn registry_path(root: i32, key: &str, name: Option<&str>) -> String
+{
+    let root = RegistryRoot::from_value(root).unwrap_or(RegistryRoot::UserOrMachine);
+    format!("{}\\{}\\{}", root, key, name.unwrap_or("(Default)"))
+}
+
+fn parse_hex(hex: &str) -> Option<Vec<u8>>
+{
+    if !hex.len().is_multiple_of(2) || !hex.bytes().all(|byte| byte.is_ascii_hexdigit())
+    {
+        return None;
+    }
+
+    (0..hex.len()).step_by(2).map(|index| u8::from_str_radix(&hex[index..index + 2], 16).ok()).collect()
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::testutil::TestPackage;
+
+    #[test]
+    fn test_read_registry()
+    {
+        assert_eq!(RegistryValue::parse("#-5"), RegistryValue::Integer(-5));
+        assert_eq!(RegistryValue::parse("#x0aFF"), RegistryValue::Binary(vec![0x0a, 0xff]));
+        assert_eq!(RegistryValue::parse("#%[INSTALLDIR]bin"), RegistryValue::ExpandString("[INSTALLDIR]bin".to_string()));
+        assert_eq!(RegistryValue::parse("##1"), RegistryValue::String("#1".to_string()));
+        assert_eq!(RegistryValue::parse("#[Count]"), RegistryValue::Formatted("#[Count]".to_string()));
+        assert_eq!(RegistryValue::parse("[~]a[~]b"), RegistryValue::MultiString { values: vec!["a".to_string(), "b".to_string()], mode: MultiStringMode::Append });
+        assert_eq!(RegistryValue::parse("a[~]b[~]"), RegistryValue::MultiString { values: vec!["a".to_string(), "b".to_string()], mode: MultiStringMode::Prepend });
+        assert_eq!(RegistryValue::parse("[~]a[~]"), RegistryValue::MultiString { values: vec!["a".to_string()], mode: MultiStringMode::Replace });
+
+        let package = TestPackage::new("registry-table", |builder| {
+            builder.table("Registry", vec![
+                msi::Column::build("Registry").primary_key().id_string(72),
+                msi::Column::build("Root").int16(),
+                msi::Column::build("Key").localizable().category(msi::Category::RegPath).string(255),
+                msi::Column::build("Name").nullable().localizable().formatted_string(255),
+                msi::Column::build("Value").nullable().localizable().formatted_string(0),
+                msi::Column::build("Component_").id_string(72)
+            ], vec![
+                vec![msi::Value::from("Version"), msi::Value::Int(2), msi::Value::from("Software\\Contoso"), msi::Value::from("Version"), msi::Value::from("#3"), msi::Value::from("Main")],
+                vec![msi::Value::from("Key"), msi::Value::Int(-1), msi::Value::from("Software\\Contoso"), msi::Value::from("*"), msi::Value::Null, msi::Value::from("Main")],
+                vec![msi::Value::from("Default"), msi::Value::Int(7), msi::Value::from("Software\\Contoso"), msi::Value::Null, msi::Value::from("Contoso"), msi::Value::from("Other")]
+            ]);
+        });
+
+        let registry = RegistryTable::read(&MsiPackage::open(package.path()).unwrap()).unwrap();
+        let version = registry.get("Version").unwrap();
+        assert_eq!((version.root(), version.value()), (Some(RegistryRoot::LocalMachine), Some(RegistryValue::Integer(3))));
+        assert_eq!(version.path(), "HKLM\\Software\\Contoso\\Version");
+        assert_eq!((registry.get("Key").unwrap().name(), registry.get("Key").unwrap().value()), (Some("*"), None));
+
+        let default = registry.get("Default").unwrap();
+        assert_eq!((default.root(), default.root_value()), (None, 7));
+        assert_eq!(default.path(), "HKMU\\Software\\Contoso\\(Default)");
+        assert_eq!(registry.by_component("Main").count(), 2);
+    }
+}
@@ -0,0 +1,282 @@
+use std::fmt::Write;
+
+use crate::customaction::CustomActionMatrix;
+use crate::diff::PackageDiff;
+use crate::error::Result;
+use crate::export::OutputOrder;
+use crate::package::MsiPackage;
+use crate::sequence;
+use crate::summary::{ PropertyValue, PID_AUTHOR, PID_COMMENTS, PID_SUBJECT, PID_TITLE };
+use crate::validation;
+
+const STYLE: &str = "body { font-family: Segoe UI, sans-serif; margin: 2em; color: #222; }
+h1 { margin-bottom: 0; } h2 { border-bottom: 1px solid #ccc; margin-top: 2em; }
+table { border-collapse: collapse; margin: 1em 0; } th, td { border: 1px solid #ddd; padding: 4px 8px; text-align: left; vertical-align: top; }
+th { background: #f3f3f3; } td.num { text-align: right; } .added { color: #176f2c; } .removed { color: #b3261e; } .none { color: #777; }";
+
+const OVERVIEW_PROPERTIES: [&str; 6] = ["ProductName", "ProductVersion", "Manufacturer", "ProductCode", "UpgradeCode", "ProductLanguage"];
+
+#[doc = "A self-contained HTML report of a package: overview, tables, custom actions and validation findings, optionally with the differences to another package."]
+pub struct HtmlReport<'a> {
+    package: &'a MsiPackage,
+    title: String,
+    comparison: Option<(String, PackageDiff)>,
+    order: OutputOrder
+}
+
+impl<'a> HtmlReport<'a> {
+
+    #[doc = "Creates a report of the package, titled e.g. with its file name."]
+    pub fn new<S: Into<String>>(package: &'a MsiPackage, title: S) -> Self
+    {
+        HtmlReport {
+            package,
+            title: title.into(),
+            comparison: None,
+            order: OutputOrder::Stored
+        }
+    }
+
+    #[doc = "Adds a section with the differences to another package, named `other` in the report. The diff is expected to go from the other package to this one."]
+    pub fn with_comparison<S: Into<String>>(mut self, other: S, diff: PackageDiff) -> Self
+    {
+        self.comparison = Some((other.into(), diff));
+        self
+    }
+
+    #[doc = "Sets the order of the findings; `OutputOrder::Sorted` makes reports of the same content byte-identical."]
+    pub fn with_order(mut self, order: OutputOrder) -> Self
+    {
+        self.order = order;
+        self
+    }
+
+    #[doc = "Renders the report as a single HTML document without external resources."]
+    pub fn render(&self) -> Result<String>
+    {
+        let mut html = String::new();
+        let _ = write!(html, "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{}\n</style>\n</head>\n<body>\n<h1>{}</h1>\n",
+            escape(&self.title), STYLE, escape(&self.title));
+
+        self.render_overview(&mut html)?;
+        self.render_tables(&mut html)?;
+        self.render_custom_actions(&mut html)?;
+        self.render_findings(&mut html)?;
+        if let Some((other, diff)) = &self.comparison
+        {
+            render_diff(&mut html, other, diff);
+        }
+
+        html.push_str("</body>\n</html>\n");
+        Ok(html)
+    }
+
+    fn render_overview(&self, html: &mut String) -> Result<()>
+    {
+        html.push_str("<h2>Overview</h2>\n<table>\n");
+        for name in OVERVIEW_PROPERTIES.iter()
+        {
+            if let Some(value) = self.package.property(name)?
+            {
+                let _ = writeln!(html, "<tr><th>{}</th><td>{}</td></tr>", name, escape(&value));
+            }
+        }
+
+        let summary = self.package.summary();
+        let fields = [("Title", PID_TITLE), ("Subject", PID_SUBJECT), ("Author", PID_AUTHOR), ("Comments", PID_COMMENTS)];
+        for (label, id) in fields.iter()
+        {
+            if let Some(PropertyValue::Str(value)) = summary.get(*id)
+            {
+                let _ = writeln!(html, "<tr><th>{}</th><td>{}</td></tr>", label, escape(value));
+            }
+        }
+        if let Some(template) = summary.template()
+        {
+            let _ = writeln!(html, "<tr><th>Template</th><td>{}</td></tr>", escape(template));
+        }
+        if let Some(revision) = summary.revision()
+        {
+            let _ = writeln!(html, "<tr><th>Package code</th><td>{}</td></tr>", escape(revision));
+        }
+
+        html.push_str("</table>\n");
+        Ok(())
+    }
+
+    fn render_tables(&self, html: &mut String) -> Result<()>
+    {
+        html.push_str("<h2>Tables</h2>\n<table>\n<tr><th>Table</th><th>Rows</th></tr>\n");
+        for name in self.package.table_names()
+        {
+            let _ = writeln!(html, "<tr><td>{}</td><td class=\"num\">{}</td></tr>", escape(name), self.package.table(name)?.len());
+        }
+
+        html.push_str("</table>\n");
+        Ok(())
+    }
+
+    fn render_custom_actions(&self, html: &mut String) -> Result<()>
+    {
+        html.push_str("<h2>Custom actions</h2>\n");
+        let matrix = CustomActionMatrix::build(self.package)?;
+        if matrix.entries().is_empty()
+        {
+            html.push_str("<p class=\"none\">None.</p>\n");
+            return Ok(());
+        }
+
+        html.push_str("<table>\n<tr><th>Action</th><th>Type</th><th>Scheduling</th><th>Context</th><th>Source</th><th>Target</th></tr>\n");
+        for entry in matrix.entries()
+        {
+            let _ = writeln!(html, "<tr><td>{}</td><td class=\"num\">{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                escape(entry.action()), entry.custom_action_type(), entry.scheduling(), entry.impersonation(),
+                escape(entry.source().unwrap_or_default()), escape(entry.target().unwrap_or_default()));
+        }
+
+        html.push_str("</table>\n");
+        Ok(())
+    }
+
+    fn render_findings(&self, html: &mut String) -> Result<()>
+    {
+        let mut findings: Vec<(String, String)> = Vec::new();
+        for anomaly in sequence::find_anomalies(self.package)?
+        {
+            findings.push((format!("{} [{}]", anomaly.table(), anomaly.action()), anomaly.kind().to_string()));
+        }
+        for undefined in validation::find_undefined_properties(self.package)?
+        {
+            findings.push((undefined.location().to_string(), format!("undefined property '{}'", undefined.property())));
+        }
+        let references = validation::find_broken_formatted_references(self.package)?.into_iter()
+            .chain(validation::find_broken_directory_references(self.package)?)
+            .chain(validation::find_dangling_foreign_keys(self.package)?);
+        for reference in references
+        {
+            findings.push((reference.location().to_string(), format!("'{}' not found in {}", reference.value(), reference.target())));
+        }
+        for invalid in validation::find_invalid_filenames(self.package)?
+        {
+            findings.push((invalid.location().to_string(), format!("invalid file name '{}': {}", invalid.name(), invalid.error())));
+        }
+
+        if self.order == OutputOrder::Sorted
+        {
+            findings.sort();
+        }
+
+        html.push_str("<h2>Findings</h2>\n");
+        if findings.is_empty()
+        {
+            html.push_str("<p class=\"none\">No problems found.</p>\n");
+            return Ok(());
+        }
+
+        html.push_str("<table>\n<tr><th>Location</th><th>Problem</th></tr>\n");
+        for (location, problem) in findings
+        {
+            let _ = writeln!(html, "<tr><td>{}</td><td>{}</td></tr>", escape(&location), escape(&problem));
+        }
+
+        html.push_str("</table>\n");
+        Ok(())
+    }
+}
+
+fn render_diff(html: &mut String, other: &str, diff: &PackageDiff)
+{
+    let _ = writeln!(html, "<h2>Differences to {}</h2>", escape(other));
+    if diff.is_empty()
+    {
+        html.push_str("<p class=\"none\">The packages have the same content.</p>\n");
+        return;
+    }
+
+    html.push_str("<table>\n<tr><th>Item</th><th>Change</th></tr>\n");
+    let lists = [
+        ("table", diff.added_tables(), "added"), ("table", diff.removed_tables(), "removed"),
+        ("stream", diff.added_streams(), "added"), ("stream", diff.removed_streams(), "removed"), ("stream", diff.modified_streams(), "modified")
+    ];
+    for (kind, names, change) in lists.iter()
+    {
+        for name in names.iter()
+        {
+            let _ = writeln!(html, "<tr><td>{} {}</td><td class=\"{}\">{}</td></tr>", kind, escape(name), change, change);
+        }
+    }
+
+    for table in diff.tables()
+    {
+        for row in table.added()
+        {
+            let _ = writeln!(html, "<tr><td>{} [{}]</td><td class=\"added\">added</td></tr>", escape(table.table()), escape(row.key()));
+        }
+        for row in table.removed()
+        {
+            let _ = writeln!(html, "<tr><td>{} [{}]</td><td class=\"removed\">removed</td></tr>", escape(table.table()), escape(row.key()));
+        }
+        for row in table.modified()
+        {
+            let changes: Vec<String> = row.changes().iter()
+                .map(|change| format!("{}: {} &rarr; {}", escape(change.column()), escape(&change.old_value().to_string()), escape(&change.new_value().to_string())))
+                .collect();
+            let _ = writeln!(html, "<tr><td>{} [{}]</td><td>{}</td></tr>", escape(table.table()), escape(row.key()), changes.join("<br>"));
+        }
+    }
+
+    html.push_str("</table>\n");
+}
+
+fn escape(text: &str) -> String
+{
+    let mut output = String::with_capacity(text.len());
+    for c in text.chars()
+    {
+        match c
+        {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '"' => output.push_str("&quot;"),
+            '\'' => output.push_str("&#39;"),
+            c => output.push(c)
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::testutil::TestPackage;
+
+    #[test]
+    fn test_render()
+    {
+        let build = |tag: &str, version: &str| TestPackage::new(tag, |builder| {
+            builder.table("Property", vec![
+                msi::Column::build("Property").primary_key().id_string(72),
+                msi::Column::build("Value").localizable().text_string(0)
+            ], vec![
+                vec![msi::Value::from("ProductName"), msi::Value::from("Alpha & <Beta>")],
+                vec![msi::Value::from("ProductVersion"), msi::Value::from(version)]
+            ]);
+        });
+        let old = build("report-old", "1.0");
+        let new = build("report-new", "2.0");
+        let old = MsiPackage::open(old.path()).unwrap();
+        let new = MsiPackage::open(new.path()).unwrap();
+
+        let html = HtmlReport::new(&new, "new.msi")
+            .with_comparison("old.msi", PackageDiff::compare(&old, &new).unwrap())
+            .render()
+            .unwrap();
+        assert!(html.contains("<td>Alpha &amp; &lt;Beta&gt;</td>"));
+        assert!(html.contains("<h2>Differences to old.msi</h2>"));
+        assert!(html.contains("Value: 1.0 &rarr; 2.0"));
+        assert!(!html.contains("<link") && !html.contains("<script"));
+    }
+}
@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::digest::Digest;
+use crate::directory::NameFormat;
+use crate::error::{ Error, Result };
+use crate::file::FileTable;
+use crate::json;
+use crate::package::MsiPackage;
+use crate::table::RowOrigin;
+
+#[doc = "The output formats a bill of materials can be written in."]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SbomFormat {
+    #[doc = "CycloneDX 1.5 JSON."]
+    CycloneDx
+}
+
+impl FromStr for SbomFormat {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<SbomFormat>
+    {
+        match value.to_ascii_lowercase().as_str()
+        {
+            "cyclonedx" => Ok(SbomFormat::CycloneDx),
+            other => Err(Error::NotFound(format!("SBOM format '{}'", other)))
+        }
+    }
+}
+
+impl Display for SbomFormat {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self
+        {
+            SbomFormat::CycloneDx => write!(fmt, "cyclonedx")
+        }
+    }
+}
+
+#[doc = "A file installed by the package."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SbomFile {
+    key: String,
+    name: String,
+    component: String,
+    version: Option<String>,
+    size: Option<i32>,
+    digest: Option<Digest>,
+    origin: RowOrigin
+}
+
+impl SbomFile {
+
+    #[doc = "Returns the primary key of the file in the File table."]
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    #[doc = "Returns the long file name."]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[doc = "Returns the component the file belongs to."]
+    pub fn component(&self) -> &str {
+        &self.component
+    }
+
+    #[doc = "Returns the file version, for versioned files."]
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+
+    #[doc = "Returns the file size declared in the File table."]
+    pub fn size(&self) -> Option<i32> {
+        self.size
+    }
+
+    #[doc = "Returns the SHA-256 digest of the file, if its payload is in an embedded cabinet."]
+    pub fn digest(&self) -> Option<Digest> {
+        self.digest
+    }
+
+    #[doc = "Returns the File row the file was read from."]
+    pub fn origin(&self) -> &RowOrigin {
+        &self.origin
+    }
+}
+
+#[doc = "A software bill of materials describing a package: the product and every file it installs."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sbom {
+    name: String,
+    version: Option<String>,
+    manufacturer: Option<String>,
+    product_code: Option<String>,
+    files: Vec<SbomFile>
+}
+
+impl Sbom {
+
+    #[doc = "Collects the product information from the Property table and the files from the File table. Digests are taken from the embedded cabinets; files stored elsewhere are listed without one."]
+    pub fn generate(package: &MsiPackage) -> Result<Sbom>
+    {
+        let digests: HashMap<&str, Digest> = package.digests()?.cabinet_files().iter()
+            .map(|((_, member), digest)| (member.as_str(), digest.digest()))
+            .collect();
+
+        let mut files: Vec<SbomFile> = FileTable::read(package)?.rows().iter()
+            .map(|file| SbomFile {
+                key: file.file().to_string(),
+                name: file.file_name().format(NameFormat::Long),
+                component: file.component().to_string(),
+                version: file.version().map(|version| version.to_string()),
+                size: Some(file.size()),
+                digest: digests.get(file.file()).copied(),
+                origin: file.origin().clone()
+            })
+            .collect();
+
+        files.sort_by(|first, second| first.key.cmp(&second.key));
+        Ok(Sbom {
+            name: package.property("ProductName")?.unwrap_or_default(),
+            version: package.property("ProductVersion")?,
+            manufacturer: package.property("Manufacturer")?,
+            product_code: package.property("ProductCode")?,
+            files
+        })
+    }
+
+    #[doc = "Returns the product name."]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[doc = "Returns the product version."]
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+
+    #[doc = "Returns the manufacturer."]
+    pub fn manufacturer(&self) -> Option<&str> {
+        self.manufacturer.as_deref()
+    }
+
+    #[doc = "Returns the ProductCode GUID."]
+    pub fn product_code(&self) -> Option<&str> {
+        self.product_code.as_deref()
+    }
+
+    #[doc = "Returns the installed files, ordered by key."]
+    pub fn files(&self) -> &[SbomFile] {
+        &self.files
+    }
+
+    #[doc = "Renders the bill of materials in the given format."]
+    pub fn render(&self, format: SbomFormat) -> String {
+        match format
+        {
+            SbomFormat::CycloneDx => self.to_cyclonedx()
+        }
+    }
+
+    fn to_cyclonedx(&self) -> String
+    {
+        let mut product = vec![
+            "\"type\": \"application\"".to_string(),
+            format!("\"bom-ref\": {}", json::string(self.product_code.as_deref().unwrap_or("product"))),
+            format!("\"name\": {}", json::string(&self.name))
+        ];
+        if let Some(version) = &self.version
+        {
+            product.push(format!("\"version\": {}", json::string(version)));
+        }
+        if let Some(manufacturer) = &self.manufacturer
+        {
+            product.push(format!("\"supplier\": {{ \"name\": {} }}", json::string(manufacturer)));
+        }
+
+        let components: Vec<String> = self.files.iter().map(|file| {
+            let mut fields = vec![
+                "\"type\": \"file\"".to_string(),
+                format!("\"bom-ref\": {}", json::string(&format!("file:{}", file.key))),
+                format!("\"name\": {}", json::string(&file.name))
+            ];
+            if let Some(version) = &file.version
+            {
+                fields.push(format!("\"version\": {}", json::string(version)));
+            }
+            if let Some(digest) = file.digest
+            {
+                fields.push(format!("\"hashes\": [ {{ \"alg\": \"SHA-256\", \"content\": \"{}\" }} ]", digest));
+            }
+            fields.push(format!("\"properties\": [ {{ \"name\": \"msi:component\", \"value\": {} }} ]", json::string(&file.component)));
+            format!("    {{\n      {}\n    }}", fields.join(",\n      "))
+        }).collect();
+
+        let mut output = String::from("{\n  \"bomFormat\": \"CycloneDX\",\n  \"specVersion\": \"1.5\",\n  \"version\": 1,\n");
+        output.push_str(&format!("  \"metadata\": {{\n    \"component\": {{\n      {}\n    }}\n  }},\n", product.join(",\n      ")));
+        if components.is_empty()
+        {
+            output.push_str("  \"components\": []\n}\n");
+        }
+        else
+        {
+            output.push_str(&format!("  \"components\": [\n{}\n  ]\n}}\n", components.join(",\n")));
+        }
+
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::testutil::TestPackage;
+
+    #[test]
+    fn test_cyclonedx()
+    {
+        let package = TestPackage::new("sbom", |builder| {
+            builder.table("Property", vec![
+                msi::Column::build("Property").primary_key().id_string(72),
+                msi::Column::build("Value").localizable().text_string(0)
+            ], vec![
+                vec![msi::Value::from("ProductName"), msi::Value::from("Alpha \"Pro\"")],
+                vec![msi::Value::from("ProductVersion"), msi::Value::from("1.2.3")],
+                vec![msi::Value::from("Manufacturer"), msi::Value::from("Contoso")]
+            ]);
+            builder.table("File", vec![
+                msi::Column::build("File").primary_key().id_string(72),
+                msi::Column::build("Component_").id_string(72),
+                msi::Column::build("FileName").localizable().text_string(255),
+                msi::Column::build("FileSize").int32(),
+                msi::Column::build("Version").nullable().text_string(72)
+            ], vec![
+                vec![msi::Value::from("app.exe"), msi::Value::from("Main"), msi::Value::from("APP~1.EXE|app.exe"), msi::Value::from(10), msi::Value::from("1.2.3.0")],
+                vec![msi::Value::from("app.ini"), msi::Value::from("Main"), msi::Value::from("app.ini"), msi::Value::from(3), msi::Value::from("app.exe")]
+            ]);
+        });
+
+        let sbom = Sbom::generate(&MsiPackage::open(package.path()).unwrap()).unwrap();
+        assert_eq!(sbom.files().len(), 2);
+        assert_eq!(sbom.files()[0].name(), "app.exe");
+        assert_eq!(sbom.files()[0].version(), Some("1.2.3.0"));
+        assert_eq!(sbom.files()[1].version(), None);
+
+        let output = sbom.render("CycloneDX".parse().unwrap());
+        assert!(output.contains("\"name\": \"Alpha \\\"Pro\\\"\""));
+        assert!(output.contains("\"supplier\": { \"name\": \"Contoso\" }"));
+        assert!(output.contains("\"bom-ref\": \"file:app.ini\""));
+        assert!("spdx".parse::<SbomFormat>().is_err());
+    }
+}
@@ -0,0 +1,235 @@
+#[doc = "A JSON Schema describing one of the JSON documents this crate writes. Every document names its schema in a `$schema` member; the id changes whenever the shape changes incompatibly."]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct JsonSchema {
+    name: &'static str,
+    id: &'static str,
+    text: &'static str
+}
+
+impl JsonSchema {
+
+    #[doc = "Returns the short name of the document format, e.g. `database`."]
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    #[doc = "Returns the versioned id of the schema, e.g. `urn:msi-reader:database:v1`."]
+    pub fn id(&self) -> &'static str {
+        self.id
+    }
+
+    #[doc = "Returns the schema document (JSON Schema draft 2020-12)."]
+    pub fn text(&self) -> &'static str {
+        self.text
+    }
+}
+
+#[doc = "The schema of the database export written by `export::json_database`."]
+pub const DATABASE: JsonSchema = JsonSchema { name: "database", id: "urn:msi-reader:database:v1", text: DATABASE_SCHEMA };
+
+#[doc = "The schema of the differences between two packages written by `PackageDiff::to_json`."]
+pub const DIFF: JsonSchema = JsonSchema { name: "diff", id: "urn:msi-reader:diff:v1", text: DIFF_SCHEMA };
+
+#[doc = "The schema of the validation findings written by `ice::findings_json`."]
+pub const FINDINGS: JsonSchema = JsonSchema { name: "findings", id: "urn:msi-reader:findings:v1", text: FINDINGS_SCHEMA };
+
+#[doc = "All published schemas."]
+pub const SCHEMAS: [JsonSchema; 3] = [DATABASE, DIFF, FINDINGS];
+
+#[doc = "Returns the schema with the given short name."]
+pub fn find(name: &str) -> Option<&'static JsonSchema>
+{
+    SCHEMAS.iter().find(|schema| schema.name == name)
+}
+
+const DATABASE_SCHEMA: &str = r##"{
+  "$schema": "https://json-schema.org/draft/2020-12/schema",
+  "$id": "urn:msi-reader:database:v1",
+  "title": "MSI database export",
+  "type": "object",
+  "required": ["$schema", "tables"],
+  "properties": {
+    "$schema": { "const": "urn:msi-reader:database:v1" },
+    "tables": {
+      "type": "array",
+      "items": {
+        "type": "object",
+        "required": ["name", "columns", "rows"],
+        "properties": {
+          "name": { "type": "string" },
+          "columns": {
+            "type": "array",
+            "items": {
+              "type": "object",
+              "required": ["name", "type", "nullable", "primaryKey", "localizable"],
+              "properties": {
+                "name": { "type": "string" },
+                "type": { "enum": ["int16", "int32", "string", "binary"] },
+                "size": { "type": "integer", "minimum": 0, "description": "Maximum length of string columns, 0 if unlimited." },
+                "nullable": { "type": "boolean" },
+                "primaryKey": { "type": "boolean" },
+                "localizable": { "type": "boolean" }
+              },
+              "additionalProperties": false
+            }
+          },
+          "rows": {
+            "type": "array",
+            "description": "Rows as arrays of cells in column order. Binary cells hold the name of their stream.",
+            "items": { "type": "array", "items": { "type": ["null", "integer", "string"] } }
+          }
+        },
+        "additionalProperties": false
+      }
+    },
+    "summary": {
+      "type": "array",
+      "description": "The properties of the summary information stream, ordered by id.",
+      "items": {
+        "type": "object",
+        "required": ["id", "type", "value"],
+        "properties": {
+          "id": { "type": "integer", "minimum": 0, "description": "Property id, e.g. 2 for Title or 9 for the package code." },
+          "type": { "enum": ["empty", "null", "int16", "int32", "string", "filetime"] },
+          "value": { "type": ["null", "integer", "string"], "description": "Filetime values are 100ns intervals since 1601-01-01 UTC." }
+        },
+        "additionalProperties": false
+      }
+    },
+    "escapedCells": {
+      "type": "array",
+      "description": "Present in escaped text mode: the string cells whose undecodable bytes are written as \\xNN, with their backslashes doubled.",
+      "items": {
+        "type": "object",
+        "required": ["table", "row", "column"],
+        "properties": {
+          "table": { "type": "string" },
+          "row": { "type": "string", "description": "Primary key of the row, with multiple key columns joined by '.'." },
+          "column": { "type": "string" }
+        },
+        "additionalProperties": false
+      }
+    }
+  },
+  "additionalProperties": false
+}
+"##;
+
+const DIFF_SCHEMA: &str = r##"{
+  "$schema": "https://json-schema.org/draft/2020-12/schema",
+  "$id": "urn:msi-reader:diff:v1",
+  "title": "Differences between two MSI packages",
+  "type": "object",
+  "required": ["$schema", "addedTables", "removedTables", "tables", "addedStreams", "removedStreams", "modifiedStreams"],
+  "properties": {
+    "$schema": { "const": "urn:msi-reader:diff:v1" },
+    "addedTables": { "$ref": "#/$defs/names" },
+    "removedTables": { "$ref": "#/$defs/names" },
+    "tables": {
+      "type": "array",
+      "items": {
+        "type": "object",
+        "required": ["name", "added", "removed", "modified"],
+        "properties": {
+          "name": { "type": "string" },
+          "added": { "$ref": "#/$defs/names", "description": "Primary keys of added rows, key columns joined by '.'." },
+          "removed": { "$ref": "#/$defs/names" },
+          "modified": {
+            "type": "array",
+            "items": {
+              "type": "object",
+              "required": ["key", "changes"],
+              "properties": {
+                "key": { "type": "string" },
+                "changes": {
+                  "type": "array",
+                  "items": {
+                    "type": "object",
+                    "required": ["column", "old", "new"],
+                    "properties": {
+                      "column": { "type": "string" },
+                      "old": { "$ref": "#/$defs/cell" },
+                      "new": { "$ref": "#/$defs/cell" }
+                    },
+                    "additionalProperties": false
+                  }
+                }
+              },
+              "additionalProperties": false
+            }
+          }
+        },
+        "additionalProperties": false
+      }
+    },
+    "addedStreams": { "$ref": "#/$defs/names" },
+    "removedStreams": { "$ref": "#/$defs/names" },
+    "modifiedStreams": { "$ref": "#/$defs/names" }
+  },
+  "additionalProperties": false,
+  "$defs": {
+    "names": { "type": "array", "items": { "type": "string" } },
+    "cell": { "type": ["null", "integer", "string"] }
+  }
+}
+"##;
+
+const FINDINGS_SCHEMA: &str = r##"{
+  "$schema": "https://json-schema.org/draft/2020-12/schema",
+  "$id": "urn:msi-reader:findings:v1",
+  "title": "MSI validation findings",
+  "type": "object",
+  "required": ["$schema", "findings"],
+  "properties": {
+    "$schema": { "const": "urn:msi-reader:findings:v1" },
+    "findings": {
+      "type": "array",
+      "items": {
+        "type": "object",
+        "required": ["rule", "severity", "message", "location"],
+        "properties": {
+          "rule": { "type": "string" },
+          "severity": { "enum": ["info", "warning", "error"] },
+          "message": { "type": "string" },
+          "location": {
+            "oneOf": [
+              { "type": "null" },
+              {
+                "type": "object",
+                "required": ["table", "column", "key", "row"],
+                "properties": {
+                  "table": { "type": "string" },
+                  "column": { "type": "string" },
+                  "key": { "type": "string" },
+                  "row": { "type": "integer", "minimum": 0, "description": "Position of the row in storage order." }
+                },
+                "additionalProperties": false
+              }
+            ]
+          }
+        },
+        "additionalProperties": false
+      }
+    }
+  },
+  "additionalProperties": false
+}
+"##;
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn test_find()
+    {
+        assert_eq!(find("diff").unwrap().id(), "urn:msi-reader:diff:v1");
+        assert!(find("changeset").is_none());
+        for schema in SCHEMAS.iter()
+        {
+            assert!(schema.text().contains(&format!("\"$id\": \"{}\"", schema.id())));
+            assert!(schema.text().contains(&format!("\"$schema\": {{ \"const\": \"{}\" }}", schema.id())));
+        }
+    }
+}
@@ -0,0 +1,568 @@
+use std::collections::{ BTreeMap, HashMap, HashSet };
+use std::fmt::Display;
+
+use crate::error::Result;
+use crate::package::MsiPackage;
+use crate::table::RowOrigin;
+
+#[doc = "Names of the four sequence tables a package may contain."]
+pub const SEQUENCE_TABLES: [&str; 4] = [
+    "InstallUISequence",
+    "InstallExecuteSequence",
+    "AdminExecuteSequence",
+    "AdvtExecuteSequence"
+];
+
+// Standard actions that each sequence table must schedule, in the order they are required to run.
+const REQUIRED_ACTIONS: [(&str, &[&str]); 4] = [
+    ("InstallUISequence", &["CostInitialize", "FileCost", "CostFinalize", "ExecuteAction"]),
+    ("InstallExecuteSequence", &["CostInitialize", "FileCost", "CostFinalize", "InstallValidate", "InstallInitialize", "InstallFinalize"]),
+    ("AdminExecuteSequence", &["CostInitialize", "FileCost", "CostFinalize", "InstallValidate", "InstallInitialize", "InstallFinalize"]),
+    ("AdvtExecuteSequence", &["CostInitialize", "CostFinalize", "InstallValidate", "InstallInitialize", "InstallFinalize"])
+];
+
+#[doc = "A row of a sequence table: an action with its optional condition and sequence number."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScheduledAction {
+    action: String,
+    condition: Option<String>,
+    sequence: Option<i32>,
+    origin: RowOrigin
+}
+
+impl ScheduledAction {
+
+    #[doc = "Returns the name of the action."]
+    pub fn action(&self) -> &str {
+        &self.action
+    }
+
+    #[doc = "Returns the condition of the action, if any."]
+    pub fn condition(&self) -> Option<&str> {
+        self.condition.as_deref()
+    }
+
+    #[doc = "Returns the sequence number of the action. Negative numbers mark exit dialogs."]
+    pub fn sequence(&self) -> Option<i32> {
+        self.sequence
+    }
+
+    #[doc = "Returns the index of the row in its sequence table."]
+    pub fn row(&self) -> usize {
+        self.origin.index()
+    }
+
+    #[doc = "Returns the sequence table row the action was read from."]
+    pub fn origin(&self) -> &RowOrigin {
+        &self.origin
+    }
+}
+
+pub(crate) fn read_actions(package: &MsiPackage, table: &str) -> Result<Vec<ScheduledAction>>
+{
+    let table = match package.optional_table(table)?
+    {
+        Some(table) => table,
+        None => return Ok(Vec::new())
+    };
+
+    Ok(table.rows()
+        .filter_map(|row| row.str("Action").map(|action| ScheduledAction {
+            action: action.to_string(),
+            condition: row.str("Condition").map(|condition| condition.trim().to_string()).filter(|condition| !condition.is_empty()),
+            sequence: row.int("Sequence"),
+            origin: row.origin()
+        }))
+        .collect())
+}
+
+#[doc = "One of the four sequence tables."]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Sequence {
+    InstallUI,
+    InstallExecute,
+    AdminExecute,
+    AdvtExecute
+}
+
+impl Sequence {
+
+    #[doc = "All sequences, in the order of `SEQUENCE_TABLES`."]
+    pub const ALL: [Sequence; 4] = [Sequence::InstallUI, Sequence::InstallExecute, Sequence::AdminExecute, Sequence::AdvtExecute];
+
+    #[doc = "Returns the name of the sequence table, e.g. `InstallExecuteSequence`."]
+    pub fn table_name(&self) -> &'static str {
+        match self
+        {
+            Sequence::InstallUI => SEQUENCE_TABLES[0],
+            Sequence::InstallExecute => SEQUENCE_TABLES[1],
+            Sequence::AdminExecute => SEQUENCE_TABLES[2],
+            Sequence::AdvtExecute => SEQUENCE_TABLES[3]
+        }
+    }
+}
+
+impl Display for Sequence {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "{}", self.table_name())
+    }
+}
+
+#[doc = "The actions of one sequence table, split into the ones that run, in order, the dialogs shown when the installation ends, and the ones that never run."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SequenceTable {
+    sequence: Sequence,
+    actions: Vec<ScheduledAction>,
+    terminations: Vec<ScheduledAction>,
+    inactive: Vec<ScheduledAction>
+}
+
+impl SequenceTable {
+
+    #[doc = "Reads a sequence table of the package; a package without it yields an empty sequence."]
+    pub fn read(package: &MsiPackage, sequence: Sequence) -> Result<SequenceTable>
+    {
+        let mut actions = Vec::new();
+        let mut terminations = Vec::new();
+        let mut inactive = Vec::new();
+        for action in read_actions(package, sequence.table_name())?
+        {
+            match action.sequence
+            {
+                Some(number) if number > 0 => actions.push(action),
+                Some(number) if number < 0 => terminations.push(action),
+                _ => inactive.push(action)
+            }
+        }
+
+        // stable sorts keep the storage order of actions sharing a number
+        actions.sort_by_key(|action| action.sequence);
+        terminations.sort_by_key(|action| std::cmp::Reverse(action.sequence));
+        Ok(SequenceTable {
+            sequence,
+            actions,
+            terminations,
+            inactive
+        })
+    }
+
+    #[doc = "Returns which sequence table the actions were read from."]
+    pub fn sequence(&self) -> Sequence {
+        self.sequence
+    }
+
+    #[doc = "Returns the actions with a positive sequence number, in the order they run; each still runs only if its condition holds."]
+    pub fn actions(&self) -> &[ScheduledAction] {
+        &self.actions
+    }
+
+    #[doc = "Returns the actions with a negative sequence number, run when the installation ends: -1 on success, -2 on cancel, -3 on a fatal error and -4 when suspended."]
+    pub fn terminations(&self) -> &[ScheduledAction] {
+        &self.terminations
+    }
+
+    #[doc = "Returns the actions with a sequence number of 0 or none, which never run."]
+    pub fn inactive(&self) -> &[ScheduledAction] {
+        &self.inactive
+    }
+
+    #[doc = "Returns the action with the given name, wherever it is scheduled."]
+    pub fn get(&self, action: &str) -> Option<&ScheduledAction> {
+        self.actions.iter().chain(self.terminations.iter()).chain(self.inactive.iter()).find(|scheduled| scheduled.action == action)
+    }
+
+    #[doc = "Returns a boolean value indicating whether the sequence has no actions at all."]
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty() && self.terminations.is_empty() && self.inactive.is_empty()
+    }
+}
+
+fn dialog_names(package: &MsiPackage) -> Result<HashSet<String>>
+{
+    Ok(match package.optional_table("Dialog")?
+    {
+        Some(table) => table.rows().filter_map(|row| row.str("Dialog").map(|name| name.to_string())).collect(),
+        None => HashSet::new()
+    })
+}
+
+#[doc = "The kind of problem found in a sequence table."]
+#[derive(Clone, Debug, PartialEq)]
+pub enum AnomalyKind {
+    #[doc = "Another action shares the same sequence number, leaving their relative order undefined."]
+    DuplicateSequence(String),
+    #[doc = "The action is scheduled after InstallFinalize and runs outside the installation transaction."]
+    AfterInstallFinalize,
+    #[doc = "A required standard action is not scheduled at all."]
+    MissingStandardAction,
+    #[doc = "A required standard action is scheduled before the given action it has to follow."]
+    OutOfOrder(String),
+    #[doc = "A dialog is scheduled in an execute sequence, which runs without UI."]
+    DialogInExecuteSequence
+}
+
+impl Display for AnomalyKind {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self
+        {
+            AnomalyKind::DuplicateSequence(other) => write!(fmt, "shares its sequence number with {}", other),
+            AnomalyKind::AfterInstallFinalize => write!(fmt, "scheduled after InstallFinalize"),
+            AnomalyKind::MissingStandardAction => write!(fmt, "required standard action is missing"),
+            AnomalyKind::OutOfOrder(other) => write!(fmt, "scheduled before {}", other),
+            AnomalyKind::DialogInExecuteSequence => write!(fmt, "dialog in an execute sequence")
+        }
+    }
+}
+
+#[doc = "A problem found in a sequence table, pointing at the offending row."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SequenceAnomaly {
+    table: String,
+    action: String,
+    sequence: Option<i32>,
+    origin: Option<RowOrigin>,
+    kind: AnomalyKind
+}
+
+impl SequenceAnomaly {
+
+    #[doc = "Returns the name of the sequence table."]
+    pub fn table(&self) -> &str {
+        &self.table
+    }
+
+    #[doc = "Returns the name of the action."]
+    pub fn action(&self) -> &str {
+        &self.action
+    }
+
+    #[doc = "Returns the sequence number of the action, if it is scheduled."]
+    pub fn sequence(&self) -> Option<i32> {
+        self.sequence
+    }
+
+    #[doc = "Returns the index of the offending row, or `None` for actions that are missing altogether."]
+    pub fn row(&self) -> Option<usize> {
+        self.origin.as_ref().map(RowOrigin::index)
+    }
+
+    #[doc = "Returns the offending row, or `None` for actions that are missing altogether."]
+    pub fn origin(&self) -> Option<&RowOrigin> {
+        self.origin.as_ref()
+    }
+
+    #[doc = "Returns the kind of the anomaly."]
+    pub fn kind(&self) -> &AnomalyKind {
+        &self.kind
+    }
+}
+
+#[doc = "Checks all sequence tables of the package for duplicate sequence numbers, actions after InstallFinalize, missing or misordered standard actions and dialogs in execute sequences."]
+pub fn find_anomalies(package: &MsiPackage) -> Result<Vec<SequenceAnomaly>>
+{
+    let dialogs = dialog_names(package)?;
+
+    let mut anomalies = Vec::new();
+    for (table_name, required) in REQUIRED_ACTIONS.iter()
+    {
+        if !package.has_table(table_name)
+        {
+            continue;
+        }
+
+        let anomaly = |action: &str, sequence: Option<i32>, origin: Option<&RowOrigin>, kind: AnomalyKind| SequenceAnomaly {
+            table: table_name.to_string(),
+            action: action.to_string(),
+            sequence,
+            origin: origin.cloned(),
+            kind
+        };
+
+        let actions = read_actions(package, table_name)?;
+        let sequence_of = |name: &str| actions.iter()
+            .find(|scheduled| scheduled.action == name)
+            .and_then(|scheduled| scheduled.sequence.map(|sequence| (scheduled.origin(), sequence)));
+
+        // negative numbers are reserved for exit dialogs and may legitimately repeat
+        let mut by_sequence: BTreeMap<i32, &str> = BTreeMap::new();
+        for scheduled in &actions
+        {
+            if let Some(sequence) = scheduled.sequence.filter(|sequence| *sequence > 0)
+            {
+                if let Some(other) = by_sequence.insert(sequence, &scheduled.action)
+                {
+                    anomalies.push(anomaly(&scheduled.action, Some(sequence), Some(scheduled.origin()), AnomalyKind::DuplicateSequence(other.to_string())));
+                }
+            }
+        }
+
+        let is_execute = *table_name != "InstallUISequence";
+        if is_execute
+        {
+            if let Some((_, finalize)) = sequence_of("InstallFinalize")
+            {
+                for scheduled in &actions
+                {
+                    if let Some(sequence) = scheduled.sequence.filter(|sequence| *sequence > finalize)
+                    {
+                        anomalies.push(anomaly(&scheduled.action, Some(sequence), Some(scheduled.origin()), AnomalyKind::AfterInstallFinalize));
+                    }
+                }
+            }
+
+            for scheduled in &actions
+            {
+                if dialogs.contains(&scheduled.action)
+                {
+                    anomalies.push(anomaly(&scheduled.action, scheduled.sequence, Some(scheduled.origin()), AnomalyKind::DialogInExecuteSequence));
+                }
+            }
+        }
+
+        let mut previous: Option<(&str, i32)> = None;
+        for action in required.iter()
+        {
+            match sequence_of(action)
+            {
+                Some((origin, sequence)) => {
+                    if let Some((before, before_sequence)) = previous
+                    {
+                        if sequence <= before_sequence
+                        {
+                            anomalies.push(anomaly(action, Some(sequence), Some(origin), AnomalyKind::OutOfOrder(before.to_string())));
+                        }
+                    }
+
+                    previous = Some((action, sequence));
+                },
+                None => anomalies.push(anomaly(action, None, None, AnomalyKind::MissingStandardAction))
+            }
+        }
+    }
+
+    Ok(anomalies)
+}
+
+#[doc = "An action scheduled in both sequences under different conditions."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConditionChange {
+    action: String,
+    ui: Option<String>,
+    execute: Option<String>
+}
+
+impl ConditionChange {
+
+    #[doc = "Returns the name of the action."]
+    pub fn action(&self) -> &str {
+        &self.action
+    }
+
+    #[doc = "Returns the condition used in InstallUISequence."]
+    pub fn ui(&self) -> Option<&str> {
+        self.ui.as_deref()
+    }
+
+    #[doc = "Returns the condition used in InstallExecuteSequence."]
+    pub fn execute(&self) -> Option<&str> {
+        self.execute.as_deref()
+    }
+}
+
+#[doc = "Differences between InstallUISequence and InstallExecuteSequence. A silent install skips the UI sequence entirely, so anything listed in `ui_only` does not happen there."]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SequenceComparison {
+    ui_only: Vec<ScheduledAction>,
+    execute_only: Vec<ScheduledAction>,
+    condition_changes: Vec<ConditionChange>
+}
+
+impl SequenceComparison {
+
+    #[doc = "Returns the actions that only run with a UI. Dialogs, exit dialogs and ExecuteAction are left out since they are UI-only by design."]
+    pub fn ui_only(&self) -> &[ScheduledAction] {
+        &self.ui_only
+    }
+
+    #[doc = "Returns the actions that are only scheduled in the execute sequence."]
+    pub fn execute_only(&self) -> &[ScheduledAction] {
+        &self.execute_only
+    }
+
+    #[doc = "Returns the actions scheduled in both sequences with different conditions."]
+    pub fn condition_changes(&self) -> &[ConditionChange] {
+        &self.condition_changes
+    }
+
+    #[doc = "Returns a boolean value indicating whether UI and silent installs schedule the same work."]
+    pub fn is_consistent(&self) -> bool {
+        self.ui_only.is_empty() && self.condition_changes.is_empty()
+    }
+}
+
+#[doc = "Compares the UI and execute sequences of the package, action by action."]
+pub fn compare_ui_and_execute(package: &MsiPackage) -> Result<SequenceComparison>
+{
+    let dialogs = dialog_names(package)?;
+    let ui = read_actions(package, "InstallUISequence")?;
+    let execute = read_actions(package, "InstallExecuteSequence")?;
+    let execute_by_name: HashMap<&str, &ScheduledAction> = execute.iter().map(|scheduled| (scheduled.action(), scheduled)).collect();
+    let ui_names: HashSet<&str> = ui.iter().map(|scheduled| scheduled.action()).collect();
+
+    let mut comparison = SequenceComparison::default();
+    for scheduled in &ui
+    {
+        match execute_by_name.get(scheduled.action())
+        {
+            Some(other) => {
+                if scheduled.condition != other.condition
+                {
+                    comparison.condition_changes.push(ConditionChange {
+                        action: scheduled.action.clone(),
+                        ui: scheduled.condition.clone(),
+                        execute: other.condition.clone()
+                    });
+                }
+            },
+            None => {
+                let is_ui_only = dialogs.contains(&scheduled.action)
+                    || scheduled.action == "ExecuteAction"
+                    || scheduled.sequence.map(|sequence| sequence < 0).unwrap_or(false);
+                if !is_ui_only
+                {
+                    comparison.ui_only.push(scheduled.clone());
+                }
+            }
+        }
+    }
+
+    comparison.execute_only = execute.iter()
+        .filter(|scheduled| !ui_names.contains(scheduled.action()))
+        .cloned()
+        .collect();
+
+    Ok(comparison)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::testutil::TestPackage;
+
+    fn sequence_columns() -> Vec<msi::Column>
+    {
+        vec![
+            msi::Column::build("Action").primary_key().id_string(72),
+            msi::Column::build("Condition").nullable().string(255),
+            msi::Column::build("Sequence").nullable().int16()
+        ]
+    }
+
+    fn action(name: &str, sequence: i32) -> Vec<msi::Value>
+    {
+        vec![msi::Value::from(name), msi::Value::Null, msi::Value::Int(sequence)]
+    }
+
+    #[test]
+    fn test_read_sequence()
+    {
+        let package = TestPackage::new("sequence-table", |builder| {
+            builder.table("InstallUISequence", sequence_columns(), vec![
+                action("CostInitialize", 800),
+                vec![msi::Value::from("LaunchConditions"), msi::Value::from(" NOT Installed "), msi::Value::Int(100)],
+                action("ExecuteAction", 1300),
+                action("FatalErrorDlg", -3),
+                action("ExitDlg", -1),
+                action("Disabled", 0)
+            ]);
+        });
+
+        let package = MsiPackage::open(package.path()).unwrap();
+        let ui = SequenceTable::read(&package, Sequence::InstallUI).unwrap();
+        let names = |actions: &[ScheduledAction]| actions.iter().map(|action| action.action().to_string()).collect::<Vec<_>>();
+        assert_eq!(names(ui.actions()), ["LaunchConditions", "CostInitialize", "ExecuteAction"]);
+        assert_eq!(names(ui.terminations()), ["ExitDlg", "FatalErrorDlg"]);
+        assert_eq!(names(ui.inactive()), ["Disabled"]);
+        assert_eq!(ui.get("LaunchConditions").unwrap().condition(), Some("NOT Installed"));
+        assert_eq!(ui.sequence().to_string(), "InstallUISequence");
+        assert!(SequenceTable::read(&package, Sequence::AdvtExecute).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_find_anomalies()
+    {
+        let package = TestPackage::new("sequence-anomalies", |builder| {
+            builder.table("Dialog", vec![msi::Column::build("Dialog").primary_key().id_string(72)], vec![vec![msi::Value::from("WelcomeDlg")]]);
+            builder.table("InstallExecuteSequence", sequence_columns(), vec![
+                action("CostInitialize", 800),
+                action("FileCost", 900),
+                action("CostFinalize", 1000),
+                action("InstallValidate", 1400),
+                action("InstallInitialize", 1500),
+                action("InstallFiles", 4000),
+                action("WriteRegistryValues", 4000),
+                action("InstallFinalize", 6600),
+                action("LaunchApp", 6700),
+                action("WelcomeDlg", 2000)
+            ]);
+            builder.table("InstallUISequence", sequence_columns(), vec![
+                action("CostInitialize", 800),
+                action("CostFinalize", 700),
+                action("ExecuteAction", 1300)
+            ]);
+        });
+
+        let package = MsiPackage::open(package.path()).unwrap();
+        let anomalies = find_anomalies(&package).unwrap();
+        let has = |table: &str, action: &str, kind: &AnomalyKind| anomalies.iter()
+            .any(|anomaly| anomaly.table() == table && anomaly.action() == action && anomaly.kind() == kind);
+
+        assert_eq!(anomalies.len(), 5);
+        assert!(has("InstallExecuteSequence", "InstallFiles", &AnomalyKind::DuplicateSequence("WriteRegistryValues".to_string()))
+            || has("InstallExecuteSequence", "WriteRegistryValues", &AnomalyKind::DuplicateSequence("InstallFiles".to_string())));
+        assert!(has("InstallExecuteSequence", "LaunchApp", &AnomalyKind::AfterInstallFinalize));
+        assert!(has("InstallExecuteSequence", "WelcomeDlg", &AnomalyKind::DialogInExecuteSequence));
+        assert!(has("InstallUISequence", "FileCost", &AnomalyKind::MissingStandardAction));
+        assert!(has("InstallUISequence", "CostFinalize", &AnomalyKind::OutOfOrder("CostInitialize".to_string())));
+
+        let launch = anomalies.iter().find(|anomaly| anomaly.action() == "LaunchApp").unwrap();
+        assert_eq!(launch.sequence(), Some(6700));
+        assert!(launch.row().is_some());
+    }
+
+    #[test]
+    fn test_compare_ui_and_execute()
+    {
+        let conditional = |name: &str, condition: &str, sequence: i32| vec![msi::Value::from(name), msi::Value::from(condition), msi::Value::Int(sequence)];
+        let package = TestPackage::new("sequence-compare", |builder| {
+            builder.table("Dialog", vec![msi::Column::build("Dialog").primary_key().id_string(72)], vec![vec![msi::Value::from("WelcomeDlg")]]);
+            builder.table("InstallUISequence", sequence_columns(), vec![
+                action("AppSearch", 50),
+                conditional("LaunchConditions", "NOT Installed", 100),
+                action("SetInstallDir", 200),
+                action("WelcomeDlg", 1230),
+                action("ExecuteAction", 1300),
+                action("ExitDialog", -1)
+            ]);
+            builder.table("InstallExecuteSequence", sequence_columns(), vec![
+                action("AppSearch", 50),
+                action("LaunchConditions", 100),
+                action("InstallFiles", 4000)
+            ]);
+        });
+
+        let package = MsiPackage::open(package.path()).unwrap();
+        let comparison = compare_ui_and_execute(&package).unwrap();
+
+        assert!(!comparison.is_consistent());
+        assert_eq!(comparison.ui_only().len(), 1);
+        assert_eq!(comparison.ui_only()[0].action(), "SetInstallDir");
+        assert_eq!(comparison.execute_only().len(), 1);
+        assert_eq!(comparison.execute_only()[0].action(), "InstallFiles");
+        assert_eq!(comparison.condition_changes().len(), 1);
+        assert_eq!(comparison.condition_changes()[0].action(), "LaunchConditions");
+        assert_eq!(comparison.condition_changes()[0].ui(), Some("NOT Installed"));
+        assert_eq!(comparison.condition_changes()[0].execute(), None);
+    }
+}
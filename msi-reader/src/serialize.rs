@@ -0,0 +1,235 @@
+// Serde impls for the types whose derived form would not fit: names, GUIDs and versions serialize as
+// the text they are written as, cells as plain JSON-like values, and types borrowing from a table as maps.
+use std::convert::TryFrom;
+use std::fmt;
+use std::sync::Arc;
+
+use serde::de::{ self, Visitor };
+use serde::ser::{ SerializeMap, SerializeStruct };
+use serde::{ Deserialize, Deserializer, Serialize, Serializer };
+
+use crate::directory::{ MsiDirectoryName, MsiDirectoryNameBuf, MsiName, MsiNameBuf };
+use crate::guid::MsiGuid;
+use crate::table::{ Row, Table, TableSchema, Value };
+use crate::version::MsiVersion;
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self
+        {
+            Value::Null => serializer.serialize_none(),
+            Value::Int(value) => serializer.serialize_i32(*value),
+            Value::Str(value) => serializer.serialize_str(value)
+        }
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "null, a 32-bit integer or a string")
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Value, E> {
+        i32::try_from(value).map(Value::Int).map_err(|_| E::custom(format!("{} does not fit a 32-bit cell", value)))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Value, E> {
+        i32::try_from(value).map(Value::Int).map_err(|_| E::custom(format!("{} does not fit a 32-bit cell", value)))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Value, E> {
+        Ok(Value::Str(Arc::from(value)))
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+impl<'a> Serialize for Row<'a> {
+    #[doc = "Serializes the row as a map of column names to cells."]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    {
+        let columns = self.table().columns();
+        let mut map = serializer.serialize_map(Some(columns.len()))?;
+        for (column, value) in columns.iter().zip(self.values())
+        {
+            map.serialize_entry(column.name(), value)?;
+        }
+        map.end()
+    }
+}
+
+struct Rows<'a>(&'a Table);
+
+impl<'a> Serialize for Rows<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.rows())
+    }
+}
+
+impl Serialize for Table {
+    #[doc = "Serializes the table as its name, columns and rows in storage order."]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    {
+        let mut table = serializer.serialize_struct("Table", 3)?;
+        table.serialize_field("name", self.name())?;
+        table.serialize_field("columns", self.columns())?;
+        table.serialize_field("rows", &Rows(self))?;
+        table.end()
+    }
+}
+
+impl<'a> Serialize for TableSchema<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    {
+        let mut schema = serializer.serialize_struct("TableSchema", 2)?;
+        schema.serialize_field("name", self.name())?;
+        schema.serialize_field("columns", self.columns())?;
+        schema.end()
+    }
+}
+
+impl<'a> Serialize for MsiName<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.combined())
+    }
+}
+
+impl<'de: 'a, 'a> Deserialize<'de> for MsiName<'a> {
+    #[doc = "Borrows the name from the input, so it only deserializes from text without escapes; use `MsiNameBuf` otherwise."]
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<MsiName<'a>, D::Error> {
+        <&'a str>::deserialize(deserializer).map(MsiName::from)
+    }
+}
+
+impl<'a> Serialize for MsiDirectoryName<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.combined())
+    }
+}
+
+impl<'de: 'a, 'a> Deserialize<'de> for MsiDirectoryName<'a> {
+    #[doc = "Borrows the name from the input, so it only deserializes from text without escapes; use `MsiDirectoryNameBuf` otherwise."]
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<MsiDirectoryName<'a>, D::Error> {
+        <&'a str>::deserialize(deserializer).map(MsiDirectoryName::from)
+    }
+}
+
+impl Serialize for MsiNameBuf {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.combined())
+    }
+}
+
+impl<'de> Deserialize<'de> for MsiNameBuf {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<MsiNameBuf, D::Error> {
+        String::deserialize(deserializer).map(MsiNameBuf::from)
+    }
+}
+
+impl Serialize for MsiDirectoryNameBuf {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.combined())
+    }
+}
+
+impl<'de> Deserialize<'de> for MsiDirectoryNameBuf {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<MsiDirectoryNameBuf, D::Error> {
+        String::deserialize(deserializer).map(MsiDirectoryNameBuf::from)
+    }
+}
+
+impl Serialize for MsiGuid {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for MsiGuid {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<MsiGuid, D::Error> {
+        MsiGuid::parse(&String::deserialize(deserializer)?).map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for MsiVersion {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for MsiVersion {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<MsiVersion, D::Error> {
+        MsiVersion::parse(&String::deserialize(deserializer)?).map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::package::MsiPackage;
+    use crate::summary::SummaryInfo;
+    use crate::testutil::TestPackage;
+
+    #[test]
+    fn test_serialize_tables()
+    {
+        let package = TestPackage::new("serialize-tables", |builder| {
+            builder.table("Directory", vec![
+                msi::Column::build("Directory").primary_key().id_string(72),
+                msi::Column::build("Directory_Parent").nullable().id_string(72),
+                msi::Column::build("DefaultDir").text_string(255)
+            ], vec![
+                vec![msi::Value::from("TARGETDIR"), msi::Value::Null, msi::Value::from("SourceDir")]
+            ]);
+        });
+        let package = MsiPackage::open(package.path()).unwrap();
+
+        let table = package.table("Directory").unwrap();
+        assert_eq!(serde_json::to_string(&table.row(0)).unwrap(), r#"{"Directory":"TARGETDIR","Directory_Parent":null,"DefaultDir":"SourceDir"}"#);
+        let json = serde_json::to_value(&table).unwrap();
+        assert_eq!(json["rows"][0]["DefaultDir"], "SourceDir");
+        assert_eq!(json["columns"][0], serde_json::to_value(package.schema("Directory").unwrap().columns()[0].clone()).unwrap());
+        assert_eq!(serde_json::to_value(package.schema("Directory").unwrap()).unwrap()["name"], "Directory");
+
+        let summary: SummaryInfo = serde_json::from_str(&serde_json::to_string(package.summary()).unwrap()).unwrap();
+        assert_eq!(&summary, package.summary());
+    }
+
+    #[test]
+    fn test_serialize_values()
+    {
+        let values: Vec<Value> = serde_json::from_str(r#"[null, 42, "Alpha"]"#).unwrap();
+        assert_eq!(values, [Value::Null, Value::Int(42), Value::from("Alpha")]);
+        assert_eq!(serde_json::to_string(&values).unwrap(), r#"[null,42,"Alpha"]"#);
+        assert!(serde_json::from_str::<Value>("4294967296").is_err());
+
+        let name: MsiName = serde_json::from_str(r#""PROGRA~1|Program Files""#).unwrap();
+        assert_eq!(name.short(), Some("PROGRA~1"));
+        let directory: MsiDirectoryNameBuf = serde_json::from_str(r#""SRC:App""#).unwrap();
+        assert_eq!(serde_json::to_string(&directory.as_name()).unwrap(), r#""SRC:App""#);
+
+        let guid: MsiGuid = serde_json::from_str(r#""{8b3a5a8e-1f5c-4e4d-9c1b-2f0f3b7a6d10}""#).unwrap();
+        assert_eq!(serde_json::to_string(&guid).unwrap(), r#""{8B3A5A8E-1F5C-4E4D-9C1B-2F0F3B7A6D10}""#);
+        assert!(serde_json::from_str::<MsiVersion>(r#""1.300""#).is_err());
+    }
+}
@@ -0,0 +1,489 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use crate::error::Result;
+use crate::package::MsiPackage;
+use crate::table::RowOrigin;
+
+const SERVICE_KERNEL_DRIVER: i32 = 0x0001;
+const SERVICE_FILE_SYSTEM_DRIVER: i32 = 0x0002;
+const SERVICE_OWN_PROCESS: i32 = 0x0010;
+const SERVICE_SHARE_PROCESS: i32 = 0x0020;
+const SERVICE_INTERACTIVE: i32 = 0x0100;
+
+const ERROR_CONTROL_VITAL: i32 = 0x8000;
+
+const EVENT_START_INSTALL: i32 = 0x0001;
+const EVENT_STOP_INSTALL: i32 = 0x0002;
+const EVENT_DELETE_INSTALL: i32 = 0x0008;
+const EVENT_START_UNINSTALL: i32 = 0x0010;
+const EVENT_STOP_UNINSTALL: i32 = 0x0020;
+const EVENT_DELETE_UNINSTALL: i32 = 0x0080;
+
+const LIST_SEPARATOR: &str = "[~]";
+
+#[doc = "The ServiceType bit field of a ServiceInstall row."]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ServiceType(i32);
+
+impl ServiceType {
+
+    #[doc = "Returns the raw bits."]
+    pub fn bits(&self) -> i32 {
+        self.0
+    }
+
+    #[doc = "Returns a boolean value indicating whether the service runs in its own process."]
+    pub fn is_own_process(&self) -> bool {
+        self.0 & SERVICE_OWN_PROCESS != 0
+    }
+
+    #[doc = "Returns a boolean value indicating whether the service shares a process with other services."]
+    pub fn is_share_process(&self) -> bool {
+        self.0 & SERVICE_SHARE_PROCESS != 0
+    }
+
+    #[doc = "Returns a boolean value indicating whether the service can interact with the desktop."]
+    pub fn is_interactive(&self) -> bool {
+        self.0 & SERVICE_INTERACTIVE != 0
+    }
+
+    #[doc = "Returns a boolean value indicating whether the service is a kernel or file system driver, which Windows Installer does not support installing."]
+    pub fn is_driver(&self) -> bool {
+        self.0 & (SERVICE_KERNEL_DRIVER | SERVICE_FILE_SYSTEM_DRIVER) != 0
+    }
+}
+
+impl From<i32> for ServiceType {
+    fn from(bits: i32) -> Self {
+        ServiceType(bits)
+    }
+}
+
+#[doc = "When a service is started."]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StartType {
+    #[doc = "0: by the boot loader; drivers only."]
+    Boot,
+    #[doc = "1: by the kernel during initialization; drivers only."]
+    System,
+    #[doc = "2: automatically when the system starts."]
+    Auto,
+    #[doc = "3: when a process asks the service control manager to start it."]
+    Demand,
+    #[doc = "4: never."]
+    Disabled,
+    Unknown(i32)
+}
+
+impl From<i32> for StartType {
+    fn from(value: i32) -> Self
+    {
+        match value
+        {
+            0 => StartType::Boot,
+            1 => StartType::System,
+            2 => StartType::Auto,
+            3 => StartType::Demand,
+            4 => StartType::Disabled,
+            _ => StartType::Unknown(value)
+        }
+    }
+}
+
+impl Display for StartType {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self
+        {
+            StartType::Boot => write!(fmt, "boot"),
+            StartType::System => write!(fmt, "system"),
+            StartType::Auto => write!(fmt, "auto"),
+            StartType::Demand => write!(fmt, "demand"),
+            StartType::Disabled => write!(fmt, "disabled"),
+            StartType::Unknown(value) => write!(fmt, "unknown ({})", value)
+        }
+    }
+}
+
+#[doc = "What happens when a service fails to start during system startup."]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ErrorControl {
+    #[doc = "0: the error is logged and startup continues."]
+    Ignore,
+    #[doc = "1: the error is logged and a message is shown."]
+    Normal,
+    #[doc = "3: the error is logged and the system restarts with the last known good configuration."]
+    Critical,
+    Unknown(i32)
+}
+
+impl Display for ErrorControl {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self
+        {
+            ErrorControl::Ignore => write!(fmt, "ignore"),
+            ErrorControl::Normal => write!(fmt, "normal"),
+            ErrorControl::Critical => write!(fmt, "critical"),
+            ErrorControl::Unknown(value) => write!(fmt, "unknown ({})", value)
+        }
+    }
+}
+
+#[doc = "A service or load ordering group a service depends on."]
+#[derive(Clone, Debug, PartialEq)]
+pub enum ServiceDependency {
+    #[doc = "A service, by name."]
+    Service(String),
+    #[doc = "A load ordering group, written with a `+` prefix; at least one of its members has to be running."]
+    Group(String)
+}
+
+#[doc = "A row of the ServiceInstall table."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ServiceInstallRow {
+    service_install: String,
+    name: String,
+    display_name: Option<String>,
+    service_type: ServiceType,
+    start_type: i32,
+    error_control: i32,
+    load_order_group: Option<String>,
+    dependencies: Option<String>,
+    start_name: Option<String>,
+    has_password: bool,
+    arguments: Option<String>,
+    component: String,
+    description: Option<String>,
+    origin: RowOrigin
+}
+
+impl ServiceInstallRow {
+
+    #[doc = "Returns the primary key of the row."]
+    pub fn service_install(&self) -> &str {
+        &self.service_install
+    }
+
+    #[doc = "Returns the name the service is registered under."]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[doc = "Returns the name shown in the Services console."]
+    pub fn display_name(&self) -> Option<&str> {
+        self.display_name.as_deref()
+    }
+
+    #[doc = "Returns the type of the service."]
+    pub fn service_type(&self) -> ServiceType {
+        self.service_type
+    }
+
+    #[doc = "Returns when the service is started."]
+    pub fn start_type(&self) -> StartType {
+        StartType::from(self.start_type)
+    }
+
+    #[doc = "Returns what happens when the service fails to start."]
+    pub fn error_control(&self) -> ErrorControl {
+        match self.error_control & !ERROR_CONTROL_VITAL
+        {
+            0 => ErrorControl::Ignore,
+            1 => ErrorControl::Normal,
+            3 => ErrorControl::Critical,
+            value => ErrorControl::Unknown(value)
+        }
+    }
+
+    #[doc = "Returns a boolean value indicating whether the installation fails if the service cannot be installed."]
+    pub fn is_vital(&self) -> bool {
+        self.error_control & ERROR_CONTROL_VITAL != 0
+    }
+
+    #[doc = "Returns the load ordering group the service belongs to."]
+    pub fn load_order_group(&self) -> Option<&str> {
+        self.load_order_group.as_deref()
+    }
+
+    #[doc = "Returns the services and groups that must run before the service starts."]
+    pub fn dependencies(&self) -> Vec<ServiceDependency> {
+        self.dependencies.as_deref().unwrap_or_default()
+            .split(LIST_SEPARATOR)
+            .filter(|dependency| !dependency.is_empty())
+            .map(|dependency| match dependency.strip_prefix('+')
+            {
+                Some(group) => ServiceDependency::Group(group.to_string()),
+                None => ServiceDependency::Service(dependency.to_string())
+            })
+            .collect()
+    }
+
+    #[doc = "Returns the account the service runs as; `None` means LocalSystem."]
+    pub fn start_name(&self) -> Option<&str> {
+        self.start_name.as_deref()
+    }
+
+    #[doc = "Returns a boolean value indicating whether the row stores a password for the account. The password itself is not kept."]
+    pub fn has_password(&self) -> bool {
+        self.has_password
+    }
+
+    #[doc = "Returns the command line arguments passed when the service starts."]
+    pub fn arguments(&self) -> Option<&str> {
+        self.arguments.as_deref()
+    }
+
+    #[doc = "Returns the component whose key file is the service executable."]
+    pub fn component(&self) -> &str {
+        &self.component
+    }
+
+    #[doc = "Returns the description of the service."]
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    #[doc = "Returns the ServiceInstall row the service was read from."]
+    pub fn origin(&self) -> &RowOrigin {
+        &self.origin
+    }
+}
+
+#[doc = "The Event bit field of a ServiceControl row."]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ControlEvents(i32);
+
+impl ControlEvents {
+
+    #[doc = "Returns the raw bits."]
+    pub fn bits(&self) -> i32 {
+        self.0
+    }
+
+    #[doc = "Returns a boolean value indicating whether the service is started on install."]
+    pub fn starts_on_install(&self) -> bool {
+        self.0 & EVENT_START_INSTALL != 0
+    }
+
+    #[doc = "Returns a boolean value indicating whether the service is stopped on install."]
+    pub fn stops_on_install(&self) -> bool {
+        self.0 & EVENT_STOP_INSTALL != 0
+    }
+
+    #[doc = "Returns a boolean value indicating whether the service is deleted on install."]
+    pub fn deletes_on_install(&self) -> bool {
+        self.0 & EVENT_DELETE_INSTALL != 0
+    }
+
+    #[doc = "Returns a boolean value indicating whether the service is started on uninstall."]
+    pub fn starts_on_uninstall(&self) -> bool {
+        self.0 & EVENT_START_UNINSTALL != 0
+    }
+
+    #[doc = "Returns a boolean value indicating whether the service is stopped on uninstall."]
+    pub fn stops_on_uninstall(&self) -> bool {
+        self.0 & EVENT_STOP_UNINSTALL != 0
+    }
+
+    #[doc = "Returns a boolean value indicating whether the service is deleted on uninstall."]
+    pub fn deletes_on_uninstall(&self) -> bool {
+        self.0 & EVENT_DELETE_UNINSTALL != 0
+    }
+}
+
+impl From<i32> for ControlEvents {
+    fn from(bits: i32) -> Self {
+        ControlEvents(bits)
+    }
+}
+
+#[doc = "A row of the ServiceControl table."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ServiceControlRow {
+    service_control: String,
+    name: String,
+    events: ControlEvents,
+    arguments: Option<String>,
+    wait: Option<i32>,
+    component: String,
+    origin: RowOrigin
+}
+
+impl ServiceControlRow {
+
+    #[doc = "Returns the primary key of the row."]
+    pub fn service_control(&self) -> &str {
+        &self.service_control
+    }
+
+    #[doc = "Returns the name of the controlled service, which need not be installed by the package."]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[doc = "Returns the events the service is controlled on."]
+    pub fn events(&self) -> ControlEvents {
+        self.events
+    }
+
+    #[doc = "Returns the arguments passed when the service is started, split on `[~]`."]
+    pub fn arguments(&self) -> Vec<&str> {
+        self.arguments.as_deref().unwrap_or_default().split(LIST_SEPARATOR).filter(|argument| !argument.is_empty()).collect()
+    }
+
+    #[doc = "Returns a boolean value indicating whether the installer waits up to 30 seconds for the service to reach the requested state; an empty Wait column waits."]
+    pub fn waits(&self) -> bool {
+        self.wait != Some(0)
+    }
+
+    #[doc = "Returns the component whose install state triggers the events."]
+    pub fn component(&self) -> &str {
+        &self.component
+    }
+
+    #[doc = "Returns the ServiceControl row the entry was read from."]
+    pub fn origin(&self) -> &RowOrigin {
+        &self.origin
+    }
+}
+
+#[doc = "The typed rows of the ServiceInstall and ServiceControl tables, in storage order."]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ServiceTables {
+    installs: Vec<ServiceInstallRow>,
+    controls: Vec<ServiceControlRow>,
+    index: HashMap<String, usize>
+}
+
+impl ServiceTables {
+
+    #[doc = "Reads both service tables of the package; missing tables yield no rows."]
+    pub fn read(package: &MsiPackage) -> Result<ServiceTables>
+    {
+        let installs: Vec<ServiceInstallRow> = match package.optional_table("ServiceInstall")?
+        {
+            Some(table) => table.rows()
+                .map(|row| ServiceInstallRow {
+                    service_install: row.str("ServiceInstall").unwrap_or_default().to_string(),
+                    name: row.str("Name").unwrap_or_default().to_string(),
+                    display_name: row.str("DisplayName").map(|name| name.to_string()),
+                    service_type: ServiceType(row.int("ServiceType").unwrap_or(0)),
+                    start_type: row.int("StartType").unwrap_or(0),
+                    error_control: row.int("ErrorControl").unwrap_or(0),
+                    load_order_group: row.str("LoadOrderGroup").map(|group| group.to_string()),
+                    dependencies: row.str("Dependencies").map(|dependencies| dependencies.to_string()),
+                    start_name: row.str("StartName").filter(|name| !name.is_empty()).map(|name| name.to_string()),
+                    has_password: row.str("Password").is_some_and(|password| !password.is_empty()),
+                    arguments: row.str("Arguments").map(|arguments| arguments.to_string()),
+                    component: row.str("Component_").unwrap_or_default().to_string(),
+                    description: row.str("Description").map(|description| description.to_string()),
+                    origin: row.origin()
+                })
+                .collect(),
+            None => Vec::new()
+        };
+        let controls = match package.optional_table("ServiceControl")?
+        {
+            Some(table) => table.rows()
+                .map(|row| ServiceControlRow {
+                    service_control: row.str("ServiceControl").unwrap_or_default().to_string(),
+                    name: row.str("Name").unwrap_or_default().to_string(),
+                    events: ControlEvents(row.int("Event").unwrap_or(0)),
+                    arguments: row.str("Arguments").map(|arguments| arguments.to_string()),
+                    wait: row.int("Wait"),
+                    component: row.str("Component_").unwrap_or_default().to_string(),
+                    origin: row.origin()
+                })
+                .collect(),
+            None => Vec::new()
+        };
+        let index = installs.iter().enumerate().map(|(index, row)| (row.service_install.clone(), index)).collect();
+
+        Ok(ServiceTables {
+            installs,
+            controls,
+            index
+        })
+    }
+
+    #[doc = "Returns the services the package installs."]
+    pub fn installs(&self) -> &[ServiceInstallRow] {
+        &self.installs
+    }
+
+    #[doc = "Returns the ServiceInstall row with the given key."]
+    pub fn install(&self, service_install: &str) -> Option<&ServiceInstallRow> {
+        self.index.get(service_install).map(|index| &self.installs[*index])
+    }
+
+    #[doc = "Returns the services the package starts, stops or deletes."]
+    pub fn controls(&self) -> &[ServiceControlRow] {
+        &self.controls
+    }
+
+    #[doc = "Returns the controls of the service with the given name, compared case-insensitively like the service control manager does."]
+    pub fn controls_of<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a ServiceControlRow> {
+        self.controls.iter().filter(move |row| row.name.eq_ignore_ascii_case(name))
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::testutil::TestPackage;
+
+    #[test]
+    fn test_read_services()
+    {
+        let package = TestPackage::new("service-tables", |builder| {
+            builder.table("ServiceInstall", vec![
+                msi::Column::build("ServiceInstall").primary_key().id_string(72),
+                msi::Column::build("Name").formatted_string(255),
+                msi::Column::build("DisplayName").nullable().formatted_string(255),
+                msi::Column::build("ServiceType").int32(),
+                msi::Column::build("StartType").int32(),
+                msi::Column::build("ErrorControl").int32(),
+                msi::Column::build("LoadOrderGroup").nullable().formatted_string(255),
+                msi::Column::build("Dependencies").nullable().formatted_string(255),
+                msi::Column::build("StartName").nullable().formatted_string(255),
+                msi::Column::build("Password").nullable().formatted_string(255),
+                msi::Column::build("Arguments").nullable().formatted_string(255),
+                msi::Column::build("Component_").id_string(72),
+                msi::Column::build("Description").nullable().formatted_string(255)
+            ], vec![
+                vec![msi::Value::from("Agent"), msi::Value::from("ContosoAgent"), msi::Value::from("Contoso Agent"), msi::Value::Int(0x110),
+                    msi::Value::Int(2), msi::Value::Int(0x8001), msi::Value::Null, msi::Value::from("RpcSs[~]+NetworkProvider[~][~]"),
+                    msi::Value::from(".\\svc"), msi::Value::from("secret"), msi::Value::Null, msi::Value::from("Main"), msi::Value::Null]
+            ]);
+            builder.table("ServiceControl", vec![
+                msi::Column::build("ServiceControl").primary_key().id_string(72),
+                msi::Column::build("Name").formatted_string(255),
+                msi::Column::build("Event").int16(),
+                msi::Column::build("Arguments").nullable().formatted_string(255),
+                msi::Column::build("Wait").nullable().int16(),
+                msi::Column::build("Component_").id_string(72)
+            ], vec![
+                vec![msi::Value::from("StartAgent"), msi::Value::from("contosoagent"), msi::Value::Int(0xa1), msi::Value::from("-a[~]-b"), msi::Value::Null, msi::Value::from("Main")],
+                vec![msi::Value::from("StopSpooler"), msi::Value::from("Spooler"), msi::Value::Int(0x2), msi::Value::Null, msi::Value::Int(0), msi::Value::from("Main")]
+            ]);
+        });
+
+        let services = ServiceTables::read(&MsiPackage::open(package.path()).unwrap()).unwrap();
+        let agent = services.install("Agent").unwrap();
+        assert!(agent.service_type().is_own_process() && agent.service_type().is_interactive() && !agent.service_type().is_driver());
+        assert_eq!((agent.start_type(), agent.error_control(), agent.is_vital()), (StartType::Auto, ErrorControl::Normal, true));
+        assert_eq!(agent.dependencies(), [ServiceDependency::Service("RpcSs".to_string()), ServiceDependency::Group("NetworkProvider".to_string())]);
+        assert_eq!((agent.start_name(), agent.has_password()), (Some(".\\svc"), true));
+
+        let controls: Vec<&ServiceControlRow> = services.controls_of(agent.name()).collect();
+        assert_eq!(controls.len(), 1);
+        let events = controls[0].events();
+        assert!(events.starts_on_install() && events.stops_on_uninstall() && events.deletes_on_uninstall());
+        assert!(!events.stops_on_install() && !events.deletes_on_install() && !events.starts_on_uninstall());
+        assert_eq!((controls[0].arguments(), controls[0].waits()), (vec!["-a", "-b"], true));
+
+        let spooler = services.controls_of("Spooler").next().unwrap();
+        assert!(spooler.events().stops_on_install() && !spooler.waits());
+    }
+}
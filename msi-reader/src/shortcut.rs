@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+
+use crate::directory::MsiName;
+use crate::error::Result;
+use crate::layout::DirectoryResolver;
+use crate::package::MsiPackage;
+use crate::table::RowOrigin;
+
+#[doc = "What a shortcut points to."]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShortcutTarget<'a> {
+    #[doc = "An advertised shortcut, which installs the given feature on first use and starts the key path of its component."]
+    Advertised(&'a str),
+    #[doc = "A formatted path such as `[#App.exe]` or `[INSTALLDIR]readme.txt`, resolved when the shortcut is created."]
+    Formatted(&'a str)
+}
+
+#[doc = "A row of the Shortcut table."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShortcutRow {
+    shortcut: String,
+    directory: String,
+    directory_path: Option<String>,
+    name: String,
+    component: String,
+    target: String,
+    arguments: Option<String>,
+    description: Option<String>,
+    icon: Option<String>,
+    working_directory: Option<String>,
+    origin: RowOrigin
+}
+
+impl ShortcutRow {
+
+    #[doc = "Returns the primary key of the shortcut."]
+    pub fn shortcut(&self) -> &str {
+        &self.shortcut
+    }
+
+    #[doc = "Returns the key of the directory the shortcut is created in."]
+    pub fn directory(&self) -> &str {
+        &self.directory
+    }
+
+    #[doc = "Returns the full target path of the directory the shortcut is created in, e.g. `C:\\ProgramData\\Microsoft\\Windows\\Start Menu\\Programs\\Contoso\\`, or `None` if it does not resolve."]
+    pub fn directory_path(&self) -> Option<&str> {
+        self.directory_path.as_deref()
+    }
+
+    #[doc = "Returns the name of the shortcut, split into its optional short and long parts."]
+    pub fn name(&self) -> MsiName<'_> {
+        MsiName::from(self.name.as_str())
+    }
+
+    #[doc = "Returns the component that installs the shortcut."]
+    pub fn component(&self) -> &str {
+        &self.component
+    }
+
+    #[doc = "Returns what the shortcut points to. Formatted targets always contain a bracketed reference; anything else is the key of a feature."]
+    pub fn target(&self) -> ShortcutTarget<'_> {
+        if self.target.contains('[')
+        {
+            ShortcutTarget::Formatted(&self.target)
+        }
+        else
+        {
+            ShortcutTarget::Advertised(&self.target)
+        }
+    }
+
+    #[doc = "Returns a boolean value indicating whether the shortcut is advertised."]
+    pub fn is_advertised(&self) -> bool {
+        matches!(self.target(), ShortcutTarget::Advertised(_))
+    }
+
+    #[doc = "Returns the command line arguments passed to the target."]
+    pub fn arguments(&self) -> Option<&str> {
+        self.arguments.as_deref()
+    }
+
+    #[doc = "Returns the description of the shortcut."]
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    #[doc = "Returns the key of the Icon row used by the shortcut."]
+    pub fn icon(&self) -> Option<&str> {
+        self.icon.as_deref()
+    }
+
+    #[doc = "Returns the property or Directory key naming the working directory."]
+    pub fn working_directory(&self) -> Option<&str> {
+        self.working_directory.as_deref()
+    }
+
+    #[doc = "Returns the Shortcut row the shortcut was read from."]
+    pub fn origin(&self) -> &RowOrigin {
+        &self.origin
+    }
+}
+
+#[doc = "The typed rows of the Shortcut table, in storage order, with their directories resolved."]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ShortcutTable {
+    rows: Vec<ShortcutRow>,
+    index: HashMap<String, usize>
+}
+
+impl ShortcutTable {
+
+    #[doc = "Reads the Shortcut table of the package; a package without one yields an empty table."]
+    pub fn read(package: &MsiPackage) -> Result<ShortcutTable>
+    {
+        let table = match package.optional_table("Shortcut")?
+        {
+            Some(table) => table,
+            None => return Ok(ShortcutTable::default())
+        };
+
+        let directories = DirectoryResolver::read(package)?;
+        let rows: Vec<ShortcutRow> = table.rows()
+            .map(|row| {
+                let directory = row.str("Directory_").unwrap_or_default();
+                ShortcutRow {
+                    shortcut: row.str("Shortcut").unwrap_or_default().to_string(),
+                    directory: directory.to_string(),
+                    directory_path: directories.target_path(directory),
+                    name: row.str("Name").unwrap_or_default().to_string(),
+                    component: row.str("Component_").unwrap_or_default().to_string(),
+                    target: row.str("Target").unwrap_or_default().to_string(),
+                    arguments: row.str("Arguments").filter(|arguments| !arguments.is_empty()).map(|arguments| arguments.to_string()),
+                    description: row.str("Description").map(|description| description.to_string()),
+                    icon: row.str("Icon_").map(|icon| icon.to_string()),
+                    working_directory: row.str("WkDir").map(|directory| directory.to_string()),
+                    origin: row.origin()
+                }
+            })
+            .collect();
+        let index = rows.iter().enumerate().map(|(index, row)| (row.shortcut.clone(), index)).collect();
+
+        Ok(ShortcutTable {
+            rows,
+            index
+        })
+    }
+
+    #[doc = "Returns all shortcuts in storage order."]
+    pub fn rows(&self) -> &[ShortcutRow] {
+        &self.rows
+    }
+
+    #[doc = "Returns the shortcut with the given key."]
+    pub fn get(&self, shortcut: &str) -> Option<&ShortcutRow> {
+        self.index.get(shortcut).map(|index| &self.rows[*index])
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::directory::NameFormat;
+    use crate::testutil::TestPackage;
+
+    #[test]
+    fn test_read_shortcuts()
+    {
+        let package = TestPackage::new("shortcut-table", |builder| {
+            builder.table("Shortcut", vec![
+                msi::Column::build("Shortcut").primary_key().id_string(72),
+                msi::Column::build("Directory_").id_string(72),
+                msi::Column::build("Name").localizable().category(msi::Category::Filename).string(128),
+                msi::Column::build("Component_").id_string(72),
+                msi::Column::build("Target").category(msi::Category::Shortcut).string(72),
+                msi::Column::build("Arguments").nullable().formatted_string(255),
+                msi::Column::build("Description").nullable().localizable().text_string(255),
+                msi::Column::build("Icon_").nullable().id_string(72),
+                msi::Column::build("WkDir").nullable().id_string(72)
+            ], vec![
+                vec![msi::Value::from("AppMenu"), msi::Value::from("MenuDir"), msi::Value::from("APP~1.LNK|Contoso App"), msi::Value::from("Main"),
+                    msi::Value::from("Complete"), msi::Value::Null, msi::Value::from("Starts the app"), msi::Value::from("App.ico"), msi::Value::Null],
+                vec![msi::Value::from("Readme"), msi::Value::from("Missing"), msi::Value::from("Readme"), msi::Value::from("Main"),
+                    msi::Value::from("[#Readme.txt]"), msi::Value::from("/view"), msi::Value::Null, msi::Value::Null, msi::Value::from("INSTALLDIR")]
+            ]);
+            builder.table("Directory", vec![
+                msi::Column::build("Directory").primary_key().id_string(72),
+                msi::Column::build("Directory_Parent").nullable().id_string(72),
+                msi::Column::build("DefaultDir").category(msi::Category::DefaultDir).string(255)
+            ], vec![
+                vec![msi::Value::from("TARGETDIR"), msi::Value::Null, msi::Value::from("SourceDir")],
+                vec![msi::Value::from("ProgramMenuFolder"), msi::Value::from("TARGETDIR"), msi::Value::from(".")],
+                vec![msi::Value::from("MenuDir"), msi::Value::from("ProgramMenuFolder"), msi::Value::from("Contoso")]
+            ]);
+        });
+
+        let shortcuts = ShortcutTable::read(&MsiPackage::open(package.path()).unwrap()).unwrap();
+        let menu = shortcuts.get("AppMenu").unwrap();
+        assert_eq!(menu.target(), ShortcutTarget::Advertised("Complete"));
+        assert!(menu.is_advertised());
+        assert_eq!(menu.name().format(NameFormat::Long), "Contoso App");
+        assert_eq!(menu.directory_path(), Some("C:\\ProgramData\\Microsoft\\Windows\\Start Menu\\Programs\\Contoso\\"));
+        assert_eq!((menu.description(), menu.icon()), (Some("Starts the app"), Some("App.ico")));
+
+        let readme = shortcuts.get("Readme").unwrap();
+        assert_eq!(readme.target(), ShortcutTarget::Formatted("[#Readme.txt]"));
+        assert_eq!((readme.directory_path(), readme.arguments(), readme.working_directory()), (None, Some("/view"), Some("INSTALLDIR")));
+        assert_eq!(shortcuts.rows().len(), 2);
+    }
+}
@@ -0,0 +1,406 @@
+use std::collections::{ BTreeMap, BTreeSet };
+
+use crate::bytes::ByteReader;
+use crate::digest::Digest;
+use crate::error::{ Error, Result };
+use crate::package::MsiPackage;
+
+const MAGIC: &[u8; 8] = b"MSISNAP1";
+
+// Properties that identify a product; the PackageCode comes from the summary information.
+const IDENTITY_PROPERTIES: [&str; 6] = ["ProductCode", "ProductVersion", "UpgradeCode", "ProductName", "Manufacturer", "ProductLanguage"];
+const PACKAGE_CODE: &str = "PackageCode";
+
+#[doc = "The content hash of a table and the hash of every row by primary key."]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TableSnapshot {
+    content_hash: u64,
+    rows: BTreeMap<String, u64>
+}
+
+impl TableSnapshot {
+
+    #[doc = "Returns the order-independent hash of the whole table."]
+    pub fn content_hash(&self) -> u64 {
+        self.content_hash
+    }
+
+    #[doc = "Returns the hash of every row by primary key, with multiple key columns joined by '.'."]
+    pub fn rows(&self) -> &BTreeMap<String, u64> {
+        &self.rows
+    }
+}
+
+#[doc = "A compact summary of a package: its identity, the hashes of its tables and rows and the digests of its other streams. It can be stored with `to_bytes` and compared with later snapshots without keeping either database open."]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PackageSnapshot {
+    identity: BTreeMap<String, String>,
+    tables: BTreeMap<String, TableSnapshot>,
+    streams: BTreeMap<String, Digest>
+}
+
+impl PackageSnapshot {
+
+    #[doc = "Captures the identity properties, the PackageCode, a hash of every table row and the digest of every non-table stream of the package."]
+    pub fn capture(package: &MsiPackage) -> Result<PackageSnapshot>
+    {
+        let mut snapshot = PackageSnapshot::default();
+        if let Some(table) = package.optional_table("Property")?
+        {
+            for row in table.rows()
+            {
+                if let (Some(name), Some(value)) = (row.str("Property"), row.str("Value"))
+                {
+                    if IDENTITY_PROPERTIES.contains(&name)
+                    {
+                        snapshot.identity.insert(name.to_string(), value.to_string());
+                    }
+                }
+            }
+        }
+        if let Some(revision) = package.summary().revision()
+        {
+            snapshot.identity.insert(PACKAGE_CODE.to_string(), revision.to_string());
+        }
+
+        for name in package.table_names()
+        {
+            let table = package.table(name)?;
+            snapshot.tables.insert(name.to_string(), TableSnapshot {
+                content_hash: table.content_hash(),
+                rows: table.rows().map(|row| (row.key(), row.content_hash())).collect()
+            });
+        }
+
+        snapshot.streams = package.digests()?.streams().iter()
+            .map(|(name, digest)| (name.clone(), digest.digest()))
+            .collect();
+        Ok(snapshot)
+    }
+
+    #[doc = "Returns the identity properties (ProductCode, ProductVersion, UpgradeCode, ProductName, Manufacturer, ProductLanguage and PackageCode) present in the package."]
+    pub fn identity(&self) -> &BTreeMap<String, String> {
+        &self.identity
+    }
+
+    #[doc = "Returns the snapshots of all tables by name."]
+    pub fn tables(&self) -> &BTreeMap<String, TableSnapshot> {
+        &self.tables
+    }
+
+    #[doc = "Returns the digests of the streams that are not tables, by name."]
+    pub fn streams(&self) -> &BTreeMap<String, Digest> {
+        &self.streams
+    }
+
+    #[doc = "Returns the semantic changes from `old` to `new`. Tables with identical content hashes are skipped without comparing their rows."]
+    pub fn compare(old: &PackageSnapshot, new: &PackageSnapshot) -> ChangeSet
+    {
+        let mut changes = ChangeSet::default();
+        let names: BTreeSet<&String> = old.identity.keys().chain(new.identity.keys()).collect();
+        for name in names
+        {
+            let (old_value, new_value) = (old.identity.get(name), new.identity.get(name));
+            if old_value != new_value
+            {
+                changes.identity.push(IdentityChange {
+                    name: name.clone(),
+                    old_value: old_value.cloned(),
+                    new_value: new_value.cloned()
+                });
+            }
+        }
+
+        changes.added_tables = added(&old.tables, &new.tables);
+        changes.removed_tables = added(&new.tables, &old.tables);
+        for (name, old_table) in &old.tables
+        {
+            let new_table = match new.tables.get(name)
+            {
+                Some(table) if table.content_hash != old_table.content_hash => table,
+                _ => continue
+            };
+
+            changes.tables.push(TableChanges {
+                table: name.clone(),
+                added: added(&old_table.rows, &new_table.rows),
+                removed: added(&new_table.rows, &old_table.rows),
+                modified: modified(&old_table.rows, &new_table.rows)
+            });
+        }
+
+        changes.added_streams = added(&old.streams, &new.streams);
+        changes.removed_streams = added(&new.streams, &old.streams);
+        changes.modified_streams = modified(&old.streams, &new.streams);
+        changes
+    }
+
+    #[doc = "Serializes the snapshot into a compact binary form that `from_bytes` reads back."]
+    pub fn to_bytes(&self) -> Vec<u8>
+    {
+        let mut output = MAGIC.to_vec();
+        write_u32(&mut output, self.identity.len());
+        for (name, value) in &self.identity
+        {
+            write_string(&mut output, name);
+            write_string(&mut output, value);
+        }
+
+        write_u32(&mut output, self.tables.len());
+        for (name, table) in &self.tables
+        {
+            write_string(&mut output, name);
+            output.extend_from_slice(&table.content_hash.to_le_bytes());
+            write_u32(&mut output, table.rows.len());
+            for (key, hash) in &table.rows
+            {
+                write_string(&mut output, key);
+                output.extend_from_slice(&hash.to_le_bytes());
+            }
+        }
+
+        write_u32(&mut output, self.streams.len());
+        for (name, digest) in &self.streams
+        {
+            write_string(&mut output, name);
+            output.extend_from_slice(digest.as_bytes());
+        }
+
+        output
+    }
+
+    #[doc = "Reads a snapshot written by `to_bytes`."]
+    pub fn from_bytes(data: &[u8]) -> Result<PackageSnapshot>
+    {
+        let mut reader = ByteReader::new(data);
+        if reader.read_bytes(MAGIC.len())? != MAGIC
+        {
+            return Err(Error::invalid("the data is not a package snapshot"));
+        }
+
+        let mut snapshot = PackageSnapshot::default();
+        for _ in 0..reader.read_u32()?
+        {
+            let name = read_string(&mut reader)?;
+            snapshot.identity.insert(name, read_string(&mut reader)?);
+        }
+
+        for _ in 0..reader.read_u32()?
+        {
+            let name = read_string(&mut reader)?;
+            let mut table = TableSnapshot {
+                content_hash: reader.read_u64()?,
+                rows: BTreeMap::new()
+            };
+            for _ in 0..reader.read_u32()?
+            {
+                let key = read_string(&mut reader)?;
+                table.rows.insert(key, reader.read_u64()?);
+            }
+            snapshot.tables.insert(name, table);
+        }
+
+        for _ in 0..reader.read_u32()?
+        {
+            let name = read_string(&mut reader)?;
+            let mut digest = [0u8; 32];
+            digest.copy_from_slice(reader.read_bytes(32)?);
+            snapshot.streams.insert(name, Digest::from_bytes(digest));
+        }
+
+        if reader.remaining() > 0
+        {
+            return Err(Error::invalid(format!("{} unexpected bytes after the package snapshot", reader.remaining())));
+        }
+
+        Ok(snapshot)
+    }
+}
+
+#[doc = "An identity property whose value differs between two snapshots."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct IdentityChange {
+    name: String,
+    old_value: Option<String>,
+    new_value: Option<String>
+}
+
+impl IdentityChange {
+
+    #[doc = "Returns the name of the property, e.g. `ProductVersion` or `PackageCode`."]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[doc = "Returns the value in the old snapshot, if it was set."]
+    pub fn old_value(&self) -> Option<&str> {
+        self.old_value.as_deref()
+    }
+
+    #[doc = "Returns the value in the new snapshot, if it is set."]
+    pub fn new_value(&self) -> Option<&str> {
+        self.new_value.as_deref()
+    }
+}
+
+#[doc = "The rows of a table that were added, removed or modified between two snapshots, by primary key."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct TableChanges {
+    table: String,
+    added: Vec<String>,
+    removed: Vec<String>,
+    modified: Vec<String>
+}
+
+impl TableChanges {
+
+    #[doc = "Returns the name of the table."]
+    pub fn table(&self) -> &str {
+        &self.table
+    }
+
+    #[doc = "Returns the keys of rows only present in the new snapshot."]
+    pub fn added(&self) -> &[String] {
+        &self.added
+    }
+
+    #[doc = "Returns the keys of rows only present in the old snapshot."]
+    pub fn removed(&self) -> &[String] {
+        &self.removed
+    }
+
+    #[doc = "Returns the keys of rows present in both snapshots with different content."]
+    pub fn modified(&self) -> &[String] {
+        &self.modified
+    }
+}
+
+#[doc = "The semantic changes between two snapshots: identity, tables, rows by primary key and non-table streams. Unlike `PackageDiff`, it names changed rows but not the changed cells, which a snapshot does not keep."]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ChangeSet {
+    identity: Vec<IdentityChange>,
+    added_tables: Vec<String>,
+    removed_tables: Vec<String>,
+    tables: Vec<TableChanges>,
+    added_streams: Vec<String>,
+    removed_streams: Vec<String>,
+    modified_streams: Vec<String>
+}
+
+impl ChangeSet {
+
+    #[doc = "Returns the identity properties that changed."]
+    pub fn identity(&self) -> &[IdentityChange] {
+        &self.identity
+    }
+
+    #[doc = "Returns the tables only present in the new snapshot."]
+    pub fn added_tables(&self) -> &[String] {
+        &self.added_tables
+    }
+
+    #[doc = "Returns the tables only present in the old snapshot."]
+    pub fn removed_tables(&self) -> &[String] {
+        &self.removed_tables
+    }
+
+    #[doc = "Returns the row changes of the tables present in both snapshots, leaving out unchanged tables."]
+    pub fn tables(&self) -> &[TableChanges] {
+        &self.tables
+    }
+
+    #[doc = "Returns the non-table streams only present in the new snapshot."]
+    pub fn added_streams(&self) -> &[String] {
+        &self.added_streams
+    }
+
+    #[doc = "Returns the non-table streams only present in the old snapshot."]
+    pub fn removed_streams(&self) -> &[String] {
+        &self.removed_streams
+    }
+
+    #[doc = "Returns the non-table streams present in both snapshots with different content."]
+    pub fn modified_streams(&self) -> &[String] {
+        &self.modified_streams
+    }
+
+    #[doc = "Returns a boolean value indicating whether both snapshots describe the same package content."]
+    pub fn is_empty(&self) -> bool {
+        self.identity.is_empty() && self.added_tables.is_empty() && self.removed_tables.is_empty() && self.tables.is_empty()
+            && self.added_streams.is_empty() && self.removed_streams.is_empty() && self.modified_streams.is_empty()
+    }
+}
+
+// Keys of `new` missing from `old`.
+fn added<T>(old: &BTreeMap<String, T>, new: &BTreeMap<String, T>) -> Vec<String>
+{
+    new.keys().filter(|key| !old.contains_key(*key)).cloned().collect()
+}
+
+// Keys present in both maps with different values.
+fn modified<T: PartialEq>(old: &BTreeMap<String, T>, new: &BTreeMap<String, T>) -> Vec<String>
+{
+    old.iter()
+        .filter(|(key, value)| new.get(*key).is_some_and(|other| other != *value))
+        .map(|(key, _)| key.clone())
+        .collect()
+}
+
+fn write_u32(output: &mut Vec<u8>, value: usize)
+{
+    output.extend_from_slice(&(value as u32).to_le_bytes());
+}
+
+fn write_string(output: &mut Vec<u8>, value: &str)
+{
+    write_u32(output, value.len());
+    output.extend_from_slice(value.as_bytes());
+}
+
+fn read_string(reader: &mut ByteReader) -> Result<String>
+{
+    let length = reader.read_u32()? as usize;
+    String::from_utf8(reader.read_bytes(length)?.to_vec()).map_err(|_| Error::invalid("package snapshot contains a string that is not UTF-8"))
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::testutil::TestPackage;
+
+    #[test]
+    fn test_compare_snapshots()
+    {
+        let build = |tag: &str, rows: &[(&str, &str)]| TestPackage::new(tag, |builder| {
+            builder.table("Property", vec![
+                msi::Column::build("Property").primary_key().id_string(72),
+                msi::Column::build("Value").localizable().text_string(0)
+            ], rows.iter().map(|(name, value)| vec![msi::Value::from(*name), msi::Value::from(*value)]).collect());
+        });
+        let old = build("snapshot-old", &[("ProductCode", "{A}"), ("ProductVersion", "1.0"), ("ARPNOREPAIR", "1")]);
+        let new = build("snapshot-new", &[("ProductCode", "{A}"), ("ProductVersion", "2.0"), ("ALLUSERS", "1")]);
+        let old = PackageSnapshot::capture(&MsiPackage::open(old.path()).unwrap()).unwrap();
+        let new = PackageSnapshot::capture(&MsiPackage::open(new.path()).unwrap()).unwrap();
+
+        assert_eq!(old.identity().get("ProductVersion").map(String::as_str), Some("1.0"));
+        assert_eq!(old.tables()["Property"].rows().len(), 3);
+        assert!(PackageSnapshot::compare(&old, &old).is_empty());
+
+        let changes = PackageSnapshot::compare(&old, &new);
+        let identity: Vec<(&str, Option<&str>, Option<&str>)> = changes.identity().iter()
+            .map(|change| (change.name(), change.old_value(), change.new_value()))
+            .collect();
+        assert!(identity.contains(&("ProductVersion", Some("1.0"), Some("2.0"))));
+        assert!(identity.iter().all(|(name, _, _)| *name != "ProductCode"));
+        assert_eq!(changes.tables().len(), 1);
+        assert_eq!(changes.tables()[0].added(), ["ALLUSERS"]);
+        assert_eq!(changes.tables()[0].removed(), ["ARPNOREPAIR"]);
+        assert_eq!(changes.tables()[0].modified(), ["ProductVersion"]);
+
+        let restored = PackageSnapshot::from_bytes(&new.to_bytes()).unwrap();
+        assert_eq!(restored, new);
+        assert!(PackageSnapshot::from_bytes(&new.to_bytes()[..20]).is_err());
+        assert!(PackageSnapshot::from_bytes(b"not a snapshot").is_err());
+    }
+}
@@ -0,0 +1,1410 @@
+use std::collections::{ BTreeMap, BTreeSet, HashMap, HashSet };
+use std::path::{ Path, PathBuf };
+
+use crate::error::{ Error, Result };
+use crate::package::MsiPackage;
+use crate::table::{ Column, ColumnType, Value };
+use crate::writer;
+
+// Words that cannot name a table or column unless quoted with backticks.
+const RESERVED: [&str; 11] = ["SELECT", "DISTINCT", "FROM", "WHERE", "ORDER", "BY", "AND", "OR", "IS", "NOT", "NULL"];
+
+#[doc = "A statement of the SQL dialect of Windows Installer, as accepted by `MsiDatabaseOpenView`."]
+#[doc = ""]
+#[doc = "Queries take the form `SELECT [DISTINCT] {* | columns} FROM tables [WHERE condition] [ORDER BY columns]`. Several tables are joined by listing them, with the join condition in WHERE. Conditions compare columns, integers, `'strings'` and `?` markers with `=`, `<>`, `<`, `<=`, `>`, `>=`, test `IS NULL` and `IS NOT NULL`, and combine with AND, OR and parentheses. Names may be written `Table.Column`, and quoted with backticks when they are keywords. Keywords are case-insensitive, names are not."]
+#[doc = ""]
+#[doc = "A `Database` also runs the statements that change tables:"]
+#[doc = ""]
+#[doc = "* `INSERT INTO table (columns) VALUES (values) [TEMPORARY]`"]
+#[doc = "* `UPDATE table SET column = value, ... [WHERE condition]`"]
+#[doc = "* `DELETE FROM table [WHERE condition]`"]
+#[doc = "* `CREATE TABLE table (column type [NOT NULL] [TEMPORARY] [LOCALIZABLE], ... PRIMARY KEY columns) [HOLD]`"]
+#[doc = "* `ALTER TABLE table ADD column type [NOT NULL] [TEMPORARY] [LOCALIZABLE] [HOLD]`, and `ALTER TABLE table {HOLD | FREE}`, which have no effect"]
+#[doc = "* `DROP TABLE table`"]
+#[doc = ""]
+#[doc = "Values are integers, strings, `?` markers or NULL; an empty string is NULL. Column types are `CHAR(size)`, `CHARACTER(size)` and `LONGCHAR` for strings, `SHORT`, `INT` and `INTEGER` for 16-bit and `LONG` for 32-bit integers, and `OBJECT` for binary data."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Statement {
+    kind: Kind,
+    parameters: usize
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Kind {
+    Select(Select),
+    Insert { table: String, columns: Vec<String>, values: Vec<Operand>, temporary: bool },
+    Update { table: String, assignments: Vec<(String, Operand)>, condition: Option<Condition> },
+    Delete { table: String, condition: Option<Condition> },
+    Create { table: String, columns: Vec<ColumnDefinition> },
+    Alter { table: String, column: Option<ColumnDefinition> },
+    Drop { table: String }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct ColumnDefinition {
+    column: Column,
+    temporary: bool
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Select {
+    distinct: bool,
+    // empty for `*`
+    columns: Vec<ColumnName>,
+    tables: Vec<String>,
+    condition: Option<Condition>,
+    order: Vec<ColumnName>
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct ColumnName {
+    table: Option<String>,
+    name: String
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Operand {
+    Column(ColumnName),
+    Literal(Value),
+    Parameter(usize)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Operator {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Condition {
+    Compare(Operand, Operator, Operand),
+    IsNull(Operand, bool),
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>)
+}
+
+impl Statement {
+
+    #[doc = "Parses a statement."]
+    pub fn parse(text: &str) -> Result<Statement>
+    {
+        let mut parser = Parser {
+            text,
+            tokens: tokenize(text)?,
+            position: 0,
+            parameters: 0
+        };
+        let kind = parser.statement()?;
+        if let Some((_, offset)) = parser.tokens.get(parser.position)
+        {
+            return Err(parser.error(*offset, "unexpected token"));
+        }
+
+        Ok(Statement {
+            kind,
+            parameters: parser.parameters
+        })
+    }
+
+    #[doc = "Returns the number of `?` markers, which is the number of parameters the statement needs."]
+    pub fn parameter_count(&self) -> usize {
+        self.parameters
+    }
+
+    #[doc = "Returns the tables the statement reads or changes, in the order they are listed."]
+    pub fn tables(&self) -> Vec<&str>
+    {
+        match &self.kind
+        {
+            Kind::Select(select) => select.tables.iter().map(String::as_str).collect(),
+            Kind::Insert { table, .. } | Kind::Update { table, .. } | Kind::Delete { table, .. }
+                | Kind::Create { table, .. } | Kind::Alter { table, .. } | Kind::Drop { table } => vec![table.as_str()]
+        }
+    }
+
+    #[doc = "Returns a boolean value indicating whether the statement is a SELECT, which only reads."]
+    pub fn is_query(&self) -> bool {
+        matches!(self.kind, Kind::Select(_))
+    }
+
+    #[doc = "Runs the query against the tables of the package. `parameters` replace the `?` markers in order. Statements that change tables need a `Database`."]
+    pub fn query(&self, package: &MsiPackage, parameters: &[Value]) -> Result<QueryResult>
+    {
+        let select = match &self.kind
+        {
+            Kind::Select(select) => select,
+            _ => return Err(Error::invalid("only SELECT statements run against a package; open a `Database` to change tables"))
+        };
+        self.check_parameters(parameters)?;
+
+        let tables = select.tables.iter().map(|name| package.table(name)).collect::<Result<Vec<_>>>()?;
+        let sources: Vec<Source> = tables.iter()
+            .map(|table| Source {
+                name: table.name(),
+                columns: table.columns(),
+                rows: table.rows().map(|row| row.values()).collect()
+            })
+            .collect();
+        run(select, &sources, parameters)
+    }
+
+    #[doc = "Runs the statement against the database; returns the selected rows of a SELECT and `None` for the other statements. `parameters` replace the `?` markers in order."]
+    pub fn execute(&self, database: &mut Database, parameters: &[Value]) -> Result<Option<QueryResult>>
+    {
+        self.check_parameters(parameters)?;
+        let value_of = |operand: &Operand| match operand
+        {
+            Operand::Parameter(index) => constant(&parameters[*index]),
+            Operand::Literal(value) => constant(value),
+            Operand::Column(_) => unreachable!("values of INSERT and UPDATE are parsed as constants")
+        };
+
+        match &self.kind
+        {
+            Kind::Select(select) => {
+                for name in &select.tables
+                {
+                    database.load(name)?;
+                }
+                let sources = select.tables.iter()
+                    .map(|name| database.tables.get(name).map(|table| table.source(name)).ok_or_else(|| Error::NotFound(format!("table '{}'", name))))
+                    .collect::<Result<Vec<Source>>>()?;
+                return run(select, &sources, parameters).map(Some);
+            },
+            Kind::Insert { table: name, columns, values, temporary } => {
+                let table = database.load(name)?;
+                let mut row = vec![Value::Null; table.columns.len()];
+                for (column, value) in columns.iter().zip(values)
+                {
+                    let index = table.column_index(name, column)?;
+                    row[index] = value_of(value);
+                }
+                for (column, value) in table.columns.iter().zip(row.iter())
+                {
+                    check_value(name, column, value)?;
+                }
+
+                let key = table.key(&row);
+                if table.rows.iter().any(|existing| table.key(&existing.values) == key)
+                {
+                    return Err(Error::invalid(format!("table '{}' already has a row with the key of the inserted one", name)));
+                }
+                table.rows.push(EditRow {
+                    values: row,
+                    temporary: *temporary
+                });
+                table.modified |= !*temporary;
+            },
+            Kind::Update { table: name, assignments, condition } => {
+                let table = database.load(name)?;
+                let mut changes = Vec::with_capacity(assignments.len());
+                for (column, value) in assignments
+                {
+                    let index = table.column_index(name, column)?;
+                    let value = value_of(value);
+                    if table.columns[index].is_primary_key()
+                    {
+                        return Err(Error::invalid(format!("column '{}.{}' is part of the primary key and cannot be updated", name, column)));
+                    }
+                    check_value(name, &table.columns[index], &value)?;
+                    changes.push((index, value));
+                }
+
+                for index in table.matching(name, condition.as_ref(), parameters)?
+                {
+                    let row = &mut table.rows[index];
+                    for (column, value) in &changes
+                    {
+                        row.values[*column] = value.clone();
+                    }
+                    table.modified |= !row.temporary && changes.iter().any(|(column, _)| !table.temporary_columns[*column]);
+                }
+            },
+            Kind::Delete { table: name, condition } => {
+                let table = database.load(name)?;
+                let matching: HashSet<usize> = table.matching(name, condition.as_ref(), parameters)?.into_iter().collect();
+                table.modified |= matching.iter().any(|index| !table.rows[*index].temporary);
+                let mut index = 0;
+                table.rows.retain(|_| {
+                    index += 1;
+                    !matching.contains(&(index - 1))
+                });
+            },
+            Kind::Create { table: name, columns } => {
+                if database.has_table(name)
+                {
+                    return Err(Error::invalid(format!("table '{}' already exists", name)));
+                }
+                database.tables.insert(name.clone(), EditTable {
+                    columns: columns.iter().map(|definition| definition.column.clone()).collect(),
+                    temporary_columns: columns.iter().map(|definition| definition.temporary).collect(),
+                    rows: Vec::new(),
+                    temporary: columns.iter().all(|definition| definition.temporary),
+                    modified: true
+                });
+            },
+            Kind::Alter { table: name, column: Some(definition) } => {
+                let table = database.load(name)?;
+                if table.columns.iter().any(|column| column.name() == definition.column.name())
+                {
+                    return Err(Error::invalid(format!("table '{}' already has a column '{}'", name, definition.column.name())));
+                }
+                if !definition.column.is_nullable() && !table.rows.is_empty()
+                {
+                    return Err(Error::invalid(format!("column '{}' cannot be NOT NULL as table '{}' has rows", definition.column.name(), name)));
+                }
+                table.columns.push(definition.column.clone());
+                table.temporary_columns.push(definition.temporary);
+                for row in &mut table.rows
+                {
+                    row.values.push(Value::Null);
+                }
+                table.modified |= !definition.temporary;
+            },
+            Kind::Alter { table: name, column: None } => {
+                // HOLD and FREE only lock the table in memory
+                database.load(name)?;
+            },
+            Kind::Drop { table: name } => {
+                if !database.has_table(name)
+                {
+                    return Err(Error::NotFound(format!("table '{}'", name)));
+                }
+                database.tables.remove(name);
+                database.dropped.insert(name.clone());
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn check_parameters(&self, parameters: &[Value]) -> Result<()>
+    {
+        if parameters.len() != self.parameters
+        {
+            return Err(Error::invalid(format!("the statement has {} parameter markers but {} parameters were given", self.parameters, parameters.len())));
+        }
+
+        Ok(())
+    }
+}
+
+fn run(select: &Select, sources: &[Source], parameters: &[Value]) -> Result<QueryResult>
+{
+    let columns: Vec<(usize, usize)> = if select.columns.is_empty()
+    {
+        sources.iter().enumerate().flat_map(|(table, source)| (0..source.columns.len()).map(move |column| (table, column))).collect()
+    }
+    else
+    {
+        select.columns.iter().map(|column| resolve(sources, column)).collect::<Result<_>>()?
+    };
+    let order = select.order.iter().map(|column| resolve(sources, column)).collect::<Result<Vec<_>>>()?;
+
+    let mut conjuncts = Vec::new();
+    if let Some(condition) = &select.condition
+    {
+        for conjunct in condition.conjuncts()
+        {
+            conjuncts.push(compile(sources, conjunct, parameters)?);
+        }
+    }
+
+    let mut join = Join {
+        sources,
+        conjuncts: conjuncts.iter().map(|check| (check.depth(), check)).collect(),
+        indexes: (0..sources.len()).map(|depth| equi_join(sources, &conjuncts, depth)).collect(),
+        binding: vec![0; sources.len()],
+        matches: Vec::new()
+    };
+    join.visit(0);
+    let mut matches = join.matches;
+
+    let cell = |binding: &[usize], (table, column): (usize, usize)| &sources[table].rows[binding[table]][column];
+    if !order.is_empty()
+    {
+        matches.sort_by(|first, second| order.iter().map(|column| cell(first, *column)).cmp(order.iter().map(|column| cell(second, *column))));
+    }
+
+    let mut seen = HashSet::new();
+    let mut rows = Vec::with_capacity(matches.len());
+    for binding in matches
+    {
+        let row: Vec<Value> = columns.iter().map(|column| cell(&binding, *column).clone()).collect();
+        if !select.distinct || seen.insert(row.clone())
+        {
+            rows.push(row);
+        }
+    }
+
+    Ok(QueryResult {
+        columns: columns.iter().map(|(table, column)| sources[*table].columns[*column].clone()).collect(),
+        rows
+    })
+}
+
+#[doc = "The columns and rows a query selects."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct QueryResult {
+    columns: Vec<Column>,
+    rows: Vec<Vec<Value>>
+}
+
+impl QueryResult {
+
+    #[doc = "Returns the selected columns, in the order of the select list."]
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    #[doc = "Returns the selected rows; without ORDER BY, in storage order of the first table, then of the next."]
+    pub fn rows(&self) -> &[Vec<Value>] {
+        &self.rows
+    }
+
+    #[doc = "Returns the number of rows."]
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    #[doc = "Returns a boolean value indicating whether the query selected no rows."]
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+}
+
+#[doc = "Parses and runs a query against the tables of the package, e.g. `SELECT File, FileName FROM File WHERE Component_ = ?`."]
+pub fn query(package: &MsiPackage, sql: &str, parameters: &[Value]) -> Result<QueryResult> {
+    Statement::parse(sql)?.query(package, parameters)
+}
+
+#[doc = "A package opened for changes through SQL statements, as a database handle of the Windows Installer API is."]
+#[doc = ""]
+#[doc = "Tables are read when a statement first uses them, and changes stay in memory until `commit` writes them to the package. Temporary tables, columns and rows are visible to later statements but never written."]
+pub struct Database {
+    path: PathBuf,
+    package: MsiPackage,
+    tables: BTreeMap<String, EditTable>,
+    dropped: BTreeSet<String>
+}
+
+impl Database {
+
+    #[doc = "Opens the package at `path`."]
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Database>
+    {
+        Ok(Database {
+            path: path.as_ref().to_path_buf(),
+            package: MsiPackage::open(&path)?,
+            tables: BTreeMap::new(),
+            dropped: BTreeSet::new()
+        })
+    }
+
+    #[doc = "Parses and runs a statement; returns the selected rows of a SELECT and `None` for the other statements."]
+    pub fn execute(&mut self, sql: &str, parameters: &[Value]) -> Result<Option<QueryResult>> {
+        Statement::parse(sql)?.execute(self, parameters)
+    }
+
+    #[doc = "Returns a boolean value indicating whether a table of the given name exists, including tables created but not yet committed."]
+    pub fn has_table(&self, name: &str) -> bool {
+        self.tables.contains_key(name) || (self.package.has_table(name) && !self.dropped.contains(name))
+    }
+
+    #[doc = "Writes the changes made since the last commit to the package; temporary tables, columns and rows are left out. An existing digital signature no longer matches the package."]
+    pub fn commit(&mut self) -> Result<()>
+    {
+        let mut writer = writer::open(&self.path)?;
+        for name in &self.dropped
+        {
+            if writer.has_table(name)
+            {
+                writer.drop_table(name)?;
+                let prefix = format!("{}.", name);
+                let streams: Vec<String> = writer.streams().filter(|stream| stream.starts_with(&prefix)).collect();
+                for stream in streams
+                {
+                    writer.remove_stream(&stream)?;
+                }
+            }
+        }
+
+        for (name, table) in self.tables.iter().filter(|(_, table)| table.modified && !table.temporary)
+        {
+            let kept: Vec<usize> = (0..table.columns.len()).filter(|index| !table.temporary_columns[*index]).collect();
+            let columns: Vec<Column> = kept.iter().map(|index| table.columns[*index].clone()).collect();
+            let rows: Vec<Vec<msi::Value>> = table.rows.iter()
+                .filter(|row| !row.temporary)
+                .map(|row| kept.iter().map(|index| writer::value(&row.values[*index])).collect())
+                .collect();
+            writer::replace_table(&mut writer, name, &columns, rows)?;
+        }
+        writer.flush()?;
+        drop(writer);
+
+        self.dropped.clear();
+        for table in self.tables.values_mut()
+        {
+            table.modified = false;
+        }
+        self.package = MsiPackage::open(&self.path)?;
+        Ok(())
+    }
+
+    fn load(&mut self, name: &str) -> Result<&mut EditTable>
+    {
+        if !self.tables.contains_key(name)
+        {
+            if !self.has_table(name)
+            {
+                return Err(Error::NotFound(format!("table '{}'", name)));
+            }
+
+            let table = self.package.table(name)?;
+            self.tables.insert(name.to_string(), EditTable {
+                columns: table.columns().to_vec(),
+                temporary_columns: vec![false; table.columns().len()],
+                rows: table.rows().map(|row| EditRow { values: row.values().to_vec(), temporary: false }).collect(),
+                temporary: false,
+                modified: false
+            });
+        }
+
+        self.tables.get_mut(name).ok_or_else(|| Error::NotFound(format!("table '{}'", name)))
+    }
+}
+
+// A table as changed by the statements run so far.
+struct EditTable {
+    columns: Vec<Column>,
+    temporary_columns: Vec<bool>,
+    rows: Vec<EditRow>,
+    // a table is temporary when all its columns are
+    temporary: bool,
+    // whether the table differs from the package in anything that is written
+    modified: bool
+}
+
+struct EditRow {
+    values: Vec<Value>,
+    temporary: bool
+}
+
+impl EditTable {
+
+    fn source<'a>(&'a self, name: &'a str) -> Source<'a>
+    {
+        Source {
+            name,
+            columns: &self.columns,
+            rows: self.rows.iter().map(|row| row.values.as_slice()).collect()
+        }
+    }
+
+    fn column_index(&self, table: &str, column: &str) -> Result<usize>
+    {
+        self.columns.iter().position(|candidate| candidate.name() == column)
+            .ok_or_else(|| Error::NotFound(format!("column '{}.{}'", table, column)))
+    }
+
+    fn key(&self, values: &[Value]) -> Vec<Value>
+    {
+        self.columns.iter().zip(values).filter(|(column, _)| column.is_primary_key()).map(|(_, value)| value.clone()).collect()
+    }
+
+    // The positions of the rows the condition holds for, or of all rows without one.
+    fn matching(&self, name: &str, condition: Option<&Condition>, parameters: &[Value]) -> Result<Vec<usize>>
+    {
+        let sources = [self.source(name)];
+        let check = match condition
+        {
+            Some(condition) => compile(&sources, condition, parameters)?,
+            None => return Ok((0..self.rows.len()).collect())
+        };
+
+        Ok((0..self.rows.len()).filter(|index| check.holds(&sources, &[*index])).collect())
+    }
+}
+
+// The database does not distinguish an empty string from null.
+fn constant(value: &Value) -> Value
+{
+    match value
+    {
+        Value::Str(text) if text.is_empty() => Value::Null,
+        value => value.clone()
+    }
+}
+
+fn check_value(table: &str, column: &Column, value: &Value) -> Result<()>
+{
+    let valid = match (value, column.column_type())
+    {
+        (Value::Null, _) => column.is_nullable(),
+        (Value::Int(value), ColumnType::Int16) => (i16::MIN as i32..=i16::MAX as i32).contains(value),
+        (Value::Int(_), ColumnType::Int32) => true,
+        (Value::Str(_), ColumnType::Str(_)) | (Value::Str(_), ColumnType::Binary) => true,
+        _ => false
+    };
+
+    if valid
+    {
+        Ok(())
+    }
+    else if value.is_null()
+    {
+        Err(Error::invalid(format!("column '{}.{}' cannot be null", table, column.name())))
+    }
+    else
+    {
+        Err(Error::invalid(format!("{:?} is not a valid value for column '{}.{}'", value, table, column.name())))
+    }
+}
+
+impl Condition {
+
+    // The operands of the top-level AND chain, which can each be checked as soon as their tables are joined.
+    fn conjuncts(&self) -> Vec<&Condition>
+    {
+        match self
+        {
+            Condition::And(left, right) => {
+                let mut conjuncts = left.conjuncts();
+                conjuncts.extend(right.conjuncts());
+                conjuncts
+            },
+            condition => vec![condition]
+        }
+    }
+}
+
+struct Source<'a> {
+    name: &'a str,
+    columns: &'a [Column],
+    rows: Vec<&'a [Value]>
+}
+
+fn resolve(sources: &[Source], column: &ColumnName) -> Result<(usize, usize)>
+{
+    let mut found = sources.iter().enumerate()
+        .filter(|(_, source)| column.table.as_ref().is_none_or(|table| table == source.name))
+        .filter_map(|(table, source)| source.columns.iter().position(|candidate| candidate.name() == column.name).map(|index| (table, index)));
+
+    let qualified = match &column.table
+    {
+        Some(table) => format!("{}.{}", table, column.name),
+        None => column.name.clone()
+    };
+    match (found.next(), found.next())
+    {
+        (Some(found), None) => Ok(found),
+        (Some(_), Some(_)) => Err(Error::invalid(format!("column '{}' is ambiguous; qualify it with its table", qualified))),
+        (None, _) => Err(Error::NotFound(format!("column '{}'", qualified)))
+    }
+}
+
+enum Compiled {
+    Cell(usize, usize),
+    Value(Value)
+}
+
+impl Compiled {
+
+    fn value<'a>(&'a self, sources: &'a [Source], binding: &[usize]) -> &'a Value
+    {
+        match self
+        {
+            Compiled::Cell(table, column) => &sources[*table].rows[binding[*table]][*column],
+            Compiled::Value(value) => value
+        }
+    }
+}
+
+enum Check {
+    Compare(Compiled, Operator, Compiled),
+    IsNull(Compiled, bool),
+    And(Box<Check>, Box<Check>),
+    Or(Box<Check>, Box<Check>)
+}
+
+impl Check {
+
+    // The last table the check refers to, after which it can be evaluated.
+    fn depth(&self) -> usize
+    {
+        let table = |operand: &Compiled| match operand
+        {
+            Compiled::Cell(table, _) => *table,
+            Compiled::Value(_) => 0
+        };
+
+        match self
+        {
+            Check::Compare(left, _, right) => table(left).max(table(right)),
+            Check::IsNull(operand, _) => table(operand),
+            Check::And(left, right) | Check::Or(left, right) => left.depth().max(right.depth())
+        }
+    }
+
+    fn holds(&self, sources: &[Source], binding: &[usize]) -> bool
+    {
+        match self
+        {
+            Check::Compare(left, operator, right) => compare(left.value(sources, binding), *operator, right.value(sources, binding)),
+            Check::IsNull(operand, negated) => operand.value(sources, binding).is_null() != *negated,
+            Check::And(left, right) => left.holds(sources, binding) && right.holds(sources, binding),
+            Check::Or(left, right) => left.holds(sources, binding) || right.holds(sources, binding)
+        }
+    }
+}
+
+fn compile(sources: &[Source], condition: &Condition, parameters: &[Value]) -> Result<Check>
+{
+    let operand = |operand: &Operand| -> Result<Compiled> {
+        match operand
+        {
+            Operand::Column(column) => resolve(sources, column).map(|(table, column)| Compiled::Cell(table, column)),
+            Operand::Literal(value) => Ok(Compiled::Value(value.clone())),
+            Operand::Parameter(index) => Ok(Compiled::Value(parameters[*index].clone()))
+        }
+    };
+
+    Ok(match condition
+    {
+        Condition::Compare(left, operator, right) => Check::Compare(operand(left)?, *operator, operand(right)?),
+        Condition::IsNull(inner, negated) => Check::IsNull(operand(inner)?, *negated),
+        Condition::And(left, right) => Check::And(Box::new(compile(sources, left, parameters)?), Box::new(compile(sources, right, parameters)?)),
+        Condition::Or(left, right) => Check::Or(Box::new(compile(sources, left, parameters)?), Box::new(compile(sources, right, parameters)?))
+    })
+}
+
+// Null compares as neither equal nor unequal to anything, and integers never equal strings.
+fn compare(left: &Value, operator: Operator, right: &Value) -> bool
+{
+    let ordering = match (left, right)
+    {
+        (Value::Int(left), Value::Int(right)) => left.cmp(right),
+        (Value::Str(left), Value::Str(right)) => left.cmp(right),
+        _ => return false
+    };
+
+    match operator
+    {
+        Operator::Equal => ordering.is_eq(),
+        Operator::NotEqual => ordering.is_ne(),
+        Operator::Less => ordering.is_lt(),
+        Operator::LessOrEqual => ordering.is_le(),
+        Operator::Greater => ordering.is_gt(),
+        Operator::GreaterOrEqual => ordering.is_ge()
+    }
+}
+
+// For a table joined to an earlier one by `column = column`, indexes its rows by the joined column, so that each
+// row of the earlier tables only meets the rows it matches rather than all of them.
+struct EquiJoin {
+    other: (usize, usize),
+    rows: HashMap<Value, Vec<usize>>
+}
+
+fn equi_join(sources: &[Source], conjuncts: &[Check], depth: usize) -> Option<EquiJoin>
+{
+    let (column, other) = conjuncts.iter().find_map(|check| match check
+    {
+        Check::Compare(Compiled::Cell(left, first), Operator::Equal, Compiled::Cell(right, second)) if *left == depth && *right < depth => Some((*first, (*right, *second))),
+        Check::Compare(Compiled::Cell(left, first), Operator::Equal, Compiled::Cell(right, second)) if *right == depth && *left < depth => Some((*second, (*left, *first))),
+        _ => None
+    })?;
+
+    let mut rows: HashMap<Value, Vec<usize>> = HashMap::new();
+    for (index, row) in sources[depth].rows.iter().enumerate().filter(|(_, row)| !row[column].is_null())
+    {
+        rows.entry(row[column].clone()).or_default().push(index);
+    }
+
+    Some(EquiJoin {
+        other,
+        rows
+    })
+}
+
+struct Join<'a> {
+    sources: &'a [Source<'a>],
+    // each condition with the depth after which it can be checked
+    conjuncts: Vec<(usize, &'a Check)>,
+    indexes: Vec<Option<EquiJoin>>,
+    binding: Vec<usize>,
+    matches: Vec<Vec<usize>>
+}
+
+impl Join<'_> {
+
+    // Binds the rows of the table at `depth` in turn, checking each condition once all its tables are bound.
+    fn visit(&mut self, depth: usize)
+    {
+        if depth == self.sources.len()
+        {
+            self.matches.push(self.binding.clone());
+            return;
+        }
+
+        let candidates: Vec<usize> = match &self.indexes[depth]
+        {
+            Some(index) => {
+                let (table, column) = index.other;
+                let value = &self.sources[table].rows[self.binding[table]][column];
+                index.rows.get(value).cloned().unwrap_or_default()
+            },
+            None => (0..self.sources[depth].rows.len()).collect()
+        };
+
+        for row in candidates
+        {
+            self.binding[depth] = row;
+            if self.conjuncts.iter().filter(|(after, _)| *after == depth).all(|(_, check)| check.holds(self.sources, &self.binding))
+            {
+                self.visit(depth + 1);
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Quoted(String),
+    Str(String),
+    Int(i32),
+    Parameter,
+    Comma,
+    Dot,
+    Star,
+    Open,
+    Close,
+    Compare(Operator)
+}
+
+fn tokenize(text: &str) -> Result<Vec<(Token, usize)>>
+{
+    let error = |offset: usize, message: &str| Error::invalid(format!("query '{}': {} at offset {}", text, message, offset));
+    let bytes = text.as_bytes();
+    let mut tokens = Vec::new();
+    let mut index = 0;
+    while index < bytes.len()
+    {
+        let c = bytes[index];
+        let start = index;
+        if c.is_ascii_whitespace()
+        {
+            index += 1;
+            continue;
+        }
+
+        let token = if c == b'`' || c == b'\''
+        {
+            let end = text[index + 1..].find(c as char).ok_or_else(|| error(start, "unterminated quote"))? + index + 1;
+            let content = text[index + 1..end].to_string();
+            index = end + 1;
+            if c == b'`' { Token::Quoted(content) } else { Token::Str(content) }
+        }
+        else if c.is_ascii_digit() || (c == b'-' && bytes.get(index + 1).is_some_and(u8::is_ascii_digit))
+        {
+            index += 1;
+            while index < bytes.len() && bytes[index].is_ascii_digit()
+            {
+                index += 1;
+            }
+            Token::Int(text[start..index].parse().map_err(|_| error(start, "integer out of range"))?)
+        }
+        else if c.is_ascii_alphabetic() || c == b'_'
+        {
+            while index < bytes.len() && (bytes[index].is_ascii_alphanumeric() || bytes[index] == b'_')
+            {
+                index += 1;
+            }
+            Token::Word(text[start..index].to_string())
+        }
+        else
+        {
+            let (token, length) = match (c, bytes.get(index + 1))
+            {
+                (b'<', Some(b'>')) => (Token::Compare(Operator::NotEqual), 2),
+                (b'<', Some(b'=')) => (Token::Compare(Operator::LessOrEqual), 2),
+                (b'>', Some(b'=')) => (Token::Compare(Operator::GreaterOrEqual), 2),
+                (b'<', _) => (Token::Compare(Operator::Less), 1),
+                (b'>', _) => (Token::Compare(Operator::Greater), 1),
+                (b'=', _) => (Token::Compare(Operator::Equal), 1),
+                (b'?', _) => (Token::Parameter, 1),
+                (b',', _) => (Token::Comma, 1),
+                (b'.', _) => (Token::Dot, 1),
+                (b'*', _) => (Token::Star, 1),
+                (b'(', _) => (Token::Open, 1),
+                (b')', _) => (Token::Close, 1),
+                _ => return Err(error(start, "unexpected character"))
+            };
+            index += length;
+            token
+        };
+
+        tokens.push((token, start));
+    }
+
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+    parameters: usize
+}
+
+impl Parser<'_> {
+
+    fn error(&self, offset: usize, message: &str) -> Error {
+        Error::invalid(format!("query '{}': {} at offset {}", self.text, message, offset))
+    }
+
+    fn offset(&self) -> usize {
+        self.tokens.get(self.position).map(|(_, offset)| *offset).unwrap_or(self.text.len())
+    }
+
+    fn accept(&mut self, token: &Token) -> bool
+    {
+        if self.tokens.get(self.position).is_some_and(|(candidate, _)| candidate == token)
+        {
+            self.position += 1;
+            true
+        }
+        else
+        {
+            false
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str) -> bool
+    {
+        if matches!(self.tokens.get(self.position), Some((Token::Word(word), _)) if word.eq_ignore_ascii_case(keyword))
+        {
+            self.position += 1;
+            true
+        }
+        else
+        {
+            false
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<()>
+    {
+        if self.keyword(keyword)
+        {
+            Ok(())
+        }
+        else
+        {
+            Err(self.error(self.offset(), &format!("expected {}", keyword)))
+        }
+    }
+
+    fn expect(&mut self, token: Token, description: &str) -> Result<()>
+    {
+        if self.accept(&token)
+        {
+            Ok(())
+        }
+        else
+        {
+            Err(self.error(self.offset(), &format!("expected {}", description)))
+        }
+    }
+
+    fn identifier(&mut self) -> Result<String>
+    {
+        let name = match self.tokens.get(self.position)
+        {
+            Some((Token::Word(word), _)) if !RESERVED.iter().any(|reserved| word.eq_ignore_ascii_case(reserved)) => word.clone(),
+            Some((Token::Quoted(name), _)) => name.clone(),
+            _ => return Err(self.error(self.offset(), "expected a name"))
+        };
+        self.position += 1;
+        Ok(name)
+    }
+
+    // A list of one or more items separated by commas.
+    fn list<T>(&mut self, item: fn(&mut Self) -> Result<T>) -> Result<Vec<T>>
+    {
+        let mut items = vec![item(self)?];
+        while self.accept(&Token::Comma)
+        {
+            items.push(item(self)?);
+        }
+
+        Ok(items)
+    }
+
+    fn column_name(&mut self) -> Result<ColumnName>
+    {
+        let name = self.identifier()?;
+        if self.accept(&Token::Dot)
+        {
+            return Ok(ColumnName {
+                table: Some(name),
+                name: self.identifier()?
+            });
+        }
+
+        Ok(ColumnName {
+            table: None,
+            name
+        })
+    }
+
+    fn statement(&mut self) -> Result<Kind>
+    {
+        let word = match self.tokens.get(self.position)
+        {
+            Some((Token::Word(word), _)) => word.to_ascii_uppercase(),
+            _ => String::new()
+        };
+
+        match word.as_str()
+        {
+            "SELECT" => self.select().map(Kind::Select),
+            "INSERT" => self.insert(),
+            "UPDATE" => self.update(),
+            "DELETE" => {
+                self.position += 1;
+                self.expect_keyword("FROM")?;
+                let table = self.identifier()?;
+                let condition = if self.keyword("WHERE") { Some(self.disjunction()?) } else { None };
+                Ok(Kind::Delete { table, condition })
+            },
+            "CREATE" => self.create(),
+            "ALTER" => {
+                self.position += 1;
+                self.expect_keyword("TABLE")?;
+                let table = self.identifier()?;
+                let column = if self.keyword("ADD") { Some(self.column_definition()?) } else if self.keyword("FREE") { None } else { self.expect_keyword("HOLD")?; None };
+                if column.is_some()
+                {
+                    self.keyword("HOLD");
+                }
+                Ok(Kind::Alter { table, column })
+            },
+            "DROP" => {
+                self.position += 1;
+                self.expect_keyword("TABLE")?;
+                Ok(Kind::Drop { table: self.identifier()? })
+            },
+            _ => Err(self.error(self.offset(), "expected SELECT, INSERT, UPDATE, DELETE, CREATE, ALTER or DROP"))
+        }
+    }
+
+    fn insert(&mut self) -> Result<Kind>
+    {
+        self.expect_keyword("INSERT")?;
+        self.expect_keyword("INTO")?;
+        let table = self.identifier()?;
+        self.expect(Token::Open, "'('")?;
+        let columns = self.list(Parser::identifier)?;
+        self.expect(Token::Close, "')'")?;
+        self.expect_keyword("VALUES")?;
+        self.expect(Token::Open, "'('")?;
+        let offset = self.offset();
+        let values = self.list(Parser::constant)?;
+        self.expect(Token::Close, "')'")?;
+        if values.len() != columns.len()
+        {
+            return Err(self.error(offset, &format!("{} values for {} columns", values.len(), columns.len())));
+        }
+
+        Ok(Kind::Insert {
+            table,
+            columns,
+            values,
+            temporary: self.keyword("TEMPORARY")
+        })
+    }
+
+    fn update(&mut self) -> Result<Kind>
+    {
+        self.expect_keyword("UPDATE")?;
+        let table = self.identifier()?;
+        self.expect_keyword("SET")?;
+        let assignments = self.list(|parser| {
+            let column = parser.identifier()?;
+            parser.expect(Token::Compare(Operator::Equal), "'='")?;
+            Ok((column, parser.constant()?))
+        })?;
+        let condition = if self.keyword("WHERE") { Some(self.disjunction()?) } else { None };
+
+        Ok(Kind::Update {
+            table,
+            assignments,
+            condition
+        })
+    }
+
+    fn create(&mut self) -> Result<Kind>
+    {
+        self.expect_keyword("CREATE")?;
+        self.expect_keyword("TABLE")?;
+        let table = self.identifier()?;
+        self.expect(Token::Open, "'('")?;
+        let mut columns = vec![self.column_definition()?];
+        while self.accept(&Token::Comma) && !self.keyword("PRIMARY")
+        {
+            columns.push(self.column_definition()?);
+        }
+        // the key list follows the last column with or without a comma
+        if !matches!(self.tokens.get(self.position - 1), Some((Token::Word(word), _)) if word.eq_ignore_ascii_case("PRIMARY"))
+        {
+            self.expect_keyword("PRIMARY")?;
+        }
+        self.expect_keyword("KEY")?;
+        let offset = self.offset();
+        let keys = self.list(Parser::identifier)?;
+        self.expect(Token::Close, "')'")?;
+        self.keyword("HOLD");
+
+        for key in keys
+        {
+            let definition = columns.iter_mut().find(|definition| definition.column.name() == key)
+                .ok_or_else(|| self.error(offset, &format!("primary key '{}' is not a column", key)))?;
+            let column = &definition.column;
+            definition.column = Column::new(column.name(), column.column_type(), column.is_nullable(), true, column.is_localizable());
+        }
+
+        Ok(Kind::Create {
+            table,
+            columns
+        })
+    }
+
+    fn column_definition(&mut self) -> Result<ColumnDefinition>
+    {
+        let name = self.identifier()?;
+        let offset = self.offset();
+        let word = match self.tokens.get(self.position)
+        {
+            Some((Token::Word(word), _)) => word.to_ascii_uppercase(),
+            _ => String::new()
+        };
+        self.position += 1;
+
+        let column_type = match word.as_str()
+        {
+            "CHAR" | "CHARACTER" => {
+                let mut size = 1;
+                if self.accept(&Token::Open)
+                {
+                    size = match self.tokens.get(self.position)
+                    {
+                        Some((Token::Int(size), _)) if (0..=255).contains(size) => *size as usize,
+                        _ => return Err(self.error(self.offset(), "expected a size from 0 to 255"))
+                    };
+                    self.position += 1;
+                    self.expect(Token::Close, "')'")?;
+                }
+                ColumnType::Str(size)
+            },
+            "LONGCHAR" => ColumnType::Str(0),
+            "SHORT" | "INT" | "INTEGER" => ColumnType::Int16,
+            "LONG" => ColumnType::Int32,
+            "OBJECT" => ColumnType::Binary,
+            _ => return Err(self.error(offset, "expected a column type"))
+        };
+
+        let (mut nullable, mut temporary, mut localizable) = (true, false, false);
+        loop
+        {
+            if self.keyword("NOT")
+            {
+                self.expect_keyword("NULL")?;
+                nullable = false;
+            }
+            else if self.keyword("TEMPORARY")
+            {
+                temporary = true;
+            }
+            else if self.keyword("LOCALIZABLE")
+            {
+                localizable = true;
+            }
+            else
+            {
+                break;
+            }
+        }
+
+        Ok(ColumnDefinition {
+            column: Column::new(&name, column_type, nullable, false, localizable),
+            temporary
+        })
+    }
+
+    fn select(&mut self) -> Result<Select>
+    {
+        self.expect_keyword("SELECT")?;
+        let distinct = self.keyword("DISTINCT");
+        let columns = if self.accept(&Token::Star) { Vec::new() } else { self.list(Parser::column_name)? };
+        self.expect_keyword("FROM")?;
+        let tables = self.list(Parser::identifier)?;
+
+        let condition = if self.keyword("WHERE") { Some(self.disjunction()?) } else { None };
+        let order = if self.keyword("ORDER")
+        {
+            self.expect_keyword("BY")?;
+            self.list(Parser::column_name)?
+        }
+        else
+        {
+            Vec::new()
+        };
+
+        Ok(Select {
+            distinct,
+            columns,
+            tables,
+            condition,
+            order
+        })
+    }
+
+    fn disjunction(&mut self) -> Result<Condition>
+    {
+        let mut condition = self.conjunction()?;
+        while self.keyword("OR")
+        {
+            condition = Condition::Or(Box::new(condition), Box::new(self.conjunction()?));
+        }
+
+        Ok(condition)
+    }
+
+    fn conjunction(&mut self) -> Result<Condition>
+    {
+        let mut condition = self.comparison()?;
+        while self.keyword("AND")
+        {
+            condition = Condition::And(Box::new(condition), Box::new(self.comparison()?));
+        }
+
+        Ok(condition)
+    }
+
+    fn comparison(&mut self) -> Result<Condition>
+    {
+        if self.accept(&Token::Open)
+        {
+            let condition = self.disjunction()?;
+            self.expect(Token::Close, "')'")?;
+            return Ok(condition);
+        }
+
+        let left = self.operand()?;
+        if self.keyword("IS")
+        {
+            let negated = self.keyword("NOT");
+            self.expect_keyword("NULL")?;
+            return Ok(Condition::IsNull(left, negated));
+        }
+
+        let operator = match self.tokens.get(self.position)
+        {
+            Some((Token::Compare(operator), _)) => *operator,
+            _ => return Err(self.error(self.offset(), "expected a comparison"))
+        };
+        self.position += 1;
+        Ok(Condition::Compare(left, operator, self.operand()?))
+    }
+
+    // A value of INSERT or UPDATE: an integer, a string, a marker or NULL.
+    fn constant(&mut self) -> Result<Operand>
+    {
+        if self.keyword("NULL")
+        {
+            return Ok(Operand::Literal(Value::Null));
+        }
+
+        match self.operand()?
+        {
+            Operand::Column(_) => Err(self.error(self.tokens[self.position - 1].1, "expected a value")),
+            operand => Ok(operand)
+        }
+    }
+
+    fn operand(&mut self) -> Result<Operand>
+    {
+        let operand = match self.tokens.get(self.position)
+        {
+            Some((Token::Str(value), _)) => Operand::Literal(Value::from(value.as_str())),
+            Some((Token::Int(value), _)) => Operand::Literal(Value::Int(*value)),
+            Some((Token::Parameter, _)) => {
+                self.parameters += 1;
+                Operand::Parameter(self.parameters - 1)
+            },
+            _ => return self.column_name().map(Operand::Column)
+        };
+        self.position += 1;
+        Ok(operand)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::testutil::TestPackage;
+
+    fn package(tag: &str) -> TestPackage
+    {
+        TestPackage::new(tag, |builder| {
+            builder.table("Component", vec![
+                msi::Column::build("Component").primary_key().id_string(72),
+                msi::Column::build("Directory_").id_string(72),
+                msi::Column::build("Attributes").int16()
+            ], vec![
+                vec![msi::Value::from("Main"), msi::Value::from("INSTALLDIR"), msi::Value::Int(0)],
+                vec![msi::Value::from("Help"), msi::Value::from("DOCS"), msi::Value::Int(256)]
+            ]);
+            builder.table("File", vec![
+                msi::Column::build("File").primary_key().id_string(72),
+                msi::Column::build("Component_").id_string(72),
+                msi::Column::build("FileName").text_string(255),
+                msi::Column::build("Version").nullable().text_string(72),
+                msi::Column::build("Sequence").int16()
+            ], vec![
+                vec![msi::Value::from("app.exe"), msi::Value::from("Main"), msi::Value::from("app.exe"), msi::Value::from("1.0.0.0"), msi::Value::Int(1)],
+                vec![msi::Value::from("app.dll"), msi::Value::from("Main"), msi::Value::from("app.dll"), msi::Value::from("1.0.0.0"), msi::Value::Int(2)],
+                vec![msi::Value::from("guide.chm"), msi::Value::from("Help"), msi::Value::from("guide.chm"), msi::Value::Null, msi::Value::Int(3)]
+            ]);
+        })
+    }
+
+    #[test]
+    fn test_parse_statement()
+    {
+        let statement = Statement::parse("select DISTINCT `File`.`Component_`, FileName FROM `File`, Component WHERE (Version IS NOT NULL OR Sequence > ?) AND File.Component_ = Component.Component ORDER BY Sequence").unwrap();
+        assert_eq!(statement.parameter_count(), 1);
+        assert_eq!(statement.tables(), ["File", "Component"]);
+        let select = match &statement.kind
+        {
+            Kind::Select(select) => select,
+            kind => panic!("{:?}", kind)
+        };
+        assert!(select.distinct && statement.is_query());
+        assert_eq!(select.columns[0], ColumnName { table: Some("File".to_string()), name: "Component_".to_string() });
+        assert_eq!(select.condition.as_ref().unwrap().conjuncts().len(), 2);
+
+        for invalid in ["", "SELECT FROM File", "SELECT * File", "SELECT * FROM File WHERE", "SELECT * FROM File WHERE A = 'open",
+            "SELECT * FROM File WHERE (A = 1", "SELECT * FROM File ORDER Sequence", "SELECT * FROM Order", "SELECT * FROM File WHERE A IS 1",
+            "SELECT * FROM File WHERE A = 99999999999", "SELECT * FROM File extra", "SELECT * FROM File WHERE A # 1"]
+        {
+            assert!(Statement::parse(invalid).is_err(), "{}", invalid);
+        }
+        assert!(Statement::parse("SELECT * FROM `Order`").is_ok());
+
+        let statement = Statement::parse("CREATE TABLE `Log` (`Id` SHORT NOT NULL, `Text` CHAR(72) LOCALIZABLE, `Data` OBJECT, `Note` LONGCHAR TEMPORARY PRIMARY KEY `Id`) HOLD").unwrap();
+        let columns = match &statement.kind
+        {
+            Kind::Create { columns, .. } => columns,
+            kind => panic!("{:?}", kind)
+        };
+        assert_eq!(columns.iter().map(|definition| definition.column.column_type()).collect::<Vec<_>>(), [ColumnType::Int16, ColumnType::Str(72), ColumnType::Binary, ColumnType::Str(0)]);
+        assert!(columns[0].column.is_primary_key() && !columns[0].column.is_nullable() && columns[1].column.is_localizable() && columns[3].temporary);
+        assert_eq!(Statement::parse("INSERT INTO File (File, Sequence) VALUES (?, NULL) TEMPORARY").unwrap().parameter_count(), 1);
+        assert!(!Statement::parse("UPDATE File SET Version = '2.0', Sequence = ? WHERE File = ?").unwrap().is_query());
+
+        for invalid in ["INSERT INTO File (File, Sequence) VALUES ('a')", "INSERT INTO File (File) VALUES (Sequence)", "UPDATE File SET Sequence",
+            "DELETE File", "CREATE TABLE Log (Id SHORT)", "CREATE TABLE Log (Id FLOAT PRIMARY KEY Id)", "CREATE TABLE Log (Id SHORT PRIMARY KEY Name)",
+            "ALTER TABLE File", "DROP File", "TRUNCATE File"]
+        {
+            assert!(Statement::parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_query()
+    {
+        let package = package("sql-query");
+        let package = MsiPackage::open(package.path()).unwrap();
+        let names = |result: QueryResult| result.rows().iter().map(|row| row[0].to_string()).collect::<Vec<_>>();
+
+        let result = package.query("SELECT * FROM File", &[]).unwrap();
+        assert_eq!(result.columns().len(), 5);
+        assert_eq!(result.len(), 3);
+
+        let result = query(&package, "SELECT FileName, Sequence FROM File WHERE Version IS NULL", &[]).unwrap();
+        assert_eq!(result.columns().iter().map(Column::name).collect::<Vec<_>>(), ["FileName", "Sequence"]);
+        assert_eq!(result.rows(), [vec![Value::from("guide.chm"), Value::Int(3)]]);
+
+        assert_eq!(names(query(&package, "SELECT File FROM File WHERE Sequence >= ? AND Component_ = ? ORDER BY Sequence", &[Value::Int(2), Value::from("Main")]).unwrap()), ["app.dll"]);
+        assert_eq!(names(query(&package, "SELECT File FROM File WHERE Sequence < 2 OR File = 'guide.chm' ORDER BY File", &[]).unwrap()), ["app.exe", "guide.chm"]);
+        assert_eq!(names(query(&package, "SELECT DISTINCT Component_ FROM File ORDER BY Component_", &[]).unwrap()), ["Help", "Main"]);
+        assert_eq!(query(&package, "SELECT File FROM File WHERE Version <> '2.0'", &[]).unwrap().len(), 2);
+        assert!(query(&package, "SELECT File FROM File WHERE Sequence = '1'", &[]).unwrap().is_empty());
+
+        let result = query(&package, "SELECT `File`, `Component`.`Directory_` FROM `File`, `Component` WHERE `File`.`Component_` = `Component`.`Component` AND `Component`.`Attributes` = 0 ORDER BY `File`", &[]).unwrap();
+        assert_eq!(result.rows(), [vec![Value::from("app.dll"), Value::from("INSTALLDIR")], vec![Value::from("app.exe"), Value::from("INSTALLDIR")]]);
+        assert_eq!(query(&package, "SELECT File, Component FROM File, Component", &[]).unwrap().len(), 6);
+
+        assert!(matches!(query(&package, "SELECT * FROM Registry", &[]), Err(Error::NotFound(_))));
+        assert!(matches!(query(&package, "SELECT Missing FROM File", &[]), Err(Error::NotFound(_))));
+        assert!(query(&package, "SELECT File FROM File WHERE Sequence = ?", &[]).is_err());
+        assert!(matches!(query(&package, "SELECT Component.File FROM File, Component", &[]), Err(Error::NotFound(_))));
+        assert!(matches!(query(&package, "SELECT File FROM File, File", &[]), Err(Error::InvalidData(_))));
+    }
+
+    #[test]
+    fn test_execute()
+    {
+        let package = package("sql-execute");
+        let mut database = Database::open(package.path()).unwrap();
+
+        database.execute("INSERT INTO File (File, Component_, FileName, Sequence) VALUES ('readme.txt', 'Help', 'readme.txt', ?)", &[Value::Int(4)]).unwrap();
+        database.execute("INSERT INTO File (File, Component_, FileName, Sequence) VALUES ('scratch.tmp', 'Main', 'scratch.tmp', 5) TEMPORARY", &[]).unwrap();
+        database.execute("UPDATE File SET Version = '2.0.0.0' WHERE Component_ = 'Main'", &[]).unwrap();
+        database.execute("DELETE FROM File WHERE File = 'guide.chm'", &[]).unwrap();
+        database.execute("ALTER TABLE Component ADD Note CHAR(32) TEMPORARY", &[]).unwrap();
+        database.execute("UPDATE Component SET Note = 'checked'", &[]).unwrap();
+        database.execute("CREATE TABLE Log (Id SHORT NOT NULL, Text CHAR(72), Scratch LONG TEMPORARY PRIMARY KEY Id)", &[]).unwrap();
+        database.execute("INSERT INTO Log (Id, Text, Scratch) VALUES (1, 'created', 7)", &[]).unwrap();
+        database.execute("CREATE TABLE Scratch (Id SHORT TEMPORARY PRIMARY KEY Id)", &[]).unwrap();
+
+        let result = database.execute("SELECT File, Version FROM File ORDER BY Sequence", &[]).unwrap().unwrap();
+        assert_eq!(result.rows().iter().map(|row| row[0].to_string()).collect::<Vec<_>>(), ["app.exe", "app.dll", "readme.txt", "scratch.tmp"]);
+        assert_eq!(result.rows()[3][1], Value::from("2.0.0.0"));
+        assert_eq!(database.execute("SELECT Note FROM Component WHERE Note = 'checked'", &[]).unwrap().unwrap().len(), 2);
+
+        assert!(database.execute("INSERT INTO File (File, Component_, FileName, Sequence) VALUES ('app.exe', 'Main', 'app.exe', 1)", &[]).is_err());
+        assert!(database.execute("UPDATE File SET File = 'other.exe'", &[]).is_err());
+        assert!(database.execute("INSERT INTO Log (Text) VALUES ('no key')", &[]).is_err());
+        assert!(database.execute("INSERT INTO Log (Id) VALUES (70000)", &[]).is_err());
+        assert!(database.execute("ALTER TABLE File ADD Flags SHORT NOT NULL", &[]).is_err());
+        assert!(database.execute("CREATE TABLE Log (Id SHORT PRIMARY KEY Id)", &[]).is_err());
+        assert!(matches!(database.execute("DELETE FROM Registry", &[]), Err(Error::NotFound(_))));
+        assert!(Statement::parse("DELETE FROM File").unwrap().query(&MsiPackage::open(package.path()).unwrap(), &[]).is_err());
+
+        database.commit().unwrap();
+        let reopened = MsiPackage::open(package.path()).unwrap();
+        let result = query(&reopened, "SELECT File, Version FROM File ORDER BY Sequence", &[]).unwrap();
+        assert_eq!(result.rows(), [
+            vec![Value::from("app.exe"), Value::from("2.0.0.0")],
+            vec![Value::from("app.dll"), Value::from("2.0.0.0")],
+            vec![Value::from("readme.txt"), Value::Null]
+        ]);
+        assert!(reopened.table("Component").unwrap().columns().iter().all(|column| column.name() != "Note"));
+        assert_eq!(query(&reopened, "SELECT * FROM Log", &[]).unwrap().rows(), [vec![Value::Int(1), Value::from("created")]]);
+        assert!(!reopened.has_table("Scratch"));
+
+        database.execute("DROP TABLE Log", &[]).unwrap();
+        assert!(!database.has_table("Log"));
+        database.commit().unwrap();
+        assert!(!MsiPackage::open(package.path()).unwrap().has_table("Log"));
+    }
+}
@@ -0,0 +1,116 @@
+// Windows Installer packs stream names into the compound file's 31-character
+// limit by folding pairs of [0-9A-Za-z._] characters into a single code point
+// from the U+3800..U+4840 range. Table streams are additionally prefixed with
+// U+4840.
+
+const TABLE_PREFIX: char = '\u{4840}';
+
+#[doc = "Decodes a raw compound-file stream name, returning the readable name and whether it names a table stream."]
+pub(crate) fn decode(name: &str) -> (String, bool)
+{
+    let mut output = String::new();
+    let mut chars = name.chars().peekable();
+    let is_table = chars.peek() == Some(&TABLE_PREFIX);
+    if is_table
+    {
+        chars.next();
+    }
+
+    for ch in chars
+    {
+        let value = ch as u32;
+        if (0x3800..0x4800).contains(&value)
+        {
+            let value = value - 0x3800;
+            output.push(from_base64(value & 0x3f));
+            output.push(from_base64(value >> 6));
+        }
+        else if (0x4800..0x4840).contains(&value)
+        {
+            output.push(from_base64(value - 0x4800));
+        }
+        else
+        {
+            output.push(ch);
+        }
+    }
+
+    (output, is_table)
+}
+
+#[doc = "Encodes a readable stream name into its compound-file form."]
+pub(crate) fn encode(name: &str, is_table: bool) -> String
+{
+    let mut output = String::new();
+    if is_table
+    {
+        output.push(TABLE_PREFIX);
+    }
+
+    let mut chars = name.chars().peekable();
+    while let Some(first) = chars.next()
+    {
+        match to_base64(first)
+        {
+            Some(low) => {
+                if let Some(high) = chars.peek().and_then(|next| to_base64(*next))
+                {
+                    chars.next();
+                    output.push(std::char::from_u32(0x3800 + (high << 6) + low).unwrap_or(first));
+                }
+                else
+                {
+                    output.push(std::char::from_u32(0x4800 + low).unwrap_or(first));
+                }
+            },
+            None => output.push(first)
+        }
+    }
+
+    output
+}
+
+fn from_base64(value: u32) -> char
+{
+    match value
+    {
+        0..=9 => (b'0' + value as u8) as char,
+        10..=35 => (b'A' + (value - 10) as u8) as char,
+        36..=61 => (b'a' + (value - 36) as u8) as char,
+        62 => '.',
+        _ => '_'
+    }
+}
+
+fn to_base64(ch: char) -> Option<u32>
+{
+    match ch
+    {
+        '0'..='9' => Some(ch as u32 - '0' as u32),
+        'A'..='Z' => Some(ch as u32 - 'A' as u32 + 10),
+        'a'..='z' => Some(ch as u32 - 'a' as u32 + 36),
+        '.' => Some(62),
+        '_' => Some(63),
+        _ => None
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn test_round_trip()
+    {
+        let encoded = encode("PCW_CAB_Patch", false);
+        assert_eq!(encoded.chars().count(), 7);
+        assert_eq!(decode(&encoded), ("PCW_CAB_Patch".to_string(), false));
+        assert_eq!(encode("File", true), "\u{4840}\u{430f}\u{422f}");
+
+        assert_eq!(decode("\u{4840}\u{430f}\u{422f}"), ("File".to_string(), true));
+        assert_eq!(decode("\u{3b19}\u{4820}"), ("PCW".to_string(), false));
+
+        assert_eq!(decode("\u{5}SummaryInformation"), ("\u{5}SummaryInformation".to_string(), false));
+    }
+}
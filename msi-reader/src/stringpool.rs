@@ -0,0 +1,361 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::Arc;
+
+use encoding::DecoderTrap;
+
+use crate::bytes::ByteReader;
+use crate::codepage;
+use crate::error::{ Error, Result };
+
+const LONG_STRING_REFS_BIT: u32 = 0x8000_0000;
+
+#[doc = "The shared string table of a database, decoded from the `_StringPool` and `_StringData` streams. Table cells refer to its strings by 1-based id."]
+pub struct StringPool {
+    codepage: u32,
+    long_refs: bool,
+    strings: Vec<Arc<str>>,
+    refcounts: Vec<u16>,
+    escaped: HashMap<u32, String>
+}
+
+impl StringPool {
+
+    pub(crate) fn parse(pool: &[u8], data: &[u8]) -> Result<StringPool>
+    {
+        let (header, entries) = read_entries(pool, data)?;
+        let codepage = header_codepage(header);
+        // Strings of an ANSI or double-byte codepage are transcoded; UTF-8 databases and those of
+        // codepages without a known character set are read as UTF-8.
+        let ansi = codepage::encoding(codepage).filter(|encoding| encoding.name() != "utf-8");
+        let mut strings = Vec::with_capacity(entries.len());
+        let mut refcounts = Vec::with_capacity(entries.len());
+        let mut escaped = HashMap::new();
+        for (bytes, refcount) in entries
+        {
+            refcounts.push(refcount);
+            if let Some(encoding) = ansi.filter(|_| !bytes.is_ascii())
+            {
+                let value = encoding.decode(&bytes, DecoderTrap::Replace).unwrap_or_default();
+                if value.contains('\u{fffd}')
+                {
+                    escaped.insert(strings.len() as u32 + 1, escape_ansi(&bytes));
+                }
+                strings.push(Arc::from(value));
+                continue;
+            }
+
+            match String::from_utf8(bytes)
+            {
+                Ok(value) => strings.push(Arc::from(value)),
+                Err(error) => {
+                    escaped.insert(strings.len() as u32 + 1, escape(error.as_bytes()));
+                    strings.push(Arc::from(String::from_utf8_lossy(error.as_bytes())));
+                }
+            }
+        }
+
+        Ok(StringPool {
+            codepage,
+            long_refs: header & LONG_STRING_REFS_BIT != 0,
+            strings,
+            refcounts,
+            escaped
+        })
+    }
+
+    #[doc = "Returns the string with the given 1-based id, shared so that table cells hold it without copying. Id 0 is the null string."]
+    pub(crate) fn get(&self, id: u32) -> Option<&Arc<str>> {
+        if id == 0
+        {
+            return None;
+        }
+
+        self.strings.get(id as usize - 1)
+    }
+
+    #[doc = "Returns the string with the given 1-based id, or `None` for id 0 (the null string) and ids beyond the pool."]
+    pub fn string(&self, id: u32) -> Option<&str> {
+        self.get(id).map(|value| &**value)
+    }
+
+    #[doc = "Returns the number of references to the string with the given id that the database recorded, or `None` for ids outside the pool. Unused entries have a count of 0."]
+    pub fn refcount(&self, id: u32) -> Option<u16> {
+        id.checked_sub(1).and_then(|index| self.refcounts.get(index as usize)).copied()
+    }
+
+    #[doc = "Returns the number of entries, including unused ones."]
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    #[doc = "Returns a boolean value indicating whether the pool has no entries."]
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    #[doc = "Returns the escaped form of a string with bytes that cannot be decoded, which `string` shows as U+FFFD."]
+    pub fn escaped(&self, id: u32) -> Option<&str> {
+        self.escaped.get(&id).map(|value| value.as_str())
+    }
+
+    #[doc = "Returns the codepage of the database, stored in the pool header."]
+    pub fn codepage(&self) -> u32 {
+        self.codepage
+    }
+
+    #[doc = "Returns the name of the character set the strings were decoded from, e.g. `windows-1252`, or `None` if the codepage is unknown and they were read as UTF-8."]
+    pub fn encoding_name(&self) -> Option<&'static str> {
+        codepage::encoding_name(self.codepage)
+    }
+
+    #[doc = "Returns a boolean value indicating whether string references take three bytes instead of two, as in pools of more than 64K strings."]
+    pub fn long_refs(&self) -> bool {
+        self.long_refs
+    }
+}
+
+// Undecodable bytes become `\xNN` and backslashes are doubled, so the original bytes can be restored.
+fn escape(bytes: &[u8]) -> String
+{
+    let mut output = String::with_capacity(bytes.len() * 2);
+    for chunk in bytes.utf8_chunks()
+    {
+        output.push_str(&chunk.valid().replace('\\', "\\\\"));
+        for byte in chunk.invalid()
+        {
+            let _ = write!(output, "\\x{:02X}", byte);
+        }
+    }
+
+    output
+}
+
+// Same escaping for strings of a single- or double-byte codepage: ASCII is kept and every other byte becomes `\xNN`.
+fn escape_ansi(bytes: &[u8]) -> String
+{
+    let mut output = String::with_capacity(bytes.len() * 2);
+    for byte in bytes
+    {
+        match byte
+        {
+            b'\\' => output.push_str("\\\\"),
+            0..=0x7f => output.push(*byte as char),
+            _ => { let _ = write!(output, "\\x{:02X}", byte); }
+        }
+    }
+
+    output
+}
+
+#[doc = "Collects the strings of a new string pool, assigning ids in order of first use."]
+#[derive(Default)]
+pub(crate) struct StringPoolWriter {
+    ids: HashMap<String, u32>,
+    strings: Vec<(String, u32)>
+}
+
+impl StringPoolWriter {
+
+    #[doc = "Adds a reference to the string, registering it on first use. The empty string is stored as null and takes no entry."]
+    pub(crate) fn intern(&mut self, value: &str)
+    {
+        if value.is_empty()
+        {
+            return;
+        }
+
+        match self.ids.get(value)
+        {
+            Some(id) => self.strings[*id as usize - 1].1 += 1,
+            None => {
+                self.strings.push((value.to_string(), 1));
+                self.ids.insert(value.to_string(), self.strings.len() as u32);
+            }
+        }
+    }
+
+    #[doc = "Returns the id of an interned string. Id 0 is the null string."]
+    pub(crate) fn id(&self, value: &str) -> u32 {
+        self.ids.get(value).copied().unwrap_or(0)
+    }
+
+    #[doc = "Returns a boolean value indicating whether the pool needs three-byte string references."]
+    pub(crate) fn long_refs(&self) -> bool {
+        self.strings.len() >= 0xffff
+    }
+
+    #[doc = "Returns a boolean value indicating whether every string is plain ASCII, which reads the same in any codepage."]
+    pub(crate) fn is_ascii(&self) -> bool {
+        self.strings.iter().all(|(value, _)| value.is_ascii())
+    }
+
+    #[doc = "Encodes the `_StringPool` and `_StringData` streams."]
+    pub(crate) fn write(&self, codepage: u32) -> (Vec<u8>, Vec<u8>)
+    {
+        let header = if self.long_refs() { codepage | LONG_STRING_REFS_BIT } else { codepage };
+        let entries: Vec<PoolEntry> = self.strings.iter()
+            .map(|(value, refcount)| (value.as_bytes().to_vec(), (*refcount).min(u16::MAX as u32) as u16))
+            .collect();
+        write_entries(header, &entries)
+    }
+}
+
+#[doc = "A raw string pool entry: the bytes of a string in the pool's codepage and its reference count."]
+pub(crate) type PoolEntry = (Vec<u8>, u16);
+
+#[doc = "Reads the header and the raw entries of a string pool, in order. Entry `n` holds string id `n + 1`."]
+pub(crate) fn read_entries(pool: &[u8], data: &[u8]) -> Result<(u32, Vec<PoolEntry>)>
+{
+    let mut reader = ByteReader::new(pool);
+    let header = reader.read_u32()?;
+    let mut data = ByteReader::new(data);
+    let mut entries = Vec::with_capacity(pool.len() / 4);
+
+    while reader.remaining() >= 4
+    {
+        let mut length = reader.read_u16()? as usize;
+        let mut refcount = reader.read_u16()?;
+        if length == 0 && refcount > 0
+        {
+            // strings of 64K and more store their length in the following entry
+            length = ((refcount as usize) << 16) | reader.read_u16()? as usize;
+            refcount = reader.read_u16()?;
+        }
+
+        let bytes = data.read_bytes(length)
+            .map_err(|_| Error::invalid(format!("string {} exceeds the string data stream", entries.len() + 1)))?;
+        entries.push((bytes.to_vec(), refcount));
+    }
+
+    Ok((header, entries))
+}
+
+#[doc = "Encodes raw entries as read by `read_entries` into the `_StringPool` and `_StringData` streams."]
+pub(crate) fn write_entries(header: u32, entries: &[PoolEntry]) -> (Vec<u8>, Vec<u8>)
+{
+    let mut pool = header.to_le_bytes().to_vec();
+    let mut data = Vec::new();
+    for (bytes, refcount) in entries
+    {
+        let length = bytes.len();
+        if length > u16::MAX as usize
+        {
+            pool.extend_from_slice(&0u16.to_le_bytes());
+            pool.extend_from_slice(&((length >> 16) as u16).to_le_bytes());
+        }
+        pool.extend_from_slice(&(length as u16).to_le_bytes());
+        pool.extend_from_slice(&refcount.to_le_bytes());
+        data.extend_from_slice(bytes);
+    }
+
+    (pool, data)
+}
+
+#[doc = "Returns the codepage stored in a string pool header."]
+pub(crate) fn header_codepage(header: u32) -> u32 {
+    header & !LONG_STRING_REFS_BIT
+}
+
+#[doc = "Returns the string pool header with its codepage replaced."]
+pub(crate) fn with_codepage(header: u32, codepage: u32) -> u32 {
+    (header & LONG_STRING_REFS_BIT) | codepage
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::table::{ CellCoercion, Column, Table };
+
+    #[test]
+    fn test_parse_pool()
+    {
+        let mut pool = Vec::new();
+        pool.extend_from_slice(&(1252u32 | LONG_STRING_REFS_BIT).to_le_bytes());
+        for (length, refcount) in [(4u16, 1u16), (0, 0), (5, 2)].iter()
+        {
+            pool.extend_from_slice(&length.to_le_bytes());
+            pool.extend_from_slice(&refcount.to_le_bytes());
+        }
+
+        let strings = StringPool::parse(&pool, b"FileAlpha").unwrap();
+        assert!(strings.long_refs());
+        assert_eq!(strings.string(0), None);
+        assert_eq!(strings.string(1), Some("File"));
+        assert_eq!(strings.string(2), Some(""));
+        assert_eq!(strings.string(3), Some("Alpha"));
+        assert_eq!(strings.string(4), None);
+        assert_eq!((strings.refcount(2), strings.refcount(3), strings.refcount(0)), (Some(0), Some(2), None));
+        assert_eq!((strings.codepage(), strings.len()), (1252, 3));
+
+        assert!(StringPool::parse(&pool, b"File").is_err());
+
+        let strings = StringPool::parse(&pool, b"File\\\xE9tt\xE9").unwrap();
+        assert_eq!(strings.string(3), Some("\\\u{e9}tt\u{e9}"));
+        assert_eq!(strings.escaped(3), None);
+        assert_eq!(strings.encoding_name(), Some("windows-1252"));
+
+        pool[..4].copy_from_slice(&(65001u32 | LONG_STRING_REFS_BIT).to_le_bytes());
+        let strings = StringPool::parse(&pool, b"File\\\xE9tt\xE9").unwrap();
+        assert_eq!(strings.string(3), Some("\\\u{fffd}tt\u{fffd}"));
+        assert_eq!(strings.escaped(3), Some("\\\\\\xE9tt\\xE9"));
+        assert_eq!(strings.escaped(1), None);
+
+        pool[..4].copy_from_slice(&932u32.to_le_bytes());
+        let strings = StringPool::parse(&pool, b"File\x93\xFA\x96\x7B\x81").unwrap();
+        assert_eq!(strings.string(3), Some("日本\u{fffd}"));
+        assert_eq!(strings.escaped(3), Some("\\x93\\xFA\\x96{\\x81"));
+    }
+
+    #[test]
+    fn test_large_pool()
+    {
+        let mut writer = StringPoolWriter::default();
+        for index in 0..70_000
+        {
+            writer.intern(&format!("s{}", index));
+        }
+        let long = "x".repeat(70_000);
+        writer.intern(&long);
+        writer.intern("s1");
+        assert!(writer.long_refs());
+        assert_eq!(writer.id(&long), 70_001);
+
+        let (pool, data) = writer.write(1252);
+        let (header, entries) = read_entries(&pool, &data).unwrap();
+        assert_eq!(header_codepage(header), 1252);
+        assert_eq!((entries[1].1, entries[70_000].0.len()), (2, 70_000));
+
+        let strings = StringPool::parse(&pool, &data).unwrap();
+        assert!(strings.long_refs());
+        assert_eq!(strings.string(70_000), Some("s69999"));
+        assert_eq!(strings.string(70_001), Some(long.as_str()));
+
+        // three-byte references are stored column by column, low word first
+        let table = Table::decode("Big", vec![Column::from_bits("Name", 0x2d40).unwrap()], &[1, 0, 0, 0x71, 0x11, 0x01], &strings, CellCoercion::Strict).unwrap();
+        let names: Vec<&str> = table.rows().map(|row| row.str("Name").unwrap()).collect();
+        assert_eq!(names, ["s0", long.as_str()]);
+    }
+
+    #[test]
+    fn test_parse_malformed()
+    {
+        let mut writer = StringPoolWriter::default();
+        writer.intern("File");
+        writer.intern("Alpha");
+        let (pool, data) = writer.write(1252);
+
+        for length in 0..data.len()
+        {
+            assert!(StringPool::parse(&pool, &data[..length]).is_err());
+        }
+        for offset in 0..pool.len()
+        {
+            let mut corrupt = pool.clone();
+            corrupt[offset] = 0xff;
+            let _ = StringPool::parse(&corrupt, &data);
+            let _ = StringPool::parse(&pool[..offset], &data);
+        }
+    }
+}
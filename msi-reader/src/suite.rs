@@ -0,0 +1,478 @@
+use std::collections::{ BTreeMap, HashMap };
+
+use crate::directory::{ MsiName, NameFormat };
+use crate::error::Result;
+use crate::layout::DirectoryResolver;
+use crate::package::MsiPackage;
+use crate::registry;
+use crate::table::RowOrigin;
+
+#[doc = "A component of one package, with the resources it installs resolved to target paths."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ComponentInstance {
+    package: String,
+    component: String,
+    directory: String,
+    key_path: String,
+    resources: Vec<String>,
+    origin: RowOrigin
+}
+
+impl ComponentInstance {
+
+    #[doc = "Returns the name of the package, as given by the caller."]
+    pub fn package(&self) -> &str {
+        &self.package
+    }
+
+    #[doc = "Returns the key of the row in the Component table."]
+    pub fn component(&self) -> &str {
+        &self.component
+    }
+
+    #[doc = "Returns the full target path of the component's directory, e.g. `C:\\Program Files (x86)\\Contoso\\`, or an empty string if it does not resolve."]
+    pub fn directory(&self) -> &str {
+        &self.directory
+    }
+
+    #[doc = "Returns the resolved key path: a file path, a registry path such as `HKLM\\Software\\Contoso\\Version`, or the directory."]
+    pub fn key_path(&self) -> &str {
+        &self.key_path
+    }
+
+    #[doc = "Returns the files and registry values of the component as sorted target paths."]
+    pub fn resources(&self) -> &[String] {
+        &self.resources
+    }
+
+    #[doc = "Returns the Component row of the package the instance was read from."]
+    pub fn origin(&self) -> &RowOrigin {
+        &self.origin
+    }
+}
+
+#[doc = "A component GUID used by several packages for components that install different resources, which breaks component reference counting when the packages are serviced independently."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ComponentCollision {
+    guid: String,
+    instances: Vec<ComponentInstance>
+}
+
+impl ComponentCollision {
+
+    #[doc = "Returns the shared component GUID, in upper case."]
+    pub fn guid(&self) -> &str {
+        &self.guid
+    }
+
+    #[doc = "Returns the components sharing the GUID, in package order."]
+    pub fn instances(&self) -> &[ComponentInstance] {
+        &self.instances
+    }
+}
+
+#[doc = "Finds component GUIDs shared by components of different packages whose directory, key path or resources differ. Components with identical content, such as merge module components, are fine and not reported. Packages are given as (name, package) pairs."]
+pub fn find_component_collisions(packages: &[(&str, &MsiPackage)]) -> Result<Vec<ComponentCollision>>
+{
+    let mut by_guid: BTreeMap<String, Vec<ComponentInstance>> = BTreeMap::new();
+    for (name, package) in packages
+    {
+        for (guid, instance) in component_instances(name, package)?
+        {
+            by_guid.entry(guid).or_default().push(instance);
+        }
+    }
+
+    Ok(by_guid.into_iter()
+        .filter(|(_, instances)| {
+            let first = &instances[0];
+            instances.iter().any(|instance| instance.package != first.package)
+                && instances.iter().any(|instance| (&instance.directory, &instance.key_path, &instance.resources) != (&first.directory, &first.key_path, &first.resources))
+        })
+        .map(|(guid, instances)| ComponentCollision { guid, instances })
+        .collect())
+}
+
+#[doc = "An item (directory, component GUID or file path) used by several packages."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SharedItem {
+    item: String,
+    packages: Vec<String>
+}
+
+impl SharedItem {
+
+    #[doc = "Returns the shared item: a target path or a component GUID."]
+    pub fn item(&self) -> &str {
+        &self.item
+    }
+
+    #[doc = "Returns the names of the packages using the item, in package order."]
+    pub fn packages(&self) -> &[String] {
+        &self.packages
+    }
+}
+
+#[doc = "A registry value written by several packages with different data."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct RegistryConflict {
+    path: String,
+    values: Vec<(String, String)>
+}
+
+impl RegistryConflict {
+
+    #[doc = "Returns the registry path, e.g. `HKLM\\Software\\Contoso\\Version`."]
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    #[doc = "Returns the (package, value) pairs, in package order."]
+    pub fn values(&self) -> &[(String, String)] {
+        &self.values
+    }
+}
+
+#[doc = "The overlap between the packages of one product suite: directories, components, files and registry values more than one package installs."]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SuiteAnalysis {
+    shared_directories: Vec<SharedItem>,
+    shared_components: Vec<SharedItem>,
+    overlapping_files: Vec<SharedItem>,
+    registry_conflicts: Vec<RegistryConflict>,
+    component_collisions: Vec<ComponentCollision>
+}
+
+impl SuiteAnalysis {
+
+    #[doc = "Analyzes packages given as (name, package) pairs. Paths are compared case-insensitively, as on Windows. Files installed by components sharing a GUID are expected to overlap and reported as shared components only."]
+    pub fn analyze(packages: &[(&str, &MsiPackage)]) -> Result<SuiteAnalysis>
+    {
+        let mut directories: BTreeMap<String, SharedItem> = BTreeMap::new();
+        let mut components: BTreeMap<String, SharedItem> = BTreeMap::new();
+        // upper-case path: (shared item, GUIDs of the installing components)
+        let mut files: BTreeMap<String, (SharedItem, Vec<String>)> = BTreeMap::new();
+        let mut registry: BTreeMap<String, RegistryConflict> = BTreeMap::new();
+
+        for (name, package) in packages
+        {
+            let PackageResources { shareable, resources, .. } = PackageResources::collect(package)?;
+            let guids: HashMap<String, String> = match package.optional_table("Component")?
+            {
+                Some(table) => table.rows()
+                    .filter_map(|row| Some((row.str("Component")?.to_string(), row.str("ComponentId")?.to_ascii_uppercase())))
+                    .collect(),
+                None => HashMap::new()
+            };
+
+            for directory in &shareable
+            {
+                share(&mut directories, directory, name);
+            }
+            for guid in guids.values()
+            {
+                share(&mut components, guid, name);
+            }
+            for resource in resources.values()
+            {
+                if resource.is_registry
+                {
+                    if let Some(value) = &resource.value
+                    {
+                        let conflict = registry.entry(resource.path.to_ascii_uppercase())
+                            .or_insert_with(|| RegistryConflict { path: resource.path.clone(), values: Vec::new() });
+                        conflict.values.push((name.to_string(), value.clone()));
+                    }
+                }
+                else
+                {
+                    let (item, file_guids) = files.entry(resource.path.to_ascii_uppercase())
+                        .or_insert_with(|| (SharedItem { item: resource.path.clone(), packages: Vec::new() }, Vec::new()));
+                    if !item.packages.iter().any(|package| package == name)
+                    {
+                        item.packages.push(name.to_string());
+                    }
+                    file_guids.extend(guids.get(&resource.component).cloned());
+                }
+            }
+        }
+
+        let shared = |items: BTreeMap<String, SharedItem>| items.into_values().filter(|item| item.packages.len() > 1).collect::<Vec<_>>();
+        Ok(SuiteAnalysis {
+            shared_directories: shared(directories),
+            shared_components: shared(components),
+            overlapping_files: files.into_values()
+                .filter(|(item, guids)| item.packages.len() > 1 && (guids.len() < item.packages.len() || guids.iter().any(|guid| *guid != guids[0])))
+                .map(|(item, _)| item)
+                .collect(),
+            registry_conflicts: registry.into_values()
+                .filter(|conflict| conflict.values.iter().any(|(package, value)| *package != conflict.values[0].0 && *value != conflict.values[0].1))
+                .collect(),
+            component_collisions: find_component_collisions(packages)?
+        })
+    }
+
+    #[doc = "Returns the component directories used by more than one package."]
+    pub fn shared_directories(&self) -> &[SharedItem] {
+        &self.shared_directories
+    }
+
+    #[doc = "Returns the component GUIDs used by more than one package."]
+    pub fn shared_components(&self) -> &[SharedItem] {
+        &self.shared_components
+    }
+
+    #[doc = "Returns the file paths installed by more than one package through different components."]
+    pub fn overlapping_files(&self) -> &[SharedItem] {
+        &self.overlapping_files
+    }
+
+    #[doc = "Returns the registry values written by more than one package with different data."]
+    pub fn registry_conflicts(&self) -> &[RegistryConflict] {
+        &self.registry_conflicts
+    }
+
+    #[doc = "Returns the shared component GUIDs whose components differ, see `find_component_collisions`."]
+    pub fn component_collisions(&self) -> &[ComponentCollision] {
+        &self.component_collisions
+    }
+}
+
+fn share(items: &mut BTreeMap<String, SharedItem>, item: &str, package: &str)
+{
+    let shared = items.entry(item.to_ascii_uppercase()).or_insert_with(|| SharedItem { item: item.to_string(), packages: Vec::new() });
+    if !shared.packages.iter().any(|name| name == package)
+    {
+        shared.packages.push(package.to_string());
+    }
+}
+
+// A file or registry value of a package, resolved to its target path.
+struct Resource {
+    component: String,
+    path: String,
+    value: Option<String>,
+    is_registry: bool
+}
+
+// The resolved directories of the components of a package, those not laid out by a well-known folder or root itself,
+// and its resources by File or Registry key.
+struct PackageResources {
+    directories: HashMap<String, String>,
+    shareable: Vec<String>,
+    resources: HashMap<String, Resource>
+}
+
+impl PackageResources {
+
+    fn collect(package: &MsiPackage) -> Result<PackageResources>
+    {
+        let resolver = DirectoryResolver::read(package)?;
+        let mut directories = HashMap::new();
+        let mut shareable = Vec::new();
+        if let Some(table) = package.optional_table("Component")?
+        {
+            for row in table.rows()
+            {
+                let (component, directory) = match (row.str("Component"), row.str("Directory_"))
+                {
+                    (Some(component), Some(directory)) => (component, directory),
+                    _ => continue
+                };
+                if let Some(path) = resolver.target_path(directory)
+                {
+                    if !resolver.is_predefined(directory)
+                    {
+                        shareable.push(path.clone());
+                    }
+                    directories.insert(component.to_string(), path);
+                }
+            }
+        }
+
+        let mut resources = HashMap::new();
+        if let Some(files) = package.optional_table("File")?
+        {
+            for row in files.rows()
+            {
+                if let (Some(file), Some(component), Some(name)) = (row.str("File"), row.str("Component_"), row.str("FileName"))
+                {
+                    let directory = directories.get(component).map(String::as_str).unwrap_or_default();
+                    resources.insert(file.to_string(), Resource {
+                        component: component.to_string(),
+                        path: format!("{}{}", directory, MsiName::from(name).format(NameFormat::Long)),
+                        value: None,
+                        is_registry: false
+                    });
+                }
+            }
+        }
+        if let Some(registry) = package.optional_table("Registry")?
+        {
+            for row in registry.rows()
+            {
+                if let (Some(key), Some(component)) = (row.str("Registry"), row.str("Component_"))
+                {
+                    resources.insert(key.to_string(), Resource {
+                        component: component.to_string(),
+                        path: registry::registry_path(row.int("Root").unwrap_or(-1), row.str("Key").unwrap_or_default(), row.str("Name")),
+                        value: row.str("Value").map(str::to_string),
+                        is_registry: true
+                    });
+                }
+            }
+        }
+
+        Ok(PackageResources {
+            directories,
+            shareable,
+            resources
+        })
+    }
+}
+
+#[doc = "Returns the components of a package that have a GUID, keyed by the GUID in upper case."]
+fn component_instances(name: &str, package: &MsiPackage) -> Result<Vec<(String, ComponentInstance)>>
+{
+    let table = match package.optional_table("Component")?
+    {
+        Some(table) => table,
+        None => return Ok(Vec::new())
+    };
+    let PackageResources { directories, resources, .. } = PackageResources::collect(package)?;
+
+    let mut by_component: HashMap<&str, Vec<String>> = HashMap::new();
+    for resource in resources.values()
+    {
+        by_component.entry(&resource.component).or_default().push(resource.path.clone());
+    }
+
+    let mut instances = Vec::new();
+    for row in table.rows()
+    {
+        let (component, guid) = match (row.str("Component"), row.str("ComponentId"))
+        {
+            (Some(component), Some(guid)) => (component, guid),
+            _ => continue
+        };
+        let directory = directories.get(component).cloned().unwrap_or_default();
+        let key_path = match row.str("KeyPath").and_then(|key| resources.get(key))
+        {
+            Some(resource) => resource.path.clone(),
+            None => directory.clone()
+        };
+        let mut paths = by_component.remove(component).unwrap_or_default();
+        paths.sort();
+
+        instances.push((guid.to_ascii_uppercase(), ComponentInstance {
+            package: name.to_string(),
+            component: component.to_string(),
+            directory,
+            key_path,
+            resources: paths,
+            origin: row.origin()
+        }));
+    }
+
+    Ok(instances)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::testutil::TestPackage;
+
+    fn suite_package(tag: &str, components: &[(&str, &str, &str)], files: &[(&str, &str, &str)], registry: &[(&str, &str, &str)]) -> TestPackage
+    {
+        let components = components.iter()
+            .map(|(component, guid, directory)| vec![msi::Value::from(*component), msi::Value::from(*guid), msi::Value::from(*directory), msi::Value::Int(0), msi::Value::Null, msi::Value::Null])
+            .collect();
+        let files = files.iter()
+            .map(|(file, component, name)| vec![msi::Value::from(*file), msi::Value::from(*component), msi::Value::from(*name)])
+            .collect();
+        let registry: Vec<Vec<msi::Value>> = registry.iter()
+            .map(|(key, component, value)| vec![msi::Value::from(*key), msi::Value::Int(2), msi::Value::from("Software\\Contoso"), msi::Value::from("Version"), msi::Value::from(*value), msi::Value::from(*component)])
+            .collect();
+        TestPackage::new(tag, |builder| {
+            builder.table("Directory", vec![
+                msi::Column::build("Directory").primary_key().id_string(72),
+                msi::Column::build("Directory_Parent").nullable().id_string(72),
+                msi::Column::build("DefaultDir").text_string(255)
+            ], vec![
+                vec![msi::Value::from("TARGETDIR"), msi::Value::Null, msi::Value::from("SourceDir")],
+                vec![msi::Value::from("ProgramFilesFolder"), msi::Value::from("TARGETDIR"), msi::Value::from(".")],
+                vec![msi::Value::from("INSTALLDIR"), msi::Value::from("ProgramFilesFolder"), msi::Value::from("Contoso")],
+                vec![msi::Value::from("BIN"), msi::Value::from("INSTALLDIR"), msi::Value::from("bin")]
+            ]);
+            builder.table("Component", vec![
+                msi::Column::build("Component").primary_key().id_string(72),
+                msi::Column::build("ComponentId").nullable().string(38),
+                msi::Column::build("Directory_").id_string(72),
+                msi::Column::build("Attributes").int16(),
+                msi::Column::build("Condition").nullable().string(255),
+                msi::Column::build("KeyPath").nullable().id_string(72)
+            ], components);
+            builder.table("File", vec![
+                msi::Column::build("File").primary_key().id_string(72),
+                msi::Column::build("Component_").id_string(72),
+                msi::Column::build("FileName").localizable().text_string(255)
+            ], files);
+            if !registry.is_empty()
+            {
+                builder.table("Registry", vec![
+                    msi::Column::build("Registry").primary_key().id_string(72),
+                    msi::Column::build("Root").int16(),
+                    msi::Column::build("Key").localizable().text_string(255),
+                    msi::Column::build("Name").nullable().localizable().text_string(255),
+                    msi::Column::build("Value").nullable().localizable().text_string(0),
+                    msi::Column::build("Component_").id_string(72)
+                ], registry);
+            }
+        })
+    }
+
+    #[test]
+    fn test_find_component_collisions()
+    {
+        let shared = "{11111111-2222-3333-4444-555555555555}";
+        let first = suite_package("suite-first", &[("Shared", shared, "INSTALLDIR"), ("Core", "{22222222-2222-3333-4444-555555555555}", "INSTALLDIR")],
+            &[("shared.dll", "Shared", "shared.dll"), ("core.dll", "Core", "core.dll")], &[]);
+        let second = suite_package("suite-second", &[("Shared", &shared.to_lowercase(), "BIN"), ("Core", "{22222222-2222-3333-4444-555555555555}", "INSTALLDIR")],
+            &[("shared.dll", "Shared", "shared.dll"), ("core.dll", "Core", "core.dll")], &[]);
+        let first = MsiPackage::open(first.path()).unwrap();
+        let second = MsiPackage::open(second.path()).unwrap();
+
+        let collisions = find_component_collisions(&[("first.msi", &first), ("second.msi", &second)]).unwrap();
+        assert_eq!(collisions.len(), 1);
+        assert_eq!(collisions[0].guid(), shared);
+        assert_eq!(collisions[0].instances()[0].resources(), ["C:\\Program Files (x86)\\Contoso\\shared.dll"]);
+        assert_eq!(collisions[0].instances()[1].package(), "second.msi");
+        assert_eq!(collisions[0].instances()[1].directory(), "C:\\Program Files (x86)\\Contoso\\bin\\");
+
+        assert!(find_component_collisions(&[("first.msi", &first), ("copy.msi", &first)]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_analyze()
+    {
+        let client = suite_package("suite-client", &[("Client", "{33333333-2222-3333-4444-555555555555}", "INSTALLDIR"), ("Common", "{44444444-2222-3333-4444-555555555555}", "BIN")],
+            &[("client.exe", "Client", "client.exe"), ("readme.txt", "Client", "readme.txt"), ("common.dll", "Common", "common.dll")], &[("Version", "Client", "1.0")]);
+        let server = suite_package("suite-server", &[("Server", "{55555555-2222-3333-4444-555555555555}", "INSTALLDIR"), ("Common", "{44444444-2222-3333-4444-555555555555}", "BIN")],
+            &[("server.exe", "Server", "server.exe"), ("README.TXT", "Server", "README.TXT"), ("common.dll", "Common", "common.dll")], &[("Version", "Server", "2.0")]);
+        let client = MsiPackage::open(client.path()).unwrap();
+        let server = MsiPackage::open(server.path()).unwrap();
+
+        let analysis = SuiteAnalysis::analyze(&[("client.msi", &client), ("server.msi", &server)]).unwrap();
+        assert_eq!(analysis.shared_directories().len(), 2);
+        assert_eq!(analysis.shared_directories()[0].item(), "C:\\Program Files (x86)\\Contoso\\");
+        assert_eq!(analysis.shared_directories()[0].packages(), ["client.msi", "server.msi"]);
+        assert_eq!(analysis.shared_components().len(), 1);
+        assert_eq!(analysis.overlapping_files().len(), 1);
+        assert_eq!(analysis.overlapping_files()[0].item(), "C:\\Program Files (x86)\\Contoso\\readme.txt");
+        assert_eq!(analysis.registry_conflicts().len(), 1);
+        assert_eq!(analysis.registry_conflicts()[0].path(), "HKLM\\Software\\Contoso\\Version");
+        assert_eq!(analysis.registry_conflicts()[0].values()[1], ("server.msi".to_string(), "2.0".to_string()));
+        assert!(analysis.component_collisions().is_empty());
+    }
+}
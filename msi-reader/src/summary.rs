@@ -0,0 +1,703 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::io::Write;
+use std::path::Path;
+use std::time::{ Duration, SystemTime };
+
+use crate::bytes::ByteReader;
+use crate::codepage;
+use crate::compound;
+use crate::error::{ Error, Result };
+use crate::guid::MsiGuid;
+use crate::language::MsiLanguage;
+
+#[doc = "Name of the stream holding the summary information property set."]
+pub const SUMMARY_INFO_STREAM: &str = "\u{5}SummaryInformation";
+
+pub const PID_CODEPAGE: u32 = 1;
+pub const PID_TITLE: u32 = 2;
+pub const PID_SUBJECT: u32 = 3;
+pub const PID_AUTHOR: u32 = 4;
+pub const PID_KEYWORDS: u32 = 5;
+pub const PID_COMMENTS: u32 = 6;
+pub const PID_TEMPLATE: u32 = 7;
+pub const PID_LASTAUTHOR: u32 = 8;
+pub const PID_REVNUMBER: u32 = 9;
+pub const PID_LASTPRINTED: u32 = 11;
+pub const PID_CREATE_DTM: u32 = 12;
+pub const PID_LASTSAVE_DTM: u32 = 13;
+pub const PID_PAGECOUNT: u32 = 14;
+pub const PID_WORDCOUNT: u32 = 15;
+pub const PID_CHARCOUNT: u32 = 16;
+pub const PID_APPNAME: u32 = 18;
+pub const PID_SECURITY: u32 = 19;
+
+const BYTE_ORDER_MARK: u16 = 0xfffe;
+const FMTID_SUMMARY_INFORMATION: [u8; 16] = [0xe0, 0x85, 0x9f, 0xf2, 0xf9, 0x4f, 0x68, 0x10, 0xab, 0x91, 0x08, 0x00, 0x2b, 0x27, 0xb3, 0xd9];
+
+const WORD_COUNT_SHORT_NAMES: i32 = 0x1;
+const WORD_COUNT_COMPRESSED: i32 = 0x2;
+const WORD_COUNT_ADMIN_IMAGE: i32 = 0x4;
+const WORD_COUNT_NO_ELEVATION: i32 = 0x8;
+
+// 100ns intervals between 1601-01-01 and 1970-01-01
+const FILETIME_UNIX_EPOCH: u64 = 116_444_736_000_000_000;
+
+const VT_EMPTY: u32 = 0;
+const VT_NULL: u32 = 1;
+const VT_I2: u32 = 2;
+const VT_I4: u32 = 3;
+const VT_LPSTR: u32 = 30;
+const VT_FILETIME: u32 = 64;
+
+#[doc = "A single value stored in the summary information property set."]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PropertyValue {
+    Empty,
+    Null,
+    I2(i16),
+    I4(i32),
+    Str(String),
+    #[doc = "A FILETIME value (100ns intervals since 1601-01-01 UTC)."]
+    FileTime(u64)
+}
+
+impl PropertyValue {
+
+    #[doc = "Returns the string value, if this is a string property."]
+    pub fn as_str(&self) -> Option<&str> {
+        match self
+        {
+            PropertyValue::Str(value) => Some(value),
+            _ => None
+        }
+    }
+
+    #[doc = "Returns the integer value, if this is an I2 or I4 property."]
+    pub fn as_int(&self) -> Option<i32> {
+        match self
+        {
+            PropertyValue::I2(value) => Some(*value as i32),
+            PropertyValue::I4(value) => Some(*value),
+            _ => None
+        }
+    }
+
+    // Strings are decoded from the codepage of the property set, or as UTF-8 if it is unknown.
+    fn read(reader: &mut ByteReader, codepage: u32) -> Result<PropertyValue>
+    {
+        let value_type = reader.read_u32()?;
+        match value_type
+        {
+            VT_EMPTY => Ok(PropertyValue::Empty),
+            VT_NULL => Ok(PropertyValue::Null),
+            VT_I2 => Ok(PropertyValue::I2(reader.read_i16()?)),
+            VT_I4 => Ok(PropertyValue::I4(reader.read_i32()?)),
+            VT_LPSTR => {
+                let length = reader.read_u32()? as usize;
+                let bytes = reader.read_bytes(length)?;
+                let bytes = match bytes.iter().position(|b| *b == 0)
+                {
+                    Some(end) => &bytes[..end],
+                    None => bytes
+                };
+                let value = codepage::decode(codepage, bytes).unwrap_or_else(|| String::from_utf8_lossy(bytes).into_owned());
+                Ok(PropertyValue::Str(value))
+            },
+            VT_FILETIME => Ok(PropertyValue::FileTime(reader.read_u64()?)),
+            _ => Err(Error::invalid(format!("unsupported property value type {}", value_type)))
+        }
+    }
+
+    // Strings are encoded in the codepage of the property set, or as UTF-8 if it is unknown.
+    fn write(&self, output: &mut Vec<u8>, codepage: u32)
+    {
+        match self
+        {
+            PropertyValue::Empty => output.extend_from_slice(&VT_EMPTY.to_le_bytes()),
+            PropertyValue::Null => output.extend_from_slice(&VT_NULL.to_le_bytes()),
+            PropertyValue::I2(value) => {
+                output.extend_from_slice(&VT_I2.to_le_bytes());
+                output.extend_from_slice(&(*value as i32).to_le_bytes());
+            },
+            PropertyValue::I4(value) => {
+                output.extend_from_slice(&VT_I4.to_le_bytes());
+                output.extend_from_slice(&value.to_le_bytes());
+            },
+            PropertyValue::Str(value) => {
+                let bytes = codepage::encode(codepage, value).unwrap_or_else(|| value.as_bytes().to_vec());
+                output.extend_from_slice(&VT_LPSTR.to_le_bytes());
+                output.extend_from_slice(&(bytes.len() as u32 + 1).to_le_bytes());
+                output.extend_from_slice(&bytes);
+                output.push(0);
+            },
+            PropertyValue::FileTime(value) => {
+                output.extend_from_slice(&VT_FILETIME.to_le_bytes());
+                output.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+
+        while !output.len().is_multiple_of(4)
+        {
+            output.push(0);
+        }
+    }
+}
+
+impl Display for PropertyValue {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self
+        {
+            PropertyValue::Empty | PropertyValue::Null => Ok(()),
+            PropertyValue::I2(value) => write!(fmt, "{}", value),
+            PropertyValue::I4(value) => write!(fmt, "{}", value),
+            PropertyValue::Str(value) => write!(fmt, "{}", value),
+            PropertyValue::FileTime(value) => write!(fmt, "{}", value)
+        }
+    }
+}
+
+#[doc = "The processor architecture a package targets, named by the platform part of its Template."]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Platform {
+    #[doc = "32-bit x86, written as `Intel` or left empty."]
+    Intel,
+    #[doc = "64-bit x86, written as `x64` or `AMD64`."]
+    X64,
+    #[doc = "Itanium, written as `Intel64`."]
+    Intel64,
+    Arm,
+    Arm64
+}
+
+impl Platform {
+
+    #[doc = "Parses the platform part of a Template; names are case-insensitive."]
+    pub fn parse(name: &str) -> Option<Platform>
+    {
+        match name.trim().to_ascii_lowercase().as_str()
+        {
+            "" | "intel" => Some(Platform::Intel),
+            "x64" | "amd64" => Some(Platform::X64),
+            "intel64" => Some(Platform::Intel64),
+            "arm" => Some(Platform::Arm),
+            "arm64" => Some(Platform::Arm64),
+            _ => None
+        }
+    }
+
+    #[doc = "Returns a boolean value indicating whether the platform is 64-bit."]
+    pub fn is_64bit(&self) -> bool {
+        matches!(self, Platform::X64 | Platform::Intel64 | Platform::Arm64)
+    }
+}
+
+impl Display for Platform {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self
+        {
+            Platform::Intel => write!(fmt, "Intel"),
+            Platform::X64 => write!(fmt, "x64"),
+            Platform::Intel64 => write!(fmt, "Intel64"),
+            Platform::Arm => write!(fmt, "Arm"),
+            Platform::Arm64 => write!(fmt, "Arm64")
+        }
+    }
+}
+
+#[doc = "The Word Count property of a package, describing the source image."]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WordCount(i32);
+
+impl WordCount {
+
+    #[doc = "Returns the raw value of the property."]
+    pub fn bits(&self) -> i32 {
+        self.0
+    }
+
+    #[doc = "Returns a boolean value indicating whether the source uses short file names."]
+    pub fn has_short_names(&self) -> bool {
+        self.0 & WORD_COUNT_SHORT_NAMES != 0
+    }
+
+    #[doc = "Returns a boolean value indicating whether source files are compressed by default."]
+    pub fn is_compressed(&self) -> bool {
+        self.0 & WORD_COUNT_COMPRESSED != 0
+    }
+
+    #[doc = "Returns a boolean value indicating whether the source is an administrative image."]
+    pub fn is_admin_image(&self) -> bool {
+        self.0 & WORD_COUNT_ADMIN_IMAGE != 0
+    }
+
+    #[doc = "Returns a boolean value indicating whether installing requires elevated privileges, i.e. the package does not declare itself as installable without them."]
+    pub fn requires_elevation(&self) -> bool {
+        self.0 & WORD_COUNT_NO_ELEVATION == 0
+    }
+}
+
+impl From<i32> for WordCount {
+    fn from(bits: i32) -> Self {
+        WordCount(bits)
+    }
+}
+
+#[doc = "The Security property: whether the package should be opened read-only."]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Security {
+    None,
+    ReadOnlyRecommended,
+    ReadOnlyEnforced,
+    Other(i32)
+}
+
+impl From<i32> for Security {
+    fn from(value: i32) -> Self {
+        match value
+        {
+            0 => Security::None,
+            2 => Security::ReadOnlyRecommended,
+            4 => Security::ReadOnlyEnforced,
+            other => Security::Other(other)
+        }
+    }
+}
+
+#[doc = "The contents of a `\\u{5}SummaryInformation` property set, keyed by property id."]
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SummaryInfo {
+    properties: BTreeMap<u32, PropertyValue>
+}
+
+impl SummaryInfo {
+
+    #[doc = "Parses the raw bytes of a summary information stream. Strings are decoded from the codepage given by the Codepage property."]
+    pub fn parse(data: &[u8]) -> Result<SummaryInfo>
+    {
+        let mut reader = ByteReader::new(data);
+        if reader.read_u16()? != BYTE_ORDER_MARK
+        {
+            return Err(Error::invalid("summary information has an invalid byte order mark"));
+        }
+
+        // format version, OS version, OS kind, CLSID
+        reader.skip(2 + 2 + 2 + 16)?;
+        if reader.read_u32()? < 1
+        {
+            return Err(Error::invalid("summary information contains no property sections"));
+        }
+
+        // FMTID of the first section
+        reader.skip(16)?;
+        let section_offset = reader.read_u32()? as usize;
+
+        reader.seek(section_offset)?;
+        let _section_size = reader.read_u32()?;
+        let count = reader.read_u32()?;
+
+        let mut offsets = Vec::new();
+        for _ in 0..count
+        {
+            let id = reader.read_u32()?;
+            let offset = reader.read_u32()? as usize;
+            offsets.push((id, offset));
+        }
+
+        // the codepage is needed to decode the strings, wherever it is stored in the section
+        let codepage = match offsets.iter().find(|(id, _)| *id == PID_CODEPAGE)
+        {
+            Some((_, offset)) => {
+                reader.seek(section_offset.saturating_add(*offset))?;
+                string_codepage(&PropertyValue::read(&mut reader, 0)?)
+            },
+            None => 0
+        };
+
+        let mut properties = BTreeMap::new();
+        for (id, offset) in offsets
+        {
+            reader.seek(section_offset.saturating_add(offset))?;
+            properties.insert(id, PropertyValue::read(&mut reader, codepage)?);
+        }
+
+        Ok(SummaryInfo {
+            properties
+        })
+    }
+
+    pub(crate) fn from_properties(properties: BTreeMap<u32, PropertyValue>) -> SummaryInfo
+    {
+        SummaryInfo {
+            properties
+        }
+    }
+
+    #[doc = "Serializes the properties as a single-section property set, the inverse of `parse`. Strings are encoded in the codepage given by the Codepage property; characters it cannot represent become `?`."]
+    pub fn to_bytes(&self) -> Vec<u8>
+    {
+        let codepage = self.properties.get(&PID_CODEPAGE).map_or(0, string_codepage);
+        let table_size = 8 + self.properties.len() * 8;
+        let mut values = Vec::new();
+        let mut offsets = Vec::new();
+        for (id, value) in &self.properties
+        {
+            offsets.push((*id, (table_size + values.len()) as u32));
+            value.write(&mut values, codepage);
+        }
+
+        let mut data = Vec::new();
+        data.extend_from_slice(&BYTE_ORDER_MARK.to_le_bytes());
+        data.extend_from_slice(&[0, 0, 10, 0, 2, 0]);
+        data.extend_from_slice(&[0u8; 16]);
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(&FMTID_SUMMARY_INFORMATION);
+        data.extend_from_slice(&48u32.to_le_bytes());
+        data.extend_from_slice(&((table_size + values.len()) as u32).to_le_bytes());
+        data.extend_from_slice(&(self.properties.len() as u32).to_le_bytes());
+        for (id, offset) in offsets
+        {
+            data.extend_from_slice(&id.to_le_bytes());
+            data.extend_from_slice(&offset.to_le_bytes());
+        }
+        data.extend_from_slice(&values);
+        data
+    }
+
+    #[doc = "Replaces the summary information stream of the package at `path` with these properties. An existing digital signature no longer matches the package."]
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<()>
+    {
+        let mut compound = compound::open_rw(path)?;
+        compound.create_stream(SUMMARY_INFO_STREAM)?.write_all(&self.to_bytes())?;
+        compound.flush()?;
+        Ok(())
+    }
+
+    #[doc = "Sets the property with the given id, replacing any previous value."]
+    pub fn set(&mut self, id: u32, value: PropertyValue) {
+        self.properties.insert(id, value);
+    }
+
+    #[doc = "Removes the property with the given id and returns its value."]
+    pub fn remove(&mut self, id: u32) -> Option<PropertyValue> {
+        self.properties.remove(&id)
+    }
+
+    #[doc = "Sets the Template property, e.g. `x64;1033`."]
+    pub fn set_template(&mut self, template: &str) {
+        self.set(PID_TEMPLATE, PropertyValue::Str(template.to_string()));
+    }
+
+    #[doc = "Sets the Revision Number of a package to the given package code."]
+    pub fn set_package_code(&mut self, package_code: MsiGuid) {
+        self.set(PID_REVNUMBER, PropertyValue::Str(package_code.to_string()));
+    }
+
+    #[doc = "Gives the package a new random package code and returns it. Every build of a package that differs from a previous one needs a new package code."]
+    pub fn regenerate_package_code(&mut self) -> MsiGuid
+    {
+        let package_code = MsiGuid::generate();
+        self.set_package_code(package_code);
+        package_code
+    }
+
+    #[doc = "Sets a time property such as `PID_LASTSAVE_DTM`."]
+    pub fn set_time(&mut self, id: u32, time: SystemTime) {
+        self.set(id, PropertyValue::FileTime(system_time_to_filetime(time)));
+    }
+
+    #[doc = "Returns the raw value of the property with the given id."]
+    pub fn get(&self, id: u32) -> Option<&PropertyValue> {
+        self.properties.get(&id)
+    }
+
+    #[doc = "Returns the string value of the property with the given id."]
+    pub fn get_str(&self, id: u32) -> Option<&str> {
+        self.get(id).and_then(|value| value.as_str())
+    }
+
+    #[doc = "Returns the integer value of the property with the given id."]
+    pub fn get_int(&self, id: u32) -> Option<i32> {
+        self.get(id).and_then(|value| value.as_int())
+    }
+
+    #[doc = "Returns the time stored in the property with the given id."]
+    pub fn get_time(&self, id: u32) -> Option<SystemTime> {
+        match self.get(id)
+        {
+            Some(PropertyValue::FileTime(value)) => filetime_to_system_time(*value),
+            _ => None
+        }
+    }
+
+    #[doc = "Returns all properties ordered by their id."]
+    pub fn properties(&self) -> impl Iterator<Item = (u32, &PropertyValue)> {
+        self.properties.iter().map(|(id, value)| (*id, value))
+    }
+
+    #[doc = "Returns the code page of the strings in the property set."]
+    pub fn codepage(&self) -> Option<i32> {
+        self.get_int(PID_CODEPAGE)
+    }
+
+    #[doc = "Returns the Title property, which describes the kind of package, e.g. `Installation Database`."]
+    pub fn title(&self) -> Option<&str> {
+        self.get_str(PID_TITLE)
+    }
+
+    #[doc = "Returns the Subject property, usually the product name."]
+    pub fn subject(&self) -> Option<&str> {
+        self.get_str(PID_SUBJECT)
+    }
+
+    #[doc = "Returns the Author property, usually the manufacturer."]
+    pub fn author(&self) -> Option<&str> {
+        self.get_str(PID_AUTHOR)
+    }
+
+    #[doc = "Returns the Keywords property."]
+    pub fn keywords(&self) -> Option<&str> {
+        self.get_str(PID_KEYWORDS)
+    }
+
+    #[doc = "Returns the Comments property."]
+    pub fn comments(&self) -> Option<&str> {
+        self.get_str(PID_COMMENTS)
+    }
+
+    #[doc = "Returns the Template property (platform and languages for packages, target products for patches)."]
+    pub fn template(&self) -> Option<&str> {
+        self.get_str(PID_TEMPLATE)
+    }
+
+    #[doc = "Returns the platform named by the Template of a package, or `None` if there is no Template or it names an unknown platform."]
+    pub fn platform(&self) -> Option<Platform> {
+        self.template().and_then(|template| Platform::parse(template.split(';').next().unwrap_or_default()))
+    }
+
+    #[doc = "Returns the languages listed by the Template of a package, in order. Language-neutral packages list 0; entries that are not numbers are skipped."]
+    pub fn languages(&self) -> Vec<MsiLanguage> {
+        self.template().and_then(|template| template.split_once(';')).map(|(_, languages)| MsiLanguage::parse_list(languages)).unwrap_or_default()
+    }
+
+    #[doc = "Returns the Revision Number property (package code for packages, patch codes for patches)."]
+    pub fn revision(&self) -> Option<&str> {
+        self.get_str(PID_REVNUMBER)
+    }
+
+    #[doc = "Returns the Revision Number of a package as its package code, or `None` if it is not a single GUID."]
+    pub fn package_code(&self) -> Option<MsiGuid> {
+        self.revision().and_then(|revision| MsiGuid::parse(revision).ok())
+    }
+
+    #[doc = "Returns the Last Saved By property (transform substorages for patches)."]
+    pub fn last_saved_by(&self) -> Option<&str> {
+        self.get_str(PID_LASTAUTHOR)
+    }
+
+    #[doc = "Returns the Last Printed property, the time an administrative image was created."]
+    pub fn last_printed(&self) -> Option<SystemTime> {
+        self.get_time(PID_LASTPRINTED)
+    }
+
+    #[doc = "Returns the Create Time/Date property."]
+    pub fn create_time(&self) -> Option<SystemTime> {
+        self.get_time(PID_CREATE_DTM)
+    }
+
+    #[doc = "Returns the Last Saved Time/Date property."]
+    pub fn last_save_time(&self) -> Option<SystemTime> {
+        self.get_time(PID_LASTSAVE_DTM)
+    }
+
+    #[doc = "Returns the Page Count property: the minimum Windows Installer version required, times 100 (e.g. 500 for 5.0)."]
+    pub fn minimum_installer_version(&self) -> Option<i32> {
+        self.get_int(PID_PAGECOUNT)
+    }
+
+    #[doc = "Returns the Word Count property of a package."]
+    pub fn word_count(&self) -> Option<WordCount> {
+        self.get_int(PID_WORDCOUNT).map(WordCount)
+    }
+
+    #[doc = "Returns the Character Count property (validation flags for transforms)."]
+    pub fn character_count(&self) -> Option<i32> {
+        self.get_int(PID_CHARCOUNT)
+    }
+
+    #[doc = "Returns the Creating Application property."]
+    pub fn application_name(&self) -> Option<&str> {
+        self.get_str(PID_APPNAME)
+    }
+
+    #[doc = "Returns the Security property."]
+    pub fn security(&self) -> Option<Security> {
+        self.get_int(PID_SECURITY).map(Security::from)
+    }
+}
+
+fn system_time_to_filetime(time: SystemTime) -> u64
+{
+    match time.duration_since(SystemTime::UNIX_EPOCH)
+    {
+        Ok(elapsed) => FILETIME_UNIX_EPOCH.saturating_add((elapsed.as_nanos() / 100) as u64),
+        Err(before) => FILETIME_UNIX_EPOCH.saturating_sub((before.duration().as_nanos() / 100) as u64)
+    }
+}
+
+fn filetime_to_system_time(filetime: u64) -> Option<SystemTime>
+{
+    if filetime >= FILETIME_UNIX_EPOCH
+    {
+        SystemTime::UNIX_EPOCH.checked_add(Duration::from_nanos((filetime - FILETIME_UNIX_EPOCH).saturating_mul(100)))
+    }
+    else
+    {
+        SystemTime::UNIX_EPOCH.checked_sub(Duration::from_nanos((FILETIME_UNIX_EPOCH - filetime).saturating_mul(100)))
+    }
+}
+
+// Returns the codepage a Codepage property names; the I2 value of codepages above 32767, such as
+// 65001, is negative.
+fn string_codepage(value: &PropertyValue) -> u32
+{
+    match value
+    {
+        PropertyValue::I2(codepage) => *codepage as u16 as u32,
+        PropertyValue::I4(codepage) => *codepage as u32,
+        _ => 0
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests
+{
+    use super::*;
+
+    // Builds a minimal single-section property set with string properties only.
+    pub(crate) fn build_summary(properties: &[(u32, &str)]) -> Vec<u8>
+    {
+        let mut values = Vec::new();
+        let mut offsets = Vec::new();
+        let table_size = 8 + properties.len() * 8;
+        for (id, value) in properties
+        {
+            offsets.push((*id, (table_size + values.len()) as u32));
+            let mut bytes = value.as_bytes().to_vec();
+            bytes.push(0);
+            values.extend_from_slice(&VT_LPSTR.to_le_bytes());
+            values.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+            values.extend_from_slice(&bytes);
+            while values.len() % 4 != 0
+            {
+                values.push(0);
+            }
+        }
+
+        let mut data = Vec::new();
+        data.extend_from_slice(&BYTE_ORDER_MARK.to_le_bytes());
+        data.extend_from_slice(&[0, 0, 10, 0, 2, 0]);
+        data.extend_from_slice(&[0u8; 16]);
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(&[0u8; 16]);
+        data.extend_from_slice(&48u32.to_le_bytes());
+        data.extend_from_slice(&((table_size + values.len()) as u32).to_le_bytes());
+        data.extend_from_slice(&(properties.len() as u32).to_le_bytes());
+        for (id, offset) in offsets
+        {
+            data.extend_from_slice(&id.to_le_bytes());
+            data.extend_from_slice(&offset.to_le_bytes());
+        }
+        data.extend_from_slice(&values);
+        data
+    }
+
+    #[test]
+    fn test_parse_strings()
+    {
+        let data = build_summary(&[(PID_TEMPLATE, "x64;1033"), (PID_AUTHOR, "marcin")]);
+        let summary = SummaryInfo::parse(&data).unwrap();
+
+        assert_eq!(summary.template(), Some("x64;1033"));
+        assert_eq!((summary.platform(), summary.languages()), (Some(Platform::X64), vec![MsiLanguage::from(1033)]));
+        assert_eq!(summary.get_str(PID_AUTHOR), Some("marcin"));
+        assert_eq!(summary.revision(), None);
+        assert_eq!(summary.properties().count(), 2);
+
+        assert!(SummaryInfo::parse(&data[..40]).is_err());
+    }
+
+    #[test]
+    fn test_typed_properties()
+    {
+        let mut properties = BTreeMap::new();
+        properties.insert(PID_CODEPAGE, PropertyValue::I2(1252));
+        properties.insert(PID_TITLE, PropertyValue::Str("Installation Database".to_string()));
+        properties.insert(PID_REVNUMBER, PropertyValue::Str("{8B3A5A8E-1F5C-4E4D-9C1B-2F0F3B7A6D10}".to_string()));
+        properties.insert(PID_CREATE_DTM, PropertyValue::FileTime(FILETIME_UNIX_EPOCH + 10_000_000));
+        properties.insert(PID_PAGECOUNT, PropertyValue::I4(500));
+        properties.insert(PID_WORDCOUNT, PropertyValue::I4(10));
+        properties.insert(PID_SECURITY, PropertyValue::I4(2));
+
+        let summary = SummaryInfo::parse(&SummaryInfo::from_properties(properties).to_bytes()).unwrap();
+        assert_eq!((summary.codepage(), summary.title()), (Some(1252), Some("Installation Database")));
+        assert_eq!(summary.package_code().map(|code| code.to_string()).as_deref(), Some("{8B3A5A8E-1F5C-4E4D-9C1B-2F0F3B7A6D10}"));
+        assert_eq!(summary.create_time(), SystemTime::UNIX_EPOCH.checked_add(Duration::from_secs(1)));
+        assert_eq!((summary.last_save_time(), summary.minimum_installer_version()), (None, Some(500)));
+        assert_eq!(summary.security(), Some(Security::ReadOnlyRecommended));
+
+        let mut summary = summary;
+        for (template, platform, languages) in [(";1033,1036", Some(Platform::Intel), vec![1033, 1036]), ("Arm64;0", Some(Platform::Arm64), vec![0]), ("AMD64", Some(Platform::X64), vec![]), ("Alpha;1033", None, vec![1033])]
+        {
+            summary.set_template(template);
+            let languages: Vec<MsiLanguage> = languages.into_iter().map(MsiLanguage::from).collect();
+            assert_eq!((summary.platform(), summary.languages()), (platform, languages), "{}", template);
+        }
+
+        let word_count = summary.word_count().unwrap();
+        assert!(word_count.is_compressed() && !word_count.has_short_names() && !word_count.is_admin_image() && !word_count.requires_elevation());
+    }
+
+    #[test]
+    fn test_codepage_strings()
+    {
+        for (codepage, title, encoded) in [(1252, "Grüße", &b"Gr\xfc\xdfe"[..]), (932, "日本語", &b"\x93\xfa\x96\x7b\x8c\xea"[..])]
+        {
+            let mut summary = SummaryInfo::default();
+            summary.set(PID_CODEPAGE, PropertyValue::I2(codepage));
+            summary.set(PID_TITLE, PropertyValue::Str(title.to_string()));
+
+            let data = summary.to_bytes();
+            assert!(data.windows(encoded.len()).any(|window| window == encoded), "{}", codepage);
+            assert_eq!(SummaryInfo::parse(&data).unwrap(), summary);
+        }
+
+        // UTF-8 has a negative I2 codepage
+        let mut summary = SummaryInfo::default();
+        summary.set(PID_CODEPAGE, PropertyValue::I2(65001u16 as i16));
+        summary.set(PID_TITLE, PropertyValue::Str("Grüße".to_string()));
+        assert_eq!(SummaryInfo::parse(&summary.to_bytes()).unwrap().title(), Some("Grüße"));
+    }
+
+    #[test]
+    fn test_parse_malformed()
+    {
+        let data = build_summary(&[(PID_TEMPLATE, "x64;1033"), (PID_AUTHOR, "marcin")]);
+        // the last byte only pads the final string
+        for length in 0..data.len() - 1
+        {
+            assert!(SummaryInfo::parse(&data[..length]).is_err(), "{}", length);
+        }
+        for offset in 0..data.len()
+        {
+            let mut corrupt = data.clone();
+            corrupt[offset] = 0xff;
+            let _ = SummaryInfo::parse(&corrupt);
+        }
+    }
+}
@@ -0,0 +1,897 @@
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::io;
+use std::sync::{ Arc, OnceLock };
+
+use crate::bytes::ByteReader;
+use crate::csv;
+use crate::error::{ Error, Result };
+use crate::export::OutputOrder;
+use crate::hash::Fnv64;
+use crate::package::MsiPackage;
+use crate::stringpool::StringPool;
+
+const COL_SIZE_MASK: i32 = 0xff;
+const COL_LOCALIZABLE_BIT: i32 = 0x200;
+const COL_NONBINARY_BIT: i32 = 0x400;
+const COL_STRING_BIT: i32 = 0x800;
+const COL_NULLABLE_BIT: i32 = 0x1000;
+const COL_PRIMARY_KEY_BIT: i32 = 0x2000;
+const COL_VALID_BIT: i32 = 0x100;
+
+#[doc = "The storage type of a table column."]
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ColumnType {
+    Int16,
+    Int32,
+    #[doc = "A string with the given maximum length (0 means unlimited)."]
+    Str(usize),
+    #[doc = "A binary column whose cells refer to streams named `Table.Key`."]
+    Binary
+}
+
+#[doc = "A column of a database table, as declared in the `_Columns` catalog."]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Column {
+    name: String,
+    column_type: ColumnType,
+    nullable: bool,
+    primary_key: bool,
+    localizable: bool
+}
+
+impl Column {
+
+    pub(crate) fn from_bits(name: &str, bits: i32) -> Result<Column>
+    {
+        let size = (bits & COL_SIZE_MASK) as usize;
+        let column_type = if bits & COL_STRING_BIT != 0
+        {
+            if size == 0 && bits & COL_NONBINARY_BIT == 0
+            {
+                ColumnType::Binary
+            }
+            else
+            {
+                ColumnType::Str(size)
+            }
+        }
+        else
+        {
+            match size
+            {
+                1 | 2 => ColumnType::Int16,
+                4 => ColumnType::Int32,
+                _ => return Err(Error::invalid(format!("column '{}' has an invalid integer size {}", name, size)))
+            }
+        };
+
+        Ok(Column {
+            name: name.to_string(),
+            column_type,
+            nullable: bits & COL_NULLABLE_BIT != 0,
+            primary_key: bits & COL_PRIMARY_KEY_BIT != 0,
+            localizable: bits & COL_LOCALIZABLE_BIT != 0
+        })
+    }
+
+    pub(crate) fn new(name: &str, column_type: ColumnType, nullable: bool, primary_key: bool, localizable: bool) -> Column
+    {
+        Column {
+            name: name.to_string(),
+            column_type,
+            nullable,
+            primary_key,
+            localizable
+        }
+    }
+
+    #[doc = "Returns the name of the column."]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[doc = "Returns the storage type of the column."]
+    pub fn column_type(&self) -> ColumnType {
+        self.column_type
+    }
+
+    #[doc = "Returns a boolean value indicating whether the column accepts null values."]
+    pub fn is_nullable(&self) -> bool {
+        self.nullable
+    }
+
+    #[doc = "Returns a boolean value indicating whether the column is part of the primary key."]
+    pub fn is_primary_key(&self) -> bool {
+        self.primary_key
+    }
+
+    #[doc = "Returns a boolean value indicating whether the column is localizable."]
+    pub fn is_localizable(&self) -> bool {
+        self.localizable
+    }
+
+    #[doc = "Returns the `_Columns` type bits of the column, the inverse of `from_bits`."]
+    pub(crate) fn bits(&self) -> i32
+    {
+        let mut bits = match self.column_type
+        {
+            ColumnType::Int16 => COL_VALID_BIT | COL_NONBINARY_BIT | 2,
+            ColumnType::Int32 => COL_VALID_BIT | COL_NONBINARY_BIT | 4,
+            ColumnType::Str(size) => COL_VALID_BIT | COL_NONBINARY_BIT | COL_STRING_BIT | (size as i32 & COL_SIZE_MASK),
+            ColumnType::Binary => COL_VALID_BIT | COL_STRING_BIT
+        };
+        if self.nullable
+        {
+            bits |= COL_NULLABLE_BIT;
+        }
+        if self.primary_key
+        {
+            bits |= COL_PRIMARY_KEY_BIT;
+        }
+        if self.localizable
+        {
+            bits |= COL_LOCALIZABLE_BIT;
+        }
+
+        bits
+    }
+
+    pub(crate) fn width(&self, long_refs: bool) -> usize
+    {
+        match self.column_type
+        {
+            ColumnType::Int16 => 2,
+            ColumnType::Int32 => 4,
+            ColumnType::Str(_) | ColumnType::Binary => if long_refs { 3 } else { 2 }
+        }
+    }
+}
+
+#[doc = "The declared schema of a table: its name and columns in catalog order."]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TableSchema<'a> {
+    name: &'a str,
+    columns: &'a [Column]
+}
+
+impl<'a> TableSchema<'a> {
+
+    pub(crate) fn new(name: &'a str, columns: &'a [Column]) -> Self
+    {
+        TableSchema {
+            name,
+            columns
+        }
+    }
+
+    #[doc = "Returns the name of the table."]
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    #[doc = "Returns the columns in catalog order."]
+    pub fn columns(&self) -> &'a [Column] {
+        self.columns
+    }
+
+    #[doc = "Returns the column with the given name."]
+    pub fn column(&self, name: &str) -> Option<&'a Column> {
+        self.columns.iter().find(|column| column.name == name)
+    }
+
+    #[doc = "Returns the primary key columns in catalog order."]
+    pub fn primary_keys(&self) -> impl Iterator<Item = &'a Column> {
+        self.columns.iter().filter(|column| column.primary_key)
+    }
+}
+
+#[doc = "The value of a single table cell. Strings are shared with the string pool, so cells referring to the same string do not allocate."]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Value {
+    Null,
+    Int(i32),
+    Str(Arc<str>)
+}
+
+impl Value {
+
+    #[doc = "Returns a boolean value indicating whether the cell is null."]
+    pub fn is_null(&self) -> bool {
+        *self == Value::Null
+    }
+
+    #[doc = "Returns the integer value of the cell, if it holds one."]
+    pub fn as_int(&self) -> Option<i32> {
+        match self
+        {
+            Value::Int(value) => Some(*value),
+            _ => None
+        }
+    }
+
+    #[doc = "Returns the string value of the cell, if it holds one."]
+    pub fn as_str(&self) -> Option<&str> {
+        match self
+        {
+            Value::Str(value) => Some(value),
+            _ => None
+        }
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Value {
+        Value::Str(Arc::from(value))
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Value {
+        Value::Str(Arc::from(value))
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self
+        {
+            Value::Null => Ok(()),
+            Value::Int(value) => write!(fmt, "{}", value),
+            Value::Str(value) => write!(fmt, "{}", value)
+        }
+    }
+}
+
+#[doc = "A cell interpreted by the type of its column, borrowed from the table."]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TypedValue<'a> {
+    Null,
+    Int16(i16),
+    Int32(i32),
+    Str(&'a str),
+    #[doc = "The name of the stream holding the data of a binary cell, e.g. `Binary.Icon`, to be read with `MsiPackage::read_stream`."]
+    Stream(&'a str)
+}
+
+impl<'a> TypedValue<'a> {
+
+    fn of(column: &Column, value: &'a Value) -> TypedValue<'a>
+    {
+        match (value, column.column_type)
+        {
+            (Value::Null, _) => TypedValue::Null,
+            (Value::Int(value), ColumnType::Int16) => TypedValue::Int16(*value as i16),
+            // integers in string columns only come from lenient decoding
+            (Value::Int(value), _) => TypedValue::Int32(*value),
+            (Value::Str(value), ColumnType::Binary) => TypedValue::Stream(value),
+            (Value::Str(value), _) => TypedValue::Str(value)
+        }
+    }
+}
+
+#[doc = "A string cell whose bytes are not valid text; its value reads with U+FFFD replacement characters."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct EscapedCell {
+    row: usize,
+    column: usize,
+    text: String
+}
+
+impl EscapedCell {
+
+    #[doc = "Returns the position of the row within its table."]
+    pub fn row(&self) -> usize {
+        self.row
+    }
+
+    #[doc = "Returns the position of the column."]
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    #[doc = "Returns the cell with undecodable bytes written as `\\xNN` and backslashes doubled, which is valid UTF-8 and keeps the original bytes."]
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+#[doc = "How cells whose stored value does not fit the declared type of their column are read."]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum CellCoercion {
+    #[doc = "The table fails to decode, with an error naming the table, column and row."]
+    #[default]
+    Strict,
+    #[doc = "The cell is read as the integer it stores and listed in `Table::mismatches`; incomplete trailing rows are ignored."]
+    Lenient
+}
+
+#[doc = "A cell of a string column whose stored value is not a reference into the string pool, typically integer data in a column declared as string."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct TypeMismatch {
+    row: usize,
+    column: usize,
+    stored: u32
+}
+
+impl TypeMismatch {
+
+    #[doc = "Returns the position of the row within its table."]
+    pub fn row(&self) -> usize {
+        self.row
+    }
+
+    #[doc = "Returns the position of the column."]
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    #[doc = "Returns the stored value, which the cell holds as an integer."]
+    pub fn stored(&self) -> u32 {
+        self.stored
+    }
+}
+
+#[doc = "The decoded contents of a database table."]
+pub struct Table {
+    name: String,
+    columns: Vec<Column>,
+    #[doc = "The cells of all rows, row after row."]
+    cells: Vec<Value>,
+    len: usize,
+    escaped: Vec<EscapedCell>,
+    mismatches: Vec<TypeMismatch>,
+    trailing_bytes: usize,
+    row_hashes: OnceLock<Vec<u64>>,
+    content_hash: OnceLock<u64>
+}
+
+impl Table {
+
+    pub(crate) fn decode(name: &str, columns: Vec<Column>, data: &[u8], strings: &StringPool, coercion: CellCoercion) -> Result<Table>
+    {
+        let selected = vec![true; columns.len()];
+        Self::decode_columns(name, columns, &selected, data, strings, coercion)
+    }
+
+    #[doc = "Decodes only the selected columns; the bytes of the others are skipped. The selection must include the primary key columns, from which the stream names of binary cells are derived."]
+    pub(crate) fn decode_columns(name: &str, columns: Vec<Column>, selected: &[bool], data: &[u8], strings: &StringPool, coercion: CellCoercion) -> Result<Table>
+    {
+        let long_refs = strings.long_refs();
+        let row_size: usize = columns.iter().map(|column| column.width(long_refs)).sum();
+        let count = data.len().checked_div(row_size).unwrap_or(0);
+        let trailing_bytes = data.len().checked_rem(row_size).unwrap_or(0);
+        if trailing_bytes > 0 && coercion == CellCoercion::Strict
+        {
+            return Err(Error::invalid(format!("table '{}' has {} bytes, which is not a whole number of rows of {} bytes; the declared column types do not match the stored data",
+                name, data.len(), row_size)));
+        }
+        let width = selected.iter().filter(|selected| **selected).count();
+
+        // cells are stored column by column
+        let mut reader = ByteReader::new(data);
+        let mut cells = vec![Value::Null; count * width];
+        let mut escaped = Vec::new();
+        let mut mismatches = Vec::new();
+        let mut column_index = 0;
+        let mut kept = Vec::with_capacity(width);
+        for (column, selected) in columns.into_iter().zip(selected)
+        {
+            if !*selected
+            {
+                reader.skip(column.width(long_refs) * count)?;
+                continue;
+            }
+
+            for index in 0..count
+            {
+                let value = match column.column_type
+                {
+                    ColumnType::Int16 => match reader.read_u16()?
+                    {
+                        0 => Value::Null,
+                        raw => Value::Int((raw ^ 0x8000) as i16 as i32)
+                    },
+                    ColumnType::Int32 => match reader.read_u32()?
+                    {
+                        0 => Value::Null,
+                        raw => Value::Int((raw ^ 0x8000_0000) as i32)
+                    },
+                    ColumnType::Str(_) | ColumnType::Binary => {
+                        let mut id = reader.read_u16()? as u32;
+                        if long_refs
+                        {
+                            id |= (reader.read_u8()? as u32) << 16;
+                        }
+
+                        if id == 0
+                        {
+                            Value::Null
+                        }
+                        else if column.column_type == ColumnType::Binary
+                        {
+                            // the stream name is derived from the row key, which is filled in below
+                            Value::Str(Arc::from(""))
+                        }
+                        else if let Some(value) = strings.get(id)
+                        {
+                            if let Some(text) = strings.escaped(id)
+                            {
+                                escaped.push(EscapedCell {
+                                    row: index,
+                                    column: column_index,
+                                    text: text.to_string()
+                                });
+                            }
+                            Value::Str(value.clone())
+                        }
+                        else if coercion == CellCoercion::Lenient
+                        {
+                            mismatches.push(TypeMismatch {
+                                row: index,
+                                column: column_index,
+                                stored: id
+                            });
+                            Value::Int(id as i32)
+                        }
+                        else
+                        {
+                            return Err(Error::invalid(format!("table '{}' column '{}' row {} holds {}, which is not a string reference as the string pool has {} strings; the column may hold integer data",
+                                name, column.name, index, id, strings.len())));
+                        }
+                    }
+                };
+
+                cells[index * width + column_index] = value;
+            }
+
+            kept.push(column);
+            column_index += 1;
+        }
+
+        let mut table = Table {
+            name: name.to_string(),
+            columns: kept,
+            cells,
+            len: count,
+            escaped,
+            mismatches,
+            trailing_bytes,
+            row_hashes: OnceLock::new(),
+            content_hash: OnceLock::new()
+        };
+
+        let binary: Vec<usize> = (0..width).filter(|index| table.columns[*index].column_type == ColumnType::Binary).collect();
+        if !binary.is_empty()
+        {
+            for index in 0..count
+            {
+                let stream: Arc<str> = Arc::from(format!("{}.{}", table.name, table.row(index).key()));
+                for column in &binary
+                {
+                    let value = &mut table.cells[index * width + column];
+                    if !value.is_null()
+                    {
+                        *value = Value::Str(stream.clone());
+                    }
+                }
+            }
+        }
+
+        Ok(table)
+    }
+
+    fn row_hashes(&self) -> &[u64]
+    {
+        self.row_hashes.get_or_init(|| self.rows().map(|row| hash_row(row.values())).collect())
+    }
+
+    fn compute_content_hash(&self) -> u64
+    {
+        // the table hash covers the schema and the set of rows, but not their storage order
+        let mut sorted = self.row_hashes().to_vec();
+        sorted.sort_unstable();
+
+        let mut hasher = Fnv64::new();
+        hasher.write_str(&self.name);
+        for column in &self.columns
+        {
+            hasher.write_str(&column.name);
+            hasher.write(format!("{:?}", column.column_type).as_bytes());
+            hasher.write(&[column.nullable as u8, column.primary_key as u8, column.localizable as u8]);
+        }
+        for hash in sorted
+        {
+            hasher.write_u64(hash);
+        }
+
+        hasher.finish()
+    }
+
+    #[doc = "Returns a stable hash of the schema and rows of the table, independent of row order. Tables with equal hashes can be treated as identical without comparing their rows."]
+    pub fn content_hash(&self) -> u64 {
+        *self.content_hash.get_or_init(|| self.compute_content_hash())
+    }
+
+    #[doc = "Returns the name of the table."]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[doc = "Returns the columns of the table in their declared order."]
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    #[doc = "Returns the position of the column with the given name."]
+    pub fn column_index(&self, name: &str) -> Option<usize> {
+        self.columns.iter().position(|column| column.name == name)
+    }
+
+    #[doc = "Returns the number of rows."]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[doc = "Returns a boolean value indicating whether the table has no rows."]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[doc = "Returns the row at the given position."]
+    pub fn row(&self, index: usize) -> Row<'_> {
+        Row {
+            table: self,
+            index
+        }
+    }
+
+    #[doc = "Returns the string cells whose bytes could not be decoded, ordered by column and then by row."]
+    pub fn escaped_cells(&self) -> &[EscapedCell] {
+        &self.escaped
+    }
+
+    #[doc = "Returns the cells read as integers because they do not fit their string column; only lenient decoding yields any."]
+    pub fn mismatches(&self) -> &[TypeMismatch] {
+        &self.mismatches
+    }
+
+    #[doc = "Returns the number of bytes after the last whole row, which lenient decoding ignores."]
+    pub fn trailing_bytes(&self) -> usize {
+        self.trailing_bytes
+    }
+
+    #[doc = "Returns an iterator over all rows in storage order."]
+    pub fn rows(&self) -> impl Iterator<Item = Row<'_>> {
+        (0..self.len).map(move |index| self.row(index))
+    }
+
+    #[doc = "Returns all rows sorted by their primary key values, comparing integers numerically, and by the remaining cells for rows with equal keys. The order depends only on the content of the table."]
+    pub fn sorted_rows(&self) -> Vec<Row<'_>>
+    {
+        let keys: Vec<usize> = (0..self.columns.len()).filter(|index| self.columns[*index].primary_key).collect();
+        let mut indexes: Vec<usize> = (0..self.len).collect();
+        indexes.sort_by(|first, second| {
+            let (first, second) = (self.row(*first).values(), self.row(*second).values());
+            keys.iter().map(|key| &first[*key]).cmp(keys.iter().map(|key| &second[*key])).then_with(|| first.cmp(second))
+        });
+
+        indexes.into_iter().map(|index| self.row(index)).collect()
+    }
+
+    #[doc = "Writes the table as CSV: a header row of column names, then one line per row in the given order, each ended by CRLF. Null cells are empty fields, integers are written bare and strings quoted, so spreadsheet tools and pandas read back the types. Binary cells hold the name of their stream."]
+    pub fn write_csv<W: io::Write>(&self, order: OutputOrder, output: &mut W) -> Result<()>
+    {
+        self.write_csv_cells(order, output, |_| Ok(None))
+    }
+
+    #[doc = "Writes the table as `write_csv` does, but with binary cells holding the data of their stream in `package` as base64. Cells whose stream is missing are written as null."]
+    pub fn write_csv_with_streams<W: io::Write>(&self, package: &MsiPackage, order: OutputOrder, output: &mut W) -> Result<()>
+    {
+        let streams: HashSet<String> = package.stream_names()?.into_iter().collect();
+        self.write_csv_cells(order, output, |stream| {
+            if streams.contains(stream)
+            {
+                Ok(Some(csv::string(&csv::base64(&package.read_stream(stream)?))))
+            }
+            else
+            {
+                Ok(Some(String::new()))
+            }
+        })
+    }
+
+    fn write_csv_cells<W: io::Write, F: Fn(&str) -> Result<Option<String>>>(&self, order: OutputOrder, output: &mut W, binary: F) -> Result<()>
+    {
+        let header: Vec<String> = self.columns.iter().map(|column| csv::string(&column.name)).collect();
+        write!(output, "{}\r\n", header.join(","))?;
+
+        for row in order.rows(self)
+        {
+            let mut fields = Vec::with_capacity(self.columns.len());
+            for (column, value) in self.columns.iter().zip(row.values())
+            {
+                let field = match value
+                {
+                    Value::Str(stream) if column.column_type == ColumnType::Binary => binary(stream)?,
+                    _ => None
+                };
+                fields.push(field.unwrap_or_else(|| csv::value(value)));
+            }
+            write!(output, "{}\r\n", fields.join(","))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[doc = "The row a model object was read from: its table, primary key and position in storage order."]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RowOrigin {
+    table: String,
+    key: String,
+    index: usize
+}
+
+impl RowOrigin {
+
+    #[doc = "Returns the name of the table."]
+    pub fn table(&self) -> &str {
+        &self.table
+    }
+
+    #[doc = "Returns the primary key of the row, with multiple key columns joined by '.'."]
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    #[doc = "Returns the position of the row within its table."]
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl Display for RowOrigin {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "{} [{}] (row {})", self.table, self.key, self.index)
+    }
+}
+
+#[doc = "A single row of a table."]
+#[derive(Clone, Copy)]
+pub struct Row<'a> {
+    table: &'a Table,
+    index: usize
+}
+
+impl<'a> Row<'a> {
+
+    #[doc = "Returns the position of the row within its table."]
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    #[doc = "Returns the table this row belongs to."]
+    pub fn table(&self) -> &'a Table {
+        self.table
+    }
+
+    #[doc = "Returns the cells of the row in column order."]
+    pub fn values(&self) -> &'a [Value] {
+        let width = self.table.columns.len();
+        &self.table.cells[self.index * width..(self.index + 1) * width]
+    }
+
+    #[doc = "Returns the cell of the given column, or `None` if the table has no such column."]
+    pub fn get(&self, column: &str) -> Option<&'a Value> {
+        self.table.column_index(column).map(|index| &self.values()[index])
+    }
+
+    #[doc = "Returns the cell of the given column interpreted by the column type, or `None` if the table has no such column."]
+    pub fn typed(&self, column: &str) -> Option<TypedValue<'a>> {
+        self.table.column_index(column).map(|index| TypedValue::of(&self.table.columns[index], &self.values()[index]))
+    }
+
+    #[doc = "Returns the cells of the row in column order, interpreted by their column types."]
+    pub fn typed_values(&self) -> impl Iterator<Item = TypedValue<'a>> {
+        self.table.columns.iter().zip(self.values()).map(|(column, value)| TypedValue::of(column, value))
+    }
+
+    #[doc = "Returns the string cell of the given column."]
+    pub fn str(&self, column: &str) -> Option<&'a str> {
+        self.get(column).and_then(|value| value.as_str())
+    }
+
+    #[doc = "Returns the integer cell of the given column."]
+    pub fn int(&self, column: &str) -> Option<i32> {
+        self.get(column).and_then(|value| value.as_int())
+    }
+
+    #[doc = "Returns a stable hash of the cells of the row."]
+    pub fn content_hash(&self) -> u64 {
+        self.table.row_hashes()[self.index]
+    }
+
+    #[doc = "Returns the primary key of the row, with multiple key columns joined by '.'."]
+    pub fn key(&self) -> String {
+        self.table.columns.iter()
+            .zip(self.values())
+            .filter(|(column, _)| column.primary_key)
+            .map(|(_, value)| value.to_string())
+            .collect::<Vec<String>>()
+            .join(".")
+    }
+
+    #[doc = "Returns the table, primary key and position of the row, to be kept by objects read from it."]
+    pub fn origin(&self) -> RowOrigin
+    {
+        RowOrigin {
+            table: self.table.name.clone(),
+            key: self.key(),
+            index: self.index
+        }
+    }
+}
+
+fn hash_row(row: &[Value]) -> u64
+{
+    let mut hasher = Fnv64::new();
+    for value in row
+    {
+        match value
+        {
+            Value::Null => hasher.write(&[0]),
+            Value::Int(value) => {
+                hasher.write(&[1]);
+                hasher.write(&value.to_le_bytes());
+            },
+            Value::Str(value) => {
+                hasher.write(&[2]);
+                hasher.write_str(value);
+            }
+        }
+    }
+
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::testutil::TestPackage;
+
+    fn table(rows: Vec<Vec<Value>>) -> Table
+    {
+        Table {
+            name: "Property".to_string(),
+            columns: vec![Column::from_bits("Property", 0x2d48).unwrap(), Column::from_bits("Value", 0x0f00).unwrap()],
+            len: rows.len(),
+            cells: rows.into_iter().flatten().collect(),
+            escaped: Vec::new(),
+            mismatches: Vec::new(),
+            trailing_bytes: 0,
+            row_hashes: OnceLock::new(),
+            content_hash: OnceLock::new()
+        }
+    }
+
+    #[test]
+    fn test_content_hash()
+    {
+        let row = |name: &str, value: Value| vec![Value::from(name), value];
+        let first = table(vec![row("A", Value::from("1")), row("B", Value::Null)]);
+        let reordered = table(vec![row("B", Value::Null), row("A", Value::from("1"))]);
+        let changed = table(vec![row("A", Value::from("1")), row("B", Value::from(""))]);
+
+        assert_eq!(first.content_hash(), reordered.content_hash());
+        assert_ne!(first.content_hash(), changed.content_hash());
+        assert_eq!(first.row(0).content_hash(), reordered.row(1).content_hash());
+        assert_ne!(first.row(1).content_hash(), changed.row(1).content_hash());
+    }
+
+    #[test]
+    fn test_type_mismatch()
+    {
+        let mut writer = crate::stringpool::StringPoolWriter::default();
+        writer.intern("ProductName");
+        let (pool, data) = writer.write(1252);
+        let strings = StringPool::parse(&pool, &data).unwrap();
+        let columns = || vec![Column::from_bits("Property", 0x0d48).unwrap(), Column::from_bits("Order", 0x1502).unwrap()];
+        let selected = [true, true];
+
+        // the second row stores 7 in the string column, beyond the single pooled string
+        let stored = [1, 0, 7, 0, 0x05, 0x80, 0, 0];
+        let error = Table::decode_columns("Property", columns(), &selected, &stored, &strings, CellCoercion::Strict).err().unwrap();
+        assert!(error.to_string().contains("column 'Property' row 1 holds 7"));
+
+        let table = Table::decode_columns("Property", columns(), &selected, &stored, &strings, CellCoercion::Lenient).unwrap();
+        assert_eq!(table.row(0).values(), [Value::from("ProductName"), Value::Int(5)]);
+        assert_eq!(table.row(1).values(), [Value::Int(7), Value::Null]);
+        assert_eq!(table.mismatches(), [TypeMismatch { row: 1, column: 0, stored: 7 }]);
+
+        let truncated = [1, 0, 0x05, 0x80, 0];
+        assert!(Table::decode_columns("Property", columns(), &selected, &truncated, &strings, CellCoercion::Strict).is_err());
+        let table = Table::decode_columns("Property", columns(), &selected, &truncated, &strings, CellCoercion::Lenient).unwrap();
+        assert_eq!((table.len(), table.trailing_bytes()), (1, 1));
+    }
+
+    #[test]
+    fn test_sorted_rows()
+    {
+        let row = |name: &str, value: Value| vec![Value::from(name), value];
+        let table = table(vec![row("b", Value::Null), row("B", Value::Int(10)), row("A", Value::Int(2))]);
+        let keys: Vec<String> = table.sorted_rows().iter().map(|row| row.key()).collect();
+        assert_eq!(keys, ["A", "B", "b"]);
+        assert_eq!(table.sorted_rows()[0].origin().to_string(), "Property [A] (row 2)");
+    }
+
+    #[test]
+    fn test_write_csv()
+    {
+        let table = table(vec![
+            vec![Value::from("Quote"), Value::from("say \"hi\", then\r\nleave")],
+            vec![Value::from("Null"), Value::Null],
+            vec![Value::from("Number"), Value::Int(-3)]
+        ]);
+        let mut output = Vec::new();
+        table.write_csv(OutputOrder::Stored, &mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "\"Property\",\"Value\"\r\n\"Quote\",\"say \"\"hi\"\", then\r\nleave\"\r\n\"Null\",\r\n\"Number\",-3\r\n");
+
+        let mut output = Vec::new();
+        table.write_csv(OutputOrder::Sorted, &mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "\"Property\",\"Value\"\r\n\"Null\",\r\n\"Number\",-3\r\n\"Quote\",\"say \"\"hi\"\", then\r\nleave\"\r\n");
+    }
+
+    #[test]
+    fn test_write_csv_with_streams()
+    {
+        let package = TestPackage::new("table-csv-streams", |builder| {
+            builder.table("Binary", vec![
+                msi::Column::build("Name").primary_key().id_string(72),
+                msi::Column::build("Data").binary()
+            ], vec![
+                vec![msi::Value::from("Icon"), msi::Value::from("Binary.Icon")],
+                vec![msi::Value::from("Missing"), msi::Value::from("Binary.Missing")]
+            ]);
+            builder.stream("Binary.Icon", b"icon!");
+        });
+        let package = MsiPackage::open(package.path()).unwrap();
+        let table = package.table("Binary").unwrap();
+
+        let mut output = Vec::new();
+        table.write_csv(OutputOrder::Stored, &mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "\"Name\",\"Data\"\r\n\"Icon\",\"Binary.Icon\"\r\n\"Missing\",\"Binary.Missing\"\r\n");
+
+        let mut output = Vec::new();
+        table.write_csv_with_streams(&package, OutputOrder::Stored, &mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "\"Name\",\"Data\"\r\n\"Icon\",\"aWNvbiE=\"\r\n\"Missing\",\r\n");
+        assert_eq!([csv::base64(b""), csv::base64(b"ic"), csv::base64(b"ico")], ["", "aWM=", "aWNv"]);
+    }
+
+    #[test]
+    fn test_decode_malformed()
+    {
+        let mut writer = crate::stringpool::StringPoolWriter::default();
+        writer.intern("ProductName");
+        let (pool, data) = writer.write(1252);
+        let strings = StringPool::parse(&pool, &data).unwrap();
+        let columns = || vec![Column::from_bits("Property", 0x0d48).unwrap(), Column::from_bits("Order", 0x1502).unwrap(), Column::from_bits("Data", 0x0900).unwrap()];
+
+        let stored = [1, 0, 1, 0, 0x05, 0x80, 0, 0, 1, 0, 0, 0];
+        for offset in 0..stored.len()
+        {
+            for value in [0, 0xff]
+            {
+                let mut corrupt = stored;
+                corrupt[offset] = value;
+                let _ = Table::decode("Property", columns(), &corrupt, &strings, CellCoercion::Strict);
+                let _ = Table::decode("Property", columns(), &corrupt, &strings, CellCoercion::Lenient);
+            }
+            let _ = Table::decode("Property", columns(), &stored[..offset], &strings, CellCoercion::Lenient);
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use std::fs::{ File, OpenOptions };
+use std::io::Write;
+use std::path::{ Path, PathBuf };
+
+#[doc = "A package written to a temporary file for the duration of a test."]
+pub(crate) struct TestPackage {
+    path: PathBuf
+}
+
+#[doc = "Collects the tables of a test package before it is written."]
+pub(crate) struct TestPackageBuilder {
+    package: msi::Package<File>
+}
+
+impl TestPackageBuilder {
+
+    pub(crate) fn codepage(&mut self, codepage: msi::CodePage)
+    {
+        self.package.set_database_codepage(codepage);
+    }
+
+    pub(crate) fn stream(&mut self, name: &str, data: &[u8])
+    {
+        self.package.write_stream(name).unwrap().write_all(data).unwrap();
+    }
+
+    pub(crate) fn table(&mut self, name: &str, columns: Vec<msi::Column>, rows: Vec<Vec<msi::Value>>)
+    {
+        self.package.create_table(name, columns).unwrap();
+        if !rows.is_empty()
+        {
+            self.package.insert_rows(msi::Insert::into(name).rows(rows)).unwrap();
+        }
+    }
+}
+
+impl TestPackage {
+
+    pub(crate) fn new<F: FnOnce(&mut TestPackageBuilder)>(tag: &str, build: F) -> TestPackage
+    {
+        Self::with_type(tag, msi::PackageType::Installer, build)
+    }
+
+    pub(crate) fn with_type<F: FnOnce(&mut TestPackageBuilder)>(tag: &str, package_type: msi::PackageType, build: F) -> TestPackage
+    {
+        let path = std::env::temp_dir().join(format!("msi-reader-{}-{}.msi", tag, std::process::id()));
+        let file = OpenOptions::new().read(true).write(true).create(true).truncate(true).open(&path).unwrap();
+        let mut builder = TestPackageBuilder {
+            package: msi::Package::create(package_type, file).unwrap()
+        };
+
+        build(&mut builder);
+        builder.package.flush().unwrap();
+
+        TestPackage {
+            path
+        }
+    }
+
+    pub(crate) fn path(&self) -> &Path
+    {
+        &self.path
+    }
+}
+
+impl Drop for TestPackage {
+    fn drop(&mut self)
+    {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
@@ -0,0 +1,833 @@
+use std::collections::{ BTreeMap, BTreeSet, HashSet };
+use std::io::{ Read, Seek, Write };
+use std::path::Path;
+
+use crate::bytes::ByteReader;
+use crate::compound;
+use crate::error::{ Error, Result };
+use crate::package::{ MsiPackage, COLUMNS_TABLE, STRING_DATA_STREAM, STRING_POOL_STREAM, TABLES_TABLE };
+use crate::streamname;
+use crate::stringpool::{ StringPool, StringPoolWriter };
+use crate::summary::{ PropertyValue, SummaryInfo, PID_CHARCOUNT, PID_CODEPAGE, PID_LASTAUTHOR, PID_PAGECOUNT, PID_REVNUMBER, PID_TEMPLATE, PID_TITLE, SUMMARY_INFO_STREAM };
+use crate::table::{ Column, ColumnType, Table, Value };
+use crate::writer::{ self, Writer };
+
+#[doc = "The CLSID of the root storage of a transform (.mst)."]
+const TRANSFORM_CLSID: u128 = 0x000c_1082_0000_0000_c000_0000_0000_0046;
+
+// the first bit of a row mask marks a complete row, with the number of columns in the high byte
+const FULL_ROW_BIT: u16 = 1;
+
+#[doc = "What a transform does to a table."]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TableOperation {
+    Added,
+    Dropped,
+    #[doc = "The table exists in both packages; rows or columns were changed."]
+    Modified
+}
+
+#[doc = "What a transform does to a row."]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RowOperation {
+    Insert,
+    Update,
+    Delete
+}
+
+#[doc = "A row inserted, updated or deleted by a transform."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct RowChange {
+    operation: RowOperation,
+    key: String,
+    values: Vec<Option<Value>>
+}
+
+impl RowChange {
+
+    #[doc = "Returns what the transform does to the row."]
+    pub fn operation(&self) -> RowOperation {
+        self.operation
+    }
+
+    #[doc = "Returns the primary key of the row, with multiple key columns joined by '.'."]
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    #[doc = "Returns the cells in column order. Key columns are always present; other columns are `None` where the transform leaves them unchanged."]
+    pub fn values(&self) -> &[Option<Value>] {
+        &self.values
+    }
+}
+
+#[doc = "The changes a transform makes to a single table."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct TableTransform {
+    name: String,
+    operation: TableOperation,
+    columns: Vec<Column>,
+    added_columns: usize,
+    rows: Vec<RowChange>
+}
+
+impl TableTransform {
+
+    fn new(name: &str, operation: TableOperation, columns: Vec<Column>) -> TableTransform
+    {
+        TableTransform {
+            name: name.to_string(),
+            operation,
+            columns,
+            added_columns: 0,
+            rows: Vec::new()
+        }
+    }
+
+    #[doc = "Returns the name of the table."]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[doc = "Returns what the transform does to the table."]
+    pub fn operation(&self) -> TableOperation {
+        self.operation
+    }
+
+    #[doc = "Returns the columns of the table once the transform is applied. Empty for dropped tables."]
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    #[doc = "Returns the columns the transform appends to the table; all of them for added tables."]
+    pub fn added_columns(&self) -> &[Column] {
+        &self.columns[self.columns.len() - self.added_columns..]
+    }
+
+    #[doc = "Returns the inserted, updated and deleted rows."]
+    pub fn rows(&self) -> &[RowChange] {
+        &self.rows
+    }
+
+    fn row_change(&self, operation: RowOperation, values: Vec<Option<Value>>) -> RowChange
+    {
+        let key = self.columns.iter()
+            .zip(&values)
+            .filter(|(column, _)| column.is_primary_key())
+            .map(|(_, value)| value.as_ref().map(|value| value.to_string()).unwrap_or_default())
+            .collect::<Vec<String>>()
+            .join(".");
+
+        let values = self.columns.iter()
+            .zip(values)
+            .map(|(column, value)| match value
+            {
+                // binary cells refer to the stream named after the row
+                Some(Value::Str(_)) if column.column_type() == ColumnType::Binary => Some(Value::from(format!("{}.{}", self.name, key))),
+                value => value
+            })
+            .collect();
+
+        RowChange {
+            operation,
+            key,
+            values
+        }
+    }
+}
+
+#[doc = "A database transform (.mst): the changes turning one package into another."]
+pub struct MsiTransform {
+    summary: SummaryInfo,
+    tables: Vec<TableTransform>,
+    streams: BTreeMap<String, Vec<u8>>
+}
+
+impl MsiTransform {
+
+    #[doc = "Computes the transform from `old` to `new`. Tables are matched by name and rows by primary key; a table may gain columns at its end but not lose or change existing ones, which transforms cannot express."]
+    pub fn generate(old: &MsiPackage, new: &MsiPackage) -> Result<MsiTransform>
+    {
+        let names: BTreeSet<&str> = old.table_names().chain(new.table_names()).collect();
+        let old_streams = old.digests()?.streams();
+        let new_streams = new.digests()?.streams();
+        let changed_stream = |name: &str| old_streams.get(name) != new_streams.get(name);
+
+        let mut tables = Vec::new();
+        for name in names
+        {
+            let table = match (old.optional_table(name)?, new.optional_table(name)?)
+            {
+                (Some(_), None) => Some(TableTransform::new(name, TableOperation::Dropped, Vec::new())),
+                (None, Some(new_table)) => {
+                    let mut table = TableTransform::new(name, TableOperation::Added, new_table.columns().to_vec());
+                    table.added_columns = table.columns.len();
+                    table.rows = new_table.rows()
+                        .map(|row| table.row_change(RowOperation::Insert, row.values().iter().cloned().map(Some).collect()))
+                        .collect();
+                    Some(table)
+                },
+                (Some(old_table), Some(new_table)) => compare_tables(&old_table, &new_table, &changed_stream)?,
+                (None, None) => None
+            };
+
+            tables.extend(table);
+        }
+
+        let mut streams = BTreeMap::new();
+        for table in &tables
+        {
+            for row in &table.rows
+            {
+                for (column, value) in table.columns.iter().zip(&row.values)
+                {
+                    if let (ColumnType::Binary, Some(Value::Str(stream))) = (column.column_type(), value)
+                    {
+                        streams.insert(stream.to_string(), new.read_stream(stream)?);
+                    }
+                }
+            }
+        }
+
+        let product = |package: &MsiPackage| -> Result<String> {
+            Ok(format!("{}{}", package.property("ProductCode")?.unwrap_or_default(), package.property("ProductVersion")?.unwrap_or_default()))
+        };
+
+        let mut properties = BTreeMap::new();
+        if let Some(codepage) = new.summary().get(PID_CODEPAGE)
+        {
+            properties.insert(PID_CODEPAGE, codepage.clone());
+        }
+        properties.insert(PID_TITLE, PropertyValue::Str("Transform".to_string()));
+        properties.insert(PID_TEMPLATE, PropertyValue::Str(old.summary().template().unwrap_or_default().to_string()));
+        properties.insert(PID_LASTAUTHOR, PropertyValue::Str(new.summary().template().unwrap_or_default().to_string()));
+        properties.insert(PID_REVNUMBER, PropertyValue::Str(format!("{};{};{}", product(old)?, product(new)?, new.property("UpgradeCode")?.unwrap_or_default())));
+        properties.insert(PID_PAGECOUNT, new.summary().get(PID_PAGECOUNT).cloned().unwrap_or(PropertyValue::I4(200)));
+        // no validation conditions and no errors suppressed
+        properties.insert(PID_CHARCOUNT, PropertyValue::I4(0));
+
+        Ok(MsiTransform {
+            summary: SummaryInfo::from_properties(properties),
+            tables,
+            streams
+        })
+    }
+
+    #[doc = "Opens a transform file. The base package supplies the columns of the tables the transform changes and decides whether a complete row is an insert or an update."]
+    pub fn open<P: AsRef<Path>>(path: P, base: &MsiPackage) -> Result<MsiTransform>
+    {
+        let mut compound = compound::open(path)?;
+        Self::read(&mut compound, Path::new("/"), base)
+    }
+
+    #[doc = "Reads a transform stored in the given storage of a compound file, such as a transform substorage of a patch."]
+    pub(crate) fn read<F: Read + Seek>(compound: &mut cfb::CompoundFile<F>, storage: &Path, base: &MsiPackage) -> Result<MsiTransform>
+    {
+        let summary = SummaryInfo::parse(&read_stream(compound, &storage.join(SUMMARY_INFO_STREAM))?)?;
+        let strings = StringPool::parse(
+            &read_stream(compound, &storage.join(streamname::encode(STRING_POOL_STREAM, true)))?,
+            &read_stream(compound, &storage.join(streamname::encode(STRING_DATA_STREAM, true)))?)?;
+
+        let mut tables: BTreeMap<String, TableTransform> = BTreeMap::new();
+        let tables_stream = storage.join(streamname::encode(TABLES_TABLE, true));
+        if compound.is_stream(&tables_stream)
+        {
+            let mut catalog = TableTransform::new(TABLES_TABLE, TableOperation::Modified, vec![Column::from_bits("Name", 0x2d40)?]);
+            decode_rows(&mut catalog, &read_stream(compound, &tables_stream)?, &strings, &HashSet::new())?;
+            for row in catalog.rows
+            {
+                if let Some(Some(Value::Str(name))) = row.values.first()
+                {
+                    let operation = if row.operation == RowOperation::Delete { TableOperation::Dropped } else { TableOperation::Added };
+                    tables.insert(name.to_string(), TableTransform::new(name, operation, Vec::new()));
+                }
+            }
+        }
+
+        let columns_stream = storage.join(streamname::encode(COLUMNS_TABLE, true));
+        if compound.is_stream(&columns_stream)
+        {
+            let mut catalog = TableTransform::new(COLUMNS_TABLE, TableOperation::Modified, vec![
+                Column::from_bits("Table", 0x2d40)?,
+                Column::from_bits("Number", 0x2502)?,
+                Column::from_bits("Name", 0x2d40)?,
+                Column::from_bits("Type", 0x0502)?
+            ]);
+            decode_rows(&mut catalog, &read_stream(compound, &columns_stream)?, &strings, &HashSet::new())?;
+
+            // new columns may leave their number null, which appends them
+            let mut added: BTreeMap<String, Vec<(i32, Column)>> = BTreeMap::new();
+            for row in catalog.rows.iter().filter(|row| row.operation != RowOperation::Delete)
+            {
+                let cell = |index: usize| row.values.get(index).cloned().flatten();
+                match (cell(0), cell(1), cell(2), cell(3))
+                {
+                    (Some(Value::Str(table)), number, Some(Value::Str(name)), Some(Value::Int(bits))) => {
+                        let number = number.and_then(|number| number.as_int()).unwrap_or(i32::MAX);
+                        added.entry(table.to_string()).or_default().push((number, Column::from_bits(&name, bits)?));
+                    },
+                    _ => return Err(Error::invalid(format!("transform _Columns row '{}' is incomplete", row.key)))
+                }
+            }
+
+            for (name, mut columns) in added
+            {
+                columns.sort_by_key(|(number, _)| *number);
+                let table = match tables.remove(&name)
+                {
+                    Some(table) => table,
+                    None => TableTransform::new(&name, TableOperation::Modified, base.table(&name)?.columns().to_vec())
+                };
+
+                let mut table = table;
+                table.added_columns = columns.len();
+                table.columns.extend(columns.into_iter().map(|(_, column)| column));
+                tables.insert(name, table);
+            }
+        }
+
+        let mut streams = BTreeMap::new();
+        let entries: Vec<(String, bool)> = compound.read_storage(storage)?
+            .filter(|entry| entry.is_stream() && entry.name() != SUMMARY_INFO_STREAM)
+            .map(|entry| streamname::decode(entry.name()))
+            .collect();
+        for (name, is_table) in entries
+        {
+            let data = read_stream(compound, &storage.join(streamname::encode(&name, is_table)))?;
+            if !is_table
+            {
+                streams.insert(name, data);
+                continue;
+            }
+            if [STRING_POOL_STREAM, STRING_DATA_STREAM, TABLES_TABLE, COLUMNS_TABLE].contains(&name.as_str())
+            {
+                continue;
+            }
+
+            let base_table = base.optional_table(&name)?;
+            let table = match tables.get_mut(&name)
+            {
+                Some(table) => table,
+                None => {
+                    let columns = base_table.as_ref()
+                        .ok_or_else(|| Error::invalid(format!("the transform changes table '{}', which the base package does not have", name)))?
+                        .columns().to_vec();
+                    tables.entry(name.clone()).or_insert_with(|| TableTransform::new(&name, TableOperation::Modified, columns))
+                }
+            };
+
+            let existing: HashSet<String> = match &base_table
+            {
+                Some(base_table) if table.operation == TableOperation::Modified => base_table.rows().map(|row| row.key()).collect(),
+                _ => HashSet::new()
+            };
+            decode_rows(table, &data, &strings, &existing)?;
+        }
+
+        Ok(MsiTransform {
+            summary,
+            tables: tables.into_values().collect(),
+            streams
+        })
+    }
+
+    #[doc = "Returns the summary information of the transform. Its Template and Last Saved By properties name the platform and languages of the old and new package; Revision Number lists their product codes and versions."]
+    pub fn summary(&self) -> &SummaryInfo {
+        &self.summary
+    }
+
+    #[doc = "Returns the changed tables, ordered by name."]
+    pub fn tables(&self) -> &[TableTransform] {
+        &self.tables
+    }
+
+    #[doc = "Returns the names of the streams the transform adds or replaces, such as the contents of new `Binary` rows."]
+    pub fn stream_names(&self) -> impl Iterator<Item = &str> {
+        self.streams.keys().map(|name| name.as_str())
+    }
+
+    #[doc = "Returns a boolean value indicating whether the transform changes nothing."]
+    pub fn is_empty(&self) -> bool {
+        self.tables.is_empty() && self.streams.is_empty()
+    }
+
+    #[doc = "Writes the transform to a file in the format read by Windows Installer."]
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<()>
+    {
+        let mut catalog_tables = Vec::new();
+        let mut catalog_columns = Vec::new();
+        let mut table_streams = Vec::new();
+        for table in &self.tables
+        {
+            let name = Value::from(table.name.as_str());
+            match table.operation
+            {
+                TableOperation::Added => catalog_tables.push(((1 << 8) | FULL_ROW_BIT, vec![(ColumnType::Str(64), name.clone())])),
+                TableOperation::Dropped => catalog_tables.push((0, vec![(ColumnType::Str(64), name.clone())])),
+                TableOperation::Modified => {}
+            }
+
+            let first_added = table.columns.len() - table.added_columns;
+            for (index, column) in table.columns.iter().enumerate().skip(first_added)
+            {
+                catalog_columns.push(((4 << 8) | FULL_ROW_BIT, vec![
+                    (ColumnType::Str(64), name.clone()),
+                    (ColumnType::Int16, Value::Int(index as i32 + 1)),
+                    (ColumnType::Str(64), Value::from(column.name())),
+                    (ColumnType::Int16, Value::Int(column.bits()))
+                ]));
+            }
+
+            if !table.rows.is_empty()
+            {
+                let rows = table.rows.iter().map(|row| encode_row(table, row)).collect::<Result<Vec<_>>>()?;
+                table_streams.push((table.name.clone(), rows));
+            }
+        }
+        table_streams.insert(0, (COLUMNS_TABLE.to_string(), catalog_columns));
+        table_streams.insert(0, (TABLES_TABLE.to_string(), catalog_tables));
+
+        let mut strings = StringPoolWriter::default();
+        for (_, rows) in &table_streams
+        {
+            for (_, cells) in rows
+            {
+                for (column_type, value) in cells
+                {
+                    if let (ColumnType::Str(_) | ColumnType::Binary, Value::Str(value)) = (column_type, value)
+                    {
+                        strings.intern(value);
+                    }
+                }
+            }
+        }
+
+        let mut compound = cfb::create(path)?;
+        compound.set_storage_clsid("/", uuid::Uuid::from_u128(TRANSFORM_CLSID))?;
+        compound.create_stream(SUMMARY_INFO_STREAM)?.write_all(&self.summary.to_bytes())?;
+
+        // strings are kept as UTF-8; a pool of plain ASCII is marked codepage neutral
+        let (pool, data) = strings.write(if strings.is_ascii() { 0 } else { 65001 });
+        compound.create_stream(streamname::encode(STRING_POOL_STREAM, true))?.write_all(&pool)?;
+        compound.create_stream(streamname::encode(STRING_DATA_STREAM, true))?.write_all(&data)?;
+
+        let long_refs = strings.long_refs();
+        for (name, rows) in table_streams.iter().filter(|(_, rows)| !rows.is_empty())
+        {
+            let mut data = Vec::new();
+            for (mask, cells) in rows
+            {
+                data.extend_from_slice(&mask.to_le_bytes());
+                for (column_type, value) in cells
+                {
+                    write_cell(&mut data, *column_type, value, &strings, long_refs);
+                }
+            }
+            compound.create_stream(streamname::encode(name, true))?.write_all(&data)?;
+        }
+
+        for (name, data) in &self.streams
+        {
+            compound.create_stream(streamname::encode(name, false))?.write_all(data)?;
+        }
+
+        compound.flush()?;
+        Ok(())
+    }
+
+    #[doc = "Applies the transform to a copy of the base package written to `output`; the base itself is not modified. Complete rows whose key already exists replace that row, as Windows Installer does."]
+    pub fn apply<P: AsRef<Path>, Q: AsRef<Path>>(&self, base: P, output: Q) -> Result<()>
+    {
+        std::fs::copy(base, &output)?;
+        self.apply_in_place(output)
+    }
+
+    #[doc = "Applies the transform to the package at `path`, modifying it."]
+    pub(crate) fn apply_in_place<P: AsRef<Path>>(&self, path: P) -> Result<()>
+    {
+        let mut writer = writer::open(path)?;
+
+        for table in self.tables.iter().filter(|table| table.operation == TableOperation::Dropped)
+        {
+            writer.drop_table(&table.name)?;
+        }
+        for table in self.tables.iter().filter(|table| table.operation == TableOperation::Added)
+        {
+            writer.create_table(table.name.as_str(), table.columns.iter().map(writer::column).collect())?;
+        }
+        for table in self.tables.iter().filter(|table| table.operation == TableOperation::Modified && table.added_columns > 0)
+        {
+            // the writer cannot alter tables, so the table is recreated with its rows padded with nulls
+            let rows: Vec<Vec<msi::Value>> = writer.select_rows(msi::Select::table(table.name.as_str()))?
+                .map(|row| {
+                    let mut values: Vec<msi::Value> = (0..row.len()).map(|index| row[index].clone()).collect();
+                    values.resize(table.columns.len(), msi::Value::Null);
+                    values
+                })
+                .collect();
+
+            writer.drop_table(&table.name)?;
+            writer.create_table(table.name.as_str(), table.columns.iter().map(writer::column).collect())?;
+            if !rows.is_empty()
+            {
+                writer.insert_rows(msi::Insert::into(table.name.as_str()).rows(rows))?;
+            }
+        }
+
+        for table in &self.tables
+        {
+            for row in &table.rows
+            {
+                apply_row(&mut writer, table, row)?;
+            }
+        }
+
+        for (name, data) in &self.streams
+        {
+            writer.write_stream(name)?.write_all(data)?;
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+fn compare_tables(old: &Table, new: &Table, changed_stream: &dyn Fn(&str) -> bool) -> Result<Option<TableTransform>>
+{
+    let has_binary = new.columns().iter().any(|column| column.column_type() == ColumnType::Binary);
+    if old.content_hash() == new.content_hash() && !has_binary
+    {
+        return Ok(None);
+    }
+
+    if new.columns().len() < old.columns().len() || new.columns()[..old.columns().len()] != *old.columns()
+    {
+        return Err(Error::invalid(format!("table '{}' removes or changes columns, which a transform cannot express", new.name())));
+    }
+
+    let mut table = TableTransform::new(new.name(), TableOperation::Modified, new.columns().to_vec());
+    table.added_columns = new.columns().len() - old.columns().len();
+
+    let old_rows: BTreeMap<String, _> = old.rows().map(|row| (row.key(), row)).collect();
+    let new_rows: BTreeMap<String, _> = new.rows().map(|row| (row.key(), row)).collect();
+    for (key, old_row) in &old_rows
+    {
+        if !new_rows.contains_key(key)
+        {
+            let values = table.columns.iter().zip(old_row.values())
+                .map(|(column, value)| if column.is_primary_key() { Some(value.clone()) } else { None })
+                .collect();
+            table.rows.push(table.row_change(RowOperation::Delete, values));
+        }
+    }
+
+    for (key, new_row) in &new_rows
+    {
+        let old_row = match old_rows.get(key)
+        {
+            Some(old_row) => old_row,
+            None => {
+                let change = table.row_change(RowOperation::Insert, new_row.values().iter().cloned().map(Some).collect());
+                table.rows.push(change);
+                continue;
+            }
+        };
+
+        let changed: Vec<usize> = table.columns.iter().enumerate()
+            .filter(|(index, column)| {
+                let old_value = old_row.values().get(*index).unwrap_or(&Value::Null);
+                let new_value = &new_row.values()[*index];
+                old_value != new_value || match new_value
+                {
+                    Value::Str(stream) if column.column_type() == ColumnType::Binary => changed_stream(stream),
+                    _ => false
+                }
+            })
+            .map(|(index, _)| index)
+            .collect();
+        if changed.is_empty()
+        {
+            continue;
+        }
+
+        // the column mask of a partial update only covers the first 16 columns
+        let complete = changed.iter().any(|index| *index >= 16);
+        let values = table.columns.iter().zip(new_row.values()).enumerate()
+            .map(|(index, (column, value))| if complete || column.is_primary_key() || changed.contains(&index) { Some(value.clone()) } else { None })
+            .collect();
+        table.rows.push(table.row_change(RowOperation::Update, values));
+    }
+
+    if table.rows.is_empty() && table.added_columns == 0
+    {
+        return Ok(None);
+    }
+
+    Ok(Some(table))
+}
+
+fn decode_rows(table: &mut TableTransform, data: &[u8], strings: &StringPool, existing: &HashSet<String>) -> Result<()>
+{
+    let long_refs = strings.long_refs();
+    let mut reader = ByteReader::new(data);
+    while reader.remaining() > 0
+    {
+        let mask = reader.read_u16()?;
+        let mut values = Vec::with_capacity(table.columns.len());
+        for (index, column) in table.columns.iter().enumerate()
+        {
+            let present = if mask & FULL_ROW_BIT != 0
+            {
+                index < (mask >> 8) as usize
+            }
+            else
+            {
+                column.is_primary_key() || (index < 16 && mask & (1 << index) != 0)
+            };
+
+            values.push(if present { Some(read_cell(&mut reader, column, strings, long_refs, &table.name)?) } else { None });
+        }
+
+        let operation = match mask
+        {
+            0 => RowOperation::Delete,
+            _ if mask & FULL_ROW_BIT == 0 => RowOperation::Update,
+            _ => RowOperation::Insert
+        };
+        let mut change = table.row_change(operation, values);
+        if change.operation == RowOperation::Insert && existing.contains(&change.key)
+        {
+            change.operation = RowOperation::Update;
+        }
+
+        table.rows.push(change);
+    }
+
+    Ok(())
+}
+
+fn read_cell(reader: &mut ByteReader, column: &Column, strings: &StringPool, long_refs: bool, table: &str) -> Result<Value>
+{
+    Ok(match column.column_type()
+    {
+        ColumnType::Int16 => match reader.read_u16()?
+        {
+            0 => Value::Null,
+            raw => Value::Int((raw ^ 0x8000) as i16 as i32)
+        },
+        ColumnType::Int32 => match reader.read_u32()?
+        {
+            0 => Value::Null,
+            raw => Value::Int((raw ^ 0x8000_0000) as i32)
+        },
+        ColumnType::Str(_) | ColumnType::Binary => {
+            let mut id = reader.read_u16()? as u32;
+            if long_refs
+            {
+                id |= (reader.read_u8()? as u32) << 16;
+            }
+
+            match id
+            {
+                0 => Value::Null,
+                // replaced by the stream name once the key is known
+                _ if column.column_type() == ColumnType::Binary => Value::from(""),
+                _ => Value::Str(strings.get(id)
+                    .ok_or_else(|| Error::invalid(format!("transform of table '{}' refers to unknown string {}", table, id)))?
+                    .clone())
+            }
+        }
+    })
+}
+
+fn encode_row(table: &TableTransform, row: &RowChange) -> Result<(u16, Vec<(ColumnType, Value)>)>
+{
+    let complete = row.operation == RowOperation::Insert || row.values.iter().all(|value| value.is_some());
+    let mut mask = 0;
+    let mut cells = Vec::new();
+    for (index, (column, value)) in table.columns.iter().zip(&row.values).enumerate()
+    {
+        if row.operation == RowOperation::Delete && !column.is_primary_key()
+        {
+            continue;
+        }
+        if complete
+        {
+            cells.push((column.column_type(), value.clone().unwrap_or(Value::Null)));
+            continue;
+        }
+
+        if let Some(value) = value
+        {
+            if !column.is_primary_key()
+            {
+                if index >= 16
+                {
+                    return Err(Error::invalid(format!("the update of {} [{}] changes column {} beyond the first 16", table.name, row.key, index + 1)));
+                }
+                mask |= 1 << index;
+            }
+            cells.push((column.column_type(), value.clone()));
+        }
+    }
+
+    if complete && row.operation != RowOperation::Delete
+    {
+        mask = ((table.columns.len() as u16) << 8) | FULL_ROW_BIT;
+    }
+
+    Ok((mask, cells))
+}
+
+fn write_cell(data: &mut Vec<u8>, column_type: ColumnType, value: &Value, strings: &StringPoolWriter, long_refs: bool)
+{
+    match (column_type, value)
+    {
+        (ColumnType::Int16, Value::Int(value)) => data.extend_from_slice(&((*value as u16) ^ 0x8000).to_le_bytes()),
+        (ColumnType::Int16, _) => data.extend_from_slice(&0u16.to_le_bytes()),
+        (ColumnType::Int32, Value::Int(value)) => data.extend_from_slice(&((*value as u32) ^ 0x8000_0000).to_le_bytes()),
+        (ColumnType::Int32, _) => data.extend_from_slice(&0u32.to_le_bytes()),
+        (_, value) => {
+            let id = value.as_str().map(|value| strings.id(value)).unwrap_or(0);
+            data.extend_from_slice(&(id as u16).to_le_bytes());
+            if long_refs
+            {
+                data.push((id >> 16) as u8);
+            }
+        }
+    }
+}
+
+fn apply_row(writer: &mut Writer, table: &TableTransform, row: &RowChange) -> Result<()>
+{
+    let name = table.name.as_str();
+    let keys = || table.columns.iter().zip(&row.values)
+        .filter(|(column, _)| column.is_primary_key())
+        .map(|(column, value)| (column, value.as_ref().unwrap_or(&Value::Null)));
+
+    if row.operation == RowOperation::Delete
+    {
+        return writer::delete_row(writer, name, &table.columns, keys(), &row.key);
+    }
+
+    if writer.select_rows(msi::Select::table(name).with(writer::key_condition(name, keys())?))?.next().is_none()
+    {
+        let values = row.values.iter().map(|value| writer::value(value.as_ref().unwrap_or(&Value::Null))).collect();
+        writer.insert_rows(msi::Insert::into(name).row(values))?;
+        return Ok(());
+    }
+
+    let mut update = msi::Update::table(name);
+    let mut changed = false;
+    for (column, value) in table.columns.iter().zip(&row.values)
+    {
+        if let (false, Some(value)) = (column.is_primary_key(), value)
+        {
+            update = update.set(column.name(), writer::value(value));
+            changed = true;
+        }
+    }
+    if changed
+    {
+        writer.update_rows(update.with(writer::key_condition(name, keys())?))?;
+    }
+
+    Ok(())
+}
+
+fn read_stream<F: Read + Seek>(compound: &mut cfb::CompoundFile<F>, path: &Path) -> Result<Vec<u8>>
+{
+    if !compound.is_stream(path)
+    {
+        return Err(Error::NotFound(format!("stream '{}'", path.display())));
+    }
+
+    let mut data = Vec::new();
+    compound.open_stream(path)?.read_to_end(&mut data)?;
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::diff::PackageDiff;
+    use crate::testutil::TestPackage;
+
+    fn build(tag: &str, version: &str, obsolete: bool, feature: bool) -> TestPackage
+    {
+        TestPackage::new(tag, |builder| {
+            let mut properties = vec![
+                vec![msi::Value::from("ProductName"), msi::Value::from("Alpha")],
+                vec![msi::Value::from("ProductVersion"), msi::Value::from(version)]
+            ];
+            if obsolete
+            {
+                properties.push(vec![msi::Value::from("ARPNOREPAIR"), msi::Value::from("1")]);
+            }
+            builder.table("Property", vec![
+                msi::Column::build("Property").primary_key().id_string(72),
+                msi::Column::build("Value").localizable().text_string(0)
+            ], properties);
+
+            let mut columns = vec![
+                msi::Column::build("Component").primary_key().id_string(72),
+                msi::Column::build("Directory_").id_string(72)
+            ];
+            let mut row = vec![msi::Value::from("Main"), msi::Value::from("TARGETDIR")];
+            if feature
+            {
+                columns.push(msi::Column::build("Attributes").nullable().int16());
+                row.push(msi::Value::Int(4));
+                builder.table("Feature", vec![
+                    msi::Column::build("Feature").primary_key().id_string(38),
+                    msi::Column::build("Level").int16()
+                ], vec![vec![msi::Value::from("Complete"), msi::Value::Int(1)]]);
+            }
+            else
+            {
+                builder.table("Obsolete", vec![msi::Column::build("Key").primary_key().id_string(72)], Vec::new());
+            }
+            builder.table("Component", columns, vec![row]);
+        })
+    }
+
+    #[test]
+    fn test_generate_and_apply()
+    {
+        let old_package = build("transform-old", "1.0", true, false);
+        let new_package = build("transform-new", "2.0", false, true);
+        let old = MsiPackage::open(old_package.path()).unwrap();
+        let new = MsiPackage::open(new_package.path()).unwrap();
+
+        let generated = MsiTransform::generate(&old, &new).unwrap();
+        assert!(MsiTransform::generate(&old, &old).unwrap().is_empty());
+
+        let path = std::env::temp_dir().join(format!("msi-reader-transform-{}.mst", std::process::id()));
+        generated.write(&path).unwrap();
+        let transform = MsiTransform::open(&path, &old).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        let operations: Vec<(&str, TableOperation)> = transform.tables().iter().map(|table| (table.name(), table.operation())).collect();
+        assert!(operations.contains(&("Feature", TableOperation::Added)));
+        assert!(operations.contains(&("Obsolete", TableOperation::Dropped)));
+        assert!(operations.contains(&("Component", TableOperation::Modified)));
+
+        let property = transform.tables().iter().find(|table| table.name() == "Property").unwrap();
+        let rows: Vec<(&str, RowOperation)> = property.rows().iter().map(|row| (row.key(), row.operation())).collect();
+        assert_eq!(rows, [("ARPNOREPAIR", RowOperation::Delete), ("ProductVersion", RowOperation::Update)]);
+        assert_eq!(property.rows()[1].values()[1], Some(Value::from("2.0")));
+
+        let component = transform.tables().iter().find(|table| table.name() == "Component").unwrap();
+        assert_eq!(component.added_columns().len(), 1);
+        assert_eq!(transform.summary().get_str(PID_TITLE), Some("Transform"));
+
+        let output = old_package.path().with_extension("transformed.msi");
+        transform.apply(old_package.path(), &output).unwrap();
+        let applied = MsiPackage::open(&output).unwrap();
+        let diff = PackageDiff::compare(&new, &applied).unwrap();
+        let _ = std::fs::remove_file(&output);
+        assert!(diff.added_tables().is_empty() && diff.removed_tables().is_empty());
+        assert!(diff.tables().iter().all(|table| table.table() == "_Validation"), "{:?}", diff.tables());
+    }
+}
@@ -0,0 +1,736 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::customaction::{ ActionKind, CustomActionTable };
+use crate::error::Result;
+use crate::json;
+use crate::language::MsiLanguage;
+use crate::package::MsiPackage;
+use crate::sequence::{ read_actions, ScheduledAction };
+use crate::table::RowOrigin;
+use crate::version::MsiVersion;
+
+const REMOVE_EXISTING_PRODUCTS: &str = "RemoveExistingProducts";
+
+const UPGRADE_MIGRATE_FEATURES: i32 = 0x1;
+const UPGRADE_ONLY_DETECT: i32 = 0x2;
+const UPGRADE_IGNORE_REMOVE_FAILURE: i32 = 0x4;
+const UPGRADE_VERSION_MIN_INCLUSIVE: i32 = 0x100;
+const UPGRADE_VERSION_MAX_INCLUSIVE: i32 = 0x200;
+const UPGRADE_LANGUAGES_EXCLUSIVE: i32 = 0x400;
+
+#[doc = "Where RemoveExistingProducts is scheduled in InstallExecuteSequence, using the four positions documented for major upgrades."]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RepPlacement {
+    #[doc = "Between InstallValidate and InstallInitialize: the old product is removed in its own transaction before the new one is installed."]
+    AfterInstallValidate,
+    #[doc = "After InstallInitialize, before the script is executed: the old product is removed first, but inside the installation transaction."]
+    AfterInstallInitialize,
+    #[doc = "Between InstallExecute (or InstallExecuteAgain) and InstallFinalize: new files are installed first, then the old product is removed in the same transaction."]
+    AfterInstallExecute,
+    #[doc = "After InstallFinalize: the new product is committed before the old one is removed."]
+    AfterInstallFinalize,
+    #[doc = "Any other position, such as before InstallValidate, which Windows Installer does not support."]
+    Unsupported
+}
+
+impl RepPlacement {
+
+    #[doc = "Returns a boolean value indicating whether the old product is gone before the new one is installed, leaving a window without a working application."]
+    pub fn removes_before_install(&self) -> Option<bool> {
+        match self
+        {
+            RepPlacement::AfterInstallValidate | RepPlacement::AfterInstallInitialize => Some(true),
+            RepPlacement::AfterInstallExecute | RepPlacement::AfterInstallFinalize => Some(false),
+            RepPlacement::Unsupported => None
+        }
+    }
+
+    #[doc = "Returns a boolean value indicating whether a failure of either half rolls the machine back to the old product."]
+    pub fn is_rollback_safe(&self) -> Option<bool> {
+        match self
+        {
+            RepPlacement::AfterInstallInitialize | RepPlacement::AfterInstallExecute => Some(true),
+            RepPlacement::AfterInstallValidate | RepPlacement::AfterInstallFinalize => Some(false),
+            RepPlacement::Unsupported => None
+        }
+    }
+
+    #[doc = "Returns a boolean value indicating whether both products are installed side by side at some point, which only works if component rules were followed."]
+    pub fn requires_component_rules(&self) -> Option<bool> {
+        self.removes_before_install().map(|removes| !removes)
+    }
+
+    #[doc = "Returns a short explanation of the upgrade behavior resulting from the placement."]
+    pub fn explanation(&self) -> &'static str {
+        match self
+        {
+            RepPlacement::AfterInstallValidate => "The old product is uninstalled completely before the new one is installed. All files are copied again, and if the new installation fails the old product is not restored.",
+            RepPlacement::AfterInstallInitialize => "The old product is uninstalled first, within the same transaction as the new installation. All files are copied again, but a failure rolls back to the old product.",
+            RepPlacement::AfterInstallExecute => "The new product is installed first and the old one removed afterwards in the same transaction. Only changed files are copied, and a failure of either part rolls back to the old product.",
+            RepPlacement::AfterInstallFinalize => "The new product is committed before the old one is removed. Only changed files are copied; if the removal fails, only the removal is rolled back and both products stay installed.",
+            RepPlacement::Unsupported => "RemoveExistingProducts is scheduled at a position Windows Installer does not support for major upgrades."
+        }
+    }
+}
+
+#[doc = "The scheduling of RemoveExistingProducts and the major-upgrade behavior it results in."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct RepAnalysis {
+    action: ScheduledAction,
+    placement: RepPlacement
+}
+
+impl RepAnalysis {
+
+    #[doc = "Returns the scheduled RemoveExistingProducts row."]
+    pub fn action(&self) -> &ScheduledAction {
+        &self.action
+    }
+
+    #[doc = "Returns the classified placement."]
+    pub fn placement(&self) -> RepPlacement {
+        self.placement
+    }
+}
+
+#[doc = "Classifies where RemoveExistingProducts is scheduled in InstallExecuteSequence. Returns `None` if the package does not schedule it, i.e. it performs no major upgrade removal."]
+pub fn analyze_remove_existing_products(package: &MsiPackage) -> Result<Option<RepAnalysis>>
+{
+    let actions = read_actions(package, "InstallExecuteSequence")?;
+    let sequence_of = |name: &str| actions.iter().find(|scheduled| scheduled.action() == name).and_then(|scheduled| scheduled.sequence());
+
+    let action = match actions.iter().find(|scheduled| scheduled.action() == REMOVE_EXISTING_PRODUCTS)
+    {
+        Some(action) => action.clone(),
+        None => return Ok(None)
+    };
+
+    let placement = match (action.sequence(), sequence_of("InstallValidate"), sequence_of("InstallInitialize"), sequence_of("InstallFinalize"))
+    {
+        (Some(rep), Some(validate), Some(initialize), Some(finalize)) => {
+            let executed_before = ["InstallExecute", "InstallExecuteAgain"].iter()
+                .filter_map(|name| sequence_of(name))
+                .any(|sequence| sequence > initialize && sequence < rep);
+
+            if rep > validate && rep < initialize
+            {
+                RepPlacement::AfterInstallValidate
+            }
+            else if rep > initialize && rep < finalize
+            {
+                if executed_before { RepPlacement::AfterInstallExecute } else { RepPlacement::AfterInstallInitialize }
+            }
+            else if rep > finalize
+            {
+                RepPlacement::AfterInstallFinalize
+            }
+            else
+            {
+                RepPlacement::Unsupported
+            }
+        },
+        _ => RepPlacement::Unsupported
+    };
+
+    Ok(Some(RepAnalysis {
+        action,
+        placement
+    }))
+}
+
+#[doc = "A package in an upgrade graph."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct UpgradeNode {
+    name: String,
+    upgrade_code: Option<String>,
+    product_code: Option<String>,
+    version: Option<String>,
+    language: Option<MsiLanguage>
+}
+
+impl UpgradeNode {
+
+    #[doc = "Returns the name of the package, as given by the caller."]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[doc = "Returns the UpgradeCode property in upper case, if set."]
+    pub fn upgrade_code(&self) -> Option<&str> {
+        self.upgrade_code.as_deref()
+    }
+
+    #[doc = "Returns the ProductCode property, if set."]
+    pub fn product_code(&self) -> Option<&str> {
+        self.product_code.as_deref()
+    }
+
+    #[doc = "Returns the ProductVersion property, if set."]
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+}
+
+#[doc = "A package whose Upgrade table matches another package: `from` removes (or only detects) `to` when installed. Both are indexes into the nodes of the graph."]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UpgradeEdge {
+    from: usize,
+    to: usize,
+    only_detect: bool
+}
+
+impl UpgradeEdge {
+
+    #[doc = "Returns the index of the package whose Upgrade table has the matching row."]
+    pub fn from(&self) -> usize {
+        self.from
+    }
+
+    #[doc = "Returns the index of the package that is matched."]
+    pub fn to(&self) -> usize {
+        self.to
+    }
+
+    #[doc = "Returns a boolean value indicating whether the row only detects the package (`OnlyDetect`) instead of removing it."]
+    pub fn only_detect(&self) -> bool {
+        self.only_detect
+    }
+}
+
+#[doc = "The upgrade lineage of a set of packages: packages grouped by UpgradeCode and ordered by version, with an edge for every package an Upgrade table row matches."]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UpgradeGraph {
+    nodes: Vec<UpgradeNode>,
+    edges: Vec<UpgradeEdge>
+}
+
+impl UpgradeGraph {
+
+    #[doc = "Builds the graph of packages given as (name, package) pairs. Versions are compared on their first three fields and languages against ProductLanguage, as Windows Installer does during FindRelatedProducts."]
+    pub fn build(packages: &[(&str, &MsiPackage)]) -> Result<UpgradeGraph>
+    {
+        let mut nodes = Vec::new();
+        let mut rows = Vec::new();
+        for (name, package) in packages
+        {
+            nodes.push(UpgradeNode {
+                name: name.to_string(),
+                upgrade_code: package.property("UpgradeCode")?.map(|code| code.to_ascii_uppercase()),
+                product_code: package.property("ProductCode")?,
+                version: package.property("ProductVersion")?,
+                language: package.properties()?.product_language()
+            });
+            rows.push(read_upgrades(package)?);
+        }
+
+        // group by UpgradeCode, then order by version; packages without either go last
+        let mut order: Vec<usize> = (0..nodes.len()).collect();
+        order.sort_by(|a, b| {
+            let key = |node: &UpgradeNode| (node.upgrade_code.is_none(), node.upgrade_code.clone(), node.version.as_deref().and_then(parse_version).is_none(), node.version.as_deref().and_then(parse_version));
+            key(&nodes[*a]).cmp(&key(&nodes[*b]))
+        });
+        let nodes: Vec<UpgradeNode> = order.iter().map(|index| nodes[*index].clone()).collect();
+        let rows: Vec<Vec<UpgradeRow>> = order.iter().map(|index| rows[*index].clone()).collect();
+
+        let mut edges = Vec::new();
+        for (from, from_rows) in rows.iter().enumerate()
+        {
+            for (to, node) in nodes.iter().enumerate()
+            {
+                let matches = |row: &&UpgradeRow| node.upgrade_code.as_deref().is_some_and(|code| row.upgrade_code.eq_ignore_ascii_case(code))
+                    && node.version.as_deref().is_some_and(|version| row.matches(version, node.language));
+                if let Some(row) = from_rows.iter().find(|row| from != to && matches(row))
+                {
+                    edges.push(UpgradeEdge { from, to, only_detect: row.attributes.only_detect() });
+                }
+            }
+        }
+
+        Ok(UpgradeGraph {
+            nodes,
+            edges
+        })
+    }
+
+    #[doc = "Returns the packages, grouped by UpgradeCode and ordered by version."]
+    pub fn nodes(&self) -> &[UpgradeNode] {
+        &self.nodes
+    }
+
+    #[doc = "Returns the matches between packages."]
+    pub fn edges(&self) -> &[UpgradeEdge] {
+        &self.edges
+    }
+
+    #[doc = "Returns the packages the given package removes or detects, as indexes into the nodes."]
+    pub fn matched_by(&self, node: usize) -> impl Iterator<Item = &UpgradeEdge> {
+        self.edges.iter().filter(move |edge| edge.from == node)
+    }
+
+    #[doc = "Renders the graph in the Graphviz DOT language, with a cluster per UpgradeCode. Detect-only matches are dashed."]
+    pub fn to_dot(&self) -> String
+    {
+        let mut dot = String::from("digraph upgrades {\n    rankdir=LR;\n    node [shape=box];\n");
+        let mut clusters: BTreeMap<Option<&str>, Vec<usize>> = BTreeMap::new();
+        for (index, node) in self.nodes.iter().enumerate()
+        {
+            clusters.entry(node.upgrade_code()).or_default().push(index);
+        }
+
+        for (cluster, (upgrade_code, members)) in clusters.iter().enumerate()
+        {
+            let _ = writeln!(dot, "    subgraph cluster_{} {{\n        label={};", cluster, json::string(upgrade_code.unwrap_or("no UpgradeCode")));
+            for index in members
+            {
+                let node = &self.nodes[*index];
+                let label = format!("{}\n{}", node.name, node.version().unwrap_or("no version"));
+                let _ = writeln!(dot, "        n{} [label={}];", index, json::string(&label));
+            }
+            dot.push_str("    }\n");
+        }
+
+        for edge in &self.edges
+        {
+            let _ = writeln!(dot, "    n{} -> n{}{};", edge.from, edge.to, if edge.only_detect { " [style=dashed]" } else { "" });
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+#[doc = "The Attributes bit field of an Upgrade row."]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct UpgradeAttributes(i32);
+
+impl UpgradeAttributes {
+
+    #[doc = "Returns the raw bits."]
+    pub fn bits(&self) -> i32 {
+        self.0
+    }
+
+    #[doc = "Returns a boolean value indicating whether the feature states of the matched product are carried over."]
+    pub fn migrates_features(&self) -> bool {
+        self.0 & UPGRADE_MIGRATE_FEATURES != 0
+    }
+
+    #[doc = "Returns a boolean value indicating whether matched products are only detected and never removed."]
+    pub fn only_detect(&self) -> bool {
+        self.0 & UPGRADE_ONLY_DETECT != 0
+    }
+
+    #[doc = "Returns a boolean value indicating whether the installation continues if removing the matched product fails."]
+    pub fn ignores_remove_failure(&self) -> bool {
+        self.0 & UPGRADE_IGNORE_REMOVE_FAILURE != 0
+    }
+
+    #[doc = "Returns a boolean value indicating whether VersionMin itself is part of the range."]
+    pub fn is_min_inclusive(&self) -> bool {
+        self.0 & UPGRADE_VERSION_MIN_INCLUSIVE != 0
+    }
+
+    #[doc = "Returns a boolean value indicating whether VersionMax itself is part of the range."]
+    pub fn is_max_inclusive(&self) -> bool {
+        self.0 & UPGRADE_VERSION_MAX_INCLUSIVE != 0
+    }
+
+    #[doc = "Returns a boolean value indicating whether the row matches all languages except the listed ones."]
+    pub fn is_languages_exclusive(&self) -> bool {
+        self.0 & UPGRADE_LANGUAGES_EXCLUSIVE != 0
+    }
+}
+
+impl From<i32> for UpgradeAttributes {
+    fn from(bits: i32) -> Self {
+        UpgradeAttributes(bits)
+    }
+}
+
+#[doc = "A row of the Upgrade table: the products FindRelatedProducts looks for and the property it lists them in."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct UpgradeRow {
+    upgrade_code: String,
+    version_min: Option<String>,
+    version_max: Option<String>,
+    language: Option<String>,
+    attributes: UpgradeAttributes,
+    remove: Option<String>,
+    action_property: String,
+    origin: RowOrigin
+}
+
+impl UpgradeRow {
+
+    #[doc = "Returns the UpgradeCode of the related products."]
+    pub fn upgrade_code(&self) -> &str {
+        &self.upgrade_code
+    }
+
+    #[doc = "Returns the lower bound of the version range; `None` means no lower bound."]
+    pub fn version_min(&self) -> Option<&str> {
+        self.version_min.as_deref()
+    }
+
+    #[doc = "Returns the upper bound of the version range; `None` means no upper bound."]
+    pub fn version_max(&self) -> Option<&str> {
+        self.version_max.as_deref()
+    }
+
+    #[doc = "Returns the version range in interval notation, e.g. `[1.0.0, 3.0.0)`, with `*` for a missing bound."]
+    pub fn version_range(&self) -> String
+    {
+        format!("{}{}, {}{}",
+            if self.attributes.is_min_inclusive() && self.version_min.is_some() { '[' } else { '(' },
+            self.version_min.as_deref().unwrap_or("*"),
+            self.version_max.as_deref().unwrap_or("*"),
+            if self.attributes.is_max_inclusive() && self.version_max.is_some() { ']' } else { ')' })
+    }
+
+    #[doc = "Returns the languages the row lists; an empty list matches every language."]
+    pub fn languages(&self) -> Vec<MsiLanguage> {
+        self.language.as_deref().map(MsiLanguage::parse_list).unwrap_or_default()
+    }
+
+    #[doc = "Returns the attributes of the row."]
+    pub fn attributes(&self) -> UpgradeAttributes {
+        self.attributes
+    }
+
+    #[doc = "Returns the comma-separated features removed from the related products; `None` removes all of them."]
+    pub fn remove(&self) -> Option<&str> {
+        self.remove.as_deref()
+    }
+
+    #[doc = "Returns the property FindRelatedProducts sets to the product codes it finds."]
+    pub fn action_property(&self) -> &str {
+        &self.action_property
+    }
+
+    #[doc = "Returns the Upgrade row the entry was read from."]
+    pub fn origin(&self) -> &RowOrigin {
+        &self.origin
+    }
+
+    #[doc = "Returns a boolean value indicating whether a product with the given ProductVersion and ProductLanguage falls in the version range and language list of the row. The UpgradeCode is not compared, and neither is the fourth version field."]
+    pub fn matches(&self, version: &str, language: Option<MsiLanguage>) -> bool
+    {
+        let version = match parse_version(version)
+        {
+            Some(version) => version,
+            None => return false
+        };
+        let above_min = match self.version_min.as_deref().and_then(parse_version)
+        {
+            Some(min) => version > min || (version == min && self.attributes.is_min_inclusive()),
+            None => true
+        };
+        let below_max = match self.version_max.as_deref().and_then(parse_version)
+        {
+            Some(max) => version < max || (version == max && self.attributes.is_max_inclusive()),
+            None => true
+        };
+
+        let languages = self.languages();
+        let listed = language.is_some_and(|language| languages.contains(&language));
+        let language_matches = languages.is_empty() || listed != self.attributes.is_languages_exclusive();
+
+        above_min && below_max && language_matches
+    }
+}
+
+#[doc = "Reads the Upgrade table of the package, in storage order; a package without one yields no rows."]
+pub fn read_upgrades(package: &MsiPackage) -> Result<Vec<UpgradeRow>>
+{
+    let table = match package.optional_table("Upgrade")?
+    {
+        Some(table) => table,
+        None => return Ok(Vec::new())
+    };
+
+    Ok(table.rows()
+        .map(|row| UpgradeRow {
+            upgrade_code: row.str("UpgradeCode").unwrap_or_default().to_string(),
+            version_min: row.str("VersionMin").filter(|version| !version.is_empty()).map(str::to_string),
+            version_max: row.str("VersionMax").filter(|version| !version.is_empty()).map(str::to_string),
+            language: row.str("Language").map(str::to_string),
+            attributes: UpgradeAttributes(row.int("Attributes").unwrap_or(0)),
+            remove: row.str("Remove").filter(|remove| !remove.is_empty()).map(str::to_string),
+            action_property: row.str("ActionProperty").unwrap_or_default().to_string(),
+            origin: row.origin()
+        })
+        .collect())
+}
+
+#[doc = "What installing a package does to the products an Upgrade row matches."]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UpgradeEffect {
+    #[doc = "They are removed by RemoveExistingProducts: a major upgrade."]
+    Remove,
+    #[doc = "They are only detected; the package does not act on the ActionProperty itself."]
+    Detect,
+    #[doc = "They are detected and the installation fails while any is installed, because a LaunchCondition or an error custom action tests the ActionProperty. This is how downgrades are prevented."]
+    Block
+}
+
+impl std::fmt::Display for UpgradeEffect {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self
+        {
+            UpgradeEffect::Remove => write!(fmt, "remove"),
+            UpgradeEffect::Detect => write!(fmt, "detect"),
+            UpgradeEffect::Block => write!(fmt, "block")
+        }
+    }
+}
+
+#[doc = "The Upgrade rows of a package with what each does to the products it matches."]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UpgradeAnalysis {
+    upgrade_code: Option<String>,
+    version: Option<String>,
+    entries: Vec<(UpgradeRow, UpgradeEffect)>
+}
+
+impl UpgradeAnalysis {
+
+    #[doc = "Returns the UpgradeCode of the analyzed package."]
+    pub fn upgrade_code(&self) -> Option<&str> {
+        self.upgrade_code.as_deref()
+    }
+
+    #[doc = "Returns the ProductVersion of the analyzed package."]
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+
+    #[doc = "Returns every Upgrade row with its effect, in storage order."]
+    pub fn entries(&self) -> &[(UpgradeRow, UpgradeEffect)] {
+        &self.entries
+    }
+
+    #[doc = "Returns the rows with the given effect."]
+    pub fn with_effect(&self, effect: UpgradeEffect) -> impl Iterator<Item = &UpgradeRow> {
+        self.entries.iter().filter(move |(_, candidate)| *candidate == effect).map(|(row, _)| row)
+    }
+
+    #[doc = "Returns the effect installing the package has on a product with the given UpgradeCode, ProductVersion and ProductLanguage, or `None` if no row matches it. Removal takes precedence over blocking, and blocking over detection."]
+    pub fn effect_on(&self, upgrade_code: &str, version: &str, language: Option<MsiLanguage>) -> Option<UpgradeEffect>
+    {
+        let effects: Vec<UpgradeEffect> = self.entries.iter()
+            .filter(|(row, _)| row.upgrade_code.eq_ignore_ascii_case(upgrade_code) && row.matches(version, language))
+            .map(|(_, effect)| *effect)
+            .collect();
+        [UpgradeEffect::Remove, UpgradeEffect::Block, UpgradeEffect::Detect].iter().copied().find(|effect| effects.contains(effect))
+    }
+}
+
+#[doc = "Classifies the Upgrade rows of a package into the products it removes, only detects, or refuses to install over."]
+pub fn analyze_upgrades(package: &MsiPackage) -> Result<UpgradeAnalysis>
+{
+    // conditions that fail the installation: launch conditions and the sequence conditions of error custom actions
+    let mut blocking: Vec<String> = match package.optional_table("LaunchCondition")?
+    {
+        Some(table) => table.rows().filter_map(|row| row.str("Condition").map(str::to_string)).collect(),
+        None => Vec::new()
+    };
+    let custom_actions = CustomActionTable::read(package)?;
+    for table in ["InstallUISequence", "InstallExecuteSequence"]
+    {
+        for scheduled in read_actions(package, table)?
+        {
+            let is_error = custom_actions.get(scheduled.action()).is_some_and(|custom| custom.action_type().kind() == ActionKind::Error);
+            if let (true, Some(condition)) = (is_error, scheduled.condition())
+            {
+                blocking.push(condition.to_string());
+            }
+        }
+    }
+
+    let entries = read_upgrades(package)?.into_iter()
+        .map(|row| {
+            let effect = if !row.attributes.only_detect()
+            {
+                UpgradeEffect::Remove
+            }
+            else if blocking.iter().any(|condition| mentions(condition, &row.action_property))
+            {
+                UpgradeEffect::Block
+            }
+            else
+            {
+                UpgradeEffect::Detect
+            };
+            (row, effect)
+        })
+        .collect();
+
+    Ok(UpgradeAnalysis {
+        upgrade_code: package.property("UpgradeCode")?,
+        version: package.property("ProductVersion")?,
+        entries
+    })
+}
+
+// Whether a condition refers to the property as a whole identifier.
+fn mentions(condition: &str, property: &str) -> bool
+{
+    !property.is_empty() && condition.split(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '.')).any(|word| word == property)
+}
+
+// Versions compare on their first three fields; Windows Installer ignores the fourth.
+fn parse_version(version: &str) -> Option<MsiVersion> {
+    MsiVersion::parse(version).ok()
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::testutil::TestPackage;
+
+    fn analyze(remove_existing_products: i32) -> Option<RepAnalysis>
+    {
+        let package = TestPackage::new(&format!("rep-{}", remove_existing_products), |builder| {
+            let action = |name: &str, sequence: i32| vec![msi::Value::from(name), msi::Value::Null, msi::Value::Int(sequence)];
+            let mut rows = vec![
+                action("InstallValidate", 1400),
+                action("InstallInitialize", 1500),
+                action("InstallExecute", 6500),
+                action("InstallFinalize", 6600)
+            ];
+            if remove_existing_products > 0
+            {
+                rows.push(action(REMOVE_EXISTING_PRODUCTS, remove_existing_products));
+            }
+
+            builder.table("InstallExecuteSequence", vec![
+                msi::Column::build("Action").primary_key().id_string(72),
+                msi::Column::build("Condition").nullable().string(255),
+                msi::Column::build("Sequence").nullable().int16()
+            ], rows);
+        });
+
+        analyze_remove_existing_products(&MsiPackage::open(package.path()).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_placements()
+    {
+        assert!(analyze(0).is_none());
+        assert_eq!(analyze(1450).unwrap().placement(), RepPlacement::AfterInstallValidate);
+        assert_eq!(analyze(1501).unwrap().placement(), RepPlacement::AfterInstallInitialize);
+        assert_eq!(analyze(6550).unwrap().placement(), RepPlacement::AfterInstallExecute);
+        assert_eq!(analyze(6700).unwrap().placement(), RepPlacement::AfterInstallFinalize);
+        assert_eq!(analyze(100).unwrap().placement(), RepPlacement::Unsupported);
+
+        let late = analyze(6550).unwrap();
+        assert_eq!(late.action().sequence(), Some(6550));
+        assert_eq!(late.placement().removes_before_install(), Some(false));
+        assert_eq!(late.placement().is_rollback_safe(), Some(true));
+        assert_eq!(RepPlacement::AfterInstallFinalize.is_rollback_safe(), Some(false));
+    }
+
+    fn versioned(tag: &str, upgrade_code: &str, version: &str, upgrades: Vec<Vec<msi::Value>>) -> TestPackage
+    {
+        TestPackage::new(tag, |builder| {
+            builder.table("Property", vec![
+                msi::Column::build("Property").primary_key().id_string(72),
+                msi::Column::build("Value").localizable().text_string(0)
+            ], vec![
+                vec![msi::Value::from("UpgradeCode"), msi::Value::from(upgrade_code)],
+                vec![msi::Value::from("ProductVersion"), msi::Value::from(version)],
+                vec![msi::Value::from("ProductLanguage"), msi::Value::from("1033")]
+            ]);
+            builder.table("Upgrade", vec![
+                msi::Column::build("UpgradeCode").primary_key().string(38),
+                msi::Column::build("VersionMin").primary_key().nullable().string(20),
+                msi::Column::build("VersionMax").primary_key().nullable().string(20),
+                msi::Column::build("Language").primary_key().nullable().string(255),
+                msi::Column::build("Attributes").primary_key().int32(),
+                msi::Column::build("Remove").nullable().string(255),
+                msi::Column::build("ActionProperty").id_string(72)
+            ], upgrades);
+        })
+    }
+
+    #[test]
+    fn test_upgrade_graph()
+    {
+        let code = "{AAAAAAAA-2222-3333-4444-555555555555}";
+        let upgrade = |min: Option<&str>, max: &str, attributes: i32, property: &str| vec![
+            msi::Value::from(code), min.map(msi::Value::from).unwrap_or(msi::Value::Null), msi::Value::from(max), msi::Value::Null, msi::Value::Int(attributes), msi::Value::Null, msi::Value::from(property)
+        ];
+        let v3 = versioned("upgrade-v3", code, "3.0.0", vec![
+            upgrade(Some("1.0.0"), "3.0.0", UPGRADE_VERSION_MIN_INCLUSIVE, "PREVIOUSVERSIONS"),
+            upgrade(Some("3.0.0"), "99.0.0", UPGRADE_ONLY_DETECT | UPGRADE_VERSION_MIN_INCLUSIVE, "NEWERVERSIONDETECTED")
+        ]);
+        let v1 = versioned("upgrade-v1", code, "1.0.0", vec![]);
+        let v2 = versioned("upgrade-v2", &code.to_lowercase(), "2.0.0.7", vec![upgrade(None, "2.0.0", 0, "PREVIOUSVERSIONS")]);
+        let packages = [MsiPackage::open(v3.path()).unwrap(), MsiPackage::open(v1.path()).unwrap(), MsiPackage::open(v2.path()).unwrap()];
+
+        let graph = UpgradeGraph::build(&[("v3.msi", &packages[0]), ("v1.msi", &packages[1]), ("v2.msi", &packages[2])]).unwrap();
+        let names: Vec<&str> = graph.nodes().iter().map(|node| node.name()).collect();
+        assert_eq!(names, ["v1.msi", "v2.msi", "v3.msi"]);
+        assert_eq!(graph.matched_by(1).map(|edge| edge.to()).collect::<Vec<_>>(), [0]);
+        assert_eq!(graph.matched_by(2).map(|edge| edge.to()).collect::<Vec<_>>(), [0, 1]);
+        assert!(graph.edges().iter().all(|edge| !edge.only_detect()));
+
+        let dot = graph.to_dot();
+        assert!(dot.contains("label=\"{AAAAAAAA-2222-3333-4444-555555555555}\""));
+        assert!(dot.contains("n2 -> n1;"));
+    }
+
+    #[test]
+    fn test_analyze_upgrades()
+    {
+        let code = "{AAAAAAAA-2222-3333-4444-555555555555}";
+        let upgrade = |min: Option<&str>, max: Option<&str>, attributes: i32, property: &str| vec![
+            msi::Value::from(code), min.map(msi::Value::from).unwrap_or(msi::Value::Null), max.map(msi::Value::from).unwrap_or(msi::Value::Null),
+            msi::Value::Null, msi::Value::Int(attributes), msi::Value::Null, msi::Value::from(property)
+        ];
+        let package = TestPackage::new("upgrade-analysis", |builder| {
+            builder.table("Property", vec![
+                msi::Column::build("Property").primary_key().id_string(72),
+                msi::Column::build("Value").localizable().text_string(0)
+            ], vec![
+                vec![msi::Value::from("UpgradeCode"), msi::Value::from(code)],
+                vec![msi::Value::from("ProductVersion"), msi::Value::from("3.0.0")]
+            ]);
+            builder.table("Upgrade", vec![
+                msi::Column::build("UpgradeCode").primary_key().string(38),
+                msi::Column::build("VersionMin").primary_key().nullable().string(20),
+                msi::Column::build("VersionMax").primary_key().nullable().string(20),
+                msi::Column::build("Language").primary_key().nullable().string(255),
+                msi::Column::build("Attributes").primary_key().int32(),
+                msi::Column::build("Remove").nullable().string(255),
+                msi::Column::build("ActionProperty").id_string(72)
+            ], vec![
+                upgrade(Some("1.0.0"), Some("3.0.0"), UPGRADE_VERSION_MIN_INCLUSIVE | UPGRADE_MIGRATE_FEATURES, "PREVIOUSVERSIONS"),
+                upgrade(Some("3.0.0"), None, UPGRADE_ONLY_DETECT, "NEWERVERSIONDETECTED"),
+                upgrade(None, Some("1.0.0"), UPGRADE_ONLY_DETECT, "ANCIENT")
+            ]);
+            builder.table("LaunchCondition", vec![
+                msi::Column::build("Condition").primary_key().string(255),
+                msi::Column::build("Description").localizable().formatted_string(255)
+            ], vec![
+                vec![msi::Value::from("NOT NEWERVERSIONDETECTED OR Installed"), msi::Value::from("A newer version is installed.")]
+            ]);
+        });
+        let package = MsiPackage::open(package.path()).unwrap();
+
+        let analysis = analyze_upgrades(&package).unwrap();
+        assert_eq!((analysis.upgrade_code(), analysis.version()), (Some(code), Some("3.0.0")));
+        let removed: Vec<String> = analysis.with_effect(UpgradeEffect::Remove).map(|row| row.version_range()).collect();
+        assert_eq!(removed, ["[1.0.0, 3.0.0)"]);
+        assert!(analysis.with_effect(UpgradeEffect::Remove).next().unwrap().attributes().migrates_features());
+        assert_eq!(analysis.with_effect(UpgradeEffect::Detect).map(|row| row.action_property()).collect::<Vec<_>>(), ["ANCIENT"]);
+        assert_eq!(analysis.effect_on(&code.to_lowercase(), "2.5.0.1", Some(MsiLanguage::from(1033))), Some(UpgradeEffect::Remove));
+        assert_eq!(analysis.effect_on(code, "3.0.0", None), None);
+        assert_eq!(analysis.effect_on(code, "4.0.0", None), Some(UpgradeEffect::Block));
+        assert_eq!(analysis.effect_on(code, "0.9", None), Some(UpgradeEffect::Detect));
+        let newer = analysis.entries().iter().find(|(row, _)| row.action_property() == "NEWERVERSIONDETECTED").unwrap();
+        assert_eq!((newer.0.version_range(), newer.1), ("(3.0.0, *)".to_string(), UpgradeEffect::Block));
+    }
+}
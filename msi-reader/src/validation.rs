@@ -0,0 +1,784 @@
+use std::collections::{ BTreeMap, HashMap, HashSet };
+use std::collections::hash_map::Entry;
+use std::fmt::Display;
+
+use crate::customaction::{ ActionKind, CustomActionType };
+use crate::directory::{ MsiName, NameError };
+use crate::error::Result;
+use crate::package::MsiPackage;
+use crate::table::{ Row, RowOrigin };
+
+// Columns holding conditional expressions.
+const CONDITION_COLUMNS: [(&str, &[&str]); 10] = [
+    ("InstallUISequence", &["Condition"]),
+    ("InstallExecuteSequence", &["Condition"]),
+    ("AdminUISequence", &["Condition"]),
+    ("AdminExecuteSequence", &["Condition"]),
+    ("AdvtExecuteSequence", &["Condition"]),
+    ("Component", &["Condition"]),
+    ("Condition", &["Condition"]),
+    ("LaunchCondition", &["Condition"]),
+    ("ControlCondition", &["Condition"]),
+    ("ControlEvent", &["Condition"])
+];
+
+// Columns of type Formatted (or a type derived from it) that may reference properties.
+const FORMATTED_COLUMNS: [(&str, &[&str]); 13] = [
+    ("CustomAction", &["Target"]),
+    ("Registry", &["Key", "Name", "Value"]),
+    ("RemoveRegistry", &["Key", "Name"]),
+    ("IniFile", &["Value"]),
+    ("RemoveIniFile", &["Value"]),
+    ("Shortcut", &["Target", "Arguments"]),
+    ("Environment", &["Name", "Value"]),
+    ("ServiceInstall", &["DisplayName", "StartName", "Password", "Arguments"]),
+    ("ServiceControl", &["Arguments"]),
+    ("LaunchCondition", &["Description"]),
+    ("Control", &["Text"]),
+    ("Dialog", &["Title"]),
+    ("MsiShortcutProperty", &["PropVariantValue"])
+];
+
+// Properties set by Windows Installer itself, which packages reference without defining.
+const STANDARD_PROPERTIES: &[&str] = &[
+    "ACTION", "ADDDEFAULT", "ADDLOCAL", "ADDSOURCE", "ADVERTISE", "AFTERREBOOT", "ALLUSERS", "ARPAUTHORIZEDCDFPREFIX",
+    "ARPCOMMENTS", "ARPCONTACT", "ARPHELPLINK", "ARPHELPTELEPHONE", "ARPINSTALLLOCATION", "ARPNOMODIFY", "ARPNOREMOVE",
+    "ARPNOREPAIR", "ARPPRODUCTICON", "ARPREADME", "ARPSIZE", "ARPSYSTEMCOMPONENT", "ARPURLINFOABOUT", "ARPURLUPDATEINFO",
+    "AdminToolsFolder", "AdminUser", "AppDataFolder", "BorderSide", "BorderTop", "CCP_DRIVE", "CLIENTPROCESSID",
+    "CLIENTUILEVEL", "COMPADDDEFAULT", "COMPADDLOCAL", "COMPADDSOURCE", "COMPANYNAME", "CURRENTDIRECTORY", "CaptionHeight",
+    "ColorBits", "CommonAppDataFolder", "CommonFiles64Folder", "CommonFilesFolder", "ComputerName", "CostingComplete",
+    "DISABLEADVTSHORTCUTS", "DISABLEMEDIA", "DISABLEROLLBACK", "Date", "DefaultUIFont", "DesktopFolder", "DiskPrompt",
+    "EXECUTEACTION", "EXECUTEMODE", "EnableUserControl", "ErrorDialog", "FASTOEM", "FILEADDDEFAULT", "FILEADDLOCAL",
+    "FILEADDSOURCE", "FavoritesFolder", "FontsFolder", "INSTALLLEVEL", "Installed", "Intel", "Intel64",
+    "IsAdminPackage", "LIMITUI", "LOGACTION", "LeftUnit", "LocalAppDataFolder", "LogonUser", "MEDIAPACKAGEPATH",
+    "MSIARPSETTINGSIDENTIFIER", "MSIDEPLOYMENTCOMPLIANT", "MSIDISABLELUAPATCHING", "MSIDISABLERMRESTART",
+    "MSIENFORCEUPGRADECOMPONENTRULES", "MSIFASTINSTALL", "MSIINSTALLPERUSER", "MSIINSTANCEGUID", "MSINEWINSTANCE",
+    "MSINODISABLEMEDIA", "MSIPATCHREMOVE", "MSIRESTARTMANAGERCONTROL", "MSIRMSHUTDOWN", "MSIUNINSTALLSUPERSEDEDCOMPONENTS",
+    "MSIUSEREALADMINDETECTION", "Manufacturer", "MsiAMD64", "MsiHiddenProperties", "MsiLogFileLocation", "MsiLogging",
+    "MsiNTProductType", "MsiNTSuiteBackOffice", "MsiNTSuiteDataCenter", "MsiNTSuiteEnterprise", "MsiNTSuitePersonal",
+    "MsiNTSuiteSmallBusiness", "MsiNTSuiteSmallBusinessRestricted", "MsiNTSuiteWebServer", "MsiNetAssemblySupport",
+    "MsiPatchRemovalList", "MsiRunningElevated", "MsiSystemRebootPending", "MsiUIHideCancel", "MsiUIProgressOnly",
+    "MsiUISourceResOnly", "MsiWin32AssemblySupport", "Msix64", "MyPicturesFolder", "NOCOMPANYNAME", "NOUSERNAME",
+    "NetHoodFolder", "OLEAdvtSupport", "OriginalDatabase", "OutOfDiskSpace", "OutOfNoRbDiskSpace", "PATCH",
+    "PATCHNEWPACKAGECODE", "PATCHNEWSUMMARYCOMMENTS", "PATCHNEWSUMMARYSUBJECT", "PIDKEY", "PIDTemplate", "PackageCode",
+    "PackagecodeChanging", "PersonalFolder", "PhysicalMemory", "Preselected", "PrimaryFolder", "PrimaryVolumePath",
+    "PrimaryVolumeSpaceAvailable", "PrimaryVolumeSpaceRemaining", "PrimaryVolumeSpaceRequired", "PrintHoodFolder",
+    "Privileged", "ProductCode", "ProductID", "ProductLanguage", "ProductName", "ProductState", "ProductToBeRegistered",
+    "ProductVersion", "ProgramFiles64Folder", "ProgramFilesFolder", "ProgramMenuFolder", "PROMPTROLLBACKCOST",
+    "REBOOT", "REBOOTPROMPT", "REINSTALL", "REINSTALLMODE", "REMOVE", "RESUME", "ROOTDRIVE", "RecentFolder",
+    "RedirectedDllSupport", "RemoteAdminTS", "ReplacedInUseFiles", "RollbackDisabled", "SECONDSEQUENCE", "SEQUENCE",
+    "SHORTFILENAMES", "SOURCEDIR", "SOURCELIST", "ScreenX", "ScreenY", "SecureCustomProperties", "SendToFolder",
+    "ServicePackLevel", "ServicePackLevelMinor", "SharedWindows", "ShellAdvtSupport", "SourceDir", "SourcedirProduct",
+    "StartMenuFolder", "StartupFolder", "System16Folder", "System64Folder", "SystemFolder", "SystemLanguageID",
+    "TARGETDIR", "TRANSFORMS", "TRANSFORMSATSOURCE", "TRANSFORMSSECURE", "TTCSupport", "TempFolder", "TemplateFolder",
+    "TerminalServer", "TextHeight", "Time", "UILevel", "UPGRADINGPRODUCTCODE", "USERNAME", "UpdateStarted",
+    "UpgradeCode", "UserLanguageID", "UserSID", "Version9X", "VersionDatabase", "VersionMsi", "VersionNT",
+    "VersionNT64", "VirtualMemory", "WindowsBuild", "WindowsFolder", "WindowsVolume"
+];
+
+// Foreign keys into the Directory table.
+const DIRECTORY_COLUMNS: [(&str, &[&str]); 4] = [
+    ("Directory", &["Directory_Parent"]),
+    ("Component", &["Directory_"]),
+    ("Shortcut", &["Directory_"]),
+    ("CreateFolder", &["Directory_"])
+];
+
+// Columns naming a property that holds a folder path, usually a Directory key.
+const DIRECTORY_PROPERTY_COLUMNS: [(&str, &[&str]); 6] = [
+    ("RemoveFile", &["DirProperty"]),
+    ("IniFile", &["DirProperty"]),
+    ("RemoveIniFile", &["DirProperty"]),
+    ("DuplicateFile", &["DestFolder"]),
+    ("MoveFile", &["SourceFolder", "DestFolder"]),
+    ("ReserveCost", &["ReserveFolder"])
+];
+
+const CONDITION_KEYWORDS: [&str; 6] = ["NOT", "AND", "OR", "XOR", "EQV", "IMP"];
+
+#[doc = "A cell of a table: the row it belongs to and its column."]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CellLocation {
+    origin: RowOrigin,
+    column: String
+}
+
+impl CellLocation {
+
+    pub(crate) fn new(origin: RowOrigin, column: &str) -> CellLocation
+    {
+        CellLocation {
+            origin,
+            column: column.to_string()
+        }
+    }
+
+    pub(crate) fn of(row: &Row, column: &str) -> CellLocation {
+        CellLocation::new(row.origin(), column)
+    }
+
+    #[doc = "Returns the row the cell belongs to."]
+    pub fn origin(&self) -> &RowOrigin {
+        &self.origin
+    }
+
+    #[doc = "Returns the name of the table."]
+    pub fn table(&self) -> &str {
+        self.origin.table()
+    }
+
+    #[doc = "Returns the name of the column."]
+    pub fn column(&self) -> &str {
+        &self.column
+    }
+
+    #[doc = "Returns the primary key of the row, with multiple key columns joined by '.'."]
+    pub fn key(&self) -> &str {
+        self.origin.key()
+    }
+
+    #[doc = "Returns the position of the row within its table."]
+    pub fn row(&self) -> usize {
+        self.origin.index()
+    }
+}
+
+impl From<CellLocation> for RowOrigin {
+    fn from(location: CellLocation) -> RowOrigin {
+        location.origin
+    }
+}
+
+impl Display for CellLocation {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "{}.{} [{}]", self.origin.table(), self.column, self.origin.key())
+    }
+}
+
+#[doc = "A property referenced by a condition or formatted field that nothing defines."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct UndefinedProperty {
+    property: String,
+    location: CellLocation
+}
+
+impl UndefinedProperty {
+
+    #[doc = "Returns the name of the referenced property."]
+    pub fn property(&self) -> &str {
+        &self.property
+    }
+
+    #[doc = "Returns the cell containing the reference."]
+    pub fn location(&self) -> &CellLocation {
+        &self.location
+    }
+}
+
+#[doc = "Finds properties referenced by conditions and formatted fields that are neither defined in the Property table, set by AppSearch, custom actions, directories, UI controls or FindRelatedProducts, nor standard Windows Installer properties."]
+pub fn find_undefined_properties(package: &MsiPackage) -> Result<Vec<UndefinedProperty>>
+{
+    let defined = defined_properties(package)?;
+    let is_undefined = |name: &str| !defined.contains(name) && !STANDARD_PROPERTIES.contains(&name);
+
+    let mut undefined = Vec::new();
+    for (table_name, columns) in CONDITION_COLUMNS.iter()
+    {
+        visit_cells(package, table_name, columns, |row, column, text| {
+            let mut seen = HashSet::new();
+            for name in condition_identifiers(text).into_iter().filter(|name| is_undefined(name) && seen.insert(*name))
+            {
+                undefined.push(UndefinedProperty { property: name.to_string(), location: CellLocation::of(row, column) });
+            }
+        })?;
+    }
+
+    visit_formatted_cells(package, |row, column, text| {
+        let mut seen = HashSet::new();
+        for name in formatted_references(text).into_iter().filter(|name| is_property_name(name) && is_undefined(name) && seen.insert(*name))
+        {
+            undefined.push(UndefinedProperty { property: name.to_string(), location: CellLocation::of(row, column) });
+        }
+    })?;
+
+    Ok(undefined)
+}
+
+#[doc = "A cell value that refers to a row that does not exist."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct DanglingReference {
+    location: CellLocation,
+    value: String,
+    target: String
+}
+
+impl DanglingReference {
+
+    #[doc = "Returns the cell containing the reference."]
+    pub fn location(&self) -> &CellLocation {
+        &self.location
+    }
+
+    #[doc = "Returns the referenced key."]
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    #[doc = "Returns the name of the table the key should exist in."]
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+}
+
+#[doc = "Finds `[#File]`, `[!File]` and `[$Component]` references in formatted fields whose File or Component row does not exist, such as broken shortcut targets or registry values."]
+pub fn find_broken_formatted_references(package: &MsiPackage) -> Result<Vec<DanglingReference>>
+{
+    let files = key_set(package, "File")?;
+    let components = key_set(package, "Component")?;
+
+    let mut broken = Vec::new();
+    visit_formatted_cells(package, |row, column, text| {
+        for reference in formatted_references(text)
+        {
+            let (target, keys) = match reference.chars().next()
+            {
+                Some('#') | Some('!') => ("File", &files),
+                Some('$') => ("Component", &components),
+                _ => continue
+            };
+
+            let key = &reference[1..];
+            if !keys.contains(key)
+            {
+                broken.push(DanglingReference { location: CellLocation::of(row, column), value: key.to_string(), target: target.to_string() });
+            }
+        }
+    })?;
+
+    Ok(broken)
+}
+
+#[doc = "Finds `Directory_` foreign keys and type 35 custom actions that name a directory missing from the Directory table. Columns that accept a property (such as `RemoveFile.DirProperty`) may also name any defined or standard property."]
+pub fn find_broken_directory_references(package: &MsiPackage) -> Result<Vec<DanglingReference>>
+{
+    let directories = key_set(package, "Directory")?;
+    let properties = defined_properties(package)?;
+
+    let mut broken = Vec::new();
+    let mut dangling = |row: &Row, column: &str, value: &str| broken.push(DanglingReference {
+        location: CellLocation::of(row, column),
+        value: value.to_string(),
+        target: "Directory".to_string()
+    });
+
+    for (table_name, columns) in DIRECTORY_COLUMNS.iter()
+    {
+        visit_cells(package, table_name, columns, |row, column, directory| {
+            if !directories.contains(directory)
+            {
+                dangling(row, column, directory);
+            }
+        })?;
+    }
+
+    for (table_name, columns) in DIRECTORY_PROPERTY_COLUMNS.iter()
+    {
+        visit_cells(package, table_name, columns, |row, column, directory| {
+            if !directories.contains(directory) && !properties.contains(directory) && !STANDARD_PROPERTIES.contains(&directory)
+            {
+                dangling(row, column, directory);
+            }
+        })?;
+    }
+
+    visit_cells(package, "CustomAction", &["Source"], |row, column, directory| {
+        if action_kind(row) == ActionKind::SetDirectory && !directories.contains(directory)
+        {
+            dangling(row, column, directory);
+        }
+    })?;
+
+    Ok(broken)
+}
+
+#[doc = "Walks every column the `_Validation` table declares as a foreign key and reports values that match no row of the key table(s). Multiple key tables, separated by ';', are alternatives. Returns an empty list for packages without `_Validation`."]
+pub fn find_dangling_foreign_keys(package: &MsiPackage) -> Result<Vec<DanglingReference>>
+{
+    let validation = match package.optional_table("_Validation")?
+    {
+        Some(table) => table,
+        None => return Ok(Vec::new())
+    };
+
+    let mut foreign_keys: BTreeMap<&str, Vec<(&str, &str, usize)>> = BTreeMap::new();
+    for row in validation.rows()
+    {
+        if let (Some(table_name), Some(column), Some(key_tables), Some(key_column)) = (row.str("Table"), row.str("Column"), row.str("KeyTable"), row.int("KeyColumn"))
+        {
+            if key_column >= 1
+            {
+                foreign_keys.entry(table_name).or_default().push((column, key_tables, key_column as usize - 1));
+            }
+        }
+    }
+
+    let mut keys: HashMap<(String, usize), HashSet<String>> = HashMap::new();
+    let mut dangling = Vec::new();
+    for (table_name, columns) in foreign_keys
+    {
+        let table = match package.optional_table(table_name)?
+        {
+            Some(table) => table,
+            None => continue
+        };
+
+        for (column, key_tables, key_column) in columns
+        {
+            let index = match table.column_index(column)
+            {
+                Some(index) => index,
+                None => continue
+            };
+
+            for key_table in key_tables.split(';')
+            {
+                if let Entry::Vacant(entry) = keys.entry((key_table.to_string(), key_column))
+                {
+                    entry.insert(match package.optional_table(key_table)?
+                    {
+                        Some(target) => target.rows().filter_map(|row| row.values().get(key_column).map(|value| value.to_string())).collect(),
+                        None => HashSet::new()
+                    });
+                }
+            }
+
+            for row in table.rows()
+            {
+                let value = &row.values()[index];
+                if value.is_null()
+                {
+                    continue;
+                }
+
+                let value = value.to_string();
+                if !key_tables.split(';').any(|key_table| keys[&(key_table.to_string(), key_column)].contains(&value))
+                {
+                    dangling.push(DanglingReference { location: CellLocation::of(&row, column), value, target: key_tables.to_string() });
+                }
+            }
+        }
+    }
+
+    Ok(dangling)
+}
+
+#[doc = "A cell of the Filename category whose long name is not a valid Windows file name."]
+#[derive(Clone, Debug, PartialEq)]
+pub struct InvalidName {
+    location: CellLocation,
+    name: String,
+    error: NameError
+}
+
+impl InvalidName {
+
+    #[doc = "Returns the cell containing the name."]
+    pub fn location(&self) -> &CellLocation {
+        &self.location
+    }
+
+    #[doc = "Returns the name as stored in the cell."]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[doc = "Returns the reason the name is invalid."]
+    pub fn error(&self) -> &NameError {
+        &self.error
+    }
+}
+
+#[doc = "Checks the short and long names of all columns the `_Validation` table declares with the Filename category; see `MsiName::validate`. Returns an empty list for packages without `_Validation`."]
+pub fn find_invalid_filenames(package: &MsiPackage) -> Result<Vec<InvalidName>>
+{
+    let mut columns: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    visit_cells(package, "_Validation", &["Category"], |row, _, category| {
+        if let (Some(table_name), Some(column), "Filename") = (row.str("Table"), row.str("Column"), category)
+        {
+            columns.entry(table_name.to_string()).or_default().push(column.to_string());
+        }
+    })?;
+
+    let mut invalid = Vec::new();
+    for (table_name, columns) in &columns
+    {
+        let columns: Vec<&str> = columns.iter().map(|column| column.as_str()).collect();
+        visit_cells(package, table_name, &columns, |row, column, name| {
+            if let Err(error) = MsiName::from(name).validate()
+            {
+                invalid.push(InvalidName { location: CellLocation::of(row, column), name: name.to_string(), error });
+            }
+        })?;
+    }
+
+    Ok(invalid)
+}
+
+fn key_set(package: &MsiPackage, table_name: &str) -> Result<HashSet<String>>
+{
+    Ok(match package.optional_table(table_name)?
+    {
+        Some(table) => table.rows().map(|row| row.key()).collect(),
+        None => HashSet::new()
+    })
+}
+
+fn visit_formatted_cells<F: FnMut(&Row, &str, &str)>(package: &MsiPackage, mut visit: F) -> Result<()>
+{
+    for (table_name, columns) in FORMATTED_COLUMNS.iter()
+    {
+        visit_cells(package, table_name, columns, |row, column, text| {
+            if *table_name != "CustomAction" || has_formatted_target(row)
+            {
+                visit(row, column, text);
+            }
+        })?;
+    }
+
+    Ok(())
+}
+
+fn visit_cells<F: FnMut(&Row, &str, &str)>(package: &MsiPackage, table_name: &str, columns: &[&str], mut visit: F) -> Result<()>
+{
+    if let Some(table) = package.optional_table(table_name)?
+    {
+        for row in table.rows()
+        {
+            for column in columns
+            {
+                if let Some(text) = row.str(column)
+                {
+                    visit(&row, column, text);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn defined_properties(package: &MsiPackage) -> Result<HashSet<String>>
+{
+    let mut defined = HashSet::new();
+    let sources: [(&str, &str); 5] = [
+        ("Property", "Property"),
+        ("AppSearch", "Property"),
+        ("Directory", "Directory"),
+        ("Upgrade", "ActionProperty"),
+        ("Control", "Property")
+    ];
+    for (table_name, column) in sources.iter()
+    {
+        visit_cells(package, table_name, &[column], |_, _, name| { defined.insert(name.to_string()); })?;
+    }
+
+    // type 35 sets a directory, type 51 a property, both named by the Source column
+    visit_cells(package, "CustomAction", &["Source"], |row, _, name| {
+        if matches!(action_kind(row), ActionKind::SetDirectory | ActionKind::SetProperty)
+        {
+            defined.insert(name.to_string());
+        }
+    })?;
+
+    // control events of the form [PROPERTY] set a property
+    visit_cells(package, "ControlEvent", &["Event"], |_, _, event| {
+        if let Some(name) = event.strip_prefix('[').and_then(|event| event.strip_suffix(']'))
+        {
+            defined.insert(name.to_string());
+        }
+    })?;
+
+    Ok(defined)
+}
+
+// Decodes the Type of a CustomAction row.
+fn action_kind(row: &Row) -> ActionKind
+{
+    CustomActionType::from(row.int("Type").unwrap_or(0)).kind()
+}
+
+// Custom actions whose Target column is formatted: executables and text data.
+fn has_formatted_target(row: &Row) -> bool
+{
+    matches!(action_kind(row), ActionKind::Exe | ActionKind::Error | ActionKind::SetDirectory | ActionKind::SetProperty)
+}
+
+fn is_property_name(name: &str) -> bool
+{
+    let mut chars = name.chars();
+    match chars.next()
+    {
+        Some(first) if first.is_ascii_alphabetic() || first == '_' => chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.'),
+        _ => false
+    }
+}
+
+#[doc = "Returns the contents of the innermost bracketed references of a formatted string, such as `PROPERTY`, `#File` or `$Component`. Escapes like `[\\[]` are skipped."]
+fn formatted_references(text: &str) -> Vec<&str>
+{
+    let bytes = text.as_bytes();
+    let mut references = Vec::new();
+    let mut open: Option<usize> = None;
+    let mut index = 0;
+    while index < bytes.len()
+    {
+        match bytes[index]
+        {
+            b'[' if bytes.get(index + 1) == Some(&b'\\') => {
+                // [\x] escapes a single character, which may itself be a bracket
+                open = None;
+                index += 3;
+            },
+            b'[' => open = Some(index + 1),
+            b']' => {
+                if let Some(start) = open.take()
+                {
+                    references.push(&text[start..index]);
+                }
+            },
+            _ => {}
+        }
+
+        index += 1;
+    }
+
+    references
+}
+
+#[doc = "Returns the identifiers of a conditional expression that name properties, skipping keywords, string literals and component, feature and environment references."]
+fn condition_identifiers(text: &str) -> Vec<&str>
+{
+    let bytes = text.as_bytes();
+    let mut identifiers = Vec::new();
+    let mut index = 0;
+    while index < bytes.len()
+    {
+        let c = bytes[index];
+        if c == b'"'
+        {
+            index += bytes[index + 1..].iter().position(|c| *c == b'"').map(|end| end + 2).unwrap_or(bytes.len() - index);
+        }
+        else if c.is_ascii_alphabetic() || c == b'_'
+        {
+            let start = index;
+            while index < bytes.len() && (bytes[index].is_ascii_alphanumeric() || bytes[index] == b'_' || bytes[index] == b'.')
+            {
+                index += 1;
+            }
+
+            let prefixed = start > 0 && matches!(bytes[start - 1], b'$' | b'?' | b'&' | b'!' | b'%');
+            let identifier = &text[start..index];
+            if !prefixed && !CONDITION_KEYWORDS.iter().any(|keyword| keyword.eq_ignore_ascii_case(identifier))
+            {
+                identifiers.push(identifier);
+            }
+        }
+        else if c.is_ascii_digit()
+        {
+            while index < bytes.len() && bytes[index].is_ascii_digit()
+            {
+                index += 1;
+            }
+        }
+        else
+        {
+            index += 1;
+        }
+    }
+
+    identifiers
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::testutil::TestPackage;
+
+    #[test]
+    fn test_references()
+    {
+        assert_eq!(formatted_references("[INSTALLDIR]bin\\[#App.exe] [\\[]x[\\]] [[Indirect]]"), ["INSTALLDIR", "#App.exe", "Indirect"]);
+        assert_eq!(condition_identifiers("NOT Installed AND (VersionNT >= 601 Or $Comp=3) AND Name=\"Some Value\" AND %PATH AND _x.y"), ["Installed", "VersionNT", "Name", "_x.y"]);
+    }
+
+    #[test]
+    fn test_undefined_properties()
+    {
+        let package = TestPackage::new("undefined-properties", |builder| {
+            builder.table("Property", vec![
+                msi::Column::build("Property").primary_key().id_string(72),
+                msi::Column::build("Value").localizable().text_string(0)
+            ], vec![
+                vec![msi::Value::from("Mode"), msi::Value::from("full")]
+            ]);
+            builder.table("CustomAction", vec![
+                msi::Column::build("Action").primary_key().id_string(72),
+                msi::Column::build("Type").int16(),
+                msi::Column::build("Source").nullable().string(72),
+                msi::Column::build("Target").nullable().formatted_string(255)
+            ], vec![
+                vec![msi::Value::from("SetFlag"), msi::Value::Int(51), msi::Value::from("FLAG"), msi::Value::from("[Mode]-[MISSING]")],
+                vec![msi::Value::from("Script"), msi::Value::Int(38), msi::Value::Null, msi::Value::from("x = a[UNDEFINED]")]
+            ]);
+            builder.table("InstallExecuteSequence", vec![
+                msi::Column::build("Action").primary_key().id_string(72),
+                msi::Column::build("Condition").nullable().string(255),
+                msi::Column::build("Sequence").nullable().int16()
+            ], vec![
+                vec![msi::Value::from("SetFlag"), msi::Value::from("NOT Installed AND FLAG AND Typo"), msi::Value::Int(100)]
+            ]);
+        });
+
+        let undefined = find_undefined_properties(&MsiPackage::open(package.path()).unwrap()).unwrap();
+        let found: Vec<(&str, String)> = undefined.iter().map(|item| (item.property(), item.location().to_string())).collect();
+        assert_eq!(found, [
+            ("Typo", "InstallExecuteSequence.Condition [SetFlag]".to_string()),
+            ("MISSING", "CustomAction.Target [SetFlag]".to_string())
+        ]);
+    }
+
+    #[test]
+    fn test_broken_formatted_references()
+    {
+        let package = TestPackage::new("broken-formatted", |builder| {
+            builder.table("Component", vec![
+                msi::Column::build("Component").primary_key().id_string(72),
+                msi::Column::build("Directory_").id_string(72)
+            ], vec![
+                vec![msi::Value::from("Main"), msi::Value::from("INSTALLDIR")]
+            ]);
+            builder.table("File", vec![
+                msi::Column::build("File").primary_key().id_string(72),
+                msi::Column::build("Component_").id_string(72)
+            ], vec![
+                vec![msi::Value::from("App.exe"), msi::Value::from("Main")]
+            ]);
+            builder.table("Registry", vec![
+                msi::Column::build("Registry").primary_key().id_string(72),
+                msi::Column::build("Root").int16(),
+                msi::Column::build("Key").formatted_string(255),
+                msi::Column::build("Name").nullable().formatted_string(255),
+                msi::Column::build("Value").nullable().formatted_string(0),
+                msi::Column::build("Component_").id_string(72)
+            ], vec![
+                vec![msi::Value::from("Path"), msi::Value::Int(2), msi::Value::from("Software\\App"), msi::Value::from("Path"), msi::Value::from("[#App.exe] [!Old.dll] [$Main] [$Gone]"), msi::Value::from("Main")]
+            ]);
+        });
+
+        let broken = find_broken_formatted_references(&MsiPackage::open(package.path()).unwrap()).unwrap();
+        let found: Vec<(&str, &str, &str)> = broken.iter().map(|item| (item.location().column(), item.target(), item.value())).collect();
+        assert_eq!(found, [("Value", "File", "Old.dll"), ("Value", "Component", "Gone")]);
+    }
+
+    #[test]
+    fn test_broken_directory_references()
+    {
+        let package = TestPackage::new("broken-directories", |builder| {
+            builder.table("Directory", vec![
+                msi::Column::build("Directory").primary_key().id_string(72),
+                msi::Column::build("Directory_Parent").nullable().id_string(72),
+                msi::Column::build("DefaultDir").category(msi::Category::DefaultDir).string(255)
+            ], vec![
+                vec![msi::Value::from("TARGETDIR"), msi::Value::Null, msi::Value::from("SourceDir")],
+                vec![msi::Value::from("INSTALLDIR"), msi::Value::from("TARGETDIR"), msi::Value::from("App")],
+                vec![msi::Value::from("Orphan"), msi::Value::from("Missing"), msi::Value::from("Orphan")]
+            ]);
+            builder.table("Component", vec![
+                msi::Column::build("Component").primary_key().id_string(72),
+                msi::Column::build("Directory_").id_string(72)
+            ], vec![
+                vec![msi::Value::from("Main"), msi::Value::from("INSTALLDIR")],
+                vec![msi::Value::from("Lost"), msi::Value::from("NOWHERE")]
+            ]);
+            builder.table("RemoveFile", vec![
+                msi::Column::build("FileKey").primary_key().id_string(72),
+                msi::Column::build("Component_").id_string(72),
+                msi::Column::build("DirProperty").id_string(72)
+            ], vec![
+                vec![msi::Value::from("Logs"), msi::Value::from("Main"), msi::Value::from("TempFolder")]
+            ]);
+            builder.table("CustomAction", vec![
+                msi::Column::build("Action").primary_key().id_string(72),
+                msi::Column::build("Type").int16(),
+                msi::Column::build("Source").nullable().string(72),
+                msi::Column::build("Target").nullable().formatted_string(255)
+            ], vec![
+                vec![msi::Value::from("SetDir"), msi::Value::Int(35), msi::Value::from("DATADIR"), msi::Value::from("[TempFolder]")]
+            ]);
+        });
+
+        let broken = find_broken_directory_references(&MsiPackage::open(package.path()).unwrap()).unwrap();
+        let found: Vec<String> = broken.iter().map(|item| format!("{} -> {}", item.location(), item.value())).collect();
+        assert_eq!(found, [
+            "Directory.Directory_Parent [Orphan] -> Missing",
+            "Component.Directory_ [Lost] -> NOWHERE",
+            "CustomAction.Source [SetDir] -> DATADIR"
+        ]);
+    }
+
+    #[test]
+    fn test_dangling_foreign_keys()
+    {
+        let package = TestPackage::new("dangling-foreign-keys", |builder| {
+            builder.table("Feature", vec![
+                msi::Column::build("Feature").primary_key().id_string(38),
+                msi::Column::build("Title").nullable().text_string(64)
+            ], vec![
+                vec![msi::Value::from("Complete"), msi::Value::Null]
+            ]);
+            builder.table("Component", vec![
+                msi::Column::build("Component").primary_key().id_string(72),
+                msi::Column::build("Directory_").nullable().id_string(72)
+            ], vec![
+                vec![msi::Value::from("Main"), msi::Value::Null]
+            ]);
+            builder.table("FeatureComponents", vec![
+                msi::Column::build("Feature_").primary_key().foreign_key("Feature", 1).id_string(38),
+                msi::Column::build("Component_").primary_key().foreign_key("Component", 1).id_string(72)
+            ], vec![
+                vec![msi::Value::from("Complete"), msi::Value::from("Main")],
+                vec![msi::Value::from("Complete"), msi::Value::from("Removed")],
+                vec![msi::Value::from("Extras"), msi::Value::from("Main")]
+            ]);
+        });
+
+        let dangling = find_dangling_foreign_keys(&MsiPackage::open(package.path()).unwrap()).unwrap();
+        let mut found: Vec<String> = dangling.iter().map(|item| format!("{} -> {}.{}", item.location(), item.target(), item.value())).collect();
+        found.sort();
+        assert_eq!(found, [
+            "FeatureComponents.Component_ [Complete.Removed] -> Component.Removed",
+            "FeatureComponents.Feature_ [Extras.Main] -> Feature.Extras"
+        ]);
+    }
+
+    #[test]
+    fn test_invalid_filenames()
+    {
+        let package = TestPackage::new("invalid-filenames", |builder| {
+            builder.table("File", vec![
+                msi::Column::build("File").primary_key().id_string(72),
+                msi::Column::build("FileName").category(msi::Category::Filename).string(255)
+            ], vec![
+                vec![msi::Value::from("App"), msi::Value::from("APP~1.EXE|Application.exe")],
+                vec![msi::Value::from("Bad"), msi::Value::from("BAD~1.TXT|Bad<1>.txt")],
+                vec![msi::Value::from("Device"), msi::Value::from("aux.txt")],
+                vec![msi::Value::from("Short"), msi::Value::from("APPLICATION.EXE|Application.exe")]
+            ]);
+        });
+
+        let invalid = find_invalid_filenames(&MsiPackage::open(package.path()).unwrap()).unwrap();
+        let found: Vec<(&str, &NameError)> = invalid.iter().map(|item| (item.location().key(), item.error())).collect();
+        assert_eq!(found, [("Bad", &NameError::InvalidCharacter('<')), ("Device", &NameError::ReservedName("aux".to_string())), ("Short", &NameError::TooLong(8))]);
+    }
+}
@@ -0,0 +1,180 @@
+use std::cmp::Ordering;
+use std::fmt::Display;
+use std::hash::{ Hash, Hasher };
+use std::str::FromStr;
+
+use crate::error::{ Error, Result };
+
+// The largest value of each field: major and minor are bytes, build and revision words.
+const FIELD_LIMITS: [u16; 4] = [255, 255, 65535, 65535];
+
+#[doc = "A version in the `major.minor.build.revision` form of ProductVersion and the Upgrade table, e.g. `1.2.300`. Missing fields count as 0."]
+#[doc = ""]
+#[doc = "Versions compare as Windows Installer compares them during upgrades: on the first three fields only, so `1.2.3.4` equals `1.2.3.5`. Use `cmp_strict` to take the revision into account."]
+#[derive(Clone, Copy, Debug)]
+pub struct MsiVersion {
+    fields: [u16; 4],
+    count: usize
+}
+
+impl MsiVersion {
+
+    #[doc = "Creates a version with all four fields; fails if a field exceeds its limit of 255.255.65535.65535."]
+    pub fn new(major: u16, minor: u16, build: u16, revision: u16) -> Result<MsiVersion>
+    {
+        let fields = [major, minor, build, revision];
+        if fields.iter().zip(FIELD_LIMITS.iter()).any(|(field, limit)| field > limit)
+        {
+            return Err(Error::invalid(format!("{}.{}.{}.{} exceeds the version limit of 255.255.65535", major, minor, build, revision)));
+        }
+
+        Ok(MsiVersion {
+            fields,
+            count: 4
+        })
+    }
+
+    #[doc = "Parses one to four dot-separated decimal fields, e.g. `1.2.300`. The major and minor version may not exceed 255, the build and revision 65535."]
+    pub fn parse(text: &str) -> Result<MsiVersion>
+    {
+        let parts: Vec<&str> = text.trim().split('.').collect();
+        if parts.len() > 4
+        {
+            return Err(Error::invalid(format!("version '{}' has more than four fields", text)));
+        }
+
+        let mut fields = [0u16; 4];
+        for (index, part) in parts.iter().enumerate()
+        {
+            if part.is_empty() || !part.bytes().all(|byte| byte.is_ascii_digit())
+            {
+                return Err(Error::invalid(format!("'{}' is not a version", text)));
+            }
+
+            fields[index] = part.parse::<u32>().ok()
+                .filter(|value| *value <= FIELD_LIMITS[index] as u32)
+                .ok_or_else(|| Error::invalid(format!("field {} of version '{}' exceeds {}", index + 1, text, FIELD_LIMITS[index])))? as u16;
+        }
+
+        Ok(MsiVersion {
+            fields,
+            count: parts.len()
+        })
+    }
+
+    #[doc = "Returns the major version, at most 255."]
+    pub fn major(&self) -> u16 {
+        self.fields[0]
+    }
+
+    #[doc = "Returns the minor version, at most 255."]
+    pub fn minor(&self) -> u16 {
+        self.fields[1]
+    }
+
+    #[doc = "Returns the build number, at most 65535."]
+    pub fn build(&self) -> u16 {
+        self.fields[2]
+    }
+
+    #[doc = "Returns the revision, the fourth field, which Windows Installer ignores when it looks for related products."]
+    pub fn revision(&self) -> u16 {
+        self.fields[3]
+    }
+
+    #[doc = "Compares all four fields, including the revision that the `Ord` implementation ignores."]
+    pub fn cmp_strict(&self, other: &MsiVersion) -> Ordering {
+        self.fields.cmp(&other.fields)
+    }
+
+    #[doc = "Returns the version without its revision, as Windows Installer compares it."]
+    pub fn without_revision(&self) -> MsiVersion {
+        MsiVersion {
+            fields: [self.fields[0], self.fields[1], self.fields[2], 0],
+            count: self.count.min(3)
+        }
+    }
+}
+
+impl PartialEq for MsiVersion {
+    fn eq(&self, other: &MsiVersion) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for MsiVersion {}
+
+impl PartialOrd for MsiVersion {
+    fn partial_cmp(&self, other: &MsiVersion) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for MsiVersion {
+    #[doc = "Compares the major, minor and build fields; the revision is ignored."]
+    fn cmp(&self, other: &MsiVersion) -> Ordering {
+        self.fields[..3].cmp(&other.fields[..3])
+    }
+}
+
+impl Hash for MsiVersion {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.fields[..3].hash(state);
+    }
+}
+
+impl FromStr for MsiVersion {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<MsiVersion>
+    {
+        MsiVersion::parse(text)
+    }
+}
+
+impl Display for MsiVersion {
+    #[doc = "Writes as many fields as were parsed, so `1.2` stays `1.2`."]
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let fields: Vec<String> = self.fields[..self.count].iter().map(|field| field.to_string()).collect();
+        fmt.pad(&fields.join("."))
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn test_version()
+    {
+        let version = MsiVersion::parse("1.2.300").unwrap();
+        assert_eq!((version.major(), version.minor(), version.build(), version.revision()), (1, 2, 300, 0));
+        assert_eq!(version.to_string(), "1.2.300");
+        assert_eq!(MsiVersion::new(255, 255, 65535, 7).unwrap().to_string(), "255.255.65535.7");
+
+        for invalid in ["", "1..2", "1.2.3.4.5", "256.0", "1.256", "1.0.65536", "1.0.0.65536", "v1.0", "1.-2"]
+        {
+            assert!(MsiVersion::parse(invalid).is_err(), "{}", invalid);
+        }
+        assert!(MsiVersion::new(1, 256, 0, 0).is_err());
+    }
+
+    #[test]
+    fn test_compare_versions()
+    {
+        let version = |text: &str| text.parse::<MsiVersion>().unwrap();
+        assert!(version("1.2.300") < version("1.10"));
+        assert!(version("2.0") > version("1.255.65535.65535"));
+        assert_eq!(version("1.2"), version("1.2.0"));
+
+        assert_eq!(version("1.2.3.4"), version("1.2.3.5"));
+        assert_eq!(version("1.2.3.4").cmp_strict(&version("1.2.3.5")), Ordering::Less);
+        assert_eq!(version("1.2.3").cmp_strict(&version("1.2.3.0")), Ordering::Equal);
+        assert_eq!(version("1.2.3.4").without_revision().to_string(), "1.2.3");
+
+        let mut versions = [version("3.0"), version("1.0.0.9"), version("2.1")];
+        versions.sort();
+        assert_eq!(versions.iter().map(|version| version.to_string()).collect::<Vec<_>>(), ["1.0.0.9", "2.1", "3.0"]);
+    }
+}
@@ -0,0 +1,63 @@
+use std::ffi::OsStr;
+use std::io;
+use std::os::windows::ffi::OsStrExt;
+
+use crate::error::{ Error, Result };
+
+pub(crate) const ERROR_SUCCESS: u32 = 0;
+pub(crate) const ERROR_MORE_DATA: u32 = 234;
+pub(crate) const ERROR_NO_MORE_ITEMS: u32 = 259;
+
+#[allow(non_snake_case)]
+#[link(name = "msi")]
+extern "system" {
+    fn MsiCloseHandle(hAny: u32) -> u32;
+}
+
+#[doc = "An MSIHANDLE that is closed when dropped."]
+pub(crate) struct Handle(pub(crate) u32);
+
+impl Drop for Handle {
+    fn drop(&mut self)
+    {
+        if self.0 != 0
+        {
+            unsafe { MsiCloseHandle(self.0); }
+        }
+    }
+}
+
+#[doc = "Turns a Win32 status code into a result."]
+pub(crate) fn check(status: u32) -> Result<()>
+{
+    if status == ERROR_SUCCESS
+    {
+        Ok(())
+    }
+    else
+    {
+        Err(Error::Io(io::Error::from_raw_os_error(status as i32)))
+    }
+}
+
+#[doc = "Encodes a string as a null-terminated UTF-16 string."]
+pub(crate) fn wide<S: AsRef<OsStr> + ?Sized>(text: &S) -> Vec<u16>
+{
+    text.as_ref().encode_wide().chain(Some(0)).collect()
+}
+
+#[doc = "Calls an API that fills a caller-provided string buffer, growing the buffer when the API reports `ERROR_MORE_DATA`. The call receives the buffer and its size in characters, which it updates to the length written or required."]
+pub(crate) fn read_string<F: FnMut(*mut u16, *mut u32) -> u32>(mut call: F) -> Result<String>
+{
+    let mut buffer = vec![0u16; 260];
+    loop
+    {
+        let mut length = buffer.len() as u32;
+        match call(buffer.as_mut_ptr(), &mut length)
+        {
+            ERROR_SUCCESS => return Ok(String::from_utf16_lossy(&buffer[..length as usize])),
+            ERROR_MORE_DATA => buffer.resize(length as usize + 1, 0),
+            status => return Err(check(status).unwrap_err())
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use std::collections::HashSet;
+use std::fs::{ File, OpenOptions };
+use std::path::Path;
+
+use crate::error::{ Error, Result };
+use crate::table::{ Column, ColumnType, Value };
+
+#[doc = "A package opened for writing through the `msi` crate."]
+pub(crate) type Writer = msi::Package<File>;
+
+#[doc = "Opens the package at `path` for writing. Changes are committed by `flush`."]
+pub(crate) fn open<P: AsRef<Path>>(path: P) -> Result<Writer>
+{
+    let file = OpenOptions::new().read(true).write(true).open(path)?;
+    Ok(msi::Package::open(file)?)
+}
+
+#[doc = "Builds the writer's definition of a column."]
+pub(crate) fn column(column: &Column) -> msi::Column
+{
+    let mut builder = msi::Column::build(column.name());
+    if column.is_nullable()
+    {
+        builder = builder.nullable();
+    }
+    if column.is_primary_key()
+    {
+        builder = builder.primary_key();
+    }
+    if column.is_localizable()
+    {
+        builder = builder.localizable();
+    }
+
+    match column.column_type()
+    {
+        ColumnType::Int16 => builder.int16(),
+        ColumnType::Int32 => builder.int32(),
+        ColumnType::Str(size) => builder.string(size),
+        ColumnType::Binary => builder.binary()
+    }
+}
+
+pub(crate) fn value(value: &Value) -> msi::Value
+{
+    match value
+    {
+        Value::Null => msi::Value::Null,
+        Value::Int(value) => msi::Value::Int(*value),
+        Value::Str(value) => msi::Value::Str(value.to_string())
+    }
+}
+
+fn expr(value: &Value) -> msi::Expr
+{
+    match value
+    {
+        Value::Null => msi::Expr::null(),
+        Value::Int(value) => msi::Expr::integer(*value),
+        Value::Str(value) => msi::Expr::string(&**value)
+    }
+}
+
+#[doc = "Builds the condition selecting a row by the values of its key columns."]
+pub(crate) fn key_condition<'a, I: IntoIterator<Item = (&'a Column, &'a Value)>>(table: &str, keys: I) -> Result<msi::Expr>
+{
+    keys.into_iter()
+        .map(|(column, value)| msi::Expr::col(column.name()).eq(expr(value)))
+        .reduce(|first, second| first.and(second))
+        .ok_or_else(|| Error::invalid(format!("table '{}' has no primary key", table)))
+}
+
+#[doc = "Deletes the row with the given key, along with the stream of its binary cells."]
+pub(crate) fn delete_row<'a, I: IntoIterator<Item = (&'a Column, &'a Value)>>(writer: &mut Writer, table: &str, columns: &[Column], keys: I, key: &str) -> Result<()>
+{
+    writer.delete_rows(msi::Delete::from(table).with(key_condition(table, keys)?))?;
+
+    let stream = format!("{}.{}", table, key);
+    if columns.iter().any(|column| column.column_type() == ColumnType::Binary) && writer.has_stream(&stream)
+    {
+        writer.remove_stream(&stream)?;
+    }
+
+    Ok(())
+}
+
+#[doc = "Replaces the table, or creates it, with the given columns and rows. The `_Validation` rows of columns the table already had are kept, and streams of its binary cells that no row refers to any more are removed."]
+pub(crate) fn replace_table(writer: &mut Writer, name: &str, columns: &[Column], rows: Vec<Vec<msi::Value>>) -> Result<()>
+{
+    let validation = || msi::Expr::col("Table").eq(msi::Expr::string(name));
+    let mut kept = Vec::new();
+    if writer.has_table(name)
+    {
+        kept = writer.select_rows(msi::Select::table("_Validation").with(validation()))?
+            .map(|row| (0..row.len()).map(|index| row[index].clone()).collect::<Vec<msi::Value>>())
+            .collect();
+        writer.drop_table(name)?;
+    }
+    writer.create_table(name, columns.iter().map(column).collect())?;
+
+    if !kept.is_empty()
+    {
+        // the second field of a _Validation row is the column name
+        let generated: Vec<Vec<msi::Value>> = writer.select_rows(msi::Select::table("_Validation").with(validation()))?
+            .map(|row| (0..row.len()).map(|index| row[index].clone()).collect())
+            .collect();
+        let merged: Vec<Vec<msi::Value>> = generated.into_iter()
+            .map(|row| kept.iter().find(|old| old.get(1) == row.get(1)).cloned().unwrap_or(row))
+            .collect();
+        writer.delete_rows(msi::Delete::from("_Validation").with(validation()))?;
+        writer.insert_rows(msi::Insert::into("_Validation").rows(merged))?;
+    }
+
+    let binary: Vec<usize> = (0..columns.len()).filter(|index| columns[*index].column_type() == ColumnType::Binary).collect();
+    if !binary.is_empty()
+    {
+        let referenced: HashSet<String> = rows.iter()
+            .flat_map(|row| binary.iter().filter_map(move |index| match &row[*index]
+            {
+                msi::Value::Str(stream) => Some(stream.clone()),
+                _ => None
+            }))
+            .collect();
+        let prefix = format!("{}.", name);
+        let stale: Vec<String> = writer.streams().filter(|stream| stream.starts_with(&prefix) && !referenced.contains(stream)).collect();
+        for stream in stale
+        {
+            writer.remove_stream(&stream)?;
+        }
+    }
+
+    if !rows.is_empty()
+    {
+        writer.insert_rows(msi::Insert::into(name).rows(rows))?;
+    }
+    Ok(())
+}